@@ -3,7 +3,7 @@
 #![allow(dead_code)]
 
 use criterion::async_executor::AsyncExecutor;
-use repodiet::model::TreeNode;
+use repodiet::model::Tree;
 use std::sync::Arc;
 use repodiet::repository::{BlobMetaRecord, BlobRecord, Database};
 use git2::{Repository, Signature};
@@ -40,8 +40,8 @@ fn path_parts(i: usize) -> Vec<String> {
 }
 
 /// Generate a tree with configurable current_size calculation
-fn generate_tree_generic(num_paths: usize, current_size: impl Fn(usize) -> u64) -> TreeNode {
-    let mut root = TreeNode::new("(root)");
+fn generate_tree_generic(num_paths: usize, current_size: impl Fn(usize) -> u64) -> Tree {
+    let mut root = Tree::new();
     for i in 0..num_paths {
         let parts = path_parts(i);
         let refs: Vec<&str> = parts.iter().map(|s| s.as_str()).collect();
@@ -52,17 +52,17 @@ fn generate_tree_generic(num_paths: usize, current_size: impl Fn(usize) -> u64)
 }
 
 /// Generate a tree with N paths for benchmarking
-pub fn generate_tree(num_paths: usize) -> TreeNode {
+pub fn generate_tree(num_paths: usize) -> Tree {
     generate_tree_generic(num_paths, |i| (i * 50) as u64)
 }
 
 /// Generate a tree wrapped in Arc for search benchmarks
-pub fn generate_tree_arc(num_paths: usize) -> Arc<TreeNode> {
+pub fn generate_tree_arc(num_paths: usize) -> Arc<Tree> {
     Arc::new(generate_tree(num_paths))
 }
 
 /// Generate a tree with some deleted files for benchmarking deletion detection
-pub fn generate_tree_with_deletions(num_paths: usize, deletion_ratio: f64) -> TreeNode {
+pub fn generate_tree_with_deletions(num_paths: usize, deletion_ratio: f64) -> Tree {
     generate_tree_generic(num_paths, |i| {
         if (i as f64 / num_paths as f64) < deletion_ratio {
             0
@@ -88,11 +88,13 @@ pub fn generate_commit_oids(num_commits: usize) -> Vec<[u8; 20]> {
 /// Generate blob data for database benchmarks
 pub fn generate_blobs(num_blobs: usize) -> Vec<BlobRecord<'static>> {
     (0..num_blobs)
-        .map(|i| BlobRecord {
-            oid: make_oid(i),
-            path: Cow::Owned(format!("src/dir_{}/file_{}.rs", i % 100, i)),
-            cumulative_size: (i * 100) as i64,
-            current_size: (i * 50) as i64,
+        .map(|i| {
+            BlobRecord::new(
+                make_oid(i),
+                Cow::Owned(format!("src/dir_{}/file_{}.rs", i % 100, i)),
+                (i * 100) as i64,
+                (i * 50) as i64,
+            )
         })
         .collect()
 }
@@ -105,7 +107,10 @@ pub fn generate_blob_metadata(num_blobs: usize) -> Vec<BlobMetaRecord<'static>>
             size: (i * 1000) as i64,
             path: Cow::Owned(format!("src/dir_{}/file_{}.rs", i % 100, i)),
             author: Cow::Owned(format!("author_{}", i % 10)),
+            email: Cow::Owned(format!("author_{}@example.com", i % 10)),
             timestamp: 1700000000 + (i as i64),
+            commit: Some(make_oid(i / 10)),
+            subject: Cow::Owned(format!("Commit {}", i / 10)),
         })
         .collect()
 }
@@ -2,7 +2,7 @@
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use std::hint::black_box;
-use repodiet::repository::{Database, GitScanner};
+use repodiet::repository::{Database, GitScanner, MemoryStore};
 use tokio::runtime::Runtime;
 use tempfile::TempDir;
 
@@ -67,6 +67,15 @@ fn bench_scan_small_repo(c: &mut Criterion) {
         });
     });
 
+    // Same scan without SQLite, to separate git traversal from persistence
+    group.bench_function("50_commits_200_files_memory_store", |b| {
+        b.to_async(common::tokio_executor()).iter(|| async {
+            let store = MemoryStore::new();
+            let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+            black_box(scanner.scan(&store).await.unwrap())
+        });
+    });
+
     group.finish();
 }
 
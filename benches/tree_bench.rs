@@ -2,7 +2,7 @@
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use std::hint::black_box;
-use repodiet::model::TreeNode;
+use repodiet::model::Tree;
 
 mod common;
 
@@ -14,7 +14,7 @@ fn bench_add_path_with_sizes(c: &mut Criterion) {
             &size,
             |b, &size| {
                 b.iter(|| {
-                    let mut root = TreeNode::new("(root)");
+                    let mut root = Tree::new();
                     for i in 0..size {
                         let dir = format!("dir_{}", i / 100);
                         let file = format!("file_{}.rs", i);
@@ -35,7 +35,7 @@ fn bench_compute_totals(c: &mut Criterion) {
     let mut group = c.benchmark_group("tree_compute_totals");
     for size in [1_000, 10_000, 50_000] {
         // Pre-generate tree without computing totals
-        let mut tree = TreeNode::new("(root)");
+        let mut tree = Tree::new();
         for i in 0..size {
             let dir = format!("dir_{}", i / 100);
             let file = format!("file_{}.rs", i);
@@ -70,7 +70,7 @@ fn bench_contains_deleted_files(c: &mut Criterion) {
             |b, tree| {
                 b.iter(|| {
                     // black_box the input to prevent compiler from predicting result
-                    black_box(black_box(tree).contains_deleted_files())
+                    black_box(black_box(tree).root().contains_deleted_files())
                 });
             },
         );
@@ -89,7 +89,7 @@ fn bench_deleted_cumulative_size(c: &mut Criterion) {
             &tree,
             |b, tree| {
                 b.iter(|| {
-                    black_box(tree.deleted_cumulative_size())
+                    black_box(tree.root().deleted_cumulative_size())
                 });
             },
         );
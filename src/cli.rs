@@ -1,14 +1,815 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use repodiet::config::Overrides;
+use repodiet::model::{AuthorGrouping, ExportFormat, GrowthLimits};
+use repodiet::repository::{GitScanner, JsonProgress, NoopProgress, RevRange, ScanOptions, DEFAULT_OBJECT_CACHE};
+use repodiet::util::{extension_label, DateFormat, TimeZone};
+use repodiet::view::Theme;
+use repodiet::viewmodel::SearchCase;
 
 #[derive(Parser, Debug)]
-#[command(name = "repodiet", about = "Analyze git repository growth")]
+#[command(
+    name = "repodiet",
+    about = "Analyze git repository growth",
+    long_about = "Analyze git repository growth.\n\n\
+        repodiet walks the whole history of a repository, attributes every blob to the \
+        commit that introduced it and shows which paths cost the most over time, \
+        including files that were deleted but still live in history. Results are \
+        indexed in a cache, so later runs only scan new commits.\n\n\
+        Without a subcommand the interactive TUI is opened (same as `repodiet tui`).",
+    after_help = "Examples:\n  \
+        repodiet                               browse the current repository\n  \
+        repodiet scan ~/src/app                update the index and print totals\n  \
+        repodiet report --out app.json         write a JSON storage report\n  \
+        repodiet report --report-md            summarize growth for a PR comment\n  \
+        repodiet check --max-deleted 200MB     fail when deleted history grows too big\n  \
+        repodiet snapshot --history sizes.csv  record today's totals (e.g. from cron)\n  \
+        repodiet pr-check --base origin/main   annotate large files a branch adds\n  \
+        repodiet lfs-plan --min-size 50MB      plan a migration of binaries to Git LFS\n  \
+        repodiet sizes --stdin < paths.txt     print the sizes of listed paths\n  \
+        repodiet cache list                    show cached indexes\n  \
+        repodiet bench --repo ~/src/app        time cold, warm and incremental scans",
+    args_conflicts_with_subcommands = true
+)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub tui: TuiArgs,
+
+    /// Log filter, e.g. `debug` or `repodiet=debug,sqlx=warn`
+    /// (defaults to RUST_LOG, then `info`; --profile adds `repodiet=debug`)
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// Emit logs as JSON lines
+    #[arg(long, global = true)]
+    pub log_json: bool,
+
+    /// Write logs to this file instead of stderr (keeps output produced while the TUI is open)
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Print nothing to stderr but errors: no logs below `error`, no progress bars
+    #[arg(long, global = true, conflicts_with = "log_level")]
+    pub quiet: bool,
+}
+
+impl Cli {
+    /// Whether any command asked for profiling output
+    pub fn profile(&self) -> bool {
+        match &self.command {
+            Some(Command::Tui(args)) => args.profile,
+            Some(Command::Scan { profile, .. }) => *profile,
+            Some(_) => false,
+            None => self.tui.profile,
+        }
+    }
+}
+
+/// Which repository to scan and how much of its history
+#[derive(Args, Debug, Clone)]
+pub struct ScanArgs {
     /// Path to the git repository
     #[arg(default_value = ".")]
     pub repo_path: PathBuf,
 
-    /// Enable profiling mode (skips TUI, prints timing)
+    /// Include blobs that only exist in stashes or the index
+    /// (shown under stash@{N}/ and :index:/)
+    #[arg(long)]
+    pub include_stash_index: bool,
+
+    /// Walk only the first parent of each commit (faster; enough for mainline bloat)
+    #[arg(long)]
+    pub first_parent: bool,
+
+    /// Scan only the N newest commits
+    #[arg(long, value_name = "N")]
+    pub max_commits: Option<usize>,
+
+    /// Scan only commits made on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_since)]
+    pub since: Option<i64>,
+
+    /// Scan only this sub-path of the repository (e.g. src/vendor); totals
+    /// and percentages are then relative to it
+    #[arg(long, value_name = "PATH", value_parser = parse_scan_path)]
+    pub path: Option<String>,
+
+    /// Scan only the commits in BASE..HEAD (HEAD defaults to HEAD), counting
+    /// only the blobs they add on top of BASE
+    #[arg(long, value_name = "BASE..HEAD", value_parser = parse_range)]
+    pub range: Option<RevRange>,
+
+    /// Scan into memory only: nothing is read from or written to the cache
+    /// directory (every run is a full scan)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Scan the original history even where `git replace` substitutes objects
+    /// (replace refs are honored by default, like git does)
+    #[arg(long)]
+    pub no_replace_objects: bool,
+
+    /// Report scan progress as newline-delimited JSON events on stderr
+    /// instead of progress bars (phase_started, progress, phase_finished, scan_summary)
+    #[arg(long)]
+    pub progress_json: bool,
+
+    /// Memory for decoded trees and delta bases reused across commits
+    /// (default 64MB; 0 leaves it to git's core.deltaBaseCacheLimit)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub object_cache: Option<u64>,
+
+    /// Fail when a pack can't be read or a blob is in no readable pack,
+    /// instead of counting its size as 0
+    #[arg(long)]
+    pub strict: bool,
+}
+
+impl ScanArgs {
+    pub fn repo_path(&self) -> &str {
+        self.repo_path.to_str().unwrap_or(".")
+    }
+
+    pub fn options(&self) -> ScanOptions {
+        ScanOptions {
+            include_stash_and_index: self.include_stash_index,
+            first_parent: self.first_parent,
+            max_commits: self.max_commits,
+            since: self.since,
+            no_replace_objects: self.no_replace_objects,
+            strict: self.strict,
+            path: self.path.clone(),
+            range: self.range.clone(),
+        }
+    }
+
+    /// A scanner for this repository with these options
+    pub fn scanner(&self, profile: bool) -> GitScanner {
+        let scanner = if profile {
+            GitScanner::profiling(self.repo_path())
+        } else {
+            GitScanner::new(self.repo_path())
+        };
+        let object_cache = self.object_cache.map_or(DEFAULT_OBJECT_CACHE, |bytes| bytes as usize);
+        let scanner = scanner.with_options(self.options()).with_object_cache(object_cache);
+        if self.progress_json {
+            scanner.with_progress(Arc::new(JsonProgress::stderr()))
+        } else if crate::logging::quiet() {
+            scanner.with_progress(Arc::new(NoopProgress))
+        } else {
+            scanner
+        }
+    }
+}
+
+/// Where settings come from and which paths to leave out
+#[derive(Args, Debug, Clone)]
+pub struct ConfigArgs {
+    /// Read settings from this file instead of ~/.config/repodiet/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Hide paths matching this pattern (repeatable;
+    /// `*.png` matches at any depth, `vendor/*.js` from the root)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Credit blobs to authors by name, or by email domain for an
+    /// organization-level view (also REPODIET_AUTHORS)
+    #[arg(long, value_name = "BY", value_parser = parse_authors)]
+    pub authors: Option<AuthorGrouping>,
+
+    /// How dates are written: relative, iso, or a format like
+    /// "[day].[month].[year]" (also REPODIET_DATE_FORMAT)
+    #[arg(long, value_name = "FORMAT", value_parser = parse_date_format)]
+    pub date_format: Option<DateFormat>,
+
+    /// Time zone of absolute dates: local, utc, or an offset like +05:30
+    /// (also REPODIET_TIMEZONE)
+    #[arg(long, value_name = "ZONE", value_parser = parse_timezone)]
+    pub timezone: Option<TimeZone>,
+}
+
+impl ConfigArgs {
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            config_file: self.config.clone(),
+            exclude: self.exclude.clone(),
+            authors: self.authors,
+            date_format: self.date_format.clone(),
+            timezone: self.timezone,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TuiArgs {
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    /// Enable profiling mode (skips TUI, prints timing; same as `scan --profile`)
     #[arg(long)]
     pub profile: bool,
+
+    /// Print suggested .gitignore entries for committed build output, caches
+    /// and logs, with the history each would have kept out (skips TUI)
+    #[arg(long)]
+    pub suggest_gitignore: bool,
+
+    /// Show the hosting API's reported size next to the local pack size
+    /// (github.com/gitlab.com, or hosts under [remote] in the user config; token from
+    /// REPODIET_REMOTE_TOKEN, or GITHUB_TOKEN/GITLAB_TOKEN for the public hosts)
+    #[arg(long)]
+    pub remote_size: bool,
+
+    /// Number of search results shown at a time (more load as you scroll)
+    #[arg(long, value_name = "N")]
+    pub search_limit: Option<usize>,
+
+    /// How search matches case: ignore, smart (only when the query has an
+    /// uppercase letter) or sensitive; Ctrl+S switches while searching
+    #[arg(long, value_name = "MODE", value_parser = parse_search_case)]
+    pub search_case: Option<SearchCase>,
+
+    /// Number of blobs listed in the large blobs view
+    #[arg(long, value_name = "N")]
+    pub top_blobs: Option<usize>,
+
+    /// Color theme: default or mono (also REPODIET_THEME, NO_COLOR)
+    #[arg(long, value_parser = parse_theme)]
+    pub theme: Option<Theme>,
+
+    /// Record the views, directories and searches visited and the entries
+    /// marked, and write them to this file as Markdown on exit
+    #[arg(long, value_name = "FILE")]
+    pub notes: Option<PathBuf>,
+}
+
+impl TuiArgs {
+    pub fn overrides(&self) -> Overrides {
+        Overrides {
+            top_blobs: self.top_blobs,
+            search_limit: self.search_limit,
+            search_case: self.search_case,
+            theme: self.theme,
+            ..self.config.overrides()
+        }
+    }
+}
+
+fn parse_since(date: &str) -> Result<i64, String> {
+    repodiet::util::parse_date(date).ok_or_else(|| format!("expected a YYYY-MM-DD date, got '{}'", date))
+}
+
+/// A repository path without "./" or surrounding slashes, as paths are stored
+fn parse_scan_path(path: &str) -> Result<String, String> {
+    let trimmed = path.trim_start_matches("./").trim_matches('/');
+    if trimmed.is_empty() || trimmed.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+        return Err(format!("expected a path inside the repository like 'src/vendor', got '{}'", path));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn parse_range(range: &str) -> Result<RevRange, String> {
+    RevRange::parse(range).ok_or_else(|| format!("expected a range like main..feature or v1.0.., got '{}'", range))
+}
+
+fn parse_theme(name: &str) -> Result<Theme, String> {
+    Theme::parse(name).ok_or_else(|| format!("expected 'default' or 'mono', got '{}'", name))
+}
+
+fn parse_search_case(name: &str) -> Result<SearchCase, String> {
+    SearchCase::parse(name).ok_or_else(|| format!("expected 'ignore', 'smart' or 'sensitive', got '{}'", name))
+}
+
+fn parse_authors(name: &str) -> Result<AuthorGrouping, String> {
+    AuthorGrouping::parse(name).ok_or_else(|| format!("expected 'name' or 'domain', got '{}'", name))
+}
+
+fn parse_date_format(format: &str) -> Result<DateFormat, String> {
+    DateFormat::try_from(format.to_string())
+}
+
+fn parse_timezone(zone: &str) -> Result<TimeZone, String> {
+    TimeZone::try_from(zone.to_string())
+}
+
+fn parse_size(size: &str) -> Result<u64, String> {
+    repodiet::util::parse_size(size).ok_or_else(|| format!("expected a size like 500KB or 1.5GB, got '{}'", size))
+}
+
+fn parse_finding_format(format: &str) -> Result<FindingFormat, String> {
+    match format {
+        "github" => Ok(FindingFormat::Github),
+        "sarif" => Ok(FindingFormat::Sarif),
+        _ => Err(format!("expected 'github' or 'sarif', got '{}'", format)),
+    }
+}
+
+fn parse_sizes_format(format: &str) -> Result<ExportFormat, String> {
+    match format {
+        "tsv" => Ok(ExportFormat::Tsv),
+        "json" => Ok(ExportFormat::Json),
+        _ => Err(format!("expected 'tsv' or 'json', got '{}'", format)),
+    }
+}
+
+fn parse_extension_budget(budget: &str) -> Result<ExtensionBudget, String> {
+    let expected = || format!("expected an extension and a size like .zip=100MB, got '{}'", budget);
+    let (extension, size) = budget.split_once('=').ok_or_else(expected)?;
+    // Named the way the extension view names it; what it can't name is no extension
+    let extension = extension_label(&format!(".{}", extension.trim().trim_start_matches('.')));
+    if !extension.starts_with('.') {
+        return Err(expected());
+    }
+    let size = repodiet::util::parse_size(size.trim()).ok_or_else(expected)?;
+    Ok(ExtensionBudget { extension: extension.into_owned(), size })
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Browse the repository interactively (the default)
+    Tui(TuiArgs),
+
+    /// Bring the cached index up to date and print totals
+    Scan {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        /// Scan into a fresh temporary index and print timings
+        #[arg(long)]
+        profile: bool,
+    },
+
+    /// Print a JSON storage report (totals, top entries, extensions and blobs)
+    Report {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+
+        /// Write the report to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+
+        /// Write a short Markdown summary for a pull-request comment instead:
+        /// totals, and the directories that grew most and the largest blobs
+        /// committed since the last `repodiet snapshot`
+        #[arg(long)]
+        report_md: bool,
+
+        /// Print the JSON Schema of the report instead, without scanning
+        #[arg(long, conflicts_with = "report_md")]
+        schema: bool,
+    },
+
+    /// Exit with an error when the repository exceeds a size budget (for CI)
+    #[command(after_help = "Sizes accept B, KB, MB and GB suffixes (powers of 1024), e.g. 750MB or 1.5GB.")]
+    Check {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+
+        #[command(flatten)]
+        limits: CheckLimits,
+    },
+
+    /// Bring the index up to date and record today's totals in it, to track
+    /// growth over time (e.g. from a daily cron job)
+    #[command(after_help = "Snapshots are kept in the repository's cached index; \
+        a history file gets one line per run.\n\
+        Example crontab entry:\n  \
+        0 3 * * *  repodiet snapshot ~/src/app --history ~/app-size.csv")]
+    Snapshot {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+
+        /// Delete snapshots older than this many days (0 keeps them all)
+        #[arg(long, value_name = "DAYS", default_value_t = 365)]
+        retention_days: u32,
+
+        /// Also append the totals to this file: JSON Lines for .jsonl and
+        /// .ndjson files, CSV otherwise
+        #[arg(long, value_name = "FILE")]
+        history: Option<PathBuf>,
+    },
+
+    /// Flag large files a branch adds on top of its base, as GitHub Actions
+    /// annotations or SARIF, so they show on the pull request's diff
+    #[command(after_help = "Sizes accept B, KB, MB and GB suffixes (powers of 1024), e.g. 750MB or 1.5GB.\n\
+        In a GitHub Actions job, check out with `fetch-depth: 0` so the base is there.")]
+    PrCheck {
+        /// Path to the git repository
+        #[arg(default_value = ".")]
+        repo_path: PathBuf,
+
+        /// What the branch is compared with, e.g. origin/main; only commits
+        /// on HEAD that aren't on it are read
+        #[arg(long, value_name = "REF")]
+        base: String,
+
+        /// Smallest added file to flag
+        #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "1MB")]
+        min_size: u64,
+
+        /// `github` for workflow command annotations, `sarif` for a SARIF 2.1.0 log
+        #[arg(long, value_name = "FORMAT", value_parser = parse_finding_format, default_value = "github")]
+        format: FindingFormat,
+
+        /// Write the findings to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+
+        /// Report findings as errors and exit with one when there are any
+        #[arg(long)]
+        fail: bool,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+    },
+
+    /// Print which binaries to move to Git LFS: patterns with estimated
+    /// savings, the `git lfs migrate` command and `.gitattributes` lines
+    #[command(after_help = "Sizes accept B, KB, MB and GB suffixes (powers of 1024), e.g. 750MB or 1.5GB.")]
+    LfsPlan {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+
+        /// Smallest history an extension or file must hold to be planned
+        #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "10MB")]
+        min_size: u64,
+    },
+
+    /// Browse a previously built index without the repository,
+    /// e.g. one copied from a build server (`repodiet cache path` finds it)
+    OpenIndex {
+        /// The index database file
+        path: PathBuf,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+
+        /// Write Markdown notes of what was looked at and marked on exit
+        #[arg(long, value_name = "FILE")]
+        notes: Option<PathBuf>,
+    },
+
+    /// Write an index as a portable, compressed snapshot (not the SQLite
+    /// file), e.g. to attach to a ticket or move to another machine
+    ExportIndex {
+        /// The snapshot file to write, e.g. app.repodiet
+        out: PathBuf,
+
+        /// Path to the git repository whose cached index is exported
+        #[arg(default_value = ".")]
+        repo_path: PathBuf,
+
+        /// Export this index database instead of the repository's cached one
+        #[arg(long, value_name = "FILE", conflicts_with = "repo_path")]
+        index: Option<PathBuf>,
+    },
+
+    /// Replace a cached index with a snapshot written by `export-index`
+    ImportIndex {
+        /// The snapshot file to read
+        snapshot: PathBuf,
+
+        /// Path to the git repository whose cached index is replaced
+        #[arg(default_value = ".")]
+        repo_path: PathBuf,
+
+        /// Import into this index database instead, e.g. to browse it with `open-index`
+        #[arg(long, value_name = "FILE", conflicts_with = "repo_path")]
+        index: Option<PathBuf>,
+    },
+
+    /// Inspect or remove cached indexes
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+
+    /// Show every path a blob appeared at, its size, author, and HEAD status
+    Blob {
+        /// Full or abbreviated (4+ hex digits) blob OID
+        oid: String,
+
+        /// Path to the git repository
+        #[arg(default_value = ".")]
+        repo_path: PathBuf,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+    },
+
+    /// Print the cumulative, current and deleted size of each listed path,
+    /// for other tools to add to their reports
+    Sizes {
+        #[command(flatten)]
+        scan: ScanArgs,
+
+        /// Read the paths from stdin, one per line, relative to the
+        /// repository root (blank lines are skipped)
+        #[arg(long, required = true)]
+        stdin: bool,
+
+        /// `tsv` for tab-separated lines with a header, `json` for an array of objects
+        #[arg(long, value_name = "FORMAT", value_parser = parse_sizes_format, default_value = "tsv")]
+        format: ExportFormat,
+    },
+
+    /// Scan many repositories and write a JSON report for each
+    Batch {
+        /// File listing one repository path per line
+        /// (blank lines and lines starting with # are ignored)
+        #[arg(long)]
+        repos_file: PathBuf,
+
+        /// Directory for the per-repository reports and summary.json
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Number of repositories to scan at the same time
+        #[arg(long, default_value_t = 1)]
+        jobs: usize,
+    },
+
+    /// Time a cold, a warm and an incremental scan of a repository and print
+    /// a JSON report, to compare scan performance between builds
+    Bench {
+        /// Path to the git repository
+        #[arg(long, value_name = "PATH")]
+        repo: PathBuf,
+
+        /// Commits back along first parents to build the index the
+        /// incremental scan starts from (merged branches add more)
+        #[arg(long, value_name = "N", default_value_t = 100,
+              value_parser = clap::value_parser!(u64).range(1..))]
+        incremental: u64,
+
+        /// Write the report to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+}
+
+/// Budgets for `repodiet check`; at least one is required
+#[derive(Args, Debug, Clone)]
+#[group(required = true, multiple = true)]
+pub struct CheckLimits {
+    /// Largest allowed total size of all history
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_cumulative: Option<u64>,
+
+    /// Largest allowed size of HEAD
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_current: Option<u64>,
+
+    /// Largest allowed size of content deleted from HEAD but kept in history
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_deleted: Option<u64>,
+
+    /// Largest allowed single blob
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_blob: Option<u64>,
+
+    /// Largest allowed total size of all history for one file extension,
+    /// e.g. `.zip=100MB`; may be given more than once
+    #[arg(long, value_name = "EXT=SIZE", value_parser = parse_extension_budget)]
+    pub max_extension: Vec<ExtensionBudget>,
+
+    /// Compare with the last `repodiet snapshot` and fail when the total,
+    /// an extension or a directory grew beyond the growth limits since
+    #[arg(long)]
+    pub against_last: bool,
+
+    /// Growth tolerated since the last snapshot (with --against-last)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, requires = "against_last")]
+    pub max_growth: Option<u64>,
+
+    /// Growth in percent tolerated since the last snapshot (with
+    /// --against-last); only growth beyond both limits counts
+    #[arg(long, value_name = "PERCENT", default_value_t = 10.0, requires = "against_last")]
+    pub max_growth_percent: f64,
+
+    /// Print growth beyond the limits as warnings instead of failing
+    #[arg(long, requires = "against_last")]
+    pub warn_growth: bool,
+}
+
+impl CheckLimits {
+    pub fn growth_limits(&self) -> GrowthLimits {
+        GrowthLimits { bytes: self.max_growth, percent: Some(self.max_growth_percent) }
+    }
+}
+
+/// `--max-extension`: a budget for one file extension
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionBudget {
+    /// As `util::extension_label` names it, e.g. `.zip`
+    pub extension: String,
+    pub size: u64,
+}
+
+/// `pr-check --format`: how findings are written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FindingFormat {
+    /// GitHub Actions workflow commands (`::warning file=...::...`)
+    Github,
+    /// A SARIF 2.1.0 log, e.g. for GitHub code scanning
+    Sarif,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Print where the index for a repository is stored
+    Path {
+        /// Path to the git repository
+        #[arg(default_value = ".")]
+        repo_path: PathBuf,
+    },
+
+    /// List every cached index with its size
+    List,
+
+    /// Delete the index for a repository (the next run rescans from scratch)
+    Clear {
+        /// Path to the git repository
+        #[arg(default_value = ".", conflicts_with = "all")]
+        repo_path: PathBuf,
+
+        /// Delete every cached index
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_bare_path_opens_tui() {
+        let cli = Cli::try_parse_from(["repodiet", "/srv/app", "--first-parent"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.tui.scan.repo_path, PathBuf::from("/srv/app"));
+        assert!(cli.tui.scan.first_parent);
+    }
+
+    #[test]
+    fn test_scan_path() {
+        let cli = Cli::try_parse_from(["repodiet", "--path", "./src/vendor/"]).unwrap();
+        assert_eq!(cli.tui.scan.path.as_deref(), Some("src/vendor"));
+        assert!(Cli::try_parse_from(["repodiet", "--path", "/"]).is_err());
+        assert!(Cli::try_parse_from(["repodiet", "--path", "src/../docs"]).is_err());
+    }
+
+    #[test]
+    fn test_scan_range() {
+        let cli = Cli::try_parse_from(["repodiet", "scan", "--range", "origin/main.."]).unwrap();
+        match cli.command {
+            Some(Command::Scan { scan, .. }) => {
+                assert_eq!(scan.options().range, Some(RevRange { base: "origin/main".into(), head: "HEAD".into() }))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["repodiet", "--range", "main...feature"]).is_err());
+    }
+
+    #[test]
+    fn test_check_needs_a_limit() {
+        assert!(Cli::try_parse_from(["repodiet", "check"]).is_err());
+        let cli = Cli::try_parse_from(["repodiet", "check", "--max-blob", "10MB"]).unwrap();
+        match cli.command {
+            Some(Command::Check { limits, .. }) => assert_eq!(limits.max_blob, Some(10 * 1024 * 1024)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_against_last() {
+        let cli = Cli::try_parse_from(["repodiet", "check", "--against-last", "--max-growth", "1MB"]).unwrap();
+        match cli.command {
+            Some(Command::Check { limits, .. }) => {
+                assert_eq!(limits.growth_limits(), GrowthLimits { bytes: Some(1024 * 1024), percent: Some(10.0) });
+                assert!(!limits.warn_growth);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["repodiet", "check", "--max-growth-percent", "5"]).is_err());
+        assert!(Cli::try_parse_from(["repodiet", "check", "--max-blob", "1MB", "--warn-growth"]).is_err());
+    }
+
+    #[test]
+    fn test_extension_budgets() {
+        let cli = Cli::try_parse_from(["repodiet", "check", "--max-extension", "ZIP=1KB", "--max-extension", ".psd=2KB"])
+            .unwrap();
+        match cli.command {
+            Some(Command::Check { limits, .. }) => assert_eq!(limits.max_extension, [
+                ExtensionBudget { extension: ".zip".into(), size: 1024 },
+                ExtensionBudget { extension: ".psd".into(), size: 2048 },
+            ]),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["repodiet", "check", "--max-extension", "100MB"]).is_err());
+        assert!(Cli::try_parse_from(["repodiet", "check", "--max-extension", ".=1KB"]).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_retention() {
+        let cli = Cli::try_parse_from(["repodiet", "snapshot", "/srv/app", "--history", "sizes.jsonl"]).unwrap();
+        match cli.command {
+            Some(Command::Snapshot { scan, retention_days, history, .. }) => {
+                assert_eq!(scan.repo_path, PathBuf::from("/srv/app"));
+                assert_eq!(retention_days, 365);
+                assert_eq!(history, Some(PathBuf::from("sizes.jsonl")));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(Cli::try_parse_from(["repodiet", "snapshot", "--retention-days", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_pr_check() {
+        assert!(Cli::try_parse_from(["repodiet", "pr-check"]).is_err());
+        assert!(Cli::try_parse_from(["repodiet", "pr-check", "--base", "main", "--format", "xml"]).is_err());
+        let cli = Cli::try_parse_from(["repodiet", "pr-check", "--base", "origin/main", "--format", "sarif"]).unwrap();
+        match cli.command {
+            Some(Command::PrCheck { base, min_size, format, fail, .. }) => {
+                assert_eq!(base, "origin/main");
+                assert_eq!(min_size, 1024 * 1024);
+                assert_eq!(format, FindingFormat::Sarif);
+                assert!(!fail);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_report_schema() {
+        assert!(Cli::try_parse_from(["repodiet", "report", "--schema", "--report-md"]).is_err());
+        let cli = Cli::try_parse_from(["repodiet", "report", "--schema", "--out", "report.schema.json"]).unwrap();
+        match cli.command {
+            Some(Command::Report { schema, report_md, out, .. }) => {
+                assert!(schema);
+                assert!(!report_md);
+                assert_eq!(out, Some(PathBuf::from("report.schema.json")));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sizes_needs_paths() {
+        assert!(Cli::try_parse_from(["repodiet", "sizes"]).is_err());
+        assert!(Cli::try_parse_from(["repodiet", "sizes", "--stdin", "--format", "csv"]).is_err());
+        let cli = Cli::try_parse_from(["repodiet", "sizes", "/srv/app", "--stdin", "--format", "json"]).unwrap();
+        match cli.command {
+            Some(Command::Sizes { scan, stdin, format }) => {
+                assert_eq!(scan.repo_path, PathBuf::from("/srv/app"));
+                assert!(stdin);
+                assert_eq!(format, ExportFormat::Json);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bench_needs_a_repo_and_incremental_commits() {
+        assert!(Cli::try_parse_from(["repodiet", "bench"]).is_err());
+        assert!(Cli::try_parse_from(["repodiet", "bench", "--repo", "/srv/app", "--incremental", "0"]).is_err());
+        let cli = Cli::try_parse_from(["repodiet", "bench", "--repo", "/srv/app"]).unwrap();
+        match cli.command {
+            Some(Command::Bench { repo, incremental, out }) => {
+                assert_eq!(repo, PathBuf::from("/srv/app"));
+                assert_eq!(incremental, 100);
+                assert_eq!(out, None);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
 }
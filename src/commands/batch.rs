@@ -0,0 +1,204 @@
+//! `repodiet batch`: scan a list of repositories and write JSON reports
+//!
+//! Each repository is scanned against its usual cached index, so re-running
+//! a batch only walks commits added since the last run. Up to `jobs` scans
+//! run at once, each on its own thread with a single-threaded runtime.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use repodiet::model::AuthorGrouping;
+use repodiet::repository::{DbTuning, GitScanner};
+use repodiet::util::{format_size, PathFilter};
+
+use super::report::{build_report, RepoReport, TOP_N};
+use super::{cache_db_path, open_database, repo_slug};
+
+const SUMMARY_FILE: &str = "summary.json";
+
+/// One line of the aggregate summary; exactly one of `report` and `error` is set
+#[derive(Debug, Serialize)]
+struct SummaryEntry {
+    repo: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cumulative_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    succeeded: usize,
+    failed: usize,
+    total_cumulative_size: u64,
+    total_current_size: u64,
+    repos: Vec<SummaryEntry>,
+}
+
+pub fn run(repos_file: &Path, out: &Path, jobs: usize) -> Result<()> {
+    let contents = fs::read_to_string(repos_file)
+        .with_context(|| format!("Could not read {}", repos_file.display()))?;
+    let repos = parse_repos_file(&contents);
+    if repos.is_empty() {
+        bail!("No repositories listed in {}", repos_file.display());
+    }
+    fs::create_dir_all(out)
+        .with_context(|| format!("Could not create {}", out.display()))?;
+
+    let jobs = jobs.clamp(1, repos.len());
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let entries: Mutex<Vec<Option<SummaryEntry>>> =
+        Mutex::new(repos.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| -> Result<()> {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| -> Result<()> {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?;
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(repo) = repos.get(i) else {
+                            return Ok(());
+                        };
+                        let entry = summarize(repo, runtime.block_on(scan_repo(repo, out)));
+                        let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                        match &entry.error {
+                            None => info!("[{}/{}] {}: {} cumulative",
+                                n, repos.len(), repo,
+                                format_size(entry.cumulative_size.unwrap_or(0))),
+                            Some(e) => warn!("[{}/{}] {}: failed: {}", n, repos.len(), repo, e),
+                        }
+                        entries.lock().map_err(|_| anyhow!("batch worker panicked"))?[i] = Some(entry);
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().map_err(|_| anyhow!("batch worker panicked"))??;
+        }
+        Ok(())
+    })?;
+
+    let entries: Vec<SummaryEntry> = entries
+        .into_inner()
+        .map_err(|_| anyhow!("batch worker panicked"))?
+        .into_iter()
+        .flatten()
+        .collect();
+    let summary = build_summary(entries);
+    let summary_path = out.join(SUMMARY_FILE);
+    fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)?;
+
+    info!("Scanned {} repositories ({} failed): {} cumulative, {} current",
+        summary.succeeded + summary.failed,
+        summary.failed,
+        format_size(summary.total_cumulative_size),
+        format_size(summary.total_current_size));
+    info!("Summary written to {}", summary_path.display());
+
+    if summary.failed > 0 {
+        bail!("{} of {} repositories failed", summary.failed, summary.succeeded + summary.failed);
+    }
+    Ok(())
+}
+
+/// Repository paths from a list file, in order and without duplicates
+fn parse_repos_file(contents: &str) -> Vec<String> {
+    let mut repos: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !repos.iter().any(|r| r == line) {
+            repos.push(line.to_string());
+        }
+    }
+    repos
+}
+
+/// Scan one repository and write its report, returning the report's file name
+async fn scan_repo(repo: &str, out: &Path) -> Result<(String, RepoReport)> {
+    let db = open_database(&cache_db_path(repo)?, &DbTuning::default()).await?;
+    let root = GitScanner::quiet(repo).scan(&db).await?;
+    super::classify_content(&db, repo).await?;
+    let head = db.get_metadata("head_oid").await;
+    let blobs = db.get_top_blobs(TOP_N).await?;
+    let extensions = db.get_extension_stats(&PathFilter::default()).await?;
+    let delta = db.get_delta_efficiency(TOP_N, &PathFilter::default()).await?;
+    let authors = db.get_author_totals(AuthorGrouping::Name, TOP_N, &PathFilter::default()).await?;
+    let diagnostics = db.get_scan_diagnostics().await?;
+
+    let report = build_report(repo, head, &root, extensions, &blobs, &authors, &delta, &diagnostics);
+    let file_name = format!("{}.json", repo_slug(repo)?);
+    fs::write(out.join(&file_name), serde_json::to_string_pretty(&report)?)?;
+    Ok((file_name, report))
+}
+
+fn summarize(repo: &str, result: Result<(String, RepoReport)>) -> SummaryEntry {
+    match result {
+        Ok((file_name, report)) => SummaryEntry {
+            repo: repo.to_string(),
+            report: Some(file_name),
+            cumulative_size: Some(report.cumulative_size),
+            current_size: Some(report.current_size),
+            error: None,
+        },
+        Err(e) => SummaryEntry {
+            repo: repo.to_string(),
+            report: None,
+            cumulative_size: None,
+            current_size: None,
+            error: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+fn build_summary(repos: Vec<SummaryEntry>) -> Summary {
+    let failed = repos.iter().filter(|r| r.error.is_some()).count();
+    Summary {
+        succeeded: repos.len() - failed,
+        failed,
+        total_cumulative_size: repos.iter().filter_map(|r| r.cumulative_size).sum(),
+        total_current_size: repos.iter().filter_map(|r| r.current_size).sum(),
+        repos,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use repodiet::model::Tree;
+
+    #[test]
+    fn test_parse_repos_file() {
+        let contents = "# org repos\n\n  ./a  \n/srv/b\n./a\n#./c\n";
+        assert_eq!(parse_repos_file(contents), vec!["./a", "/srv/b"]);
+    }
+
+    #[test]
+    fn test_summary_counts_failures() {
+        let ok = summarize("a", Ok(("a.json".into(), build_report("a", None, &Tree::new(), vec![], &[], &[], &[], &[]))));
+        let failed = summarize("b", Err(anyhow!("not a git repository")));
+        let summary = build_summary(vec![ok, failed]);
+
+        assert_eq!(summary.succeeded, 1);
+        assert_eq!(summary.failed, 1);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["repos"][1]["error"], "not a git repository");
+        assert!(json["repos"][0].get("error").is_none());
+    }
+}
@@ -0,0 +1,112 @@
+//! `repodiet bench`: time scans of a real repository
+//!
+//! Runs a cold scan into a fresh index, a warm scan of that index once it is
+//! up to date, and an incremental scan of the commits made since `HEAD~N`,
+//! each against temporary indexes so the repository's cached one is left
+//! alone. The JSON report is meant to be kept and compared between builds.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use tracing::info;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::repository::{Database, GitScanner, SCHEMA_VERSION};
+use repodiet::util::{peak_rss, reset_peak_rss};
+
+use crate::interrupt;
+
+use super::open_database;
+
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    repo: String,
+    version: &'static str,
+    schema: &'static str,
+    head: Option<String>,
+    /// Commits in the whole index
+    commits: usize,
+    /// Where the index the incremental phase starts from was scanned to
+    incremental_base: String,
+    phases: Vec<PhaseReport>,
+}
+
+/// One timed scan
+#[derive(Debug, Serialize)]
+struct PhaseReport {
+    name: &'static str,
+    wall_ms: u64,
+    commits_scanned: usize,
+    /// Most memory held during the scan, including what the process already
+    /// held when it started; null where the OS doesn't report it
+    peak_rss_bytes: Option<u64>,
+}
+
+pub async fn run(repo_path: &Path, incremental: u64, out: Option<&Path>) -> Result<()> {
+    let temp_dir = std::env::temp_dir().join(format!("repodiet-bench-{}", std::process::id()));
+    fs::create_dir_all(&temp_dir)?;
+    let report = bench(repo_path, incremental, &temp_dir).await;
+    // The indexes are only useful while they are being timed
+    fs::remove_dir_all(&temp_dir).ok();
+    let json = serde_json::to_string_pretty(&report?)?;
+    match out {
+        Some(path) => {
+            fs::write(path, json + "\n").with_context(|| format!("Could not write {}", path.display()))?;
+            info!("Benchmark written to {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+async fn bench(repo_path: &Path, incremental: u64, temp_dir: &Path) -> Result<BenchReport> {
+    let repo = repo_path.to_str().context("Invalid path encoding")?;
+    let tuning = Config::load(repo_path, &Overrides::default())?.database;
+    let interrupt = interrupt::on_ctrl_c();
+    let scanner = GitScanner::quiet(repo).with_cancel(interrupt.token());
+
+    // Set up first: it fails fast when history is too short for the base
+    let incremental_base = format!("HEAD~{}", incremental);
+    info!("Preparing an index as of {}...", incremental_base);
+    let base_db = open_database(&temp_dir.join("incremental.db"), &tuning).await?;
+    GitScanner::quiet(repo)
+        .with_tip(&incremental_base)
+        .with_cancel(interrupt.token())
+        .scan(&base_db)
+        .await?;
+
+    let db = open_database(&temp_dir.join("cold.db"), &tuning).await?;
+    let cold = timed_scan("cold", &scanner, &db).await?;
+    let warm = timed_scan("warm", &scanner, &db).await?;
+    let head = db.get_metadata("head_oid").await;
+    let commits = db.load_scanned_commit_oids().await.len();
+    db.close().await;
+
+    let incremental = timed_scan("incremental", &scanner, &base_db).await?;
+    base_db.close().await;
+
+    Ok(BenchReport {
+        repo: repo.to_string(),
+        version: env!("CARGO_PKG_VERSION"),
+        schema: SCHEMA_VERSION,
+        head,
+        commits,
+        incremental_base,
+        phases: vec![cold, warm, incremental],
+    })
+}
+
+/// Scan `db` up to HEAD, timing the whole scan including loading the tree
+async fn timed_scan(name: &'static str, scanner: &GitScanner, db: &Database) -> Result<PhaseReport> {
+    let before = db.load_scanned_commit_oids().await.len();
+    let measures_peak = reset_peak_rss();
+    let started = Instant::now();
+    scanner.scan(db).await?;
+    let wall_ms = started.elapsed().as_millis() as u64;
+    let peak_rss_bytes = if measures_peak { peak_rss() } else { None };
+    let commits_scanned = db.load_scanned_commit_oids().await.len().saturating_sub(before);
+    info!("{} scan: {} commits in {} ms", name, commits_scanned, wall_ms);
+    Ok(PhaseReport { name, wall_ms, commits_scanned, peak_rss_bytes })
+}
@@ -0,0 +1,60 @@
+//! `repodiet blob <oid>`: everything the index knows about one blob
+
+use anyhow::{bail, Context, Result};
+use std::fmt::Write;
+use std::path::Path;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::BlobLookup;
+use repodiet::repository::GitScanner;
+use repodiet::util::{format_size, DateStyle};
+
+use crate::exit_code::ScanFailed;
+use crate::logging;
+
+use super::{cache_db_path, open_database};
+
+pub async fn run(repo_path: &str, oid: &str, overrides: &Overrides) -> Result<()> {
+    let config = Config::load(Path::new(repo_path), overrides)?;
+    let db = open_database(&cache_db_path(repo_path)?, &config.database).await?;
+
+    // Bring the index up to date first so recent commits are covered
+    let scanner = if logging::quiet() { GitScanner::quiet(repo_path) } else { GitScanner::new(repo_path) };
+    scanner.scan(&db).await.context(ScanFailed)?;
+
+    match db.lookup_blob(oid).await? {
+        Some(blob) => {
+            print!("{}", format_lookup(&blob, &config.export_dates()));
+            Ok(())
+        }
+        None => bail!("No blob matching {} in the history of {}", oid, repo_path),
+    }
+}
+
+/// Plain-text report for a looked-up blob
+pub fn format_lookup(blob: &BlobLookup, dates: &DateStyle) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "blob        {}", hex::encode(&blob.oid));
+    let _ = writeln!(out, "size        {} (on disk)", format_size(blob.size));
+    let _ = writeln!(
+        out,
+        "introduced  {} by {} <{}> at {}",
+        dates.format(blob.first_date),
+        blob.first_author,
+        blob.first_email,
+        blob.path
+    );
+    if !blob.first_subject.is_empty() {
+        let _ = writeln!(out, "commit      {}", blob.first_subject);
+    }
+    let _ = writeln!(out, "in HEAD     {}", if blob.in_head() { "yes" } else { "no" });
+    let _ = writeln!(out, "paths ({}):", blob.occurrences.len());
+    for occurrence in &blob.occurrences {
+        let marker = if occurrence.in_head { "*" } else { " " };
+        let _ = writeln!(out, "  {} {}", marker, occurrence.path);
+    }
+    if blob.in_head() {
+        let _ = writeln!(out, "(* = still in HEAD at this path)");
+    }
+    out
+}
@@ -0,0 +1,109 @@
+//! `repodiet cache`: find, list and delete cached indexes
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use repodiet::util::format_size;
+
+use super::{cache_dir, cache_db_path};
+
+/// SQLite keeps these next to an index while it is open
+const SIDECAR_SUFFIXES: &[&str] = &["-wal", "-shm"];
+
+pub fn path(repo_path: &str) -> Result<()> {
+    println!("{}", cache_db_path(repo_path)?.display());
+    Ok(())
+}
+
+pub fn list() -> Result<()> {
+    let dir = cache_dir()?;
+    let indexes = cached_indexes(&dir)?;
+    let mut total = 0;
+    for index in &indexes {
+        let size = index_size(index);
+        total += size;
+        let name = index.file_stem().and_then(|n| n.to_str()).unwrap_or("?");
+        println!("{:>10}  {}", format_size(size), name);
+    }
+    println!("{} indexes, {} in {}", indexes.len(), format_size(total), dir.display());
+    Ok(())
+}
+
+/// Delete the index for `repo_path`, or every index when it is `None`
+pub fn clear(repo_path: Option<&str>) -> Result<()> {
+    let indexes = match repo_path {
+        Some(repo_path) => vec![cache_db_path(repo_path)?],
+        None => cached_indexes(&cache_dir()?)?,
+    };
+    let mut freed = 0;
+    let mut removed = 0;
+    for index in &indexes {
+        let size = index_size(index);
+        for file in index_files(index) {
+            match fs::remove_file(&file) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("Could not delete {}", file.display())),
+            }
+        }
+        if size > 0 {
+            removed += 1;
+            freed += size;
+        }
+    }
+    info!("Deleted {} indexes, freed {}", removed, format_size(freed));
+    Ok(())
+}
+
+/// Index files in the cache directory, sorted by name
+fn cached_indexes(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut indexes: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Could not read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+        .collect();
+    indexes.sort();
+    Ok(indexes)
+}
+
+/// The index and its SQLite sidecar files
+fn index_files(index: &Path) -> Vec<PathBuf> {
+    let mut files = vec![index.to_path_buf()];
+    for suffix in SIDECAR_SUFFIXES {
+        let mut name = index.as_os_str().to_os_string();
+        name.push(suffix);
+        files.push(PathBuf::from(name));
+    }
+    files
+}
+
+/// Bytes used by an index including sidecars; 0 if it doesn't exist
+fn index_size(index: &Path) -> u64 {
+    index_files(index)
+        .iter()
+        .filter_map(|file| fs::metadata(file).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indexes_and_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b_1.db"), [0u8; 10]).unwrap();
+        fs::write(dir.path().join("b_1.db-wal"), [0u8; 5]).unwrap();
+        fs::write(dir.path().join("a_2.db"), [0u8; 1]).unwrap();
+        fs::write(dir.path().join("notes.txt"), "x").unwrap();
+
+        let indexes = cached_indexes(dir.path()).unwrap();
+        let names: Vec<_> = indexes.iter().map(|p| p.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, vec!["a_2.db", "b_1.db"]);
+        assert_eq!(index_size(&indexes[1]), 15);
+        assert_eq!(index_size(&dir.path().join("missing.db")), 0);
+    }
+}
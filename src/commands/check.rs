@@ -0,0 +1,247 @@
+//! `repodiet check`: fail when the repository exceeds a size budget
+//!
+//! Meant for CI: every configured budget is printed with its measured value,
+//! and the command exits with an error if any of them is exceeded. Paths
+//! excluded by the configuration don't count.
+//!
+//! With `--against-last` the scan is also compared with the last snapshot
+//! `repodiet snapshot` recorded, and growth of the total, an extension or a
+//! directory beyond the growth limits fails the check too (or, with
+//! `--warn-growth`, is only printed).
+
+use anyhow::Result;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::{ExtensionStats, Growth, GrowthLimits, SizeSnapshot, SnapshotEntryKind};
+use repodiet::util::{format_size, DateStyle};
+
+use crate::cli::{CheckLimits, ExtensionBudget, ScanArgs};
+use crate::exit_code::ThresholdExceeded;
+
+use super::scan_with_config;
+
+/// One measured size against its budget
+#[derive(Debug, PartialEq)]
+struct Check {
+    label: String,
+    measured: u64,
+    limit: u64,
+}
+
+impl Check {
+    fn passed(&self) -> bool {
+        self.measured <= self.limit
+    }
+
+    fn line(&self) -> String {
+        format!(
+            "{:<4}  {} {} (limit {})",
+            if self.passed() { "ok" } else { "FAIL" },
+            self.label,
+            format_size(self.measured),
+            format_size(self.limit)
+        )
+    }
+}
+
+pub async fn run(args: &ScanArgs, overrides: &Overrides, limits: &CheckLimits) -> Result<()> {
+    let config = Config::load(&args.repo_path, overrides)?;
+    let (db, root) = scan_with_config(args, &config).await?;
+    let exclude = config.path_filter()?;
+    let largest = db.get_top_blobs_filtered(1, false, &exclude).await?.into_iter().next();
+    let extensions = if limits.max_extension.is_empty() && !limits.against_last {
+        Vec::new()
+    } else {
+        db.get_extension_stats(&exclude).await?
+    };
+    let last = if limits.against_last {
+        db.get_latest_size_snapshot(args.path.as_deref().unwrap_or("")).await?
+    } else {
+        None
+    };
+    db.close().await;
+
+    let blob_label = match &largest {
+        Some(blob) => format!("largest blob ({})", blob.path),
+        None => "largest blob".to_string(),
+    };
+    let largest_size = largest.map_or(0, |b| b.size);
+    let mut checks = collect_checks(limits, [
+        ("cumulative size".to_string(), root.root().cumulative_size),
+        ("current size".to_string(), root.root().current_size),
+        ("deleted size".to_string(), root.root().deleted_cumulative_size()),
+        (blob_label, largest_size),
+    ]);
+    checks.extend(extension_checks(&limits.max_extension, &extensions));
+
+    for check in &checks {
+        println!("{}", check.line());
+    }
+    let mut problems = Vec::new();
+    let failed = checks.iter().filter(|c| !c.passed()).count();
+    if failed > 0 {
+        problems.push(format!("{} of {} size checks failed", failed, checks.len()));
+    }
+
+    if limits.against_last {
+        let current = SizeSnapshot::of_tree(0, &root, &extensions, largest_size);
+        let alerts = growth_alerts(last.as_ref(), &current, &limits.growth_limits());
+        let lines = growth_lines(last.as_ref(), &alerts, limits.warn_growth, &config.export_dates());
+        for line in &lines {
+            println!("{}", line);
+        }
+        if !alerts.is_empty() && !limits.warn_growth {
+            problems.push(format!("{} grew beyond the growth limits since the last snapshot", alerts.len()));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(ThresholdExceeded(problems.join("; ")).into());
+    }
+    Ok(())
+}
+
+/// What grew beyond `limits` from `last` to `current`; nothing without a
+/// snapshot to compare with
+fn growth_alerts(last: Option<&SizeSnapshot>, current: &SizeSnapshot, limits: &GrowthLimits) -> Vec<Growth> {
+    let Some(last) = last else {
+        return Vec::new();
+    };
+    current.growth_since(last).into_iter().filter(|growth| limits.exceeded_by(growth)).collect()
+}
+
+/// The block `--against-last` prints: a heading naming the snapshot, then a
+/// line per alert, tagged FAIL or, with `warn`, WARN
+fn growth_lines(last: Option<&SizeSnapshot>, alerts: &[Growth], warn: bool, dates: &DateStyle) -> Vec<String> {
+    let Some(last) = last else {
+        return vec!["skip  growth: no snapshot to compare with yet (record one with `repodiet snapshot`)".to_string()];
+    };
+    let mut since = dates.format(last.taken_at);
+    if let Some(head) = last.head.get(..12) {
+        since = format!("{} ({})", since, head);
+    }
+    if alerts.is_empty() {
+        return vec![format!("ok    growth since the snapshot of {} within limits", since)];
+    }
+    let mut lines = vec![format!("growth since the snapshot of {}:", since)];
+    for growth in alerts {
+        let label = match growth.kind {
+            None => "cumulative size".to_string(),
+            Some(SnapshotEntryKind::Extension) => format!("cumulative size of {} files", growth.name),
+            Some(SnapshotEntryKind::Directory) => format!("cumulative size of {}/", growth.name),
+        };
+        let percent = growth.percent().map_or_else(|| "new".to_string(), |percent| format!("+{:.1}%", percent));
+        lines.push(format!(
+            "{:<4}  {} grew {} ({}) to {}",
+            if warn { "WARN" } else { "FAIL" },
+            label,
+            format_size(growth.bytes()),
+            percent,
+            format_size(growth.after)
+        ));
+    }
+    lines
+}
+
+/// Pair each measurement with its budget, skipping those without one;
+/// `measured` is in the order cumulative, current, deleted, largest blob
+fn collect_checks(limits: &CheckLimits, measured: [(String, u64); 4]) -> Vec<Check> {
+    let budgets = [limits.max_cumulative, limits.max_current, limits.max_deleted, limits.max_blob];
+    measured
+        .into_iter()
+        .zip(budgets)
+        .filter_map(|((label, measured), limit)| limit.map(|limit| Check { label, measured, limit }))
+        .collect()
+}
+
+/// Each extension budget against the cumulative size of that extension; an
+/// extension the repository doesn't have measures zero
+fn extension_checks(budgets: &[ExtensionBudget], stats: &[(String, ExtensionStats)]) -> Vec<Check> {
+    budgets
+        .iter()
+        .map(|budget| Check {
+            label: format!("cumulative size of {} files", budget.extension),
+            measured: stats
+                .iter()
+                .find(|(extension, _)| *extension == budget.extension)
+                .map_or(0, |(_, s)| s.cumulative_size),
+            limit: budget.size,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_configured_checks_run() {
+        let limits = CheckLimits {
+            max_cumulative: None,
+            max_current: Some(1024),
+            max_deleted: Some(100),
+            max_blob: None,
+            max_extension: vec![],
+            against_last: false,
+            max_growth: None,
+            max_growth_percent: 10.0,
+            warn_growth: false,
+        };
+        let checks = collect_checks(&limits, [
+            ("cumulative size".into(), 5000),
+            ("current size".into(), 1024),
+            ("deleted size".into(), 101),
+            ("largest blob".into(), 900),
+        ]);
+
+        assert_eq!(checks.len(), 2);
+        assert!(checks[0].passed());
+        assert!(!checks[1].passed());
+        assert_eq!(checks[0].line(), "ok    current size 1.0 KB (limit 1.0 KB)");
+        assert_eq!(checks[1].line(), "FAIL  deleted size 101 B (limit 100 B)");
+    }
+
+    #[test]
+    fn test_growth_block() {
+        let dates = DateStyle::default();
+        assert!(growth_lines(None, &[], false, &dates)[0].starts_with("skip  growth: no snapshot"));
+
+        let last = SizeSnapshot { taken_at: 1_700_000_000, head: "ab".repeat(20), cumulative_size: 2048, ..Default::default() };
+        let mut current = SizeSnapshot { cumulative_size: 2100, ..Default::default() };
+        let limits = GrowthLimits { bytes: None, percent: Some(10.0) };
+        let alerts = growth_alerts(Some(&last), &current, &limits);
+        assert_eq!(
+            growth_lines(Some(&last), &alerts, false, &dates),
+            ["ok    growth since the snapshot of 2023-11-14 (abababababab) within limits"]
+        );
+
+        current.cumulative_size = 4096;
+        current.entries.push(repodiet::model::SnapshotEntry {
+            kind: SnapshotEntryKind::Directory,
+            name: "assets".into(),
+            cumulative_size: 2048,
+            current_size: 0,
+        });
+        let alerts = growth_alerts(Some(&last), &current, &limits);
+        assert_eq!(growth_lines(Some(&last), &alerts, true, &dates), [
+            "growth since the snapshot of 2023-11-14 (abababababab):",
+            "WARN  cumulative size grew 2.0 KB (+100.0%) to 4.0 KB",
+            "WARN  cumulative size of assets/ grew 2.0 KB (new) to 2.0 KB",
+        ]);
+    }
+
+    #[test]
+    fn test_extension_checks() {
+        let budgets = [
+            ExtensionBudget { extension: ".zip".into(), size: 1000 },
+            ExtensionBudget { extension: ".psd".into(), size: 1000 },
+        ];
+        let zip = ExtensionStats { cumulative_size: 1500, current_size: 0, deleted_size: 1500, file_count: 2, binary_size: 0 };
+        let checks = extension_checks(&budgets, &[(".zip".into(), zip)]);
+
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].line(), "FAIL  cumulative size of .zip files 1.5 KB (limit 1000 B)");
+        assert!(checks[1].passed());
+        assert_eq!(checks[1].measured, 0);
+    }
+}
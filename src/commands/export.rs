@@ -0,0 +1,55 @@
+//! Lists exported from the TUI: the rows a view shows, as CSV or JSON
+//!
+//! Files go to the working directory, named after the view
+//! (`repodiet-extensions.csv`); a number is added rather than overwriting
+//! an earlier export (`repodiet-extensions-2.csv`).
+
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use repodiet::model::{ExportFormat, ListExport};
+
+/// Write `list` into `dir` as `format`, returning the file written
+pub fn write(dir: &Path, list: &ListExport, format: ExportFormat) -> Result<PathBuf> {
+    let contents = list.render(format);
+    for n in 1.. {
+        let name = match n {
+            1 => format!("repodiet-{}.{}", list.name, format.extension()),
+            n => format!("repodiet-{}-{}.{}", list.name, n, format.extension()),
+        };
+        let path = dir.join(name);
+        // Created only if missing, so two exports never share a file
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes()).with_context(|| format!("Could not write {}", path.display()))?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("Could not create {}", path.display())),
+        }
+    }
+    unreachable!("some file name is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_never_overwrites() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut list = ListExport::new("tree", vec!["path", "cumulative_size"]);
+        list.push(vec!["src".into(), 1000u64.into()]);
+
+        let first = write(dir.path(), &list, ExportFormat::Csv).unwrap();
+        assert_eq!(first, dir.path().join("repodiet-tree.csv"));
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "path,cumulative_size\nsrc,1000\n");
+
+        let second = write(dir.path(), &list, ExportFormat::Csv).unwrap();
+        assert_eq!(second, dir.path().join("repodiet-tree-2.csv"));
+        let json = write(dir.path(), &list, ExportFormat::Json).unwrap();
+        assert_eq!(json, dir.path().join("repodiet-tree.json"));
+    }
+}
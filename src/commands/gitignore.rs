@@ -0,0 +1,82 @@
+//! `repodiet --suggest-gitignore`: print `.gitignore` entries for build
+//! output, caches and logs that were committed, with the history each would
+//! have kept out
+//!
+//! The output can be appended to `.gitignore` as is: sizes are comments on
+//! their own lines.
+
+use anyhow::Result;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::{suggest_gitignore, ExtensionStats, GitignoreSuggestion, GENERATED_DIRS};
+use repodiet::repository::Database;
+use repodiet::util::{format_size, PathFilter};
+
+use crate::cli::ScanArgs;
+
+use super::scan_top_level;
+
+pub async fn run(args: &ScanArgs, overrides: &Overrides) -> Result<()> {
+    let config = Config::load(&args.repo_path, overrides)?;
+    // Everything needed is summed in the index; the tree isn't walked
+    let (db, _) = scan_top_level(args, &config).await?;
+    let exclude = config.path_filter()?;
+    let extensions = db.get_extension_stats(&exclude).await?;
+    let found = suggestions(&db, &extensions, &exclude).await?;
+    db.close().await;
+
+    print!("{}", format_suggestions(&found));
+    Ok(())
+}
+
+/// `suggest_gitignore` for the indexed repository, leaving out excluded
+/// directories
+pub async fn suggestions(
+    db: &Database,
+    extensions: &[(String, ExtensionStats)],
+    exclude: &PathFilter,
+) -> Result<Vec<GitignoreSuggestion>> {
+    let mut dirs = db.get_dirs_named(GENERATED_DIRS).await?;
+    dirs.retain(|(path, ..)| !exclude.is_excluded(path));
+    Ok(suggest_gitignore(&dirs, extensions))
+}
+
+/// Suggestions as `.gitignore` lines, each after a comment with its sizes
+fn format_suggestions(suggestions: &[GitignoreSuggestion]) -> String {
+    if suggestions.is_empty() {
+        return "# Nothing to suggest: no build output, caches or logs in history\n".to_string();
+    }
+    let mut out = String::from("# Suggested by repodiet: history each pattern would have kept out\n");
+    for suggestion in suggestions {
+        let mut sizes = format!("# {} in history", format_size(suggestion.prevented));
+        if suggestion.deleted > 0 {
+            sizes.push_str(&format!(", {} since deleted", format_size(suggestion.deleted)));
+        }
+        if suggestion.current > 0 {
+            sizes.push_str(&format!(", {} still tracked (git rm -r --cached)", format_size(suggestion.current)));
+        }
+        out.push_str(&format!("{}\n{}\n", sizes, suggestion.pattern));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_is_valid_gitignore() {
+        let suggestions = [
+            GitignoreSuggestion { pattern: "build/".into(), prevented: 3072, current: 1024, deleted: 2048 },
+            GitignoreSuggestion { pattern: "*.log".into(), prevented: 10, current: 0, deleted: 0 },
+        ];
+        assert_eq!(
+            format_suggestions(&suggestions),
+            "# Suggested by repodiet: history each pattern would have kept out\n\
+             # 3.0 KB in history, 2.0 KB since deleted, 1.0 KB still tracked (git rm -r --cached)\n\
+             build/\n\
+             # 10 B in history\n\
+             *.log\n"
+        );
+    }
+}
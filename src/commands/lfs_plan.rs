@@ -0,0 +1,80 @@
+//! `repodiet lfs-plan`: which binaries to move to Git LFS, and how
+//!
+//! Prints the patterns with their estimated savings, the `git lfs migrate`
+//! command and the `.gitattributes` lines; the TUI shows the same plan from
+//! the Insights and extension views. Paths excluded by the configuration
+//! don't count.
+
+use anyhow::Result;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::LfsPlan;
+use repodiet::util::format_size;
+
+use crate::cli::ScanArgs;
+
+use super::scan_top_level;
+
+/// Largest blobs considered for single-path patterns
+const PLAN_BLOBS: usize = 1000;
+
+pub async fn run(args: &ScanArgs, overrides: &Overrides, min_size: u64) -> Result<()> {
+    let config = Config::load(&args.repo_path, overrides)?;
+    let (db, _) = scan_top_level(args, &config).await?;
+    let exclude = config.path_filter()?;
+    let extensions = db.get_extension_stats(&exclude).await?;
+    let blobs = db.get_top_blobs_filtered(PLAN_BLOBS, false, &exclude).await?;
+    db.close().await;
+
+    let extensions: Vec<_> = extensions
+        .iter()
+        .map(|(extension, stats)| (extension.as_str(), stats.cumulative_size, stats.file_count))
+        .collect();
+    print!("{}", format_plan(&LfsPlan::new(&extensions, &blobs, min_size)));
+    Ok(())
+}
+
+fn format_plan(plan: &LfsPlan) -> String {
+    if plan.is_empty() {
+        return format!(
+            "No binary extension or file holds {} or more of history; nothing to migrate\n",
+            format_size(plan.min_size)
+        );
+    }
+    let mut out = format!(
+        "LFS migration plan: {} patterns, ~{} out of history\n\n",
+        plan.patterns.len(),
+        format_size(plan.savings())
+    );
+    for pattern in &plan.patterns {
+        out.push_str(&format!(
+            "  ~{:>10}  {:>6} versions  {}\n",
+            format_size(pattern.savings()),
+            pattern.files,
+            pattern.pattern
+        ));
+    }
+    out.push_str(&format!("\nRewrite all branches and tags:\n  {}\n", plan.command()));
+    out.push_str("\n.gitattributes:\n");
+    for attribute in plan.gitattributes().lines() {
+        out.push_str(&format!("  {}\n", attribute));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_output() {
+        let plan = LfsPlan::new(&[(".psd", 10 * 1024 * 1024, 2)], &[], 1024);
+        let out = format_plan(&plan);
+        assert!(out.starts_with("LFS migration plan: 1 patterns, ~10.0 MB out of history\n"), "{}", out);
+        assert!(out.contains("git lfs migrate import --everything --include=\"*.psd\""));
+        assert!(out.ends_with("  *.psd filter=lfs diff=lfs merge=lfs -text\n"));
+
+        let empty = format_plan(&LfsPlan::new(&[], &[], 1024));
+        assert_eq!(empty, "No binary extension or file holds 1.0 KB or more of history; nothing to migrate\n");
+    }
+}
@@ -0,0 +1,234 @@
+//! Headless subcommands and the helpers they share with the TUI
+
+pub mod batch;
+pub mod bench;
+pub mod blob;
+pub mod cache;
+pub mod check;
+pub mod export;
+pub mod gitignore;
+pub mod lfs_plan;
+pub mod notes;
+pub mod pr_check;
+pub mod report;
+pub mod report_md;
+pub mod scan;
+pub mod size_snapshot;
+pub mod sizes;
+pub mod snapshot;
+pub mod summary;
+
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use repodiet::config::Config;
+use repodiet::model::{majority_binary, PackHealth, RemoteSize, ScanDiagnostic, StorageHint, Tree};
+use repodiet::repository::{
+    classify_blobs, fetch_remote_size, local_pack_size, origin_url, Checkout, Database, DbTuning, RemoteHosts, RemoteRepo,
+    SCHEMA_VERSION,
+};
+use repodiet::util::PathFilter;
+
+use crate::cli::ScanArgs;
+use crate::exit_code::ScanFailed;
+use crate::interrupt;
+
+/// Directory holding the cached indexes of every repository
+pub fn cache_dir() -> Result<PathBuf> {
+    let cache_dir = dirs::cache_dir()
+        .context("Could not determine cache directory")?
+        .join("repodiet");
+    fs::create_dir_all(&cache_dir)?;
+    Ok(cache_dir)
+}
+
+/// Location of the cached index for a repository
+pub fn cache_db_path(repo_path: &str) -> Result<PathBuf> {
+    Ok(cache_dir()?.join(format!("{}.db", repo_slug(repo_path)?)))
+}
+
+/// Stable file stem identifying a repository
+///
+/// Combines the repository's name with a hash of its common git dir, so
+/// different clones never share an index or a report while every worktree
+/// of one repository does.
+pub fn repo_slug(repo_path: &str) -> Result<String> {
+    let checkout = Checkout::open(Path::new(repo_path))?;
+    let mut hasher = DefaultHasher::new();
+    checkout.common_dir.hash(&mut hasher);
+    let hash = hasher.finish();
+    Ok(format!("{}_{:016x}", checkout.name, hash))
+}
+
+/// Directory name of a checkout, for titles and summaries
+pub fn repo_name(repo_path: &str) -> String {
+    match fs::canonicalize(repo_path) {
+        Ok(abs_repo_path) => abs_repo_path.file_name().and_then(|n| n.to_str()).unwrap_or("repo").to_string(),
+        Err(_) => repo_path.to_string(),
+    }
+}
+
+/// "app" for "app_1a2b3c4d5e6f7a8b.db", the name of a cached index
+pub fn index_name(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("index");
+    match stem.rsplit_once('_') {
+        Some((name, hash)) if !name.is_empty() && hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            name.to_string()
+        }
+        _ => stem.to_string(),
+    }
+}
+
+/// Connect to the index at `db_path` and bring its schema up to date
+pub async fn open_database(db_path: &Path, tuning: &DbTuning) -> Result<Database> {
+    let db_path_str = db_path.to_str().context("Invalid path encoding")?;
+    let db = Database::with_tuning(db_path_str, tuning).await?;
+    db.init_schema().await?;
+    Ok(db)
+}
+
+/// Open the existing index at `path` for reading, refusing one whose schema
+/// this version of repodiet doesn't read
+pub async fn open_existing_index(path: &Path) -> Result<Database> {
+    anyhow::ensure!(path.is_file(), "No index at {}", path.display());
+    let db = Database::open_read_only(path.to_str().context("Invalid path encoding")?).await?;
+    let version = db.get_metadata("schema_version").await;
+    anyhow::ensure!(
+        version.as_deref() == Some(SCHEMA_VERSION),
+        "{} was built by a different version of repodiet (index schema {}, this version reads {})",
+        path.display(),
+        version.as_deref().unwrap_or("unknown"),
+        SCHEMA_VERSION
+    );
+    Ok(db)
+}
+
+/// The index a scan of `args` should use: the repository's cached one, or a
+/// throwaway in-memory one with `--no-cache`
+pub async fn open_index(args: &ScanArgs, tuning: &DbTuning) -> Result<Database> {
+    if args.no_cache {
+        info!("Using an in-memory index (--no-cache)");
+        let db = Database::in_memory().await?;
+        db.init_schema().await?;
+        return Ok(db);
+    }
+    let db_path = cache_db_path(args.repo_path())?;
+    info!("Using index: {}", db_path.display());
+    open_database(&db_path, tuning).await
+}
+
+/// Bring the cached index for `args` up to date, with `config`'s excluded
+/// paths removed from the returned tree
+pub async fn scan_with_config(args: &ScanArgs, config: &Config) -> Result<(Database, Tree)> {
+    let db = open_index(args, &config.database).await?;
+    let interrupt = interrupt::on_ctrl_c();
+    let mut root = args.scanner(false).with_cancel(interrupt.token()).scan(&db).await.context(ScanFailed)?;
+    classify_content(&db, args.repo_path()).await?;
+    let exclude = config.path_filter()?;
+    if !exclude.is_empty() {
+        root.prune(|path| exclude.is_excluded(path));
+    }
+    warn_skipped(&db).await?;
+    Ok((db, root))
+}
+
+/// Whether the TUI can load the tree one directory at a time; excluded paths
+/// and stash and index entries are applied to a whole tree in memory
+pub fn loads_lazily(args: &ScanArgs, exclude: &PathFilter) -> bool {
+    !args.include_stash_index && exclude.is_empty()
+}
+
+/// Bring the cached index for `args` up to date and load only the top level
+/// of its tree (see `Tree::from_dir_entries`); the rest is read on demand
+pub async fn scan_top_level(args: &ScanArgs, config: &Config) -> Result<(Database, Tree)> {
+    let db = open_index(args, &config.database).await?;
+    let interrupt = interrupt::on_ctrl_c();
+    args.scanner(false).with_cancel(interrupt.token()).update_index(&db).await.context(ScanFailed)?;
+    classify_content(&db, args.repo_path()).await?;
+    warn_skipped(&db).await?;
+    let root = Tree::from_dir_entries(db.get_dir_children("").await?);
+    Ok((db, root))
+}
+
+/// Blobs sampled per extension to tell binary from text
+const CONTENT_SAMPLES: usize = 3;
+
+/// Classify extensions (and files without one) new to the index as binary
+/// or text, from the first bytes of sampled blobs
+///
+/// Verdicts are kept in the index, so later scans only read blobs of
+/// extensions they bring in. Blobs that can't be read leave their
+/// extension unclassified, to be tried again next time.
+pub async fn classify_content(db: &Database, repo_path: &str) -> Result<()> {
+    let unclassified = db.get_unclassified_content(CONTENT_SAMPLES).await?;
+    if unclassified.is_empty() {
+        return Ok(());
+    }
+    let oids: Vec<&[u8]> = unclassified.iter().flat_map(|(_, _, oids)| oids.iter().map(Vec::as_slice)).collect();
+    let verdicts = classify_blobs(repo_path, &oids)?;
+
+    let mut kinds = Vec::with_capacity(unclassified.len());
+    let mut verdicts = verdicts.into_iter();
+    for (extension, path, oids) in unclassified {
+        let samples: Vec<Option<bool>> = verdicts.by_ref().take(oids.len()).collect();
+        if let Some(binary) = majority_binary(&samples) {
+            kinds.push((extension, path, binary));
+        }
+    }
+    info!("Classified {} extensions and paths as binary or text", kinds.len());
+    db.save_content_kinds(&kinds).await
+}
+
+/// Log every object and pack the last scan could not read, and return them
+pub async fn warn_skipped(db: &Database) -> Result<Vec<ScanDiagnostic>> {
+    let diagnostics = db.get_scan_diagnostics().await?;
+    for skipped in diagnostics.iter().filter(|d| d.is_skipped_object()) {
+        warn!("Skipped unreadable {} {}: {}", skipped.kind, skipped.oid, skipped.reason);
+    }
+    let health = PackHealth::from_diagnostics(&diagnostics);
+    for pack in &health.corrupt_packs {
+        warn!("Corrupt pack {}", pack);
+    }
+    if health.unknown_sizes > 0 {
+        warn!("{} blobs have unknown sizes and count as 0 B", health.unknown_sizes);
+    }
+    Ok(diagnostics)
+}
+
+/// Local pack size plus, if `origin` is on github.com, gitlab.com or one of
+/// `hosts`, the server's figure
+///
+/// Problems talking to the host are reported as warnings and simply leave
+/// the remote part empty; only failing to open the repository is an error.
+pub async fn storage_hint(repo_path: &str, hosts: &RemoteHosts) -> Result<StorageHint> {
+    let local_pack_size = local_pack_size(repo_path)?;
+
+    let remote = match origin_url(repo_path)?.as_deref().and_then(|url| RemoteRepo::parse(url, hosts)) {
+        Some(remote) => {
+            let token = remote.token(|var| std::env::var(var).ok());
+            let host = remote.host.clone();
+            let fetched: Result<RemoteSize> =
+                tokio::task::spawn_blocking(move || fetch_remote_size(&remote, token.as_deref()))
+                    .await
+                    .context("Remote size lookup panicked")
+                    .and_then(|r| r);
+            match fetched {
+                Ok(size) => Some(size),
+                Err(e) => {
+                    warn!("Could not get repository size from {}: {:#}", host, e);
+                    None
+                }
+            }
+        }
+        None => {
+            warn!("origin is not on github.com, gitlab.com or a host under [remote]; showing local pack size only");
+            None
+        }
+    };
+
+    Ok(StorageHint { local_pack_size, remote })
+}
@@ -0,0 +1,100 @@
+//! `--notes FILE`: what was investigated in the TUI, written up as Markdown
+//!
+//! The steps taken (views opened, directories visited, searches run and
+//! entries marked or unmarked) in order, then what was still marked when the
+//! TUI closed, to paste into a ticket or hand over to whoever cleans up.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use repodiet::model::SessionEvent;
+use repodiet::util::format_size;
+use repodiet::viewmodel::{AppViewModel, MarkSizes};
+
+/// Write the notes of `app`'s recorded session to `path`
+pub fn write(path: &Path, repo: &str, app: &AppViewModel) -> Result<()> {
+    let events = app.session().map_or(&[][..], |session| session.events());
+    let marked: Vec<(String, MarkSizes)> = app.marks.iter().map(|(key, sizes)| (app.mark_label(key), *sizes)).collect();
+    fs::write(path, render(repo, events, &marked)).with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// The notes for `events`, with `marked` entries (label and sizes) listed
+/// as the cleanup candidates
+fn render(repo: &str, events: &[SessionEvent], marked: &[(String, MarkSizes)]) -> String {
+    let mut md = format!("# Investigation notes: {}\n\n## Steps\n\n", repo);
+    if events.is_empty() {
+        md += "Nothing was looked at.\n";
+    }
+    for (i, event) in events.iter().enumerate() {
+        md += &format!("{}. {}\n", i + 1, step(event));
+    }
+
+    md += "\n## Marked for cleanup\n\n";
+    if marked.is_empty() {
+        md += "Nothing was marked.\n";
+        return md;
+    }
+    md += "| Entry | History | In HEAD |\n|---|---:|---:|\n";
+    let mut total = MarkSizes::default();
+    for (label, sizes) in marked {
+        md += &format!("| `{}` | {} | {} |\n", label, format_size(sizes.cumulative), format_size(sizes.current));
+        total.cumulative += sizes.cumulative;
+        total.current += sizes.current;
+    }
+    md += &format!("| **Total** | **{}** | **{}** |\n", format_size(total.cumulative), format_size(total.current));
+    md
+}
+
+/// One numbered line of the steps
+fn step(event: &SessionEvent) -> String {
+    match event {
+        SessionEvent::View(name) => format!("Opened the {} view", name),
+        SessionEvent::Directory(path) => format!("Went to `{}`", path),
+        SessionEvent::Search { query, matches, opened } => {
+            let found = format!("Searched for `{}` ({} matches)", query, matches);
+            match opened {
+                Some(path) => format!("{}, opened `{}`", found, path),
+                None => found,
+            }
+        }
+        SessionEvent::Marked { label, cumulative, current } => {
+            format!("Marked `{}` ({} in history, {} in HEAD)", label, format_size(*cumulative), format_size(*current))
+        }
+        SessionEvent::Unmarked { label } => format!("Unmarked `{}`", label),
+        SessionEvent::ClearedMarks(count) => format!("Cleared {} marks", count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let events = [
+            SessionEvent::View("Tree"),
+            SessionEvent::Directory("/assets".to_string()),
+            SessionEvent::Search { query: "mp4".to_string(), matches: 3, opened: Some("assets/intro.mp4".to_string()) },
+            SessionEvent::Marked { label: "assets/intro.mp4".to_string(), cumulative: 3 << 20, current: 0 },
+        ];
+        let marked = [
+            ("assets/intro.mp4".to_string(), MarkSizes { cumulative: 3 << 20, current: 0 }),
+            ("all .png files".to_string(), MarkSizes { cumulative: 1 << 20, current: 512 << 10 }),
+        ];
+        assert_eq!(
+            render("app", &events, &marked),
+            "# Investigation notes: app\n\n## Steps\n\n\
+             1. Opened the Tree view\n\
+             2. Went to `/assets`\n\
+             3. Searched for `mp4` (3 matches), opened `assets/intro.mp4`\n\
+             4. Marked `assets/intro.mp4` (3.0 MB in history, 0 B in HEAD)\n\
+             \n## Marked for cleanup\n\n\
+             | Entry | History | In HEAD |\n|---|---:|---:|\n\
+             | `assets/intro.mp4` | 3.0 MB | 0 B |\n\
+             | `all .png files` | 1.0 MB | 512.0 KB |\n\
+             | **Total** | **4.0 MB** | **512.0 KB** |\n"
+        );
+        assert!(render("app", &[], &[]).ends_with("Nothing was marked.\n"));
+    }
+}
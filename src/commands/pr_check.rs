@@ -0,0 +1,181 @@
+//! `repodiet pr-check`: flag large files a branch adds, on the pull request
+//!
+//! Only the commits on HEAD that aren't on the base are read, so it is
+//! quick enough for every push and needs no index. Findings are printed as
+//! GitHub Actions workflow commands, which annotate the files on the pull
+//! request's diff, or as a SARIF log for code scanning.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::AddedBlob;
+use repodiet::repository::branch_blobs;
+use repodiet::util::{format_size, uri_path};
+
+use crate::cli::FindingFormat;
+use crate::exit_code::ThresholdExceeded;
+
+/// The rule every finding is reported under
+const RULE_ID: &str = "large-file";
+
+pub struct PrCheck<'a> {
+    pub base: &'a str,
+    pub min_size: u64,
+    pub format: FindingFormat,
+    pub out: Option<&'a Path>,
+    /// Report findings as errors and exit with one
+    pub fail: bool,
+}
+
+pub fn run(repo_path: &str, check: &PrCheck<'_>, overrides: &Overrides) -> Result<()> {
+    let config = Config::load(Path::new(repo_path), overrides)?;
+    let exclude = config.path_filter()?;
+    let mut found = branch_blobs(repo_path, check.base, check.min_size)?;
+    found.retain(|blob| !exclude.is_excluded(&blob.path));
+
+    let output = match check.format {
+        FindingFormat::Github => annotations(&found, check.min_size, check.fail),
+        FindingFormat::Sarif => serde_json::to_string_pretty(&sarif(&found, check.min_size, check.fail))? + "\n",
+    };
+    match check.out {
+        Some(path) => {
+            fs::write(path, output).with_context(|| format!("Could not write {}", path.display()))?;
+            info!("Findings written to {}", path.display());
+        }
+        None => print!("{}", output),
+    }
+
+    if check.fail && !found.is_empty() {
+        let message = format!("{} files of {} or more added since {}", found.len(), format_size(check.min_size), check.base);
+        return Err(ThresholdExceeded(message).into());
+    }
+    Ok(())
+}
+
+/// What a finding says about `blob`
+fn message(blob: &AddedBlob, min_size: u64) -> String {
+    format!(
+        "{} is {} (limit {}), added in {}",
+        blob.path,
+        format_size(blob.size),
+        format_size(min_size),
+        blob.commit.get(..12).unwrap_or(&blob.commit)
+    )
+}
+
+/// One GitHub Actions `::warning` (or `::error`) command per finding
+fn annotations(found: &[AddedBlob], min_size: u64, fail: bool) -> String {
+    let level = if fail { "error" } else { "warning" };
+    found
+        .iter()
+        .map(|blob| {
+            format!(
+                "::{} file={},title={}::{}\n",
+                level,
+                escape_property(&blob.path),
+                escape_property("Large file"),
+                escape_data(&message(blob, min_size))
+            )
+        })
+        .collect()
+}
+
+/// A workflow command's message, with `%` and line breaks encoded
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// A workflow command's property value, which can't hold `:` or `,` either
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// A SARIF 2.1.0 log with a result per finding
+fn sarif(found: &[AddedBlob], min_size: u64, fail: bool) -> Value {
+    let level = if fail { "error" } else { "warning" };
+    let results: Vec<Value> = found
+        .iter()
+        .map(|blob| {
+            json!({
+                "ruleId": RULE_ID,
+                "level": level,
+                "message": { "text": message(blob, min_size) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri_path(&blob.path) },
+                        "region": { "startLine": 1 }
+                    }
+                }],
+                "partialFingerprints": { "blobOid": blob.oid },
+                "properties": { "size": blob.size, "commit": blob.commit }
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "repodiet",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/IlyaGulya/repodiet",
+                    "rules": [{
+                        "id": RULE_ID,
+                        "name": "LargeFile",
+                        "shortDescription": { "text": "Large file added" },
+                        "fullDescription": {
+                            "text": "A file at or above the size limit was committed; every clone keeps it forever, \
+                                     even after it is deleted. Consider Git LFS or keeping it out of the repository."
+                        },
+                        "defaultConfiguration": { "level": level }
+                    }]
+                }
+            },
+            "results": results
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(path: &str) -> AddedBlob {
+        AddedBlob { path: path.to_string(), oid: "ab".repeat(20), size: 3 * 1024 * 1024, commit: "cd".repeat(20) }
+    }
+
+    #[test]
+    fn test_annotations() {
+        let found = [blob("assets/intro.mp4"), blob("odd,name:100%.bin")];
+        assert_eq!(
+            annotations(&found, 1024 * 1024, false),
+            "::warning file=assets/intro.mp4,title=Large file::assets/intro.mp4 is 3.0 MB (limit 1.0 MB), added in cdcdcdcdcdcd\n\
+             ::warning file=odd%2Cname%3A100%25.bin,title=Large file::odd,name:100%25.bin is 3.0 MB (limit 1.0 MB), added in cdcdcdcdcdcd\n"
+        );
+        assert!(annotations(&found[..1], 1024 * 1024, true).starts_with("::error file="));
+    }
+
+    #[test]
+    fn test_sarif() {
+        let log = sarif(&[blob("assets/intro.mp4")], 1024 * 1024, true);
+        assert_eq!(log["version"], "2.1.0");
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], RULE_ID);
+        assert_eq!(result["level"], "error");
+        assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "assets/intro.mp4");
+        assert_eq!(result["properties"]["size"], 3 * 1024 * 1024);
+        assert!(sarif(&[], 1024, false)["runs"][0]["results"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sarif_uri_is_percent_encoded() {
+        let log = sarif(&[blob("docs/Release notes #2.pdf")], 1024 * 1024, false);
+        let location = &log["runs"][0]["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "docs/Release%20notes%20%232.pdf");
+    }
+}
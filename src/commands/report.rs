@@ -0,0 +1,443 @@
+//! `repodiet report`: a JSON storage report for one repository
+//!
+//! The same report `repodiet batch` writes for each repository it scans.
+//! `--report-md` writes a Markdown summary instead (see `report_md`).
+//!
+//! The JSON is described by `schema/report.schema.json`, which `--schema`
+//! prints. `schema_version` changes only when a field is removed, renamed or
+//! changes type; new fields keep it, so consumers should ignore keys they do
+//! not know.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::{AuthorTotals, DeltaEfficiency, ExtensionStats, LargeBlobInfo, PackHealth, ScanDiagnostic, Tree};
+use repodiet::viewmodel::ExtensionViewModel;
+
+use crate::cli::ScanArgs;
+
+use super::{repo_name, report_md, scan_with_config};
+
+/// Entries kept in each ranked section of a report
+pub const TOP_N: usize = 20;
+
+/// `schema_version` of the reports written, matching the schema's `const`
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of `RepoReport`
+pub const REPORT_SCHEMA: &str = include_str!("../../schema/report.schema.json");
+
+/// Storage report for one repository
+#[derive(Debug, Serialize)]
+pub struct RepoReport {
+    /// `REPORT_SCHEMA_VERSION`, first so readers can check it before the rest
+    pub schema_version: u32,
+    pub repo: String,
+    pub head: Option<String>,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+    pub deleted_size: u64,
+    pub blob_count: u64,
+    pub top_entries: Vec<EntryReport>,
+    pub top_extensions: Vec<ExtensionReport>,
+    pub top_blobs: Vec<BlobReport>,
+    /// Who first committed the most bytes, by name or by email domain
+    /// (`authors` setting), after `.mailmap`
+    pub top_authors: Vec<AuthorReport>,
+    /// Paths holding the most versions' bytes, with how well they delta
+    pub delta_efficiency: Vec<DeltaReport>,
+    /// Objects that could not be read, so the sizes above leave them out
+    pub skipped_objects: Vec<SkippedReport>,
+    /// Packs that could not be read in full, as "pack-<checksum>.idx: why"
+    pub corrupt_packs: Vec<String>,
+    /// Blobs in no readable pack, counted as 0 bytes in the sizes above
+    pub unknown_sizes: usize,
+}
+
+/// A top-level file or directory
+#[derive(Debug, Serialize)]
+pub struct EntryReport {
+    pub name: String,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExtensionReport {
+    pub extension: String,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+    pub file_count: u64,
+    /// Part of `cumulative_size` classified as binary
+    pub binary_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlobReport {
+    pub oid: String,
+    pub size: u64,
+    pub path: String,
+    pub first_author: String,
+    pub first_email: String,
+    pub first_date: i64,
+    pub in_head: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthorReport {
+    pub author: String,
+    pub blob_count: u64,
+    /// On-disk bytes of the blobs they committed first
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeltaReport {
+    pub path: String,
+    pub versions: u64,
+    /// Sum of the versions' pack entry sizes
+    pub stored: u64,
+    pub largest: u64,
+    /// `stored` over `versions` times `largest`
+    pub ratio: f64,
+    pub delta_hostile: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedReport {
+    pub oid: String,
+    pub kind: String,
+    pub reason: String,
+}
+
+pub async fn run(args: &ScanArgs, overrides: &Overrides, out: Option<&Path>, markdown: bool) -> Result<()> {
+    let config = Config::load(&args.repo_path, overrides)?;
+    let (db, root) = scan_with_config(args, &config).await?;
+    if markdown {
+        let scope = args.path.as_deref().unwrap_or("");
+        let md = report_md::build(&repo_name(args.repo_path()), &db, &root, &config, scope).await?;
+        db.close().await;
+        return write_report(md, out);
+    }
+    let head = db.get_metadata("head_oid").await;
+    let exclude = config.path_filter()?;
+    let blobs = db.get_top_blobs_filtered(TOP_N, false, &exclude).await?;
+    let extensions = db.get_extension_stats(&exclude).await?;
+    let delta = db.get_delta_efficiency(TOP_N, &exclude).await?;
+    let authors = db.get_author_totals(config.authors, TOP_N, &exclude).await?;
+    let diagnostics = db.get_scan_diagnostics().await?;
+    db.close().await;
+
+    let report = build_report(args.repo_path(), head, &root, extensions, &blobs, &authors, &delta, &diagnostics);
+    write_report(serde_json::to_string_pretty(&report)? + "\n", out)
+}
+
+/// Print the report schema, or write it to `out`, without scanning
+pub fn run_schema(out: Option<&Path>) -> Result<()> {
+    write_report(REPORT_SCHEMA.to_string(), out)
+}
+
+/// Write `report` to `out`, or to stdout without one
+fn write_report(report: String, out: Option<&Path>) -> Result<()> {
+    match out {
+        Some(path) => {
+            fs::write(path, report).with_context(|| format!("Could not write {}", path.display()))?;
+            info!("Report written to {}", path.display());
+        }
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn build_report(
+    repo: &str,
+    head: Option<String>,
+    root: &Tree,
+    extensions: Vec<(String, ExtensionStats)>,
+    blobs: &[LargeBlobInfo],
+    authors: &[AuthorTotals],
+    delta: &[DeltaEfficiency],
+    diagnostics: &[ScanDiagnostic],
+) -> RepoReport {
+    let mut top_entries: Vec<EntryReport> = root
+        .root()
+        .children()
+        .map(|node| EntryReport {
+            name: node.name().to_string(),
+            cumulative_size: node.cumulative_size,
+            current_size: node.current_size,
+        })
+        .collect();
+    top_entries.sort_by_key(|e| std::cmp::Reverse(e.cumulative_size));
+    top_entries.truncate(TOP_N);
+
+    let top_extensions = ExtensionViewModel::new(extensions)
+        .stats()
+        .iter()
+        .take(TOP_N)
+        .map(|s| ExtensionReport {
+            extension: s.extension.clone(),
+            cumulative_size: s.cumulative_size,
+            current_size: s.current_size,
+            file_count: s.file_count,
+            binary_size: s.binary_size,
+        })
+        .collect();
+
+    let top_blobs = blobs
+        .iter()
+        .map(|b| BlobReport {
+            oid: hex::encode(&b.oid),
+            size: b.size,
+            path: b.path.clone(),
+            first_author: b.first_author.clone(),
+            first_email: b.first_email.clone(),
+            first_date: b.first_date,
+            in_head: b.in_head,
+        })
+        .collect();
+
+    let top_authors = authors
+        .iter()
+        .map(|a| AuthorReport { author: a.author.clone(), blob_count: a.blob_count, size: a.size })
+        .collect();
+
+    let delta_efficiency = delta
+        .iter()
+        .map(|d| DeltaReport {
+            path: d.path.clone(),
+            versions: d.versions,
+            stored: d.stored,
+            largest: d.largest,
+            ratio: d.ratio(),
+            delta_hostile: d.is_delta_hostile(),
+        })
+        .collect();
+
+    let skipped_objects = diagnostics
+        .iter()
+        .filter(|d| d.is_skipped_object())
+        .map(|d| SkippedReport {
+            oid: d.oid.clone(),
+            kind: d.kind.clone(),
+            reason: d.reason.clone(),
+        })
+        .collect();
+    let health = PackHealth::from_diagnostics(diagnostics);
+
+    RepoReport {
+        schema_version: REPORT_SCHEMA_VERSION,
+        repo: repo.to_string(),
+        head,
+        cumulative_size: root.root().cumulative_size,
+        current_size: root.root().current_size,
+        deleted_size: root.root().deleted_cumulative_size(),
+        blob_count: root.root().blob_count,
+        top_entries,
+        top_extensions,
+        top_blobs,
+        top_authors,
+        delta_efficiency,
+        skipped_objects,
+        corrupt_packs: health.corrupt_packs,
+        unknown_sizes: health.unknown_sizes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use repodiet::util::extension_label;
+    use serde_json::Value;
+
+    #[test]
+    fn test_build_report_ranks_entries() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
+        root.add_path_with_sizes(&["assets", "logo.png"], 5000, 0, 1);
+        root.compute_totals();
+
+        let png = ExtensionStats { cumulative_size: 5000, current_size: 0, deleted_size: 5000, file_count: 1, binary_size: 0 };
+        let rs = ExtensionStats { cumulative_size: 1000, current_size: 500, deleted_size: 0, file_count: 1, binary_size: 0 };
+        let extensions = vec![(".png".to_string(), png), (".rs".to_string(), rs)];
+
+        let report = build_report("repo", Some("abc".into()), &root, extensions, &[], &[], &[], &[]);
+        assert_eq!(report.cumulative_size, 6000);
+        assert_eq!(report.current_size, 500);
+        assert_eq!(report.deleted_size, 5000);
+        assert_eq!(report.top_entries[0].name, "assets");
+        assert_eq!(report.top_extensions[0].extension, ".png");
+    }
+
+    #[test]
+    fn test_report_json_is_stable() {
+        let build = |paths: &[&str]| {
+            let mut root = Tree::new();
+            let mut extensions = Vec::new();
+            for path in paths {
+                let parts: Vec<&str> = path.split('/').collect();
+                root.add_path_with_sizes(&parts, 100, 100, 1);
+                let stats = ExtensionStats { cumulative_size: 100, current_size: 100, deleted_size: 0, file_count: 1, binary_size: 0 };
+                extensions.push((extension_label(path).into_owned(), stats));
+            }
+            root.compute_totals();
+            serde_json::to_string(&build_report("repo", None, &root, extensions, &[], &[], &[], &[])).unwrap()
+        };
+
+        // Entries and extensions of equal size, added in different orders
+        let forward = build(&["a.txt", "b.md", "c.rs", "d/e.toml"]);
+        let backward = build(&["d/e.toml", "c.rs", "b.md", "a.txt"]);
+        assert_eq!(forward, backward);
+        assert!(forward.find("\"a.txt\"").unwrap() < forward.find("\"b.md\"").unwrap());
+    }
+
+    /// Problems with `value` against the subset of JSON Schema the report
+    /// schema uses, plus keys the schema does not document
+    fn schema_errors(value: &Value, schema: &Value, at: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(expected) = schema.get("const")
+            && value != expected
+        {
+            errors.push(format!("{at}: {value} is not {expected}"));
+        }
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                other => other.as_str().into_iter().collect(),
+            };
+            let matches = |ty: &&str| match *ty {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "number" => value.is_number(),
+                "boolean" => value.is_boolean(),
+                "null" => value.is_null(),
+                other => panic!("{at}: type {other} not handled"),
+            };
+            if !types.iter().any(matches) {
+                errors.push(format!("{at}: {value} is not {types:?}"));
+            }
+        }
+        if let (Some(minimum), Some(number)) = (schema.get("minimum").and_then(Value::as_f64), value.as_f64())
+            && number < minimum
+        {
+            errors.push(format!("{at}: {number} is below {minimum}"));
+        }
+        if let Some(object) = value.as_object() {
+            let properties = schema["properties"].as_object().cloned().unwrap_or_default();
+            for required in schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+                if !object.contains_key(required) {
+                    errors.push(format!("{at}: {required} is missing"));
+                }
+            }
+            for (key, field) in object {
+                match properties.get(key) {
+                    Some(field_schema) => errors.extend(schema_errors(field, field_schema, &format!("{at}.{key}"))),
+                    None => errors.push(format!("{at}: {key} is not in the schema")),
+                }
+            }
+        }
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                errors.extend(schema_errors(item, items, &format!("{at}[{i}]")));
+            }
+        }
+        errors
+    }
+
+    #[test]
+    fn test_report_matches_schema() {
+        let schema: Value = serde_json::from_str(REPORT_SCHEMA).unwrap();
+        assert_eq!(schema["properties"]["schema_version"]["const"], REPORT_SCHEMA_VERSION);
+
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["assets", "logo.png"], 5000, 0, 1);
+        root.compute_totals();
+        let png = ExtensionStats { cumulative_size: 5000, current_size: 0, deleted_size: 5000, file_count: 1, binary_size: 5000 };
+        let blob = LargeBlobInfo {
+            oid: vec![0xab; 20],
+            size: 5000,
+            path: "assets/logo.png".into(),
+            first_author: "Alice".into(),
+            first_email: "alice@example.com".into(),
+            first_date: 1_700_000_000,
+            first_subject: "Add logo".into(),
+            in_head: false,
+        };
+        let author = AuthorTotals { author: "Alice".into(), blob_count: 1, size: 5000 };
+        let delta = DeltaEfficiency { path: "assets/logo.png".into(), versions: 3, stored: 4000, largest: 2000 };
+        let diagnostic = |kind: &str| ScanDiagnostic {
+            oid: "00".repeat(20),
+            kind: kind.into(),
+            path: "pack-ab.idx".into(),
+            reason: "truncated".into(),
+        };
+        let diagnostics = [diagnostic("tree"), diagnostic("pack"), diagnostic("size")];
+
+        // Every section filled, so every field and item schema is checked
+        let full = build_report(
+            "repo",
+            Some("ab".repeat(20)),
+            &root,
+            vec![(".png".into(), png)],
+            &[blob],
+            &[author],
+            &[delta],
+            &diagnostics,
+        );
+        let empty = build_report("repo", None, &Tree::new(), vec![], &[], &[], &[], &[]);
+        for report in [full, empty] {
+            let json = serde_json::to_value(&report).unwrap();
+            assert_eq!(schema_errors(&json, &schema, "report"), Vec::<String>::new());
+        }
+        let json = serde_json::to_string(&build_report("repo", None, &root, vec![], &[], &[], &[], &[])).unwrap();
+        assert!(json.starts_with("{\"schema_version\":1,"));
+    }
+
+    #[test]
+    fn test_schema_keeps_version_1_fields() {
+        // Consumers of version 1 rely on these; dropping or renaming one
+        // needs a new `REPORT_SCHEMA_VERSION`
+        let schema: Value = serde_json::from_str(REPORT_SCHEMA).unwrap();
+        let required = |schema: &Value| -> Vec<String> {
+            schema["required"].as_array().unwrap().iter().map(|key| key.as_str().unwrap().to_string()).collect()
+        };
+        let properties = &schema["properties"];
+        assert_eq!(REPORT_SCHEMA_VERSION, 1);
+        assert_eq!(
+            required(&schema),
+            [
+                "schema_version",
+                "repo",
+                "head",
+                "cumulative_size",
+                "current_size",
+                "deleted_size",
+                "blob_count",
+                "top_entries",
+                "top_extensions",
+                "top_blobs",
+                "top_authors",
+                "delta_efficiency",
+                "skipped_objects",
+                "corrupt_packs",
+                "unknown_sizes",
+            ]
+        );
+        assert_eq!(required(&properties["top_entries"]["items"]), ["name", "cumulative_size", "current_size"]);
+        assert_eq!(
+            required(&properties["top_blobs"]["items"]),
+            ["oid", "size", "path", "first_author", "first_email", "first_date", "in_head"]
+        );
+        assert_eq!(
+            required(&properties["delta_efficiency"]["items"]),
+            ["path", "versions", "stored", "largest", "ratio", "delta_hostile"]
+        );
+    }
+}
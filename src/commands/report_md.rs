@@ -0,0 +1,199 @@
+//! `repodiet report --report-md`: a short Markdown summary for a
+//! pull-request comment
+//!
+//! Totals, the directories that grew most and the largest blobs committed
+//! since the last snapshot `repodiet snapshot` recorded. Without a snapshot
+//! the growth section says so and the largest blobs overall are listed.
+
+use anyhow::Result;
+use time::OffsetDateTime;
+
+use repodiet::config::Config;
+use repodiet::model::{LargeBlobInfo, SizeSnapshot, SnapshotEntryKind, Tree};
+use repodiet::repository::Database;
+use repodiet::util::{format_size, DateStyle};
+
+/// Rows in each ranked section
+const MD_TOP: usize = 10;
+
+/// Build the summary of `root`, scanned into `db` (limited to `scope`,
+/// empty for the whole repository)
+pub async fn build(repo: &str, db: &Database, root: &Tree, config: &Config, scope: &str) -> Result<String> {
+    let exclude = config.path_filter()?;
+    let last = db.get_latest_size_snapshot(scope).await?;
+    let blobs = match &last {
+        Some(last) => db.get_top_blobs_since(last.taken_at, MD_TOP, &exclude).await?,
+        None => db.get_top_blobs_filtered(MD_TOP, false, &exclude).await?,
+    };
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let current = SizeSnapshot::of_tree(now, root, &[], 0);
+    Ok(render(repo, &current, last.as_ref(), &blobs, &config.export_dates()))
+}
+
+/// The summary of `current`, compared with `last` when there is one;
+/// `blobs` are those committed since `last`, or the largest of all
+fn render(repo: &str, current: &SizeSnapshot, last: Option<&SizeSnapshot>, blobs: &[LargeBlobInfo], dates: &DateStyle) -> String {
+    let mut md = format!("### Repository size: {}\n\n", repo);
+    let totals = [
+        ("Cumulative (all history)", current.cumulative_size, last.map(|l| l.cumulative_size)),
+        ("Current (HEAD)", current.current_size, last.map(|l| l.current_size)),
+        ("Deleted, still in history", current.deleted_size, last.map(|l| l.deleted_size)),
+    ];
+    match last {
+        Some(last) => {
+            md += &format!("| | Size | Since {} |\n|---|---:|---:|\n", dates.format(last.taken_at));
+            for (label, size, before) in totals {
+                md += &format!("| {} | {} | {} |\n", label, format_size(size), size_change(before.unwrap_or(0), size));
+            }
+            let blob_change = current.blob_count as i64 - last.blob_count as i64;
+            md += &format!("| Blobs | {} | {:+} |\n", current.blob_count, blob_change);
+        }
+        None => {
+            md += "| | Size |\n|---|---:|\n";
+            for (label, size, _) in totals {
+                md += &format!("| {} | {} |\n", label, format_size(size));
+            }
+            md += &format!("| Blobs | {} |\n", current.blob_count);
+        }
+    }
+
+    match last {
+        Some(last) => {
+            let mut since = dates.format(last.taken_at);
+            if let Some(head) = last.head.get(..12) {
+                since = format!("{} (`{}`)", since, head);
+            }
+            md += &format!("\n**Largest growth since the snapshot of {}**\n\n", since);
+            let grown: Vec<_> = current
+                .growth_since(last)
+                .into_iter()
+                .filter(|growth| growth.kind == Some(SnapshotEntryKind::Directory))
+                .take(MD_TOP)
+                .collect();
+            if grown.is_empty() {
+                md += "No directory grew.\n";
+            } else {
+                md += "| Directory | Grew | Now |\n|---|---:|---:|\n";
+                for growth in grown {
+                    let percent = growth.percent().map_or_else(|| "new".to_string(), |p| format!("+{:.1}%", p));
+                    md += &format!(
+                        "| {} | +{} ({}) | {} |\n",
+                        code(&format!("{}/", growth.name)),
+                        format_size(growth.bytes()),
+                        percent,
+                        format_size(growth.after)
+                    );
+                }
+            }
+            md += "\n**Largest blobs committed since**\n\n";
+        }
+        None => {
+            md += "\n_No snapshot to compare with yet; `repodiet snapshot` records one._\n";
+            md += "\n**Largest blobs**\n\n";
+        }
+    }
+
+    if blobs.is_empty() {
+        md += "None.\n";
+    } else {
+        md += "| Path | Size | Author | Date | In HEAD |\n|---|---:|---|---|:---:|\n";
+        for blob in blobs {
+            md += &format!(
+                "| {} | {} | {} | {} | {} |\n",
+                code(&blob.path),
+                format_size(blob.size),
+                blob.first_author.replace('|', "\\|"),
+                dates.format(blob.first_date),
+                if blob.in_head { "yes" } else { "no" }
+            );
+        }
+    }
+    md
+}
+
+/// "+1.5 KB (+20.0%)", "-300 B" or "0 B": how a size changed
+fn size_change(before: u64, after: u64) -> String {
+    if after == before {
+        return "0 B".to_string();
+    }
+    let (sign, bytes) = if after > before { ('+', after - before) } else { ('-', before - after) };
+    match before {
+        0 => format!("{}{}", sign, format_size(bytes)),
+        _ => format!("{}{} ({}{:.1}%)", sign, format_size(bytes), sign, bytes as f64 * 100.0 / before as f64),
+    }
+}
+
+/// `text` as inline code that is safe in a table cell
+fn code(text: &str) -> String {
+    format!("`{}`", text.replace('`', "'").replace('|', "\\|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use repodiet::model::SnapshotEntry;
+
+    fn dir(name: &str, size: u64) -> SnapshotEntry {
+        SnapshotEntry { kind: SnapshotEntryKind::Directory, name: name.to_string(), cumulative_size: size, current_size: size }
+    }
+
+    fn blob(path: &str, size: u64) -> LargeBlobInfo {
+        LargeBlobInfo {
+            oid: vec![0xab; 20],
+            size,
+            path: path.to_string(),
+            first_author: "Ann".to_string(),
+            first_email: "ann@example.com".to_string(),
+            first_date: 1_700_000_000,
+            first_subject: String::new(),
+            in_head: true,
+        }
+    }
+
+    #[test]
+    fn test_size_change() {
+        assert_eq!(size_change(1024, 1536), "+512 B (+50.0%)");
+        assert_eq!(size_change(2048, 1024), "-1.0 KB (-50.0%)");
+        assert_eq!(size_change(0, 2048), "+2.0 KB");
+        assert_eq!(size_change(5, 5), "0 B");
+    }
+
+    #[test]
+    fn test_render_against_snapshot() {
+        let last = SizeSnapshot {
+            taken_at: 1_699_000_000,
+            head: "cd".repeat(20),
+            cumulative_size: 4096,
+            current_size: 2048,
+            blob_count: 10,
+            entries: vec![dir("assets", 1024)],
+            ..Default::default()
+        };
+        let current = SizeSnapshot {
+            cumulative_size: 6144,
+            current_size: 2048,
+            blob_count: 12,
+            entries: vec![dir("assets", 3072), dir("a|b", 100)],
+            ..Default::default()
+        };
+        let md = render("app", &current, Some(&last), &[blob("assets/intro.mp4", 2048)], &DateStyle::default());
+        let lines: Vec<&str> = md.lines().collect();
+        assert_eq!(lines[0], "### Repository size: app");
+        assert_eq!(lines[2], "| | Size | Since 2023-11-03 |");
+        assert_eq!(lines[4], "| Cumulative (all history) | 6.0 KB | +2.0 KB (+50.0%) |");
+        assert_eq!(lines[5], "| Current (HEAD) | 2.0 KB | 0 B |");
+        assert_eq!(lines[7], "| Blobs | 12 | +2 |");
+        assert!(md.contains("**Largest growth since the snapshot of 2023-11-03 (`cdcdcdcdcdcd`)**"));
+        assert!(md.contains("| `assets/` | +2.0 KB (+200.0%) | 3.0 KB |\n| `a\\|b/` | +100 B (new) | 100 B |"));
+        assert!(md.ends_with("| `assets/intro.mp4` | 2.0 KB | Ann | 2023-11-14 | yes |\n"));
+    }
+
+    #[test]
+    fn test_render_without_snapshot() {
+        let current = SizeSnapshot { cumulative_size: 100, blob_count: 1, ..Default::default() };
+        let md = render("app", &current, None, &[], &DateStyle::default());
+        assert!(md.contains("| Cumulative (all history) | 100 B |\n"));
+        assert!(md.contains("_No snapshot to compare with yet"));
+        assert!(md.ends_with("**Largest blobs**\n\nNone.\n"));
+    }
+}
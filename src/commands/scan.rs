@@ -0,0 +1,41 @@
+//! `repodiet scan`: update the index without opening the TUI
+//!
+//! With `--profile` the scan runs against a fresh temporary index, so every
+//! run measures a full scan; add `--no-cache` to keep that index in memory.
+
+use anyhow::{Context, Result};
+use std::fs;
+use tracing::info;
+
+use repodiet::config::{Config, Overrides};
+
+use crate::cli::ScanArgs;
+use crate::exit_code::ScanFailed;
+use crate::interrupt;
+
+use super::summary::format_totals;
+use super::{open_database, open_index, warn_skipped};
+
+pub async fn run(args: &ScanArgs, profile: bool) -> Result<()> {
+    let tuning = Config::load(&args.repo_path, &Overrides::default())?.database;
+    let db = if profile && !args.no_cache {
+        let temp_dir = std::env::temp_dir().join("repodiet-profile");
+        fs::create_dir_all(&temp_dir)?;
+        let db_path = temp_dir.join("profile.db");
+        if db_path.exists() {
+            fs::remove_file(&db_path)?;
+        }
+        info!("Using index: {}", db_path.display());
+        open_database(&db_path, &tuning).await?
+    } else {
+        open_index(args, &tuning).await?
+    };
+    let interrupt = interrupt::on_ctrl_c();
+    let root = args.scanner(profile).with_cancel(interrupt.token()).scan(&db).await.context(ScanFailed)?;
+    let diagnostics = warn_skipped(&db).await?;
+    // Leaves a database file that can be copied and opened elsewhere
+    db.close().await;
+
+    print!("{}", format_totals(&root, args.options().limits_label().as_deref(), &diagnostics));
+    Ok(())
+}
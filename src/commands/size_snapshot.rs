@@ -0,0 +1,203 @@
+//! `repodiet snapshot`: record today's totals to track growth over time
+//!
+//! Meant to run from cron: the index is brought up to date incrementally,
+//! its totals (and those of each extension and top directory) are kept in
+//! the index as a dated snapshot, and snapshots older than the retention
+//! window are pruned. With `--history` the totals are also appended to a
+//! CSV or JSON Lines file, for spreadsheets and dashboards.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::warn;
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::{csv_field, SizeSnapshot};
+use repodiet::util::format_size;
+
+use crate::cli::ScanArgs;
+
+use super::scan_with_config;
+
+const DAY: i64 = 24 * 3600;
+
+/// Columns of a CSV history file, in order
+const CSV_HEADER: &str = "date,head,path,cumulative_size,current_size,deleted_size,blob_count,largest_blob";
+
+/// How a history file is written, from its extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HistoryFormat {
+    Csv,
+    JsonLines,
+}
+
+impl HistoryFormat {
+    /// JSON Lines for `.jsonl` and `.ndjson` files, CSV otherwise
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("jsonl" | "ndjson") => HistoryFormat::JsonLines,
+            _ => HistoryFormat::Csv,
+        }
+    }
+}
+
+/// A snapshot as a line of a JSON Lines history file
+#[derive(Debug, Serialize)]
+struct HistoryLine<'a> {
+    date: String,
+    head: &'a str,
+    path: &'a str,
+    cumulative_size: u64,
+    current_size: u64,
+    deleted_size: u64,
+    blob_count: u64,
+    largest_blob: u64,
+}
+
+impl<'a> HistoryLine<'a> {
+    fn new(snapshot: &'a SizeSnapshot) -> Self {
+        let date = OffsetDateTime::from_unix_timestamp(snapshot.taken_at)
+            .ok()
+            .and_then(|date| date.format(&Rfc3339).ok())
+            .unwrap_or_default();
+        Self {
+            date,
+            head: &snapshot.head,
+            path: &snapshot.scope,
+            cumulative_size: snapshot.cumulative_size,
+            current_size: snapshot.current_size,
+            deleted_size: snapshot.deleted_size,
+            blob_count: snapshot.blob_count,
+            largest_blob: snapshot.largest_blob,
+        }
+    }
+
+    fn csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.date,
+            self.head,
+            csv_field(self.path),
+            self.cumulative_size,
+            self.current_size,
+            self.deleted_size,
+            self.blob_count,
+            self.largest_blob
+        )
+    }
+}
+
+pub async fn run(args: &ScanArgs, overrides: &Overrides, retention_days: u32, history: Option<&Path>) -> Result<()> {
+    if args.no_cache {
+        warn!("--no-cache: the snapshot is not kept, as the index isn't");
+    }
+    let config = Config::load(&args.repo_path, overrides)?;
+    let (db, root) = scan_with_config(args, &config).await?;
+    let exclude = config.path_filter()?;
+    let largest = db.get_top_blobs_filtered(1, false, &exclude).await?.into_iter().next();
+    let extensions = db.get_extension_stats(&exclude).await?;
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let mut snapshot = SizeSnapshot::of_tree(now, &root, &extensions, largest.map_or(0, |b| b.size));
+    snapshot.head = db.get_metadata("head_oid").await.unwrap_or_default();
+    snapshot.scope = args.path.clone().unwrap_or_default();
+    db.add_size_snapshot(&snapshot).await?;
+    let pruned = match retention_days {
+        0 => 0,
+        days => db.prune_size_snapshots(now - i64::from(days) * DAY).await?,
+    };
+    let kept = db.get_size_snapshots(&snapshot.scope).await?.len();
+    db.close().await;
+
+    if let Some(path) = history {
+        append_history(path, &snapshot)?;
+    }
+
+    println!(
+        "Recorded {} cumulative, {} current at {}",
+        format_size(snapshot.cumulative_size),
+        format_size(snapshot.current_size),
+        snapshot.head.get(..12).unwrap_or("an unborn HEAD")
+    );
+    match retention_days {
+        0 => println!("{} snapshots kept", kept),
+        days => println!("{} snapshots kept, {} older than {} days pruned", kept, pruned, days),
+    }
+    Ok(())
+}
+
+/// Append `snapshot` to the history file at `path`, writing the CSV header
+/// first when the file is new
+fn append_history(path: &Path, snapshot: &SizeSnapshot) -> Result<()> {
+    let format = HistoryFormat::of(path);
+    let is_new = fs::metadata(path).map_or(true, |m| m.len() == 0);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let line = HistoryLine::new(snapshot);
+    let text = match format {
+        HistoryFormat::Csv if is_new => format!("{}\n{}\n", CSV_HEADER, line.csv()),
+        HistoryFormat::Csv => format!("{}\n", line.csv()),
+        HistoryFormat::JsonLines => format!("{}\n", serde_json::to_string(&line)?),
+    };
+    file.write_all(text.as_bytes()).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_snapshot() -> SizeSnapshot {
+        SizeSnapshot {
+            taken_at: 1_700_000_000,
+            head: "ab".repeat(20),
+            scope: "assets,raw".to_string(),
+            cumulative_size: 4096,
+            current_size: 1024,
+            deleted_size: 2048,
+            blob_count: 7,
+            largest_blob: 900,
+            entries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_format() {
+        assert_eq!(HistoryFormat::of(Path::new("sizes.JSONL")), HistoryFormat::JsonLines);
+        assert_eq!(HistoryFormat::of(Path::new("sizes.ndjson")), HistoryFormat::JsonLines);
+        assert_eq!(HistoryFormat::of(Path::new("sizes.csv")), HistoryFormat::Csv);
+        assert_eq!(HistoryFormat::of(Path::new("sizes")), HistoryFormat::Csv);
+    }
+
+    #[test]
+    fn test_history_lines() {
+        let snapshot = sample_snapshot();
+        let line = HistoryLine::new(&snapshot);
+        assert_eq!(
+            line.csv(),
+            format!("2023-11-14T22:13:20Z,{},\"assets,raw\",4096,1024,2048,7,900", "ab".repeat(20))
+        );
+        let json: serde_json::Value = serde_json::to_value(&line).unwrap();
+        assert_eq!(json["date"], "2023-11-14T22:13:20Z");
+        assert_eq!(json["largest_blob"], 900);
+    }
+
+    #[test]
+    fn test_append_history_writes_csv_header_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sizes.csv");
+        append_history(&path, &sample_snapshot()).unwrap();
+        append_history(&path, &sample_snapshot()).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], lines[2]);
+    }
+}
@@ -0,0 +1,108 @@
+//! `repodiet sizes`: what the index knows about a list of paths, for other
+//! tools to add to their own reports
+//!
+//! Paths are read from standard input (`--stdin`), one per line. Each gets
+//! one row, in the order given and named as given, so the
+//! output lines up with the input; a path no scan saw has `found` false and
+//! no sizes.
+
+use anyhow::{Context, Result};
+use std::io::{self, BufRead};
+
+use repodiet::config::{Config, Overrides};
+use repodiet::model::{DirEntry, ExportFormat, ExportValue, ListExport};
+
+use crate::cli::ScanArgs;
+
+use super::scan_top_level;
+
+pub async fn run(args: &ScanArgs, format: ExportFormat) -> Result<()> {
+    let mut paths = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let line = line.context("Could not read paths from stdin")?;
+        if !line.trim().is_empty() {
+            paths.push(line);
+        }
+    }
+
+    let config = Config::load(&args.repo_path, &Overrides::default())?;
+    let (db, _) = scan_top_level(args, &config).await?;
+    let mut entries = Vec::with_capacity(paths.len());
+    for path in &paths {
+        entries.push(db.get_path_entry(index_path(path)).await?);
+    }
+    db.close().await;
+
+    print!("{}", sizes(&paths, &entries).render(format));
+    Ok(())
+}
+
+/// `path` as the index names it: relative to the root, without `./` or a
+/// trailing `/`
+fn index_path(path: &str) -> &str {
+    let path = path.trim();
+    let path = path.strip_prefix("./").unwrap_or(path);
+    path.trim_end_matches('/')
+}
+
+/// A row per path, with its entry if the index has one
+fn sizes(paths: &[String], entries: &[Option<DirEntry>]) -> ListExport {
+    let mut list = ListExport::new(
+        "sizes",
+        vec!["path", "found", "is_dir", "cumulative_size", "current_size", "deleted_size"],
+    );
+    for (path, entry) in paths.iter().zip(entries) {
+        let row = match entry {
+            Some(entry) => vec![
+                path.as_str().into(),
+                true.into(),
+                entry.is_dir.into(),
+                entry.cumulative_size.into(),
+                entry.current_size.into(),
+                entry.deleted_size.into(),
+            ],
+            None => {
+                let mut row = vec![path.as_str().into(), false.into()];
+                row.resize(list.columns.len(), ExportValue::Empty);
+                row
+            }
+        };
+        list.push(row);
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use repodiet::model::EntryKind;
+
+    #[test]
+    fn test_index_path() {
+        assert_eq!(index_path("./src/main.rs"), "src/main.rs");
+        assert_eq!(index_path("assets/ "), "assets");
+        assert_eq!(index_path("README.md"), "README.md");
+    }
+
+    #[test]
+    fn test_rows_line_up_with_input() {
+        let assets = DirEntry {
+            name: "assets".into(),
+            is_dir: true,
+            cumulative_size: 5000,
+            current_size: 1000,
+            blob_count: 3,
+            deleted_size: 4000,
+            kind: EntryKind::File,
+        };
+        let paths = vec!["./assets/".to_string(), "gone.txt".to_string()];
+        let list = sizes(&paths, &[Some(assets), None]);
+        assert_eq!(
+            list.to_tsv(),
+            "path\tfound\tis_dir\tcumulative_size\tcurrent_size\tdeleted_size\n\
+             ./assets/\ttrue\ttrue\t5000\t1000\t4000\n\
+             gone.txt\tfalse\t\t\t\t\n"
+        );
+        assert!(list.to_json().contains("{\"path\": \"gone.txt\", \"found\": false, \"is_dir\": null,"));
+    }
+}
@@ -0,0 +1,51 @@
+//! `repodiet export-index` / `import-index`: move an index between machines
+//! as a portable snapshot
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use repodiet::repository::DbTuning;
+use repodiet::util::format_size;
+
+use super::{cache_db_path, open_database, open_existing_index};
+
+/// Write the index at `index`, or the cached one of `repo_path`, to `out`
+pub async fn export(repo_path: &str, index: Option<&Path>, out: &Path) -> Result<()> {
+    let db_path = index_path(repo_path, index)?;
+    let db = open_existing_index(&db_path)
+        .await
+        .with_context(|| format!("Nothing to export for {} (run `repodiet scan` first?)", repo_path))?;
+    let file = File::create(out).with_context(|| format!("Could not create {}", out.display()))?;
+    let rows = db.export_snapshot(BufWriter::new(file)).await?;
+    db.close().await;
+
+    let size = fs::metadata(out).map(|m| m.len()).unwrap_or(0);
+    info!("Exported {} rows to {} ({})", rows, out.display(), format_size(size));
+    Ok(())
+}
+
+/// Replace the index at `index`, or the cached one of `repo_path`, with the
+/// snapshot in `snapshot`
+pub async fn import(snapshot: &Path, repo_path: &str, index: Option<&Path>) -> Result<()> {
+    let file = File::open(snapshot).with_context(|| format!("Could not open {}", snapshot.display()))?;
+    let db_path = index_path(repo_path, index)?;
+    let db = open_database(&db_path, &DbTuning::default()).await?;
+    let rows = db
+        .import_snapshot(BufReader::new(file))
+        .await
+        .with_context(|| format!("Could not import {}", snapshot.display()))?;
+    db.close().await;
+
+    info!("Imported {} rows into {}", rows, db_path.display());
+    Ok(())
+}
+
+fn index_path(repo_path: &str, index: Option<&Path>) -> Result<PathBuf> {
+    match index {
+        Some(index) => Ok(index.to_path_buf()),
+        None => cache_db_path(repo_path),
+    }
+}
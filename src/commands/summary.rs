@@ -0,0 +1,156 @@
+//! Plain-text totals printed by `repodiet scan` and when the TUI exits
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use repodiet::model::{PackHealth, RecoveryCommands, ScanDiagnostic, Tree};
+use repodiet::repository::Database;
+use repodiet::util::format_size;
+
+/// Largest files listed when the TUI exits
+const TOP_OFFENDERS: usize = 5;
+
+/// Totals for a scanned tree, one `label  value` line each
+pub fn format_totals(root: &Tree, limits: Option<&str>, diagnostics: &[ScanDiagnostic]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "cumulative  {}", format_size(root.root().cumulative_size));
+    let _ = writeln!(out, "current     {}", format_size(root.root().current_size));
+    let _ = writeln!(out, "deleted     {}", format_size(root.root().deleted_cumulative_size()));
+    let _ = writeln!(out, "blobs       {}", root.root().blob_count);
+    if let Some(limits) = limits {
+        let _ = writeln!(out, "partial     {}", limits);
+    }
+    let skipped = diagnostics.iter().filter(|d| d.is_skipped_object()).count();
+    if skipped > 0 {
+        let _ = writeln!(out, "skipped     {} unreadable objects", skipped);
+    }
+    let health = PackHealth::from_diagnostics(diagnostics);
+    if !health.corrupt_packs.is_empty() {
+        let _ = writeln!(out, "corrupt     {} packs", health.corrupt_packs.len());
+    }
+    if health.unknown_sizes > 0 {
+        let _ = writeln!(out, "unknown     {} blob sizes, counted as 0 B", health.unknown_sizes);
+    }
+    out
+}
+
+/// What stays in the scrollback once the TUI's alternate screen closes:
+/// the headline sizes and the files costing the most history
+/// (`exit_offenders`)
+pub fn format_exit_summary(repo_name: &str, root: &Tree, offenders: &[(String, u64, bool)]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", repo_name);
+    let _ = writeln!(out, "  cumulative  {}", format_size(root.root().cumulative_size));
+    let _ = writeln!(out, "  current     {}", format_size(root.root().current_size));
+    let _ = writeln!(out, "  deleted     {} reclaimable", format_size(root.root().deleted_cumulative_size()));
+
+    if !offenders.is_empty() {
+        let _ = writeln!(out, "  top offenders:");
+        for (path, size, deleted) in offenders {
+            let marker = if *deleted { "  (deleted)" } else { "" };
+            let _ = writeln!(out, "    {:>10}  {}{}", format_size(*size), path, marker);
+        }
+    }
+    out
+}
+
+/// The restore commands copied in the TUI, so they outlive the clipboard;
+/// empty when none were
+pub fn format_recovery_commands(copied: &[RecoveryCommands]) -> String {
+    let mut out = String::new();
+    if !copied.is_empty() {
+        let _ = writeln!(out, "  restore commands:");
+        for commands in copied {
+            let _ = writeln!(out, "    {}", commands.cat_file);
+        }
+    }
+    out
+}
+
+/// The files `format_exit_summary` lists, from the index when `root` is
+/// only partly loaded
+pub async fn exit_offenders(root: &Tree, db: &Database) -> Result<Vec<(String, u64, bool)>> {
+    if !root.is_partial() {
+        return Ok(top_offenders(root, TOP_OFFENDERS));
+    }
+    let files = db.search_paths("", false, false, TOP_OFFENDERS).await?;
+    Ok(files
+        .into_iter()
+        .filter(|(_, cumulative, _)| *cumulative > 0)
+        .map(|(path, cumulative, current)| (path, cumulative, current == 0))
+        .collect())
+}
+
+/// The `limit` files with the largest cumulative size, as (path, size, deleted)
+fn top_offenders(root: &Tree, limit: usize) -> Vec<(String, u64, bool)> {
+    root.root()
+        .top_leaves(limit)
+        .into_iter()
+        .filter(|(_, node)| node.cumulative_size > 0)
+        .map(|(path, node)| (path, node.cumulative_size, node.current_size == 0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree() -> Tree {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["a.bin"], 2048, 1024, 2);
+        root.add_path_with_sizes(&["gone.bin"], 1024, 0, 1);
+        root.compute_totals();
+        root
+    }
+
+    #[test]
+    fn test_format_totals() {
+        let root = sample_tree();
+        let summary = format_totals(&root, Some("first-parent"), &[]);
+        assert_eq!(
+            summary,
+            "cumulative  3.0 KB\ncurrent     1.0 KB\ndeleted     1.0 KB\nblobs       3\npartial     first-parent\n"
+        );
+        let diagnostic = |kind: &str| ScanDiagnostic { oid: "00".repeat(20), kind: kind.to_string(), path: String::new(), reason: String::new() };
+        let diagnostics = [diagnostic("commit"), diagnostic("tree"), diagnostic("pack"), diagnostic("size")];
+        assert!(format_totals(&root, None, &diagnostics).ends_with(
+            "skipped     2 unreadable objects\ncorrupt     1 packs\nunknown     1 blob sizes, counted as 0 B\n"
+        ));
+    }
+
+    #[test]
+    fn test_exit_summary_lists_top_offenders() {
+        let mut root = Tree::new();
+        for i in 0..7u64 {
+            root.add_path_with_sizes(&["assets", &format!("f{}.bin", i)], (i + 1) * 1024, 0, 1);
+        }
+        root.add_path_with_sizes(&["src", "main.rs"], 10 * 1024, 512, 3);
+        root.compute_totals();
+
+        let summary = format_exit_summary("app", &root, &top_offenders(&root, TOP_OFFENDERS));
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[0], "app");
+        assert_eq!(lines[3], "  deleted     28.0 KB reclaimable");
+        assert_eq!(lines[4], "  top offenders:");
+        assert_eq!(lines[5], "       10.0 KB  src/main.rs");
+        assert_eq!(lines[6], "        7.0 KB  assets/f6.bin  (deleted)");
+        assert_eq!(lines.len(), 5 + TOP_OFFENDERS);
+    }
+
+    #[test]
+    fn test_recovery_commands_on_exit() {
+        assert_eq!(format_recovery_commands(&[]), "");
+        let copied = [RecoveryCommands::new("abc123", "assets/intro.mp4"), RecoveryCommands::new("def456", "old/data.bin")];
+        assert_eq!(
+            format_recovery_commands(&copied),
+            "  restore commands:\n    git cat-file -p abc123 > intro.mp4\n    git cat-file -p def456 > data.bin\n"
+        );
+    }
+
+    #[test]
+    fn test_exit_summary_of_empty_tree() {
+        let root = Tree::new();
+        assert!(!format_exit_summary("empty", &root, &top_offenders(&root, TOP_OFFENDERS)).contains("top offenders"));
+    }
+}
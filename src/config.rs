@@ -0,0 +1,469 @@
+//! Settings merged from config files, environment variables and flags
+//!
+//! Later sources win, key by key:
+//!
+//! 1. built-in defaults
+//! 2. the user file, `~/.config/repodiet/config.toml` (or `$REPODIET_CONFIG`,
+//!    or `--config`)
+//! 3. the repository's `.repodiet.toml`
+//! 4. `REPODIET_*` environment variables
+//! 5. command-line flags
+//!
+//! `exclude` lists add up across all sources instead of replacing each other.
+//! Every setting is optional; missing files are skipped.
+//!
+//! ```toml
+//! exclude = ["*.lock", "vendor/"]
+//! top_blobs = 50
+//! search_limit = 200
+//! search_case = "ignore"     # or "smart", or "sensitive"
+//! flatten_depth = 3
+//! old_after_years = 5
+//! theme = "default"          # or "mono"
+//! images = "auto"            # or "kitty", "iterm", or "off"
+//! authors = "name"           # or "domain"
+//! date_format = "relative"   # or "iso", or e.g. "[day].[month].[year]"
+//! timezone = "local"         # or "utc", or e.g. "+05:30"
+//!
+//! [bloat]
+//! warn = 20.0
+//! severe = 50.0
+//!
+//! [keys]
+//! quit = "q"
+//! large_blobs = "L"
+//!
+//! [database]
+//! cache_mb = 64
+//! read_connections = 4
+//!
+//! [remote]                   # user file only
+//! github = ["github.example.com"]
+//! gitlab = ["gitlab.example.com"]
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::input::KeyMap;
+use crate::model::{AuthorGrouping, BloatThresholds, DEFAULT_OLD_AFTER_YEARS};
+use crate::repository::{DbTuning, RemoteHosts};
+use crate::util::{DateFormat, DateStyle, PathFilter, TimeZone};
+use crate::view::{Images, Theme};
+use crate::viewmodel::{SearchCase, DEFAULT_FLATTEN_DEPTH, DEFAULT_PAGE_SIZE};
+
+/// Name of the per-repository config file, looked up in the scanned repository path
+pub const REPO_CONFIG_FILE: &str = ".repodiet.toml";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Path patterns hidden from every view (see `PathFilter`)
+    pub exclude: Vec<String>,
+    /// Number of blobs loaded into the large blobs view
+    pub top_blobs: usize,
+    /// Search results shown at a time
+    pub search_limit: usize,
+    /// How search matches letter case (toggled in the TUI too)
+    pub search_case: SearchCase,
+    /// Levels below the root the flattened view lists
+    pub flatten_depth: usize,
+    /// Content first committed longer ago than this is old
+    pub old_after_years: u32,
+    pub theme: Theme,
+    /// Whether charts are drawn as images, in terminals that can
+    pub images: Images,
+    /// Whether blobs are credited to authors or their email domains
+    pub authors: AuthorGrouping,
+    /// How dates are written; unset, the TUI shows them relative to now
+    /// and text exports as ISO dates
+    pub date_format: Option<DateFormat>,
+    /// Time zone of absolute dates
+    pub timezone: TimeZone,
+    /// Bloat ratios at which entries turn yellow and red
+    pub bloat: BloatThresholds,
+    pub keys: KeyMap,
+    /// SQLite settings for the index
+    pub database: DbTuning,
+    /// Self-hosted instances `--remote-size` may query
+    pub remote: RemoteHosts,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            exclude: Vec::new(),
+            top_blobs: 50,
+            search_limit: DEFAULT_PAGE_SIZE,
+            search_case: SearchCase::default(),
+            flatten_depth: DEFAULT_FLATTEN_DEPTH,
+            old_after_years: DEFAULT_OLD_AFTER_YEARS,
+            theme: Theme::default(),
+            images: Images::default(),
+            authors: AuthorGrouping::default(),
+            date_format: None,
+            timezone: TimeZone::default(),
+            bloat: BloatThresholds::default(),
+            keys: KeyMap::default(),
+            database: DbTuning::default(),
+            remote: RemoteHosts::default(),
+        }
+    }
+}
+
+/// Settings given on the command line; they win over every other source
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    /// User config file to read instead of the default one; it must exist
+    pub config_file: Option<PathBuf>,
+    pub exclude: Vec<String>,
+    pub top_blobs: Option<usize>,
+    pub search_limit: Option<usize>,
+    pub search_case: Option<SearchCase>,
+    pub theme: Option<Theme>,
+    pub authors: Option<AuthorGrouping>,
+    pub date_format: Option<DateFormat>,
+    pub timezone: Option<TimeZone>,
+}
+
+impl Config {
+    /// Default location of the user config file
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("repodiet").join("config.toml"))
+    }
+
+    /// Resolve the settings for `repo_path` from every source
+    pub fn load(repo_path: &Path, overrides: &Overrides) -> Result<Self> {
+        Self::load_with_env(repo_path, overrides, |name| std::env::var(name).ok())
+    }
+
+    /// Like `load`, reading environment variables through `env`
+    pub fn load_with_env(
+        repo_path: &Path,
+        overrides: &Overrides,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let mut files = Vec::new();
+        match overrides.config_file.clone().or_else(|| env("REPODIET_CONFIG").map(PathBuf::from)) {
+            Some(path) => files.push(path),
+            None => files.extend(Self::default_path().filter(|path| path.exists())),
+        }
+        let repo_file = repo_path.join(REPO_CONFIG_FILE);
+        // A cloned repository must not choose where tokens are sent
+        let mut remote = None;
+        if repo_file.exists() {
+            remote = Some(Self::load_files(&files)?.remote);
+            files.push(repo_file);
+        }
+
+        let mut config = Self::load_files(&files)?;
+        if let Some(remote) = remote {
+            config.remote = remote;
+        }
+        config.apply_env(&env)?;
+        config.apply_overrides(overrides);
+        config.validate().context("Invalid configuration")?;
+        Ok(config)
+    }
+
+    /// Merge config files, later files overriding earlier ones key by key
+    pub fn load_files(paths: &[PathBuf]) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        for path in paths {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("Could not read {}", path.display()))?;
+            // Check each file on its own so errors name the file at fault
+            let table = Self::parse_table(&contents)
+                .with_context(|| format!("Invalid config file {}", path.display()))?;
+            merge_tables(&mut merged, table);
+        }
+        Ok(merged.try_into()?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let config = Self::load_files(&[path.to_path_buf()])?;
+        config.validate().with_context(|| format!("Invalid config file {}", path.display()))?;
+        Ok(config)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let config: Self = Self::parse_table(contents)?.try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse one file, rejecting unknown keys and values of the wrong type
+    fn parse_table(contents: &str) -> Result<toml::Table> {
+        let table: toml::Table = toml::from_str(contents)?;
+        let _: Self = table.clone().try_into()?;
+        Ok(table)
+    }
+
+    fn apply_env(&mut self, env: &impl Fn(&str) -> Option<String>) -> Result<()> {
+        fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+            value.trim().parse().map_err(|_| anyhow::anyhow!("{} must be a number, got '{}'", name, value))
+        }
+
+        if let Some(value) = env("REPODIET_EXCLUDE") {
+            self.exclude.extend(value.split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string));
+        }
+        if let Some(value) = env("REPODIET_TOP_BLOBS") {
+            self.top_blobs = number("REPODIET_TOP_BLOBS", &value)?;
+        }
+        if let Some(value) = env("REPODIET_SEARCH_LIMIT") {
+            self.search_limit = number("REPODIET_SEARCH_LIMIT", &value)?;
+        }
+        if let Some(value) = env("REPODIET_FLATTEN_DEPTH") {
+            self.flatten_depth = number("REPODIET_FLATTEN_DEPTH", &value)?;
+        }
+        if let Some(value) = env("REPODIET_OLD_AFTER_YEARS") {
+            self.old_after_years = number("REPODIET_OLD_AFTER_YEARS", &value)?;
+        }
+        if let Some(name) = env("REPODIET_SEARCH_CASE") {
+            match SearchCase::parse(&name) {
+                Some(case) => self.search_case = case,
+                None => bail!("REPODIET_SEARCH_CASE must be 'ignore', 'smart' or 'sensitive', got '{}'", name),
+            }
+        }
+        if let Some(value) = env("REPODIET_BLOAT_WARN") {
+            self.bloat.warn = number("REPODIET_BLOAT_WARN", &value)?;
+        }
+        if let Some(value) = env("REPODIET_BLOAT_SEVERE") {
+            self.bloat.severe = number("REPODIET_BLOAT_SEVERE", &value)?;
+        }
+        if let Some(name) = env("REPODIET_AUTHORS") {
+            match AuthorGrouping::parse(&name) {
+                Some(authors) => self.authors = authors,
+                None => bail!("REPODIET_AUTHORS must be 'name' or 'domain', got '{}'", name),
+            }
+        }
+        if let Some(format) = env("REPODIET_DATE_FORMAT") {
+            match DateFormat::try_from(format) {
+                Ok(format) => self.date_format = Some(format),
+                Err(e) => bail!("REPODIET_DATE_FORMAT: {}", e),
+            }
+        }
+        if let Some(zone) = env("REPODIET_TIMEZONE") {
+            match TimeZone::try_from(zone) {
+                Ok(zone) => self.timezone = zone,
+                Err(e) => bail!("REPODIET_TIMEZONE: {}", e),
+            }
+        }
+        if let Some(name) = env("REPODIET_IMAGES") {
+            match Images::parse(&name) {
+                Some(images) => self.images = images,
+                None => bail!("REPODIET_IMAGES must be 'auto', 'kitty', 'iterm' or 'off', got '{}'", name),
+            }
+        }
+        match env("REPODIET_THEME") {
+            Some(name) => match Theme::parse(&name) {
+                Some(theme) => self.theme = theme,
+                None => bail!("REPODIET_THEME must be 'default' or 'mono', got '{}'", name),
+            },
+            // https://no-color.org
+            None if env("NO_COLOR").is_some_and(|v| !v.is_empty()) => self.theme = Theme::Mono,
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn apply_overrides(&mut self, overrides: &Overrides) {
+        self.exclude.extend(overrides.exclude.iter().cloned());
+        if let Some(top_blobs) = overrides.top_blobs {
+            self.top_blobs = top_blobs;
+        }
+        if let Some(search_limit) = overrides.search_limit {
+            self.search_limit = search_limit;
+        }
+        if let Some(case) = overrides.search_case {
+            self.search_case = case;
+        }
+        if let Some(theme) = overrides.theme {
+            self.theme = theme;
+        }
+        if let Some(authors) = overrides.authors {
+            self.authors = authors;
+        }
+        if let Some(format) = &overrides.date_format {
+            self.date_format = Some(format.clone());
+        }
+        if let Some(zone) = overrides.timezone {
+            self.timezone = zone;
+        }
+    }
+
+    /// Check settings that are valid on their own but not together, or out of range
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(self.top_blobs > 0, "top_blobs must be at least 1");
+        anyhow::ensure!(self.search_limit > 0, "search_limit must be at least 1");
+        anyhow::ensure!(self.flatten_depth > 0, "flatten_depth must be at least 1");
+        anyhow::ensure!(self.old_after_years > 0, "old_after_years must be at least 1");
+        self.bloat.validate()?;
+        self.keys.validate()?;
+        self.database.validate()?;
+        self.path_filter()?;
+        Ok(())
+    }
+
+    /// How the TUI writes dates
+    pub fn tui_dates(&self) -> DateStyle {
+        DateStyle::new(self.date_format.clone().unwrap_or(DateFormat::Relative), self.timezone)
+    }
+
+    /// How text exports write dates
+    pub fn export_dates(&self) -> DateStyle {
+        DateStyle::new(self.date_format.clone().unwrap_or(DateFormat::Iso), self.timezone)
+    }
+
+    /// The compiled `exclude` patterns
+    pub fn path_filter(&self) -> Result<PathFilter> {
+        PathFilter::new(&self.exclude)
+    }
+}
+
+/// Overlay `layer` onto `base`: tables merge recursively, `exclude` lists
+/// are concatenated and any other value replaces the one below it
+fn merge_tables(base: &mut toml::Table, layer: toml::Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(below)), toml::Value::Table(above)) => merge_tables(below, above),
+            (Some(toml::Value::Array(below)), toml::Value::Array(above)) if key == "exclude" => {
+                below.extend(above)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_partial_config() {
+        let config = Config::parse("[bloat]\nsevere = 200.0\n").unwrap();
+        assert_eq!(config.bloat, BloatThresholds { warn: 20.0, severe: 200.0 });
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+
+        let config = Config::parse("[database]\nread_connections = 0\n").unwrap();
+        assert_eq!(config.database, DbTuning { read_connections: 0, ..DbTuning::default() });
+
+        // The TUI defaults to relative dates, exports to ISO ones
+        let config = Config::parse("timezone = \"+09:00\"\n").unwrap();
+        assert_eq!(config.export_dates().format(1_700_000_000), "2023-11-15");
+        assert!(config.tui_dates().format(1_700_000_000).ends_with(" ago"));
+        let config = Config::parse("date_format = \"[day].[month].[year]\"\ntimezone = \"utc\"\n").unwrap();
+        assert_eq!(config.tui_dates().format(1_700_000_000), "14.11.2023");
+
+        assert_eq!(Config::parse("search_case = \"smart\"\n").unwrap().search_case, SearchCase::Smart);
+        assert_eq!(Config::parse("images = \"off\"\n").unwrap().images, Images::Off);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_config() {
+        assert!(Config::parse("[bloat]\nwarn = 80.0\n").is_err());
+        assert!(Config::parse("[bloat]\nwarm = 10.0\n").is_err());
+        assert!(Config::parse("top_blobs = 0\n").is_err());
+        assert!(Config::parse("flatten_depth = 0\n").is_err());
+        assert!(Config::parse("old_after_years = 0\n").is_err());
+        assert!(Config::parse("theme = \"neon\"\n").is_err());
+        assert!(Config::parse("images = \"sixel\"\n").is_err());
+        assert!(Config::parse("authors = \"team\"\n").is_err());
+        assert!(Config::parse("search_case = \"upper\"\n").is_err());
+        assert!(Config::parse("date_format = \"[year\"\n").is_err());
+        assert!(Config::parse("timezone = \"Mars/Olympus\"\n").is_err());
+        assert!(Config::parse("exclude = [\"\"]\n").is_err());
+        assert!(Config::parse("[keys]\nquit = \"qq\"\n").is_err());
+        assert!(Config::parse("[keys]\nsearch = \"q\"\n").is_err());
+        assert!(Config::parse("[database]\ncache_mb = 0\n").is_err());
+    }
+
+    #[test]
+    fn test_merge_tables() {
+        let mut base: toml::Table =
+            toml::from_str("top_blobs = 10\nexclude = [\"*.png\"]\n[bloat]\nwarn = 5.0\n").unwrap();
+        let layer: toml::Table =
+            toml::from_str("exclude = [\"vendor/\"]\n[bloat]\nsevere = 9.0\n").unwrap();
+        merge_tables(&mut base, layer);
+
+        let config: Config = base.try_into().unwrap();
+        assert_eq!(config.top_blobs, 10);
+        assert_eq!(config.exclude, vec!["*.png", "vendor/"]);
+        assert_eq!(config.bloat, BloatThresholds { warn: 5.0, severe: 9.0 });
+    }
+
+    #[test]
+    fn test_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.toml");
+        fs::write(&user, "top_blobs = 10\nsearch_limit = 30\ntheme = \"mono\"\nexclude = [\"*.png\"]\n").unwrap();
+        fs::write(dir.path().join(REPO_CONFIG_FILE), "top_blobs = 20\n[bloat]\nwarn = 2.0\n").unwrap();
+
+        let env = |name: &str| match name {
+            "REPODIET_CONFIG" => Some(user.display().to_string()),
+            "REPODIET_SEARCH_LIMIT" => Some("40".to_string()),
+            "REPODIET_FLATTEN_DEPTH" => Some("5".to_string()),
+            "REPODIET_OLD_AFTER_YEARS" => Some("10".to_string()),
+            "REPODIET_SEARCH_CASE" => Some("sensitive".to_string()),
+            "REPODIET_EXCLUDE" => Some("*.lock, dist/".to_string()),
+            _ => None,
+        };
+        let overrides = Overrides {
+            search_limit: Some(50),
+            theme: Some(Theme::Default),
+            exclude: vec!["docs/".to_string()],
+            ..Default::default()
+        };
+        let config = Config::load_with_env(dir.path(), &overrides, env).unwrap();
+
+        assert_eq!(config.top_blobs, 20);
+        assert_eq!(config.search_limit, 50);
+        assert_eq!(config.search_case, SearchCase::Sensitive);
+        assert_eq!(config.flatten_depth, 5);
+        assert_eq!(config.old_after_years, 10);
+        assert_eq!(config.theme, Theme::Default);
+        assert_eq!(config.bloat.warn, 2.0);
+        assert_eq!(config.exclude, vec!["*.png", "*.lock", "dist/", "docs/"]);
+    }
+
+    #[test]
+    fn test_remote_hosts_come_from_the_user_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.toml");
+        fs::write(&user, "[remote]\ngithub = [\"github.corp.example\"]\n").unwrap();
+        fs::write(dir.path().join(REPO_CONFIG_FILE), "[remote]\ngithub = [\"github.attacker.example\"]\n").unwrap();
+
+        let env = |name: &str| (name == "REPODIET_CONFIG").then(|| user.display().to_string());
+        let config = Config::load_with_env(dir.path(), &Overrides::default(), env).unwrap();
+        assert_eq!(config.remote.github, vec!["github.corp.example"]);
+
+        let no_user = |_: &str| None;
+        let config = Config::load_with_env(dir.path(), &Overrides::default(), no_user).unwrap();
+        assert!(!config.remote.github.contains(&"github.attacker.example".to_string()));
+    }
+
+    #[test]
+    fn test_errors_name_their_source() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(REPO_CONFIG_FILE), "top_blobs = \"many\"\n").unwrap();
+        let no_env = |_: &str| None;
+        let err = Config::load_with_env(dir.path(), &Overrides::default(), no_env).unwrap_err();
+        assert!(format!("{:#}", err).contains(REPO_CONFIG_FILE));
+
+        let empty = tempfile::tempdir().unwrap();
+        let env = |name: &str| (name == "REPODIET_TOP_BLOBS").then(|| "lots".to_string());
+        let err = Config::load_with_env(empty.path(), &Overrides::default(), env).unwrap_err();
+        assert!(err.to_string().contains("REPODIET_TOP_BLOBS"));
+
+        let missing = Overrides { config_file: Some(empty.path().join("nope.toml")), ..Default::default() };
+        assert!(Config::load_with_env(empty.path(), &missing, no_env).is_err());
+
+        let env = |name: &str| (name == "REPODIET_BLOAT_WARN").then(|| "90".to_string());
+        let err = Config::load_with_env(empty.path(), &Overrides::default(), env).unwrap_err();
+        assert!(format!("{:#}", err).contains("must be above warn"));
+    }
+}
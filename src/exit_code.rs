@@ -0,0 +1,96 @@
+//! Exit statuses scripts can branch on
+//!
+//! | Status | Meaning |
+//! |--------|---------|
+//! | 0 | Success |
+//! | 1 | Any other error, including a bad command line |
+//! | 2 | A `check` limit or `pr-check --fail` finding was exceeded |
+//! | 3 | The scan itself failed |
+//! | 4 | The path is not a git repository |
+//! | 130 | Interrupted with Ctrl+C |
+//!
+//! Clap's own status for a bad command line is 2, so it is mapped to 1 to
+//! keep 2 meaning a threshold.
+
+use std::fmt;
+use std::process::ExitCode;
+
+use repodiet::repository::ScanCancelled;
+
+use crate::interrupt::SIGINT_EXIT;
+
+pub const FAILURE: u8 = 1;
+pub const THRESHOLD: u8 = 2;
+pub const SCAN_ERROR: u8 = 3;
+pub const NOT_A_REPO: u8 = 4;
+
+/// A limit was exceeded; the message lists which
+#[derive(Debug)]
+pub struct ThresholdExceeded(pub String);
+
+impl fmt::Display for ThresholdExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ThresholdExceeded {}
+
+/// Context marking an error as raised by the scan
+#[derive(Debug)]
+pub struct ScanFailed;
+
+impl fmt::Display for ScanFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Scan failed")
+    }
+}
+
+impl std::error::Error for ScanFailed {}
+
+/// Status for a command that failed with `err`
+pub fn of(err: &anyhow::Error) -> u8 {
+    let not_a_repo = find::<gix::open::Error>(err).is_some_and(|e| matches!(e, gix::open::Error::NotARepository { .. }));
+    if not_a_repo {
+        NOT_A_REPO
+    } else if find::<ScanCancelled>(err).is_some() {
+        SIGINT_EXIT as u8
+    } else if find::<ThresholdExceeded>(err).is_some() {
+        THRESHOLD
+    } else if find::<ScanFailed>(err).is_some() {
+        SCAN_ERROR
+    } else {
+        FAILURE
+    }
+}
+
+/// Status after clap rejected the command line or printed help or version
+pub fn of_usage(err: &clap::Error) -> ExitCode {
+    if err.use_stderr() { ExitCode::from(FAILURE) } else { ExitCode::SUCCESS }
+}
+
+/// `T` in `err`, as context added to it or among its causes
+fn find<T: std::error::Error + Send + Sync + 'static>(err: &anyhow::Error) -> Option<&T> {
+    err.downcast_ref::<T>().or_else(|| err.chain().find_map(|cause| cause.downcast_ref::<T>()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_status_follows_the_cause() {
+        assert_eq!(of(&anyhow!("Invalid log level")), FAILURE);
+        assert_eq!(of(&anyhow::Error::new(ThresholdExceeded("1 of 3 size checks failed".into()))), THRESHOLD);
+
+        let scan = Err::<(), _>(anyhow!("Object not found")).context(ScanFailed).unwrap_err();
+        assert_eq!(of(&scan), SCAN_ERROR);
+        let cancelled = Err::<(), _>(ScanCancelled { scanned: 3, total: 10 }).context(ScanFailed).unwrap_err();
+        assert_eq!(of(&cancelled), 130);
+
+        let dir = tempfile::tempdir().unwrap();
+        let open = gix::open(dir.path()).context("Failed to open git repository").context(ScanFailed);
+        assert_eq!(of(&open.unwrap_err()), NOT_A_REPO);
+    }
+}
@@ -1,4 +1,7 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::KeyMap;
+use crate::model::ExportFormat;
 
 /// Map a character to its QWERTY equivalent for layout-independent key handling.
 /// This allows vim-style navigation to work regardless of keyboard layout.
@@ -69,11 +72,83 @@ pub enum Intent {
     ShowExtensions,
     ShowLargeBlobs,
     EnterSearch,
+    EnterBlobLookup,
+
+    /// Open the chain of largest children down to the biggest file
+    JumpToLargest,
+
+    // Bookmarks
+    ToggleBookmark,
+    ShowBookmarks,
+
+    /// Recommended cleanups with estimated savings
+    ShowInsights,
+    /// Patterns and command for moving binaries to Git LFS
+    ShowLfsPlan,
+    /// Blobs committed at several paths
+    ShowDuplicates,
+    /// Commits that added the most blob data
+    ShowHeavyCommits,
+    /// Sizes of the directories at the repository root
+    ShowTopDirs,
+    /// Every path down to a depth as one list
+    ShowFlatten,
+    /// Objects and packs the scans could not read
+    ShowDiagnostics,
+    /// Size per CODEOWNERS owner
+    ShowOwners,
 
     // Filters
     ToggleDeletedOnly,
+    /// List everything, hide generated and vendored files, or list only them
+    CycleVendoredFilter,
+    /// List only content first committed longer ago than the configured age
+    ToggleOldOnly,
+    /// Rank extensions by cumulative, current or deleted size; list deleted
+    /// files in the tree largest or most recently deleted first
+    CycleRanking,
+    /// Switch how search matches letter case
+    CycleSearchCase,
+    /// Group search matches by directory, or list them flat again
+    ToggleSearchGrouping,
+
+    // Blob list columns
+    ToggleOidColumn,
+    ToggleAuthorColumn,
+    ToggleDateColumn,
+    /// Cluster near-duplicate blobs together
+    ToggleSimilarGroups,
+
+    /// Show the selected file's content over the view
+    Preview,
+    /// Copy a command that restores the selected file's content
+    CopyRecoveryCommand,
+    /// Check the selected deleted file out of history again
+    RestoreFile,
+    /// Hide or show the panel detailing the selected directory
+    ToggleDetails,
+    /// Scroll long paths towards their starts
+    ScrollLeft,
+    /// Scroll long paths towards their ends
+    ScrollRight,
+
+    // Marking
+    ToggleMark,
+    /// Start or stop selecting a range of rows to mark at once
+    ToggleVisual,
+    /// Take back the last change to marks or filters
+    Undo,
+    Redo,
+    ClearMarks,
+    /// Write the rows the view lists to a file
+    ExportList(ExportFormat),
+
+    // Overlays
+    ToggleHelp,
 
     // Actions
+    /// Scan again and badge what changed
+    Rescan,
     Quit,
 
     // Search input
@@ -87,12 +162,35 @@ pub enum ViewMode {
     Tree,
     ByExtension,
     LargeBlobs,
+    Bookmarks,
+    Insights,
+    Duplicates,
+    HeavyCommits,
+    TopDirs,
+    Flatten,
+    Diagnostics,
+    Owners,
 }
 
 /// Map a key event to a user intent based on the current view mode and search state
-pub fn map_key_to_intent(key: &KeyEvent, mode: ViewMode, search_mode: bool, is_at_root: bool) -> Option<Intent> {
+pub fn map_key_to_intent(
+    key: &KeyEvent,
+    mode: ViewMode,
+    search_mode: bool,
+    is_at_root: bool,
+    keys: &KeyMap,
+) -> Option<Intent> {
     let code = &key.code;
 
+    // Same in search, where plain letters are the query
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        match code {
+            KeyCode::Char('z') => return Some(Intent::Undo),
+            KeyCode::Char('y') => return Some(Intent::Redo),
+            _ => {}
+        }
+    }
+
     if search_mode {
         return match code {
             KeyCode::Esc => Some(Intent::ShowTree),  // Exit search
@@ -100,18 +198,72 @@ pub fn map_key_to_intent(key: &KeyEvent, mode: ViewMode, search_mode: bool, is_a
             KeyCode::Up => Some(Intent::MoveUp),
             KeyCode::Down => Some(Intent::MoveDown),
             KeyCode::Backspace => Some(Intent::SearchBackspace),
+            KeyCode::Left => Some(Intent::ScrollLeft),
+            KeyCode::Right => Some(Intent::ScrollRight),
+            KeyCode::Tab => Some(Intent::ToggleMark),
+            // Plain 'd' is part of the query
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Intent::ToggleDeletedOnly)
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Intent::CycleSearchCase)
+            }
+            KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Intent::ToggleSearchGrouping)
+            }
+            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Intent::ToggleVisual)
+            }
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Intent::ExportList(ExportFormat::Csv))
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Intent::ExportList(ExportFormat::Json))
+            }
             KeyCode::Char(c) => Some(Intent::SearchChar(*c)),
             _ => None,
         };
     }
 
     // Common keys across all modes
-    if key_matches(code, 'q') {
+    if key_matches(code, keys.quit) {
         return Some(Intent::Quit);
     }
-    if *code == KeyCode::Char('/') {
+    if key_matches(code, keys.search) {
         return Some(Intent::EnterSearch);
     }
+    if key_matches(code, keys.blob_lookup) {
+        return Some(Intent::EnterBlobLookup);
+    }
+    if key_matches(code, keys.mark) {
+        return Some(Intent::ToggleMark);
+    }
+    // Before the views' `preview`, which would match it too
+    if key_matches(code, keys.visual) {
+        return Some(Intent::ToggleVisual);
+    }
+    if key_matches(code, keys.clear_marks) {
+        return Some(Intent::ClearMarks);
+    }
+    // Before `export_csv`, which would match it too
+    if key_matches(code, keys.export_json) {
+        return Some(Intent::ExportList(ExportFormat::Json));
+    }
+    if key_matches(code, keys.export_csv) {
+        return Some(Intent::ExportList(ExportFormat::Csv));
+    }
+    if key_matches(code, keys.help) {
+        return Some(Intent::ToggleHelp);
+    }
+    if key_matches(code, keys.rescan) {
+        return Some(Intent::Rescan);
+    }
+    if *code == KeyCode::Up || key_matches(code, keys.up) {
+        return Some(Intent::MoveUp);
+    }
+    if *code == KeyCode::Down || key_matches(code, keys.down) {
+        return Some(Intent::MoveDown);
+    }
 
     match mode {
         ViewMode::Tree => {
@@ -122,58 +274,392 @@ pub fn map_key_to_intent(key: &KeyEvent, mode: ViewMode, search_mode: bool, is_a
                     return Some(Intent::Back);
                 }
             }
-            if *code == KeyCode::Up || key_matches(code, 'k') {
-                return Some(Intent::MoveUp);
+            // Before `open`: a lowercase binding would match the uppercase key too
+            if key_matches(code, keys.large_blobs) {
+                return Some(Intent::ShowLargeBlobs);
+            }
+            // Before `vendored`, for the same reason
+            if key_matches(code, keys.flatten) {
+                return Some(Intent::ShowFlatten);
             }
-            if *code == KeyCode::Down || key_matches(code, 'j') {
-                return Some(Intent::MoveDown);
+            if key_matches(code, keys.old_only) {
+                return Some(Intent::ToggleOldOnly);
             }
-            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, 'l') {
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
                 return Some(Intent::Enter);
             }
-            if *code == KeyCode::Backspace || *code == KeyCode::Left || key_matches(code, 'h') {
+            if *code == KeyCode::Backspace || *code == KeyCode::Left || key_matches(code, keys.back) {
                 return Some(Intent::Back);
             }
-            if key_matches(code, 'd') {
+            if key_matches(code, keys.deleted_only) {
                 return Some(Intent::ToggleDeletedOnly);
             }
-            if key_matches(code, 't') {
+            if key_matches(code, keys.ranking) {
+                return Some(Intent::CycleRanking);
+            }
+            if key_matches(code, keys.extensions) {
                 return Some(Intent::ShowExtensions);
             }
-            if key_matches(code, 'L') {
-                return Some(Intent::ShowLargeBlobs);
+            if key_matches(code, keys.largest) {
+                return Some(Intent::JumpToLargest);
+            }
+            if key_matches(code, keys.details) {
+                return Some(Intent::ToggleDetails);
+            }
+            if key_matches(code, keys.vendored) {
+                return Some(Intent::CycleVendoredFilter);
+            }
+            if key_matches(code, keys.preview) {
+                return Some(Intent::Preview);
+            }
+            if key_matches(code, keys.copy_command) {
+                return Some(Intent::CopyRecoveryCommand);
+            }
+            if key_matches(code, keys.restore) {
+                return Some(Intent::RestoreFile);
+            }
+            if key_matches(code, keys.bookmark) {
+                return Some(Intent::ToggleBookmark);
+            }
+            if key_matches(code, keys.bookmarks) {
+                return Some(Intent::ShowBookmarks);
+            }
+            if key_matches(code, keys.insights) {
+                return Some(Intent::ShowInsights);
+            }
+            if key_matches(code, keys.duplicates) {
+                return Some(Intent::ShowDuplicates);
+            }
+            if key_matches(code, keys.heavy_commits) {
+                return Some(Intent::ShowHeavyCommits);
+            }
+            if key_matches(code, keys.top_dirs) {
+                return Some(Intent::ShowTopDirs);
+            }
+            if key_matches(code, keys.diagnostics) {
+                return Some(Intent::ShowDiagnostics);
+            }
+            if key_matches(code, keys.owners) {
+                return Some(Intent::ShowOwners);
             }
             None
         }
-        ViewMode::ByExtension => {
-            if *code == KeyCode::Esc || key_matches(code, 't') {
+        ViewMode::Owners => {
+            if key_matches(code, keys.owners) {
+                return Some(Intent::ShowOwners);
+            }
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
+                return Some(Intent::Enter);
+            }
+            // Esc leaves an owner's files first, then the view
+            if *code == KeyCode::Esc
+                || *code == KeyCode::Backspace
+                || *code == KeyCode::Left
+                || key_matches(code, keys.back)
+            {
+                return Some(Intent::Back);
+            }
+            None
+        }
+        ViewMode::Diagnostics => {
+            if *code == KeyCode::Esc || key_matches(code, keys.diagnostics) {
+                return Some(Intent::ShowDiagnostics);
+            }
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
+                return Some(Intent::Enter);
+            }
+            None
+        }
+        ViewMode::TopDirs => {
+            if *code == KeyCode::Esc || key_matches(code, keys.top_dirs) {
+                return Some(Intent::ShowTopDirs);
+            }
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
+                return Some(Intent::Enter);
+            }
+            None
+        }
+        ViewMode::Flatten => {
+            if *code == KeyCode::Esc || key_matches(code, keys.flatten) {
+                return Some(Intent::ShowFlatten);
+            }
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
+                return Some(Intent::Enter);
+            }
+            None
+        }
+        ViewMode::HeavyCommits => {
+            if *code == KeyCode::Esc || key_matches(code, keys.heavy_commits) {
+                return Some(Intent::ShowHeavyCommits);
+            }
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
+                return Some(Intent::Enter);
+            }
+            None
+        }
+        ViewMode::Duplicates => {
+            if *code == KeyCode::Esc || key_matches(code, keys.duplicates) {
+                return Some(Intent::ShowDuplicates);
+            }
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
+                return Some(Intent::Enter);
+            }
+            None
+        }
+        ViewMode::Insights => {
+            if *code == KeyCode::Esc || key_matches(code, keys.insights) {
+                return Some(Intent::ShowInsights);
+            }
+            if key_matches(code, keys.lfs_plan) {
+                return Some(Intent::ShowLfsPlan);
+            }
+            None
+        }
+        ViewMode::Bookmarks => {
+            if *code == KeyCode::Esc || key_matches(code, keys.bookmarks) {
                 return Some(Intent::ShowTree);
             }
-            if *code == KeyCode::Up || key_matches(code, 'k') {
-                return Some(Intent::MoveUp);
+            if *code == KeyCode::Enter || *code == KeyCode::Right || key_matches(code, keys.open) {
+                return Some(Intent::Enter);
+            }
+            if *code == KeyCode::Delete || key_matches(code, keys.bookmark) {
+                return Some(Intent::ToggleBookmark);
             }
-            if *code == KeyCode::Down || key_matches(code, 'j') {
-                return Some(Intent::MoveDown);
+            None
+        }
+        ViewMode::ByExtension => {
+            if *code == KeyCode::Esc || key_matches(code, keys.extensions) {
+                return Some(Intent::ShowTree);
+            }
+            if *code == KeyCode::Enter {
+                return Some(Intent::Enter);
             }
-            if key_matches(code, 'L') {
+            if key_matches(code, keys.large_blobs) {
                 return Some(Intent::ShowLargeBlobs);
             }
+            if key_matches(code, keys.deleted_only) {
+                return Some(Intent::ToggleDeletedOnly);
+            }
+            if key_matches(code, keys.lfs_plan) {
+                return Some(Intent::ShowLfsPlan);
+            }
+            if key_matches(code, keys.ranking) {
+                return Some(Intent::CycleRanking);
+            }
             None
         }
         ViewMode::LargeBlobs => {
-            if *code == KeyCode::Esc || key_matches(code, 'l') || key_matches(code, 'L') {
+            if *code == KeyCode::Esc || key_matches(code, keys.open) || key_matches(code, keys.large_blobs) {
                 return Some(Intent::ShowTree);
             }
-            if *code == KeyCode::Up || key_matches(code, 'k') {
-                return Some(Intent::MoveUp);
-            }
-            if *code == KeyCode::Down || key_matches(code, 'j') {
-                return Some(Intent::MoveDown);
-            }
             if *code == KeyCode::Enter {
                 return Some(Intent::Enter);
             }
+            if *code == KeyCode::Left {
+                return Some(Intent::ScrollLeft);
+            }
+            if *code == KeyCode::Right {
+                return Some(Intent::ScrollRight);
+            }
+            if key_matches(code, keys.oid_column) {
+                return Some(Intent::ToggleOidColumn);
+            }
+            if key_matches(code, keys.author_column) {
+                return Some(Intent::ToggleAuthorColumn);
+            }
+            // Before `deleted_only`: with the default 'd' it would match 'D' too
+            if key_matches(code, keys.date_column) {
+                return Some(Intent::ToggleDateColumn);
+            }
+            if key_matches(code, keys.deleted_only) {
+                return Some(Intent::ToggleDeletedOnly);
+            }
+            if key_matches(code, keys.group_similar) {
+                return Some(Intent::ToggleSimilarGroups);
+            }
+            if key_matches(code, keys.preview) {
+                return Some(Intent::Preview);
+            }
+            if key_matches(code, keys.copy_command) {
+                return Some(Intent::CopyRecoveryCommand);
+            }
+            if key_matches(code, keys.restore) {
+                return Some(Intent::RestoreFile);
+            }
             None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(c: char, mode: ViewMode, keys: &KeyMap) -> Option<Intent> {
+        let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        map_key_to_intent(&key, mode, false, true, keys)
+    }
+
+    #[test]
+    fn test_uppercase_bindings_win_over_lowercase() {
+        let keys = KeyMap::default();
+        assert_eq!(press('L', ViewMode::Tree, &keys), Some(Intent::ShowLargeBlobs));
+        assert_eq!(press('l', ViewMode::Tree, &keys), Some(Intent::Enter));
+        assert_eq!(press('D', ViewMode::LargeBlobs, &keys), Some(Intent::ToggleDateColumn));
+        assert_eq!(press('d', ViewMode::LargeBlobs, &keys), Some(Intent::ToggleDeletedOnly));
+    }
+
+    #[test]
+    fn test_bookmark_keys() {
+        let keys = KeyMap::default();
+        assert_eq!(press('m', ViewMode::Tree, &keys), Some(Intent::ToggleBookmark));
+        assert_eq!(press('\'', ViewMode::Tree, &keys), Some(Intent::ShowBookmarks));
+        assert_eq!(press('\'', ViewMode::Bookmarks, &keys), Some(Intent::ShowTree));
+        assert_eq!(press('m', ViewMode::Bookmarks, &keys), Some(Intent::ToggleBookmark));
+    }
+
+    #[test]
+    fn test_arrows_scroll_paths() {
+        let keys = KeyMap::default();
+        let arrow = |code: KeyCode, mode: ViewMode, search: bool| {
+            map_key_to_intent(&KeyEvent::new(code, KeyModifiers::NONE), mode, search, true, &keys)
+        };
+        assert_eq!(arrow(KeyCode::Left, ViewMode::LargeBlobs, false), Some(Intent::ScrollLeft));
+        assert_eq!(arrow(KeyCode::Right, ViewMode::LargeBlobs, false), Some(Intent::ScrollRight));
+        assert_eq!(arrow(KeyCode::Left, ViewMode::Tree, true), Some(Intent::ScrollLeft));
+        assert_eq!(arrow(KeyCode::Right, ViewMode::Tree, false), Some(Intent::Enter));
+    }
+
+    #[test]
+    fn test_details_key() {
+        let keys = KeyMap::default();
+        assert_eq!(press('b', ViewMode::Tree, &keys), Some(Intent::ToggleDetails));
+        assert_eq!(press('b', ViewMode::LargeBlobs, &keys), None);
+        assert_eq!(press('f', ViewMode::Tree, &keys), Some(Intent::CycleVendoredFilter));
+        assert_eq!(press('A', ViewMode::Tree, &keys), Some(Intent::ToggleOldOnly));
+        assert_eq!(press('a', ViewMode::Tree, &keys), None);
+    }
+
+    #[test]
+    fn test_preview_key() {
+        let keys = KeyMap::default();
+        assert_eq!(press('v', ViewMode::Tree, &keys), Some(Intent::Preview));
+        assert_eq!(press('v', ViewMode::LargeBlobs, &keys), Some(Intent::Preview));
+        assert_eq!(press('v', ViewMode::Insights, &keys), None);
+        assert_eq!(press('V', ViewMode::Tree, &keys), Some(Intent::ToggleVisual));
+        assert_eq!(press('V', ViewMode::LargeBlobs, &keys), Some(Intent::ToggleVisual));
+        assert_eq!(press('y', ViewMode::Tree, &keys), Some(Intent::CopyRecoveryCommand));
+        assert_eq!(press('x', ViewMode::Tree, &keys), Some(Intent::RestoreFile));
+        assert_eq!(press('x', ViewMode::LargeBlobs, &keys), Some(Intent::RestoreFile));
+        assert_eq!(press('y', ViewMode::LargeBlobs, &keys), Some(Intent::CopyRecoveryCommand));
+    }
+
+    #[test]
+    fn test_insights_key_toggles_the_view() {
+        let keys = KeyMap::default();
+        assert_eq!(press('i', ViewMode::Tree, &keys), Some(Intent::ShowInsights));
+        assert_eq!(press('i', ViewMode::Insights, &keys), Some(Intent::ShowInsights));
+        assert_eq!(press('m', ViewMode::Insights, &keys), None);
+        assert_eq!(press('p', ViewMode::Insights, &keys), Some(Intent::ShowLfsPlan));
+        assert_eq!(press('p', ViewMode::ByExtension, &keys), Some(Intent::ShowLfsPlan));
+        assert_eq!(press('s', ViewMode::ByExtension, &keys), Some(Intent::CycleRanking));
+        assert_eq!(press('s', ViewMode::Tree, &keys), Some(Intent::CycleRanking));
+        assert_eq!(press('s', ViewMode::Insights, &keys), None);
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(map_key_to_intent(&enter, ViewMode::ByExtension, false, true, &keys), Some(Intent::Enter));
+    }
+
+    #[test]
+    fn test_duplicates_keys() {
+        let keys = KeyMap::default();
+        assert_eq!(press('c', ViewMode::Tree, &keys), Some(Intent::ShowDuplicates));
+        assert_eq!(press('c', ViewMode::Duplicates, &keys), Some(Intent::ShowDuplicates));
+        assert_eq!(press('l', ViewMode::Duplicates, &keys), Some(Intent::Enter));
+        assert_eq!(press('w', ViewMode::Tree, &keys), Some(Intent::ShowHeavyCommits));
+        assert_eq!(press('w', ViewMode::HeavyCommits, &keys), Some(Intent::ShowHeavyCommits));
+        assert_eq!(press('l', ViewMode::HeavyCommits, &keys), Some(Intent::Enter));
+        assert_eq!(press('1', ViewMode::Tree, &keys), Some(Intent::ShowTopDirs));
+        assert_eq!(press('1', ViewMode::TopDirs, &keys), Some(Intent::ShowTopDirs));
+        assert_eq!(press('l', ViewMode::TopDirs, &keys), Some(Intent::Enter));
+    }
+
+    #[test]
+    fn test_flatten_keys() {
+        let keys = KeyMap::default();
+        assert_eq!(press('F', ViewMode::Tree, &keys), Some(Intent::ShowFlatten));
+        assert_eq!(press('f', ViewMode::Tree, &keys), Some(Intent::CycleVendoredFilter));
+        assert_eq!(press('F', ViewMode::Flatten, &keys), Some(Intent::ShowFlatten));
+        assert_eq!(press('l', ViewMode::Flatten, &keys), Some(Intent::Enter));
+        assert_eq!(press('f', ViewMode::Flatten, &keys), None);
+    }
+
+    #[test]
+    fn test_diagnostics_keys() {
+        let keys = KeyMap::default();
+        assert_eq!(press('!', ViewMode::Tree, &keys), Some(Intent::ShowDiagnostics));
+        assert_eq!(press('!', ViewMode::Diagnostics, &keys), Some(Intent::ShowDiagnostics));
+        assert_eq!(press('l', ViewMode::Diagnostics, &keys), Some(Intent::Enter));
+    }
+
+    #[test]
+    fn test_owners_keys() {
+        let keys = KeyMap::default();
+        assert_eq!(press('O', ViewMode::Tree, &keys), Some(Intent::ShowOwners));
+        assert_eq!(press('O', ViewMode::Owners, &keys), Some(Intent::ShowOwners));
+        assert_eq!(press('l', ViewMode::Owners, &keys), Some(Intent::Enter));
+        assert_eq!(press('h', ViewMode::Owners, &keys), Some(Intent::Back));
+    }
+
+    #[test]
+    fn test_undo_keys_win_over_letters() {
+        let keys = KeyMap::default();
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(map_key_to_intent(&ctrl('z'), ViewMode::Tree, false, true, &keys), Some(Intent::Undo));
+        assert_eq!(map_key_to_intent(&ctrl('y'), ViewMode::Tree, false, true, &keys), Some(Intent::Redo));
+        assert_eq!(press('y', ViewMode::Tree, &keys), Some(Intent::CopyRecoveryCommand));
+    }
+
+    #[test]
+    fn test_search_control_keys() {
+        let keys = KeyMap::default();
+        let search = |c, modifiers| {
+            map_key_to_intent(&KeyEvent::new(KeyCode::Char(c), modifiers), ViewMode::Tree, true, true, &keys)
+        };
+        assert_eq!(search('s', KeyModifiers::CONTROL), Some(Intent::CycleSearchCase));
+        assert_eq!(search('d', KeyModifiers::CONTROL), Some(Intent::ToggleDeletedOnly));
+        assert_eq!(search('g', KeyModifiers::CONTROL), Some(Intent::ToggleSearchGrouping));
+        assert_eq!(search('v', KeyModifiers::CONTROL), Some(Intent::ToggleVisual));
+        assert_eq!(search('e', KeyModifiers::CONTROL), Some(Intent::ExportList(ExportFormat::Csv)));
+        assert_eq!(search('o', KeyModifiers::CONTROL), Some(Intent::ExportList(ExportFormat::Json)));
+        assert_eq!(search('e', KeyModifiers::NONE), Some(Intent::SearchChar('e')));
+        assert_eq!(search('z', KeyModifiers::CONTROL), Some(Intent::Undo));
+        assert_eq!(search('y', KeyModifiers::CONTROL), Some(Intent::Redo));
+        assert_eq!(search('s', KeyModifiers::NONE), Some(Intent::SearchChar('s')));
+    }
+
+    #[test]
+    fn test_export_keys_in_every_list() {
+        let keys = KeyMap::default();
+        assert_eq!(press('e', ViewMode::Tree, &keys), Some(Intent::ExportList(ExportFormat::Csv)));
+        assert_eq!(press('E', ViewMode::Tree, &keys), Some(Intent::ExportList(ExportFormat::Json)));
+        assert_eq!(press('e', ViewMode::ByExtension, &keys), Some(Intent::ExportList(ExportFormat::Csv)));
+        assert_eq!(press('E', ViewMode::LargeBlobs, &keys), Some(Intent::ExportList(ExportFormat::Json)));
+    }
+
+    #[test]
+    fn test_jump_to_largest_only_in_tree() {
+        let keys = KeyMap::default();
+        assert_eq!(press('*', ViewMode::Tree, &keys), Some(Intent::JumpToLargest));
+        assert_eq!(press('*', ViewMode::LargeBlobs, &keys), None);
+    }
+
+    #[test]
+    fn test_remapped_keys() {
+        let keys = KeyMap { quit: 'x', down: 'n', ..KeyMap::default() };
+        assert_eq!(press('x', ViewMode::Tree, &keys), Some(Intent::Quit));
+        assert_eq!(press('q', ViewMode::Tree, &keys), None);
+        assert_eq!(press('n', ViewMode::ByExtension, &keys), Some(Intent::MoveDown));
+        // Russian layout still maps onto the binding
+        assert_eq!(press('т', ViewMode::Tree, &keys), Some(Intent::MoveDown));
+    }
+}
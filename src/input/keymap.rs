@@ -0,0 +1,181 @@
+use serde::Deserialize;
+
+/// Character keys bound to each action; arrows, Enter, Esc and Backspace
+/// always keep their meaning
+///
+/// Lowercase bindings match either case (and the Russian layout equivalent),
+/// uppercase bindings only match uppercase.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyMap {
+    pub quit: char,
+    pub search: char,
+    pub blob_lookup: char,
+    pub mark: char,
+    pub visual: char,
+    pub clear_marks: char,
+    pub export_csv: char,
+    pub export_json: char,
+    pub help: char,
+    pub deleted_only: char,
+    pub ranking: char,
+    pub up: char,
+    pub down: char,
+    pub open: char,
+    pub back: char,
+    pub extensions: char,
+    pub large_blobs: char,
+    pub largest: char,
+    pub bookmark: char,
+    pub bookmarks: char,
+    pub insights: char,
+    pub lfs_plan: char,
+    pub duplicates: char,
+    pub heavy_commits: char,
+    pub top_dirs: char,
+    pub flatten: char,
+    pub diagnostics: char,
+    pub owners: char,
+    pub rescan: char,
+    pub oid_column: char,
+    pub author_column: char,
+    pub date_column: char,
+    pub group_similar: char,
+    pub preview: char,
+    pub copy_command: char,
+    pub restore: char,
+    pub details: char,
+    pub vendored: char,
+    pub old_only: char,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            quit: 'q',
+            search: '/',
+            blob_lookup: '#',
+            mark: ' ',
+            visual: 'V',
+            clear_marks: 'u',
+            export_csv: 'e',
+            export_json: 'E',
+            help: '?',
+            deleted_only: 'd',
+            ranking: 's',
+            up: 'k',
+            down: 'j',
+            open: 'l',
+            back: 'h',
+            extensions: 't',
+            large_blobs: 'L',
+            largest: '*',
+            bookmark: 'm',
+            bookmarks: '\'',
+            insights: 'i',
+            lfs_plan: 'p',
+            duplicates: 'c',
+            heavy_commits: 'w',
+            top_dirs: '1',
+            flatten: 'F',
+            diagnostics: '!',
+            owners: 'O',
+            rescan: 'r',
+            oid_column: 'o',
+            author_column: 'a',
+            date_column: 'D',
+            group_similar: 'g',
+            preview: 'v',
+            copy_command: 'y',
+            restore: 'x',
+            details: 'b',
+            vendored: 'f',
+            old_only: 'A',
+        }
+    }
+}
+
+impl KeyMap {
+    fn bindings(&self) -> [(&'static str, char); 39] {
+        [
+            ("quit", self.quit),
+            ("search", self.search),
+            ("blob_lookup", self.blob_lookup),
+            ("mark", self.mark),
+            ("visual", self.visual),
+            ("clear_marks", self.clear_marks),
+            ("export_csv", self.export_csv),
+            ("export_json", self.export_json),
+            ("help", self.help),
+            ("deleted_only", self.deleted_only),
+            ("ranking", self.ranking),
+            ("up", self.up),
+            ("down", self.down),
+            ("open", self.open),
+            ("back", self.back),
+            ("extensions", self.extensions),
+            ("large_blobs", self.large_blobs),
+            ("largest", self.largest),
+            ("bookmark", self.bookmark),
+            ("bookmarks", self.bookmarks),
+            ("insights", self.insights),
+            ("lfs_plan", self.lfs_plan),
+            ("duplicates", self.duplicates),
+            ("heavy_commits", self.heavy_commits),
+            ("top_dirs", self.top_dirs),
+            ("flatten", self.flatten),
+            ("diagnostics", self.diagnostics),
+            ("owners", self.owners),
+            ("rescan", self.rescan),
+            ("oid_column", self.oid_column),
+            ("author_column", self.author_column),
+            ("date_column", self.date_column),
+            ("group_similar", self.group_similar),
+            ("preview", self.preview),
+            ("copy_command", self.copy_command),
+            ("restore", self.restore),
+            ("details", self.details),
+            ("vendored", self.vendored),
+            ("old_only", self.old_only),
+        ]
+    }
+
+    /// Every action needs its own printable key
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let bindings = self.bindings();
+        for (i, (action, key)) in bindings.iter().enumerate() {
+            anyhow::ensure!(!key.is_control(), "key for {} must be a printable character", action);
+            if let Some((other, _)) = bindings[..i].iter().find(|(_, k)| k == key) {
+                anyhow::bail!("'{}' is bound to both {} and {}", key, other, action);
+            }
+        }
+        Ok(())
+    }
+
+    /// Label for a key in hints, e.g. "Space" for ' '
+    pub fn label(key: char) -> String {
+        match key {
+            ' ' => "Space".to_string(),
+            c => c.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_keymap_is_valid() {
+        assert!(KeyMap::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_conflicting_keys_rejected() {
+        let keys = KeyMap { search: 'q', ..KeyMap::default() };
+        let err = keys.validate().unwrap_err().to_string();
+        assert_eq!(err, "'q' is bound to both quit and search");
+
+        assert!(KeyMap { help: '\t', ..KeyMap::default() }.validate().is_err());
+    }
+}
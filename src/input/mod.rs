@@ -1,3 +1,5 @@
 mod keyboard;
+mod keymap;
 
 pub use keyboard::{Intent, map_key_to_intent, ViewMode};
+pub use keymap::KeyMap;
@@ -0,0 +1,114 @@
+//! Interrupting a running scan
+//!
+//! Outside the TUI, Ctrl+C cancels the scan in progress: the scanner stops
+//! between commits and saves what it got through, so the next run resumes.
+//! A second Ctrl+C, or one while no scan runs, exits right away as it would
+//! without a handler. Inside the TUI the terminal is in raw mode and Ctrl+C
+//! arrives as a key, so a rescan watches the keyboard for Esc or Ctrl+C.
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::warn;
+
+use repodiet::repository::CancelToken;
+
+/// Exit status of a process killed by SIGINT
+pub const SIGINT_EXIT: i32 = 130;
+
+/// Token of the scan Ctrl+C currently cancels
+static ACTIVE: Mutex<Option<CancelToken>> = Mutex::new(None);
+static LISTENER: Once = Once::new();
+
+/// Cancels its token on Ctrl+C until dropped
+pub struct CtrlCGuard {
+    token: CancelToken,
+}
+
+impl CtrlCGuard {
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+}
+
+impl Drop for CtrlCGuard {
+    fn drop(&mut self) {
+        *lock_active() = None;
+    }
+}
+
+/// Have the next Ctrl+C cancel a scan instead of ending the process
+///
+/// Must be called inside the tokio runtime.
+pub fn on_ctrl_c() -> CtrlCGuard {
+    LISTENER.call_once(|| {
+        tokio::spawn(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                let active = lock_active().take();
+                match active {
+                    Some(token) => {
+                        warn!("Cancelling the scan after the current commit (Ctrl+C again to quit now)");
+                        token.cancel();
+                    }
+                    None => std::process::exit(SIGINT_EXIT),
+                }
+            }
+        });
+    });
+    let token = CancelToken::new();
+    *lock_active() = Some(token.clone());
+    CtrlCGuard { token }
+}
+
+fn lock_active() -> std::sync::MutexGuard<'static, Option<CancelToken>> {
+    ACTIVE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Cancels its token when Esc or Ctrl+C is pressed, until dropped
+///
+/// Other keys pressed meanwhile are discarded.
+pub struct KeyWatcher {
+    token: CancelToken,
+    done: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl KeyWatcher {
+    pub fn start() -> Self {
+        let token = CancelToken::new();
+        let done = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (token, done) = (token.clone(), Arc::clone(&done));
+            thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    if !event::poll(Duration::from_millis(50)).unwrap_or(false) {
+                        continue;
+                    }
+                    if let Ok(Event::Key(key)) = event::read()
+                        && key.kind == KeyEventKind::Press
+                        && (key.code == KeyCode::Esc
+                            || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)))
+                    {
+                        token.cancel();
+                    }
+                }
+            })
+        };
+        Self { token, done, thread: Some(thread) }
+    }
+
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+}
+
+impl Drop for KeyWatcher {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
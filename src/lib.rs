@@ -6,3 +6,6 @@ pub mod viewmodel;
 pub mod view;
 pub mod input;
 pub mod util;
+pub mod config;
+#[cfg(feature = "test-support")]
+pub mod synthetic;
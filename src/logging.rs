@@ -0,0 +1,90 @@
+//! Log output setup
+//!
+//! Logs go to stderr, or to a file with `--log-file`. While the TUI owns
+//! the terminal, stderr output is dropped so it can't corrupt the
+//! alternate screen; use a log file to keep those lines. `--quiet` keeps
+//! only errors and hides progress bars.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, EitherWriter, MakeWriter};
+use tracing_subscriber::EnvFilter;
+
+static TERMINAL_SUSPENDED: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Output settings gathered from the command line
+pub struct LogConfig<'a> {
+    /// Filter directive such as `info` or `repodiet=debug,sqlx=warn`
+    pub level: &'a str,
+    pub json: bool,
+    pub file: Option<&'a Path>,
+    /// Report every span's duration when it closes (profile mode)
+    pub span_timings: bool,
+}
+
+/// Stderr, unless the TUI is currently drawing
+struct TerminalWriter;
+
+impl<'a> MakeWriter<'a> for TerminalWriter {
+    type Writer = EitherWriter<io::Stderr, io::Sink>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        if TERMINAL_SUSPENDED.load(Ordering::Relaxed) {
+            EitherWriter::B(io::sink())
+        } else {
+            EitherWriter::A(io::stderr())
+        }
+    }
+}
+
+/// Install the global subscriber
+pub fn init(config: LogConfig<'_>) -> Result<()> {
+    let filter = EnvFilter::try_new(config.level)
+        .with_context(|| format!("Invalid log level: {}", config.level))?;
+    let span_events = if config.span_timings { FmtSpan::CLOSE } else { FmtSpan::NONE };
+
+    let (writer, to_file) = match config.file {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Could not create log file {}", path.display()))?;
+            (BoxMakeWriter::new(Mutex::new(file)), true)
+        }
+        None => (BoxMakeWriter::new(TerminalWriter), false),
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(span_events)
+        .with_writer(writer);
+
+    if config.json {
+        builder.json().init();
+    } else if to_file {
+        builder.with_ansi(false).init();
+    } else {
+        // Terse terminal output; a unit timer drops timestamps but, unlike
+        // without_time(), keeps span durations
+        builder.with_timer(()).with_target(false).init();
+    }
+    Ok(())
+}
+
+/// Stop or resume writing logs to stderr (around the TUI's alternate screen)
+pub fn suspend_terminal(suspended: bool) {
+    TERMINAL_SUSPENDED.store(suspended, Ordering::Relaxed);
+}
+
+/// Keep stderr to errors only: no progress bars (`--quiet`)
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
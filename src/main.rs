@@ -1,132 +1,627 @@
 mod cli;
+mod commands;
+mod exit_code;
+mod interrupt;
+mod logging;
+mod terminal;
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::collections::hash_map::DefaultHasher;
-use std::fs;
-use std::hash::{Hash, Hasher};
-use std::io;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+use crossterm::event::{self, Event, KeyEventKind};
+use tracing::{info, warn};
+use time::OffsetDateTime;
 
+use repodiet::config::{Config, Overrides};
 use repodiet::input::map_key_to_intent;
-use repodiet::repository::{Database, GitScanner};
-use repodiet::util::format_size;
-use repodiet::viewmodel::{Action, AppViewModel, ViewMode};
-use repodiet::view::{render_tree, render_extension, render_search, render_blobs};
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = cli::Cli::parse();
-    let profile_mode = args.profile;
-    let repo_path = args.repo_path.to_str().unwrap_or(".").to_string();
-
-    // Get cache directory - use temp dir for profile mode to get fresh scan
-    let db_path = if profile_mode {
-        let temp_dir = std::env::temp_dir().join("repodiet-profile");
-        fs::create_dir_all(&temp_dir)?;
-        temp_dir.join("profile.db")
+use repodiet::model::{BlobPreview, CodeOwners, RecoveryCommands, Tree, DETAIL_ROWS};
+use repodiet::repository::{
+    fingerprint_blobs, git_dir_usage, plan_restore, preview_blob, read_codeowners, restore_file, Checkout, Database,
+    NoopProgress, PackSizeIndex, ScanCancelled,
+};
+use repodiet::util::{format_size, PathFilter};
+use repodiet::viewmodel::{Action, AppViewModel, ViewMode, INDEX_MATCH_LIMIT};
+use repodiet::view::{apply_theme, DonutImage, Theme, render_tree, render_extension, render_search, render_blobs, render_blob_lookup, render_bookmarks, render_insights, render_duplicates, render_commits, render_top_dirs, render_flatten, render_diagnostics, render_owners, render_error, render_help, render_lfs_plan, render_notice, render_preview, render_recovery, render_restore};
+
+use terminal::{InlineImages, TerminalGuard, Tui};
+
+fn main() -> ExitCode {
+    // Read while this is the only thread; the runtime starts more
+    repodiet::util::init_local_offset();
+    let args = match cli::Cli::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            let _ = e.print();
+            return exit_code::of_usage(&e);
+        }
+    };
+    let result = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(anyhow::Error::from)
+        .and_then(|runtime| runtime.block_on(run(args)));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(exit_code::of(&e))
+        }
+    }
+}
+
+async fn run(args: cli::Cli) -> Result<()> {
+    let default_level = if args.profile() { "info,repodiet=debug" } else { "info" };
+    let log_level = if args.quiet {
+        "error".to_string()
     } else {
-        let cache_dir = dirs::cache_dir()
-            .context("Could not determine cache directory")?
-            .join("repodiet");
-        fs::create_dir_all(&cache_dir)?;
-
-        // Generate unique index filename based on repo's absolute path
-        let abs_repo_path = fs::canonicalize(&repo_path)
-            .with_context(|| format!("Could not resolve path: {}", repo_path))?;
-        let repo_name = abs_repo_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("repo");
-        let mut hasher = DefaultHasher::new();
-        abs_repo_path.hash(&mut hasher);
-        let hash = hasher.finish();
-        cache_dir.join(format!("{}_{:016x}.db", repo_name, hash))
+        args.log_level
+            .clone()
+            .or_else(|| std::env::var("RUST_LOG").ok())
+            .unwrap_or_else(|| default_level.to_string())
     };
+    logging::set_quiet(args.quiet);
+    logging::init(logging::LogConfig {
+        level: &log_level,
+        json: args.log_json,
+        file: args.log_file.as_deref(),
+        span_timings: args.profile(),
+    })?;
 
-    // Delete existing DB in profile mode for fresh scan
-    if profile_mode && db_path.exists() {
-        fs::remove_file(&db_path)?;
+    match args.command {
+        None => run_tui(&args.tui).await,
+        Some(cli::Command::Tui(tui)) => run_tui(&tui).await,
+        Some(cli::Command::Scan { scan, profile }) => commands::scan::run(&scan, profile).await,
+        Some(cli::Command::Report { out, schema: true, .. }) => commands::report::run_schema(out.as_deref()),
+        Some(cli::Command::Report { scan, config, out, report_md, schema: false }) => {
+            commands::report::run(&scan, &config.overrides(), out.as_deref(), report_md).await
+        }
+        Some(cli::Command::Check { scan, config, limits }) => {
+            commands::check::run(&scan, &config.overrides(), &limits).await
+        }
+        Some(cli::Command::Snapshot { scan, config, retention_days, history }) => {
+            commands::size_snapshot::run(&scan, &config.overrides(), retention_days, history.as_deref()).await
+        }
+        Some(cli::Command::PrCheck { repo_path, base, min_size, format, out, fail, config }) => {
+            let check = commands::pr_check::PrCheck { base: &base, min_size, format, out: out.as_deref(), fail };
+            commands::pr_check::run(repo_path.to_str().unwrap_or("."), &check, &config.overrides())
+        }
+        Some(cli::Command::LfsPlan { scan, config, min_size }) => {
+            commands::lfs_plan::run(&scan, &config.overrides(), min_size).await
+        }
+        Some(cli::Command::OpenIndex { path, config, notes }) => {
+            run_open_index(&path, &config.overrides(), notes.as_deref()).await
+        }
+        Some(cli::Command::ExportIndex { out, repo_path, index }) => {
+            commands::snapshot::export(repo_path.to_str().unwrap_or("."), index.as_deref(), &out).await
+        }
+        Some(cli::Command::ImportIndex { snapshot, repo_path, index }) => {
+            commands::snapshot::import(&snapshot, repo_path.to_str().unwrap_or("."), index.as_deref()).await
+        }
+        Some(cli::Command::Cache { action }) => match action {
+            cli::CacheCommand::Path { repo_path } => commands::cache::path(repo_path.to_str().unwrap_or(".")),
+            cli::CacheCommand::List => commands::cache::list(),
+            cli::CacheCommand::Clear { repo_path, all } => {
+                commands::cache::clear((!all).then(|| repo_path.to_str().unwrap_or(".")))
+            }
+        },
+        Some(cli::Command::Blob { oid, repo_path, config }) => {
+            commands::blob::run(repo_path.to_str().unwrap_or("."), &oid, &config.overrides()).await
+        }
+        Some(cli::Command::Sizes { scan, stdin: _, format }) => commands::sizes::run(&scan, format).await,
+        Some(cli::Command::Batch { repos_file, out, jobs }) => commands::batch::run(&repos_file, &out, jobs),
+        Some(cli::Command::Bench { repo, incremental, out }) => {
+            commands::bench::run(&repo, incremental, out.as_deref()).await
+        }
     }
+}
 
-    eprintln!("Using index: {}", db_path.display());
-
-    // Connect to database
-    let db_path_str = db_path.to_str().context("Invalid path encoding")?;
-    let db = Database::new(db_path_str).await?;
-    db.init_schema().await?;
+/// Scan the repository and browse the results
+async fn run_tui(args: &cli::TuiArgs) -> Result<()> {
+    // Profile mode only measures the scan
+    if args.profile {
+        return commands::scan::run(&args.scan, true).await;
+    }
+    if args.suggest_gitignore {
+        return commands::gitignore::run(&args.scan, &args.overrides()).await;
+    }
 
-    // Scan repository
-    let scanner = if profile_mode {
-        GitScanner::profiling(&repo_path)
+    let config = Config::load(&args.scan.repo_path, &args.overrides())?;
+    let repo_path = args.scan.repo_path();
+    let exclude = config.path_filter()?;
+    let (db, root) = if commands::loads_lazily(&args.scan, &exclude) {
+        commands::scan_top_level(&args.scan, &config).await?
     } else {
-        GitScanner::new(&repo_path)
+        commands::scan_with_config(&args.scan, &config).await?
     };
-    let root = scanner.scan(&db).await?;
 
-    eprintln!("Total cumulative: {}, Current: {}",
-        format_size(root.cumulative_size),
-        format_size(root.current_size));
+    info!("Total cumulative: {}, Current: {}",
+        format_size(root.root().cumulative_size),
+        format_size(root.root().current_size));
 
-    // In profile mode, exit after scanning
-    if profile_mode {
-        eprintln!("Profile mode: exiting after scan");
-        return Ok(());
+    let storage_hint = if args.remote_size {
+        Some(commands::storage_hint(repo_path, &config.remote).await?)
+    } else {
+        None
+    };
+
+    let repo_name = commands::repo_name(repo_path);
+    let mut app = load_app(root, &db, &config, &exclude).await?;
+    if let Ok(checkout) = Checkout::open(&args.scan.repo_path) {
+        app.tree_vm.set_checkout(checkout.label());
+    }
+    if let Some(hint) = storage_hint {
+        app.tree_vm.set_storage_hint(hint);
     }
+    refresh_git_dir_usage(&mut app, repo_path);
+    refresh_owners(&mut app, &db, repo_path, &exclude).await?;
 
-    // Load large blobs
-    let large_blobs = db.get_top_blobs(50).await?;
-    eprintln!("Loaded {} large blobs for detective view", large_blobs.len());
+    browse(app, &db, Some(args), &config, &repo_name, args.notes.as_deref()).await
+}
+
+/// Browse an index built elsewhere; nothing is scanned or written
+///
+/// Settings come from the user config file, a `.repodiet.toml` next to the
+/// index, the environment and flags.
+async fn run_open_index(path: &Path, overrides: &Overrides, notes: Option<&Path>) -> Result<()> {
+    let db = commands::open_existing_index(path).await?;
+    let config = Config::load(path.parent().unwrap_or(Path::new(".")), overrides)?;
+
+    let exclude = config.path_filter()?;
+    let root = if exclude.is_empty() {
+        Tree::from_dir_entries(db.get_dir_children("").await?)
+    } else {
+        let mut root = db.load_tree().await?;
+        root.prune(|path| exclude.is_excluded(path));
+        root
+    };
+    let mut app = load_app(root, &db, &config, &exclude).await?;
+    let head = db.get_metadata("head_oid").await.filter(|oid| !oid.is_empty());
+    app.tree_vm.set_checkout(match head {
+        Some(oid) => format!("index at {}", &oid[..oid.len().min(7)]),
+        None => "partial index".to_string(),
+    });
+
+    browse(app, &db, None, &config, &commands::index_name(path), notes).await
+}
 
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// View models for `root`, with everything else the views show read from `db`
+async fn load_app(root: Tree, db: &Database, config: &Config, exclude: &PathFilter) -> Result<AppViewModel> {
+    let large_blobs = db.get_top_blobs_filtered(config.top_blobs, false, exclude).await?;
+    let deleted_blobs = db.get_top_blobs_filtered(config.top_blobs, true, exclude).await?;
+    info!("Loaded {} large blobs for detective view", large_blobs.len());
 
-    // Create ViewModel
     let mut app = AppViewModel::new(root, large_blobs);
+    app.search_vm.set_page_size(config.search_limit);
+    app.search_vm.set_case(config.search_case);
+    app.flatten_vm.set_depth(config.flatten_depth);
+    app.tree_vm.set_old_age(config.old_after_years, OffsetDateTime::now_utc().unix_timestamp());
+    app.blobs_vm.set_deleted_blobs(deleted_blobs);
+    app.blobs_vm.set_author_grouping(config.authors);
+    app.duplicates_vm.set_duplicates(db.get_duplicate_blobs(config.top_blobs, exclude).await?);
+    app.commits_vm.set_commits(db.get_heavy_commits(config.top_blobs).await?);
+    let extensions = db.get_extension_stats(exclude).await?;
+    app.insights_vm.set_gitignore_suggestions(commands::gitignore::suggestions(db, &extensions, exclude).await?);
+    app.insights_vm.set_delta_efficiency(db.get_delta_efficiency(config.top_blobs, exclude).await?);
+    app.insights_vm.set_vendored_shares(db.get_vendored_shares(exclude).await?);
+    app.extension_vm.set_stats(extensions);
+    app.bloat_thresholds = config.bloat;
+    app.date_style = config.tui_dates();
+    if let Some((first, last)) = db.get_history_range().await? {
+        app.tree_vm.set_history_range(first, last);
+    }
+    app.set_diagnostics(db.get_scan_diagnostics().await?);
+    if let Some(limits) = db.get_metadata("scan_limits").await.filter(|l| !l.is_empty()) {
+        app.tree_vm.set_scan_limits(limits);
+    }
+    // A scan scoped to a sub-path holds nothing above it worth browsing
+    if let Some(path) = db.get_metadata("scan_path").await.filter(|p| !p.is_empty()) {
+        app.tree_vm.open_path(&path);
+    }
+    app.bookmarks_vm.set_bookmarks(db.get_bookmarks().await?);
+    Ok(app)
+}
+
+/// Run the TUI until the user quits, then print the exit summary
+///
+/// `source` is what a rescan scans again; without it rescans are ignored.
+/// With `notes`, the session is recorded and written there as Markdown.
+async fn browse(
+    mut app: AppViewModel,
+    db: &Database,
+    source: Option<&cli::TuiArgs>,
+    config: &Config,
+    name: &str,
+    notes: Option<&Path>,
+) -> Result<()> {
+    if notes.is_some() {
+        app.record_session();
+    }
+    // The guard restores the terminal however the loop ends; the panic hook
+    // covers unwinding past it
+    terminal::install_panic_hook();
+    let mut guard = TerminalGuard::enter(name)?;
+    let result = run_app(guard.terminal(), &mut app, db, source, config).await;
+    if let Err(e) = &result {
+        let _ = show_error(guard.terminal(), e);
+    }
+    drop(guard);
 
-    // Main event loop
+    if result.is_ok() {
+        let offenders = commands::summary::exit_offenders(app.root(), db).await?;
+        print!("{}", commands::summary::format_exit_summary(name, app.root(), &offenders));
+        print!("{}", commands::summary::format_recovery_commands(app.recovered()));
+        if let Some(path) = notes {
+            commands::notes::write(path, name, &app)?;
+            println!("  notes written to {}", path.display());
+        }
+    }
+    result
+}
+
+/// Main event loop
+async fn run_app(
+    terminal: &mut Tui,
+    app: &mut AppViewModel,
+    db: &Database,
+    source: Option<&cli::TuiArgs>,
+    config: &Config,
+) -> Result<()> {
+    let exclude = config.path_filter()?;
+    // Pack indexes of the scanned repository, read when the large blobs are
+    // first shown and again after a rescan
+    let mut packs: Option<PackSizeIndex> = None;
+    // Images in mono would bring the colors back
+    let protocol = match config.theme {
+        Theme::Mono => None,
+        Theme::Default => config.images.protocol(|name| std::env::var(name).ok()),
+    };
+    let mut images = InlineImages::new(protocol);
     loop {
-        // Render
-        terminal.draw(|f| {
-            let area = f.area();
-            match app.view_mode() {
-                ViewMode::Tree => render_tree(f, &app.tree_vm, area),
-                ViewMode::ByExtension => render_extension(f, &app.extension_vm, area),
-                ViewMode::Search => render_search(f, &app.search_vm, area),
-                ViewMode::LargeBlobs => render_blobs(f, &app.blobs_vm, area),
+        // A partial tree reads what the views need from the index
+        while let Some((dir, path)) = app.pending_dir() {
+            let entries = db.get_dir_children(&path).await?;
+            app.load_dir(dir, &path, entries);
+        }
+        if app.view_mode() == ViewMode::Tree
+            && let Some((dir, deleted_only)) = app.tree_vm.pending_biggest_dir()
+        {
+            let found = db.get_largest_file(&dir, deleted_only).await?;
+            app.tree_vm.set_biggest_file(dir, deleted_only, found);
+        }
+        if let Some(query) = app.search_vm.pending_query().map(str::to_string) {
+            let deleted_only = app.search_vm.is_deleted_only();
+            let case_sensitive = app.search_vm.is_case_sensitive();
+            let matches = db.search_paths(&query, deleted_only, case_sensitive, INDEX_MATCH_LIMIT).await?;
+            app.search_vm.set_matches(&query, deleted_only, case_sensitive, matches);
+        }
+
+        // A filtered blob list shows the largest matches in the index, not
+        // just those among the blobs loaded up front
+        if app.view_mode() == ViewMode::LargeBlobs
+            && let Some(matching) = app.blobs_vm.pending_filter()
+        {
+            let text = app.blobs_vm.filter().text().to_string();
+            let deleted_only = app.blobs_vm.is_deleted_only();
+            let blobs = db.get_top_blobs_matching(config.top_blobs, deleted_only, &matching, &exclude).await?;
+            app.blobs_vm.set_filter_matches(&text, deleted_only, blobs);
+        }
+
+        if app.view_mode() == ViewMode::Tree
+            && let Some(dir) = app.tree_vm.pending_vendored_dir()
+        {
+            let sizes = db.get_vendored_sizes(&dir, &exclude).await?;
+            app.tree_vm.set_vendored_sizes(dir, sizes);
+        }
+
+        if app.view_mode() == ViewMode::Tree
+            && let Some(dir) = app.tree_vm.pending_deletion_dates_dir()
+        {
+            let dates = db.get_deletion_dates(&dir, &exclude).await?;
+            app.tree_vm.set_deletion_dates(dir, dates);
+        }
+
+        if app.view_mode() == ViewMode::Tree
+            && let Some(dir) = app.tree_vm.pending_old_dir()
+        {
+            let sizes = db.get_old_sizes(&dir, app.tree_vm.old_cutoff(), &exclude).await?;
+            app.tree_vm.set_old_sizes(dir, sizes);
+        }
+
+        if app.view_mode() == ViewMode::Tree
+            && let Some(dir) = app.tree_vm.pending_binary_dir()
+        {
+            let size = db.get_binary_size(&dir, &exclude).await?;
+            app.tree_vm.set_binary_size(dir, size);
+        }
+
+        // Fetch the selected tree entry's growth and breakdown before drawing them
+        if app.view_mode() == ViewMode::Tree
+            && let Some(path) = app.tree_vm.pending_history_path()
+        {
+            let added = db.get_path_history(&path).await?;
+            app.tree_vm.set_history(path, &added);
+        }
+        if app.view_mode() == ViewMode::Tree
+            && let Some((dir, deleted_only)) = app.tree_vm.pending_details_dir()
+        {
+            let details = db.get_entry_details(&dir, deleted_only, DETAIL_ROWS).await?;
+            app.tree_vm.set_details(dir, deleted_only, details);
+        }
+
+        // Blobs `git gc` has pruned since are only a row in the index; an
+        // opened index has no object database to check them against
+        if app.view_mode() == ViewMode::LargeBlobs
+            && let Some(args) = source
+        {
+            let oids = app.blobs_vm.unchecked_oids();
+            if !oids.is_empty() {
+                if packs.is_none() {
+                    packs = PackSizeIndex::open(args.scan.repo_path())
+                        .inspect_err(|e| warn!("Could not read the packs: {:#}", e))
+                        .ok();
+                }
+                let index = packs.as_ref();
+                app.blobs_vm.set_stored(oids.into_iter().map(|oid| {
+                    let stored = index.and_then(|index| index.holds(&oid));
+                    (oid, stored)
+                }));
             }
-        })?;
+        }
+
+        // Reading the blobs back takes a moment, so it waits until asked for
+        if app.view_mode() == ViewMode::LargeBlobs && app.blobs_vm.needs_groups() {
+            redraw(terminal, &mut images, app, config, Some("Comparing large blobs..."))?;
+            let oids: Vec<&[u8]> = app.blobs_vm.group_candidates().iter().map(|b| b.oid.as_slice()).collect();
+            // An opened index has no object database to read from
+            let fingerprints = match source {
+                Some(args) => fingerprint_blobs(args.scan.repo_path(), &oids).unwrap_or_else(|e| {
+                    warn!("Could not read blobs to compare: {:#}", e);
+                    vec![None; oids.len()]
+                }),
+                None => vec![None; oids.len()],
+            };
+            app.blobs_vm.set_fingerprints(&fingerprints);
+        }
+
+        redraw(terminal, &mut images, app, config, app.notice())?;
 
         // Handle input
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                let is_at_root = app.tree_vm.is_at_root();
-                let is_search = app.is_search_mode();
-                let input_mode = app.input_view_mode();
-
-                if let Some(intent) = map_key_to_intent(&key, input_mode, is_search, is_at_root) {
-                    match app.handle_intent(intent) {
-                        Action::Quit => break,
-                        Action::Redraw => {}
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            // Esc cancels a visual selection before it quits
+            let is_at_root = app.tree_vm.is_at_root() && !app.marks.is_visual();
+            let is_search = app.is_text_input();
+            let input_mode = app.input_view_mode();
+
+            if let Some(intent) = map_key_to_intent(&key, input_mode, is_search, is_at_root, &config.keys) {
+                match app.handle_intent(intent) {
+                    Action::Quit => return Ok(()),
+                    Action::Redraw => {}
+                    Action::LookupBlob(oid) => {
+                        let result = db.lookup_blob(&oid).await;
+                        app.set_lookup_result(result);
+                    }
+                    // A read-only index keeps bookmarks for the session only
+                    Action::SaveBookmark(_) | Action::DeleteBookmark(_) if db.is_read_only() => {}
+                    Action::SaveBookmark(path) => db.add_bookmark(&path).await?,
+                    Action::DeleteBookmark(path) => db.remove_bookmark(&path).await?,
+                    Action::Preview { oid, path } => {
+                        let preview = match source {
+                            Some(args) => preview(db, args.scan.repo_path(), oid, &path).await,
+                            None => Err(anyhow::anyhow!("Previews read the repository; an opened index has none")),
+                        };
+                        app.set_preview(preview);
+                    }
+                    Action::CopyRecoveryCommand { oid, path } => {
+                        let commands = blob_at(db, oid, &path).await.map(|oid| RecoveryCommands::new(&hex::encode(oid), &path));
+                        if let Ok(commands) = &commands {
+                            // The commands are shown and printed on exit too,
+                            // for terminals that ignore the clipboard request
+                            let _ = terminal::copy_to_clipboard(&commands.cat_file);
+                        }
+                        app.set_recovery(commands);
+                    }
+                    Action::PlanRestore { oid, path } => {
+                        let plan = match source {
+                            Some(args) => match blob_at(db, oid, &path).await {
+                                Ok(oid) => plan_restore(args.scan.repo_path(), &oid, &path),
+                                Err(e) => Err(e),
+                            },
+                            None => Err(anyhow::anyhow!("Restoring reads the repository; an opened index has none")),
+                        };
+                        if let Ok(plan) = &plan {
+                            let _ = terminal::copy_to_clipboard(&plan.checkout);
+                        }
+                        app.set_restore(plan);
+                    }
+                    Action::RestoreFile { commit, path } => {
+                        // Only reachable from a plan, which needs a source
+                        if let Some(args) = source {
+                            app.set_restored(restore_file(args.scan.repo_path(), &commit, &path).map(|_| ()));
+                        }
+                    }
+                    Action::ExportList { list, format } => {
+                        let written = commands::export::write(Path::new("."), &list, format);
+                        app.set_exported(list.rows.len(), written);
+                    }
+                    Action::Rescan => {
+                        if let Some(args) = source {
+                            redraw(terminal, &mut images, app, config, Some("Rescanning... (Esc to cancel)"))?;
+                            rescan(app, db, args, config).await?;
+                            packs = None;
+                        }
                     }
                 }
             }
         }
     }
+}
+
+/// Preview of blob `oid` or, without one, of the blob `path` has in HEAD
+/// (or last had, for a deleted file)
+async fn preview(db: &Database, repo_path: &str, oid: Option<Vec<u8>>, path: &str) -> Result<BlobPreview> {
+    let oid = blob_at(db, oid, path).await?;
+    preview_blob(repo_path, &oid, path)
+}
+
+/// `oid` when there is one, else the blob `path` has in HEAD (or last had)
+async fn blob_at(db: &Database, oid: Option<Vec<u8>>, path: &str) -> Result<Vec<u8>> {
+    match oid {
+        Some(oid) => Ok(oid),
+        None => db.get_path_blob(path).await?.with_context(|| format!("No blob recorded for {}", path)),
+    }
+}
+
+/// Render the current view, with an optional notice on top
+/// Draw a frame, then the chart image it leaves room for
+fn redraw(terminal: &mut Tui, images: &mut InlineImages, app: &AppViewModel, config: &Config, notice: Option<&str>) -> Result<()> {
+    let mut image = None;
+    terminal.draw(|f| image = draw(f, app, config, images.enabled(), notice))?;
+    images.update(terminal, image)
+}
+
+/// Draw a frame; with `images`, a chart is left for the terminal to draw
+/// and returned
+fn draw(f: &mut ratatui::Frame, app: &AppViewModel, config: &Config, images: bool, notice: Option<&str>) -> Option<DonutImage> {
+    let area = f.area();
+    let thresholds = &app.bloat_thresholds;
+    // An image would cover the popups drawn over the view
+    let popup = app.is_help_visible()
+        || app.lfs_plan().is_some()
+        || app.preview().is_some()
+        || app.recovery().is_some()
+        || app.restore().is_some()
+        || notice.is_some();
+    let mut image = None;
+    match app.view_mode() {
+        ViewMode::Tree => render_tree(f, &app.tree_vm, &app.marks, &app.bookmarks_vm, thresholds, area),
+        ViewMode::ByExtension => {
+            image = render_extension(f, &app.extension_vm, &app.marks, thresholds, images && !popup, area);
+        }
+        ViewMode::Search => render_search(f, &app.search_vm, &app.marks, thresholds, area),
+        ViewMode::LargeBlobs => render_blobs(f, &app.blobs_vm, &app.marks, &app.date_style, area),
+        ViewMode::BlobLookup => render_blob_lookup(f, &app.lookup_vm, &app.date_style, area),
+        ViewMode::Bookmarks => render_bookmarks(f, &app.bookmarks_vm, &app.marks, area),
+        ViewMode::Insights => render_insights(f, &app.insights_vm, &app.marks, area),
+        ViewMode::Duplicates => render_duplicates(f, &app.duplicates_vm, &app.marks, area),
+        ViewMode::HeavyCommits => render_commits(f, &app.commits_vm, &app.marks, &app.date_style, area),
+        ViewMode::TopDirs => render_top_dirs(f, &app.top_dirs_vm, &app.marks, area),
+        ViewMode::Flatten => render_flatten(f, &app.flatten_vm, &app.marks, thresholds, area),
+        ViewMode::Diagnostics => render_diagnostics(f, &app.diagnostics_vm, &app.marks, area),
+        ViewMode::Owners => render_owners(f, &app.owners_vm, &app.marks, thresholds, area),
+    }
+    if app.is_help_visible() {
+        render_help(f, thresholds, &config.keys, area);
+    }
+    if let Some(plan) = app.lfs_plan() {
+        render_lfs_plan(f, plan, area);
+    }
+    if let Some(preview) = app.preview() {
+        render_preview(f, preview, area);
+    }
+    if let Some(recovery) = app.recovery() {
+        render_recovery(f, recovery, area);
+    }
+    if let Some(restore) = app.restore() {
+        render_restore(f, restore, area);
+    }
+    if let Some(message) = notice {
+        render_notice(f, message, area);
+    }
+    apply_theme(config.theme, f.buffer_mut());
+    image
+}
+
+/// Scan the repository again, picking up new commits, and show the results
+///
+/// A cancelled rescan keeps showing the previous results; the commits it got
+/// through are saved for the next one.
+async fn rescan(app: &mut AppViewModel, db: &Database, args: &cli::TuiArgs, config: &Config) -> Result<()> {
+    let watcher = interrupt::KeyWatcher::start();
+    // Progress bars would draw over the TUI
+    let scanner = args.scan.scanner(false).with_progress(Arc::new(NoopProgress)).with_cancel(watcher.token());
+    let exclude = config.path_filter()?;
+    let scanned = if app.root().is_partial() {
+        scanner.update_index(db).await.map(|()| None)
+    } else {
+        scanner.scan(db).await.map(Some)
+    };
+    drop(watcher);
+    let mut root = match scanned {
+        Ok(Some(root)) => root,
+        // A partial tree is read again one directory at a time
+        Ok(None) => Tree::from_dir_entries(db.get_dir_children("").await?),
+        Err(e) if e.is::<ScanCancelled>() => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    commands::classify_content(db, args.scan.repo_path()).await?;
+    if !exclude.is_empty() {
+        root.prune(|path| exclude.is_excluded(path));
+    }
+    let large_blobs = db.get_top_blobs_filtered(config.top_blobs, false, &exclude).await?;
+    let deleted_blobs = db.get_top_blobs_filtered(config.top_blobs, true, &exclude).await?;
+    app.replace_tree(root, large_blobs, deleted_blobs);
+    app.duplicates_vm.set_duplicates(db.get_duplicate_blobs(config.top_blobs, &exclude).await?);
+    app.commits_vm.set_commits(db.get_heavy_commits(config.top_blobs).await?);
+    let extensions = db.get_extension_stats(&exclude).await?;
+    app.insights_vm.set_gitignore_suggestions(commands::gitignore::suggestions(db, &extensions, &exclude).await?);
+    app.insights_vm.set_delta_efficiency(db.get_delta_efficiency(config.top_blobs, &exclude).await?);
+    app.insights_vm.set_vendored_shares(db.get_vendored_shares(&exclude).await?);
+    app.extension_vm.set_stats(extensions);
 
-    // Cleanup
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
-    terminal.show_cursor()?;
+    if let Some((first, last)) = db.get_history_range().await? {
+        app.tree_vm.set_history_range(first, last);
+    }
+    app.set_diagnostics(db.get_scan_diagnostics().await?);
+    // A gc or repack may be why the user rescanned
+    refresh_git_dir_usage(app, args.scan.repo_path());
+    refresh_owners(app, db, args.scan.repo_path(), &exclude).await?;
+    Ok(())
+}
+
+/// Measure the repository's `.git` directory for the tree header; a
+/// directory that can't be read only costs the header line
+fn refresh_git_dir_usage(app: &mut AppViewModel, repo_path: &str) {
+    match git_dir_usage(repo_path) {
+        Ok(usage) => app.tree_vm.set_git_dir_usage(usage),
+        Err(e) => warn!("Could not measure the git directory: {:#}", e),
+    }
+}
 
+/// Credit the indexed files to their owners in HEAD's CODEOWNERS file; one
+/// that can't be read leaves the owners view empty
+async fn refresh_owners(app: &mut AppViewModel, db: &Database, repo_path: &str, exclude: &PathFilter) -> Result<()> {
+    let codeowners = read_codeowners(repo_path).unwrap_or_else(|e| {
+        warn!("Could not read CODEOWNERS: {:#}", e);
+        None
+    });
+    match codeowners {
+        Some((source, text)) => {
+            let owners = CodeOwners::parse(&text);
+            let totals = owners.tally(db.get_file_sizes(exclude).await?);
+            app.owners_vm.set_owners(Some(source), owners.invalid_lines, totals);
+        }
+        None => app.owners_vm.set_owners(None, Vec::new(), Vec::new()),
+    }
     Ok(())
 }
+
+/// Show a fatal error inside the TUI and wait for a key before leaving it
+fn show_error(terminal: &mut Tui, error: &anyhow::Error) -> Result<()> {
+    let message = error
+        .chain()
+        .map(|cause| cause.to_string())
+        .collect::<Vec<_>>()
+        .join("\n  caused by: ");
+    terminal.draw(|f| render_error(f, &message, f.area()))?;
+    loop {
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            return Ok(());
+        }
+    }
+}
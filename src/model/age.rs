@@ -0,0 +1,50 @@
+use time::OffsetDateTime;
+
+/// Content first committed longer ago than this counts as old, unless
+/// configured otherwise
+pub const DEFAULT_OLD_AFTER_YEARS: u32 = 5;
+
+/// Unix timestamp `years` calendar years before `now`; content first
+/// committed before it is old
+///
+/// A February 29th with no counterpart becomes the 28th.
+pub fn old_cutoff(now: i64, years: u32) -> i64 {
+    let Ok(now) = OffsetDateTime::from_unix_timestamp(now) else {
+        return now;
+    };
+    let Some(year) = now.year().checked_sub(i32::try_from(years).unwrap_or(i32::MAX)) else {
+        return i64::MIN;
+    };
+    let then = now.replace_year(year).or_else(|_| now.replace_day(28).and_then(|d| d.replace_year(year)));
+    match then {
+        Ok(then) => then.unix_timestamp(),
+        // Before the earliest year `time` handles, so nothing is that old
+        Err(_) => i64::MIN,
+    }
+}
+
+/// Old content below one entry of a directory, for filtering the tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OldSizes {
+    /// Blob data first committed before the cutoff
+    pub cumulative: u64,
+    /// Of that, at paths HEAD no longer has
+    pub deleted: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::parse_date;
+
+    #[test]
+    fn test_old_cutoff_counts_calendar_years() {
+        let now = parse_date("2026-10-15").unwrap() + 3600;
+        assert_eq!(old_cutoff(now, 5), parse_date("2021-10-15").unwrap() + 3600);
+        assert_eq!(old_cutoff(now, 0), now);
+
+        let leap_day = parse_date("2024-02-29").unwrap();
+        assert_eq!(old_cutoff(leap_day, 1), parse_date("2023-02-28").unwrap());
+        assert_eq!(old_cutoff(leap_day, 4), parse_date("2020-02-29").unwrap());
+    }
+}
@@ -0,0 +1,64 @@
+use serde::Deserialize;
+
+/// How blobs are credited to the people who introduced them
+///
+/// Identities are already normalized through the repository's `.mailmap`
+/// when scanned; grouping by domain then credits them to organizations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorGrouping {
+    /// Each author by name
+    #[default]
+    Name,
+    /// Each email domain, e.g. "example.com"
+    Domain,
+}
+
+impl AuthorGrouping {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(AuthorGrouping::Name),
+            "domain" => Some(AuthorGrouping::Domain),
+            _ => None,
+        }
+    }
+
+    /// What an author with `name` and `email` is shown and counted as;
+    /// domains are lowercased, as they compare
+    pub fn label(self, name: &str, email: &str) -> String {
+        match self {
+            AuthorGrouping::Name => name.to_string(),
+            AuthorGrouping::Domain => email_domain(email).to_ascii_lowercase(),
+        }
+    }
+}
+
+/// The part of `email` after its last '@'; all of it when there is none
+pub fn email_domain(email: &str) -> &str {
+    email.rsplit_once('@').map_or(email, |(_, domain)| domain)
+}
+
+/// Blobs first committed by one author (or one email domain)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorTotals {
+    /// Name or domain, as grouped
+    pub author: String,
+    pub blob_count: u64,
+    /// Bytes those blobs take on disk
+    pub size: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        assert_eq!(AuthorGrouping::Name.label("Ann", "ann@corp.example"), "Ann");
+        assert_eq!(AuthorGrouping::Domain.label("Ann", "ann@Corp.Example"), "corp.example");
+        assert_eq!(AuthorGrouping::Domain.label("Ann", "odd@name@corp.example"), "corp.example");
+        assert_eq!(AuthorGrouping::Domain.label("Ann", "localhost"), "localhost");
+        assert_eq!(AuthorGrouping::parse("domain"), Some(AuthorGrouping::Domain));
+        assert_eq!(AuthorGrouping::parse("team"), None);
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+/// How bad an entry's bloat ratio (cumulative / current) is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BloatLevel {
+    Low,
+    Warn,
+    Severe,
+}
+
+/// Ratios above which an entry is flagged; deleted entries are always severe
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BloatThresholds {
+    pub warn: f64,
+    pub severe: f64,
+}
+
+impl Default for BloatThresholds {
+    fn default() -> Self {
+        Self { warn: 20.0, severe: 50.0 }
+    }
+}
+
+impl BloatThresholds {
+    pub fn level(&self, bloat: f64) -> BloatLevel {
+        if bloat > self.severe {
+            BloatLevel::Severe
+        } else if bloat > self.warn {
+            BloatLevel::Warn
+        } else {
+            BloatLevel::Low
+        }
+    }
+
+    /// Both thresholds must be at least 1x, with `warn` below `severe`
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.warn >= 1.0 && self.warn.is_finite(),
+            "bloat warn threshold must be at least 1, got {}",
+            self.warn
+        );
+        anyhow::ensure!(
+            self.severe > self.warn && self.severe.is_finite(),
+            "bloat severe threshold must be above warn ({}), got {}",
+            self.warn,
+            self.severe
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_and_validation() {
+        let t = BloatThresholds { warn: 2.0, severe: 5.0 };
+        assert_eq!(t.level(1.5), BloatLevel::Low);
+        assert_eq!(t.level(2.0), BloatLevel::Low);
+        assert_eq!(t.level(3.0), BloatLevel::Warn);
+        assert_eq!(t.level(f64::INFINITY), BloatLevel::Severe);
+        assert!(t.validate().is_ok());
+
+        assert!(BloatThresholds { warn: 5.0, severe: 5.0 }.validate().is_err());
+        assert!(BloatThresholds { warn: 0.5, severe: 5.0 }.validate().is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::util::extension_label;
+
 /// Large blob information for display in the large blob detective view
 #[derive(Debug, Clone)]
 pub struct LargeBlobInfo {
@@ -5,7 +7,25 @@ pub struct LargeBlobInfo {
     pub size: u64,
     pub path: String,
     pub first_author: String,
+    /// `first_author`'s email, normalized through `.mailmap` like the name
+    pub first_email: String,
     pub first_date: i64,
+    /// Subject of the commit that introduced it
+    pub first_subject: String,
+    /// Whether any path in HEAD still points at this blob
+    pub in_head: bool,
+}
+
+/// A blob a branch adds on top of its base (see `repository::branch_blobs`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddedBlob {
+    /// Where it first appears on the branch
+    pub path: String,
+    pub oid: String,
+    /// Its own size, not what it takes in a pack
+    pub size: u64,
+    /// The first commit on the branch with it
+    pub commit: String,
 }
 
 /// Statistics aggregated by file extension
@@ -13,5 +33,212 @@ pub struct LargeBlobInfo {
 pub struct ExtensionStats {
     pub cumulative_size: u64,
     pub current_size: u64,
+    /// Cumulative size of files with this extension that are gone from HEAD
+    pub deleted_size: u64,
     pub file_count: u64,
+    /// Cumulative size of files with this extension classified as binary
+    pub binary_size: u64,
+}
+
+/// A path a blob was committed at
+#[derive(Debug, Clone)]
+pub struct BlobOccurrence {
+    pub path: String,
+    /// Whether HEAD still has this exact blob at this path
+    pub in_head: bool,
+}
+
+/// A blob committed at more than one path
+#[derive(Debug, Clone)]
+pub struct DuplicateBlob {
+    pub oid: Vec<u8>,
+    pub size: u64,
+    /// Every path it was committed at, sorted by path
+    pub paths: Vec<BlobOccurrence>,
+}
+
+impl DuplicateBlob {
+    /// Copies HEAD checks out; each one past the first is wasted worktree space
+    pub fn head_copies(&self) -> usize {
+        self.paths.iter().filter(|p| p.in_head).count()
+    }
+
+    /// Bytes the extra copies take in a checkout
+    pub fn wasted(&self) -> u64 {
+        self.size * self.head_copies().saturating_sub(1) as u64
+    }
+}
+
+/// Everything the index knows about a single blob, for OID lookups
+#[derive(Debug, Clone)]
+pub struct BlobLookup {
+    pub oid: Vec<u8>,
+    pub size: u64,
+    /// Path the blob was introduced at
+    pub path: String,
+    pub first_author: String,
+    /// `first_author`'s email, normalized through `.mailmap` like the name
+    pub first_email: String,
+    pub first_date: i64,
+    /// Subject of the commit that introduced it
+    pub first_subject: String,
+    /// All paths the blob appeared at, sorted by path
+    pub occurrences: Vec<BlobOccurrence>,
+}
+
+impl BlobLookup {
+    /// Whether the blob is still present anywhere in HEAD
+    pub fn in_head(&self) -> bool {
+        self.occurrences.iter().any(|o| o.in_head)
+    }
+}
+
+/// What the large blobs list is narrowed to, parsed from the text typed
+/// after '/'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlobFilter {
+    /// ".zip" (or "*.zip"): files with that extension, as
+    /// `util::extension_label` names it
+    Extension(String),
+    /// "assets/": paths under a directory, anchored at the root like
+    /// `exclude` patterns
+    PathPrefix(String),
+    /// Anything else: paths containing the text
+    PathContains(String),
+}
+
+impl BlobFilter {
+    /// Parse filter text; `None` when it is blank. Matching ignores case.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let extension = text.strip_prefix('*').unwrap_or(text);
+        if let Some(ext) = extension.strip_prefix('.')
+            && !ext.is_empty()
+            && ext.len() <= 10
+            && !ext.contains(['.', '/', '*'])
+        {
+            return Some(BlobFilter::Extension(format!(".{}", ext.to_ascii_lowercase())));
+        }
+        if text.contains('/') {
+            let prefix = text.trim_start_matches('/');
+            return (!prefix.is_empty()).then(|| BlobFilter::PathPrefix(prefix.to_string()));
+        }
+        (!text.is_empty()).then(|| BlobFilter::PathContains(text.to_string()))
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        match self {
+            BlobFilter::Extension(ext) => {
+                let file_name = path.rsplit('/').next().unwrap_or(path);
+                extension_label(file_name) == ext.as_str()
+            }
+            BlobFilter::PathPrefix(prefix) => {
+                path.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+            }
+            BlobFilter::PathContains(text) => path.to_ascii_lowercase().contains(&text.to_ascii_lowercase()),
+        }
+    }
+}
+
+/// Commands that bring back a blob's content, e.g. a deleted large file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryCommands {
+    /// Path the blob was committed at
+    pub path: String,
+    /// `git show <oid>`, printing the content
+    pub show: String,
+    /// `git cat-file -p <oid> > <name>`, writing it to a file named after
+    /// the path the blob was committed at
+    pub cat_file: String,
+}
+
+impl RecoveryCommands {
+    pub fn new(oid: &str, path: &str) -> Self {
+        let name = path.rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or(oid);
+        Self {
+            path: path.to_string(),
+            show: format!("git show {}", oid),
+            cat_file: format!("git cat-file -p {} > {}", oid, shell_quote(name)),
+        }
+    }
+}
+
+/// How to bring a deleted file back into the worktree: check it out of a
+/// commit that still has it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestorePlan {
+    pub path: String,
+    /// Hex id of the newest commit whose tree has the blob at `path`
+    pub commit: String,
+    /// `git checkout <commit> -- <path>`
+    pub checkout: String,
+    /// Something is at `path` in the worktree already and would be replaced
+    pub overwrites: bool,
+    /// The file has been written back
+    pub restored: bool,
+}
+
+impl RestorePlan {
+    pub fn new(commit: &str, path: &str, overwrites: bool) -> Self {
+        Self {
+            path: path.to_string(),
+            commit: commit.to_string(),
+            checkout: format!("git checkout {} -- {}", commit, shell_quote(path)),
+            overwrites,
+            restored: false,
+        }
+    }
+}
+
+/// `word` as a single POSIX shell word, quoted only when it needs to be
+fn shell_quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "._-+,@%=:/".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_filter() {
+        assert_eq!(BlobFilter::parse(".ZIP"), Some(BlobFilter::Extension(".zip".to_string())));
+        assert_eq!(BlobFilter::parse("*.zip"), Some(BlobFilter::Extension(".zip".to_string())));
+        assert_eq!(BlobFilter::parse("/assets/"), Some(BlobFilter::PathPrefix("assets/".to_string())));
+        assert_eq!(BlobFilter::parse("video"), Some(BlobFilter::PathContains("video".to_string())));
+        assert_eq!(BlobFilter::parse(".tar.gz"), Some(BlobFilter::PathContains(".tar.gz".to_string())));
+        assert_eq!(BlobFilter::parse("  "), None);
+
+        let zip = BlobFilter::parse(".zip").unwrap();
+        assert!(zip.matches("dist/Release.ZIP"));
+        assert!(!zip.matches("dist.zip/readme"));
+        assert!(!zip.matches("dist/release.zipx"));
+        let assets = BlobFilter::parse("Assets/").unwrap();
+        assert!(assets.matches("assets/intro.mp4"));
+        assert!(!assets.matches("web/assets/intro.mp4"));
+        assert!(BlobFilter::parse("INTRO").unwrap().matches("web/assets/intro.mp4"));
+    }
+
+    #[test]
+    fn test_recovery_commands() {
+        let commands = RecoveryCommands::new("abc123", "assets/intro.mp4");
+        assert_eq!(commands.show, "git show abc123");
+        assert_eq!(commands.cat_file, "git cat-file -p abc123 > intro.mp4");
+
+        let odd = RecoveryCommands::new("abc123", "docs/Bob's notes (v2).txt");
+        assert_eq!(odd.cat_file, "git cat-file -p abc123 > 'Bob'\\''s notes (v2).txt'");
+    }
+
+    #[test]
+    fn test_restore_plan() {
+        let plan = RestorePlan::new("cafe01", "assets/intro.mp4", false);
+        assert_eq!(plan.checkout, "git checkout cafe01 -- assets/intro.mp4");
+        assert!(!plan.restored);
+        let spaced = RestorePlan::new("cafe01", "old docs/a b.pdf", true);
+        assert_eq!(spaced.checkout, "git checkout cafe01 -- 'old docs/a b.pdf'");
+    }
 }
@@ -0,0 +1,14 @@
+/// A commit with the blob data it was first to add
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeavyCommit {
+    pub oid: Vec<u8>,
+    pub author: String,
+    pub email: String,
+    pub date: i64,
+    pub subject: String,
+    /// Bytes of the blobs no earlier commit had
+    pub added_size: u64,
+    pub blob_count: u64,
+    /// The largest of those blobs' first path, to show in the tree
+    pub largest_path: String,
+}
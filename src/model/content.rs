@@ -0,0 +1,40 @@
+/// Leading bytes of a blob looked at to tell binary from text
+pub const SNIFF_LEN: usize = 1024;
+
+/// Whether content looks binary: a NUL byte among the first `SNIFF_LEN`
+/// bytes, as text in any common encoding but UTF-16 has none
+pub fn looks_binary(data: &[u8]) -> bool {
+    data[..data.len().min(SNIFF_LEN)].contains(&0)
+}
+
+/// Verdict for an extension from its sampled blobs (`None` where one
+/// couldn't be read): binary when most readable samples are, unknown when
+/// none could be read
+pub fn majority_binary(samples: &[Option<bool>]) -> Option<bool> {
+    let readable = samples.iter().flatten().count();
+    let binary = samples.iter().flatten().filter(|&&b| b).count();
+    (readable > 0).then_some(binary * 2 > readable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nul_in_first_kilobyte_means_binary() {
+        assert!(looks_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(!looks_binary("fn main() {}\n// ünïcode\n".as_bytes()));
+        assert!(!looks_binary(b""));
+
+        let mut late = vec![b'a'; SNIFF_LEN];
+        late.push(0);
+        assert!(!looks_binary(&late));
+    }
+
+    #[test]
+    fn test_majority_of_readable_samples() {
+        assert_eq!(majority_binary(&[Some(true), Some(true), Some(false)]), Some(true));
+        assert_eq!(majority_binary(&[Some(true), None, Some(false)]), Some(false));
+        assert_eq!(majority_binary(&[None, None]), None);
+    }
+}
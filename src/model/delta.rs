@@ -0,0 +1,73 @@
+use crate::util::format_size;
+
+/// Storage ratio at or above which a path counts as delta-hostile
+pub const DELTA_HOSTILE_RATIO: f64 = 0.5;
+/// Fewer versions say little about how well a file deltas
+const MIN_VERSIONS: u64 = 3;
+
+/// How well git delta-compresses the versions of one path
+///
+/// Each version's size is its entry in the pack (or its loose object): a
+/// version stored as a delta against another takes a fraction of the
+/// largest one, a version git couldn't delta takes about as much.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeltaEfficiency {
+    pub path: String,
+    /// Distinct blobs committed at the path
+    pub versions: u64,
+    /// Sum of the versions' entry sizes
+    pub stored: u64,
+    /// Entry size of the largest version
+    pub largest: u64,
+}
+
+impl DeltaEfficiency {
+    /// Stored bytes over what every version stored whole would take: near
+    /// `1 / versions` when deltas work, near 1 when they don't
+    pub fn ratio(&self) -> f64 {
+        if self.versions == 0 || self.largest == 0 {
+            return 0.0;
+        }
+        self.stored as f64 / (self.versions * self.largest) as f64
+    }
+
+    /// Versions that barely delta against each other, like encrypted or
+    /// already compressed binaries: each one adds about its full size
+    pub fn is_delta_hostile(&self) -> bool {
+        self.versions >= MIN_VERSIONS && self.ratio() >= DELTA_HOSTILE_RATIO
+    }
+
+    /// "12 versions, 48.0 MB stored at 91% of full size each"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} versions, {} stored at {:.0}% of full size each",
+            self.versions,
+            format_size(self.stored),
+            self.ratio() * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn efficiency(versions: u64, stored: u64, largest: u64) -> DeltaEfficiency {
+        DeltaEfficiency { path: "a.bin".to_string(), versions, stored, largest }
+    }
+
+    #[test]
+    fn test_delta_hostile_needs_versions_and_ratio() {
+        // One full copy and nine small deltas
+        let text = efficiency(10, 1000 + 9 * 20, 1000);
+        assert!((text.ratio() - 0.118).abs() < 0.001);
+        assert!(!text.is_delta_hostile());
+
+        let encrypted = efficiency(4, 3900, 1000);
+        assert!(encrypted.is_delta_hostile());
+        assert_eq!(encrypted.summary(), "4 versions, 3.8 KB stored at 98% of full size each");
+
+        assert!(!efficiency(2, 2000, 1000).is_delta_hostile());
+        assert_eq!(efficiency(3, 0, 0).ratio(), 0.0);
+    }
+}
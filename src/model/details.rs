@@ -0,0 +1,12 @@
+/// Rows in each section of a directory's details
+pub const DETAIL_ROWS: usize = 5;
+
+/// What a directory holds, for the tree's details panel
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntryDetails {
+    /// Cumulative size per extension label, largest first
+    pub extensions: Vec<(String, u64)>,
+    /// Files with the largest cumulative size below the directory, as
+    /// (repository-relative path, size), largest first
+    pub largest: Vec<(String, u64)>,
+}
@@ -0,0 +1,77 @@
+/// An object a scan could not read, so whatever it holds is missing from
+/// the results (typically a shallow or partial clone), or a pack that could
+/// not be read and the blobs it left without a size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanDiagnostic {
+    /// Hex OID of the object; a pack's checksum
+    pub oid: String,
+    /// "commit", "tree", "size" (a blob counted as 0 bytes) or "pack"
+    pub kind: String,
+    /// Where the scan met the object: a tree's or blob's path, a pack's
+    /// file name; empty for commits
+    pub path: String,
+    pub reason: String,
+}
+
+impl ScanDiagnostic {
+    /// Whether this is an object the scan skipped, rather than a damaged
+    /// pack or a blob it could only count as 0 bytes
+    pub fn is_skipped_object(&self) -> bool {
+        matches!(self.kind.as_str(), "commit" | "tree")
+    }
+}
+
+/// Packs a scan could not read in full, and the blobs counted as 0 bytes
+/// because of them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackHealth {
+    /// "pack-<checksum>.idx: why", one per pack
+    pub corrupt_packs: Vec<String>,
+    /// Blobs in no readable pack nor loose
+    pub unknown_sizes: usize,
+}
+
+impl PackHealth {
+    pub fn from_diagnostics(diagnostics: &[ScanDiagnostic]) -> Self {
+        let mut health = Self::default();
+        for diagnostic in diagnostics {
+            match diagnostic.kind.as_str() {
+                "pack" => health.corrupt_packs.push(format!("{}: {}", diagnostic.path, diagnostic.reason)),
+                "size" => health.unknown_sizes += 1,
+                _ => {}
+            }
+        }
+        health
+    }
+
+    /// Whether every pack was read and every blob's size found
+    pub fn is_healthy(&self) -> bool {
+        self.corrupt_packs.is_empty() && self.unknown_sizes == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(kind: &str, path: &str, reason: &str) -> ScanDiagnostic {
+        ScanDiagnostic { oid: "00".repeat(20), kind: kind.to_string(), path: path.to_string(), reason: reason.to_string() }
+    }
+
+    #[test]
+    fn test_pack_health_from_diagnostics() {
+        let diagnostics = [
+            diagnostic("commit", "", "object not found"),
+            diagnostic("pack", "pack-ab.idx", "truncated: 2 of 9 objects are incomplete"),
+            diagnostic("size", "a.bin", "not in any readable pack or loose object"),
+            diagnostic("size", "b.bin", "not in any readable pack or loose object"),
+        ];
+        let health = PackHealth::from_diagnostics(&diagnostics);
+        assert_eq!(health.corrupt_packs, vec!["pack-ab.idx: truncated: 2 of 9 objects are incomplete"]);
+        assert_eq!(health.unknown_sizes, 2);
+        assert!(!health.is_healthy());
+        assert_eq!(diagnostics.iter().filter(|d| d.is_skipped_object()).count(), 1);
+
+        assert!(PackHealth::from_diagnostics(&diagnostics[..1]).is_healthy());
+    }
+}
@@ -0,0 +1,209 @@
+/// A cell of an exported list
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportValue {
+    Text(String),
+    /// Sizes, counts and Unix timestamps, in full rather than formatted
+    Number(i64),
+    Flag(bool),
+    /// Nothing to tell, e.g. a blob in no group: blank in CSV, null in JSON
+    Empty,
+}
+
+impl From<&str> for ExportValue {
+    fn from(text: &str) -> Self {
+        ExportValue::Text(text.to_string())
+    }
+}
+
+impl From<String> for ExportValue {
+    fn from(text: String) -> Self {
+        ExportValue::Text(text)
+    }
+}
+
+impl From<u64> for ExportValue {
+    fn from(number: u64) -> Self {
+        ExportValue::Number(i64::try_from(number).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<i64> for ExportValue {
+    fn from(number: i64) -> Self {
+        ExportValue::Number(number)
+    }
+}
+
+impl From<bool> for ExportValue {
+    fn from(flag: bool) -> Self {
+        ExportValue::Flag(flag)
+    }
+}
+
+/// How an exported list is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// Tab-separated, with tabs and line breaks in text escaped
+    Tsv,
+    /// An array of objects, keys in column order
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// The rows a view lists, with its filters applied, as a table to write
+/// out and share
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListExport {
+    /// What the file is named after, e.g. "extensions"
+    pub name: String,
+    pub columns: Vec<&'static str>,
+    /// One cell per column each
+    pub rows: Vec<Vec<ExportValue>>,
+}
+
+impl ListExport {
+    pub fn new(name: impl Into<String>, columns: Vec<&'static str>) -> Self {
+        Self { name: name.into(), columns, rows: Vec::new() }
+    }
+
+    pub fn push(&mut self, row: Vec<ExportValue>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub fn render(&self, format: ExportFormat) -> String {
+        match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Tsv => self.to_tsv(),
+            ExportFormat::Json => self.to_json(),
+        }
+    }
+
+    /// A header line, then a line per row
+    pub fn to_csv(&self) -> String {
+        self.to_lines(',', csv_field)
+    }
+
+    /// A header line, then a line per row, so `cut -f` can pick columns
+    pub fn to_tsv(&self) -> String {
+        self.to_lines('\t', tsv_field)
+    }
+
+    fn to_lines(&self, separator: char, text_field: fn(&str) -> String) -> String {
+        let mut lines = self.columns.join(&separator.to_string()) + "\n";
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| match cell {
+                    ExportValue::Text(text) => text_field(text),
+                    ExportValue::Number(number) => number.to_string(),
+                    ExportValue::Flag(flag) => flag.to_string(),
+                    ExportValue::Empty => String::new(),
+                })
+                .collect();
+            lines += &cells.join(&separator.to_string());
+            lines.push('\n');
+        }
+        lines
+    }
+
+    /// An array with an object per row
+    pub fn to_json(&self) -> String {
+        let rows: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = self
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, cell)| {
+                        let value = match cell {
+                            ExportValue::Text(text) => serde_json::Value::from(text.as_str()),
+                            ExportValue::Number(number) => serde_json::Value::from(*number),
+                            ExportValue::Flag(flag) => serde_json::Value::from(*flag),
+                            ExportValue::Empty => serde_json::Value::Null,
+                        };
+                        format!("{}: {}", serde_json::Value::from(*column), value)
+                    })
+                    .collect();
+                format!("  {{{}}}", fields.join(", "))
+            })
+            .collect();
+        if rows.is_empty() {
+            return "[]\n".to_string();
+        }
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
+}
+
+/// `field` quoted when it holds a comma, quote or line break
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `field` with backslashes, tabs and line breaks written as `\\`, `\t`,
+/// `\n` and `\r`
+fn tsv_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> ListExport {
+        let mut export = ListExport::new("search", vec!["path", "cumulative_size", "in_head"]);
+        export.push(vec!["assets/a,b.png".into(), 5000u64.into(), false.into()]);
+        export.push(vec!["src/\"main\".rs".into(), 1000u64.into(), true.into()]);
+        export
+    }
+
+    #[test]
+    fn test_csv_quotes_text_only_when_needed() {
+        assert_eq!(
+            export().to_csv(),
+            "path,cumulative_size,in_head\n\"assets/a,b.png\",5000,false\n\"src/\"\"main\"\".rs\",1000,true\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv_escapes_tabs_and_line_breaks() {
+        let mut export = ListExport::new("sizes", vec!["path", "found", "cumulative_size"]);
+        export.push(vec!["docs/a\tb.md".into(), true.into(), 300u64.into()]);
+        export.push(vec!["C:\\old".into(), false.into(), ExportValue::Empty]);
+        assert_eq!(export.to_tsv(), "path\tfound\tcumulative_size\ndocs/a\\tb.md\ttrue\t300\nC:\\\\old\tfalse\t\n");
+    }
+
+    #[test]
+    fn test_json_keeps_column_order_and_types() {
+        let json = export().to_json();
+        assert!(json.starts_with("[\n  {\"path\": \"assets/a,b.png\", \"cumulative_size\": 5000, \"in_head\": false},\n"));
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[1]["path"], "src/\"main\".rs");
+        assert_eq!(ListExport::new("empty", vec!["path"]).to_json(), "[]\n");
+    }
+}
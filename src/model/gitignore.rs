@@ -0,0 +1,107 @@
+use super::ExtensionStats;
+
+/// Directory names that hold build output, dependencies or tool caches
+pub const GENERATED_DIRS: &[&str] = &[
+    "build", "dist", "out", "target", "bin", "obj", "node_modules", "bower_components",
+    "__pycache__", ".venv", "venv", ".tox", ".pytest_cache", ".mypy_cache", ".gradle",
+    ".next", ".nuxt", ".cache", "coverage", ".nyc_output", "DerivedData", ".terraform",
+];
+
+/// File kinds that are logs, editor leftovers or compiler output, as
+/// (`util::extension_label`, .gitignore pattern)
+const GENERATED_FILES: &[(&str, &str)] = &[
+    (".log", "*.log"),
+    (".tmp", "*.tmp"),
+    (".swp", "*.swp"),
+    (".bak", "*.bak"),
+    (".orig", "*.orig"),
+    (".pyc", "*.pyc"),
+    (".class", "*.class"),
+    (".o", "*.o"),
+    (".pdb", "*.pdb"),
+    (".ds_store", ".DS_Store"),
+];
+
+/// A `.gitignore` entry and what it would have kept out of history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitignoreSuggestion {
+    pub pattern: String,
+    /// Cumulative size of everything the pattern matches
+    pub prevented: u64,
+    /// Of that, what is still in HEAD (and would need `git rm --cached`)
+    pub current: u64,
+    /// Of that, what was committed and later deleted
+    pub deleted: u64,
+}
+
+/// Suggested entries, most history prevented first
+///
+/// `dirs` are the directories named like `GENERATED_DIRS`, as (path,
+/// cumulative, current, deleted) in path order (`Database::get_dirs_named`);
+/// one inside another is counted once. `extensions` are the per-extension
+/// totals. A `*.log` file inside `build/` counts toward both patterns.
+pub fn suggest_gitignore(
+    dirs: &[(String, u64, u64, u64)],
+    extensions: &[(String, ExtensionStats)],
+) -> Vec<GitignoreSuggestion> {
+    let mut suggestions: Vec<GitignoreSuggestion> = Vec::new();
+    let mut counted: Vec<&str> = Vec::new();
+    for (path, cumulative, current, deleted) in dirs {
+        if counted.iter().any(|c| path.strip_prefix(c).is_some_and(|rest| rest.starts_with('/'))) {
+            continue;
+        }
+        counted.push(path);
+        let name = path.rsplit_once('/').map_or(path.as_str(), |(_, name)| name);
+        let pattern = format!("{}/", name);
+        let suggestion = match suggestions.iter_mut().find(|s| s.pattern == pattern) {
+            Some(existing) => existing,
+            None => {
+                suggestions.push(GitignoreSuggestion { pattern, prevented: 0, current: 0, deleted: 0 });
+                suggestions.last_mut().expect("pushed above")
+            }
+        };
+        suggestion.prevented += cumulative;
+        suggestion.current += current;
+        suggestion.deleted += deleted;
+    }
+
+    for (extension, stats) in extensions {
+        if let Some((_, pattern)) = GENERATED_FILES.iter().find(|(e, _)| e == extension) {
+            suggestions.push(GitignoreSuggestion {
+                pattern: pattern.to_string(),
+                prevented: stats.cumulative_size,
+                current: stats.current_size,
+                deleted: stats.deleted_size,
+            });
+        }
+    }
+
+    suggestions.retain(|s| s.prevented > 0);
+    suggestions.sort_by(|a, b| b.prevented.cmp(&a.prevented).then_with(|| a.pattern.cmp(&b.pattern)));
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions_merge_dirs_and_skip_nested_ones() {
+        let dirs = vec![
+            ("app/build".to_string(), 300, 0, 300),
+            ("app/build-tools/dist".to_string(), 10, 10, 0),
+            ("app/build/out".to_string(), 100, 0, 100),
+            ("build".to_string(), 200, 50, 150),
+            ("web/node_modules".to_string(), 0, 0, 0),
+        ];
+        let log = ExtensionStats { cumulative_size: 250, current_size: 0, deleted_size: 250, file_count: 40, binary_size: 0 };
+        let rs = ExtensionStats { cumulative_size: 900, current_size: 900, deleted_size: 0, file_count: 3, binary_size: 0 };
+        let extensions = vec![(".log".to_string(), log), (".rs".to_string(), rs)];
+
+        assert_eq!(suggest_gitignore(&dirs, &extensions), vec![
+            GitignoreSuggestion { pattern: "build/".into(), prevented: 500, current: 50, deleted: 450 },
+            GitignoreSuggestion { pattern: "*.log".into(), prevented: 250, current: 0, deleted: 250 },
+            GitignoreSuggestion { pattern: "dist/".into(), prevented: 10, current: 10, deleted: 0 },
+        ]);
+    }
+}
@@ -0,0 +1,47 @@
+/// Running cumulative size of a path, one value per calendar quarter
+///
+/// Quarters are numbered as in `util::quarter_of`; `values[i]` is the total
+/// at the end of quarter `start + i`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeHistory {
+    pub start: i32,
+    pub values: Vec<u64>,
+}
+
+impl SizeHistory {
+    /// Accumulate per-quarter additions over `first..=last`
+    ///
+    /// `added` holds (quarter, bytes added) pairs in any order; quarters
+    /// outside the range are clamped into it.
+    pub fn from_additions(added: &[(i32, u64)], first: i32, last: i32) -> Self {
+        let len = (last - first + 1).max(1) as usize;
+        let mut values = vec![0u64; len];
+        for &(quarter, size) in added {
+            let i = (quarter.clamp(first, first + len as i32 - 1) - first) as usize;
+            values[i] += size;
+        }
+        let mut total = 0;
+        for value in &mut values {
+            total += *value;
+            *value = total;
+        }
+        Self { start: first, values }
+    }
+
+    /// Quarter of the last value
+    pub fn end(&self) -> i32 {
+        self.start + self.values.len() as i32 - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_additions_is_cumulative() {
+        let history = SizeHistory::from_additions(&[(8003, 50), (8000, 100), (8003, 25)], 8000, 8004);
+        assert_eq!(history.values, vec![100, 100, 100, 175, 175]);
+        assert_eq!(history.end(), 8004);
+    }
+}
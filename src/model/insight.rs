@@ -0,0 +1,259 @@
+use crate::util::{extension_label, format_size};
+
+use super::lfs::LFS_EXTENSIONS;
+use super::{DeltaEfficiency, GitDirUsage, GitignoreSuggestion};
+
+/// Loose objects at which `git gc --auto` would pack them (`gc.auto`)
+pub const LOOSE_OBJECT_LIMIT: u64 = 6700;
+/// Packs at which `git gc --auto` would consolidate them (`gc.autoPackLimit`)
+pub const PACK_LIMIT: u64 = 50;
+/// Share of the history a path or extension must hold to be worth a rewrite
+const MIN_SHARE: f64 = 0.05;
+/// Savings below this aren't worth rewriting history for
+const MIN_REWRITE_SAVINGS: u64 = 1024 * 1024;
+
+/// One concrete step toward a smaller repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Insight {
+    /// What to do, e.g. "Enable LFS for *.psd"
+    pub title: String,
+    /// The numbers that call for it
+    pub reason: String,
+    /// Command that carries it out
+    pub command: String,
+    /// Estimated bytes saved; `None` when there is nothing to base it on
+    pub savings: Option<u64>,
+}
+
+/// What the recommendations are drawn from
+#[derive(Debug, Clone, Default)]
+pub struct InsightInputs<'a> {
+    /// Unknown for an index opened without its repository
+    pub git_dir: Option<&'a GitDirUsage>,
+    /// Cumulative size of all history
+    pub cumulative_size: u64,
+    /// Top-level entries as (name, current size, deleted size)
+    pub top_level: Vec<(&'a str, u64, u64)>,
+    /// File extensions (`util::extension_label`) with their cumulative size
+    pub extensions: Vec<(&'a str, u64)>,
+    /// From `suggest_gitignore`
+    pub gitignore: &'a [GitignoreSuggestion],
+    /// From `Database::get_delta_efficiency`
+    pub delta: &'a [DeltaEfficiency],
+}
+
+/// Recommendations for `inputs`, largest estimated savings first
+pub fn recommend(inputs: &InsightInputs<'_>) -> Vec<Insight> {
+    let mut insights = Vec::new();
+    if let Some(usage) = inputs.git_dir {
+        recommend_housekeeping(usage, &mut insights);
+    }
+    recommend_rewrites(inputs, &mut insights);
+    recommend_ignores(inputs.gitignore, &mut insights);
+    // Estimated savings first, then the ones without an estimate
+    insights.sort_by_key(|i| std::cmp::Reverse(i.savings.map(|s| s + 1).unwrap_or(0)));
+    insights
+}
+
+/// `git gc` and friends: nothing in history changes
+fn recommend_housekeeping(usage: &GitDirUsage, insights: &mut Vec<Insight>) {
+    if usage.loose_count >= LOOSE_OBJECT_LIMIT {
+        insights.push(Insight {
+            title: "Pack loose objects".to_string(),
+            reason: format!(
+                "{} loose objects take {}; packing deltas them against each other",
+                usage.loose_count,
+                format_size(usage.loose_objects)
+            ),
+            command: "git gc".to_string(),
+            // Loose objects are compressed one by one; packed, roughly half goes
+            savings: Some(usage.loose_objects / 2),
+        });
+    }
+    if usage.pack_count >= PACK_LIMIT {
+        insights.push(Insight {
+            title: format!("Consolidate {} packs", usage.pack_count),
+            reason: format!(
+                "objects spread over {} packs can't be deltified against each other",
+                usage.pack_count
+            ),
+            command: "git gc --aggressive".to_string(),
+            savings: None,
+        });
+    }
+    if usage.cruft_packs > 0 {
+        insights.push(Insight {
+            title: "Drop unreachable objects".to_string(),
+            reason: format!("cruft packs keep {} of objects no ref reaches", format_size(usage.cruft_packs)),
+            command: "git reflog expire --expire-unreachable=now --all && git gc --prune=now".to_string(),
+            savings: Some(usage.cruft_packs),
+        });
+    }
+}
+
+/// History rewrites: removing deleted directories, moving binaries to LFS
+fn recommend_rewrites(inputs: &InsightInputs<'_>, insights: &mut Vec<Insight>) {
+    let total = inputs.cumulative_size;
+    let worth_it = |size: u64| size >= MIN_REWRITE_SAVINGS && size as f64 >= total as f64 * MIN_SHARE;
+    let percent = |size: u64| size as f64 * 100.0 / total as f64;
+
+    for &(name, current, deleted) in &inputs.top_level {
+        if !worth_it(deleted) {
+            continue;
+        }
+        // Only a directory gone from HEAD can be dropped as a whole
+        let (title, command) = if current == 0 {
+            (
+                format!("{:.0}% reclaimable via filter-repo on /{}", percent(deleted), name),
+                format!("git filter-repo --invert-paths --path {}", name),
+            )
+        } else {
+            (
+                format!("{:.0}% reclaimable via filter-repo on deleted files in /{}", percent(deleted), name),
+                "git filter-repo --invert-paths --paths-from-file deleted-paths.txt".to_string(),
+            )
+        };
+        insights.push(Insight {
+            title,
+            reason: format!("{} of /{} is no longer in HEAD", format_size(deleted), name),
+            command,
+            savings: Some(deleted),
+        });
+    }
+
+    let mut lfs_extensions = Vec::new();
+    for &(extension, cumulative) in &inputs.extensions {
+        if !LFS_EXTENSIONS.contains(&extension) || !worth_it(cumulative) {
+            continue;
+        }
+        lfs_extensions.push(extension);
+        insights.push(Insight {
+            title: format!("Enable LFS for *{}", extension),
+            reason: format!(
+                "*{} files make up {:.0}% of history ({})",
+                extension,
+                percent(cumulative),
+                format_size(cumulative)
+            ),
+            command: format!("git lfs migrate import --everything --include=\"*{}\"", extension),
+            savings: Some(cumulative),
+        });
+    }
+
+    // Single files whatever their extension, unless LFS already takes it
+    for delta in inputs.delta {
+        let name = delta.path.rsplit_once('/').map_or(delta.path.as_str(), |(_, name)| name);
+        if !delta.is_delta_hostile()
+            || delta.stored < MIN_REWRITE_SAVINGS
+            || lfs_extensions.contains(&extension_label(name).as_ref())
+        {
+            continue;
+        }
+        insights.push(Insight {
+            title: format!("Move {} to LFS: its versions don't delta", delta.path),
+            reason: delta.summary(),
+            command: format!("git lfs migrate import --everything --include=\"{}\"", delta.path),
+            savings: Some(delta.stored),
+        });
+    }
+}
+
+/// `.gitignore` entries: nothing is reclaimed, history stops growing
+fn recommend_ignores(suggestions: &[GitignoreSuggestion], insights: &mut Vec<Insight>) {
+    for suggestion in suggestions {
+        let mut reason = format!("would have kept {} out of history", format_size(suggestion.prevented));
+        if suggestion.current > 0 {
+            reason.push_str(&format!("; {} is still tracked", format_size(suggestion.current)));
+        }
+        insights.push(Insight {
+            title: format!("Add {} to .gitignore", suggestion.pattern),
+            reason,
+            command: format!("echo '{}' >> .gitignore", suggestion.pattern),
+            savings: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_housekeeping_thresholds() {
+        let usage = GitDirUsage {
+            loose_objects: 80 * MB,
+            loose_count: LOOSE_OBJECT_LIMIT,
+            pack_count: PACK_LIMIT - 1,
+            cruft_packs: 100 * MB,
+            ..GitDirUsage::default()
+        };
+        let insights = recommend(&InsightInputs { git_dir: Some(&usage), ..InsightInputs::default() });
+        let commands: Vec<_> = insights.iter().map(|i| i.command.as_str()).collect();
+        assert_eq!(commands, [
+            "git reflog expire --expire-unreachable=now --all && git gc --prune=now",
+            "git gc",
+        ]);
+        assert_eq!(insights[1].savings, Some(40 * MB));
+
+        let usage = GitDirUsage { pack_count: PACK_LIMIT, ..GitDirUsage::default() };
+        let insights = recommend(&InsightInputs { git_dir: Some(&usage), ..InsightInputs::default() });
+        assert_eq!(insights.len(), 1);
+        assert_eq!(insights[0].command, "git gc --aggressive");
+        assert_eq!(insights[0].savings, None);
+    }
+
+    #[test]
+    fn test_rewrites_for_deleted_dirs_and_binaries() {
+        let inputs = InsightInputs {
+            git_dir: None,
+            cumulative_size: 100 * MB,
+            top_level: vec![("assets", 0, 26 * MB), ("src", 10 * MB, 4 * MB), ("docs", 5 * MB, 6 * MB)],
+            extensions: vec![(".psd", 30 * MB), (".rs", 40 * MB), (".png", 2 * MB)],
+            gitignore: &[],
+            delta: &[],
+        };
+        let insights = recommend(&inputs);
+        let titles: Vec<_> = insights.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, [
+            "Enable LFS for *.psd",
+            "26% reclaimable via filter-repo on /assets",
+            "6% reclaimable via filter-repo on deleted files in /docs",
+        ]);
+        assert_eq!(insights[1].command, "git filter-repo --invert-paths --path assets");
+        assert_eq!(insights[1].savings, Some(26 * MB));
+    }
+
+    #[test]
+    fn test_delta_hostile_files() {
+        let delta = |path: &str, versions, stored, largest| DeltaEfficiency { path: path.into(), versions, stored, largest };
+        let delta = [
+            delta("secrets/vault.gpg", 5, 9 * MB, 2 * MB),
+            delta("design/logo.psd", 4, 8 * MB, 2 * MB),
+            delta("data/schema.sql", 20, 3 * MB, 2 * MB),
+            delta("tiny.enc", 5, MB / 2, MB / 8),
+        ];
+        let inputs = InsightInputs {
+            cumulative_size: 100 * MB,
+            extensions: vec![(".psd", 30 * MB)],
+            delta: &delta,
+            ..InsightInputs::default()
+        };
+        let insights = recommend(&inputs);
+        let titles: Vec<_> = insights.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, ["Enable LFS for *.psd", "Move secrets/vault.gpg to LFS: its versions don't delta"]);
+        assert_eq!(insights[1].reason, "5 versions, 9.0 MB stored at 90% of full size each");
+        assert_eq!(insights[1].savings, Some(9 * MB));
+    }
+
+    #[test]
+    fn test_gitignore_suggestions_come_last() {
+        let usage = GitDirUsage { cruft_packs: MB, ..GitDirUsage::default() };
+        let gitignore = [GitignoreSuggestion { pattern: "build/".into(), prevented: 50 * MB, current: MB, deleted: 0 }];
+        let insights = recommend(&InsightInputs { git_dir: Some(&usage), gitignore: &gitignore, ..InsightInputs::default() });
+        assert_eq!(insights.len(), 2);
+        assert_eq!(insights[1].title, "Add build/ to .gitignore");
+        assert_eq!(insights[1].reason, "would have kept 50.0 MB out of history; 1.0 MB is still tracked");
+    }
+}
@@ -0,0 +1,145 @@
+use crate::util::extension_label;
+
+use super::LargeBlobInfo;
+
+/// Smallest history a pattern must hold to be planned by default
+pub const DEFAULT_LFS_MIN_SIZE: u64 = 10 * 1024 * 1024;
+/// Size of the pointer file LFS leaves in history for each version
+const POINTER_SIZE: u64 = 132;
+
+/// Extensions of binary formats that don't diff or delta well, and belong
+/// in Git LFS once they pile up
+pub(crate) const LFS_EXTENSIONS: &[&str] = &[
+    ".psd", ".psb", ".ai", ".sketch", ".fig", ".xcf", ".blend", ".fbx", ".obj", ".unitypackage",
+    ".png", ".jpg", ".jpeg", ".gif", ".tif", ".tiff", ".bmp", ".webp", ".ico",
+    ".mp3", ".wav", ".flac", ".ogg", ".mp4", ".mov", ".avi", ".mkv", ".webm",
+    ".zip", ".tar", ".gz", ".tgz", ".bz2", ".xz", ".7z", ".rar", ".jar", ".war", ".apk", ".ipa",
+    ".exe", ".dll", ".so", ".dylib", ".a", ".lib", ".bin", ".iso", ".dmg", ".msi",
+    ".pdf", ".doc", ".docx", ".xls", ".xlsx", ".ppt", ".pptx",
+    ".ttf", ".otf", ".woff", ".woff2", ".sqlite", ".db", ".h5", ".onnx", ".pt", ".pkl",
+];
+
+/// One `--include` pattern of an LFS migration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LfsPattern {
+    /// `*.psd` for an extension, or a single path
+    pub pattern: String,
+    /// Cumulative size of what the pattern matches
+    pub size: u64,
+    /// Versions that become pointers
+    pub files: u64,
+}
+
+impl LfsPattern {
+    /// Bytes leaving history: the content, less the pointers left behind
+    pub fn savings(&self) -> u64 {
+        self.size.saturating_sub(self.files * POINTER_SIZE)
+    }
+}
+
+/// Patterns to move to Git LFS, largest first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LfsPlan {
+    pub patterns: Vec<LfsPattern>,
+    /// The threshold the plan was made with
+    pub min_size: u64,
+}
+
+impl LfsPlan {
+    /// Plan binary extensions whose history holds at least `min_size`, then
+    /// single paths not covered by them with a blob of at least `min_size`
+    ///
+    /// `extensions` are (`util::extension_label`, cumulative size, file
+    /// count); `blobs` are the largest blobs of the index.
+    pub fn new(extensions: &[(&str, u64, u64)], blobs: &[LargeBlobInfo], min_size: u64) -> Self {
+        let mut patterns: Vec<LfsPattern> = extensions
+            .iter()
+            .filter(|(extension, size, _)| *size >= min_size && LFS_EXTENSIONS.contains(extension))
+            .map(|&(extension, size, files)| LfsPattern { pattern: format!("*{}", extension), size, files })
+            .collect();
+
+        let mut paths: Vec<LfsPattern> = Vec::new();
+        for blob in blobs.iter().filter(|b| b.size >= min_size) {
+            let name = blob.path.rsplit_once('/').map_or(blob.path.as_str(), |(_, name)| name);
+            let covered = patterns.iter().any(|p| p.pattern == format!("*{}", extension_label(name)));
+            // Commas separate `--include` patterns and quotes end the argument
+            if covered || blob.path.contains([',', '"']) {
+                continue;
+            }
+            match paths.iter_mut().find(|p| p.pattern == blob.path) {
+                Some(path) => {
+                    path.size += blob.size;
+                    path.files += 1;
+                }
+                None => paths.push(LfsPattern { pattern: blob.path.clone(), size: blob.size, files: 1 }),
+            }
+        }
+
+        patterns.extend(paths);
+        patterns.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.pattern.cmp(&b.pattern)));
+        Self { patterns, min_size }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn savings(&self) -> u64 {
+        self.patterns.iter().map(LfsPattern::savings).sum()
+    }
+
+    /// The `git lfs migrate` command rewriting all branches and tags
+    pub fn command(&self) -> String {
+        let include: Vec<&str> = self.patterns.iter().map(|p| p.pattern.as_str()).collect();
+        format!("git lfs migrate import --everything --include=\"{}\"", include.join(","))
+    }
+
+    /// The `.gitattributes` lines `git lfs track` would add for the plan
+    pub fn gitattributes(&self) -> String {
+        self.patterns
+            .iter()
+            .map(|p| format!("{} filter=lfs diff=lfs merge=lfs -text\n", p.pattern.replace(' ', "[[:space:]]")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn blob(path: &str, size: u64) -> LargeBlobInfo {
+        LargeBlobInfo {
+            oid: vec![0; 20],
+            size,
+            path: path.to_string(),
+            first_author: String::new(),
+            first_email: String::new(),
+            first_date: 0,
+            first_subject: String::new(),
+            in_head: true,
+        }
+    }
+
+    #[test]
+    fn test_plan_covers_extensions_then_paths() {
+        let extensions = [(".psd", 40 * MB, 8), (".rs", 90 * MB, 500), (".zip", 5 * MB, 1), (".csv", 30 * MB, 3)];
+        let blobs = [
+            blob("art/cover.psd", 20 * MB),
+            blob("data/dump.csv", 15 * MB),
+            blob("data/dump.csv", 12 * MB),
+            blob("data/small.csv", 3 * MB),
+        ];
+        let plan = LfsPlan::new(&extensions, &blobs, 10 * MB);
+
+        let patterns: Vec<_> = plan.patterns.iter().map(|p| (p.pattern.as_str(), p.size, p.files)).collect();
+        assert_eq!(patterns, [("*.psd", 40 * MB, 8), ("data/dump.csv", 27 * MB, 2)]);
+        assert_eq!(plan.savings(), 67 * MB - 10 * POINTER_SIZE);
+        assert_eq!(plan.command(), "git lfs migrate import --everything --include=\"*.psd,data/dump.csv\"");
+        assert_eq!(
+            plan.gitattributes(),
+            "*.psd filter=lfs diff=lfs merge=lfs -text\ndata/dump.csv filter=lfs diff=lfs merge=lfs -text\n"
+        );
+    }
+}
@@ -1,5 +1,47 @@
 mod tree;
 mod blob;
+mod storage;
+mod bloat;
+mod history;
+mod details;
+mod diagnostic;
+mod insight;
+mod gitignore;
+mod lfs;
+mod delta;
+mod content;
+mod similarity;
+mod author;
+mod owners;
+mod snapshot;
+mod preview;
+mod session;
+mod vendored;
+mod commit;
+mod search_query;
+mod export;
+mod age;
 
-pub use tree::TreeNode;
-pub use blob::{LargeBlobInfo, ExtensionStats};
\ No newline at end of file
+pub use tree::{Descendant, Descendants, DirEntry, EntryKind, NodeId, NodeRef, SubtreeStats, Tree, TreeNode};
+pub use blob::{AddedBlob, BlobFilter, LargeBlobInfo, ExtensionStats, BlobLookup, BlobOccurrence, DuplicateBlob, RecoveryCommands, RestorePlan};
+pub use storage::{GitDirUsage, RemoteSize, StorageHint};
+pub use bloat::{BloatLevel, BloatThresholds};
+pub use history::SizeHistory;
+pub use details::{EntryDetails, DETAIL_ROWS};
+pub use diagnostic::{PackHealth, ScanDiagnostic};
+pub use insight::{recommend, Insight, InsightInputs};
+pub use gitignore::{suggest_gitignore, GitignoreSuggestion, GENERATED_DIRS};
+pub use lfs::{LfsPattern, LfsPlan, DEFAULT_LFS_MIN_SIZE};
+pub use delta::{DeltaEfficiency, DELTA_HOSTILE_RATIO};
+pub use similarity::{cluster_near_duplicates, Fingerprint};
+pub use content::{looks_binary, majority_binary, SNIFF_LEN};
+pub use author::{email_domain, AuthorGrouping, AuthorTotals};
+pub use owners::{CodeOwners, OwnedFile, OwnerTotals, UNOWNED};
+pub use snapshot::{Growth, GrowthLimits, SizeSnapshot, SnapshotEntry, SnapshotEntryKind, SNAPSHOT_DIR_DEPTH};
+pub use preview::{BlobPreview, PreviewContent, PREVIEW_BYTES};
+pub use session::{SessionEvent, SessionLog};
+pub use vendored::{vendored_rule, VendoredShare, VendoredSizes, LOCKFILE_RULE};
+pub use commit::HeavyCommit;
+pub use search_query::SearchQuery;
+pub use export::{csv_field, ExportFormat, ExportValue, ListExport};
+pub use age::{old_cutoff, OldSizes, DEFAULT_OLD_AFTER_YEARS};
@@ -0,0 +1,140 @@
+use crate::util::Glob;
+
+/// What files no CODEOWNERS rule assigns are credited to
+pub const UNOWNED: &str = "(unowned)";
+
+/// A parsed CODEOWNERS file: the last rule matching a path owns it
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<(Glob, Vec<String>)>,
+    /// 1-based numbers of lines that aren't valid rules (e.g. `!negation`
+    /// or `[ranges]`, which GitHub doesn't support either)
+    pub invalid_lines: Vec<usize>,
+}
+
+impl CodeOwners {
+    pub fn parse(text: &str) -> Self {
+        let mut owners = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split_once(" #").map_or(line, |(rule, _)| rule).trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next().expect("line is not empty");
+            let glob = (!pattern.starts_with('!') && !pattern.contains('['))
+                .then(|| Glob::new(pattern).ok())
+                .flatten();
+            match glob {
+                Some(glob) => owners.rules.push((glob, fields.map(str::to_string).collect())),
+                None => owners.invalid_lines.push(number + 1),
+            }
+        }
+        owners
+    }
+
+    /// Owners of the file at `path`; none when no rule matches or the last
+    /// match lists no owners
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(glob, _)| glob.matches(path))
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+
+    /// Size totals per owner of `files`, given as (path, cumulative size,
+    /// current size), largest cumulative size first
+    ///
+    /// A file with several owners counts in full for each of them, as each
+    /// is answerable for it; files nobody owns are credited to `UNOWNED`.
+    pub fn tally(&self, files: Vec<(String, u64, u64)>) -> Vec<OwnerTotals> {
+        let mut totals: Vec<OwnerTotals> = Vec::new();
+        let mut index = rustc_hash::FxHashMap::default();
+        for (path, cumulative_size, current_size) in files {
+            let owners = self.owners_of(&path);
+            let names: Vec<&str> = if owners.is_empty() {
+                vec![UNOWNED]
+            } else {
+                owners.iter().map(String::as_str).collect()
+            };
+            for name in names {
+                let slot = *index.entry(name.to_string()).or_insert_with(|| {
+                    totals.push(OwnerTotals { owner: name.to_string(), ..Default::default() });
+                    totals.len() - 1
+                });
+                let entry = &mut totals[slot];
+                entry.cumulative_size += cumulative_size;
+                entry.current_size += current_size;
+                entry.files.push(OwnedFile { path: path.clone(), cumulative_size, current_size });
+            }
+        }
+        for entry in &mut totals {
+            entry.files.sort_by(|a, b| b.cumulative_size.cmp(&a.cumulative_size).then_with(|| a.path.cmp(&b.path)));
+        }
+        totals.sort_by(|a, b| b.cumulative_size.cmp(&a.cumulative_size).then_with(|| a.owner.cmp(&b.owner)));
+        totals
+    }
+}
+
+/// Files one owner (a team, user or email) is answerable for
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OwnerTotals {
+    pub owner: String,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+    /// Largest cumulative size first
+    pub files: Vec<OwnedFile>,
+}
+
+/// A file in an owner's bill
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedFile {
+    pub path: String,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODEOWNERS: &str = "\
+# Default owners
+*       @org/platform
+*.js    @org/web @alice   # inline comment
+/docs/  docs@example.com
+/docs/generated/
+!vendor @org/nobody
+";
+
+    #[test]
+    fn test_last_match_wins() {
+        let owners = CodeOwners::parse(CODEOWNERS);
+        assert_eq!(owners.owners_of("src/main.rs"), ["@org/platform"]);
+        assert_eq!(owners.owners_of("web/app.js"), ["@org/web", "@alice"]);
+        assert_eq!(owners.owners_of("docs/app.js"), ["docs@example.com"]);
+        // A rule without owners leaves its paths unowned
+        assert!(owners.owners_of("docs/generated/api.md").is_empty());
+        assert_eq!(owners.invalid_lines, [6]);
+    }
+
+    #[test]
+    fn test_tally() {
+        let owners = CodeOwners::parse(CODEOWNERS);
+        let totals = owners.tally(vec![
+            ("web/app.js".to_string(), 300, 100),
+            ("src/main.rs".to_string(), 200, 200),
+            ("src/big.bin".to_string(), 900, 0),
+            ("docs/generated/api.md".to_string(), 50, 50),
+        ]);
+        let summary: Vec<(&str, u64, usize)> =
+            totals.iter().map(|t| (t.owner.as_str(), t.cumulative_size, t.files.len())).collect();
+        assert_eq!(
+            summary,
+            [("@org/platform", 1100, 2), ("@alice", 300, 1), ("@org/web", 300, 1), (UNOWNED, 50, 1)]
+        );
+        assert_eq!(totals[0].files[0].path, "src/big.bin");
+        assert_eq!(totals[0].current_size, 200);
+    }
+}
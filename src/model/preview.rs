@@ -0,0 +1,142 @@
+use super::content::looks_binary;
+
+/// Leading bytes of a blob a preview shows at most
+pub const PREVIEW_BYTES: usize = 4096;
+/// Lines of text a preview shows at most
+const PREVIEW_LINES: usize = 40;
+/// Bytes per hexdump line
+const HEX_WIDTH: usize = 16;
+/// Hexdump lines of a binary preview
+const HEX_LINES: usize = 16;
+
+/// The first lines or bytes of a blob, to tell what it is without leaving
+/// the TUI
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlobPreview {
+    /// Path the blob was opened from
+    pub path: String,
+    /// Hex OID
+    pub oid: String,
+    /// Size of the whole blob
+    pub size: u64,
+    pub content: PreviewContent,
+    /// Whether there is more to the blob than the preview shows
+    pub truncated: bool,
+}
+
+/// What a preview shows of a blob
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreviewContent {
+    /// The first lines, as (lossy) UTF-8 with tabs expanded
+    Text(Vec<String>),
+    /// A hexdump of the first bytes, with what the leading bytes say the
+    /// file is when they are recognized
+    Binary { kind: Option<&'static str>, lines: Vec<String> },
+}
+
+impl BlobPreview {
+    /// Preview of a blob of `size` bytes that starts with `head`
+    pub fn new(path: &str, oid: &str, size: u64, head: &[u8]) -> Self {
+        let head = &head[..head.len().min(PREVIEW_BYTES)];
+        let (content, truncated) = if looks_binary(head) {
+            let shown = head.len().min(HEX_WIDTH * HEX_LINES);
+            let lines = head[..shown].chunks(HEX_WIDTH).enumerate().map(|(i, row)| hex_line(i * HEX_WIDTH, row)).collect();
+            (PreviewContent::Binary { kind: file_kind(head), lines }, (shown as u64) < size)
+        } else {
+            let complete = head.len() as u64 >= size;
+            let text = String::from_utf8_lossy(head);
+            let mut lines: Vec<&str> = text.lines().collect();
+            // The read may have stopped in the middle of the last line
+            if !complete && !text.ends_with('\n') && lines.len() > 1 {
+                lines.pop();
+            }
+            let truncated = !complete || lines.len() > PREVIEW_LINES;
+            let lines = lines.iter().take(PREVIEW_LINES).map(|line| line.replace('\t', "    ")).collect();
+            (PreviewContent::Text(lines), truncated)
+        };
+        Self { path: path.to_string(), oid: oid.to_string(), size, content, truncated }
+    }
+}
+
+/// `offset  xx xx ... xx  |ascii|`, as `hexdump -C` prints it
+fn hex_line(offset: usize, row: &[u8]) -> String {
+    let mut hex = String::with_capacity(HEX_WIDTH * 3 + 1);
+    for i in 0..HEX_WIDTH {
+        if i == HEX_WIDTH / 2 {
+            hex.push(' ');
+        }
+        match row.get(i) {
+            Some(byte) => hex += &format!("{:02x} ", byte),
+            None => hex += "   ",
+        }
+    }
+    let ascii: String = row.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+    format!("{:08x}  {} |{}|", offset, hex, ascii)
+}
+
+/// What common binary formats' leading bytes say a file is
+fn file_kind(head: &[u8]) -> Option<&'static str> {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "PNG image"),
+        (b"\xff\xd8\xff", "JPEG image"),
+        (b"GIF8", "GIF image"),
+        (b"%PDF-", "PDF document"),
+        (b"PK\x03\x04", "ZIP archive (also jar, docx, apk)"),
+        (b"\x1f\x8b", "gzip data"),
+        (b"BZh", "bzip2 data"),
+        (b"\xfd7zXZ\0", "xz data"),
+        (b"7z\xbc\xaf\x27\x1c", "7-Zip archive"),
+        (b"\x28\xb5\x2f\xfd", "zstd data"),
+        (b"\x7fELF", "ELF executable"),
+        (b"MZ", "Windows executable"),
+        (b"\xcf\xfa\xed\xfe", "Mach-O executable"),
+        (b"SQLite format 3\0", "SQLite database"),
+        (b"\0asm", "WebAssembly module"),
+        (b"OggS", "Ogg media"),
+        (b"ID3", "MP3 audio"),
+        (b"\x1a\x45\xdf\xa3", "Matroska/WebM video"),
+    ];
+    if let Some((_, kind)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(kind);
+    }
+    match head.get(4..8) {
+        Some(b"ftyp") => Some("MP4/QuickTime video"),
+        _ if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WAVE") => Some("WAV audio"),
+        _ if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") => Some("WebP image"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_preview() {
+        let preview = BlobPreview::new("notes.txt", "ab", 12, b"one\n\ttwo\n");
+        assert_eq!(preview.content, PreviewContent::Text(vec!["one".to_string(), "    two".to_string()]));
+        assert!(preview.truncated);
+
+        let whole = BlobPreview::new("notes.txt", "ab", 8, b"one\ntwo\n");
+        assert!(!whole.truncated);
+
+        // Only part of a long blob was read: its last line may be cut short
+        let cut = BlobPreview::new("log.txt", "ab", 1 << 20, b"first\nsecond\nthi");
+        assert_eq!(cut.content, PreviewContent::Text(vec!["first".to_string(), "second".to_string()]));
+        assert!(cut.truncated);
+    }
+
+    #[test]
+    fn test_binary_preview() {
+        let head = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\x01\0";
+        let preview = BlobPreview::new("logo.png", "ab", 40_000, head);
+        let PreviewContent::Binary { kind, lines } = preview.content else { panic!("expected a hexdump") };
+        assert_eq!(kind, Some("PNG image"));
+        assert_eq!(lines[0], "00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|");
+        assert_eq!(lines[1], format!("00000010  00 00 01 00 {:37} |....|", ""));
+        assert!(preview.truncated);
+
+        assert_eq!(file_kind(b"\0\0\0\x18ftypmp42"), Some("MP4/QuickTime video"));
+        assert_eq!(file_kind(b"\0\0\0\0"), None);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::util::{extension_label, parse_size};
+
+/// A search as typed: text paths must contain, narrowed by size bounds
+/// (">10MB", "<=1.5GB") and an extension ("ext:zip") anywhere in it
+///
+/// Sizes are cumulative, the size results are ranked and shown by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// The query without its predicates
+    pub text: String,
+    /// Smallest cumulative size, included
+    pub min_size: u64,
+    /// Largest cumulative size, included
+    pub max_size: u64,
+    /// As `util::extension_label` names it, e.g. ".zip"
+    pub extension: Option<String>,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self { text: String::new(), min_size: 0, max_size: u64::MAX, extension: None }
+    }
+}
+
+impl SearchQuery {
+    /// Split predicates out of `query`; ones still being typed (">", ">10X")
+    /// narrow nothing down. A query without predicates is all text, spaces
+    /// included.
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = Self::default();
+        let mut words = Vec::new();
+        let mut predicates = false;
+        for word in query.split_whitespace() {
+            if let Some(ext) = word.strip_prefix("ext:") {
+                predicates = true;
+                let ext = ext.trim_start_matches('*').trim_start_matches('.');
+                if !ext.is_empty() {
+                    parsed.extension = Some(extension_label(&format!("file.{}", ext)).into_owned());
+                }
+            } else if word.starts_with(['>', '<']) {
+                predicates = true;
+                parsed.add_bound(word);
+            } else {
+                words.push(word);
+            }
+        }
+        parsed.text = if predicates { words.join(" ") } else { query.to_string() };
+        parsed
+    }
+
+    fn add_bound(&mut self, word: &str) {
+        let (op, size) = word.split_at(if word[1..].starts_with('=') { 2 } else { 1 });
+        let Some(size) = parse_size(size) else {
+            return;
+        };
+        match op {
+            ">" => self.min_size = self.min_size.max(size.saturating_add(1)),
+            ">=" => self.min_size = self.min_size.max(size),
+            "<" => match size.checked_sub(1) {
+                Some(max) => self.max_size = self.max_size.min(max),
+                // Nothing is smaller than nothing
+                None => self.min_size = u64::MAX,
+            },
+            _ => self.max_size = self.max_size.min(size),
+        }
+    }
+
+    /// Whether anything narrows the search down beyond the text
+    pub fn has_predicates(&self) -> bool {
+        self.min_size > 0 || self.max_size < u64::MAX || self.extension.is_some()
+    }
+
+    /// Blank queries match nothing
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && !self.has_predicates()
+    }
+
+    /// Whether a file passes the size and extension predicates; its path
+    /// is matched against the text separately
+    pub fn accepts(&self, path: &str, cumulative_size: u64) -> bool {
+        (self.min_size..=self.max_size).contains(&cumulative_size)
+            && self.extension.as_deref().is_none_or(|ext| {
+                extension_label(path.rsplit_once('/').map_or(path, |(_, name)| name)) == ext
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_parse_predicates() {
+        let query = SearchQuery::parse(">=1GB ext:ZIP assets");
+        assert_eq!(query.text, "assets");
+        assert_eq!(query.min_size, 1024 * MB);
+        assert_eq!(query.max_size, u64::MAX);
+        assert_eq!(query.extension.as_deref(), Some(".zip"));
+
+        let query = SearchQuery::parse(">10MB <20MB");
+        assert_eq!((query.min_size, query.max_size), (10 * MB + 1, 20 * MB - 1));
+        assert!(query.text.is_empty() && !query.is_empty());
+        assert!(query.accepts("video.mp4", 15 * MB));
+        assert!(!query.accepts("video.mp4", 10 * MB));
+    }
+
+    #[test]
+    fn test_plain_and_unfinished_queries() {
+        // Spaces in plain text still count
+        assert_eq!(SearchQuery::parse("my file "), SearchQuery { text: "my file ".into(), ..Default::default() });
+        // Predicates still being typed narrow nothing down
+        assert!(SearchQuery::parse(">").is_empty());
+        assert!(SearchQuery::parse(">=10X ext:").is_empty());
+        assert!(SearchQuery::parse("").is_empty());
+        // Nothing is below zero bytes
+        assert!(!SearchQuery::parse("<0").accepts("empty.txt", 0));
+    }
+
+    #[test]
+    fn test_extension_uses_extension_labels() {
+        let query = SearchQuery::parse("ext:.PNG");
+        assert!(query.accepts("assets/Logo.PNG", 1));
+        assert!(!query.accepts("assets/logo.png.bak", 1));
+        assert!(!query.accepts("png/README", 1));
+    }
+}
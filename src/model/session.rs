@@ -0,0 +1,58 @@
+/// Something done while investigating
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    /// A view was opened, by its name
+    View(&'static str),
+    /// A directory was opened in the tree ("/" for the root)
+    Directory(String),
+    /// A search, with how many paths matched and the one opened from the
+    /// results, if any
+    Search { query: String, matches: usize, opened: Option<String> },
+    Marked { label: String, cumulative: u64, current: u64 },
+    Unmarked { label: String },
+    /// Every mark was dropped at once
+    ClearedMarks(usize),
+}
+
+/// What was looked at, searched for and marked during a TUI session, in
+/// order, for writing up as investigation notes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionLog {
+    events: Vec<SessionEvent>,
+}
+
+impl SessionLog {
+    pub fn events(&self) -> &[SessionEvent] {
+        &self.events
+    }
+
+    /// Add `event`, unless it only repeats the view or directory last
+    /// recorded
+    pub fn record(&mut self, event: SessionEvent) {
+        let last = match &event {
+            SessionEvent::View(_) => self.events.iter().rev().find(|e| matches!(e, SessionEvent::View(_))),
+            SessionEvent::Directory(_) => self.events.iter().rev().find(|e| matches!(e, SessionEvent::Directory(_))),
+            _ => None,
+        };
+        if last != Some(&event) {
+            self.events.push(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_are_dropped() {
+        let mut log = SessionLog::default();
+        log.record(SessionEvent::Directory("/assets".to_string()));
+        log.record(SessionEvent::Directory("/assets".to_string()));
+        log.record(SessionEvent::View("Large blobs"));
+        log.record(SessionEvent::View("Large blobs"));
+        log.record(SessionEvent::Directory("/assets".to_string()));
+        log.record(SessionEvent::View("Tree"));
+        assert_eq!(log.events().len(), 3);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::util::extension_label;
+
+use super::LargeBlobInfo;
+
+/// Min-hash slots in a fingerprint
+const SLOTS: usize = 64;
+/// Share of matching slots at which two blobs count as near-duplicates
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+/// Near-duplicates are within this factor of each other's size
+const MAX_SIZE_RATIO: u64 = 2;
+/// Features are taken where the top 8 bits of the rolling hash are zero,
+/// about once every 256 bytes
+const FEATURE_MASK: u64 = 0xff << 56;
+
+const fn splitmix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Random value per byte for the gear rolling hash
+const GEAR: [u64; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = splitmix(i as u64);
+        i += 1;
+    }
+    table
+};
+
+/// A fuzzy hash of a blob's content: blobs sharing most of their bytes,
+/// wherever the shared runs sit, share most slots
+///
+/// Features are cut at content-defined points of a rolling hash, so an
+/// insertion only changes the features around it, and each slot keeps the
+/// smallest of the features under its own hash function (min-hash).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint([u64; SLOTS]);
+
+impl Fingerprint {
+    pub fn of(data: &[u8]) -> Self {
+        let mut mins = [u64::MAX; SLOTS];
+        let mut hash: u64 = 0;
+        for &byte in data {
+            hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+            if hash & FEATURE_MASK == 0 {
+                for (slot, min) in mins.iter_mut().enumerate() {
+                    *min = (*min).min(splitmix(hash ^ GEAR[slot]));
+                }
+            }
+        }
+        Self(mins)
+    }
+
+    /// Share of slots the two fingerprints agree on; content too short to
+    /// have any feature is similar to nothing
+    pub fn similarity(&self, other: &Fingerprint) -> f64 {
+        let same = self.0.iter().zip(&other.0).filter(|(a, b)| a == b && **a != u64::MAX).count();
+        same as f64 / SLOTS as f64
+    }
+}
+
+/// Groups of near-duplicate blobs, as indexes into `blobs`: the same
+/// extension, sizes within a factor of two, and fingerprints at least
+/// `SIMILARITY_THRESHOLD` alike, directly or through other members
+///
+/// `fingerprints` lines up with `blobs`; blobs without one (not readable)
+/// join no group. Groups have at least two members, in `blobs` order, and
+/// come in the order of their first member.
+pub fn cluster_near_duplicates(blobs: &[LargeBlobInfo], fingerprints: &[Option<Fingerprint>]) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..blobs.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let extensions: Vec<_> = blobs
+        .iter()
+        .map(|b| extension_label(b.path.rsplit_once('/').map_or(b.path.as_str(), |(_, name)| name)))
+        .collect();
+    for i in 0..blobs.len() {
+        let Some(a) = &fingerprints[i] else { continue };
+        for j in i + 1..blobs.len() {
+            let Some(b) = &fingerprints[j] else { continue };
+            let (small, large) = (blobs[i].size.min(blobs[j].size), blobs[i].size.max(blobs[j].size));
+            if extensions[i] != extensions[j] || small * MAX_SIZE_RATIO < large {
+                continue;
+            }
+            if a.similarity(b) >= SIMILARITY_THRESHOLD {
+                let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+                parent[ri.max(rj)] = ri.min(rj);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root = vec![usize::MAX; blobs.len()];
+    for i in 0..blobs.len() {
+        let r = root(&mut parent, i);
+        if group_of_root[r] == usize::MAX {
+            group_of_root[r] = groups.len();
+            groups.push(Vec::new());
+        }
+        groups[group_of_root[r]].push(i);
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(seed: u64, len: usize) -> Vec<u8> {
+        (0..len as u64).map(|i| splitmix(seed ^ (i << 8)) as u8).collect()
+    }
+
+    fn blob(path: &str, size: u64) -> LargeBlobInfo {
+        LargeBlobInfo {
+            oid: vec![0; 20],
+            size,
+            path: path.to_string(),
+            first_author: String::new(),
+            first_email: String::new(),
+            first_date: 0,
+            first_subject: String::new(),
+            in_head: true,
+        }
+    }
+
+    #[test]
+    fn test_edited_content_stays_similar() {
+        let original = noise(1, 200_000);
+        let mut edited = original.clone();
+        // A header rewritten and a chunk inserted mid-file
+        edited[..500].copy_from_slice(&noise(2, 500));
+        edited.splice(100_000..100_000, noise(3, 4000));
+
+        let a = Fingerprint::of(&original);
+        assert!(a.similarity(&Fingerprint::of(&edited)) >= 0.8);
+        assert!(a.similarity(&Fingerprint::of(&noise(4, 200_000))) < 0.1);
+        assert_eq!(Fingerprint::of(b"tiny").similarity(&Fingerprint::of(b"tiny")), 0.0);
+    }
+
+    #[test]
+    fn test_clusters_need_extension_size_and_content() {
+        let v1 = noise(1, 100_000);
+        let mut v2 = v1.clone();
+        v2.extend(noise(2, 10_000));
+        let blobs = [
+            blob("setup-1.0.exe", 100_000),
+            blob("other.bin", 100_000),
+            blob("dist/setup-1.1.exe", 110_000),
+            blob("setup-1.0.zip", 100_000),
+            blob("unreadable.exe", 100_000),
+        ];
+        let fingerprints = [
+            Some(Fingerprint::of(&v1)),
+            Some(Fingerprint::of(&noise(5, 100_000))),
+            Some(Fingerprint::of(&v2)),
+            Some(Fingerprint::of(&v1)),
+            None,
+        ];
+        assert_eq!(cluster_near_duplicates(&blobs, &fingerprints), vec![vec![0, 2]]);
+    }
+}
@@ -0,0 +1,241 @@
+use super::{ExtensionStats, NodeRef, Tree};
+
+/// How deep directories are recorded in a snapshot: "src" and "src/app",
+/// but not "src/app/views"
+pub const SNAPSHOT_DIR_DEPTH: usize = 2;
+
+/// Totals of the repository at one `repodiet snapshot` run, kept to track
+/// growth over time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SizeSnapshot {
+    /// Unix timestamp of the run
+    pub taken_at: i64,
+    /// HEAD commit scanned; empty when unknown
+    pub head: String,
+    /// Sub-path the scan was limited to (`--path`); empty for all of it
+    pub scope: String,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+    pub deleted_size: u64,
+    pub blob_count: u64,
+    pub largest_blob: u64,
+    /// Totals per extension and per directory (to `SNAPSHOT_DIR_DEPTH`), to
+    /// tell where growth came from; empty when read without them
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// What a `SnapshotEntry` totals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SnapshotEntryKind {
+    Extension,
+    Directory,
+}
+
+impl SnapshotEntryKind {
+    pub fn code(self) -> &'static str {
+        match self {
+            SnapshotEntryKind::Extension => "extension",
+            SnapshotEntryKind::Directory => "dir",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "extension" => Some(SnapshotEntryKind::Extension),
+            "dir" => Some(SnapshotEntryKind::Directory),
+            _ => None,
+        }
+    }
+}
+
+/// One extension's or directory's totals in a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotEntry {
+    pub kind: SnapshotEntryKind,
+    /// An extension as `util::extension_label` names it, or a directory path
+    pub name: String,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+}
+
+impl SizeSnapshot {
+    /// Totals of `tree` taken at `taken_at`, with an entry for each of
+    /// `extensions` and each directory down to `SNAPSHOT_DIR_DEPTH`
+    pub fn of_tree(taken_at: i64, tree: &Tree, extensions: &[(String, ExtensionStats)], largest_blob: u64) -> Self {
+        let root = tree.root();
+        let mut entries: Vec<SnapshotEntry> = extensions
+            .iter()
+            .map(|(extension, stats)| SnapshotEntry {
+                kind: SnapshotEntryKind::Extension,
+                name: extension.clone(),
+                cumulative_size: stats.cumulative_size,
+                current_size: stats.current_size,
+            })
+            .collect();
+        collect_dirs(root, "", 1, &mut entries);
+        Self {
+            taken_at,
+            cumulative_size: root.cumulative_size,
+            current_size: root.current_size,
+            deleted_size: root.deleted_cumulative_size(),
+            blob_count: root.blob_count,
+            largest_blob,
+            entries,
+            ..Default::default()
+        }
+    }
+
+    /// How cumulative sizes grew from `previous` to this snapshot: the total
+    /// first, then each extension and directory that grew, most first
+    pub fn growth_since(&self, previous: &SizeSnapshot) -> Vec<Growth> {
+        let mut grown: Vec<Growth> = self
+            .entries
+            .iter()
+            .map(|entry| Growth {
+                kind: Some(entry.kind),
+                name: entry.name.clone(),
+                before: previous
+                    .entries
+                    .iter()
+                    .find(|e| e.kind == entry.kind && e.name == entry.name)
+                    .map_or(0, |e| e.cumulative_size),
+                after: entry.cumulative_size,
+            })
+            .filter(|growth| growth.bytes() > 0)
+            .collect();
+        grown.sort_by(|a, b| b.bytes().cmp(&a.bytes()).then_with(|| a.kind.cmp(&b.kind)).then_with(|| a.name.cmp(&b.name)));
+        let total = Growth {
+            kind: None,
+            name: String::new(),
+            before: previous.cumulative_size,
+            after: self.cumulative_size,
+        };
+        if total.bytes() > 0 {
+            grown.insert(0, total);
+        }
+        grown
+    }
+}
+
+/// Growth of the cumulative size of the repository, an extension or a
+/// directory between two snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub struct Growth {
+    /// What grew; `None` for the whole repository
+    pub kind: Option<SnapshotEntryKind>,
+    /// The extension or directory, empty for the whole repository
+    pub name: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl Growth {
+    pub fn bytes(&self) -> u64 {
+        self.after.saturating_sub(self.before)
+    }
+
+    /// Growth as a percentage of the size before; `None` for something new
+    pub fn percent(&self) -> Option<f64> {
+        (self.before > 0).then(|| self.bytes() as f64 * 100.0 / self.before as f64)
+    }
+}
+
+/// How much growth is tolerated before it is reported
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GrowthLimits {
+    pub bytes: Option<u64>,
+    pub percent: Option<f64>,
+}
+
+impl GrowthLimits {
+    /// Whether `growth` goes beyond every limit that is set, so a small
+    /// directory doubling or a huge one growing a little doesn't count when
+    /// both are; something new is beyond any percentage
+    pub fn exceeded_by(&self, growth: &Growth) -> bool {
+        let bytes = growth.bytes();
+        bytes > 0
+            && self.bytes.is_none_or(|limit| bytes > limit)
+            && self.percent.is_none_or(|limit| growth.percent().is_none_or(|percent| percent > limit))
+    }
+}
+
+fn collect_dirs(node: NodeRef<'_>, prefix: &str, depth: usize, entries: &mut Vec<SnapshotEntry>) {
+    for child in node.children().filter(|c| c.has_children()) {
+        let path = if prefix.is_empty() { child.name().to_string() } else { format!("{}/{}", prefix, child.name()) };
+        if depth < SNAPSHOT_DIR_DEPTH {
+            collect_dirs(child, &path, depth + 1, entries);
+        }
+        entries.push(SnapshotEntry {
+            kind: SnapshotEntryKind::Directory,
+            name: path,
+            cumulative_size: child.cumulative_size,
+            current_size: child.current_size,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of_tree() {
+        let mut tree = Tree::new();
+        tree.add_path_with_sizes(&["src", "app", "views", "main.rs"], 300, 300, 1);
+        tree.add_path_with_sizes(&["src", "lib.rs"], 100, 0, 2);
+        tree.add_path_with_sizes(&["README.md"], 50, 50, 1);
+        tree.compute_totals();
+        let rs = ExtensionStats { cumulative_size: 400, current_size: 300, ..Default::default() };
+        let snapshot = SizeSnapshot::of_tree(1_700_000_000, &tree, &[(".rs".to_string(), rs)], 200);
+
+        assert_eq!(snapshot.cumulative_size, 450);
+        assert_eq!(snapshot.current_size, 350);
+        assert_eq!(snapshot.deleted_size, 100);
+        assert_eq!(snapshot.blob_count, 4);
+        let names: Vec<(SnapshotEntryKind, &str, u64)> =
+            snapshot.entries.iter().map(|e| (e.kind, e.name.as_str(), e.cumulative_size)).collect();
+        assert_eq!(names, [
+            (SnapshotEntryKind::Extension, ".rs", 400),
+            (SnapshotEntryKind::Directory, "src/app", 300),
+            (SnapshotEntryKind::Directory, "src", 400),
+        ]);
+    }
+
+    #[test]
+    fn test_growth_since() {
+        let entry = |kind, name: &str, size| SnapshotEntry {
+            kind,
+            name: name.to_string(),
+            cumulative_size: size,
+            current_size: size,
+        };
+        let before = SizeSnapshot {
+            cumulative_size: 1000,
+            entries: vec![
+                entry(SnapshotEntryKind::Extension, ".png", 400),
+                entry(SnapshotEntryKind::Directory, "src", 600),
+            ],
+            ..Default::default()
+        };
+        let after = SizeSnapshot {
+            cumulative_size: 1700,
+            entries: vec![
+                entry(SnapshotEntryKind::Extension, ".png", 1000),
+                entry(SnapshotEntryKind::Extension, ".zip", 40),
+                entry(SnapshotEntryKind::Directory, "src", 660),
+            ],
+            ..Default::default()
+        };
+        let growth = after.growth_since(&before);
+        let summary: Vec<(&str, u64, Option<f64>)> =
+            growth.iter().map(|g| (g.name.as_str(), g.bytes(), g.percent())).collect();
+        assert_eq!(summary, [("", 700, Some(70.0)), (".png", 600, Some(150.0)), ("src", 60, Some(10.0)), (".zip", 40, None)]);
+        assert!(before.growth_since(&after).is_empty());
+
+        let limits = GrowthLimits { bytes: Some(50), percent: Some(10.0) };
+        let alerts: Vec<&str> = growth.iter().filter(|g| limits.exceeded_by(g)).map(|g| g.name.as_str()).collect();
+        assert_eq!(alerts, ["", ".png"]);
+        let any = GrowthLimits::default();
+        assert_eq!(growth.iter().filter(|g| any.exceeded_by(g)).count(), 4);
+    }
+}
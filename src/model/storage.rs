@@ -0,0 +1,44 @@
+/// Sizes reported by a hosting API (GitHub, GitLab)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSize {
+    pub host: String,
+    pub repository_size: u64,
+    /// Not every API exposes LFS usage per repository
+    pub lfs_size: Option<u64>,
+}
+
+/// On-disk storage of the local clone, optionally with the server's view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageHint {
+    pub local_pack_size: u64,
+    pub remote: Option<RemoteSize>,
+}
+
+/// Bytes of a `.git` directory by what they hold; the total is what
+/// `du -s --apparent-size .git` reports
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitDirUsage {
+    /// `objects/pack`: packs, their indexes and reverse indexes
+    pub packs: u64,
+    pub loose_objects: u64,
+    /// `refs/` and `packed-refs`
+    pub refs: u64,
+    /// Staging index files, linked worktrees' included
+    pub index: u64,
+    pub reflogs: u64,
+    /// Hooks, config, LFS objects and everything else
+    pub other: u64,
+    /// Number of loose object files
+    pub loose_count: u64,
+    /// Number of `.pack` files
+    pub pack_count: u64,
+    /// Bytes of cruft packs (`git gc --cruft`): unreachable objects kept
+    /// until they expire; counted in `packs` as well
+    pub cruft_packs: u64,
+}
+
+impl GitDirUsage {
+    pub fn total(&self) -> u64 {
+        self.packs + self.loose_objects + self.refs + self.index + self.reflogs + self.other
+    }
+}
@@ -1,103 +1,531 @@
-use std::collections::HashMap;
+//! The directory tree of scan results
+//!
+//! Nodes live in one arena (`Tree`) and refer to each other by `NodeId`, and
+//! every distinct file or directory name is stored once, so repositories
+//! with millions of paths stay compact and cloning a tree is a handful of
+//! vector copies rather than one allocation per node.
+//!
+//! `NodeRef` is how a tree is read: `find` a path below a node, walk its
+//! `descendants`, pick its `top_leaves` or count them with `subtree_stats`.
+
+use rustc_hash::FxHashMap;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Name of the root node
+const ROOT_NAME: &str = "(root)";
+
+/// Index of a node in its `Tree`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// The root of every tree
+    pub const ROOT: NodeId = NodeId(0);
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
 
-/// A node in our directory tree representing file/directory statistics
+/// File/directory statistics of one node; its name and children are looked
+/// up through the `Tree` (see `NodeRef`)
 #[derive(Debug, Clone)]
 pub struct TreeNode {
-    pub name: String,
     pub cumulative_size: u64,
     pub current_size: u64,
     pub blob_count: u64,
-    pub children: HashMap<String, TreeNode>,
     /// Precomputed: whether this node or any descendant has deleted files
     pub has_deleted_descendants: bool,
     /// Precomputed: cumulative size of only deleted content in this subtree
     pub deleted_size: u64,
+    /// What a leaf is in git; directories are always `File`
+    pub kind: EntryKind,
+    name: u32,
+    /// In name order once `Tree::compute_totals` has run
+    children: Vec<NodeId>,
+    /// A directory whose entries are still to be loaded
+    unloaded: bool,
+}
+
+/// One entry of a directory as the index stores it, without the entries
+/// below it (see `Database::get_dir_children`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirEntry {
+    pub name: String,
+    /// Whether this is a directory; its totals cover everything below it
+    pub is_dir: bool,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+    pub blob_count: u64,
+    /// Cumulative size of only deleted content
+    pub deleted_size: u64,
+    /// What a file is in git; directories are always `File`
+    pub kind: EntryKind,
+}
+
+/// Git entry type of a file-like path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryKind {
+    #[default]
+    File,
+    Executable,
+    Symlink,
+    /// A submodule commit; its contents live in another repository
+    Gitlink,
+}
+
+impl EntryKind {
+    /// Integer stored in the database
+    pub fn code(self) -> i64 {
+        match self {
+            EntryKind::File => 0,
+            EntryKind::Executable => 1,
+            EntryKind::Symlink => 2,
+            EntryKind::Gitlink => 3,
+        }
+    }
+
+    /// Inverse of `code`; unknown values read as `File`
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            1 => EntryKind::Executable,
+            2 => EntryKind::Symlink,
+            3 => EntryKind::Gitlink,
+            _ => EntryKind::File,
+        }
+    }
 }
 
 impl TreeNode {
-    pub fn new(name: &str) -> Self {
+    fn new(name: u32) -> Self {
         Self {
-            name: name.to_string(),
             cumulative_size: 0,
             current_size: 0,
             blob_count: 0,
-            children: HashMap::new(),
             has_deleted_descendants: false,
             deleted_size: 0,
+            kind: EntryKind::File,
+            name,
+            children: Vec::new(),
+            unloaded: false,
+        }
+    }
+
+    /// Whether this is a directory
+    #[inline]
+    pub fn has_children(&self) -> bool {
+        !self.children.is_empty() || self.unloaded
+    }
+
+    /// Whether this is a directory whose entries are still to be loaded
+    /// (see `Tree::set_dir_entries`)
+    #[inline]
+    pub fn is_unloaded(&self) -> bool {
+        self.unloaded
+    }
+
+    /// Check if this node or any of its descendants contains deleted files
+    /// (files with current_size == 0 but cumulative_size > 0)
+    #[inline]
+    pub fn contains_deleted_files(&self) -> bool {
+        self.has_deleted_descendants
+    }
+
+    /// Get the cumulative size of only deleted content
+    /// (files where current_size == 0 but cumulative_size > 0)
+    #[inline]
+    pub fn deleted_cumulative_size(&self) -> u64 {
+        self.deleted_size
+    }
+}
+
+/// Counts over everything below a node; see `NodeRef::subtree_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubtreeStats {
+    pub files: u64,
+    pub directories: u64,
+    /// Files HEAD no longer has (current_size == 0 but cumulative_size > 0)
+    pub deleted_files: u64,
+    /// Directories whose entries are still to be loaded; what they hold is
+    /// not counted
+    pub unloaded_directories: u64,
+    /// Levels below the node, 1 when it only holds files
+    pub depth: usize,
+}
+
+/// A directory tree: an arena of nodes with interned names
+#[derive(Debug, Clone)]
+pub struct Tree {
+    nodes: Vec<TreeNode>,
+    names: Vec<Arc<str>>,
+    name_ids: FxHashMap<Arc<str>, u32>,
+    /// (parent, name) -> child
+    child_ids: FxHashMap<(NodeId, u32), NodeId>,
+    /// Directories whose entries are still to be loaded
+    unloaded_dirs: usize,
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tree {
+    /// A tree holding only the root directory
+    pub fn new() -> Self {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            names: Vec::new(),
+            name_ids: FxHashMap::default(),
+            child_ids: FxHashMap::default(),
+            unloaded_dirs: 0,
+        };
+        let name = tree.intern(ROOT_NAME);
+        tree.nodes.push(TreeNode::new(name));
+        tree
+    }
+
+    pub fn root(&self) -> NodeRef<'_> {
+        self.node(NodeId::ROOT)
+    }
+
+    pub fn node(&self, id: NodeId) -> NodeRef<'_> {
+        NodeRef { tree: self, id }
+    }
+
+    /// Number of nodes, the root included
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the tree holds nothing but the root
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// The node at a `/`-separated path from the root
+    pub fn find(&self, path: &str) -> Option<NodeRef<'_>> {
+        self.root().find(path)
+    }
+
+    /// A tree of the root's `entries` alone, for loading one directory at a
+    /// time: each directory keeps the totals given until `set_dir_entries`
+    /// fills in its own entries
+    pub fn from_dir_entries(entries: Vec<DirEntry>) -> Self {
+        let mut tree = Self::new();
+        tree.nodes[NodeId::ROOT.index()].unloaded = true;
+        tree.unloaded_dirs = 1;
+        tree.set_dir_entries(NodeId::ROOT, entries);
+
+        let mut totals = TreeNode::new(tree.nodes[NodeId::ROOT.index()].name);
+        for child in tree.root().children() {
+            totals.cumulative_size += child.cumulative_size;
+            totals.current_size += child.current_size;
+            totals.blob_count += child.blob_count;
+            totals.deleted_size += child.deleted_size;
+            totals.has_deleted_descendants |= child.has_deleted_descendants;
+        }
+        totals.children = std::mem::take(&mut tree.nodes[NodeId::ROOT.index()].children);
+        tree.nodes[NodeId::ROOT.index()] = totals;
+        tree
+    }
+
+    /// Whether some directories' entries are still to be loaded
+    pub fn is_partial(&self) -> bool {
+        self.unloaded_dirs > 0
+    }
+
+    /// Fill in the entries of the unloaded directory `dir`, in the order
+    /// given (name order); `dir`'s own totals stay as they are
+    pub fn set_dir_entries(&mut self, dir: NodeId, entries: Vec<DirEntry>) {
+        if !self.nodes[dir.index()].unloaded {
+            return;
+        }
+        self.nodes[dir.index()].unloaded = false;
+        self.unloaded_dirs -= 1;
+
+        for entry in entries {
+            let id = self.child_or_insert(dir, &entry.name);
+            let node = &mut self.nodes[id.index()];
+            node.cumulative_size = entry.cumulative_size;
+            node.current_size = entry.current_size;
+            node.blob_count = entry.blob_count;
+            node.deleted_size = entry.deleted_size;
+            node.has_deleted_descendants = entry.deleted_size > 0;
+            node.kind = entry.kind;
+            if entry.is_dir && !node.unloaded {
+                node.unloaded = true;
+                self.unloaded_dirs += 1;
+            }
         }
     }
 
+    /// The unloaded directory that keeps `path` from being found, if any,
+    /// with its own path (a prefix of `path`)
+    pub fn unloaded_ancestor<'p>(&self, path: &'p str) -> Option<(NodeRef<'_>, &'p str)> {
+        let mut node = self.root();
+        let mut end = 0;
+        for part in path.split('/') {
+            if node.unloaded {
+                return Some((node, path[..end].trim_end_matches('/')));
+            }
+            if !part.is_empty() {
+                node = node.child(part)?;
+            }
+            end += part.len() + 1;
+        }
+        None
+    }
+
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.name_ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as u32;
+        let name: Arc<str> = Arc::from(name);
+        self.names.push(Arc::clone(&name));
+        self.name_ids.insert(name, id);
+        id
+    }
+
+    fn child_id(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+        let name = *self.name_ids.get(name)?;
+        self.child_ids.get(&(parent, name)).copied()
+    }
+
+    fn child_or_insert(&mut self, parent: NodeId, name: &str) -> NodeId {
+        let name = self.intern(name);
+        if let Some(&id) = self.child_ids.get(&(parent, name)) {
+            return id;
+        }
+        let id = NodeId(u32::try_from(self.nodes.len()).expect("more than u32::MAX tree nodes"));
+        self.nodes.push(TreeNode::new(name));
+        self.nodes[parent.index()].children.push(id);
+        self.child_ids.insert((parent, name), id);
+        id
+    }
+
     pub fn add_path_with_sizes(&mut self, path_parts: &[&str], cumulative: u64, current: u64, count: u64) {
+        self.add_entry(path_parts, cumulative, current, count, EntryKind::File);
+    }
+
+    /// Like `add_path_with_sizes`, also setting the leaf's entry kind
+    pub fn add_entry(&mut self, path_parts: &[&str], cumulative: u64, current: u64, count: u64, kind: EntryKind) {
         if path_parts.is_empty() {
             return;
         }
 
-        let child_name = path_parts[0];
-        let child = self
-            .children
-            .entry(child_name.to_string())
-            .or_insert_with(|| TreeNode::new(child_name));
-
-        if path_parts.len() == 1 {
-            // Leaf node - add sizes here only, compute_totals will roll up
-            child.cumulative_size += cumulative;
-            child.current_size += current;
-            child.blob_count += count;
-        } else {
-            child.add_path_with_sizes(&path_parts[1..], cumulative, current, count);
+        let mut id = NodeId::ROOT;
+        for part in path_parts {
+            id = self.child_or_insert(id, part);
         }
+
+        // Leaf node - add sizes here only, compute_totals will roll up
+        let leaf = &mut self.nodes[id.index()];
+        leaf.cumulative_size += cumulative;
+        leaf.current_size += current;
+        leaf.blob_count += count;
+        leaf.kind = kind;
     }
 
+    /// Roll leaf sizes up into every directory and put children in name order
     pub fn compute_totals(&mut self) {
-        // Always reset derived values so this is idempotent.
-        self.has_deleted_descendants = false;
-        self.deleted_size = 0;
-
-        if self.children.is_empty() {
-            let is_deleted = self.current_size == 0 && self.cumulative_size > 0;
-            self.has_deleted_descendants = is_deleted;
-            self.deleted_size = if is_deleted { self.cumulative_size } else { 0 };
-            return;
+        // Children are always created after their parent, so walking the
+        // arena backwards finishes every child before its parent
+        for index in (0..self.nodes.len()).rev() {
+            let mut children = std::mem::take(&mut self.nodes[index].children);
+            let node = &self.nodes[index];
+            // Totals of a directory not loaded yet came with it
+            if node.unloaded {
+                continue;
+            }
+            if children.is_empty() {
+                let is_deleted = node.current_size == 0 && node.cumulative_size > 0;
+                let node = &mut self.nodes[index];
+                // Always reset derived values so this is idempotent.
+                node.has_deleted_descendants = is_deleted;
+                node.deleted_size = if is_deleted { node.cumulative_size } else { 0 };
+                continue;
+            }
+
+            let (names, nodes) = (&self.names, &self.nodes);
+            children.sort_unstable_by(|a, b| names[nodes[a.index()].name as usize].cmp(&names[nodes[b.index()].name as usize]));
+
+            // Reset rollups for directories (leaf sizes were written directly to leaf nodes).
+            let mut totals = TreeNode { children: Vec::new(), ..TreeNode::new(node.name) };
+            for child in &children {
+                let child = &self.nodes[child.index()];
+                totals.cumulative_size += child.cumulative_size;
+                totals.current_size += child.current_size;
+                totals.blob_count += child.blob_count;
+                totals.deleted_size += child.deleted_size;
+                totals.has_deleted_descendants |= child.has_deleted_descendants;
+            }
+            totals.children = children;
+            self.nodes[index] = totals;
+        }
+    }
+
+    /// Drop every entry whose path `excluded` matches, then recompute totals
+    ///
+    /// A directory emptied by the filter goes too. Node ids are not kept.
+    pub fn prune(&mut self, excluded: impl Fn(&str) -> bool) {
+        fn copy_kept(node: NodeRef<'_>, path: &mut String, excluded: &dyn Fn(&str) -> bool, kept: &mut Tree) {
+            let base_len = path.len();
+            for child in node.children() {
+                path.truncate(base_len);
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(child.name());
+                if excluded(path) {
+                    continue;
+                }
+                if child.has_children() {
+                    copy_kept(child, path, excluded, kept);
+                } else {
+                    let parts: Vec<&str> = path.split('/').collect();
+                    kept.add_entry(&parts, child.cumulative_size, child.current_size, child.blob_count, child.kind);
+                }
+            }
+            path.truncate(base_len);
         }
 
-        // Reset rollups for directories (leaf sizes were written directly to leaf nodes).
-        self.cumulative_size = 0;
-        self.current_size = 0;
-        self.blob_count = 0;
+        let mut kept = Tree::new();
+        copy_kept(self.root(), &mut String::new(), &excluded, &mut kept);
+        kept.compute_totals();
+        *self = kept;
+    }
 
-        for child in self.children.values_mut() {
-            child.compute_totals();
-            self.cumulative_size += child.cumulative_size;
-            self.current_size += child.current_size;
-            self.blob_count += child.blob_count;
-            self.deleted_size += child.deleted_size;
-            self.has_deleted_descendants |= child.has_deleted_descendants;
+    /// Visits all leaf nodes in path order; see `NodeRef::visit_leaves`
+    pub fn visit_leaves<'a>(&'a self, f: impl FnMut(&str, NodeRef<'a>)) {
+        self.root().visit_leaves(f);
+    }
+
+    /// Visits all leaf nodes without building paths; see `NodeRef::visit_leaf_nodes`
+    pub fn visit_leaf_nodes<'a>(&'a self, f: impl FnMut(NodeRef<'a>)) {
+        self.root().visit_leaf_nodes(f);
+    }
+}
+
+/// A node together with the tree it belongs to; derefs to its statistics
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    tree: &'a Tree,
+    id: NodeId,
+}
+
+impl std::fmt::Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeRef").field("id", &self.id).field("name", &self.name()).finish()
+    }
+}
+
+impl Deref for NodeRef<'_> {
+    type Target = TreeNode;
+
+    fn deref(&self) -> &TreeNode {
+        &self.tree.nodes[self.id.index()]
+    }
+}
+
+impl<'a> NodeRef<'a> {
+    pub fn id(self) -> NodeId {
+        self.id
+    }
+
+    /// The node's statistics, borrowed for as long as the tree
+    pub fn stats(self) -> &'a TreeNode {
+        &self.tree.nodes[self.id.index()]
+    }
+
+    pub fn name(self) -> &'a str {
+        &self.tree.names[self.stats().name as usize]
+    }
+
+    /// Direct children, in name order
+    pub fn children(self) -> impl DoubleEndedIterator<Item = NodeRef<'a>> + ExactSizeIterator {
+        let tree = self.tree;
+        self.stats().children.iter().map(move |&id| NodeRef { tree, id })
+    }
+
+    /// The direct child called `name`
+    pub fn child(self, name: &str) -> Option<NodeRef<'a>> {
+        self.tree.child_id(self.id, name).map(|id| self.tree.node(id))
+    }
+
+    /// The node at a `/`-separated path below this one
+    pub fn find(self, path: &str) -> Option<NodeRef<'a>> {
+        path.split('/')
+            .filter(|part| !part.is_empty())
+            .try_fold(self, |node, part| node.child(part))
+    }
+
+    /// Every node below this one, depth first in path order, each directory
+    /// before its entries
+    pub fn descendants(self) -> Descendants<'a> {
+        Descendants {
+            stack: self.children().rev().map(|child| (child, 0, 1)).collect(),
+            path: String::new(),
+            max_depth: usize::MAX,
         }
     }
 
-    /// Check if this node or any of its descendants contains deleted files
-    /// (files with current_size == 0 but cumulative_size > 0)
-    #[inline]
-    pub fn contains_deleted_files(&self) -> bool {
-        self.has_deleted_descendants
+    /// The `n` files below this node with the largest cumulative size, with
+    /// their paths from it; largest first, ties in path order
+    pub fn top_leaves(self, n: usize) -> Vec<(String, NodeRef<'a>)> {
+        let mut leaves: Vec<(String, NodeRef<'a>)> = Vec::new();
+        if n == 0 {
+            return leaves;
+        }
+        let rank = |leaves: &mut Vec<(String, NodeRef<'a>)>| {
+            // Stable, so equal sizes stay in the path order they were visited in
+            leaves.sort_by_key(|(_, node)| std::cmp::Reverse(node.cumulative_size));
+            leaves.truncate(n);
+        };
+        self.visit_leaves(|path, node| {
+            leaves.push((path.to_string(), node));
+            if leaves.len() >= 2 * n {
+                rank(&mut leaves);
+            }
+        });
+        rank(&mut leaves);
+        leaves
     }
 
-    /// Get the cumulative size of only deleted content
-    /// (files where current_size == 0 but cumulative_size > 0)
-    #[inline]
-    pub fn deleted_cumulative_size(&self) -> u64 {
-        self.deleted_size
+    /// How many files and directories are below this node, and how deep
+    pub fn subtree_stats(self) -> SubtreeStats {
+        let mut stats = SubtreeStats::default();
+        let mut stack: Vec<(NodeRef<'a>, usize)> = self.children().map(|child| (child, 1)).collect();
+        while let Some((node, depth)) = stack.pop() {
+            stats.depth = stats.depth.max(depth);
+            if node.is_unloaded() {
+                stats.directories += 1;
+                stats.unloaded_directories += 1;
+            } else if node.has_children() {
+                stats.directories += 1;
+                stack.extend(node.children().map(|child| (child, depth + 1)));
+            } else {
+                stats.files += 1;
+                if node.current_size == 0 && node.cumulative_size > 0 {
+                    stats.deleted_files += 1;
+                }
+            }
+        }
+        stats
     }
 
-    /// Visits all leaf nodes, calling `f` with the full path and node.
+    /// Visits all leaf nodes in path order, calling `f` with the full path and node.
     /// Uses a reusable path buffer - only allocates once per leaf when caller clones.
-    pub fn visit_leaves(&self, mut f: impl FnMut(&str, &TreeNode)) {
+    pub fn visit_leaves(self, mut f: impl FnMut(&str, NodeRef<'a>)) {
         // Stack stores (node, base_len) where base_len is path length before this node
-        let mut stack: Vec<(&TreeNode, usize)> = Vec::new();
+        let mut stack: Vec<(NodeRef<'a>, usize)> = Vec::new();
         let mut path = String::new();
 
-        // Seed with root's children
-        for child in self.children.values() {
+        // Seed with this node's children; reversed, so the stack pops them in order
+        for child in self.children().rev() {
             stack.push((child, 0));
         }
 
@@ -107,39 +535,90 @@ impl TreeNode {
             if !path.is_empty() {
                 path.push('/');
             }
-            path.push_str(&node.name);
+            path.push_str(node.name());
 
-            if node.children.is_empty() {
-                f(&path, node);
-            } else {
+            if node.has_children() {
                 let current_len = path.len();
-                for child in node.children.values() {
+                for child in node.children().rev() {
                     stack.push((child, current_len));
                 }
+            } else {
+                f(&path, node);
             }
         }
     }
 
-    /// Visits all leaf nodes without path allocation.
+    /// Visits all leaf nodes in path order without path allocation.
     /// Use when you only need node data, not paths.
-    pub fn visit_leaf_nodes(&self, mut f: impl FnMut(&TreeNode)) {
-        let mut stack: Vec<&TreeNode> = self.children.values().collect();
+    pub fn visit_leaf_nodes(self, mut f: impl FnMut(NodeRef<'a>)) {
+        let mut stack: Vec<NodeRef<'a>> = self.children().rev().collect();
         while let Some(node) = stack.pop() {
-            if node.children.is_empty() {
-                f(node);
+            if node.has_children() {
+                stack.extend(node.children().rev());
             } else {
-                stack.extend(node.children.values());
+                f(node);
             }
         }
     }
 }
 
+/// A node below another, with where it sits from there; see
+/// `NodeRef::descendants`
+#[derive(Debug, Clone)]
+pub struct Descendant<'a> {
+    /// `/`-separated, from the node the walk started at
+    pub path: String,
+    /// 1 for a direct child
+    pub depth: usize,
+    pub node: NodeRef<'a>,
+}
+
+/// Walk below a node; see `NodeRef::descendants`
+pub struct Descendants<'a> {
+    /// (node, length of its parent's path, depth), next on top
+    stack: Vec<(NodeRef<'a>, usize, usize)>,
+    /// Path of the node last returned; cut back to the parent of the next
+    path: String,
+    max_depth: usize,
+}
+
+impl Descendants<'_> {
+    /// Stop `depth` levels down, 1 being the direct children
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = Descendant<'a>;
+
+    fn next(&mut self) -> Option<Descendant<'a>> {
+        loop {
+            let (node, base_len, depth) = self.stack.pop()?;
+            if depth > self.max_depth {
+                continue;
+            }
+            self.path.truncate(base_len);
+            if !self.path.is_empty() {
+                self.path.push('/');
+            }
+            self.path.push_str(node.name());
+            if depth < self.max_depth {
+                let len = self.path.len();
+                self.stack.extend(node.children().rev().map(|child| (child, len, depth + 1)));
+            }
+            return Some(Descendant { path: self.path.clone(), depth, node });
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn create_test_tree() -> TreeNode {
-        let mut root = TreeNode::new("(root)");
+    fn create_test_tree() -> Tree {
+        let mut root = Tree::new();
 
         root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
         root.add_path_with_sizes(&["src", "lib.rs"], 800, 400, 1);
@@ -154,32 +633,38 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_tree_node_totals() {
         let tree = create_test_tree();
 
-        assert_eq!(tree.cumulative_size, 1000 + 800 + 300 + 5000 + 2000 + 100 + 200);
-        assert_eq!(tree.current_size, 500 + 400 + 300 + 0 + 2000 + 100 + 200);
+        assert_eq!(tree.root().cumulative_size, 1000 + 800 + 300 + 5000 + 2000 + 100 + 200);
+        assert_eq!(tree.root().current_size, 500 + 400 + 300 + 0 + 2000 + 100 + 200);
     }
 
     #[test]
     fn test_tree_node_children() {
         let tree = create_test_tree();
 
-        assert_eq!(tree.children.len(), 4);
-        assert!(tree.children.contains_key("src"));
-        assert!(tree.children.contains_key("assets"));
-        assert!(tree.children.contains_key("README.md"));
-        assert!(tree.children.contains_key("Cargo.toml"));
+        assert_eq!(tree.root().children().len(), 4);
+        assert!(tree.root().child("src").is_some());
+        assert!(tree.root().child("assets").is_some());
+        assert!(tree.root().child("README.md").is_some());
+        assert!(tree.root().child("Cargo.toml").is_some());
+
+        // Name order, and names are shared rather than copied per node
+        let names: Vec<_> = tree.root().children().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["Cargo.toml", "README.md", "assets", "src"]);
+        assert_eq!(tree.len(), 11);
     }
 
     #[test]
     fn test_contains_deleted_files() {
         let tree = create_test_tree();
 
-        let assets = tree.children.get("assets").unwrap();
+        let assets = tree.find("assets").unwrap();
         assert!(assets.contains_deleted_files());
 
-        let src = tree.children.get("src").unwrap();
+        let src = tree.find("src").unwrap();
         assert!(!src.contains_deleted_files());
     }
 
@@ -187,7 +672,7 @@ mod tests {
     fn test_deleted_cumulative_size() {
         let tree = create_test_tree();
 
-        let assets = tree.children.get("assets").unwrap();
+        let assets = tree.find("assets").unwrap();
         assert_eq!(assets.cumulative_size, 7000);
         assert_eq!(assets.deleted_cumulative_size(), 5000);
     }
@@ -196,17 +681,28 @@ mod tests {
     fn test_bloat_calculation() {
         let tree = create_test_tree();
 
-        let assets = tree.children.get("assets").unwrap();
-        let logo = assets.children.get("logo.png").unwrap();
+        let assets = tree.find("assets").unwrap();
+        let logo = assets.child("logo.png").unwrap();
         assert_eq!(logo.cumulative_size, 5000);
         assert_eq!(logo.current_size, 0);
 
-        let src = tree.children.get("src").unwrap();
-        let main = src.children.get("main.rs").unwrap();
+        let src = tree.find("src").unwrap();
+        let main = src.child("main.rs").unwrap();
         assert_eq!(main.cumulative_size, 1000);
         assert_eq!(main.current_size, 500);
     }
 
+    #[test]
+    fn test_prune() {
+        let mut tree = create_test_tree();
+        tree.prune(|path| path.ends_with(".png") || path == "src/utils");
+
+        assert!(tree.find("assets").is_none());
+        assert!(tree.find("src/utils").is_none());
+        assert_eq!(tree.root().cumulative_size, 1000 + 800 + 100 + 200);
+        assert!(!tree.root().contains_deleted_files());
+    }
+
     #[test]
     fn test_visit_leaves() {
         let tree = create_test_tree();
@@ -228,6 +724,20 @@ mod tests {
 
         // Paths should include full path
         assert!(paths.iter().any(|p| p == "src/main.rs" || p == "src/lib.rs"));
+
+        // Depth-first in name order, whatever order the paths were added in
+        assert_eq!(
+            paths,
+            vec![
+                "Cargo.toml",
+                "README.md",
+                "assets/icon.png",
+                "assets/logo.png",
+                "src/lib.rs",
+                "src/main.rs",
+                "src/utils/helper.rs",
+            ]
+        );
     }
 
     #[test]
@@ -245,6 +755,126 @@ mod tests {
         assert_eq!(count, 7);
 
         // Verify we can access node data
-        assert_eq!(total_size, tree.cumulative_size);
+        assert_eq!(total_size, tree.root().cumulative_size);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let tree = create_test_tree();
+
+        let all: Vec<_> = tree.root().descendants().map(|d| (d.path, d.depth)).collect();
+        assert_eq!(all.len(), tree.len() - 1);
+        assert_eq!(all[..4], [
+            ("Cargo.toml".to_string(), 1),
+            ("README.md".to_string(), 1),
+            ("assets".to_string(), 1),
+            ("assets/icon.png".to_string(), 2),
+        ]);
+        assert_eq!(all.last().unwrap(), &("src/utils/helper.rs".to_string(), 3));
+
+        // Paths are relative to where the walk starts
+        let src: Vec<_> = tree.find("src").unwrap().descendants().max_depth(1).map(|d| d.path).collect();
+        assert_eq!(src, ["lib.rs", "main.rs", "utils"]);
+        assert_eq!(tree.root().descendants().max_depth(0).count(), 0);
+    }
+
+    #[test]
+    fn test_top_leaves() {
+        let tree = create_test_tree();
+
+        let top: Vec<_> = tree.root().top_leaves(3).into_iter().map(|(path, node)| (path, node.cumulative_size)).collect();
+        assert_eq!(top, [
+            ("assets/logo.png".to_string(), 5000),
+            ("assets/icon.png".to_string(), 2000),
+            ("src/main.rs".to_string(), 1000),
+        ]);
+        assert_eq!(tree.find("src").unwrap().top_leaves(1)[0].0, "main.rs");
+        assert_eq!(tree.root().top_leaves(100).len(), 7);
+        assert!(tree.root().top_leaves(0).is_empty());
+
+        // Ties keep path order, however many are pruned on the way
+        let mut ties = Tree::new();
+        for name in ["d", "c", "b", "a", "e"] {
+            ties.add_path_with_sizes(&[name], 10, 10, 1);
+        }
+        ties.compute_totals();
+        let names: Vec<_> = ties.root().top_leaves(2).into_iter().map(|(path, _)| path).collect();
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_subtree_stats() {
+        let tree = create_test_tree();
+
+        assert_eq!(tree.root().subtree_stats(), SubtreeStats {
+            files: 7,
+            directories: 3,
+            deleted_files: 1,
+            unloaded_directories: 0,
+            depth: 3,
+        });
+        let main = tree.find("src/main.rs").unwrap();
+        assert_eq!(main.subtree_stats(), SubtreeStats::default());
+
+        let partial = Tree::from_dir_entries(vec![DirEntry {
+            name: "assets".into(),
+            is_dir: true,
+            cumulative_size: 7000,
+            current_size: 2000,
+            blob_count: 2,
+            deleted_size: 5000,
+            kind: EntryKind::File,
+        }]);
+        let stats = partial.root().subtree_stats();
+        assert_eq!((stats.directories, stats.unloaded_directories, stats.files), (1, 1, 0));
+    }
+
+    #[test]
+    fn test_compute_totals_is_idempotent() {
+        let mut tree = create_test_tree();
+        tree.add_path_with_sizes(&["assets", "banner.png"], 700, 0, 1);
+        tree.compute_totals();
+        tree.compute_totals();
+
+        let assets = tree.find("assets").unwrap();
+        assert_eq!(assets.cumulative_size, 7700);
+        assert_eq!(assets.deleted_cumulative_size(), 5700);
+        let names: Vec<_> = assets.children().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["banner.png", "icon.png", "logo.png"]);
+        assert_eq!(tree.find("/src//utils/helper.rs").unwrap().cumulative_size, 300);
+    }
+
+    #[test]
+    fn test_loading_one_directory_at_a_time() {
+        let dir = |name: &str, cumulative, deleted| DirEntry {
+            name: name.into(),
+            is_dir: true,
+            cumulative_size: cumulative,
+            current_size: cumulative - deleted,
+            blob_count: 2,
+            deleted_size: deleted,
+            kind: EntryKind::File,
+        };
+        let file = |name: &str, cumulative| DirEntry { is_dir: false, blob_count: 1, ..dir(name, cumulative, 0) };
+
+        let mut tree = Tree::from_dir_entries(vec![file("README.md", 100), dir("assets", 7000, 5000)]);
+        assert!(tree.is_partial());
+        assert_eq!(tree.root().cumulative_size, 7100);
+        assert_eq!(tree.root().deleted_cumulative_size(), 5000);
+        let assets = tree.find("assets").unwrap();
+        assert!(assets.is_unloaded() && assets.has_children());
+        assert!(assets.contains_deleted_files());
+        let (blocking, path) = tree.unloaded_ancestor("assets/img/logo.png").unwrap();
+        assert_eq!((blocking.id(), path), (assets.id(), "assets"));
+        assert!(tree.unloaded_ancestor("assets").is_none());
+
+        // A directory's own totals stay as given; its entries arrive in order
+        let id = assets.id();
+        tree.set_dir_entries(id, vec![file("icon.png", 2000), file("logo.png", 5000)]);
+        assert!(!tree.is_partial());
+        let names: Vec<_> = tree.node(id).children().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["icon.png", "logo.png"]);
+        assert_eq!(tree.node(id).cumulative_size, 7000);
+        assert!(tree.unloaded_ancestor("assets/img/logo.png").is_none());
     }
 }
@@ -0,0 +1,85 @@
+/// Directories whose contents are dependencies or build output rather
+/// than the project's own files, as (name, rule)
+const VENDORED_DIRS: &[(&str, &str)] = &[
+    ("node_modules", "node_modules/"), ("bower_components", "bower_components/"),
+    ("jspm_packages", "jspm_packages/"), ("vendor", "vendor/"), ("third_party", "third_party/"),
+    ("Pods", "Pods/"), ("Carthage", "Carthage/"), ("target", "target/"), ("__pycache__", "__pycache__/"),
+    (".venv", ".venv/"), ("venv", "venv/"), (".gradle", ".gradle/"), (".next", ".next/"), (".nuxt", ".nuxt/"),
+    ("DerivedData", "DerivedData/"),
+];
+
+/// File name endings of minified or generated code, as (ending, rule)
+const GENERATED_SUFFIXES: &[(&str, &str)] = &[
+    (".min.js", "*.min.js"),
+    (".min.css", "*.min.css"),
+    (".min.js.map", "*.min.js"),
+    (".pb.go", "*.pb.go"),
+    ("_pb2.py", "*_pb2.py"),
+];
+
+/// Lockfiles package managers write
+const LOCKFILES: &[&str] = &[
+    "package-lock.json", "npm-shrinkwrap.json", "yarn.lock", "pnpm-lock.yaml", "bun.lockb", "Cargo.lock",
+    "Gemfile.lock", "Podfile.lock", "composer.lock", "poetry.lock", "Pipfile.lock", "uv.lock", "go.sum",
+    "mix.lock", "pubspec.lock", "packages.lock.json", "flake.lock",
+];
+
+/// Rule every lockfile is tagged with
+pub const LOCKFILE_RULE: &str = "lockfiles";
+
+/// The rule tagging the file at `path` as generated or vendored, e.g.
+/// "node_modules/", "*.min.js" or "lockfiles"; `None` for the project's own
+/// files
+///
+/// The outermost matching directory wins, so a lockfile inside
+/// `node_modules` counts toward "node_modules/".
+pub fn vendored_rule(path: &str) -> Option<&'static str> {
+    let (dirs, name) = path.rsplit_once('/').unwrap_or(("", path));
+    for dir in dirs.split('/') {
+        if let Some((_, rule)) = VENDORED_DIRS.iter().find(|(vendored, _)| *vendored == dir) {
+            return Some(rule);
+        }
+    }
+    if LOCKFILES.contains(&name) {
+        return Some(LOCKFILE_RULE);
+    }
+    GENERATED_SUFFIXES.iter().find(|(suffix, _)| name.ends_with(suffix)).map(|(_, rule)| *rule)
+}
+
+/// History held by the files one rule tags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendoredShare {
+    pub rule: String,
+    pub files: u64,
+    pub cumulative: u64,
+    pub current: u64,
+}
+
+/// Tagged files below one entry of a directory, for filtering the tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VendoredSizes {
+    pub files: u64,
+    pub cumulative: u64,
+    /// Of that, what HEAD no longer has
+    pub deleted: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(vendored_rule("web/node_modules/left-pad/index.js"), Some("node_modules/"));
+        assert_eq!(vendored_rule("node_modules/x/vendor/package-lock.json"), Some("node_modules/"));
+        assert_eq!(vendored_rule("ios/Pods/Alamofire/Source/AF.swift"), Some("Pods/"));
+        assert_eq!(vendored_rule("Cargo.lock"), Some(LOCKFILE_RULE));
+        assert_eq!(vendored_rule("static/app.min.js"), Some("*.min.js"));
+        assert_eq!(vendored_rule("api/user.pb.go"), Some("*.pb.go"));
+
+        // Only whole directory names count, and never the file's own name
+        assert_eq!(vendored_rule("src/vendoring.rs"), None);
+        assert_eq!(vendored_rule("docs/target"), None);
+        assert_eq!(vendored_rule("src/main.rs"), None);
+    }
+}
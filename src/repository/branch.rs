@@ -0,0 +1,83 @@
+//! Blobs a branch adds on top of its base, for pull-request checks
+
+use anyhow::{Context, Result};
+use gix::ObjectId;
+use rustc_hash::FxHashSet;
+
+use crate::model::AddedBlob;
+
+/// Blobs of at least `min_size` bytes that commits on HEAD but not on
+/// `base` add, largest first
+///
+/// The commits are walked oldest first. A blob counts unless a commit the
+/// branch grows from (a parent of its commits that isn't one of them)
+/// already has it, and is credited to the first commit and path it appears
+/// at. Sizes are the blobs' own, as `git cat-file -s` reports them, since
+/// that is what a reviewer sees added.
+pub fn branch_blobs(repo_path: &str, base: &str, min_size: u64) -> Result<Vec<AddedBlob>> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let head = repo.head_commit().context("Failed to get HEAD commit")?.id;
+    let base_id = repo
+        .rev_parse_single(base)
+        .with_context(|| format!("Unknown base '{}'", base))?
+        .object()?
+        .peel_to_commit()
+        .with_context(|| format!("Base '{}' is not a commit", base))?
+        .id;
+
+    let mut commits = Vec::new();
+    for info in repo.rev_walk([head]).with_hidden([base_id]).all()? {
+        commits.push(info.context("Failed to walk the branch's commits")?.id);
+    }
+    commits.reverse();
+    let mut known = boundary_blobs(&repo, &commits, false)?;
+
+    let mut added = Vec::new();
+    for commit in commits {
+        for (path, oid) in tree_blobs(&repo, commit)? {
+            if !known.insert(oid) {
+                continue;
+            }
+            let size = repo.find_header(oid).with_context(|| format!("Failed to read blob {}", oid))?.size();
+            if size >= min_size {
+                added.push(AddedBlob { path, oid: oid.to_string(), size, commit: commit.to_string() });
+            }
+        }
+    }
+    added.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    Ok(added)
+}
+
+/// Blobs of the commits a set of `commits` grows from: their parents (only
+/// first parents with `first_parent`) that aren't among them
+pub(crate) fn boundary_blobs(repo: &gix::Repository, commits: &[ObjectId], first_parent: bool) -> Result<FxHashSet<ObjectId>> {
+    let listed: FxHashSet<ObjectId> = commits.iter().copied().collect();
+    let mut bases: FxHashSet<ObjectId> = FxHashSet::default();
+    let mut known: FxHashSet<ObjectId> = FxHashSet::default();
+    for &commit in commits {
+        let commit = repo.find_commit(commit).with_context(|| format!("Failed to read commit {}", commit))?;
+        let parents = commit.parent_ids().take(if first_parent { 1 } else { usize::MAX });
+        for parent in parents.map(|p| p.detach()).filter(|p| !listed.contains(p)) {
+            if bases.insert(parent) {
+                known.extend(tree_blobs(repo, parent)?.into_iter().map(|(_, oid)| oid));
+            }
+        }
+    }
+    Ok(known)
+}
+
+/// Every file (not symlink or submodule) in `commit`'s tree, as (path, blob)
+fn tree_blobs(repo: &gix::Repository, commit: ObjectId) -> Result<Vec<(String, ObjectId)>> {
+    let context = || format!("Failed to read the tree of {}", commit);
+    let tree = repo.find_commit(commit).with_context(context)?.tree().with_context(context)?;
+    let mut recorder = gix::traverse::tree::Recorder::default();
+    tree.traverse()
+        .breadthfirst(&mut recorder)
+        .with_context(context)?;
+    Ok(recorder
+        .records
+        .into_iter()
+        .filter(|entry| entry.mode.is_blob())
+        .map(|entry| (entry.filepath.to_string(), entry.oid))
+        .collect())
+}
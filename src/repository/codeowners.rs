@@ -0,0 +1,25 @@
+//! The CODEOWNERS file of a repository's HEAD
+
+use anyhow::{Context, Result};
+
+/// Where GitHub looks for CODEOWNERS, in the order it looks
+const LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The first CODEOWNERS file HEAD has, as (path, contents)
+///
+/// Read from HEAD rather than the worktree, so bare repositories and
+/// scans of a sub-path see the same file.
+pub fn read_codeowners(repo_path: &str) -> Result<Option<(String, String)>> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let tree = repo.head_commit().context("Failed to get HEAD commit")?.tree()?;
+    for location in LOCATIONS {
+        let Some(entry) = tree.lookup_entry_by_path(location)? else {
+            continue;
+        };
+        if entry.mode().is_blob() {
+            let object = entry.object()?;
+            return Ok(Some((location.to_string(), String::from_utf8_lossy(&object.data).into_owned())));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,74 @@
+//! Binary or text, judged from blob content
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use flate2::read::ZlibDecoder;
+use gix::ObjectId;
+use gix_hash::Kind as HashKind;
+use gix_pack::{data, index};
+
+use crate::model::{looks_binary, SNIFF_LEN};
+
+/// Blobs up to this size are read whole; of larger ones only the first
+/// `SNIFF_LEN` bytes are inflated
+const READ_WHOLE_LIMIT: u64 = 1024 * 1024;
+
+/// Whether each blob looks binary, read from the object database
+///
+/// The result lines up with `oids`; a blob that can't be read gets `None`,
+/// and so does a large one stored as a delta, which can't be sniffed
+/// without rebuilding it whole.
+pub fn classify_blobs(repo_path: &str, oids: &[&[u8]]) -> Result<Vec<Option<bool>>> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let objects_dir = repo.objects.store_ref().path().to_path_buf();
+    Ok(oids
+        .iter()
+        .map(|oid| {
+            let id = ObjectId::try_from(*oid).ok()?;
+            let header = repo.find_header(id).ok()?;
+            if header.size() <= READ_WHOLE_LIMIT {
+                let object = repo.find_object(id).ok()?;
+                return Some(looks_binary(&object.data));
+            }
+            let prefix = loose_prefix(&objects_dir, id).or_else(|| packed_prefix(&objects_dir, id))?;
+            Some(looks_binary(&prefix))
+        })
+        .collect())
+}
+
+/// First `SNIFF_LEN` bytes of a loose object's content
+fn loose_prefix(objects_dir: &Path, oid: ObjectId) -> Option<Vec<u8>> {
+    let hex = oid.to_hex().to_string();
+    let file = std::fs::File::open(objects_dir.join(&hex[..2]).join(&hex[2..])).ok()?;
+    // "<kind> <size>\0" comes first
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(file).take(64 + SNIFF_LEN as u64).read_to_end(&mut inflated).ok()?;
+    let content = inflated.iter().position(|&b| b == 0)? + 1;
+    inflated.drain(..content);
+    inflated.truncate(SNIFF_LEN);
+    Some(inflated)
+}
+
+/// First `SNIFF_LEN` bytes of an object stored whole in one of the packs
+fn packed_prefix(objects_dir: &Path, oid: ObjectId) -> Option<Vec<u8>> {
+    let mut idx_paths = std::fs::read_dir(objects_dir.join("pack"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "idx"));
+    idx_paths.find_map(|idx_path| {
+        let idx = index::File::at(&idx_path, HashKind::Sha1).ok()?;
+        let offset = idx.pack_offset_at_index(idx.lookup(oid)?);
+        let pack = data::File::at(idx_path.with_extension("pack"), HashKind::Sha1).ok()?;
+        let entry = pack.entry(offset).ok()?;
+        if entry.header.is_delta() {
+            return None;
+        }
+        let compressed = pack.entry_slice(entry.data_offset..pack.pack_end() as u64)?;
+        let mut prefix = Vec::with_capacity(SNIFF_LEN);
+        ZlibDecoder::new(compressed).take(SNIFF_LEN as u64).read_to_end(&mut prefix).ok()?;
+        Some(prefix)
+    })
+}
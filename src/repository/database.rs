@@ -1,12 +1,66 @@
 use anyhow::{Context, Result};
-use sqlx::{sqlite::{SqliteConnectOptions, SqlitePoolOptions}, Pool, QueryBuilder, Row, Sqlite, Transaction};
+use serde::Deserialize;
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow}, Pool, QueryBuilder, Row, Sqlite, Transaction};
 use std::borrow::Cow;
 use std::str::FromStr;
 
-use crate::model::{LargeBlobInfo, TreeNode};
+use crate::model::{AuthorGrouping, AuthorTotals, BlobFilter, BlobLookup, BlobOccurrence, DeltaEfficiency, DirEntry, DuplicateBlob, EntryDetails, EntryKind, ExtensionStats, HeavyCommit, LargeBlobInfo, OldSizes, ScanDiagnostic, SearchQuery, SizeSnapshot, SnapshotEntry, SnapshotEntryKind, Tree, VendoredShare, VendoredSizes, vendored_rule};
+use crate::util::{extension_label, quarter_of, PathFilter};
 
+use super::snapshot::{Cell, ColumnKind, TableSpec};
 use super::SCHEMA_VERSION;
 
+/// Most variables SQLite binds in one statement; multi-row inserts are
+/// chunked to stay under it
+const MAX_VARIABLES: usize = 32766;
+
+/// `query_top_blobs` conditions
+const TOP_BLOBS_ALL: &str = "";
+const TOP_BLOBS_NOT_IN_HEAD: &str = "WHERE NOT EXISTS (SELECT 1 FROM head_blobs h WHERE h.oid = b.oid)";
+
+/// Files of the tree; a path that is also the parent of other paths is a
+/// directory in the tree
+const LEAF_PATHS: &str = "SELECT path, extension, vendored, cumulative_size, current_size, blob_count
+    FROM paths p
+    WHERE NOT EXISTS (SELECT 1 FROM paths c WHERE c.path >= p.path || '/' AND c.path < p.path || '0')";
+
+/// Whether the `LEAF_PATHS` row `l` was classified as binary
+const IS_BINARY: &str = "EXISTS (SELECT 1 FROM content_kinds k
+    WHERE k.binary = 1 AND k.extension = l.extension
+      AND k.path = CASE WHEN l.extension = '(no ext)' THEN l.path ELSE '' END)";
+
+/// A row of the `get_dir_children` query as an entry named after its last
+/// path component
+fn dir_entry(row: &SqliteRow) -> DirEntry {
+    let path: &str = row.get("path");
+    DirEntry {
+        name: path.rsplit_once('/').map_or(path, |(_, name)| name).to_string(),
+        is_dir: row.get::<i64, _>("is_dir") != 0,
+        cumulative_size: row.get::<i64, _>("cumulative_size") as u64,
+        current_size: row.get::<i64, _>("current_size") as u64,
+        blob_count: row.get::<i64, _>("blob_count") as u64,
+        deleted_size: row.get::<i64, _>("deleted_size") as u64,
+        kind: EntryKind::from_code(row.get("kind")),
+    }
+}
+
+/// `paths.extension`: `util::extension_label` of the file name
+fn path_extension(path: &str) -> Cow<'static, str> {
+    extension_label(path.rsplit_once('/').map_or(path, |(_, name)| name))
+}
+
+/// `paths.vendored`: the `model::vendored_rule` tagging `path`, `""` for
+/// none
+fn path_vendored(path: &str) -> &'static str {
+    vendored_rule(path).unwrap_or("")
+}
+
+/// `paths.parent` and `dirs.parent`: the directory holding `path`, `""` at
+/// the top level
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
 /// A blob record for database storage (zero-copy friendly)
 #[derive(Debug, Clone)]
 pub struct BlobRecord<'a> {
@@ -14,11 +68,17 @@ pub struct BlobRecord<'a> {
     pub path: Cow<'a, str>,
     pub cumulative_size: i64,
     pub current_size: i64,
+    pub kind: EntryKind,
 }
 
 impl<'a> BlobRecord<'a> {
     pub fn new(oid: [u8; 20], path: impl Into<Cow<'a, str>>, cumulative_size: i64, current_size: i64) -> Self {
-        Self { oid, path: path.into(), cumulative_size, current_size }
+        Self { oid, path: path.into(), cumulative_size, current_size, kind: EntryKind::File }
+    }
+
+    pub fn with_kind(mut self, kind: EntryKind) -> Self {
+        self.kind = kind;
+        self
     }
 }
 
@@ -29,7 +89,12 @@ pub struct BlobMetaRecord<'a> {
     pub size: i64,
     pub path: Cow<'a, str>,
     pub author: Cow<'a, str>,
+    pub email: Cow<'a, str>,
     pub timestamp: i64,
+    /// Commit that introduced the blob, whose added size it counts toward
+    pub commit: Option<[u8; 20]>,
+    /// That commit's subject
+    pub subject: Cow<'a, str>,
 }
 
 impl<'a> BlobMetaRecord<'a> {
@@ -38,26 +103,201 @@ impl<'a> BlobMetaRecord<'a> {
         size: i64,
         path: impl Into<Cow<'a, str>>,
         author: impl Into<Cow<'a, str>>,
+        email: impl Into<Cow<'a, str>>,
         timestamp: i64,
     ) -> Self {
-        Self { oid, size, path: path.into(), author: author.into(), timestamp }
+        Self { oid, size, path: path.into(), author: author.into(), email: email.into(), timestamp, commit: None, subject: Cow::Borrowed("") }
+    }
+
+    pub fn with_commit(mut self, commit: [u8; 20], subject: impl Into<Cow<'a, str>>) -> Self {
+        self.commit = Some(commit);
+        self.subject = subject.into();
+        self
+    }
+}
+
+/// A path of the path dictionary under the id the scanner interned it as
+#[derive(Debug, Clone)]
+pub struct PathRecord<'a> {
+    pub id: i64,
+    pub path: Cow<'a, str>,
+    /// The path's bytes when they aren't valid UTF-8 (`path` is then lossy),
+    /// so the next scan interns them under the same id; empty otherwise
+    pub raw: &'a [u8],
+}
+
+impl<'a> PathRecord<'a> {
+    /// `bytes` as the dictionary stores them
+    pub fn new(id: i64, bytes: &'a [u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(path) => Self { id, path: Cow::Borrowed(path), raw: &[] },
+            Err(_) => Self { id, path: String::from_utf8_lossy(bytes), raw: bytes },
+        }
+    }
+}
+
+/// A (blob, path) pair for database storage, by path dictionary id
+#[derive(Debug, Clone)]
+pub struct BlobPathRecord {
+    pub oid: [u8; 20],
+    pub path_id: i64,
+}
+
+impl BlobPathRecord {
+    pub fn new(oid: [u8; 20], path_id: i64) -> Self {
+        Self { oid, path_id }
+    }
+}
+
+/// A tree fully indexed at a path, for database storage, by path dictionary id
+#[derive(Debug, Clone)]
+pub struct TreePathRecord {
+    pub oid: [u8; 20],
+    pub path_id: i64,
+}
+
+impl TreePathRecord {
+    pub fn new(oid: [u8; 20], path_id: i64) -> Self {
+        Self { oid, path_id }
+    }
+}
+
+/// A path a commit removed, for database storage, by path dictionary id
+#[derive(Debug, Clone)]
+pub struct RemovalRecord {
+    pub path_id: i64,
+    /// Commit date
+    pub date: i64,
+}
+
+impl RemovalRecord {
+    pub fn new(path_id: i64, date: i64) -> Self {
+        Self { path_id, date }
+    }
+}
+
+/// A blob in the HEAD tree with its compressed size, for database storage
+#[derive(Debug, Clone)]
+pub struct HeadBlobRecord<'a> {
+    pub oid: [u8; 20],
+    pub path: Cow<'a, str>,
+    pub size: i64,
+    pub kind: EntryKind,
+}
+
+impl<'a> HeadBlobRecord<'a> {
+    pub fn new(oid: [u8; 20], path: impl Into<Cow<'a, str>>, size: i64) -> Self {
+        Self { oid, path: path.into(), size, kind: EntryKind::File }
+    }
+
+    pub fn with_kind(mut self, kind: EntryKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// An object the scanner could not read, for database storage
+#[derive(Debug, Clone)]
+pub struct SkippedRecord<'a> {
+    pub oid: [u8; 20],
+    pub kind: &'a str,
+    /// Where the scan met the object; may be empty
+    pub path: Cow<'a, str>,
+    pub reason: Cow<'a, str>,
+}
+
+impl<'a> SkippedRecord<'a> {
+    pub fn new(
+        oid: [u8; 20],
+        kind: &'a str,
+        path: impl Into<Cow<'a, str>>,
+        reason: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        Self { oid, kind, path: path.into(), reason: reason.into() }
+    }
+}
+
+/// Everything one scan writes, applied together by `apply_scan_with_callback`
+#[derive(Debug, Clone, Default)]
+pub struct ScanRecords<'a> {
+    /// Dictionary entries the other records refer to that aren't stored yet
+    pub paths: Vec<PathRecord<'a>>,
+    pub blobs: Vec<BlobRecord<'a>>,
+    pub metadata: Vec<BlobMetaRecord<'a>>,
+    pub blob_paths: Vec<BlobPathRecord>,
+    pub seen_trees: Vec<TreePathRecord>,
+    pub skipped: Vec<SkippedRecord<'a>>,
+    /// Paths that held a submodule commit
+    pub gitlinks: Vec<Cow<'a, str>>,
+    pub removals: Vec<RemovalRecord>,
+}
+
+/// SQLite settings for an index (the `[database]` config section)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DbTuning {
+    /// Page cache per connection, in MiB
+    pub cache_mb: u32,
+    /// Bytes of the file to memory-map, in MiB; 0 reads through the page cache
+    pub mmap_mb: u32,
+    /// WAL size, in pages, at which SQLite checkpoints it into the database
+    pub wal_autocheckpoint: u32,
+    /// Read-only connections for queries, so they don't wait behind a scan
+    /// writing; 0 sends them through the write connection
+    pub read_connections: u32,
+}
+
+impl Default for DbTuning {
+    fn default() -> Self {
+        Self {
+            cache_mb: 64,
+            mmap_mb: 0,
+            wal_autocheckpoint: 1000,
+            read_connections: 4,
+        }
+    }
+}
+
+impl DbTuning {
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(self.cache_mb > 0, "database.cache_mb must be at least 1");
+        Ok(())
+    }
+
+    /// PRAGMAs applied to every connection
+    fn apply(&self, options: SqliteConnectOptions) -> SqliteConnectOptions {
+        options
+            .pragma("temp_store", "MEMORY")
+            // Negative sizes are in KiB
+            .pragma("cache_size", format!("-{}", u64::from(self.cache_mb) * 1024))
+            .pragma("mmap_size", (u64::from(self.mmap_mb) << 20).to_string())
     }
 }
 
 /// Database abstraction for SQLite operations
+///
+/// Writes go through a single connection. Queries for the views go through
+/// `reader`, a pool of read-only connections that, thanks to WAL, see the
+/// last committed state while a scan is writing.
 pub struct Database {
     pool: Pool<Sqlite>,
+    reader: Pool<Sqlite>,
+    read_only: bool,
 }
 
 impl Database {
     /// Create a new database connection
     pub async fn new(db_path: &str) -> Result<Self> {
-        // Configure connection options with PRAGMAs applied to every connection
-        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=rwc", db_path))?
+        Self::with_tuning(db_path, &DbTuning::default()).await
+    }
+
+    /// Create a new database connection with `tuning` applied
+    pub async fn with_tuning(db_path: &str, tuning: &DbTuning) -> Result<Self> {
+        let options = tuning
+            .apply(SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=rwc", db_path))?)
             .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
             .synchronous(sqlx::sqlite::SqliteSynchronous::Normal)
-            .pragma("temp_store", "MEMORY")
-            .pragma("cache_size", "-64000"); // 64MB cache
+            .pragma("wal_autocheckpoint", tuning.wal_autocheckpoint.to_string());
 
         let pool = SqlitePoolOptions::new()
             .max_connections(1)
@@ -65,7 +305,62 @@ impl Database {
             .await
             .context("Failed to connect to database")?;
 
-        Ok(Self { pool })
+        // Every connection to ":memory:" is a database of its own
+        if tuning.read_connections == 0 || db_path == ":memory:" {
+            return Ok(Self { reader: pool.clone(), pool, read_only: false });
+        }
+        // Lazy, so the first reader connects after the writer switched to WAL
+        let read_options = tuning
+            .apply(SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=ro", db_path))?)
+            .read_only(true);
+        let reader = SqlitePoolOptions::new()
+            .max_connections(tuning.read_connections)
+            .connect_lazy_with(read_options);
+
+        Ok(Self { pool, reader, read_only: false })
+    }
+
+    /// Open an existing index for reading only, e.g. one copied from another
+    /// machine; writes fail
+    pub async fn open_read_only(db_path: &str) -> Result<Self> {
+        let options = DbTuning::default()
+            .apply(SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=ro", db_path))?)
+            .read_only(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .with_context(|| format!("Failed to open index {}", db_path))?;
+        Ok(Self { reader: pool.clone(), pool, read_only: true })
+    }
+
+    /// Whether this handle was opened with `open_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Close every connection, folding the write-ahead log into the database
+    /// file so it can be copied on its own
+    pub async fn close(self) {
+        self.reader.close().await;
+        self.pool.close().await;
+    }
+
+    /// A private index that lives as long as this handle and never touches disk
+    pub async fn in_memory() -> Result<Self> {
+        let options = DbTuning::default().apply(SqliteConnectOptions::from_str("sqlite::memory:")?);
+
+        // The data lives in the one connection, so the pool must never close it
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await
+            .context("Failed to create in-memory database")?;
+
+        Ok(Self { reader: pool.clone(), pool, read_only: false })
     }
 
     /// Ensure the metadata table exists
@@ -92,9 +387,19 @@ impl Database {
         sqlx::query("DROP TABLE IF EXISTS path_stats").execute(&self.pool).await?;
         sqlx::query("DROP TABLE IF EXISTS path_lookup").execute(&self.pool).await?;
         sqlx::query("DROP TABLE IF EXISTS paths").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS dirs").execute(&self.pool).await?;
         sqlx::query("DROP TABLE IF EXISTS seen_blobs").execute(&self.pool).await?;
         sqlx::query("DROP TABLE IF EXISTS scanned_commits").execute(&self.pool).await?;
         sqlx::query("DROP TABLE IF EXISTS blobs").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS blob_paths").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS head_blobs").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS path_history").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS commits").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS seen_trees").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS path_removals").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS scan_diagnostics").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS content_kinds").execute(&self.pool).await?;
+        sqlx::query("DROP TABLE IF EXISTS path_ids").execute(&self.pool).await?;
         sqlx::query("DELETE FROM metadata").execute(&self.pool).await?;
         Ok(())
     }
@@ -106,7 +411,24 @@ impl Database {
                 path TEXT PRIMARY KEY,
                 cumulative_size INTEGER NOT NULL,
                 current_size INTEGER NOT NULL,
-                blob_count INTEGER NOT NULL
+                blob_count INTEGER NOT NULL,
+                kind INTEGER NOT NULL DEFAULT 0,
+                extension TEXT NOT NULL,
+                vendored TEXT NOT NULL,
+                parent TEXT NOT NULL
+            )"
+        ).execute(&self.pool).await?;
+
+        // Totals of every directory below the root; kept up to date by
+        // `refresh_dirs_in_tx` so one level loads without the rest
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS dirs (
+                path TEXT PRIMARY KEY,
+                parent TEXT NOT NULL,
+                cumulative_size INTEGER NOT NULL,
+                current_size INTEGER NOT NULL,
+                blob_count INTEGER NOT NULL,
+                deleted_size INTEGER NOT NULL
             )"
         ).execute(&self.pool).await?;
 
@@ -128,10 +450,166 @@ impl Database {
                 size INTEGER NOT NULL,
                 path TEXT NOT NULL,
                 first_author TEXT NOT NULL,
-                first_date INTEGER NOT NULL
+                first_email TEXT NOT NULL,
+                first_date INTEGER NOT NULL,
+                first_subject TEXT NOT NULL
+            )"
+        ).execute(&self.pool).await?;
+
+        // Every path the scanner interned, under its id; rows seen per
+        // commit refer to paths by id
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS path_ids (
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL,
+                raw BLOB NOT NULL
+            )"
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS blob_paths (
+                oid BLOB NOT NULL,
+                path_id INTEGER NOT NULL,
+                PRIMARY KEY (oid, path_id)
+            ) WITHOUT ROWID"
+        ).execute(&self.pool).await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS head_blobs (
+                path TEXT PRIMARY KEY,
+                oid BLOB NOT NULL,
+                size INTEGER NOT NULL,
+                kind INTEGER NOT NULL DEFAULT 0
+            )"
+        ).execute(&self.pool).await?;
+
+        // Bytes first committed at each path, per calendar quarter
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS path_history (
+                path TEXT NOT NULL,
+                quarter INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                PRIMARY KEY (path, quarter)
+            ) WITHOUT ROWID"
+        ).execute(&self.pool).await?;
+
+        // Blob data each commit was first to add, with the largest such
+        // blob's path
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS commits (
+                oid BLOB PRIMARY KEY,
+                author TEXT NOT NULL,
+                email TEXT NOT NULL,
+                date INTEGER NOT NULL,
+                subject TEXT NOT NULL,
+                added_size INTEGER NOT NULL,
+                blob_count INTEGER NOT NULL,
+                largest_path TEXT NOT NULL,
+                largest_size INTEGER NOT NULL
+            )"
+        ).execute(&self.pool).await?;
+
+        // Trees whose whole subtree is indexed at that path; the scanner skips them
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS seen_trees (
+                oid BLOB NOT NULL,
+                path_id INTEGER NOT NULL,
+                PRIMARY KEY (oid, path_id)
+            ) WITHOUT ROWID"
+        ).execute(&self.pool).await?;
+
+        // Latest date a scanned commit removed each path; a directory's
+        // stands for everything it held
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS path_removals (
+                path_id INTEGER PRIMARY KEY,
+                date INTEGER NOT NULL
             )"
         ).execute(&self.pool).await?;
 
+        // Objects that couldn't be read, whose contents are missing from the
+        // results, and anomalies the scan worked around
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS scan_diagnostics (
+                oid BLOB PRIMARY KEY,
+                kind TEXT NOT NULL,
+                path TEXT NOT NULL,
+                reason TEXT NOT NULL
+            )"
+        ).execute(&self.pool).await?;
+
+        // Binary or text, per extension (`path` empty) or, for files without
+        // one, per path
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS content_kinds (
+                extension TEXT NOT NULL,
+                path TEXT NOT NULL,
+                binary INTEGER NOT NULL,
+                PRIMARY KEY (extension, path)
+            ) WITHOUT ROWID"
+        ).execute(&self.pool).await?;
+
+        // Paths the user bookmarked; not scan data, so schema rebuilds and
+        // `clear_index` keep them
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS bookmarks (
+                path TEXT PRIMARY KEY
+            )"
+        ).execute(&self.pool).await?;
+
+        // Totals recorded by `repodiet snapshot`, a history the index can't
+        // rebuild, so kept like bookmarks
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS size_snapshots (
+                scope TEXT NOT NULL,
+                taken_at INTEGER NOT NULL,
+                head TEXT NOT NULL,
+                cumulative_size INTEGER NOT NULL,
+                current_size INTEGER NOT NULL,
+                deleted_size INTEGER NOT NULL,
+                blob_count INTEGER NOT NULL,
+                largest_blob INTEGER NOT NULL,
+                PRIMARY KEY (scope, taken_at)
+            ) WITHOUT ROWID"
+        ).execute(&self.pool).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS size_snapshot_entries (
+                scope TEXT NOT NULL,
+                taken_at INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                cumulative_size INTEGER NOT NULL,
+                current_size INTEGER NOT NULL,
+                PRIMARY KEY (scope, taken_at, kind, name)
+            ) WITHOUT ROWID"
+        ).execute(&self.pool).await?;
+
+        // One directory's entries
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS paths_parent ON paths (parent)"
+        ).execute(&self.pool).await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS dirs_parent ON dirs (parent)"
+        ).execute(&self.pool).await?;
+
+        // Per-extension totals and budgets
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS paths_extension ON paths (extension)"
+        ).execute(&self.pool).await?;
+
+        // Every version committed at a path, for delta efficiency
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS blob_paths_path ON blob_paths (path_id)"
+        ).execute(&self.pool).await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS path_ids_path ON path_ids (path)"
+        ).execute(&self.pool).await?;
+
+        // "Is this blob still in HEAD?" is asked by OID
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS head_blobs_oid ON head_blobs (oid)"
+        ).execute(&self.pool).await?;
+
         Ok(())
     }
 
@@ -153,7 +631,7 @@ impl Database {
 
         if needs_rebuild {
             if stored_version.is_some() {
-                eprintln!("Schema version changed ({} -> {}), rebuilding index...",
+                tracing::info!("Schema version changed ({} -> {}), rebuilding index...",
                     stored_version.unwrap_or_default(), SCHEMA_VERSION);
             }
             self.drop_old_tables().await?;
@@ -168,6 +646,13 @@ impl Database {
         Ok(needs_rebuild)
     }
 
+    /// Delete every scanned row and all metadata but the schema version
+    pub async fn clear_index(&self) -> Result<()> {
+        self.drop_old_tables().await?;
+        self.create_tables().await?;
+        self.write_schema_version().await
+    }
+
     /// Get metadata value by key
     pub async fn get_metadata(&self, key: &str) -> Option<String> {
         sqlx::query("SELECT value FROM metadata WHERE key = ?")
@@ -190,110 +675,1277 @@ impl Database {
     }
 
     /// Load tree from database
-    pub async fn load_tree(&self) -> Result<TreeNode> {
-        let rows = sqlx::query("SELECT path, cumulative_size, current_size, blob_count FROM paths")
+    pub async fn load_tree(&self) -> Result<Tree> {
+        let rows = sqlx::query("SELECT path, cumulative_size, current_size, blob_count, kind FROM paths")
             .fetch_all(&self.pool)
             .await?;
 
-        let mut root = TreeNode::new("(root)");
+        let mut root = Tree::new();
 
         for row in rows {
             let path: String = row.get("path");
             let cumulative: i64 = row.get("cumulative_size");
             let current: i64 = row.get("current_size");
             let count: i64 = row.get("blob_count");
+            let kind = EntryKind::from_code(row.get("kind"));
 
             let parts: Vec<&str> = path.split('/').collect();
-            root.add_path_with_sizes(&parts, cumulative as u64, current as u64, count as u64);
+            root.add_entry(&parts, cumulative as u64, current as u64, count as u64, kind);
         }
 
         root.compute_totals();
         Ok(root)
     }
 
-    /// Get top N largest blobs
-    pub async fn get_top_blobs(&self, limit: usize) -> Result<Vec<LargeBlobInfo>> {
+    /// The entries directly inside `dir` (`""` for the root), in name order,
+    /// with each directory's totals read from the `dirs` table
+    ///
+    /// Gives one level of the tree without loading the rest of it. A path a
+    /// scan saw both as a file and as a directory is the directory, as in
+    /// `load_tree`.
+    pub async fn get_dir_children(&self, dir: &str) -> Result<Vec<DirEntry>> {
         let rows = sqlx::query(
-            "SELECT oid, size, path, first_author, first_date FROM blobs ORDER BY size DESC LIMIT ?"
+            "SELECT path, 1 AS is_dir, cumulative_size, current_size, blob_count, deleted_size, 0 AS kind
+             FROM dirs WHERE parent = ?1
+             UNION ALL
+             SELECT path, 0, cumulative_size, current_size, blob_count,
+                    CASE WHEN current_size = 0 THEN cumulative_size ELSE 0 END, kind
+             FROM paths p WHERE parent = ?1 AND NOT EXISTS (SELECT 1 FROM dirs d WHERE d.path = p.path)
+             ORDER BY path"
         )
-        .bind(limit as i64)
-        .fetch_all(&self.pool)
+        .bind(dir)
+        .fetch_all(&self.reader)
         .await?;
 
-        Ok(rows.into_iter().map(|row| {
-            LargeBlobInfo {
-                oid: row.get("oid"),
-                size: row.get::<i64, _>("size") as u64,
-                path: row.get("path"),
-                first_author: row.get("first_author"),
-                first_date: row.get("first_date"),
-            }
-        }).collect())
+        Ok(rows.iter().map(dir_entry).collect())
     }
 
-    /// Check if a commit has been scanned (used by tests)
-    #[allow(dead_code)]
-    pub async fn is_commit_scanned(&self, oid: &[u8; 20]) -> bool {
-        sqlx::query("SELECT 1 FROM scanned_commits WHERE oid = ?")
-            .bind(oid.as_slice())
-            .fetch_optional(&self.pool)
-            .await
-            .ok()
-            .flatten()
-            .is_some()
+    /// The file or directory at `path`, with the totals `get_dir_children`
+    /// would list it with, or `None` when no scan saw it
+    pub async fn get_path_entry(&self, path: &str) -> Result<Option<DirEntry>> {
+        let row = sqlx::query(
+            "SELECT path, 1 AS is_dir, cumulative_size, current_size, blob_count, deleted_size, 0 AS kind
+             FROM dirs WHERE path = ?1
+             UNION ALL
+             SELECT path, 0, cumulative_size, current_size, blob_count,
+                    CASE WHEN current_size = 0 THEN cumulative_size ELSE 0 END, kind
+             FROM paths p WHERE path = ?1 AND NOT EXISTS (SELECT 1 FROM dirs d WHERE d.path = p.path)"
+        )
+        .bind(path)
+        .fetch_optional(&self.reader)
+        .await?;
+        Ok(row.as_ref().map(dir_entry))
     }
 
-    /// Load all scanned commit OIDs into a HashSet for fast lookup
-    /// Returns raw 20-byte SHA-1 hashes
-    pub async fn load_scanned_commit_oids(&self) -> rustc_hash::FxHashSet<[u8; 20]> {
-        let rows: Vec<Vec<u8>> = sqlx::query_scalar("SELECT oid FROM scanned_commits")
-            .fetch_all(&self.pool)
-            .await
-            .unwrap_or_default();
-        rows.into_iter()
-            .filter_map(|v| v.try_into().ok())
-            .collect()
+    /// The file with the largest cumulative size below `dir` (`""` for the
+    /// whole tree), as (path, size); with `deleted_only` only files gone from
+    /// HEAD count, and ties go to the first path
+    pub async fn get_largest_file(&self, dir: &str, deleted_only: bool) -> Result<Option<(String, u64)>> {
+        let sql = format!(
+            "SELECT path, cumulative_size FROM ({})
+             WHERE cumulative_size > 0
+               AND (?1 = '' OR (path >= ?1 || '/' AND path < ?1 || '0'))
+               AND (?2 = 0 OR current_size = 0)
+             ORDER BY cumulative_size DESC, path LIMIT 1",
+            LEAF_PATHS
+        );
+        let row = sqlx::query(&sql)
+            .bind(dir)
+            .bind(deleted_only)
+            .fetch_optional(&self.reader)
+            .await?;
+        Ok(row.map(|row| (row.get("path"), row.get::<i64, _>("cumulative_size") as u64)))
     }
 
-    /// Load all previously seen blob OIDs
-    /// Returns raw 20-byte SHA-1 hashes
-    pub async fn load_seen_blobs(&self) -> Result<rustc_hash::FxHashSet<[u8; 20]>> {
-        let rows = sqlx::query("SELECT oid FROM seen_blobs")
-            .fetch_all(&self.pool)
+    /// Sizes per extension and the largest files below `dir`, `limit` of
+    /// each; with `deleted_only` only files gone from HEAD count
+    pub async fn get_entry_details(&self, dir: &str, deleted_only: bool, limit: usize) -> Result<EntryDetails> {
+        let below = "cumulative_size > 0
+               AND (?1 = '' OR (path >= ?1 || '/' AND path < ?1 || '0'))
+               AND (?2 = 0 OR current_size = 0)";
+        let sql = format!(
+            "SELECT extension, SUM(cumulative_size) AS size FROM ({}) WHERE {}
+             GROUP BY extension ORDER BY size DESC, extension LIMIT ?3",
+            LEAF_PATHS, below
+        );
+        let extensions = sqlx::query(&sql)
+            .bind(dir)
+            .bind(deleted_only)
+            .bind(limit as i64)
+            .fetch_all(&self.reader)
             .await?;
-        Ok(rows.iter()
-            .filter_map(|row| {
-                let v: Vec<u8> = row.get("oid");
-                v.try_into().ok()
-            })
-            .collect())
+        let sql = format!(
+            "SELECT path, cumulative_size FROM ({}) WHERE {} ORDER BY cumulative_size DESC, path LIMIT ?3",
+            LEAF_PATHS, below
+        );
+        let largest = sqlx::query(&sql)
+            .bind(dir)
+            .bind(deleted_only)
+            .bind(limit as i64)
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(EntryDetails {
+            extensions: extensions.iter().map(|row| (row.get("extension"), row.get::<i64, _>("size") as u64)).collect(),
+            largest: largest.iter().map(|row| (row.get("path"), row.get::<i64, _>("cumulative_size") as u64)).collect(),
+        })
     }
 
-    /// Save batch of new blobs with a callback for progress
-    pub async fn save_blobs_with_callback<F>(
-        &self,
-        blobs: &[BlobRecord<'_>],
-        mut on_progress: F,
-    ) -> Result<()>
-    where
-        F: FnMut(usize),
-    {
-        let mut tx = self.pool.begin().await?;
-        self.save_blobs_in_tx(&mut tx, blobs, &mut on_progress)
-            .await?;
-        tx.commit().await?;
-        Ok(())
+    /// Cumulative size of the files below `dir` (everything with `""`)
+    /// classified as binary, leaving out paths `filter` excludes
+    pub async fn get_binary_size(&self, dir: &str, filter: &PathFilter) -> Result<u64> {
+        let sql = format!(
+            "SELECT path, cumulative_size FROM ({}) l
+             WHERE (?1 = '' OR (path >= ?1 || '/' AND path < ?1 || '0')) AND {}",
+            LEAF_PATHS, IS_BINARY
+        );
+        let rows = sqlx::query(&sql).bind(dir).fetch_all(&self.reader).await?;
+        Ok(rows
+            .iter()
+            .filter(|row| !filter.is_excluded(row.get("path")))
+            .map(|row| row.get::<i64, _>("cumulative_size") as u64)
+            .sum())
     }
 
-    /// Save blob metadata with a callback for progress
-    pub async fn save_blob_metadata_with_callback<F>(
-        &self,
-        metadata: &[BlobMetaRecord<'_>],
-        mut on_progress: F,
-    ) -> Result<()>
-    where
-        F: FnMut(usize),
-    {
+    /// History held by the files each `model::vendored_rule` tags, most
+    /// first, leaving out paths `filter` excludes
+    pub async fn get_vendored_shares(&self, filter: &PathFilter) -> Result<Vec<VendoredShare>> {
+        let sql = format!("SELECT path, vendored, cumulative_size, current_size FROM ({}) WHERE vendored != ''", LEAF_PATHS);
+        let rows = sqlx::query(&sql).fetch_all(&self.reader).await?;
+        let mut shares: Vec<VendoredShare> = Vec::new();
+        for row in rows.iter().filter(|row| !filter.is_excluded(row.get("path"))) {
+            let rule: &str = row.get("vendored");
+            let share = match shares.iter_mut().position(|s| s.rule == rule) {
+                Some(i) => &mut shares[i],
+                None => {
+                    shares.push(VendoredShare { rule: rule.to_string(), files: 0, cumulative: 0, current: 0 });
+                    shares.last_mut().expect("pushed above")
+                }
+            };
+            share.files += 1;
+            share.cumulative += row.get::<i64, _>("cumulative_size") as u64;
+            share.current += row.get::<i64, _>("current_size") as u64;
+        }
+        shares.sort_by(|a, b| b.cumulative.cmp(&a.cumulative).then_with(|| a.rule.cmp(&b.rule)));
+        Ok(shares)
+    }
+
+    /// Tagged files below each entry directly inside `dir` (`""` for the
+    /// root), by entry name; entries without any are left out
+    pub async fn get_vendored_sizes(&self, dir: &str, filter: &PathFilter) -> Result<Vec<(String, VendoredSizes)>> {
+        let sql = format!(
+            "SELECT path, cumulative_size, current_size FROM ({})
+             WHERE vendored != '' AND (?1 = '' OR (path >= ?1 || '/' AND path < ?1 || '0'))
+             ORDER BY path",
+            LEAF_PATHS
+        );
+        let rows = sqlx::query(&sql).bind(dir).fetch_all(&self.reader).await?;
+        let skip = if dir.is_empty() { 0 } else { dir.len() + 1 };
+        let mut sizes: Vec<(String, VendoredSizes)> = Vec::new();
+        for row in rows {
+            let path: &str = row.get("path");
+            if filter.is_excluded(path) {
+                continue;
+            }
+            let rest = &path[skip..];
+            let name = rest.split_once('/').map_or(rest, |(name, _)| name);
+            // Rows come in path order, so an entry's files are together
+            if sizes.last().is_none_or(|(last, _)| last != name) {
+                sizes.push((name.to_string(), VendoredSizes::default()));
+            }
+            let entry = &mut sizes.last_mut().expect("pushed above").1;
+            let cumulative = row.get::<i64, _>("cumulative_size") as u64;
+            entry.files += 1;
+            entry.cumulative += cumulative;
+            if row.get::<i64, _>("current_size") == 0 {
+                entry.deleted += cumulative;
+            }
+        }
+        Ok(sizes)
+    }
+
+    /// Blob data first committed before `before` (a Unix timestamp) below
+    /// each entry directly inside `dir` (`""` for the root), by entry name;
+    /// entries without any are left out
+    pub async fn get_old_sizes(&self, dir: &str, before: i64, filter: &PathFilter) -> Result<Vec<(String, OldSizes)>> {
+        let rows = sqlx::query(
+            "SELECT b.path, SUM(b.size) AS size, p.current_size FROM blobs b JOIN paths p ON p.path = b.path
+             WHERE b.first_date > 0 AND b.first_date < ?2
+               AND (?1 = '' OR (b.path >= ?1 || '/' AND b.path < ?1 || '0'))
+             GROUP BY b.path
+             ORDER BY b.path"
+        )
+        .bind(dir)
+        .bind(before)
+        .fetch_all(&self.reader)
+        .await?;
+        let skip = if dir.is_empty() { 0 } else { dir.len() + 1 };
+        let mut sizes: Vec<(String, OldSizes)> = Vec::new();
+        for row in rows {
+            let path: &str = row.get("path");
+            if filter.is_excluded(path) {
+                continue;
+            }
+            let rest = &path[skip..];
+            let name = rest.split_once('/').map_or(rest, |(name, _)| name);
+            // Rows come in path order, so an entry's files are together
+            if sizes.last().is_none_or(|(last, _)| last != name) {
+                sizes.push((name.to_string(), OldSizes::default()));
+            }
+            let entry = &mut sizes.last_mut().expect("pushed above").1;
+            let size = row.get::<i64, _>("size") as u64;
+            entry.cumulative += size;
+            if row.get::<i64, _>("current_size") == 0 {
+                entry.deleted += size;
+            }
+        }
+        Ok(sizes)
+    }
+
+    /// When the deleted files below each entry directly inside `dir` (`""`
+    /// for the root) were last deleted, by entry name, as the latest commit
+    /// date; entries whose deleted files no scanned commit removed (they
+    /// only ever lived on a side branch, say) are left out
+    ///
+    /// A file's date is that of the last removal of the file itself or,
+    /// failing that, of the nearest directory above it.
+    pub async fn get_deletion_dates(&self, dir: &str, filter: &PathFilter) -> Result<Vec<(String, i64)>> {
+        let sql = format!(
+            "SELECT path FROM ({})
+             WHERE current_size = 0 AND cumulative_size > 0
+               AND (?1 = '' OR (path >= ?1 || '/' AND path < ?1 || '0'))
+             ORDER BY path",
+            LEAF_PATHS
+        );
+        let deleted: Vec<String> = sqlx::query_scalar(&sql).bind(dir).fetch_all(&self.reader).await?;
+
+        // Removals of paths below `dir`, of `dir` itself and of its ancestors
+        let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "SELECT i.path, r.date FROM path_removals r JOIN path_ids i ON i.id = r.path_id
+             WHERE ("
+        );
+        qb.push_bind(dir)
+            .push(" = '' OR (i.path >= ")
+            .push_bind(dir)
+            .push(" || '/' AND i.path < ")
+            .push_bind(dir)
+            .push(" || '0'))");
+        let mut ancestor = dir;
+        while !ancestor.is_empty() {
+            qb.push(" OR i.path = ").push_bind(ancestor);
+            ancestor = parent_dir(ancestor);
+        }
+        let mut removed: rustc_hash::FxHashMap<String, i64> = rustc_hash::FxHashMap::default();
+        for row in qb.build().fetch_all(&self.reader).await? {
+            let date: i64 = row.get("date");
+            let latest = removed.entry(row.get("path")).or_insert(date);
+            *latest = (*latest).max(date);
+        }
+
+        let skip = if dir.is_empty() { 0 } else { dir.len() + 1 };
+        let mut dates: Vec<(String, i64)> = Vec::new();
+        for path in &deleted {
+            if filter.is_excluded(path) {
+                continue;
+            }
+            let mut removal = path.as_str();
+            let date = loop {
+                if let Some(date) = removed.get(removal) {
+                    break Some(*date);
+                }
+                match removal.rsplit_once('/') {
+                    Some((parent, _)) => removal = parent,
+                    None => break None,
+                }
+            };
+            let Some(date) = date else { continue };
+            let rest = &path[skip..];
+            let name = rest.split_once('/').map_or(rest, |(name, _)| name);
+            // Rows come in path order, so an entry's files are together
+            match dates.last_mut() {
+                Some((last, latest)) if last == name => *latest = (*latest).max(date),
+                _ => dates.push((name.to_string(), date)),
+            }
+        }
+        Ok(dates)
+    }
+
+    /// What still needs classifying as binary or text, as (extension,
+    /// path, sample blobs): up to `samples` of the smallest blobs of each
+    /// extension with `path` empty, and for files without an extension the
+    /// smallest version of each path
+    pub async fn get_unclassified_content(&self, samples: usize) -> Result<Vec<(String, String, Vec<Vec<u8>>)>> {
+        let extensions: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT extension FROM paths p
+             WHERE extension <> '(no ext)'
+               AND NOT EXISTS (SELECT 1 FROM content_kinds k WHERE k.extension = p.extension AND k.path = '')
+             ORDER BY extension"
+        )
+        .fetch_all(&self.reader)
+        .await?;
+
+        let mut unclassified = Vec::with_capacity(extensions.len());
+        for extension in extensions {
+            let oids: Vec<Vec<u8>> = sqlx::query_scalar(
+                "SELECT b.oid FROM paths p
+                 JOIN path_ids d ON d.path = p.path
+                 JOIN blob_paths bp ON bp.path_id = d.id
+                 JOIN blobs b ON b.oid = bp.oid
+                 WHERE p.extension = ? AND b.size > 0
+                 ORDER BY b.size, b.oid LIMIT ?"
+            )
+            .bind(&extension)
+            .bind(samples as i64)
+            .fetch_all(&self.reader)
+            .await?;
+            if !oids.is_empty() {
+                unclassified.push((extension, String::new(), oids));
+            }
+        }
+
+        let rows = sqlx::query(
+            "SELECT p.path, (
+                SELECT bp.oid FROM path_ids d
+                JOIN blob_paths bp ON bp.path_id = d.id
+                JOIN blobs b ON b.oid = bp.oid
+                WHERE d.path = p.path AND b.size > 0 ORDER BY b.size, b.oid LIMIT 1
+             ) AS oid
+             FROM paths p
+             WHERE p.extension = '(no ext)'
+               AND NOT EXISTS (SELECT 1 FROM content_kinds k WHERE k.extension = p.extension AND k.path = p.path)
+             ORDER BY p.path"
+        )
+        .fetch_all(&self.reader)
+        .await?;
+        for row in rows {
+            if let Some(oid) = row.get::<Option<Vec<u8>>, _>("oid") {
+                unclassified.push(("(no ext)".to_string(), row.get("path"), vec![oid]));
+            }
+        }
+        Ok(unclassified)
+    }
+
+    /// Record (extension, path, binary) verdicts for
+    /// `get_unclassified_content`'s entries
+    pub async fn save_content_kinds(&self, kinds: &[(String, String, bool)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for chunk in kinds.chunks(5000) {
+            let mut qb: QueryBuilder<Sqlite> =
+                QueryBuilder::new("INSERT OR REPLACE INTO content_kinds (extension, path, binary) ");
+            qb.push_values(chunk, |mut row, (extension, path, binary)| {
+                row.push_bind(extension).push_bind(path).push_bind(*binary);
+            });
+            qb.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Files whose path contains the text of `query` (ASCII
+    /// case-insensitively unless `case_sensitive`) and that pass its size and
+    /// extension predicates (`SearchQuery`), as (path, cumulative size,
+    /// current size), largest first; with `deleted_only` only files gone
+    /// from HEAD
+    ///
+    /// Searches the index when the tree isn't loaded in full.
+    pub async fn search_paths(
+        &self,
+        query: &str,
+        deleted_only: bool,
+        case_sensitive: bool,
+        limit: usize,
+    ) -> Result<Vec<(String, u64, u64)>> {
+        let query = SearchQuery::parse(query);
+        let (haystack, needle) = match case_sensitive {
+            true => ("path", query.text),
+            false => ("lower(path)", query.text.to_lowercase()),
+        };
+        let sql = format!(
+            "SELECT path, cumulative_size, current_size FROM ({})
+             WHERE instr({}, ?1) > 0 AND (?2 = 0 OR current_size = 0)
+               AND cumulative_size BETWEEN ?4 AND ?5 AND (?6 IS NULL OR extension = ?6)
+             ORDER BY cumulative_size DESC, path LIMIT ?3",
+            LEAF_PATHS, haystack
+        );
+        let rows = sqlx::query(&sql)
+            .bind(needle)
+            .bind(deleted_only)
+            .bind(limit as i64)
+            .bind(query.min_size.min(i64::MAX as u64) as i64)
+            .bind(query.max_size.min(i64::MAX as u64) as i64)
+            .bind(query.extension)
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(rows.into_iter().map(|row| (
+            row.get("path"),
+            row.get::<i64, _>("cumulative_size") as u64,
+            row.get::<i64, _>("current_size") as u64,
+        )).collect())
+    }
+
+    /// (path, cumulative size, current size) of every file in the tree, in
+    /// path order, leaving out paths `filter` excludes
+    pub async fn get_file_sizes(&self, filter: &PathFilter) -> Result<Vec<(String, u64, u64)>> {
+        let sql = format!("SELECT path, cumulative_size, current_size FROM ({}) ORDER BY path", LEAF_PATHS);
+        let rows = sqlx::query(&sql).fetch_all(&self.reader).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (
+                row.get::<String, _>("path"),
+                row.get::<i64, _>("cumulative_size") as u64,
+                row.get::<i64, _>("current_size") as u64,
+            ))
+            .filter(|(path, _, _)| !filter.is_excluded(path))
+            .collect())
+    }
+
+    /// Directories at any depth whose name is one of `names`, as (path,
+    /// cumulative size, current size, deleted size), in path order
+    pub async fn get_dirs_named(&self, names: &[&str]) -> Result<Vec<(String, u64, u64, u64)>> {
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut qb: QueryBuilder<Sqlite> =
+            QueryBuilder::new("SELECT path, cumulative_size, current_size, deleted_size FROM dirs WHERE ");
+        // The last component is compared as a string; LIKE would treat `_` as a wildcard
+        for (i, name) in names.iter().enumerate() {
+            if i > 0 {
+                qb.push(" OR ");
+            }
+            qb.push("path = ").push_bind(*name);
+            qb.push(" OR substr(path, -").push_bind(name.len() as i64 + 1).push(") = ").push_bind(format!("/{}", name));
+        }
+        qb.push(" ORDER BY path");
+        let rows = qb.build().fetch_all(&self.reader).await?;
+        Ok(rows.into_iter().map(|row| (
+            row.get("path"),
+            row.get::<i64, _>("cumulative_size") as u64,
+            row.get::<i64, _>("current_size") as u64,
+            row.get::<i64, _>("deleted_size") as u64,
+        )).collect())
+    }
+
+    /// Size totals of every file extension (`util::extension_label`) in the
+    /// tree, in extension order, leaving out paths `filter` excludes
+    ///
+    /// Matches walking the leaves of `load_tree` without building it.
+    pub async fn get_extension_stats(&self, filter: &PathFilter) -> Result<Vec<(String, ExtensionStats)>> {
+        // Exclusion patterns are matched here, so with any set the rows are
+        // summed here too
+        if !filter.is_empty() {
+            let sql = format!("SELECT l.*, {} AS binary FROM ({}) l ORDER BY extension", IS_BINARY, LEAF_PATHS);
+            let rows = sqlx::query(&sql).fetch_all(&self.reader).await?;
+            let mut stats: Vec<(String, ExtensionStats)> = Vec::new();
+            for row in rows {
+                if filter.is_excluded(row.get("path")) {
+                    continue;
+                }
+                let extension: String = row.get("extension");
+                if stats.last().is_none_or(|(last, _)| *last != extension) {
+                    stats.push((extension, ExtensionStats::default()));
+                }
+                let entry = &mut stats.last_mut().expect("pushed above").1;
+                let cumulative = row.get::<i64, _>("cumulative_size") as u64;
+                let current = row.get::<i64, _>("current_size") as u64;
+                entry.cumulative_size += cumulative;
+                entry.current_size += current;
+                if current == 0 {
+                    entry.deleted_size += cumulative;
+                }
+                if row.get::<bool, _>("binary") {
+                    entry.binary_size += cumulative;
+                }
+                entry.file_count += row.get::<i64, _>("blob_count") as u64;
+            }
+            return Ok(stats);
+        }
+
+        let sql = format!(
+            "SELECT extension,
+                    SUM(cumulative_size) AS cumulative_size,
+                    SUM(current_size) AS current_size,
+                    SUM(CASE WHEN current_size = 0 THEN cumulative_size ELSE 0 END) AS deleted_size,
+                    SUM(blob_count) AS file_count,
+                    SUM(CASE WHEN {} THEN cumulative_size ELSE 0 END) AS binary_size
+             FROM ({}) l GROUP BY extension ORDER BY extension",
+            IS_BINARY, LEAF_PATHS
+        );
+        let rows = sqlx::query(&sql).fetch_all(&self.reader).await?;
+        Ok(rows.into_iter().map(|row| {
+            let stats = ExtensionStats {
+                cumulative_size: row.get::<i64, _>("cumulative_size") as u64,
+                current_size: row.get::<i64, _>("current_size") as u64,
+                deleted_size: row.get::<i64, _>("deleted_size") as u64,
+                file_count: row.get::<i64, _>("file_count") as u64,
+                binary_size: row.get::<i64, _>("binary_size") as u64,
+            };
+            (row.get("extension"), stats)
+        }).collect())
+    }
+
+    /// Get top N largest blobs
+    pub async fn get_top_blobs(&self, limit: usize) -> Result<Vec<LargeBlobInfo>> {
+        self.query_top_blobs(TOP_BLOBS_ALL, &[], limit, 0).await
+    }
+
+    /// Get top N largest blobs that no path in HEAD still points at
+    pub async fn get_top_blobs_not_in_head(&self, limit: usize) -> Result<Vec<LargeBlobInfo>> {
+        self.query_top_blobs(TOP_BLOBS_NOT_IN_HEAD, &[], limit, 0).await
+    }
+
+    /// Like `get_top_blobs` (or `get_top_blobs_not_in_head`), skipping blobs
+    /// whose path `filter` excludes
+    pub async fn get_top_blobs_filtered(
+        &self,
+        limit: usize,
+        not_in_head: bool,
+        filter: &PathFilter,
+    ) -> Result<Vec<LargeBlobInfo>> {
+        let condition = if not_in_head { TOP_BLOBS_NOT_IN_HEAD } else { TOP_BLOBS_ALL };
+        self.query_top_blobs_filtered(condition, &[], limit, filter).await
+    }
+
+    /// Top N largest blobs first committed after `since` (a Unix
+    /// timestamp), skipping blobs whose path `filter` excludes
+    pub async fn get_top_blobs_since(&self, since: i64, limit: usize, filter: &PathFilter) -> Result<Vec<LargeBlobInfo>> {
+        self.query_top_blobs_filtered(&format!("WHERE b.first_date > {}", since), &[], limit, filter).await
+    }
+
+    /// Top N largest blobs whose path `matching` accepts (ignoring case),
+    /// only those not in HEAD with `not_in_head`, skipping blobs whose path
+    /// `filter` excludes
+    pub async fn get_top_blobs_matching(
+        &self,
+        limit: usize,
+        not_in_head: bool,
+        matching: &BlobFilter,
+        filter: &PathFilter,
+    ) -> Result<Vec<LargeBlobInfo>> {
+        // Compared with substr/instr; LIKE would treat `_` and `%` as wildcards
+        let (sql, value) = match matching {
+            BlobFilter::Extension(ext) => ("substr(lower(b.path), -length(?1)) = ?1", ext.clone()),
+            BlobFilter::PathPrefix(prefix) => ("substr(lower(b.path), 1, length(?1)) = ?1", prefix.to_ascii_lowercase()),
+            BlobFilter::PathContains(text) => ("instr(lower(b.path), ?1) > 0", text.to_ascii_lowercase()),
+        };
+        let base = if not_in_head { TOP_BLOBS_NOT_IN_HEAD } else { "WHERE 1" };
+        let condition = format!("{} AND {}", base, sql);
+        self.query_top_blobs_filtered(&condition, &[value], limit, filter).await
+    }
+
+    async fn query_top_blobs_filtered(
+        &self,
+        condition: &str,
+        binds: &[String],
+        limit: usize,
+        filter: &PathFilter,
+    ) -> Result<Vec<LargeBlobInfo>> {
+        let mut blobs = Vec::with_capacity(limit);
+        let mut offset = 0;
+        loop {
+            let page = self.query_top_blobs(condition, binds, limit, offset).await?;
+            let exhausted = page.len() < limit;
+            offset += page.len();
+            blobs.extend(page.into_iter().filter(|blob| !filter.is_excluded(&blob.path)));
+            if exhausted || blobs.len() >= limit {
+                blobs.truncate(limit);
+                return Ok(blobs);
+            }
+        }
+    }
+
+    /// `binds` fill the condition's `?1`, `?2`...; the page follows them
+    async fn query_top_blobs(&self, filter: &str, binds: &[String], limit: usize, offset: usize) -> Result<Vec<LargeBlobInfo>> {
+        let sql = format!(
+            "SELECT b.oid, b.size, b.path, b.first_author, b.first_email, b.first_date, b.first_subject,
+                    EXISTS (SELECT 1 FROM head_blobs h WHERE h.oid = b.oid) AS in_head
+             FROM blobs b {} ORDER BY b.size DESC, b.oid LIMIT ?{} OFFSET ?{}",
+            filter,
+            binds.len() + 1,
+            binds.len() + 2
+        );
+        let mut query = sqlx::query(&sql);
+        for value in binds {
+            query = query.bind(value);
+        }
+        let rows = query
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.reader)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| {
+            LargeBlobInfo {
+                oid: row.get("oid"),
+                size: row.get::<i64, _>("size") as u64,
+                path: row.get("path"),
+                first_author: row.get("first_author"),
+                first_email: row.get("first_email"),
+                first_date: row.get("first_date"),
+                first_subject: row.get("first_subject"),
+                in_head: row.get("in_head"),
+            }
+        }).collect())
+    }
+
+    /// First and last quarter with any recorded growth
+    pub async fn get_history_range(&self) -> Result<Option<(i32, i32)>> {
+        let row = sqlx::query("SELECT MIN(quarter) AS first, MAX(quarter) AS last FROM path_history")
+            .fetch_one(&self.reader)
+            .await?;
+        let first: Option<i32> = row.get("first");
+        let last: Option<i32> = row.get("last");
+        Ok(first.zip(last))
+    }
+
+    /// Bytes added per quarter to a file, or to everything under a directory
+    ///
+    /// An empty `path` means the whole repository.
+    pub async fn get_path_history(&self, path: &str) -> Result<Vec<(i32, u64)>> {
+        // Children of "dir" sort between "dir/" and "dir0" ('0' follows '/')
+        let rows = if path.is_empty() {
+            sqlx::query("SELECT quarter, SUM(size) AS size FROM path_history GROUP BY quarter")
+                .fetch_all(&self.reader)
+                .await?
+        } else {
+            sqlx::query(
+                "SELECT quarter, SUM(size) AS size FROM path_history
+                 WHERE path = ?1 OR (path >= ?2 AND path < ?3)
+                 GROUP BY quarter"
+            )
+            .bind(path)
+            .bind(format!("{}/", path))
+            .bind(format!("{}0", path))
+            .fetch_all(&self.reader)
+            .await?
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("quarter"), row.get::<i64, _>("size") as u64))
+            .collect())
+    }
+
+    /// Find blob OIDs whose hex form starts with `hex_prefix` (at most `limit`)
+    pub async fn find_blob_oids(&self, hex_prefix: &str, limit: usize) -> Result<Vec<Vec<u8>>> {
+        let pattern = format!("{}%", hex_prefix.to_ascii_uppercase());
+        Ok(sqlx::query_scalar("SELECT oid FROM blobs WHERE hex(oid) LIKE ? LIMIT ?")
+            .bind(pattern)
+            .bind(limit as i64)
+            .fetch_all(&self.reader)
+            .await?)
+    }
+
+    /// Get everything known about a blob by its raw OID
+    pub async fn get_blob(&self, oid: &[u8]) -> Result<Option<BlobLookup>> {
+        let Some(row) = sqlx::query(
+            "SELECT oid, size, path, first_author, first_email, first_date, first_subject FROM blobs WHERE oid = ?"
+        )
+        .bind(oid)
+        .fetch_optional(&self.reader)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let occurrences = sqlx::query(
+            "SELECT d.path, EXISTS(
+                SELECT 1 FROM head_blobs h WHERE h.path = d.path AND h.oid = bp.oid
+             ) AS in_head
+             FROM blob_paths bp JOIN path_ids d ON d.id = bp.path_id
+             WHERE bp.oid = ? ORDER BY d.path"
+        )
+        .bind(oid)
+        .fetch_all(&self.reader)
+        .await?
+        .into_iter()
+        .map(|r| BlobOccurrence {
+            path: r.get("path"),
+            in_head: r.get("in_head"),
+        })
+        .collect();
+
+        Ok(Some(BlobLookup {
+            oid: row.get("oid"),
+            size: row.get::<i64, _>("size") as u64,
+            path: row.get("path"),
+            first_author: row.get("first_author"),
+            first_email: row.get("first_email"),
+            first_date: row.get("first_date"),
+            first_subject: row.get("first_subject"),
+            occurrences,
+        }))
+    }
+
+    /// The blob a file has in HEAD or, for a file gone from it, the one
+    /// most recently committed at its path
+    pub async fn get_path_blob(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        let head = sqlx::query_scalar("SELECT oid FROM head_blobs WHERE path = ?")
+            .bind(path)
+            .fetch_optional(&self.reader)
+            .await?;
+        if head.is_some() {
+            return Ok(head);
+        }
+        Ok(sqlx::query_scalar(
+            "SELECT bp.oid FROM path_ids d
+             JOIN blob_paths bp ON bp.path_id = d.id
+             LEFT JOIN blobs b ON b.oid = bp.oid
+             WHERE d.path = ? ORDER BY b.first_date DESC LIMIT 1"
+        )
+        .bind(path)
+        .fetch_optional(&self.reader)
+        .await?)
+    }
+
+    /// Blobs committed at more than one path, those whose copies take the
+    /// most room first, with the paths `filter` doesn't exclude
+    ///
+    /// Ranked by size times the number of extra paths; a blob left with a
+    /// single path once excluded ones are dropped isn't a duplicate.
+    pub async fn get_duplicate_blobs(&self, limit: usize, filter: &PathFilter) -> Result<Vec<DuplicateBlob>> {
+        let mut duplicates = Vec::with_capacity(limit);
+        let mut offset = 0;
+        loop {
+            let page = sqlx::query(
+                "SELECT b.oid, b.size FROM blob_paths bp JOIN blobs b ON b.oid = bp.oid
+                 WHERE b.size > 0
+                 GROUP BY bp.oid HAVING COUNT(*) > 1
+                 ORDER BY b.size * (COUNT(*) - 1) DESC, b.oid LIMIT ? OFFSET ?"
+            )
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.reader)
+            .await?;
+            let exhausted = page.len() < limit;
+            offset += page.len();
+
+            for row in page {
+                let oid: Vec<u8> = row.get("oid");
+                let paths = sqlx::query(
+                    "SELECT d.path, EXISTS(
+                        SELECT 1 FROM head_blobs h WHERE h.path = d.path AND h.oid = bp.oid
+                     ) AS in_head
+                     FROM blob_paths bp JOIN path_ids d ON d.id = bp.path_id
+                     WHERE bp.oid = ? ORDER BY d.path"
+                )
+                .bind(&oid)
+                .fetch_all(&self.reader)
+                .await?
+                .into_iter()
+                .map(|r| BlobOccurrence { path: r.get("path"), in_head: r.get("in_head") })
+                .filter(|o| !filter.is_excluded(&o.path))
+                .collect::<Vec<_>>();
+                if paths.len() > 1 {
+                    duplicates.push(DuplicateBlob { oid, size: row.get::<i64, _>("size") as u64, paths });
+                }
+            }
+            if exhausted || duplicates.len() >= limit {
+                duplicates.truncate(limit);
+                return Ok(duplicates);
+            }
+        }
+    }
+
+    /// Commits that added the most new blob data, heaviest first
+    pub async fn get_heavy_commits(&self, limit: usize) -> Result<Vec<HeavyCommit>> {
+        let rows = sqlx::query(
+            "SELECT oid, author, email, date, subject, added_size, blob_count, largest_path FROM commits
+             WHERE added_size > 0
+             ORDER BY added_size DESC, oid LIMIT ?"
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.reader)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| HeavyCommit {
+                oid: row.get("oid"),
+                author: row.get("author"),
+                email: row.get("email"),
+                date: row.get("date"),
+                subject: row.get("subject"),
+                added_size: row.get::<i64, _>("added_size") as u64,
+                blob_count: row.get::<i64, _>("blob_count") as u64,
+                largest_path: row.get("largest_path"),
+            })
+            .collect())
+    }
+
+    /// Paths with more than one version, holding the most bytes first, with
+    /// how well their versions delta; paths `filter` excludes are skipped
+    ///
+    /// A version's size is the entry size recorded in `blobs`, so a blob
+    /// committed at several paths counts at each of them.
+    pub async fn get_delta_efficiency(&self, limit: usize, filter: &PathFilter) -> Result<Vec<DeltaEfficiency>> {
+        let mut paths = Vec::with_capacity(limit);
+        let mut offset = 0;
+        loop {
+            let page = sqlx::query(
+                "SELECT d.path, COUNT(*) AS versions, SUM(b.size) AS stored, MAX(b.size) AS largest
+                 FROM blob_paths bp JOIN blobs b ON b.oid = bp.oid JOIN path_ids d ON d.id = bp.path_id
+                 GROUP BY bp.path_id HAVING COUNT(*) > 1
+                 ORDER BY stored DESC, d.path LIMIT ? OFFSET ?"
+            )
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.reader)
+            .await?;
+            let exhausted = page.len() < limit;
+            offset += page.len();
+
+            paths.extend(
+                page.into_iter()
+                    .map(|row| DeltaEfficiency {
+                        path: row.get("path"),
+                        versions: row.get::<i64, _>("versions") as u64,
+                        stored: row.get::<i64, _>("stored") as u64,
+                        largest: row.get::<i64, _>("largest") as u64,
+                    })
+                    .filter(|d| !filter.is_excluded(&d.path)),
+            );
+            if exhausted || paths.len() >= limit {
+                paths.truncate(limit);
+                return Ok(paths);
+            }
+        }
+    }
+
+    /// The `limit` authors (or email domains) whose blobs take the most
+    /// bytes, counting each blob for whoever committed it first
+    pub async fn get_author_totals(
+        &self,
+        grouping: AuthorGrouping,
+        limit: usize,
+        filter: &PathFilter,
+    ) -> Result<Vec<AuthorTotals>> {
+        // Exclusion patterns are matched here, so with any set every blob is
+        // read; otherwise identities are summed first and grouped here
+        let rows = if filter.is_empty() {
+            sqlx::query(
+                "SELECT first_author, first_email, COUNT(*) AS blob_count, SUM(size) AS size
+                 FROM blobs GROUP BY first_author, first_email"
+            )
+            .fetch_all(&self.reader)
+            .await?
+        } else {
+            sqlx::query("SELECT first_author, first_email, path, 1 AS blob_count, size FROM blobs")
+                .fetch_all(&self.reader)
+                .await?
+                .into_iter()
+                .filter(|row| !filter.is_excluded(row.get("path")))
+                .collect()
+        };
+
+        let mut totals: rustc_hash::FxHashMap<String, AuthorTotals> = rustc_hash::FxHashMap::default();
+        for row in rows {
+            let author = grouping.label(row.get("first_author"), row.get("first_email"));
+            let entry = totals
+                .entry(author.clone())
+                .or_insert_with(|| AuthorTotals { author, blob_count: 0, size: 0 });
+            entry.blob_count += row.get::<i64, _>("blob_count") as u64;
+            entry.size += row.get::<i64, _>("size") as u64;
+        }
+        let mut totals: Vec<AuthorTotals> = totals.into_values().collect();
+        totals.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.author.cmp(&b.author)));
+        totals.truncate(limit);
+        Ok(totals)
+    }
+
+    /// Look up a blob by full or abbreviated (at least 4 digits) hex OID
+    ///
+    /// Returns `Ok(None)` when no blob matches and an error when the input
+    /// is not a valid OID or the prefix is ambiguous.
+    pub async fn lookup_blob(&self, hex: &str) -> Result<Option<BlobLookup>> {
+        let hex = hex.trim();
+        anyhow::ensure!(
+            (4..=40).contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+            "Invalid object id: {:?} (expected 4-40 hex digits)",
+            hex
+        );
+
+        let oids = self.find_blob_oids(hex, 2).await?;
+        match oids.as_slice() {
+            [] => Ok(None),
+            [oid] => self.get_blob(oid).await,
+            _ => anyhow::bail!("Ambiguous object id prefix: {}", hex),
+        }
+    }
+
+    /// Replace the stored HEAD snapshot (path -> blob at the scanned HEAD)
+    /// and re-derive every path's current size from it
+    pub async fn replace_head_blobs(&self, head: &[HeadBlobRecord<'_>]) -> Result<()> {
+        const BATCH_SIZE: usize = 5000;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM head_blobs").execute(&mut *tx).await?;
+
+        for chunk in head.chunks(BATCH_SIZE) {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT OR REPLACE INTO head_blobs (path, oid, size, kind) "
+            );
+            qb.push_values(chunk, |mut row, record| {
+                row.push_bind(record.path.as_ref())
+                    .push_bind(record.oid.as_slice())
+                    .push_bind(record.size)
+                    .push_bind(record.kind.code());
+            });
+            qb.build().execute(&mut *tx).await?;
+        }
+
+        let changed = self.refresh_current_sizes_in_tx(&mut tx).await?;
+        self.refresh_dirs_in_tx(&mut tx, changed.iter().map(String::as_str)).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Objects the scans so far could not read and anomalies they worked
+    /// around, ordered by kind and OID
+    pub async fn get_scan_diagnostics(&self) -> Result<Vec<ScanDiagnostic>> {
+        let rows = sqlx::query("SELECT oid, kind, path, reason FROM scan_diagnostics ORDER BY kind, oid")
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(rows.iter()
+            .map(|row| ScanDiagnostic {
+                oid: hex::encode(row.get::<Vec<u8>, _>("oid")),
+                kind: row.get("kind"),
+                path: row.get("path"),
+                reason: row.get("reason"),
+            })
+            .collect())
+    }
+
+    /// Bookmarked paths, sorted
+    pub async fn get_bookmarks(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT path FROM bookmarks ORDER BY path")
+            .fetch_all(&self.reader)
+            .await?;
+        Ok(rows.iter().map(|row| row.get("path")).collect())
+    }
+
+    pub async fn add_bookmark(&self, path: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO bookmarks (path) VALUES (?)")
+            .bind(path)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_bookmark(&self, path: &str) -> Result<()> {
+        sqlx::query("DELETE FROM bookmarks WHERE path = ?")
+            .bind(path)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Record `snapshot` with its entries, replacing one taken the same
+    /// second for the same scope
+    pub async fn add_size_snapshot(&self, snapshot: &SizeSnapshot) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM size_snapshot_entries WHERE scope = ? AND taken_at = ?")
+            .bind(&snapshot.scope)
+            .bind(snapshot.taken_at)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO size_snapshots
+                (scope, taken_at, head, cumulative_size, current_size, deleted_size, blob_count, largest_blob)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&snapshot.scope)
+        .bind(snapshot.taken_at)
+        .bind(&snapshot.head)
+        .bind(snapshot.cumulative_size as i64)
+        .bind(snapshot.current_size as i64)
+        .bind(snapshot.deleted_size as i64)
+        .bind(snapshot.blob_count as i64)
+        .bind(snapshot.largest_blob as i64)
+        .execute(&mut *tx)
+        .await?;
+        for chunk in snapshot.entries.chunks(MAX_VARIABLES / 6) {
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT OR REPLACE INTO size_snapshot_entries
+                    (scope, taken_at, kind, name, cumulative_size, current_size) ",
+            );
+            qb.push_values(chunk, |mut row, entry| {
+                row.push_bind(&snapshot.scope)
+                    .push_bind(snapshot.taken_at)
+                    .push_bind(entry.kind.code())
+                    .push_bind(&entry.name)
+                    .push_bind(entry.cumulative_size as i64)
+                    .push_bind(entry.current_size as i64);
+            });
+            qb.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Delete snapshots taken before `before` (a Unix timestamp); returns
+    /// how many were deleted
+    pub async fn prune_size_snapshots(&self, before: i64) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM size_snapshot_entries WHERE taken_at < ?")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM size_snapshots WHERE taken_at < ?")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Recorded snapshots of `scope` ("" for whole-repository scans), oldest
+    /// first, without their entries
+    pub async fn get_size_snapshots(&self, scope: &str) -> Result<Vec<SizeSnapshot>> {
+        let rows = sqlx::query(
+            "SELECT scope, taken_at, head, cumulative_size, current_size, deleted_size, blob_count, largest_blob
+             FROM size_snapshots WHERE scope = ? ORDER BY taken_at"
+        )
+        .bind(scope)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(rows.iter()
+            .map(|row| SizeSnapshot {
+                taken_at: row.get("taken_at"),
+                head: row.get("head"),
+                scope: row.get("scope"),
+                cumulative_size: row.get::<i64, _>("cumulative_size") as u64,
+                current_size: row.get::<i64, _>("current_size") as u64,
+                deleted_size: row.get::<i64, _>("deleted_size") as u64,
+                blob_count: row.get::<i64, _>("blob_count") as u64,
+                largest_blob: row.get::<i64, _>("largest_blob") as u64,
+                entries: Vec::new(),
+            })
+            .collect())
+    }
+
+    /// The newest snapshot of `scope`, with its entries
+    pub async fn get_latest_size_snapshot(&self, scope: &str) -> Result<Option<SizeSnapshot>> {
+        let Some(mut latest) = self.get_size_snapshots(scope).await?.pop() else {
+            return Ok(None);
+        };
+        latest.entries = self.get_size_snapshot_entries(scope, latest.taken_at).await?;
+        Ok(Some(latest))
+    }
+
+    /// The entries recorded with the snapshot of `scope` taken at `taken_at`,
+    /// by kind and name
+    pub async fn get_size_snapshot_entries(&self, scope: &str, taken_at: i64) -> Result<Vec<SnapshotEntry>> {
+        let rows = sqlx::query(
+            "SELECT kind, name, cumulative_size, current_size FROM size_snapshot_entries
+             WHERE scope = ? AND taken_at = ? ORDER BY kind, name"
+        )
+        .bind(scope)
+        .bind(taken_at)
+        .fetch_all(&self.reader)
+        .await?;
+        Ok(rows.iter()
+            .filter_map(|row| {
+                Some(SnapshotEntry {
+                    kind: SnapshotEntryKind::from_code(row.get("kind"))?,
+                    name: row.get("name"),
+                    cumulative_size: row.get::<i64, _>("cumulative_size") as u64,
+                    current_size: row.get::<i64, _>("current_size") as u64,
+                })
+            })
+            .collect())
+    }
+
+    /// Every row of each of `tables`, read in one transaction so they agree
+    pub(super) async fn dump_tables(&self, tables: &[TableSpec]) -> Result<Vec<Vec<Vec<Cell>>>> {
+        let mut tx = self.reader.begin().await?;
+        let mut dumped = Vec::with_capacity(tables.len());
+        for table in tables {
+            let columns: Vec<&str> = table.columns.iter().map(|(name, _)| *name).collect();
+            let sql = format!("SELECT {} FROM {}", columns.join(", "), table.name);
+            let mut rows = Vec::new();
+            for row in sqlx::query(&sql).fetch_all(&mut *tx).await? {
+                let mut cells = Vec::with_capacity(table.columns.len());
+                for (index, (_, kind)) in table.columns.iter().enumerate() {
+                    cells.push(match kind {
+                        ColumnKind::Integer => Cell::Integer(row.try_get(index)?),
+                        ColumnKind::Text => Cell::Text(row.try_get(index)?),
+                        ColumnKind::Blob => Cell::Blob(row.try_get(index)?),
+                    });
+                }
+                rows.push(cells);
+            }
+            dumped.push(rows);
+        }
+        tx.commit().await?;
+        Ok(dumped)
+    }
+
+    /// Replace the contents of each of `tables` with the matching `rows`, in
+    /// one transaction
+    ///
+    /// Bookmarks and size snapshots are added to the existing ones, and the
+    /// schema version in `metadata` is kept.
+    pub(super) async fn restore_tables(&self, tables: &[TableSpec], rows: &[Vec<Vec<Cell>>]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (table, rows) in tables.iter().zip(rows) {
+            match table.name {
+                "bookmarks" | "size_snapshots" | "size_snapshot_entries" => {}
+                "metadata" => {
+                    sqlx::query("DELETE FROM metadata WHERE key <> 'schema_version'").execute(&mut *tx).await?;
+                }
+                name => {
+                    sqlx::query(&format!("DELETE FROM {}", name)).execute(&mut *tx).await?;
+                }
+            }
+
+            let columns: Vec<&str> = table.columns.iter().map(|(name, _)| *name).collect();
+            for chunk in rows.chunks(MAX_VARIABLES / columns.len()) {
+                let mut qb: QueryBuilder<Sqlite> =
+                    QueryBuilder::new(format!("INSERT OR IGNORE INTO {} ({}) ", table.name, columns.join(", ")));
+                qb.push_values(chunk, |mut row, cells| {
+                    for cell in cells {
+                        match cell {
+                            Cell::Integer(value) => row.push_bind(*value),
+                            Cell::Text(value) => row.push_bind(value.as_str()),
+                            Cell::Blob(value) => row.push_bind(value.as_slice()),
+                        };
+                    }
+                });
+                qb.build().execute(&mut *tx).await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Add entries to the path dictionary
+    pub async fn save_paths(&self, paths: &[PathRecord<'_>]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.save_paths_in_tx(&mut tx, paths).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every path of the path dictionary as interned bytes, in id order
+    ///
+    /// Ids are handed out densely from 0, so a path's id is its position.
+    pub async fn load_paths(&self) -> Result<Vec<Vec<u8>>> {
+        let rows = sqlx::query("SELECT id, path, raw FROM path_ids ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        let mut paths = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: i64 = row.get("id");
+            anyhow::ensure!(id == paths.len() as i64, "Path dictionary has no path with id {}", paths.len());
+            let raw: Vec<u8> = row.get("raw");
+            paths.push(if raw.is_empty() { row.get::<String, _>("path").into_bytes() } else { raw });
+        }
+        Ok(paths)
+    }
+
+    /// Number of paths in the path dictionary, which is the next id
+    pub async fn path_count(&self) -> Result<usize> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM path_ids").fetch_one(&self.pool).await?;
+        Ok(count as usize)
+    }
+
+    /// Save (blob, path) occurrences
+    pub async fn save_blob_paths(&self, blob_paths: &[BlobPathRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.save_blob_paths_in_tx(&mut tx, blob_paths).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Save trees whose whole subtree has been indexed
+    pub async fn save_seen_trees(&self, trees: &[TreePathRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.save_seen_trees_in_tx(&mut tx, trees).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Load all (tree OID, path id) pairs known to be fully indexed
+    pub async fn load_seen_trees(&self) -> Result<Vec<([u8; 20], i64)>> {
+        let rows = sqlx::query("SELECT oid, path_id FROM seen_trees")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let oid: Vec<u8> = row.get("oid");
+                Some((oid.try_into().ok()?, row.get("path_id")))
+            })
+            .collect())
+    }
+
+    /// Check if a commit has been scanned (used by tests)
+    #[allow(dead_code)]
+    pub async fn is_commit_scanned(&self, oid: &[u8; 20]) -> bool {
+        sqlx::query("SELECT 1 FROM scanned_commits WHERE oid = ?")
+            .bind(oid.as_slice())
+            .fetch_optional(&self.pool)
+            .await
+            .ok()
+            .flatten()
+            .is_some()
+    }
+
+    /// Load all scanned commit OIDs into a HashSet for fast lookup
+    /// Returns raw 20-byte SHA-1 hashes
+    pub async fn load_scanned_commit_oids(&self) -> rustc_hash::FxHashSet<[u8; 20]> {
+        let rows: Vec<Vec<u8>> = sqlx::query_scalar("SELECT oid FROM scanned_commits")
+            .fetch_all(&self.pool)
+            .await
+            .unwrap_or_default();
+        rows.into_iter()
+            .filter_map(|v| v.try_into().ok())
+            .collect()
+    }
+
+    /// Load all previously seen blob OIDs
+    /// Returns raw 20-byte SHA-1 hashes
+    pub async fn load_seen_blobs(&self) -> Result<rustc_hash::FxHashSet<[u8; 20]>> {
+        let rows = sqlx::query("SELECT oid FROM seen_blobs")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter()
+            .filter_map(|row| {
+                let v: Vec<u8> = row.get("oid");
+                v.try_into().ok()
+            })
+            .collect())
+    }
+
+    /// Save batch of new blobs with a callback for progress
+    pub async fn save_blobs_with_callback<F>(
+        &self,
+        blobs: &[BlobRecord<'_>],
+        mut on_progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(usize),
+    {
+        let mut tx = self.pool.begin().await?;
+        self.save_blobs_in_tx(&mut tx, blobs, &mut on_progress)
+            .await?;
+        self.refresh_dirs_in_tx(&mut tx, blobs.iter().map(|b| b.path.as_ref())).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Save blob metadata with a callback for progress
+    pub async fn save_blob_metadata_with_callback<F>(
+        &self,
+        metadata: &[BlobMetaRecord<'_>],
+        mut on_progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(usize),
+    {
         let mut tx = self.pool.begin().await?;
         self.save_blob_metadata_in_tx(&mut tx, metadata, &mut on_progress)
             .await?;
@@ -310,11 +1962,19 @@ impl Database {
         Ok(())
     }
 
-    /// Apply blobs + metadata + scanned commits in ONE transaction.
+    /// Replace the packs recorded as unreadable with `packs`
+    pub async fn replace_corrupt_packs(&self, packs: &[SkippedRecord<'_>]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM scan_diagnostics WHERE kind = 'pack'").execute(&mut *tx).await?;
+        self.save_skipped_in_tx(&mut tx, packs).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Apply a scan's records and scanned commits in ONE transaction.
     pub async fn apply_scan_with_callback<F1, F2>(
         &self,
-        blobs: &[BlobRecord<'_>],
-        metadata: &[BlobMetaRecord<'_>],
+        records: &ScanRecords<'_>,
         scanned_commits: &[[u8; 20]],
         mut on_blobs_progress: F1,
         mut on_meta_progress: F2,
@@ -325,20 +1985,210 @@ impl Database {
     {
         let mut tx = self.pool.begin().await?;
 
-        // Persist rows
-        self.save_blobs_in_tx(&mut tx, blobs, &mut on_blobs_progress)
+        // Persist rows, after the paths they refer to
+        self.save_paths_in_tx(&mut tx, &records.paths).await?;
+        self.save_blobs_in_tx(&mut tx, &records.blobs, &mut on_blobs_progress)
             .await?;
-        self.save_blob_metadata_in_tx(&mut tx, metadata, &mut on_meta_progress)
+        self.save_blob_metadata_in_tx(&mut tx, &records.metadata, &mut on_meta_progress)
             .await?;
+        self.save_blob_paths_in_tx(&mut tx, &records.blob_paths).await?;
+        self.save_skipped_in_tx(&mut tx, &records.skipped).await?;
+        self.save_gitlinks_in_tx(&mut tx, &records.gitlinks).await?;
+        self.save_removals_in_tx(&mut tx, &records.removals).await?;
 
         // Advance state
+        self.save_seen_trees_in_tx(&mut tx, &records.seen_trees).await?;
         self.mark_commits_scanned_in_tx(&mut tx, scanned_commits)
             .await?;
 
+        // Commits that were skipped before and read fine this time
+        sqlx::query("DELETE FROM scan_diagnostics WHERE kind = 'commit' AND oid IN (SELECT oid FROM scanned_commits)")
+            .execute(&mut *tx)
+            .await?;
+        let refreshed = self.refresh_current_sizes_in_tx(&mut tx).await?;
+
+        let changed = records.blobs.iter().map(|b| b.path.as_ref())
+            .chain(records.gitlinks.iter().map(AsRef::as_ref))
+            .chain(refreshed.iter().map(String::as_str));
+        self.refresh_dirs_in_tx(&mut tx, changed).await?;
+
         tx.commit().await?;
         Ok(())
     }
 
+    /// Set each path's current size and entry kind from the HEAD snapshot,
+    /// returning the paths whose current size changed
+    ///
+    /// Sizes added per scan would keep counting blobs an earlier HEAD had,
+    /// and miss blobs in subtrees pruned as already seen.
+    async fn refresh_current_sizes_in_tx(&self, tx: &mut Transaction<'_, Sqlite>) -> Result<Vec<String>> {
+        let changed = sqlx::query_scalar(
+            "UPDATE paths SET current_size = head.size
+             FROM (SELECT p.path, COALESCE(h.size, 0) AS size
+                   FROM paths p LEFT JOIN head_blobs h ON h.path = p.path) head
+             WHERE head.path = paths.path AND paths.current_size <> head.size
+             RETURNING paths.path"
+        ).fetch_all(&mut **tx).await?;
+        // A chmod or file/symlink swap keeps the blob, so only HEAD knows the mode
+        sqlx::query(
+            "UPDATE paths SET kind = h.kind FROM head_blobs h WHERE h.path = paths.path"
+        ).execute(&mut **tx).await?;
+        Ok(changed)
+    }
+
+    /// Recompute the `dirs` rows of every directory above the `changed`
+    /// paths, deepest first, each from the directory's direct entries
+    async fn refresh_dirs_in_tx<'p>(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        changed: impl IntoIterator<Item = &'p str>,
+    ) -> Result<()> {
+        const BATCH_SIZE: usize = 5000;
+
+        let mut dirty: rustc_hash::FxHashSet<&str> = rustc_hash::FxHashSet::default();
+        for path in changed {
+            let mut path = path;
+            while let Some((parent, _)) = path.rsplit_once('/') {
+                // Its ancestors went in with it
+                if !dirty.insert(parent) {
+                    break;
+                }
+                path = parent;
+            }
+        }
+        if dirty.is_empty() {
+            return Ok(());
+        }
+
+        // By depth; a level only reads the one below it
+        let mut levels: Vec<Vec<&str>> = Vec::new();
+        for dir in dirty {
+            let depth = dir.matches('/').count();
+            if levels.len() <= depth {
+                levels.resize_with(depth + 1, Vec::new);
+            }
+            levels[depth].push(dir);
+        }
+
+        for level in levels.iter().rev() {
+            for chunk in level.chunks(BATCH_SIZE) {
+                let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new("WITH dirty (path, parent) AS (");
+                qb.push_values(chunk, |mut row, dir| {
+                    row.push_bind(*dir).push_bind(parent_dir(dir));
+                });
+                qb.push(
+                    ") INSERT INTO dirs (path, parent, cumulative_size, current_size, blob_count, deleted_size)
+                    SELECT d.path, d.parent, SUM(c.cumulative_size), SUM(c.current_size),
+                           SUM(c.blob_count), SUM(c.deleted_size)
+                    FROM dirty d JOIN (
+                        SELECT parent, cumulative_size, current_size, blob_count, deleted_size
+                        FROM dirs WHERE parent IN (SELECT path FROM dirty)
+                        UNION ALL
+                        SELECT parent, cumulative_size, current_size, blob_count,
+                               CASE WHEN current_size = 0 THEN cumulative_size ELSE 0 END
+                        FROM paths p WHERE parent IN (SELECT path FROM dirty)
+                            AND NOT EXISTS (SELECT 1 FROM dirs x WHERE x.path = p.path)
+                    ) c ON c.parent = d.path
+                    WHERE true
+                    GROUP BY d.path
+                    ON CONFLICT(path) DO UPDATE SET
+                        cumulative_size = excluded.cumulative_size,
+                        current_size = excluded.current_size,
+                        blob_count = excluded.blob_count,
+                        deleted_size = excluded.deleted_size"
+                );
+                qb.build().execute(&mut **tx).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Give submodule paths a row so they show up, with no size of their own
+    async fn save_gitlinks_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        gitlinks: &[Cow<'_, str>],
+    ) -> Result<()> {
+        // Eight variables a row
+        const BATCH_SIZE: usize = MAX_VARIABLES / 8;
+
+        for chunk in gitlinks.chunks(BATCH_SIZE) {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO paths (path, cumulative_size, current_size, blob_count, kind, extension, vendored, parent) "
+            );
+            qb.push_values(chunk, |mut row, path| {
+                row.push_bind(path.as_ref())
+                    .push_bind(0_i64)
+                    .push_bind(0_i64)
+                    .push_bind(0_i64)
+                    .push_bind(EntryKind::Gitlink.code())
+                    .push_bind(path_extension(path))
+                    .push_bind(path_vendored(path))
+                    .push_bind(parent_dir(path));
+            });
+            qb.push(" ON CONFLICT(path) DO UPDATE SET kind = excluded.kind");
+            qb.build().execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Keep the latest removal of each path
+    async fn save_removals_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        removals: &[RemovalRecord],
+    ) -> Result<()> {
+        const BATCH_SIZE: usize = 5000;
+
+        for chunk in removals.chunks(BATCH_SIZE) {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new("INSERT INTO path_removals (path_id, date) ");
+            qb.push_values(chunk, |mut row, record| {
+                row.push_bind(record.path_id).push_bind(record.date);
+            });
+            // Also when one batch removes a path twice
+            qb.push(" ON CONFLICT(path_id) DO UPDATE SET date = MAX(date, excluded.date)");
+            qb.build().execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_skipped_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        skipped: &[SkippedRecord<'_>],
+    ) -> Result<()> {
+        const BATCH_SIZE: usize = 5000;
+
+        for chunk in skipped.chunks(BATCH_SIZE) {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT OR REPLACE INTO scan_diagnostics (oid, kind, path, reason) "
+            );
+            qb.push_values(chunk, |mut row, record| {
+                row.push_bind(record.oid.as_slice())
+                    .push_bind(record.kind)
+                    .push_bind(record.path.as_ref())
+                    .push_bind(record.reason.as_ref());
+            });
+            qb.build().execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
     async fn save_blobs_in_tx<F>(
         &self,
         tx: &mut Transaction<'_, Sqlite>,
@@ -348,7 +2198,8 @@ impl Database {
     where
         F: FnMut(usize),
     {
-        const BATCH_SIZE: usize = 5000;
+        // Nine variables a row in the paths upsert
+        const BATCH_SIZE: usize = MAX_VARIABLES / 9;
 
         for chunk in blobs.chunks(BATCH_SIZE) {
             // Multi-row INSERT for seen_blobs using QueryBuilder
@@ -362,21 +2213,32 @@ impl Database {
                 qb.build().execute(&mut **tx).await?;
             }
 
-            // Multi-row upsert for paths using QueryBuilder
+            // Multi-row upsert for paths, skipping (blob, path) pairs an
+            // earlier scan already counted: an incremental scan meets them
+            // again when a commit brings an old version back
             let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
-                "INSERT INTO paths (path, cumulative_size, current_size, blob_count) "
+                "WITH added (oid, path, cumulative_size, current_size, kind, extension, vendored, parent) AS ("
             );
             qb.push_values(chunk, |mut row, record| {
-                row.push_bind(record.path.as_ref())
+                row.push_bind(record.oid.as_slice())
+                    .push_bind(record.path.as_ref())
                     .push_bind(record.cumulative_size)
                     .push_bind(record.current_size)
-                    .push_bind(1_i64);
+                    .push_bind(record.kind.code())
+                    .push_bind(path_extension(&record.path))
+                    .push_bind(path_vendored(&record.path))
+                    .push_bind(parent_dir(&record.path));
             });
             qb.push(
-                " ON CONFLICT(path) DO UPDATE SET \
+                ") INSERT INTO paths (path, cumulative_size, current_size, blob_count, kind, extension, vendored, parent) \
+                 SELECT path, cumulative_size, current_size, 1, kind, extension, vendored, parent FROM added a \
+                 WHERE NOT EXISTS (SELECT 1 FROM path_ids d JOIN blob_paths bp ON bp.path_id = d.id \
+                                   WHERE d.path = a.path AND bp.oid = a.oid) \
+                 ON CONFLICT(path) DO UPDATE SET \
                     cumulative_size = cumulative_size + excluded.cumulative_size, \
                     current_size = current_size + excluded.current_size, \
-                    blob_count = blob_count + excluded.blob_count"
+                    blob_count = blob_count + excluded.blob_count, \
+                    kind = excluded.kind"
             );
             qb.build().execute(&mut **tx).await?;
 
@@ -395,7 +2257,8 @@ impl Database {
     where
         F: FnMut(usize),
     {
-        const BATCH_SIZE: usize = 5000;
+        // Seven variables a row
+        const BATCH_SIZE: usize = MAX_VARIABLES / 7;
 
         for chunk in metadata.chunks(BATCH_SIZE) {
             if chunk.is_empty() {
@@ -403,23 +2266,186 @@ impl Database {
             }
 
             let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
-                "INSERT OR IGNORE INTO blobs (oid, size, path, first_author, first_date) "
+                "INSERT OR IGNORE INTO blobs (oid, size, path, first_author, first_email, first_date, first_subject) "
             );
             qb.push_values(chunk, |mut row, record| {
                 row.push_bind(record.oid.as_slice())
                     .push_bind(record.size)
                     .push_bind(record.path.as_ref())
                     .push_bind(record.author.as_ref())
-                    .push_bind(record.timestamp);
+                    .push_bind(record.email.as_ref())
+                    .push_bind(record.timestamp)
+                    .push_bind(record.subject.as_ref());
             });
             qb.build().execute(&mut **tx).await?;
 
+            self.save_path_history_in_tx(tx, chunk).await?;
+            self.save_commit_sizes_in_tx(tx, chunk).await?;
+
             on_progress(chunk.len());
         }
 
         Ok(())
     }
 
+    /// Add each new blob's size to its first path's quarter
+    ///
+    /// Metadata rows are exactly the blobs counted towards a path's
+    /// cumulative size, so the history sums to it.
+    async fn save_path_history_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        metadata: &[BlobMetaRecord<'_>],
+    ) -> Result<()> {
+        let mut added: rustc_hash::FxHashMap<(&str, i32), i64> = rustc_hash::FxHashMap::default();
+        for record in metadata {
+            if let Some(quarter) = quarter_of(record.timestamp) {
+                *added.entry((record.path.as_ref(), quarter)).or_default() += record.size;
+            }
+        }
+        if added.is_empty() {
+            return Ok(());
+        }
+
+        let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "INSERT INTO path_history (path, quarter, size) "
+        );
+        qb.push_values(added, |mut row, ((path, quarter), size)| {
+            row.push_bind(path)
+                .push_bind(quarter)
+                .push_bind(size);
+        });
+        qb.push(" ON CONFLICT(path, quarter) DO UPDATE SET size = size + excluded.size");
+        qb.build().execute(&mut **tx).await?;
+        Ok(())
+    }
+
+    /// Add each new blob's size to the commit that introduced it
+    async fn save_commit_sizes_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        metadata: &[BlobMetaRecord<'_>],
+    ) -> Result<()> {
+        // Nine variables a row
+        const BATCH_SIZE: usize = MAX_VARIABLES / 9;
+
+        let mut added: rustc_hash::FxHashMap<[u8; 20], (&BlobMetaRecord<'_>, i64, i64)> = rustc_hash::FxHashMap::default();
+        for record in metadata {
+            let Some(commit) = record.commit else { continue };
+            let (largest, size, count) = added.entry(commit).or_insert((record, 0, 0));
+            if record.size > largest.size {
+                *largest = record;
+            }
+            *size += record.size;
+            *count += 1;
+        }
+        let added: Vec<_> = added.into_iter().collect();
+
+        for chunk in added.chunks(BATCH_SIZE) {
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO commits (oid, author, email, date, subject, added_size, blob_count, largest_path, largest_size) "
+            );
+            qb.push_values(chunk, |mut row, (commit, (largest, size, count))| {
+                row.push_bind(commit.as_slice())
+                    .push_bind(largest.author.as_ref())
+                    .push_bind(largest.email.as_ref())
+                    .push_bind(largest.timestamp)
+                    .push_bind(largest.subject.as_ref())
+                    .push_bind(*size)
+                    .push_bind(*count)
+                    .push_bind(largest.path.as_ref())
+                    .push_bind(largest.size);
+            });
+            qb.push(
+                " ON CONFLICT(oid) DO UPDATE SET \
+                    added_size = added_size + excluded.added_size, \
+                    blob_count = blob_count + excluded.blob_count, \
+                    largest_path = CASE WHEN excluded.largest_size > largest_size \
+                        THEN excluded.largest_path ELSE largest_path END, \
+                    largest_size = MAX(largest_size, excluded.largest_size)"
+            );
+            qb.build().execute(&mut **tx).await?;
+        }
+        Ok(())
+    }
+
+    async fn save_paths_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        paths: &[PathRecord<'_>],
+    ) -> Result<()> {
+        const BATCH_SIZE: usize = 5000;
+
+        for chunk in paths.chunks(BATCH_SIZE) {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            // An id taken by another path is an error, not something to ignore
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT INTO path_ids (id, path, raw) "
+            );
+            qb.push_values(chunk, |mut row, record| {
+                row.push_bind(record.id)
+                    .push_bind(record.path.as_ref())
+                    .push_bind(record.raw);
+            });
+            qb.build().execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_blob_paths_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        blob_paths: &[BlobPathRecord],
+    ) -> Result<()> {
+        const BATCH_SIZE: usize = 5000;
+
+        for chunk in blob_paths.chunks(BATCH_SIZE) {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT OR IGNORE INTO blob_paths (oid, path_id) "
+            );
+            qb.push_values(chunk, |mut row, record| {
+                row.push_bind(record.oid.as_slice())
+                    .push_bind(record.path_id);
+            });
+            qb.build().execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_seen_trees_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        trees: &[TreePathRecord],
+    ) -> Result<()> {
+        const BATCH_SIZE: usize = 5000;
+
+        for chunk in trees.chunks(BATCH_SIZE) {
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let mut qb: QueryBuilder<Sqlite> = QueryBuilder::new(
+                "INSERT OR IGNORE INTO seen_trees (oid, path_id) "
+            );
+            qb.push_values(chunk, |mut row, record| {
+                row.push_bind(record.oid.as_slice())
+                    .push_bind(record.path_id);
+            });
+            qb.build().execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
     async fn mark_commits_scanned_in_tx(
         &self,
         tx: &mut Transaction<'_, Sqlite>,
@@ -0,0 +1,129 @@
+//! What a repository's `.git` directory takes on disk
+//!
+//! Sizes are apparent file sizes, summed by walking the common git dir, so
+//! they can be put next to `du -sh .git` and the pack sizes the scan reads.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::model::GitDirUsage;
+
+/// Bytes under the git dir of the repository at `repo_path`, by kind
+pub fn git_dir_usage(repo_path: &str) -> Result<GitDirUsage> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let mut usage = GitDirUsage::default();
+    add_dir(repo.common_dir(), "", &mut usage)
+        .with_context(|| format!("Could not read {}", repo.common_dir().display()))?;
+    Ok(usage)
+}
+
+/// Add every file below `dir`, whose path relative to the git dir is `rel`
+fn add_dir(dir: &Path, rel: &str, usage: &mut GitDirUsage) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let path = if rel.is_empty() { name.into_owned() } else { format!("{}/{}", rel, name) };
+        // Never follow links out of the git dir
+        let metadata = entry.path().symlink_metadata()?;
+        if metadata.is_dir() {
+            add_dir(&entry.path(), &path, usage)?;
+        } else {
+            *bucket(usage, &path) += metadata.len();
+            count_object_file(dir, &path, usage);
+        }
+    }
+    Ok(())
+}
+
+/// Count loose objects and packs, and measure cruft packs, which are the
+/// ones with a `.mtimes` file next to them
+fn count_object_file(dir: &Path, path: &str, usage: &mut GitDirUsage) {
+    if is_loose_object(path) {
+        usage.loose_count += 1;
+    } else if let Some(file) = path.strip_prefix("objects/pack/") {
+        if file.ends_with(".pack") {
+            usage.pack_count += 1;
+        } else if let Some(stem) = file.strip_suffix(".mtimes")
+            && let Ok(pack) = dir.join(format!("{}.pack", stem)).symlink_metadata()
+        {
+            usage.cruft_packs += pack.len();
+        }
+    }
+}
+
+/// Whether `path` is under a fan-out directory like `objects/ab/`
+fn is_loose_object(path: &str) -> bool {
+    let mut parts = path.split('/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("objects"), Some(fanout), Some(_)) => fanout.len() == 2 && fanout.bytes().all(|b| b.is_ascii_hexdigit()),
+        _ => false,
+    }
+}
+
+/// The total a file at `path` (relative to the git dir) counts toward
+fn bucket<'a>(usage: &'a mut GitDirUsage, path: &str) -> &'a mut u64 {
+    // A linked worktree keeps its own index, HEAD and reflogs
+    let path = match path.strip_prefix("worktrees/") {
+        Some(rest) => rest.split_once('/').map_or(rest, |(_, inner)| inner),
+        None => path,
+    };
+    if is_loose_object(path) {
+        return &mut usage.loose_objects;
+    }
+    let mut parts = path.split('/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("objects"), Some("pack"), Some(_)) => &mut usage.packs,
+        (Some("refs"), Some(_), _) | (Some("packed-refs"), None, _) => &mut usage.refs,
+        (Some("index"), None, _) => &mut usage.index,
+        (Some("logs"), Some(_), _) => &mut usage.reflogs,
+        _ => &mut usage.other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_are_bucketed_by_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("objects/pack/pack-1.pack", 1000),
+            ("objects/pack/pack-1.idx", 100),
+            ("objects/pack/pack-2.pack", 400),
+            ("objects/pack/pack-2.mtimes", 8),
+            ("objects/ab/cdef0123", 50),
+            ("objects/info/packs", 7),
+            ("refs/heads/main", 41),
+            ("packed-refs", 200),
+            ("index", 300),
+            ("logs/HEAD", 20),
+            ("logs/refs/heads/main", 20),
+            ("worktrees/feature/index", 30),
+            ("worktrees/feature/logs/HEAD", 5),
+            ("config", 90),
+        ];
+        for (path, size) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![0u8; size]).unwrap();
+        }
+
+        let mut usage = GitDirUsage::default();
+        add_dir(dir.path(), "", &mut usage).unwrap();
+        assert_eq!(usage, GitDirUsage {
+            packs: 1508,
+            loose_objects: 50,
+            refs: 241,
+            index: 330,
+            reflogs: 45,
+            other: 97,
+            loose_count: 1,
+            pack_count: 2,
+            cruft_packs: 400,
+        });
+        assert_eq!(usage.total(), 2271);
+    }
+}
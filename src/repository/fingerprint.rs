@@ -0,0 +1,21 @@
+//! Fuzzy hashes of blob content, for spotting near-duplicate large files
+
+use anyhow::{Context, Result};
+
+use crate::model::Fingerprint;
+
+/// Fingerprint each blob's content, read from the object database
+///
+/// The result lines up with `oids`; a blob that can't be read (pruned since
+/// the scan, or only reachable through a promisor remote) gets `None`.
+pub fn fingerprint_blobs(repo_path: &str, oids: &[&[u8]]) -> Result<Vec<Option<Fingerprint>>> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    Ok(oids
+        .iter()
+        .map(|oid| {
+            let id = gix::ObjectId::try_from(*oid).ok()?;
+            let object = repo.find_object(id).ok()?;
+            Some(Fingerprint::of(&object.data))
+        })
+        .collect())
+}
@@ -1,8 +1,34 @@
+mod branch;
+mod codeowners;
+mod content;
 mod database;
+mod disk_usage;
+mod fingerprint;
+mod preview;
+mod remote;
+mod restore;
 mod scanner;
+mod snapshot;
+mod worktree;
 
-pub use database::{BlobMetaRecord, BlobRecord, Database};
-pub use scanner::GitScanner;
+pub use branch::branch_blobs;
+pub use codeowners::read_codeowners;
+pub use content::classify_blobs;
+pub use database::{
+    BlobMetaRecord, BlobPathRecord, BlobRecord, Database, DbTuning, HeadBlobRecord, PathRecord, RemovalRecord,
+    ScanRecords, SkippedRecord, TreePathRecord,
+};
+pub use disk_usage::git_dir_usage;
+pub use fingerprint::fingerprint_blobs;
+pub use preview::preview_blob;
+pub use remote::{fetch_remote_size, local_pack_size, origin_url, HostKind, RemoteHosts, RemoteRepo};
+pub use restore::{plan_restore, restore_file};
+pub use scanner::{
+    CancelToken, GitScanner, JsonProgress, MemoryStore, NoopProgress, PackSizeIndex, ProgressHandle,
+    ProgressReporter, RevRange, ScanCancelled, ScanOptions, ScanStore, SkippedKind, SkippedObject, DEFAULT_OBJECT_CACHE,
+};
+pub use snapshot::{SNAPSHOT_FORMAT, SNAPSHOT_VERSION};
+pub use worktree::Checkout;
 
 // Re-export the schema version for callers who need it
-pub const SCHEMA_VERSION: &str = "8";
+pub const SCHEMA_VERSION: &str = "25";
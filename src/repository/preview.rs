@@ -0,0 +1,19 @@
+//! Blob content for the TUI's preview popup
+
+use anyhow::{Context, Result};
+
+use crate::model::BlobPreview;
+
+/// Preview of the blob `oid`, read from the object database, as opened
+/// from `path`
+///
+/// gix inflates the whole object, so a preview of a huge blob takes a
+/// moment; only its first `PREVIEW_BYTES` are kept.
+pub fn preview_blob(repo_path: &str, oid: &[u8], path: &str) -> Result<BlobPreview> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let id = gix::ObjectId::try_from(oid).context("Invalid object id")?;
+    let object = repo
+        .find_object(id)
+        .with_context(|| format!("Could not read blob {} (pruned since the scan?)", id))?;
+    Ok(BlobPreview::new(path, &id.to_string(), object.data.len() as u64, &object.data))
+}
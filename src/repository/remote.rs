@@ -0,0 +1,321 @@
+//! Hosting API size hints
+//!
+//! Asks GitHub or GitLab how large they think a repository is, so the
+//! number can be shown next to the locally computed pack size. Servers
+//! repack on their own schedule and count LFS separately, so the two
+//! rarely agree exactly.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::model::RemoteSize;
+use crate::util::percent_encode;
+
+/// Supported hosting APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostKind {
+    GitHub,
+    GitLab,
+}
+
+/// Self-hosted GitHub and GitLab instances (the `[remote]` config section)
+///
+/// Only github.com and gitlab.com are recognised unless listed here, and
+/// only `REPODIET_REMOTE_TOKEN` is ever sent to a listed host.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RemoteHosts {
+    /// GitHub Enterprise Server hosts
+    pub github: Vec<String>,
+    /// Self-managed GitLab hosts
+    pub gitlab: Vec<String>,
+}
+
+impl RemoteHosts {
+    fn kind_of(&self, host: &str) -> Option<HostKind> {
+        let listed = |hosts: &[String]| hosts.iter().any(|h| h.eq_ignore_ascii_case(host));
+        if listed(&self.github) {
+            Some(HostKind::GitHub)
+        } else if listed(&self.gitlab) {
+            Some(HostKind::GitLab)
+        } else {
+            None
+        }
+    }
+}
+
+/// A repository on a recognised host, parsed from a remote URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRepo {
+    pub kind: HostKind,
+    pub host: String,
+    /// `owner/name` (GitLab allows nested groups: `group/sub/name`)
+    pub path: String,
+    /// github.com or gitlab.com rather than a self-hosted instance
+    pub canonical: bool,
+}
+
+impl RemoteRepo {
+    /// Parse `https://host/owner/repo(.git)`, `ssh://[user@]host[:port]/owner/repo`
+    /// or scp-style `user@host:owner/repo` URLs; `None` for hosts other than
+    /// github.com, gitlab.com and those in `hosts`
+    pub fn parse(url: &str, hosts: &RemoteHosts) -> Option<Self> {
+        let url = url.trim();
+        let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit('@').next()?;
+            (host.split(':').next()?, path)
+        } else {
+            // scp-style: [user@]host:path
+            let (authority, path) = url.split_once(':')?;
+            (authority.rsplit('@').next()?, path)
+        };
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        if host.is_empty() || !path.contains('/') {
+            return None;
+        }
+
+        let host_lower = host.to_ascii_lowercase();
+        let canonical = host_lower.strip_prefix("api.").unwrap_or(&host_lower);
+        let (kind, host, canonical) = match canonical {
+            "github.com" => (HostKind::GitHub, canonical.to_string(), true),
+            "gitlab.com" => (HostKind::GitLab, canonical.to_string(), true),
+            // Matched exactly: a look-alike such as github.example.org must
+            // not be sent GITHUB_TOKEN
+            _ => (hosts.kind_of(&host_lower)?, host_lower, false),
+        };
+        // Goes into the API URL as is, next to the token
+        if kind == HostKind::GitHub && !is_owner_and_repo(path) {
+            return None;
+        }
+
+        Some(Self {
+            kind,
+            host,
+            path: path.to_string(),
+            canonical,
+        })
+    }
+
+    /// API endpoint describing the repository
+    fn api_url(&self) -> String {
+        match self.kind {
+            HostKind::GitHub if self.canonical => {
+                format!("https://api.github.com/repos/{}", self.path)
+            }
+            // GitHub Enterprise Server
+            HostKind::GitHub => format!("https://{}/api/v3/repos/{}", self.host, self.path),
+            HostKind::GitLab => format!(
+                "https://{}/api/v4/projects/{}?statistics=true",
+                self.host,
+                percent_encode(&self.path)
+            ),
+        }
+    }
+
+    /// Environment variables checked for a token, most specific first
+    ///
+    /// CI sets `GITHUB_TOKEN` and `GITLAB_TOKEN` for the public hosts, so
+    /// self-hosted instances only get the explicit `REPODIET_REMOTE_TOKEN`.
+    pub fn token_vars(&self) -> &'static [&'static str] {
+        match (self.kind, self.canonical) {
+            (HostKind::GitHub, true) => &["REPODIET_REMOTE_TOKEN", "GITHUB_TOKEN"],
+            (HostKind::GitLab, true) => &["REPODIET_REMOTE_TOKEN", "GITLAB_TOKEN"],
+            (_, false) => &["REPODIET_REMOTE_TOKEN"],
+        }
+    }
+
+    /// The first non-empty token among `token_vars`, read through `env`
+    pub fn token(&self, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+        self.token_vars().iter().find_map(|var| env(var).filter(|t| !t.is_empty()))
+    }
+}
+
+/// Whether `path` is exactly `owner/repo`, in the characters GitHub allows
+/// in either
+fn is_owner_and_repo(path: &str) -> bool {
+    let name = |s: &str| {
+        !s.is_empty()
+            && s != "."
+            && s != ".."
+            && s.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+    };
+    path.split_once('/').is_some_and(|(owner, repo)| name(owner) && name(repo))
+}
+
+/// URL of the `origin` remote, if configured
+pub fn origin_url(repo_path: &str) -> Result<Option<String>> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let url = repo
+        .config_snapshot()
+        .string("remote.origin.url")
+        .map(|url| url.to_string());
+    Ok(url)
+}
+
+/// Total size of the repository's local pack files on disk
+pub fn local_pack_size(repo_path: &str) -> Result<u64> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let pack_dir = repo.common_dir().join("objects").join("pack");
+    pack_files_size(&pack_dir)
+}
+
+fn pack_files_size(pack_dir: &Path) -> Result<u64> {
+    if !pack_dir.exists() {
+        return Ok(0);
+    }
+    let mut total = 0;
+    for entry in std::fs::read_dir(pack_dir)? {
+        let entry = entry?;
+        if entry.path().extension().is_some_and(|ext| ext == "pack") {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Query the hosting API (blocking) for the repository's reported size
+pub fn fetch_remote_size(remote: &RemoteRepo, token: Option<&str>) -> Result<RemoteSize> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(10)))
+        .build()
+        .into();
+
+    let mut request = agent
+        .get(remote.api_url())
+        .header("User-Agent", "repodiet");
+    if let Some(token) = token {
+        request = match remote.kind {
+            HostKind::GitHub => request
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Accept", "application/vnd.github+json"),
+            HostKind::GitLab => request.header("PRIVATE-TOKEN", token),
+        };
+    }
+
+    let body = request
+        .call()
+        .with_context(|| format!("Request to {} failed", remote.host))?
+        .body_mut()
+        .read_to_string()?;
+    parse_response(remote, &body)
+}
+
+fn parse_response(remote: &RemoteRepo, body: &str) -> Result<RemoteSize> {
+    let json: serde_json::Value = serde_json::from_str(body).context("Invalid API response")?;
+    let (repository_size, lfs_size) = match remote.kind {
+        // GitHub reports disk usage in KiB and has no per-repository LFS figure
+        HostKind::GitHub => match json["size"].as_u64() {
+            Some(kib) => (kib * 1024, None),
+            None => bail!("GitHub response has no size for {}", remote.path),
+        },
+        // Statistics are only included for tokens with at least reporter access
+        HostKind::GitLab => {
+            let stats = &json["statistics"];
+            match stats["repository_size"].as_u64() {
+                Some(bytes) => (bytes, stats["lfs_objects_size"].as_u64()),
+                None => bail!("GitLab response has no statistics for {} (token needs reporter access)", remote.path),
+            }
+        }
+    };
+
+    Ok(RemoteSize {
+        host: remote.host.clone(),
+        repository_size,
+        lfs_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_urls() {
+        let hosts = RemoteHosts::default();
+        let https = RemoteRepo::parse("https://github.com/IlyaGulya/repodiet.git", &hosts).unwrap();
+        assert_eq!(https.kind, HostKind::GitHub);
+        assert_eq!(https.path, "IlyaGulya/repodiet");
+        assert_eq!(https.api_url(), "https://api.github.com/repos/IlyaGulya/repodiet");
+
+        let scp = RemoteRepo::parse("git@gitlab.com:group/sub/project.git", &hosts).unwrap();
+        assert_eq!(scp.kind, HostKind::GitLab);
+        assert_eq!(scp.api_url(), "https://gitlab.com/api/v4/projects/group%2Fsub%2Fproject?statistics=true");
+
+        // Self-hosted instances only when configured
+        assert_eq!(RemoteRepo::parse("ssh://git@github.example.com:2222/org/repo", &hosts), None);
+        let enterprise = RemoteHosts { github: vec!["GitHub.example.com".into()], gitlab: vec![] };
+        let ssh = RemoteRepo::parse("ssh://git@github.example.com:2222/org/repo", &enterprise).unwrap();
+        assert_eq!(ssh.host, "github.example.com");
+        assert!(!ssh.canonical);
+        assert_eq!(ssh.api_url(), "https://github.example.com/api/v3/repos/org/repo");
+
+        assert_eq!(RemoteRepo::parse("https://bitbucket.org/org/repo.git", &hosts), None);
+        assert_eq!(RemoteRepo::parse("/srv/git/repo.git", &hosts), None);
+
+        // Anything but owner/repo would steer the authenticated request elsewhere
+        for url in [
+            "https://github.com/org/repo/extra",
+            "https://github.com/org/../../user",
+            "https://github.com/org/repo?per_page=1",
+            "https://github.com/org/repo#x",
+            "git@github.com:org/re%2Fpo.git",
+            "https://github.com/org//repo",
+        ] {
+            assert_eq!(RemoteRepo::parse(url, &hosts), None, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_ci_tokens_only_go_to_canonical_hosts() {
+        let env = |var: &str| match var {
+            "GITHUB_TOKEN" => Some("ghs_ci".to_string()),
+            "GITLAB_TOKEN" => Some("glpat_ci".to_string()),
+            _ => None,
+        };
+        let hosts = RemoteHosts::default();
+        let github = RemoteRepo::parse("https://github.com/o/r", &hosts).unwrap();
+        assert_eq!(github.token(env).as_deref(), Some("ghs_ci"));
+        let api = RemoteRepo::parse("https://api.github.com/o/r", &hosts).unwrap();
+        assert_eq!(api.host, "github.com");
+        assert_eq!(api.token(env).as_deref(), Some("ghs_ci"));
+
+        // Look-alikes are not recognised at all
+        for url in [
+            "https://github.attacker.example/o/r",
+            "git@gitlab.com.attacker.example:o/r.git",
+            "https://notgithub.com/o/r",
+        ] {
+            assert_eq!(RemoteRepo::parse(url, &hosts), None, "{}", url);
+        }
+
+        // A configured instance gets the explicit token only
+        let hosts = RemoteHosts { github: vec![], gitlab: vec!["gitlab.corp.example".into()] };
+        let corp = RemoteRepo::parse("https://gitlab.corp.example/o/r", &hosts).unwrap();
+        assert_eq!(corp.kind, HostKind::GitLab);
+        assert_eq!(corp.token(env), None);
+        let explicit = |var: &str| (var == "REPODIET_REMOTE_TOKEN").then(|| "explicit".to_string()).or_else(|| env(var));
+        assert_eq!(corp.token(explicit).as_deref(), Some("explicit"));
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let github = RemoteRepo::parse("https://github.com/o/r", &RemoteHosts::default()).unwrap();
+        let size = parse_response(&github, r#"{"size": 2048}"#).unwrap();
+        assert_eq!(size.repository_size, 2048 * 1024);
+        assert_eq!(size.lfs_size, None);
+
+        let gitlab = RemoteRepo::parse("https://gitlab.com/o/r", &RemoteHosts::default()).unwrap();
+        let body = r#"{"statistics": {"repository_size": 5000, "lfs_objects_size": 700}}"#;
+        let size = parse_response(&gitlab, body).unwrap();
+        assert_eq!(size.repository_size, 5000);
+        assert_eq!(size.lfs_size, Some(700));
+
+        // Without a privileged token GitLab omits statistics
+        assert!(parse_response(&gitlab, r#"{"id": 1}"#).is_err());
+    }
+}
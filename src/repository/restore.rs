@@ -0,0 +1,78 @@
+//! Bringing a deleted file back from history into the worktree
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::model::RestorePlan;
+
+/// How to restore blob `oid` at `path`: the newest commit on HEAD's history
+/// whose tree still has it there
+///
+/// Walking newest first finds the commit just before a deletion quickly;
+/// a blob that was never committed at `path` walks all of history.
+pub fn plan_restore(repo_path: &str, oid: &[u8], path: &str) -> Result<RestorePlan> {
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let id = gix::ObjectId::try_from(oid).context("Invalid object id")?;
+    let head = repo.head_commit().context("Failed to get HEAD commit")?.id;
+    for info in repo.rev_walk([head]).all()? {
+        let commit = info.context("Failed to walk the history")?.id;
+        let tree = repo.find_commit(commit)?.tree().with_context(|| format!("Failed to read the tree of {}", commit))?;
+        let entry = tree.lookup_entry(path.split('/'))?;
+        if entry.is_some_and(|entry| entry.mode().is_blob() && entry.oid() == id) {
+            let overwrites = repo.workdir().is_some_and(|dir| dir.join(path).symlink_metadata().is_ok());
+            return Ok(RestorePlan::new(&commit.to_string(), path, overwrites));
+        }
+    }
+    bail!("No commit on HEAD's history has {} at {}", id, path)
+}
+
+/// Write `path` as `commit` has it into the worktree, returning where it
+/// went
+///
+/// Like `git checkout <commit> -- <path>` without staging the file: `git
+/// add` keeps it, `git status` shows it until then. Directories on the way
+/// must not be symlinks, and a symlink at `path` is replaced by the file.
+pub fn restore_file(repo_path: &str, commit: &str, path: &str) -> Result<PathBuf> {
+    if path.is_empty() || !Path::new(path).components().all(|c| matches!(c, Component::Normal(name) if name != ".git")) {
+        bail!("Refusing to write outside the worktree: {}", path);
+    }
+    let repo = gix::open(repo_path).context("Failed to open git repository")?;
+    let workdir = repo.workdir().context("A bare repository has no worktree to restore into")?.to_path_buf();
+    let id = gix::ObjectId::from_hex(commit.as_bytes()).context("Invalid commit id")?;
+    let tree = repo.find_commit(id)?.tree().with_context(|| format!("Failed to read the tree of {}", commit))?;
+    let entry = tree
+        .lookup_entry(path.split('/'))?
+        .filter(|entry| entry.mode().is_blob())
+        .with_context(|| format!("{} has no file at {}", commit, path))?;
+    let blob = repo.find_object(entry.oid()).with_context(|| format!("Could not read blob {}", entry.oid()))?;
+
+    let target = workdir.join(path);
+    let dir = target.parent().unwrap_or(&workdir);
+    // A symlinked directory already in the worktree, say `assets -> ~/.ssh`,
+    // would take the file outside it
+    let mut below = workdir.clone();
+    for name in Path::new(path).parent().into_iter().flat_map(Path::components) {
+        below.push(name);
+        if below.symlink_metadata().is_ok_and(|meta| meta.file_type().is_symlink()) {
+            bail!("Refusing to write through the symlink {}", below.display());
+        }
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    let root = workdir.canonicalize().with_context(|| format!("Could not resolve {}", workdir.display()))?;
+    if !dir.canonicalize().is_ok_and(|dir| dir.starts_with(&root)) {
+        bail!("Refusing to write outside the worktree: {}", path);
+    }
+    // Like git, replace a symlink at the path instead of writing to its target
+    if target.symlink_metadata().is_ok_and(|meta| meta.file_type().is_symlink()) {
+        fs::remove_file(&target).with_context(|| format!("Could not remove the symlink {}", target.display()))?;
+    }
+    fs::write(&target, &blob.data).with_context(|| format!("Could not write {}", target.display()))?;
+    #[cfg(unix)]
+    if entry.mode().is_executable() {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Could not make {} executable", target.display()))?;
+    }
+    Ok(target)
+}
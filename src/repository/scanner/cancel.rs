@@ -0,0 +1,69 @@
+//! Cancelling a running scan
+//!
+//! The scanner checks its token between commits. Commits scanned before the
+//! token was cancelled are saved like a finished scan's, but HEAD is not
+//! recorded as indexed, so the next scan picks up the remaining commits.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared flag asking a scan to stop; clones cancel together
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Cancels its token when dropped, unless disarmed first
+pub(super) struct CancelOnDrop(Option<CancelToken>);
+
+impl CancelOnDrop {
+    pub(super) fn new(token: CancelToken) -> Self {
+        Self(Some(token))
+    }
+
+    pub(super) fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(token) = self.0.take() {
+            token.cancel();
+        }
+    }
+}
+
+/// Error returned by `GitScanner::scan` when its token was cancelled
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanCancelled {
+    /// Commits scanned and saved before stopping
+    pub scanned: usize,
+    /// Commits the scan set out to cover
+    pub total: usize,
+}
+
+impl fmt::Display for ScanCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Scan cancelled after {} of {} commits; the next scan resumes from there",
+            self.scanned, self.total
+        )
+    }
+}
+
+impl std::error::Error for ScanCancelled {}
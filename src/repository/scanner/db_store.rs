@@ -4,22 +4,31 @@ use anyhow::Result;
 use gix::ObjectId;
 use rustc_hash::FxHashSet;
 
-use crate::model::TreeNode;
-use crate::repository::{BlobMetaRecord, BlobRecord, Database};
+use crate::model::Tree;
+use crate::repository::{
+    BlobMetaRecord, BlobPathRecord, BlobRecord, Database, HeadBlobRecord, PathRecord, RemovalRecord, ScanRecords,
+    SkippedRecord, TreePathRecord,
+};
 
 use super::interner::PathInterner;
 use super::progress::ProgressReporter;
 use super::store::ScanStore;
-use super::types::ScanDelta;
+use super::types::{HeadSnapshot, PathId, ScanDelta, SkippedObject};
 
 fn oid20(oid: ObjectId) -> [u8; 20] {
     oid.as_bytes().try_into().unwrap()
 }
 
-fn convert_delta<'a>(
-    delta: &'a ScanDelta,
-    interner: &'a PathInterner,
-) -> (Vec<BlobRecord<'a>>, Vec<BlobMetaRecord<'a>>) {
+/// Records for `delta`, with the paths `interner` holds beyond the first
+/// `stored` the index already has
+fn convert_delta<'a>(delta: &'a ScanDelta, interner: &'a PathInterner, stored: usize) -> ScanRecords<'a> {
+    let paths = interner
+        .paths_since(stored)
+        .iter()
+        .enumerate()
+        .map(|(i, path)| PathRecord::new((stored + i) as i64, path))
+        .collect();
+
     let blobs = delta
         .blobs
         .iter()
@@ -30,6 +39,7 @@ fn convert_delta<'a>(
                 row.cumulative_size,
                 row.current_size,
             )
+            .with_kind(row.kind)
         })
         .collect();
 
@@ -42,12 +52,40 @@ fn convert_delta<'a>(
                 row.size,
                 interner.get_str(row.path_id),
                 row.author.clone(),
+                row.email.clone(),
                 row.timestamp,
             )
+            .with_commit(oid20(row.commit), row.subject.as_str())
         })
         .collect();
 
-    (blobs, metadata)
+    let blob_paths = delta
+        .occurrences
+        .iter()
+        .map(|row| BlobPathRecord::new(oid20(row.oid), row.path_id.0.into()))
+        .collect();
+
+    let seen_trees = delta
+        .trees
+        .iter()
+        .map(|row| TreePathRecord::new(oid20(row.oid), row.path_id.0.into()))
+        .collect();
+
+    let skipped = delta
+        .skipped
+        .iter()
+        .map(|row| SkippedRecord::new(oid20(row.oid), row.kind.as_str(), row.path.as_str(), row.reason.as_str()))
+        .collect();
+
+    let gitlinks = delta.gitlinks.iter().map(|id| interner.get_str(*id)).collect();
+
+    let removals = delta
+        .removals
+        .iter()
+        .map(|row| RemovalRecord::new(row.path_id.0.into(), row.timestamp))
+        .collect();
+
+    ScanRecords { paths, blobs, metadata, blob_paths, seen_trees, skipped, gitlinks, removals }
 }
 
 fn convert_commits(commits: &[ObjectId]) -> Vec<[u8; 20]> {
@@ -63,6 +101,50 @@ impl ScanStore for Database {
         self.set_metadata("head_oid", oid_hex).await
     }
 
+    async fn get_scan_limits(&self) -> Option<String> {
+        self.get_metadata("scan_limits").await
+    }
+
+    async fn set_scan_limits(&self, limits: &str) -> Result<()> {
+        self.set_metadata("scan_limits", limits).await
+    }
+
+    async fn get_replacements(&self) -> Option<String> {
+        self.get_metadata("replacements").await
+    }
+
+    async fn set_replacements(&self, replacements: &str) -> Result<()> {
+        self.set_metadata("replacements", replacements).await
+    }
+
+    async fn get_mailmap(&self) -> Option<String> {
+        self.get_metadata("mailmap").await
+    }
+
+    async fn set_mailmap(&self, mailmap: &str) -> Result<()> {
+        self.set_metadata("mailmap", mailmap).await
+    }
+
+    async fn get_scan_path(&self) -> Option<String> {
+        self.get_metadata("scan_path").await
+    }
+
+    async fn set_scan_path(&self, path: &str) -> Result<()> {
+        self.set_metadata("scan_path", path).await
+    }
+
+    async fn get_scan_range(&self) -> Option<String> {
+        self.get_metadata("scan_range").await
+    }
+
+    async fn set_scan_range(&self, base: &str) -> Result<()> {
+        self.set_metadata("scan_range", base).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.clear_index().await
+    }
+
     async fn load_scanned_commits(&self) -> FxHashSet<[u8; 20]> {
         self.load_scanned_commit_oids().await
     }
@@ -75,30 +157,48 @@ impl ScanStore for Database {
             .collect())
     }
 
+    async fn load_paths(&self) -> Result<PathInterner> {
+        Ok(PathInterner::from_paths(Database::load_paths(self).await?))
+    }
+
+    async fn load_seen_trees(&self) -> Result<Vec<(ObjectId, PathId)>> {
+        let rows = Database::load_seen_trees(self).await?;
+        Ok(rows
+            .into_iter()
+            .map(|(oid, path_id)| (ObjectId::from_bytes_or_panic(&oid), PathId(path_id as u32)))
+            .collect())
+    }
+
     async fn save_delta_rows(
         &self,
         delta: &ScanDelta,
         interner: &PathInterner,
         progress: &dyn ProgressReporter,
     ) -> Result<()> {
-        if delta.blobs.is_empty() {
+        if delta.is_empty() {
             return Ok(());
         }
 
-        let (blobs_for_db, metadata_for_db) = convert_delta(delta, interner);
+        let records = convert_delta(delta, interner, self.path_count().await?);
 
-        let pb = progress.start("Indexing", blobs_for_db.len() as u64);
-        self.save_blobs_with_callback(&blobs_for_db, |n| pb.inc(n as u64))
+        self.save_paths(&records.paths).await?;
+        let pb = progress.start("Indexing", records.blobs.len() as u64);
+        self.save_blobs_with_callback(&records.blobs, |n| pb.inc(n as u64))
             .await?;
         pb.finish();
 
-        if !metadata_for_db.is_empty() {
-            let pb = progress.start("Indexing metadata", metadata_for_db.len() as u64);
-            self.save_blob_metadata_with_callback(&metadata_for_db, |n| pb.inc(n as u64))
+        self.save_blob_paths(&records.blob_paths).await?;
+
+        if !records.metadata.is_empty() {
+            let pb = progress.start("Indexing metadata", records.metadata.len() as u64);
+            self.save_blob_metadata_with_callback(&records.metadata, |n| pb.inc(n as u64))
                 .await?;
             pb.finish();
         }
 
+        // Only after the rows, so a pruned tree always has its blobs stored
+        self.save_seen_trees(&records.seen_trees).await?;
+
         Ok(())
     }
 
@@ -110,7 +210,26 @@ impl ScanStore for Database {
         Database::mark_commits_scanned(self, &convert_commits(commits)).await
     }
 
-    async fn load_tree(&self) -> Result<TreeNode> {
+    async fn replace_corrupt_packs(&self, packs: &[SkippedObject]) -> Result<()> {
+        let records: Vec<_> = packs
+            .iter()
+            .map(|pack| SkippedRecord::new(oid20(pack.oid), pack.kind.as_str(), pack.path.as_str(), pack.reason.as_str()))
+            .collect();
+        Database::replace_corrupt_packs(self, &records).await
+    }
+
+    async fn replace_head_blobs(&self, head: &HeadSnapshot, interner: &PathInterner) -> Result<()> {
+        let records: Vec<_> = head
+            .blobs_by_path
+            .iter()
+            .map(|(path_id, blob)| {
+                HeadBlobRecord::new(oid20(blob.oid), interner.get_str(*path_id), blob.size).with_kind(blob.kind)
+            })
+            .collect();
+        Database::replace_head_blobs(self, &records).await
+    }
+
+    async fn load_tree(&self) -> Result<Tree> {
         Database::load_tree(self).await
     }
 
@@ -121,15 +240,14 @@ impl ScanStore for Database {
         interner: &PathInterner,
         progress: &dyn ProgressReporter,
     ) -> Result<()> {
-        let (blobs_for_db, metadata_for_db) = convert_delta(delta, interner);
+        let records = convert_delta(delta, interner, self.path_count().await?);
         let commit_oids = convert_commits(commits);
 
-        let pb_blobs = progress.start("Indexing", blobs_for_db.len() as u64);
-        let pb_meta = progress.start("Indexing metadata", metadata_for_db.len() as u64);
+        let pb_blobs = progress.start("Indexing", records.blobs.len() as u64);
+        let pb_meta = progress.start("Indexing metadata", records.metadata.len() as u64);
 
         self.apply_scan_with_callback(
-            &blobs_for_db,
-            &metadata_for_db,
+            &records,
             &commit_oids,
             |n| pb_blobs.inc(n as u64),
             |n| pb_meta.inc(n as u64),
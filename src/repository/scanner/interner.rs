@@ -5,11 +5,12 @@
 use gix::bstr::BString;
 use rustc_hash::FxHashMap;
 
+use super::memory::{map_bytes, vec_bytes};
 use super::types::PathId;
 
 /// Path interner to avoid allocating String for every path during tree traversal.
 /// Maps paths to PathId for compact storage in HashSets.
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 pub struct PathInterner {
     map: FxHashMap<BString, u32>,
     vec: Vec<BString>,
@@ -20,6 +21,18 @@ impl PathInterner {
         Self::default()
     }
 
+    /// An interner holding `paths` under their positions as IDs, as a
+    /// stored path dictionary lists them
+    pub fn from_paths(paths: impl IntoIterator<Item = impl Into<BString>>) -> Self {
+        let mut interner = Self::new();
+        for path in paths {
+            let path = path.into();
+            interner.map.insert(path.clone(), interner.vec.len() as u32);
+            interner.vec.push(path);
+        }
+        interner
+    }
+
     /// Intern a path and return its ID
     pub fn intern(&mut self, bytes: &[u8]) -> PathId {
         // Check if already interned
@@ -44,8 +57,16 @@ impl PathInterner {
         self.vec[id.0 as usize].as_ref()
     }
 
+    /// Paths interned at `start` and after, in ID order
+    ///
+    /// Interning them in order into a clone taken when `len()` was `start`
+    /// gives them the same IDs there; a store holding the first `start`
+    /// paths saves these to catch up.
+    pub fn paths_since(&self, start: usize) -> &[BString] {
+        &self.vec[start..]
+    }
+
     /// Number of interned paths
-    #[allow(dead_code)]
     pub fn len(&self) -> usize {
         self.vec.len()
     }
@@ -54,6 +75,13 @@ impl PathInterner {
     pub fn is_empty(&self) -> bool {
         self.vec.is_empty()
     }
+
+    /// Approximate memory the interner holds, for `--profile`; every path
+    /// is stored twice, as a map key and in ID order
+    pub fn heap_bytes(&self) -> usize {
+        let strings: usize = self.vec.iter().map(|path| path.capacity()).sum();
+        map_bytes(&self.map) + vec_bytes(&self.vec) + 2 * strings
+    }
 }
 
 #[cfg(test)]
@@ -82,4 +110,38 @@ mod tests {
         let id = interner.intern(b"src/main.rs");
         assert_eq!(interner.get_str(id), "src/main.rs");
     }
+
+    #[test]
+    fn test_clone_catches_up_with_paths_since() {
+        let mut interner = PathInterner::new();
+        interner.intern(b"README.md");
+        let mut replica = interner.clone();
+        let start = interner.len();
+        let id = interner.intern(b"src/lib.rs");
+        interner.intern(b"README.md");
+
+        assert_eq!(interner.paths_since(start).len(), 1);
+        for path in interner.paths_since(start) {
+            replica.intern(path);
+        }
+        assert_eq!(replica.get_str(id), "src/lib.rs");
+        assert_eq!(replica.len(), interner.len());
+    }
+
+    #[test]
+    fn test_from_paths_keeps_positions() {
+        let mut interner = PathInterner::from_paths([&b"src"[..], b"src/lib.rs"]);
+        assert_eq!(interner.intern(b"src/lib.rs"), PathId(1));
+        assert_eq!(interner.intern(b"README.md"), PathId(2));
+        assert_eq!(interner.get_str(PathId(0)), "src");
+    }
+
+    #[test]
+    fn test_heap_bytes_counts_each_path_twice() {
+        let mut interner = PathInterner::new();
+        assert_eq!(interner.heap_bytes(), 0);
+        let path = "a/long/enough/path/".repeat(50);
+        interner.intern(path.as_bytes());
+        assert!(interner.heap_bytes() >= 2 * path.len());
+    }
 }
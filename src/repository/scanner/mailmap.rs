@@ -0,0 +1,76 @@
+//! Author identities normalized through the repository's `.mailmap`
+//!
+//! The same person often commits under several names and emails; git's
+//! mailmap (the worktree's `.mailmap`, `mailmap.file` and `mailmap.blob`)
+//! says which identity each of them is, so blobs are credited to one.
+
+use gix::actor::SignatureRef;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// A repository's mailmap and what identifies it
+pub struct Mailmap {
+    snapshot: gix::mailmap::Snapshot,
+    /// Identifies the entries in effect ("" for none), so a cached index
+    /// whose authors were mapped differently can be detected
+    pub label: String,
+}
+
+impl Mailmap {
+    /// Load `repo`'s mailmap; one that can't be read counts as empty, as in git
+    pub fn open(repo: &gix::Repository) -> Self {
+        Self::new(repo.open_mailmap())
+    }
+
+    fn new(snapshot: gix::mailmap::Snapshot) -> Self {
+        let entries = snapshot.entries();
+        let label = if entries.is_empty() {
+            String::new()
+        } else {
+            let mut hasher = DefaultHasher::new();
+            for entry in &entries {
+                (entry.old_email(), entry.old_name(), entry.new_email(), entry.new_name()).hash(&mut hasher);
+            }
+            format!("{}:{:016x}", entries.len(), hasher.finish())
+        };
+        Self { snapshot, label }
+    }
+
+    /// The (name, email) `author` is known as
+    pub fn resolve(&self, author: SignatureRef<'_>) -> (String, String) {
+        let resolved = self.snapshot.resolve_cow(author);
+        (resolved.name.to_string(), resolved.email.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_bytes(contents: &[u8]) -> Mailmap {
+        Mailmap::new(gix::mailmap::Snapshot::from_bytes(contents))
+    }
+
+    fn signature<'a>(name: &'a str, email: &'a str) -> SignatureRef<'a> {
+        SignatureRef { name: name.into(), email: email.into(), time: "0 +0000" }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mailmap = from_bytes(
+            b"Ann Lee <ann@corp.example> <ann@home.example>\n\
+              <ann@corp.example> Annie <annie@laptop.local>\n",
+        );
+        assert_eq!(
+            mailmap.resolve(signature("ann", "ann@home.example")),
+            ("Ann Lee".to_string(), "ann@corp.example".to_string())
+        );
+        assert_eq!(
+            mailmap.resolve(signature("Annie", "annie@laptop.local")),
+            ("Annie".to_string(), "ann@corp.example".to_string())
+        );
+        assert_eq!(mailmap.resolve(signature("Bob", "bob@x.example")), ("Bob".to_string(), "bob@x.example".to_string()));
+        assert!(mailmap.label.starts_with("2:"));
+        assert_eq!(from_bytes(b"# nothing yet\n").label, "");
+    }
+}
@@ -0,0 +1,51 @@
+//! Approximate heap sizes of what a scan holds in memory, for `--profile`
+//!
+//! Collections are counted by capacity rather than length, since that is
+//! what they have allocated. Hash tables are laid out as hashbrown does: a
+//! power of two buckets with room for 8/7 of the capacity, each holding an
+//! entry and a control byte.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::mem::size_of;
+
+/// Heap bytes of a hash table with room for `capacity` entries of `T`
+fn table_bytes<T>(capacity: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    let buckets = (capacity * 8 / 7).next_power_of_two();
+    buckets * (size_of::<T>() + 1)
+}
+
+/// Heap bytes of `set`, not counting anything its entries point to
+pub fn set_bytes<T>(set: &FxHashSet<T>) -> usize {
+    table_bytes::<T>(set.capacity())
+}
+
+/// Heap bytes of `map`, not counting anything its entries point to
+pub fn map_bytes<K, V>(map: &FxHashMap<K, V>) -> usize {
+    table_bytes::<(K, V)>(map.capacity())
+}
+
+/// Heap bytes of `vec`, not counting anything its items point to
+pub fn vec_bytes<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_bytes() {
+        assert_eq!(table_bytes::<u64>(0), 0);
+        // 7 entries fit 8 buckets, 8 need 16
+        assert_eq!(table_bytes::<u64>(7), 8 * 9);
+        assert_eq!(table_bytes::<u64>(14), 16 * 9);
+
+        let mut set: FxHashSet<u32> = FxHashSet::default();
+        set.extend(0..100);
+        assert!(set_bytes(&set) >= 100 * (size_of::<u32>() + 1));
+        assert_eq!(vec_bytes(&Vec::<u64>::with_capacity(10)), 80);
+    }
+}
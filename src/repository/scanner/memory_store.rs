@@ -0,0 +1,312 @@
+//! In-memory implementation of ScanStore
+//!
+//! Keeps the same state as the database (per-path totals, the HEAD snapshot,
+//! seen blobs and trees, scanned commits) in hash maps, so the scanner can
+//! run without SQLite: in tests, benchmarks, or a program embedding the
+//! scanner. Incremental scans work as long as the store is kept around.
+//!
+//! Only what the scanner itself needs is kept, plus blob metadata for
+//! `top_blobs`; path history and removal dates are not.
+
+use anyhow::Result;
+use gix::ObjectId;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::model::{EntryKind, LargeBlobInfo, Tree};
+
+use super::interner::PathInterner;
+use super::progress::ProgressReporter;
+use super::store::ScanStore;
+use super::types::{HeadSnapshot, PathId, ScanDelta, SkippedKind, SkippedObject};
+
+/// Scan state held in memory; see the module docs
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    head_oid: Option<String>,
+    scan_limits: Option<String>,
+    replacements: Option<String>,
+    mailmap: Option<String>,
+    scan_path: Option<String>,
+    scan_range: Option<String>,
+    scanned_commits: FxHashSet<[u8; 20]>,
+    seen_blobs: FxHashSet<ObjectId>,
+    /// (blob, path) pairs counted, so a later scan meeting one again does not
+    /// count it twice
+    blob_paths: FxHashSet<(ObjectId, PathId)>,
+    /// Every path interned so far, like the database's path dictionary
+    path_ids: PathInterner,
+    seen_trees: FxHashSet<(ObjectId, PathId)>,
+    paths: FxHashMap<String, PathTotals>,
+    /// Path -> (size, kind) of the blob HEAD has there
+    head_blobs: FxHashMap<String, (i64, EntryKind)>,
+    head_oids: FxHashSet<ObjectId>,
+    blobs: FxHashMap<ObjectId, LargeBlobInfo>,
+    skipped: FxHashMap<ObjectId, SkippedObject>,
+}
+
+#[derive(Debug, Default)]
+struct PathTotals {
+    cumulative_size: i64,
+    current_size: i64,
+    blob_count: i64,
+    kind: EntryKind,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest blobs by on-disk size, like `Database::get_top_blobs`
+    pub fn top_blobs(&self, limit: usize) -> Vec<LargeBlobInfo> {
+        let state = self.lock();
+        let mut blobs: Vec<LargeBlobInfo> = state
+            .blobs
+            .iter()
+            .map(|(oid, blob)| LargeBlobInfo { in_head: state.head_oids.contains(oid), ..blob.clone() })
+            .collect();
+        blobs.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.oid.cmp(&b.oid)));
+        blobs.truncate(limit);
+        blobs
+    }
+
+    /// Objects the scans so far could not read (commits read fine later drop out)
+    pub fn skipped(&self) -> Vec<SkippedObject> {
+        let mut skipped: Vec<_> = self.lock().skipped.values().cloned().collect();
+        skipped.sort_by_key(|s| s.oid);
+        skipped
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is only touched in short non-panicking sections
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl State {
+    fn save_rows(&mut self, delta: &ScanDelta, interner: &PathInterner) {
+        for row in &delta.blobs {
+            self.seen_blobs.insert(row.oid);
+            if !self.blob_paths.insert((row.oid, row.path_id)) {
+                continue;
+            }
+            let totals = self.paths.entry(interner.get_str(row.path_id).to_string()).or_default();
+            totals.cumulative_size += row.cumulative_size;
+            totals.current_size += row.current_size;
+            totals.blob_count += 1;
+            totals.kind = row.kind;
+        }
+
+        for row in &delta.metadata {
+            self.blobs.entry(row.oid).or_insert_with(|| LargeBlobInfo {
+                oid: row.oid.as_bytes().to_vec(),
+                size: row.size as u64,
+                path: interner.get_str(row.path_id).to_string(),
+                first_author: row.author.clone(),
+                first_email: row.email.clone(),
+                first_date: row.timestamp,
+                first_subject: row.subject.clone(),
+                in_head: false,
+            });
+        }
+
+        for path_id in &delta.gitlinks {
+            let totals = self.paths.entry(interner.get_str(*path_id).to_string()).or_default();
+            totals.kind = EntryKind::Gitlink;
+        }
+
+        for skipped in &delta.skipped {
+            self.skipped.insert(skipped.oid, skipped.clone());
+        }
+
+        for path in interner.paths_since(self.path_ids.len()) {
+            self.path_ids.intern(path);
+        }
+        for row in &delta.trees {
+            self.seen_trees.insert((row.oid, row.path_id));
+        }
+    }
+
+    fn mark_scanned(&mut self, commits: &[ObjectId]) {
+        for oid in commits {
+            let bytes: [u8; 20] = oid.as_bytes().try_into().unwrap_or([0; 20]);
+            self.scanned_commits.insert(bytes);
+            // Skipped before, read fine this time
+            if self.skipped.get(oid).is_some_and(|s| s.kind == SkippedKind::Commit) {
+                self.skipped.remove(oid);
+            }
+        }
+    }
+
+    /// Current sizes and kinds come from the HEAD snapshot, as in the database
+    fn refresh_current_sizes(&mut self) {
+        for (path, totals) in self.paths.iter_mut() {
+            match self.head_blobs.get(path) {
+                Some(&(size, kind)) => {
+                    totals.current_size = size;
+                    totals.kind = kind;
+                }
+                None => totals.current_size = 0,
+            }
+        }
+    }
+}
+
+impl ScanStore for MemoryStore {
+    async fn get_head_oid(&self) -> Option<String> {
+        self.lock().head_oid.clone()
+    }
+
+    async fn set_head_oid(&self, oid_hex: &str) -> Result<()> {
+        self.lock().head_oid = Some(oid_hex.to_string());
+        Ok(())
+    }
+
+    async fn get_scan_limits(&self) -> Option<String> {
+        self.lock().scan_limits.clone()
+    }
+
+    async fn set_scan_limits(&self, limits: &str) -> Result<()> {
+        self.lock().scan_limits = Some(limits.to_string());
+        Ok(())
+    }
+
+    async fn get_replacements(&self) -> Option<String> {
+        self.lock().replacements.clone()
+    }
+
+    async fn set_replacements(&self, replacements: &str) -> Result<()> {
+        self.lock().replacements = Some(replacements.to_string());
+        Ok(())
+    }
+
+    async fn get_mailmap(&self) -> Option<String> {
+        self.lock().mailmap.clone()
+    }
+
+    async fn set_mailmap(&self, mailmap: &str) -> Result<()> {
+        self.lock().mailmap = Some(mailmap.to_string());
+        Ok(())
+    }
+
+    async fn get_scan_path(&self) -> Option<String> {
+        self.lock().scan_path.clone()
+    }
+
+    async fn set_scan_path(&self, path: &str) -> Result<()> {
+        self.lock().scan_path = Some(path.to_string());
+        Ok(())
+    }
+
+    async fn get_scan_range(&self) -> Option<String> {
+        self.lock().scan_range.clone()
+    }
+
+    async fn set_scan_range(&self, base: &str) -> Result<()> {
+        self.lock().scan_range = Some(base.to_string());
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        *self.lock() = State::default();
+        Ok(())
+    }
+
+    async fn load_scanned_commits(&self) -> FxHashSet<[u8; 20]> {
+        self.lock().scanned_commits.clone()
+    }
+
+    async fn load_seen_blobs(&self) -> Result<FxHashSet<ObjectId>> {
+        Ok(self.lock().seen_blobs.clone())
+    }
+
+    async fn load_paths(&self) -> Result<PathInterner> {
+        Ok(self.lock().path_ids.clone())
+    }
+
+    async fn load_seen_trees(&self) -> Result<Vec<(ObjectId, PathId)>> {
+        Ok(self.lock().seen_trees.iter().copied().collect())
+    }
+
+    async fn save_delta_rows(
+        &self,
+        delta: &ScanDelta,
+        interner: &PathInterner,
+        progress: &dyn ProgressReporter,
+    ) -> Result<()> {
+        let pb = progress.start("Indexing", delta.blobs.len() as u64);
+        self.lock().save_rows(delta, interner);
+        pb.inc(delta.blobs.len() as u64);
+        pb.finish();
+        Ok(())
+    }
+
+    async fn mark_scanned_commits(&self, commits: &[ObjectId]) -> Result<()> {
+        self.lock().mark_scanned(commits);
+        Ok(())
+    }
+
+    async fn replace_corrupt_packs(&self, packs: &[SkippedObject]) -> Result<()> {
+        let mut state = self.lock();
+        state.skipped.retain(|_, skipped| skipped.kind != SkippedKind::Pack);
+        for pack in packs {
+            state.skipped.insert(pack.oid, pack.clone());
+        }
+        Ok(())
+    }
+
+    async fn replace_head_blobs(&self, head: &HeadSnapshot, interner: &PathInterner) -> Result<()> {
+        let mut state = self.lock();
+        state.head_blobs = head
+            .blobs_by_path
+            .iter()
+            .map(|(path_id, blob)| (interner.get_str(*path_id).to_string(), (blob.size, blob.kind)))
+            .collect();
+        state.head_oids = head.blobs_by_path.values().map(|blob| blob.oid).collect();
+        state.refresh_current_sizes();
+        Ok(())
+    }
+
+    async fn load_tree(&self) -> Result<Tree> {
+        let state = self.lock();
+        let mut root = Tree::new();
+        for (path, totals) in &state.paths {
+            let parts: Vec<&str> = path.split('/').collect();
+            root.add_entry(
+                &parts,
+                totals.cumulative_size as u64,
+                totals.current_size as u64,
+                totals.blob_count as u64,
+                totals.kind,
+            );
+        }
+        root.compute_totals();
+        Ok(root)
+    }
+
+    async fn apply_scan(
+        &self,
+        delta: &ScanDelta,
+        commits: &[ObjectId],
+        interner: &PathInterner,
+        progress: &dyn ProgressReporter,
+    ) -> Result<()> {
+        let pb = progress.start("Indexing", delta.blobs.len() as u64);
+        {
+            // One lock for the whole update, so it is as atomic as the database's
+            let mut state = self.lock();
+            state.save_rows(delta, interner);
+            state.mark_scanned(commits);
+            state.refresh_current_sizes();
+        }
+        pb.inc(delta.blobs.len() as u64);
+        pb.finish();
+        Ok(())
+    }
+}
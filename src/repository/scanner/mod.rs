@@ -6,44 +6,97 @@
 //!
 //! The scanner is organized into layers:
 //!
+//! - **cancel**: Stopping a running scan between commits
 //! - **types**: Domain types (PathId, BlobRow, ScanDelta, etc.)
 //! - **interner**: Path interning for zero-allocation traversal
 //! - **pack**: Pack file size index
+//! - **object_cache**: Decoded object and delta base caches shared across commits
 //! - **tree**: Tree scanning context (replaces the 14-param recursive function)
 //! - **progress**: Progress reporting abstraction
 //! - **store**: Persistence layer trait
 //! - **db_store**: Database implementation of ScanStore
+//! - **memory_store**: In-memory implementation of ScanStore (no SQLite)
+//! - **options**: Scan options (what the scan covers)
+//! - **order**: Deterministic commit scan order (decides blob attribution)
+//! - **replace**: Replace refs and grafts (which history is scanned)
+//! - **virtual_paths**: Stash/index blobs grafted under synthetic paths
 //! - **scanner**: Main scanner orchestrator
 
+mod cancel;
 mod db_store;
 mod interner;
+mod mailmap;
+mod memory;
+mod memory_store;
+mod object_cache;
+mod options;
+mod order;
 mod pack;
 mod progress;
+mod replace;
 mod store;
 mod tree;
 mod types;
+mod virtual_paths;
 
+pub use cancel::{CancelToken, ScanCancelled};
 pub use interner::PathInterner;
+pub use memory_store::MemoryStore;
+pub use object_cache::DEFAULT_OBJECT_CACHE;
+pub use options::{RevRange, ScanOptions};
 pub use pack::PackSizeIndex;
-pub use progress::{NoopProgress, ProgressHandle, ProgressReporter, VerboseProgress};
+pub use progress::{JsonProgress, NoopProgress, ProgressHandle, ProgressReporter, VerboseProgress};
 pub use store::ScanStore;
 pub use tree::TreeScanCtx;
-pub use types::{CommitInfo, HeadSnapshot, ScanDelta};
+pub use types::{CommitInfo, HeadSnapshot, PathId, ScanDelta, SkippedKind, SkippedObject};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use gix::bstr::BString;
 use gix::prelude::FindExt;
+use gix::revision::walk::Sorting;
+use gix::traverse::commit::simple::CommitTimeOrder;
 use gix::ObjectId;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::path::PathBuf;
-use std::time::Instant;
-
-use crate::model::TreeNode;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+use crate::model::Tree;
+use crate::repository::branch::boundary_blobs;
+
+use cancel::CancelOnDrop;
+use mailmap::Mailmap;
+use memory::{map_bytes, set_bytes};
+use replace::OpenedRepo;
+use tree::ScopedRecorder;
+use types::{entry_kind, HeadBlob};
+
+/// Rows of scan results written per transaction; each write also refreshes
+/// every path's current size, so batches are kept large
+const WRITE_BATCH_ROWS: usize = 1_000_000;
+
+/// What `finish_tree` needs from an up to date index
+struct Indexed {
+    repo: gix::Repository,
+    /// Loaded unless the index was already up to date
+    pack: Option<PackSizeIndex>,
+}
 
 /// Git repository scanner for extracting history statistics
 pub struct GitScanner {
     repo_path: PathBuf,
     verbose: bool,
     profile: bool,
+    options: ScanOptions,
+    /// Bytes of decoded objects and delta bases kept between commits
+    object_cache: usize,
+    /// Rows of scan results written per transaction while scanning goes on
+    batch_rows: usize,
+    /// Revision scanned in place of HEAD
+    tip: Option<String>,
+    progress: Option<Arc<dyn ProgressReporter>>,
+    cancel: CancelToken,
 }
 
 impl GitScanner {
@@ -52,166 +105,441 @@ impl GitScanner {
             repo_path: PathBuf::from(repo_path),
             verbose: true,
             profile: false,
+            options: ScanOptions::default(),
+            object_cache: DEFAULT_OBJECT_CACHE,
+            batch_rows: WRITE_BATCH_ROWS,
+            tip: None,
+            progress: None,
+            cancel: CancelToken::new(),
         }
     }
 
-    /// Create a quiet scanner (no logging output, used by benchmarks)
-    #[allow(dead_code)]
+    /// Create a quiet scanner (no progress bars, used by benchmarks and batch scans)
     pub fn quiet(repo_path: &str) -> Self {
         Self {
             repo_path: PathBuf::from(repo_path),
             verbose: false,
             profile: false,
+            options: ScanOptions::default(),
+            object_cache: DEFAULT_OBJECT_CACHE,
+            batch_rows: WRITE_BATCH_ROWS,
+            tip: None,
+            progress: None,
+            cancel: CancelToken::new(),
         }
     }
 
-    /// Create a profiling scanner (detailed timing output)
+    /// Create a profiling scanner (no progress bars, so phase span timings
+    /// are the only output)
     pub fn profiling(repo_path: &str) -> Self {
         Self {
             repo_path: PathBuf::from(repo_path),
             verbose: true,
             profile: true,
+            options: ScanOptions::default(),
+            object_cache: DEFAULT_OBJECT_CACHE,
+            batch_rows: WRITE_BATCH_ROWS,
+            tip: None,
+            progress: None,
+            cancel: CancelToken::new(),
         }
     }
 
+    /// Replace the scan options
+    pub fn with_options(mut self, options: ScanOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Keep up to `bytes` of decoded trees and delta bases while scanning
+    /// commits (0 leaves the caches to git configuration)
+    pub fn with_object_cache(mut self, bytes: usize) -> Self {
+        self.object_cache = bytes;
+        self
+    }
+
+    /// Write scan results every `rows` rows instead of the default; smaller
+    /// batches overlap more of the writing with scanning, each at a cost
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    /// Scan history as of `rev` (anything git resolves to a commit, e.g.
+    /// `HEAD~100`) instead of HEAD; the index then records it as its HEAD
+    pub fn with_tip(mut self, rev: &str) -> Self {
+        self.tip = Some(rev.to_string());
+        self
+    }
+
+    /// Report progress to `progress` instead of the default bars
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressReporter>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Stop between commits once `cancel` is cancelled; the scan then fails
+    /// with `ScanCancelled` after saving the commits it got through
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     /// Scan repository and return tree, using store for caching
-    pub async fn scan(&self, store: &impl ScanStore) -> Result<TreeNode> {
-        let total_start = Instant::now();
-        let progress: Box<dyn ProgressReporter> = if self.profile {
-            Box::new(NoopProgress)
-        } else {
-            Box::new(VerboseProgress::new(self.verbose))
-        };
+    ///
+    /// Every phase runs in its own span under a `scan` span for the
+    /// repository, so phase timings come from span close events.
+    pub async fn scan(&self, store: &impl ScanStore) -> Result<Tree> {
+        let progress = self.reporter();
+        let span = info_span!("scan", repo = %self.repo_path.display());
+        let tree = async {
+            let Indexed { repo, pack } = self.index_phases(store, &progress).await?;
+
+            // Phase 10: Load tree
+            info!("Loading tree from database...");
+            let tree = store.load_tree().instrument(info_span!("load_tree")).await?;
+            let tree = self.finish_tree(&repo, pack, store, tree).await?;
+            log_peak_rss();
+            Ok::<_, anyhow::Error>(tree)
+        }
+        .instrument(span)
+        .await?;
+        progress.scan_finished(&tree);
+        Ok(tree)
+    }
 
+    /// Bring the index up to date like `scan`, without loading the tree
+    ///
+    /// For browsing one directory at a time (`Database::get_dir_children`);
+    /// stash and index entries are not collected.
+    pub async fn update_index(&self, store: &impl ScanStore) -> Result<()> {
+        let progress = self.reporter();
+        let span = info_span!("scan", repo = %self.repo_path.display());
+        self.index_phases(store, &progress).instrument(span.clone()).await?;
+        span.in_scope(log_peak_rss);
+        Ok(())
+    }
+
+    fn reporter(&self) -> Arc<dyn ProgressReporter> {
+        match &self.progress {
+            Some(progress) => Arc::clone(progress),
+            None if self.profile => Arc::new(NoopProgress),
+            None => Arc::new(VerboseProgress::new(self.verbose)),
+        }
+    }
+
+    async fn index_phases(&self, store: &impl ScanStore, progress: &Arc<dyn ProgressReporter>) -> Result<Indexed> {
         // Phase 1: Open repository
-        self.log("Opening repository...");
-        let phase_start = Instant::now();
-        let repo = gix::open(&self.repo_path).context("Failed to open git repository")?;
+        info!("Opening repository...");
+        let OpenedRepo { repo, replacements } = info_span!("open_repo")
+            .in_scope(|| replace::open_repo(&self.repo_path, self.options.no_replace_objects))?;
+
+        // Results scanned through other replacements describe another history
+        if store.get_head_oid().await.is_some() && store.get_replacements().await.unwrap_or_default() != replacements {
+            info!("Replace refs changed since the last scan, rebuilding the index...");
+            store.clear().await?;
+        }
+        // First authors were credited through the mailmap in effect then
+        let mailmap = Mailmap::open(&repo);
+        if store.get_head_oid().await.is_some() && store.get_mailmap().await.unwrap_or_default() != mailmap.label {
+            info!(".mailmap changed since the last scan, rebuilding the index...");
+            store.clear().await?;
+        }
+        // Results scoped to another sub-path don't cover this one, and vice versa
+        let scope = self.options.path.clone().unwrap_or_default();
+        if store.get_head_oid().await.is_some() && store.get_scan_path().await.unwrap_or_default() != scope {
+            info!("Scanned path changed since the last scan, rebuilding the index...");
+            store.clear().await?;
+        }
+        // Results over more history than the limits allow can't be narrowed down
+        let limits = self.options.limits_label().unwrap_or_default();
+        if store.get_head_oid().await.is_some() && store.get_scan_limits().await.unwrap_or_default() != limits {
+            info!("History limits changed since the last scan, rebuilding the index...");
+            store.clear().await?;
+        }
 
-        let head_commit = repo.head_commit().context("Failed to get HEAD commit")?;
-        let head_oid = head_commit.id();
+        // Results of another range credit blobs its base had, or miss some
+        let range_base = match &self.options.range {
+            Some(range) => Some(
+                repo.rev_parse_single(range.base.as_str())
+                    .with_context(|| format!("Could not resolve {}", range.base))?
+                    .object()?
+                    .peel_to_commit()
+                    .with_context(|| format!("{} is not a commit", range.base))?
+                    .id,
+            ),
+            None => None,
+        };
+        let range_hex = range_base.map(|oid| oid.to_hex().to_string()).unwrap_or_default();
+        if store.get_head_oid().await.is_some() && store.get_scan_range().await.unwrap_or_default() != range_hex {
+            info!("Scanned range changed since the last scan, rebuilding the index...");
+            store.clear().await?;
+        }
+
+        let tip = self.tip.as_ref().or(self.options.range.as_ref().map(|range| &range.head));
+        let head_commit = match tip {
+            Some(rev) => repo
+                .rev_parse_single(rev.as_str())
+                .with_context(|| format!("Could not resolve {}", rev))?
+                .object()?
+                .peel_to_commit()
+                .with_context(|| format!("{} is not a commit", rev))?,
+            None => repo.head_commit().context("Failed to get HEAD commit")?,
+        };
+        let head_oid = head_commit.id;
         let head_hex = head_oid.to_hex().to_string();
-        self.profile_phase("Open repo + get HEAD", phase_start);
 
         // Phase 2: Check cache
         if store.get_head_oid().await.as_deref() == Some(&head_hex) {
-            self.log(&format!(
-                "Index is up to date (HEAD: {}), loading from cache...",
-                &head_hex[..8]
-            ));
-            return store.load_tree().await;
+            info!("Index is up to date (HEAD: {})", &head_hex[..8]);
+            drop(head_commit);
+            return Ok(Indexed { repo, pack: None });
         }
 
         // Phase 3: Load pack sizes
-        self.log("Loading compressed sizes from pack files...");
-        let phase_start = Instant::now();
-        let pack = PackSizeIndex::load(&repo);
-        self.profile_phase(&format!("Load pack sizes ({} objects)", pack.len()), phase_start);
+        info!("Loading compressed sizes from pack files...");
+        let pack = info_span!("load_pack_sizes").in_scope(|| PackSizeIndex::load(&repo));
+        debug!(objects = pack.len(), "Loaded pack sizes");
+        self.check_packs(&pack, store).await?;
 
         // Phase 4: Build HEAD snapshot
-        self.log("Scanning current HEAD for working tree...");
-        let phase_start = Instant::now();
-        let mut interner = PathInterner::new();
-        let head_tree = head_commit.tree().context("Failed to get HEAD tree")?;
-        let head_snapshot = self.build_head_snapshot(&head_tree, &head_hex, &pack, &mut interner)?;
-        self.profile_phase(
-            &format!("Scan HEAD tree ({} files)", head_snapshot.blobs_by_path.len()),
-            phase_start,
-        );
+        info!("Scanning current HEAD for working tree...");
+        let mut interner = store.load_paths().instrument(info_span!("load_paths")).await?;
+        debug!(paths = interner.len(), "Loaded path dictionary");
+        let mut head_snapshot = info_span!("head_snapshot").in_scope(|| {
+            let head_tree = head_commit.tree_id().context("Failed to get HEAD tree")?;
+            self.build_head_snapshot(&repo, head_tree.detach(), &head_hex, &pack, &mut interner)
+        })?;
+        drop(head_commit);
+        debug!(files = head_snapshot.blobs_by_path.len(), "Scanned HEAD tree");
 
         // Phase 5: Collect commits via revwalk
-        self.log("Collecting commits...");
-        let phase_start = Instant::now();
-        let all_commits = self.collect_commits(&repo, head_oid.into())?;
-        self.profile_phase(&format!("Revwalk ({} commits)", all_commits.len()), phase_start);
+        info!("Collecting commits...");
+        let all_commits = info_span!("revwalk")
+            .in_scope(|| self.collect_commits(&repo, head_oid, range_base))?;
+        debug!(commits = all_commits.len(), "Revwalk complete");
+
+        // A range only counts the blobs its base doesn't have, in HEAD too
+        let range_known = match range_base {
+            Some(_) => info_span!("range_base")
+                .in_scope(|| boundary_blobs(&repo, &all_commits, self.options.first_parent))?,
+            None => FxHashSet::default(),
+        };
+        head_snapshot.blobs_by_path.retain(|_, blob| !range_known.contains(&blob.oid));
+        debug!(blobs = range_known.len(), "Loaded blobs the range grows from");
 
         // Phase 6: Filter to unscanned commits
-        let phase_start = Instant::now();
-        let scanned_commits = store.load_scanned_commits().await;
+        let scanned_commits = store
+            .load_scanned_commits()
+            .instrument(info_span!("load_scanned_commits"))
+            .await;
         let commits_to_scan = self.plan_commits(&all_commits, &scanned_commits);
-        self.profile_phase(
-            &format!(
-                "Filter unscanned ({} of {} need scanning, {} cached)",
-                commits_to_scan.len(),
-                all_commits.len(),
-                scanned_commits.len()
-            ),
-            phase_start,
+        debug!(
+            pending = commits_to_scan.len(),
+            total = all_commits.len(),
+            cached = scanned_commits.len(),
+            "Filtered unscanned commits"
         );
 
+        store
+            .replace_head_blobs(&head_snapshot, &interner)
+            .instrument(info_span!("replace_head_blobs"))
+            .await?;
+
         if commits_to_scan.is_empty() {
             store.set_head_oid(&head_hex).await?;
-            return store.load_tree().await;
+            store.set_scan_limits(&limits).await?;
+            store.set_replacements(&replacements).await?;
+            store.set_mailmap(&mailmap.label).await?;
+            store.set_scan_path(&scope).await?;
+            store.set_scan_range(&range_hex).await?;
+            return Ok(Indexed { repo, pack: Some(pack) });
         }
 
-        self.log(&format!("{} commits need scanning", commits_to_scan.len()));
-
-        // Phase 7: Load seen blobs
-        let phase_start = Instant::now();
-        let seen_blobs = store.load_seen_blobs().await?;
-        self.profile_phase(&format!("Load seen blobs ({} blobs)", seen_blobs.len()), phase_start);
-
-        // Phase 8: Scan commits
-        let phase_start = Instant::now();
-        let delta = self.scan_commits(
-            &repo,
-            &pack,
-            &head_snapshot,
-            &mut interner,
-            seen_blobs,
-            &commits_to_scan,
-            progress.as_ref(),
-        )?;
-        self.profile_phase(
-            &format!(
-                "Scan {} commits (found {} new blobs)",
-                commits_to_scan.len(),
-                delta.blobs.len()
-            ),
-            phase_start,
-        );
+        info!("{} commits need scanning", commits_to_scan.len());
 
-        // Phase 9: Apply scan atomically (rows + scanned commits)
-        let phase_start = Instant::now();
-        store
-            .apply_scan(&delta, &commits_to_scan, &interner, progress.as_ref())
+        // Phase 7: Load seen blobs and fully indexed trees
+        let seen_blobs = store
+            .load_seen_blobs()
+            .instrument(info_span!("load_seen_blobs"))
             .await?;
-        self.profile_phase(
-            &format!("Apply scan ({} commits)", commits_to_scan.len()),
-            phase_start,
-        );
+        debug!(blobs = seen_blobs.len(), "Loaded seen blobs");
+
+        let seen_trees: FxHashSet<(ObjectId, PathId)> = store
+            .load_seen_trees()
+            .instrument(info_span!("load_seen_trees"))
+            .await?
+            .into_iter()
+            .collect();
+        debug!(trees = seen_trees.len(), "Loaded seen trees");
+
+        // Phases 8-9: Scan commits on a blocking thread while the batches it
+        // sends are written. A batch ends between commits and is applied
+        // atomically, so skipped commits, and those a cancelled or failed scan
+        // didn't reach, stay unscanned and a later run retries them
+        let total = commits_to_scan.len();
+        let mut paths = interner.clone();
+        let (batches, mut received) = mpsc::channel(1);
+        let mailmap_label = mailmap.label.clone();
+        let job = CommitScanJob {
+            odb: repo.objects.clone(),
+            mailmap,
+            scope: self.options.path.clone(),
+            pack,
+            head: head_snapshot,
+            interner,
+            seen_blobs,
+            range_known,
+            seen_trees,
+            scanned_before: scanned_commits,
+            commits: commits_to_scan,
+            object_cache: self.object_cache,
+            batch_rows: self.batch_rows,
+            cancel: self.cancel.clone(),
+            progress: Arc::clone(progress),
+        };
+        let span = info_span!("scan_commits", commits = total);
+        let scan = tokio::task::spawn_blocking(move || span.in_scope(|| job.run(&batches)));
+        // Should writing fail, or this future be dropped, the scan stops at
+        // its next commit instead of running on unseen
+        let stop = CancelOnDrop::new(self.cancel.clone());
+
+        let written = async {
+            let (mut new_blobs, mut skipped, mut unknown_sizes) = (0, 0, 0);
+            while let Some(batch) = received.recv().await {
+                for path in &batch.paths {
+                    paths.intern(path);
+                }
+                new_blobs += batch.delta.blobs.len();
+                let sizes = batch.delta.skipped.iter().filter(|s| s.kind == SkippedKind::Size).count();
+                unknown_sizes += sizes;
+                skipped += batch.delta.skipped.len() - sizes;
+                // Indexing bars of batches written mid-scan would fight the Scanning bar
+                let batch_progress: &dyn ProgressReporter =
+                    if batch.last { progress.as_ref() } else { &NoopProgress };
+                store
+                    .apply_scan(&batch.delta, &batch.commits, &paths, batch_progress)
+                    .instrument(info_span!("apply_scan", commits = batch.commits.len()))
+                    .await?;
+            }
+            Ok::<_, anyhow::Error>((new_blobs, skipped, unknown_sizes))
+        }
+        .await;
+        let (new_blobs, skipped, unknown_sizes) = match written {
+            Ok(counts) => {
+                stop.disarm();
+                counts
+            }
+            Err(e) => {
+                drop(stop);
+                drop(received);
+                // Its result is moot next to the write error
+                let _ = scan.await;
+                return Err(e);
+            }
+        };
+        let CommitScan { pack, visited, cancelled } = scan.await.context("Commit scan panicked")?;
+        debug!(new_blobs, "Scanned commits");
+        if skipped > 0 {
+            warn!(objects = skipped, "Some objects could not be read; results are incomplete");
+        }
+        if unknown_sizes > 0 {
+            warn!(objects = unknown_sizes, "Some blobs are in no readable pack; their sizes count as 0");
+        }
 
+        store.set_scan_limits(&limits).await?;
+        store.set_replacements(&replacements).await?;
+        store.set_mailmap(&mailmap_label).await?;
+        store.set_scan_path(&scope).await?;
+        store.set_scan_range(&range_hex).await?;
+        if cancelled {
+            // Never let a later run of the same HEAD take this for a full index
+            store.set_head_oid("").await?;
+            info!("Scan cancelled after {} of {} commits", visited, total);
+            return Err(ScanCancelled { scanned: visited, total }.into());
+        }
+        if self.options.strict && unknown_sizes > 0 {
+            // Saved, but never taken for a full index
+            store.set_head_oid("").await?;
+            bail!("{} blobs have no known size (--strict)", unknown_sizes);
+        }
         store.set_head_oid(&head_hex).await?;
 
-        if self.profile {
-            eprintln!("[PROFILE] TOTAL scanning time: {:?}", total_start.elapsed());
+        Ok(Indexed { repo, pack: Some(pack) })
+    }
+
+    /// Record the packs `pack` could not read, so their objects' sizes are
+    /// known to be missing; with `--strict`, fail instead
+    async fn check_packs(&self, pack: &PackSizeIndex, store: &impl ScanStore) -> Result<()> {
+        let corrupt = pack.corrupt_packs();
+        if self.options.strict {
+            if let Some(first) = corrupt.first() {
+                bail!("{} packs could not be read (--strict); {}: {}", corrupt.len(), first.path.display(), first.reason);
+            }
+        } else if !corrupt.is_empty() {
+            warn!(packs = corrupt.len(), "Some packs could not be read in full; results are incomplete");
         }
+        let skipped: Vec<SkippedObject> = corrupt
+            .iter()
+            .map(|pack| SkippedObject {
+                oid: pack.checksum().unwrap_or_else(|| ObjectId::null(gix::hash::Kind::Sha1)),
+                kind: SkippedKind::Pack,
+                path: pack.path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                reason: pack.reason.clone(),
+            })
+            .collect();
+        store.replace_corrupt_packs(&skipped).await
+    }
+
+    /// Apply option-dependent post-processing to a loaded tree
+    ///
+    /// Stash and index blobs are grafted here rather than stored, so they are
+    /// always current even when the history scan was served from cache.
+    async fn finish_tree(
+        &self,
+        repo: &gix::Repository,
+        pack: Option<PackSizeIndex>,
+        store: &impl ScanStore,
+        mut tree: Tree,
+    ) -> Result<Tree> {
+        if !self.options.include_stash_and_index {
+            return Ok(tree);
+        }
+
+        info!("Collecting stash and index entries...");
+        let pack = pack.unwrap_or_else(|| PackSizeIndex::load(repo));
+        let seen_blobs = store.load_seen_blobs().await?;
+        let blobs = info_span!("virtual_paths")
+            .in_scope(|| virtual_paths::collect_virtual_blobs(repo, &pack, &seen_blobs, self.options.scope()))?;
+        virtual_paths::graft(&mut tree, &blobs);
+        debug!(blobs = blobs.len(), "Grafted stash and index blobs");
 
-        // Phase 11: Load tree
-        self.log("Loading tree from database...");
-        let phase_start = Instant::now();
-        let tree = store.load_tree().await;
-        self.profile_phase("Load tree from DB", phase_start);
-        tree
+        Ok(tree)
     }
 
     /// Build a snapshot of HEAD tree
     fn build_head_snapshot(
         &self,
-        head_tree: &gix::Tree<'_>,
+        repo: &gix::Repository,
+        head_tree: ObjectId,
         head_hex: &str,
         pack: &PackSizeIndex,
         interner: &mut PathInterner,
     ) -> Result<HeadSnapshot> {
-        let mut recorder = gix::traverse::tree::Recorder::default();
-        head_tree.traverse().breadthfirst(&mut recorder)?;
+        let odb = pack.guard(&repo.objects);
+        let mut buf = Vec::new();
+        let root = odb.find_tree_iter(&head_tree, &mut buf).context("Failed to read HEAD tree")?;
+        let mut recorder = ScopedRecorder::new(self.options.scope());
+        gix::traverse::tree::breadthfirst(root, gix::traverse::tree::breadthfirst::State::default(), &odb, &mut recorder)
+            .context("Failed to read HEAD tree")?;
 
         let mut blobs_by_path = rustc_hash::FxHashMap::default();
-        for entry in recorder.records {
-            if entry.mode.is_blob() {
+        for entry in recorder.into_records() {
+            if entry.mode.is_blob_or_symlink() {
                 let path_id = interner.intern(entry.filepath.as_ref());
                 let size = pack.size_of(entry.oid);
-                blobs_by_path.insert(path_id, (entry.oid, size));
+                let kind = entry_kind(entry.mode);
+                blobs_by_path.insert(path_id, HeadBlob { oid: entry.oid, size, kind });
             }
         }
 
@@ -221,16 +549,40 @@ impl GitScanner {
         })
     }
 
-    /// Collect all commits via revwalk (oldest first)
-    fn collect_commits(&self, repo: &gix::Repository, head: ObjectId) -> Result<Vec<ObjectId>> {
+    /// Collect commits via revwalk (oldest first), within the configured limits
+    /// and leaving out those `hidden` (a range's base) can reach
+    ///
+    /// A commit that can't be decoded (e.g. a malformed author line) doesn't
+    /// stop the walk: its parents are still followed, and it is listed last
+    /// so the commit scan records why it was skipped.
+    fn collect_commits(&self, repo: &gix::Repository, head: ObjectId, hidden: Option<ObjectId>) -> Result<Vec<ObjectId>> {
+        let options = &self.options;
+        let sorting = match (options.since, options.max_commits) {
+            (Some(seconds), _) => Sorting::ByCommitTimeCutoff { order: CommitTimeOrder::NewestFirst, seconds },
+            // "Newest N" needs commit time order; breadth-first is cheaper otherwise
+            (None, Some(_)) => Sorting::ByCommitTime(CommitTimeOrder::NewestFirst),
+            (None, None) => Sorting::BreadthFirst,
+        };
+        let mut walk = repo.rev_walk([head]).sorting(sorting).with_hidden(hidden);
+        if options.first_parent {
+            walk = walk.first_parent_only();
+        }
+
         let mut commits: Vec<ObjectId> = Vec::new();
-        let walk = repo.rev_walk([head]);
-        for commit_info in walk.all()? {
-            let commit_info = commit_info?;
-            commits.push(commit_info.id);
+        let mut undecodable = 0;
+        let limit = options.max_commits.unwrap_or(usize::MAX);
+        for commit_info in walk.all()?.take(limit) {
+            match commit_info {
+                Ok(commit_info) => commits.push(commit_info.id),
+                Err(_) => undecodable += 1,
+            }
         }
         // Reverse to process oldest first
         commits.reverse();
+        if undecodable > 0 {
+            warn!(commits = undecodable, "Some commits could not be decoded; their changes are credited to their children");
+            commits.extend(undecodable_commits(repo, head, &commits));
+        }
         Ok(commits)
     }
 
@@ -246,70 +598,234 @@ impl GitScanner {
             .copied()
             .collect()
     }
+}
 
-    /// Scan commits and return delta
-    fn scan_commits(
-        &self,
-        repo: &gix::Repository,
-        pack: &PackSizeIndex,
-        head: &HeadSnapshot,
-        interner: &mut PathInterner,
-        seen_blobs: FxHashSet<ObjectId>,
-        commits: &[ObjectId],
-        progress: &dyn ProgressReporter,
-    ) -> Result<ScanDelta> {
-        let odb = repo.objects.clone();
-        let mut ctx = TreeScanCtx::new(&odb, pack, head, interner, seen_blobs);
+/// Log the most memory the process has held so far, where the OS reports it
+fn log_peak_rss() {
+    if let Some(bytes) = crate::util::peak_rss() {
+        debug!(peak_rss_bytes = bytes, "Peak memory");
+    }
+}
 
-        let pb = progress.start("Scanning", commits.len() as u64);
+/// Commits the walk went through without listing: `head` and parents of
+/// `walked` that aren't among them and can't be decoded
+///
+/// The walk's errors don't name their commit, so this decodes every walked
+/// commit again; it only runs when there were errors.
+fn undecodable_commits(repo: &gix::Repository, head: ObjectId, walked: &[ObjectId]) -> Vec<ObjectId> {
+    let listed: FxHashSet<ObjectId> = walked.iter().copied().collect();
+    let mut buf = Vec::new();
+    let mut candidates: FxHashSet<ObjectId> = FxHashSet::default();
+    candidates.insert(head);
+    for oid in walked {
+        if let Ok(commit) = repo.objects.find_commit_iter(oid, &mut buf) {
+            candidates.extend(commit.parent_ids());
+        }
+    }
+    let mut undecodable: Vec<ObjectId> = candidates
+        .into_iter()
+        .filter(|oid| !listed.contains(oid) && repo.objects.find_commit(oid, &mut buf).is_err())
+        .collect();
+    undecodable.sort();
+    undecodable
+}
 
-        for oid in commits {
-            pb.inc(1);
+/// Everything the commit scan owns while it runs on a blocking thread
+struct CommitScanJob {
+    odb: gix::OdbHandle,
+    /// Authors are credited as the identity it maps them to
+    mailmap: Mailmap,
+    /// Sub-path the scan is limited to (see `ScanOptions::path`)
+    scope: Option<String>,
+    pack: PackSizeIndex,
+    head: HeadSnapshot,
+    /// Holds the paths the writer's copy has; batches bring it up to date
+    interner: PathInterner,
+    seen_blobs: FxHashSet<ObjectId>,
+    /// Blobs of the commits a range grows from, left out everywhere
+    range_known: FxHashSet<ObjectId>,
+    seen_trees: FxHashSet<(ObjectId, PathId)>,
+    scanned_before: FxHashSet<[u8; 20]>,
+    commits: Vec<ObjectId>,
+    object_cache: usize,
+    batch_rows: usize,
+    cancel: CancelToken,
+    progress: Arc<dyn ProgressReporter>,
+}
 
+/// Commits scanned since the previous batch and the rows they produced
+struct ScanBatch {
+    delta: ScanDelta,
+    /// Commits whose trees were walked, in scan order
+    commits: Vec<ObjectId>,
+    /// Paths interned since the previous batch, in ID order
+    paths: Vec<BString>,
+    /// Sent once scanning is over
+    last: bool,
+}
+
+/// What the commit scan got through
+struct CommitScan {
+    /// Handed back for `finish_tree`
+    pack: PackSizeIndex,
+    /// Commits whose trees were walked
+    visited: usize,
+    /// Stopped early by the cancel token
+    cancelled: bool,
+}
+
+impl CommitScanJob {
+    /// Scan commits, oldest first, until done or cancelled, sending a batch
+    /// whenever `batch_rows` rows have piled up and a last one at the end
+    ///
+    /// Stops early if the writer hangs up; it has an error to report.
+    fn run(self, batches: &mpsc::Sender<ScanBatch>) -> CommitScan {
+        let Self {
+            mut odb,
+            mailmap,
+            scope,
+            pack,
+            head,
+            mut interner,
+            seen_blobs,
+            range_known,
+            seen_trees,
+            scanned_before,
+            commits,
+            object_cache: cache_bytes,
+            batch_rows,
+            cancel,
+            progress,
+        } = self;
+        let cache = object_cache::install(&mut odb, cache_bytes);
+        let odb = pack.guard(&odb);
+        let mut sent_paths = interner.len();
+        let mut ctx = TreeScanCtx::new(&odb, &pack, &head, &mut interner, seen_blobs, seen_trees)
+            .with_scope(scope.as_deref().map(str::as_bytes))
+            .with_range_known(range_known);
+
+        let mut infos = Vec::with_capacity(commits.len());
+        for oid in &commits {
             let mut commit_buf = Vec::new();
             let commit = match odb.find_commit(oid, &mut commit_buf) {
                 Ok(c) => c,
-                Err(_) => continue,
+                Err(e) => {
+                    ctx.skip_commit(*oid, e.to_string());
+                    continue;
+                }
             };
 
-            let tree_id = commit.tree();
             let author_sig = match commit.author() {
                 Ok(sig) => sig,
-                Err(_) => continue,
+                Err(e) => {
+                    ctx.skip_commit(*oid, format!("unparseable author: {}", e));
+                    continue;
+                }
             };
 
-            let commit_info = CommitInfo {
+            let (author, email) = mailmap.resolve(author_sig);
+            infos.push(CommitInfo {
                 oid: *oid,
-                tree: tree_id,
-                author: author_sig.name.to_string(),
+                tree: commit.tree(),
+                parents: commit.parents().collect(),
+                author,
+                email,
                 timestamp: author_sig.seconds(),
-            };
+                subject: commit.message_summary().to_string(),
+            });
+        }
 
-            ctx.scan_commit(&commit_info);
+        let pb = progress.start("Scanning", infos.len() as u64);
+
+        // Trees of the commits scanned so far; a commit whose first parent
+        // is among them, or was scanned by an earlier run, is diffed against it
+        let mut scanned_trees: FxHashMap<ObjectId, ObjectId> = FxHashMap::default();
+        let mut parent_buf = Vec::new();
+        let mut batch = Vec::new();
+        // Rows and bytes of the largest batch sent
+        let mut largest = (0, 0);
+        let mut visited = 0;
+        let mut cancelled = false;
+        for commit_info in order::introduction_order(infos) {
+            // Commits before this one are complete, so the delta can be saved
+            if cancel.is_cancelled() {
+                cancelled = true;
+                break;
+            }
+            pb.inc(1);
+            let parent_tree = commit_info.parents.first().and_then(|parent| match scanned_trees.get(parent) {
+                Some(tree) => Some(*tree),
+                None if scanned_before.contains(parent.as_bytes()) => {
+                    odb.find_commit(parent, &mut parent_buf).ok().map(|c| c.tree())
+                }
+                None => None,
+            });
+            ctx.scan_commit(&commit_info, parent_tree);
+            scanned_trees.insert(commit_info.oid, commit_info.tree);
+            batch.push(commit_info.oid);
+            visited += 1;
+
+            if ctx.pending_rows() >= batch_rows
+                && !send_batch(&mut ctx, &mut sent_paths, std::mem::take(&mut batch), false, &mut largest, batches)
+            {
+                break;
+            }
         }
 
         pb.finish();
 
-        if self.profile {
-            eprintln!(
-                "[PROFILE] Trees visited: {}, blobs found: {}",
-                ctx.trees_visited(),
-                ctx.blobs_found()
-            );
-        }
-
-        Ok(ctx.finish())
-    }
+        debug!(
+            trees_visited = ctx.trees_visited(),
+            commits_diffed = ctx.commits_diffed(),
+            blobs_found = ctx.blobs_found(),
+            "Tree traversal stats"
+        );
+        let ((object_hits, object_misses), (base_hits, base_misses)) = (cache.objects(), cache.delta_bases());
+        debug!(
+            bytes = cache_bytes,
+            object_hits,
+            object_misses,
+            base_hits,
+            base_misses,
+            "Object cache stats"
+        );
 
-    fn log(&self, msg: &str) {
-        if self.verbose {
-            eprintln!("{}", msg);
-        }
+        send_batch(&mut ctx, &mut sent_paths, batch, true, &mut largest, batches);
+        // The writer holds a second copy of the paths
+        debug!(
+            pack_objects = pack.len(),
+            pack_bytes = pack.heap_bytes(),
+            paths = ctx.interner.len(),
+            path_bytes = ctx.interner.heap_bytes(),
+            seen_trees = ctx.trees_visited(),
+            seen_blobs = ctx.blobs_seen(),
+            seen_bytes = ctx.seen_bytes(),
+            scanned_commits = scanned_before.len() + scanned_trees.len(),
+            commit_bytes = set_bytes(&scanned_before) + map_bytes(&scanned_trees),
+            batch_rows = largest.0,
+            batch_bytes = largest.1,
+            "Memory usage"
+        );
+        drop(ctx);
+        CommitScan { pack, visited, cancelled }
     }
+}
 
-    fn profile_phase(&self, name: &str, start: Instant) {
-        if self.profile {
-            eprintln!("[PROFILE] {}: {:?}", name, start.elapsed());
-        }
-    }
+/// Send what `ctx` recorded for `commits`, with the paths interned since
+/// `sent_paths`; false once the writer has hung up
+///
+/// `largest` keeps the most rows and bytes a batch has held.
+fn send_batch<S: gix::prelude::Find>(
+    ctx: &mut TreeScanCtx<'_, S>,
+    sent_paths: &mut usize,
+    commits: Vec<ObjectId>,
+    last: bool,
+    largest: &mut (usize, usize),
+    batches: &mpsc::Sender<ScanBatch>,
+) -> bool {
+    *largest = (largest.0.max(ctx.pending_rows()), largest.1.max(ctx.pending_bytes()));
+    let paths = ctx.interner.paths_since(*sent_paths).to_vec();
+    *sent_paths = ctx.interner.len();
+    let batch = ScanBatch { delta: ctx.take_delta(), commits, paths, last };
+    batches.blocking_send(batch).is_ok()
 }
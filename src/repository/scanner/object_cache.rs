@@ -0,0 +1,126 @@
+//! Object cache shared by every commit of a scan
+//!
+//! Most trees in a pack are deltas against other trees, so without a cache
+//! each lookup re-inflates its whole delta chain. With one, a delta base is
+//! decoded once however many trees are built from it.
+
+use gix::object::Kind;
+use gix::ObjectId;
+use gix_pack::cache::lru::MemoryCappedHashmap as PackCache;
+use gix_pack::cache::object::MemoryCappedHashmap as ObjectCache;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Memory for decoded objects and delta bases unless a scan asks otherwise
+pub const DEFAULT_OBJECT_CACHE: usize = 64 * 1024 * 1024;
+
+/// Lookups each cache served or missed, for `--profile`
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    object_hits: AtomicUsize,
+    object_misses: AtomicUsize,
+    base_hits: AtomicUsize,
+    base_misses: AtomicUsize,
+}
+
+impl CacheStats {
+    /// (hits, misses) of decoded objects looked up by id
+    pub fn objects(&self) -> (usize, usize) {
+        (self.object_hits.load(Ordering::Relaxed), self.object_misses.load(Ordering::Relaxed))
+    }
+
+    /// (hits, misses) of delta bases looked up by pack offset
+    pub fn delta_bases(&self) -> (usize, usize) {
+        (self.base_hits.load(Ordering::Relaxed), self.base_misses.load(Ordering::Relaxed))
+    }
+}
+
+fn count(found: bool, hits: &AtomicUsize, misses: &AtomicUsize) {
+    if found { hits } else { misses }.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A memory-capped object cache counting its hits and misses
+struct CountingObjects {
+    inner: ObjectCache,
+    stats: Arc<CacheStats>,
+}
+
+impl gix_pack::cache::Object for CountingObjects {
+    fn put(&mut self, id: ObjectId, kind: Kind, data: &[u8]) {
+        self.inner.put(id, kind, data);
+    }
+
+    fn get(&mut self, id: &ObjectId, out: &mut Vec<u8>) -> Option<Kind> {
+        let kind = self.inner.get(id, out);
+        count(kind.is_some(), &self.stats.object_hits, &self.stats.object_misses);
+        kind
+    }
+}
+
+/// A memory-capped delta base cache counting its hits and misses
+struct CountingBases {
+    inner: PackCache,
+    stats: Arc<CacheStats>,
+}
+
+impl gix_pack::cache::DecodeEntry for CountingBases {
+    fn put(&mut self, pack_id: u32, offset: u64, data: &[u8], kind: Kind, compressed_size: usize) {
+        self.inner.put(pack_id, offset, data, kind, compressed_size);
+    }
+
+    fn get(&mut self, pack_id: u32, offset: u64, out: &mut Vec<u8>) -> Option<(Kind, usize)> {
+        let entry = self.inner.get(pack_id, offset, out);
+        count(entry.is_some(), &self.stats.base_hits, &self.stats.base_misses);
+        entry
+    }
+}
+
+/// Give `odb` an object cache and a delta base cache sharing `bytes`
+///
+/// With 0 `odb` keeps the caches git configuration gave it. Clones of `odb`
+/// made afterwards get caches of their own, counted in the same stats.
+pub fn install(odb: &mut gix::OdbHandle, bytes: usize) -> Arc<CacheStats> {
+    let stats = Arc::new(CacheStats::default());
+    if bytes == 0 {
+        return stats;
+    }
+    // A (tree, path) is only read once, so whole trees are rarely asked for
+    // again; most of the memory goes to the bases they are deltas against
+    let object_bytes = bytes / 4;
+    let base_bytes = bytes - object_bytes;
+    let counted = Arc::clone(&stats);
+    odb.set_object_cache(move || {
+        Box::new(CountingObjects { inner: ObjectCache::new(object_bytes), stats: Arc::clone(&counted) })
+    });
+    let counted = Arc::clone(&stats);
+    odb.set_pack_cache(move || {
+        Box::new(CountingBases { inner: PackCache::new(base_bytes), stats: Arc::clone(&counted) })
+    });
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gix_pack::cache::{DecodeEntry, Object};
+
+    #[test]
+    fn test_counting_caches() {
+        let stats = Arc::new(CacheStats::default());
+        let mut objects = CountingObjects { inner: ObjectCache::new(1024), stats: Arc::clone(&stats) };
+        let mut bases = CountingBases { inner: PackCache::new(1024), stats: Arc::clone(&stats) };
+        let id = ObjectId::null(gix::hash::Kind::Sha1);
+        let mut out = Vec::new();
+
+        assert_eq!(objects.get(&id, &mut out), None);
+        objects.put(id, Kind::Tree, b"entries");
+        assert_eq!(objects.get(&id, &mut out), Some(Kind::Tree));
+        assert_eq!(out, b"entries");
+        assert_eq!(stats.objects(), (1, 1));
+
+        bases.put(0, 12, b"base", Kind::Tree, 4);
+        assert_eq!(bases.get(0, 12, &mut out), Some((Kind::Tree, 4)));
+        assert_eq!(bases.get(0, 99, &mut out), None);
+        assert_eq!(stats.delta_bases(), (1, 1));
+    }
+}
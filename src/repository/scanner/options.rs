@@ -0,0 +1,146 @@
+//! Scan options
+//!
+//! Knobs that change what the scanner considers part of the repository.
+
+use crate::util::format_timestamp;
+
+/// Options controlling which objects a scan covers
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Include blobs that only exist in stash entries or the index,
+    /// reported under synthetic `stash@{N}/...` and `:index:/...` paths
+    pub include_stash_and_index: bool,
+    /// Follow only the first parent of each commit
+    pub first_parent: bool,
+    /// Scan at most this many of the newest commits
+    pub max_commits: Option<usize>,
+    /// Skip commits made before this Unix timestamp
+    pub since: Option<i64>,
+    /// Scan the original objects even where `git replace` substitutes others
+    pub no_replace_objects: bool,
+    /// Fail when a pack can't be read or a blob's size can't be found,
+    /// rather than counting it as 0 bytes
+    pub strict: bool,
+    /// Scan only this sub-path of the repository (e.g. "src/vendor"),
+    /// without leading or trailing slashes
+    pub path: Option<String>,
+    /// Scan only the commits of this range, crediting only the blobs they
+    /// bring in (blobs its base already had don't count)
+    pub range: Option<RevRange>,
+}
+
+/// A `base..head` revision range: commits reachable from `head` but not
+/// from `base`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevRange {
+    pub base: String,
+    pub head: String,
+}
+
+impl RevRange {
+    /// Parse "base..head"; a missing head means HEAD, as in git
+    pub fn parse(range: &str) -> Option<Self> {
+        let (base, head) = range.split_once("..")?;
+        if base.is_empty() || head.starts_with('.') || head.contains("..") {
+            return None;
+        }
+        let head = if head.is_empty() { "HEAD" } else { head };
+        Some(Self { base: base.to_string(), head: head.to_string() })
+    }
+}
+
+impl std::fmt::Display for RevRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.base, self.head)
+    }
+}
+
+impl ScanOptions {
+    /// Describe the history limits in effect, e.g. "first-parent, 500 newest
+    /// commits"; `None` when every reachable commit is scanned
+    pub fn limits_label(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.first_parent {
+            parts.push("first-parent".to_string());
+        }
+        if let Some(n) = self.max_commits {
+            parts.push(format!("{} newest commit{}", n, if n == 1 { "" } else { "s" }));
+        }
+        if let Some(since) = self.since {
+            parts.push(format!("since {}", format_timestamp(since)));
+        }
+        if let Some(path) = &self.path {
+            parts.push(format!("under {}", path));
+        }
+        if let Some(range) = &self.range {
+            parts.push(format!("range {}", range));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// The sub-path scanned, if not the whole repository
+    pub fn scope(&self) -> Option<&[u8]> {
+        self.path.as_deref().map(str::as_bytes)
+    }
+}
+
+/// Whether `path` is on the way to `scope` or inside it
+///
+/// Trees above the scope are still walked to reach it; everything beside
+/// it is pruned.
+pub fn within_scope(scope: &[u8], path: &[u8]) -> bool {
+    let (shorter, longer) = if path.len() <= scope.len() { (path, scope) } else { (scope, path) };
+    longer.starts_with(shorter) && (shorter.is_empty() || longer.len() == shorter.len() || longer[shorter.len()] == b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_label() {
+        assert_eq!(ScanOptions::default().limits_label(), None);
+
+        let options = ScanOptions {
+            first_parent: true,
+            max_commits: Some(500),
+            since: Some(1704067200),
+            ..Default::default()
+        };
+        assert_eq!(options.limits_label().unwrap(), "first-parent, 500 newest commits, since 2024-01-01");
+
+        let one = ScanOptions { max_commits: Some(1), ..Default::default() };
+        assert_eq!(one.limits_label().unwrap(), "1 newest commit");
+
+        let scoped = ScanOptions { path: Some("src/vendor".to_string()), ..Default::default() };
+        assert_eq!(scoped.limits_label().unwrap(), "under src/vendor");
+
+        let ranged = ScanOptions { range: RevRange::parse("main.."), ..Default::default() };
+        assert_eq!(ranged.limits_label().unwrap(), "range main..HEAD");
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let range = RevRange::parse("origin/main..feature").unwrap();
+        assert_eq!((range.base.as_str(), range.head.as_str()), ("origin/main", "feature"));
+        assert_eq!(RevRange::parse("v1.0..").unwrap().head, "HEAD");
+        assert_eq!(RevRange::parse("main"), None);
+        assert_eq!(RevRange::parse("..feature"), None);
+        // Symmetric differences aren't ranges of one line of history
+        assert_eq!(RevRange::parse("main...feature"), None);
+        assert_eq!(RevRange::parse("a..b..c"), None);
+    }
+
+    #[test]
+    fn test_within_scope() {
+        let scope = b"src/vendor";
+        assert!(within_scope(scope, b""));
+        assert!(within_scope(scope, b"src"));
+        assert!(within_scope(scope, b"src/vendor"));
+        assert!(within_scope(scope, b"src/vendor/lib/a.bin"));
+        assert!(!within_scope(scope, b"docs"));
+        assert!(!within_scope(scope, b"src/main.rs"));
+        assert!(!within_scope(scope, b"src/vendored"));
+        assert!(!within_scope(scope, b"sr"));
+    }
+}
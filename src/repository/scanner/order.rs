@@ -0,0 +1,109 @@
+//! Commit scan order
+//!
+//! A blob is attributed to the first commit that is scanned with it, so the
+//! scan order decides `first_author` and `first_date`. Revwalk order depends
+//! on how branches were merged (parent order, breadth-first queueing), which
+//! let a merge claim blobs its second parent already introduced.
+//!
+//! Commits are instead scanned parents-first, and among commits that are
+//! ready at the same time the earliest by author date goes first (then the
+//! lowest OID). The result only depends on the commit graph and dates, not
+//! on the order the commits were collected in. Blobs indexed by an earlier
+//! run keep their attribution.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use gix::ObjectId;
+use rustc_hash::FxHashMap;
+
+use super::types::CommitInfo;
+
+/// Sort commits parents-first, breaking ties by author date, then OID
+///
+/// Parents outside `commits` (already scanned, or beyond a scan limit) are
+/// ignored.
+pub fn introduction_order(commits: Vec<CommitInfo>) -> Vec<CommitInfo> {
+    let index: FxHashMap<ObjectId, usize> =
+        commits.iter().enumerate().map(|(i, c)| (c.oid, i)).collect();
+
+    let mut pending_parents = vec![0usize; commits.len()];
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); commits.len()];
+    for (i, commit) in commits.iter().enumerate() {
+        for parent in &commit.parents {
+            if let Some(&p) = index.get(parent) {
+                pending_parents[i] += 1;
+                children[p].push(i);
+            }
+        }
+    }
+
+    let key = |i: usize| Reverse((commits[i].timestamp, commits[i].oid, i));
+    let mut ready: BinaryHeap<_> = (0..commits.len())
+        .filter(|&i| pending_parents[i] == 0)
+        .map(key)
+        .collect();
+
+    let mut order = Vec::with_capacity(commits.len());
+    while let Some(Reverse((_, _, i))) = ready.pop() {
+        order.push(i);
+        for &child in &children[i] {
+            pending_parents[child] -= 1;
+            if pending_parents[child] == 0 {
+                ready.push(key(child));
+            }
+        }
+    }
+
+    let mut slots: Vec<Option<CommitInfo>> = commits.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(id: u8, timestamp: i64, parents: &[u8]) -> CommitInfo {
+        let oid = |b: u8| ObjectId::from_bytes_or_panic(&[b; 20]);
+        CommitInfo {
+            oid: oid(id),
+            tree: oid(0),
+            parents: parents.iter().map(|&p| oid(p)).collect(),
+            author: format!("author {}", id),
+            email: String::new(),
+            timestamp,
+            subject: String::new(),
+        }
+    }
+
+    fn ids(commits: &[CommitInfo]) -> Vec<u8> {
+        commits.iter().map(|c| c.oid.as_bytes()[0]).collect()
+    }
+
+    #[test]
+    fn test_parents_first_then_author_date() {
+        // 1 -- 3 ------ 5 (merge of 3 and 4)
+        //  \-- 2 -- 4 -/
+        // 4 is dated before its parent 2, which must still go first
+        let graph = vec![
+            commit(5, 500, &[3, 4]),
+            commit(3, 300, &[1]),
+            commit(4, 150, &[2]),
+            commit(2, 200, &[1]),
+            commit(1, 100, &[]),
+        ];
+        let expected = vec![1, 2, 4, 3, 5];
+        assert_eq!(ids(&introduction_order(graph.clone())), expected);
+
+        let mut reversed = graph;
+        reversed.reverse();
+        assert_eq!(ids(&introduction_order(reversed)), expected);
+    }
+
+    #[test]
+    fn test_ties_and_missing_parents() {
+        // Parent 9 was scanned earlier; 2 and 3 share a date
+        let graph = vec![commit(3, 100, &[9]), commit(2, 100, &[9]), commit(4, 50, &[3])];
+        assert_eq!(ids(&introduction_order(graph)), vec![2, 3, 4]);
+    }
+}
@@ -1,20 +1,73 @@
 //! Pack file size index
 //!
 //! Encapsulates compressed size lookups from pack files and loose objects.
+//!
+//! Pack indices are read in parallel, one pack per task. gix memory-maps
+//! both the `.idx` and the `.pack`, so only the index pages are touched and
+//! pack data is never read (its length is all we need).
 
-use anyhow::Result;
+use anyhow::{ensure, Context, Result};
+use gix::objs::{find, Data, Find};
 use gix::ObjectId;
 use gix_hash::Kind as HashKind;
 use gix_pack::{data, index};
-use rustc_hash::FxHashMap;
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::path::{Path, PathBuf};
 
+use super::memory::{map_bytes, vec_bytes};
+
+/// Size stored in `PackSizeIndex::sizes` when `exact` holds the real entry
+const SEE_EXACT: u32 = u32::MAX;
+
 /// Index of compressed (on-disk) sizes for git objects
+///
+/// Packed objects are kept as a sorted array of 64-bit OID prefixes next to
+/// an array of 32-bit sizes, about 12 bytes per object instead of the ~60 a
+/// hash map of full OIDs takes. Objects whose prefix is shared, or whose
+/// size needs more than 32 bits, are looked up by full OID in `exact`. An
+/// OID that isn't packed but shares its prefix with a packed one would get
+/// that object's size; with 64 bits that takes billions of objects to become
+/// likely. Building it peaks at about 16 bytes per object, see
+/// `from_packs`.
 pub struct PackSizeIndex {
-    /// Sizes from pack files (oid -> size)
-    packed: FxHashMap<ObjectId, u64>,
+    /// First 8 bytes of each packed OID, sorted and unique
+    prefixes: Vec<u64>,
+    /// Compressed size for the prefix at the same position, or `SEE_EXACT`
+    sizes: Vec<u32>,
+    exact: FxHashMap<ObjectId, u64>,
+    /// Number of distinct packed objects
+    count: usize,
     /// Path to objects directory for loose object lookups
     objects_dir: PathBuf,
+    /// Packs that could not be read, or only in part
+    corrupt: Vec<CorruptPack>,
+    /// Objects truncated packs no longer hold in full; gix can panic on them
+    lost: FxHashSet<ObjectId>,
+}
+
+/// A pack whose index or data could not be read, or that was cut short,
+/// so some or all of its objects have no known size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptPack {
+    /// The pack's `.idx` file
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+impl CorruptPack {
+    /// The pack's checksum, from the `pack-<checksum>.idx` name git gives it
+    pub fn checksum(&self) -> Option<ObjectId> {
+        let stem = self.path.file_stem()?.to_str()?;
+        ObjectId::from_hex(stem.strip_prefix("pack-")?.as_bytes()).ok()
+    }
+}
+
+fn oid_prefix(oid: &ObjectId) -> u64 {
+    let bytes: [u8; 8] = oid.as_bytes()[..8].try_into().expect("OIDs are at least 8 bytes");
+    u64::from_be_bytes(bytes)
 }
 
 impl PackSizeIndex {
@@ -24,92 +77,313 @@ impl PackSizeIndex {
     /// which handles bare repos, worktrees, and repos where .git is a file.
     pub fn load(repo: &gix::Repository) -> Self {
         let objects_dir = repo.objects.store_ref().path().to_path_buf();
-        let packed = load_all_compressed_sizes(&objects_dir);
+        let LoadedPacks { packs, corrupt, mut lost } = load_all_compressed_sizes(&objects_dir);
+        let index = Self::from_packs(packs, objects_dir);
+        // Another pack may still hold a lost object whole
+        lost.retain(|oid| index.packed_size(*oid).is_none());
+        Self { corrupt, lost, ..index }
+    }
+
+    /// Load pack sizes of the repository at `repo_path`
+    pub fn open(repo_path: &str) -> Result<Self> {
+        let repo = gix::open(repo_path).context("Failed to open git repository")?;
+        Ok(Self::load(&repo))
+    }
+
+    /// Build the index by merging the packs' OID-ordered entries; for an
+    /// object stored in several packs, one of its sizes is kept
+    ///
+    /// The OIDs are read from the memory-mapped `.idx` files, so while
+    /// building, the heap holds the packs' 4-byte sizes and the final
+    /// arrays, about 16 bytes per object, rather than a list of full OIDs.
+    fn from_packs<O: PackOids>(packs: Vec<PackSizes<O>>, objects_dir: PathBuf) -> Self {
+        let upper_bound: usize = packs.iter().map(|pack| pack.sizes.len()).sum();
+        let mut prefixes = Vec::with_capacity(upper_bound);
+        let mut sizes = Vec::with_capacity(upper_bound);
+        let mut exact: FxHashMap<ObjectId, u64> =
+            packs.iter().flat_map(|pack| pack.large.iter().copied()).collect();
+        let mut count = 0;
+
+        // The next entry of every pack, smallest OID first
+        let mut cursors = vec![0u32; packs.len()];
+        let mut heads = BinaryHeap::new();
+        for (p, pack) in packs.iter().enumerate() {
+            if let Some(oid) = pack.next(&mut cursors[p]) {
+                heads.push(Reverse((oid, p)));
+            }
+        }
+
+        // Entries sharing a prefix, the one being built
+        let mut run: Vec<(ObjectId, u32)> = Vec::new();
+        let mut last = None;
+        while let Some(Reverse((oid, p))) = heads.pop() {
+            let size = packs[p].sizes[cursors[p] as usize];
+            cursors[p] += 1;
+            if let Some(next) = packs[p].next(&mut cursors[p]) {
+                heads.push(Reverse((next, p)));
+            }
+            if last == Some(oid) {
+                continue;
+            }
+            last = Some(oid);
+            count += 1;
+            if run.first().is_some_and(|(first, _)| oid_prefix(first) != oid_prefix(&oid)) {
+                close_run(&mut run, &mut prefixes, &mut sizes, &mut exact);
+            }
+            run.push((oid, size));
+        }
+        close_run(&mut run, &mut prefixes, &mut sizes, &mut exact);
+        drop(packs);
+        prefixes.shrink_to_fit();
+        sizes.shrink_to_fit();
+
         Self {
-            packed,
+            prefixes,
+            sizes,
+            exact,
+            count,
             objects_dir,
+            corrupt: Vec::new(),
+            lost: FxHashSet::default(),
         }
     }
 
-    /// Get the compressed size for an object
-    pub fn size_of(&self, oid: ObjectId) -> i64 {
-        // First check pack files
-        if let Some(&size) = self.packed.get(&oid) {
-            return size as i64;
+    fn packed_size(&self, oid: ObjectId) -> Option<u64> {
+        let i = self.prefixes.binary_search(&oid_prefix(&oid)).ok()?;
+        match self.sizes[i] {
+            SEE_EXACT => self.exact.get(&oid).copied(),
+            size => Some(size as u64),
         }
-        // Fall back to loose object
-        if let Some(size) = get_loose_object_size(&self.objects_dir, oid) {
-            return size as i64;
+    }
+
+    /// Compressed size of an object, packed or loose; `None` when it is in
+    /// neither a readable pack nor the loose objects
+    pub fn lookup(&self, oid: ObjectId) -> Option<u64> {
+        self.packed_size(oid).or_else(|| get_loose_object_size(&self.objects_dir, oid))
+    }
+
+    /// Whether a pack or loose object still holds `oid`, or `gc` has pruned
+    /// it; `None` for a malformed OID, or one found nowhere while some pack
+    /// couldn't be read (it may be in that one)
+    pub fn holds(&self, oid: &[u8]) -> Option<bool> {
+        let oid = ObjectId::try_from(oid).ok()?;
+        match self.lookup(oid) {
+            Some(_) => Some(true),
+            None if self.corrupt.is_empty() => Some(false),
+            None => None,
         }
-        0
+    }
+
+    /// Get the compressed size for an object, 0 when it is unknown
+    pub fn size_of(&self, oid: ObjectId) -> i64 {
+        self.lookup(oid).unwrap_or(0) as i64
+    }
+
+    /// Packs that could not be read when the index was loaded
+    pub fn corrupt_packs(&self) -> &[CorruptPack] {
+        &self.corrupt
+    }
+
+    /// `odb`, failing to find the objects truncated packs lost instead of
+    /// reading past their end
+    pub fn guard<'a, S>(&'a self, odb: &'a S) -> Guarded<'a, S> {
+        Guarded { odb, lost: &self.lost }
     }
 
     /// Number of objects in pack index
     pub fn len(&self) -> usize {
-        self.packed.len()
+        self.count
     }
 
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
-        self.packed.is_empty()
+        self.count == 0
+    }
+
+    /// Approximate memory the index holds, for `--profile`
+    pub fn heap_bytes(&self) -> usize {
+        vec_bytes(&self.prefixes) + vec_bytes(&self.sizes) + map_bytes(&self.exact)
+    }
+}
+
+/// An object store that fails on objects lost from truncated packs, which
+/// gix would otherwise read past the end of the pack (and panic)
+pub struct Guarded<'a, S> {
+    odb: &'a S,
+    lost: &'a FxHashSet<ObjectId>,
+}
+
+impl<S: Find> Find for Guarded<'_, S> {
+    fn try_find<'b>(&self, id: &gix::oid, buffer: &'b mut Vec<u8>) -> Result<Option<Data<'b>>, find::Error> {
+        if self.lost.contains(id) {
+            return Err("lost from a truncated pack".into());
+        }
+        self.odb.try_find(id, buffer)
+    }
+}
+
+/// Size stored in `PackSizes::sizes` for an object a truncated pack lost
+const LOST: u32 = u32::MAX - 1;
+
+/// Add the entries in `run`, which share a prefix, to the index arrays
+fn close_run(
+    run: &mut Vec<(ObjectId, u32)>,
+    prefixes: &mut Vec<u64>,
+    sizes: &mut Vec<u32>,
+    exact: &mut FxHashMap<ObjectId, u64>,
+) {
+    let Some((first, size)) = run.first() else {
+        return;
+    };
+    prefixes.push(oid_prefix(first));
+    if run.len() == 1 {
+        // `SEE_EXACT` stays, `exact` already has the large size
+        sizes.push(*size);
+    } else {
+        // One slot per shared prefix; every one of them defers to `exact`
+        sizes.push(SEE_EXACT);
+        for (oid, size) in run.iter().filter(|(_, size)| *size != SEE_EXACT) {
+            exact.insert(*oid, u64::from(*size));
+        }
+    }
+    run.clear();
+}
+
+/// OIDs of one pack by position, in ascending order
+trait PackOids {
+    fn oid_at(&self, index: u32) -> ObjectId;
+}
+
+impl PackOids for index::File {
+    fn oid_at(&self, index: u32) -> ObjectId {
+        self.oid_at_index(index).to_owned()
+    }
+}
+
+/// Sizes read from one pack, by the position of the object in its index
+struct PackSizes<O = index::File> {
+    oids: O,
+    /// Compressed size, `SEE_EXACT` when it is in `large`, or `LOST`
+    sizes: Vec<u32>,
+    /// Objects too large for `sizes`
+    large: Vec<(ObjectId, u64)>,
+    /// Objects a truncated pack lists but no longer holds in full
+    lost: Vec<ObjectId>,
+}
+
+impl<O: PackOids> PackSizes<O> {
+    /// OID of the first entry at or after `cursor` that was not lost,
+    /// moving `cursor` there
+    fn next(&self, cursor: &mut u32) -> Option<ObjectId> {
+        while let Some(&size) = self.sizes.get(*cursor as usize) {
+            if size != LOST {
+                return Some(self.oids.oid_at(*cursor));
+            }
+            *cursor += 1;
+        }
+        None
     }
 }
 
 /// Load compressed (on-disk) sizes for all objects in a pack file
+///
+/// Fails when the index or the pack header can't be read, or they disagree
+/// on the number of objects. A pack cut short (a truncated download or
+/// copy) keeps the sizes of the objects before the cut; the rest are lost.
+/// Only the trailer is compared with the index: hashing whole packs would
+/// cost more than the rest of the load.
 fn load_pack_compressed_sizes(
     idx_path: &Path,
     pack_path: &Path,
-) -> Result<FxHashMap<ObjectId, u64>> {
+) -> Result<PackSizes> {
     let hash_kind = HashKind::Sha1;
 
     let idx = index::File::at(idx_path, hash_kind)?;
     let pack = data::File::at(pack_path, hash_kind)?;
+    ensure!(
+        idx.num_objects() == pack.num_objects(),
+        "index lists {} objects, pack holds {}",
+        idx.num_objects(),
+        pack.num_objects()
+    );
 
-    let mut entries: Vec<_> = idx.iter().collect();
-    entries.sort_by_key(|e| e.pack_offset);
+    // Index positions (OID order) by pack offset, 4 bytes an object
+    let mut by_offset: Vec<u32> = (0..idx.num_objects()).collect();
+    by_offset.sort_unstable_by_key(|&i| idx.pack_offset_at_index(i));
 
-    let pack_end = pack.pack_end() as u64;
-    let mut sizes = FxHashMap::default();
-    sizes.reserve(entries.len());
-
-    for (i, entry) in entries.iter().enumerate() {
-        let entry_end = entries
-            .get(i + 1)
-            .map(|next| next.pack_offset)
+    // A pack cut short has lost its trailer, so where its last object ended
+    // is unknown; the checksum the index recorded no longer matches
+    let intact = pack.checksum() == idx.pack_checksum();
+    let (pack_end, data_end) = if intact {
+        (pack.pack_end() as u64, pack.pack_end() as u64)
+    } else {
+        (u64::MAX, pack.data_len() as u64)
+    };
+    let mut sizes = vec![LOST; by_offset.len()];
+    let mut large = Vec::new();
+    let mut lost = Vec::new();
+    for (k, &i) in by_offset.iter().enumerate() {
+        let entry_end = by_offset
+            .get(k + 1)
+            .map(|&next| idx.pack_offset_at_index(next))
             .unwrap_or(pack_end);
-
-        let entry_size = entry_end - entry.pack_offset;
-        sizes.insert(entry.oid, entry_size);
+        let size = entry_end.saturating_sub(idx.pack_offset_at_index(i));
+        if entry_end > data_end {
+            lost.push(idx.oid_at(i));
+        } else if size >= u64::from(LOST) {
+            large.push((idx.oid_at(i), size));
+            sizes[i as usize] = SEE_EXACT;
+        } else {
+            sizes[i as usize] = size as u32;
+        }
     }
+    drop(by_offset);
 
-    Ok(sizes)
+    Ok(PackSizes { oids: idx, sizes, large, lost })
+}
+
+/// Sizes from every pack in a repository, and the packs that let them down
+struct LoadedPacks {
+    packs: Vec<PackSizes>,
+    corrupt: Vec<CorruptPack>,
+    lost: FxHashSet<ObjectId>,
 }
 
 /// Load compressed sizes from all pack files in objects/pack/
-fn load_all_compressed_sizes(objects_dir: &Path) -> FxHashMap<ObjectId, u64> {
-    let mut all_sizes = FxHashMap::default();
+fn load_all_compressed_sizes(objects_dir: &Path) -> LoadedPacks {
+    let mut loaded = LoadedPacks { packs: Vec::new(), corrupt: Vec::new(), lost: FxHashSet::default() };
     let pack_dir = objects_dir.join("pack");
+    let mut idx_paths: Vec<PathBuf> = match std::fs::read_dir(&pack_dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "idx"))
+            .filter(|path| path.with_extension("pack").exists())
+            .collect(),
+        Err(_) => return loaded,
+    };
+    idx_paths.sort();
+
+    let per_pack: Vec<Result<PackSizes>> = idx_paths
+        .par_iter()
+        .map(|path| load_pack_compressed_sizes(path, &path.with_extension("pack")))
+        .collect();
 
-    if let Ok(entries) = std::fs::read_dir(&pack_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().is_some_and(|e| e == "idx") {
-                let pack_path = path.with_extension("pack");
-                if pack_path.exists() {
-                    match load_pack_compressed_sizes(&path, &pack_path) {
-                        Ok(sizes) => {
-                            all_sizes.extend(sizes);
-                        }
-                        Err(e) => {
-                            eprintln!("Warning: Failed to load pack {:?}: {}", path, e);
-                        }
-                    }
+    for (path, pack) in idx_paths.into_iter().zip(per_pack) {
+        match pack {
+            Ok(mut pack) => {
+                if !pack.lost.is_empty() {
+                    let reason =
+                        format!("truncated: {} of {} objects are incomplete", pack.lost.len(), pack.sizes.len());
+                    loaded.corrupt.push(CorruptPack { path, reason });
                 }
+                loaded.lost.extend(pack.lost.drain(..));
+                loaded.packs.push(pack);
             }
+            Err(e) => loaded.corrupt.push(CorruptPack { path, reason: format!("{:#}", e) }),
         }
     }
-
-    all_sizes
+    loaded
 }
 
 /// Get compressed size for a loose object by reading file size
@@ -118,3 +392,89 @@ fn get_loose_object_size(objects_dir: &Path, oid: ObjectId) -> Option<u64> {
     let path = objects_dir.join(&hex[..2]).join(&hex[2..]);
     std::fs::metadata(&path).ok().map(|m| m.len())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oid(prefix: u64, tail: u8) -> ObjectId {
+        let mut bytes = [tail; 20];
+        bytes[..8].copy_from_slice(&prefix.to_be_bytes());
+        ObjectId::from_bytes_or_panic(&bytes)
+    }
+
+    impl PackOids for Vec<ObjectId> {
+        fn oid_at(&self, index: u32) -> ObjectId {
+            self[index as usize]
+        }
+    }
+
+    /// A pack holding `entries` (a `None` size is lost), in index order
+    fn pack(entries: &[(ObjectId, Option<u64>)]) -> PackSizes<Vec<ObjectId>> {
+        let mut entries = entries.to_vec();
+        entries.sort_by_key(|(oid, _)| *oid);
+        let mut pack = PackSizes { oids: Vec::new(), sizes: Vec::new(), large: Vec::new(), lost: Vec::new() };
+        for (oid, size) in entries {
+            pack.oids.push(oid);
+            pack.sizes.push(match size {
+                None => LOST,
+                Some(size) if size >= u64::from(LOST) => {
+                    pack.large.push((oid, size));
+                    SEE_EXACT
+                }
+                Some(size) => size as u32,
+            });
+        }
+        pack
+    }
+
+    fn index_of(packs: Vec<PackSizes<Vec<ObjectId>>>) -> PackSizeIndex {
+        PackSizeIndex::from_packs(packs, PathBuf::from("/nonexistent"))
+    }
+
+    #[test]
+    fn test_lookup_with_shared_prefixes_and_large_sizes() {
+        let index = index_of(vec![
+            pack(&[(oid(3, 0), Some(30)), (oid(1, 0), Some(10)), (oid(2, 1), Some(21))]),
+            pack(&[(oid(2, 2), Some(22)), (oid(4, 0), Some(5 << 32)), (oid(1, 0), Some(10))]),
+        ]);
+
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.packed_size(oid(1, 0)), Some(10));
+        assert_eq!(index.packed_size(oid(2, 1)), Some(21));
+        assert_eq!(index.packed_size(oid(2, 2)), Some(22));
+        assert_eq!(index.packed_size(oid(3, 0)), Some(30));
+        assert_eq!(index.packed_size(oid(4, 0)), Some(5 << 32));
+
+        // Not packed: a fresh prefix, and an unknown OID under a shared one
+        assert_eq!(index.packed_size(oid(9, 0)), None);
+        assert_eq!(index.packed_size(oid(2, 3)), None);
+        assert_eq!(index.size_of(oid(9, 0)), 0);
+    }
+
+    #[test]
+    fn test_lost_objects_are_skipped() {
+        let index = index_of(vec![
+            pack(&[(oid(1, 0), Some(10)), (oid(2, 0), None), (oid(3, 0), None)]),
+            pack(&[(oid(3, 0), Some(30))]),
+        ]);
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.packed_size(oid(1, 0)), Some(10));
+        assert_eq!(index.packed_size(oid(2, 0)), None);
+        assert_eq!(index.packed_size(oid(3, 0)), Some(30));
+    }
+
+    #[test]
+    fn test_holds() {
+        let mut index = index_of(vec![pack(&[(oid(1, 0), Some(10))])]);
+        assert_eq!(index.holds(oid(1, 0).as_bytes()), Some(true));
+        assert_eq!(index.holds(oid(2, 0).as_bytes()), Some(false));
+        assert_eq!(index.holds(b"short"), None);
+
+        // A pack that couldn't be read may be the one holding it
+        index.corrupt.push(CorruptPack { path: PathBuf::from("pack-x.idx"), reason: "truncated".to_string() });
+        assert_eq!(index.holds(oid(1, 0).as_bytes()), Some(true));
+        assert_eq!(index.holds(oid(2, 0).as_bytes()), None);
+    }
+}
@@ -1,8 +1,16 @@
 //! Progress reporting abstraction
 //!
-//! Decouples scanning logic from UI concerns (indicatif).
+//! Decouples scanning logic from UI concerns (indicatif, or JSON events for
+//! programs wrapping repodiet).
 
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::model::Tree;
 
 /// A handle to an active progress bar
 pub trait ProgressHandle: Send + Sync {
@@ -13,6 +21,9 @@ pub trait ProgressHandle: Send + Sync {
 /// Factory for creating progress handles
 pub trait ProgressReporter: Send + Sync {
     fn start(&self, label: &str, total: u64) -> Box<dyn ProgressHandle>;
+
+    /// Called once with the finished tree at the end of a scan
+    fn scan_finished(&self, _root: &Tree) {}
 }
 
 /// Indicatif-based progress reporter for CLI usage
@@ -82,3 +93,156 @@ impl ProgressReporter for VerboseProgress {
         }
     }
 }
+
+/// Progress reporter writing newline-delimited JSON events, for wrappers and
+/// editor plugins that draw their own progress UI
+///
+/// Every line is one object with an `event` field:
+/// `phase_started` (`phase`, `total`), `progress` (`phase`, `done`, `total`;
+/// at most one per percent), `phase_finished` (`phase`, `done`,
+/// `elapsed_ms`) and finally `scan_summary` with the scanned totals.
+pub struct JsonProgress {
+    out: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JsonEvent<'a> {
+    PhaseStarted { phase: &'a str, total: u64 },
+    Progress { phase: &'a str, done: u64, total: u64 },
+    PhaseFinished { phase: &'a str, done: u64, elapsed_ms: u64 },
+    ScanSummary {
+        cumulative_size: u64,
+        current_size: u64,
+        deleted_size: u64,
+        blob_count: u64,
+    },
+}
+
+impl JsonProgress {
+    /// Events go to stderr, leaving stdout to the command's own output
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self { out: Arc::new(Mutex::new(Box::new(out))) }
+    }
+}
+
+impl ProgressReporter for JsonProgress {
+    fn start(&self, label: &str, total: u64) -> Box<dyn ProgressHandle> {
+        let phase = label.to_lowercase();
+        emit(&self.out, &JsonEvent::PhaseStarted { phase: &phase, total });
+        Box::new(JsonHandle {
+            out: Arc::clone(&self.out),
+            phase,
+            total,
+            done: AtomicU64::new(0),
+            reported_percent: AtomicU64::new(0),
+            started: Instant::now(),
+        })
+    }
+
+    fn scan_finished(&self, tree: &Tree) {
+        let root = tree.root();
+        emit(&self.out, &JsonEvent::ScanSummary {
+            cumulative_size: root.cumulative_size,
+            current_size: root.current_size,
+            deleted_size: root.deleted_cumulative_size(),
+            blob_count: root.blob_count,
+        });
+    }
+}
+
+struct JsonHandle {
+    out: Arc<Mutex<Box<dyn Write + Send>>>,
+    phase: String,
+    total: u64,
+    done: AtomicU64,
+    reported_percent: AtomicU64,
+    started: Instant,
+}
+
+impl ProgressHandle for JsonHandle {
+    fn inc(&self, n: u64) {
+        let done = self.done.fetch_add(n, Ordering::Relaxed) + n;
+        if self.total == 0 {
+            return;
+        }
+        let percent = (done.min(self.total) * 100) / self.total;
+        if self.reported_percent.fetch_max(percent, Ordering::Relaxed) < percent {
+            emit(&self.out, &JsonEvent::Progress { phase: &self.phase, done, total: self.total });
+        }
+    }
+
+    fn finish(&self) {
+        emit(&self.out, &JsonEvent::PhaseFinished {
+            phase: &self.phase,
+            done: self.done.load(Ordering::Relaxed),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+        });
+    }
+}
+
+/// Write one event line; a closed stream must not fail the scan
+fn emit(out: &Mutex<Box<dyn Write + Send>>, event: &JsonEvent<'_>) {
+    let Ok(line) = serde_json::to_string(event) else { return };
+    let mut out = out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let _ = writeln!(out, "{}", line);
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cloneable in-memory sink, so the test can read what was written
+    #[derive(Clone, Default)]
+    struct Sink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_progress_events() {
+        let sink = Sink::default();
+        let progress = JsonProgress::new(sink.clone());
+
+        let pb = progress.start("Scanning", 200);
+        for _ in 0..200 {
+            pb.inc(1);
+        }
+        pb.finish();
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["a"], 30, 10, 2);
+        root.compute_totals();
+        progress.scan_finished(&root);
+
+        let output = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        // start, one per percent, finish, summary
+        assert_eq!(events.len(), 1 + 100 + 2);
+        assert_eq!(events[0], serde_json::json!({"event": "phase_started", "phase": "scanning", "total": 200}));
+        assert_eq!(events[1], serde_json::json!({"event": "progress", "phase": "scanning", "done": 2, "total": 200}));
+        assert_eq!(events[101]["event"], "phase_finished");
+        assert_eq!(events[101]["done"], 200);
+        assert_eq!(
+            events[102],
+            serde_json::json!({
+                "event": "scan_summary",
+                "cumulative_size": 30,
+                "current_size": 10,
+                "deleted_size": 0,
+                "blob_count": 2
+            })
+        );
+    }
+}
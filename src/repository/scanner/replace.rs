@@ -0,0 +1,85 @@
+//! Replace refs and grafts: which history a scan sees
+//!
+//! `git replace` stores substitute objects under `refs/replace/<oid>`, and git
+//! shows the substitutes everywhere unless `--no-replace-objects`,
+//! `GIT_NO_REPLACE_OBJECTS` or `core.useReplaceRefs = false` says otherwise.
+//! The scanner follows the same rules, so its numbers describe the history
+//! `git log` shows.
+//!
+//! Grafts (`info/grafts`) are deprecated in git and not supported by gix; a
+//! graft file is reported and ignored.
+
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use tracing::{debug, warn};
+
+const REPLACE_REF_PREFIX: &str = "refs/replace/";
+
+/// An opened repository and the replacements its object store applies
+pub struct OpenedRepo {
+    pub repo: gix::Repository,
+    /// Identifies the replacements in effect ("" for none), so a cached index
+    /// built from a different view of history can be detected
+    pub replacements: String,
+}
+
+/// Open the repository at `path`, applying replace refs unless `no_replace`
+pub fn open_repo(path: &Path, no_replace: bool) -> Result<OpenedRepo> {
+    let mut repo = gix::open(path).context("Failed to open git repository")?;
+    warn_about_grafts(&repo);
+
+    let disabled = no_replace
+        || std::env::var_os("GIT_NO_REPLACE_OBJECTS").is_some()
+        || repo.config_snapshot().boolean("core.useReplaceRefs") == Some(false);
+    let wanted = replace_ref_count(&repo);
+    if disabled || wanted == 0 {
+        repo.objects.ignore_replacements = true;
+        return Ok(OpenedRepo { repo, replacements: String::new() });
+    }
+
+    // Whether gix loads replacements depends on how its version reads
+    // `core.useReplaceRefs` (0.76 only does when it is false), so try both
+    // settings rather than relying on either
+    if repo.objects.store_ref().replacements().count() == 0 {
+        for value in ["true", "false"] {
+            let options = gix::open::Options::default().config_overrides([format!("core.useReplaceRefs={}", value)]);
+            let candidate = gix::open_opts(path, options).context("Failed to open git repository")?;
+            if candidate.objects.store_ref().replacements().count() > 0 {
+                repo = candidate;
+                break;
+            }
+        }
+    }
+
+    let replacements: Vec<_> = repo.objects.store_ref().replacements().collect();
+    if replacements.is_empty() {
+        warn!(refs = wanted, "Replace refs could not be applied; scanning the original history");
+        return Ok(OpenedRepo { repo, replacements: String::new() });
+    }
+    debug!(objects = replacements.len(), "Applying replace refs");
+    let mut hasher = DefaultHasher::new();
+    replacements.hash(&mut hasher);
+    Ok(OpenedRepo {
+        repo,
+        replacements: format!("{}:{:016x}", replacements.len(), hasher.finish()),
+    })
+}
+
+fn replace_ref_count(repo: &gix::Repository) -> usize {
+    repo.references()
+        .ok()
+        .and_then(|refs| refs.prefixed(REPLACE_REF_PREFIX).ok().map(|iter| iter.filter_map(Result::ok).count()))
+        .unwrap_or(0)
+}
+
+fn warn_about_grafts(repo: &gix::Repository) {
+    let grafts = repo.common_dir().join("info").join("grafts");
+    if std::fs::metadata(&grafts).is_ok_and(|meta| meta.len() > 0) {
+        warn!(
+            "{} is ignored; convert it with `git replace --convert-graft-file` to have it applied",
+            grafts.display()
+        );
+    }
+}
@@ -6,11 +6,11 @@ use anyhow::Result;
 use gix::ObjectId;
 use rustc_hash::FxHashSet;
 
-use crate::model::TreeNode;
+use crate::model::Tree;
 
 use super::interner::PathInterner;
 use super::progress::ProgressReporter;
-use super::types::ScanDelta;
+use super::types::{HeadSnapshot, PathId, ScanDelta, SkippedObject};
 
 /// Persistence layer for scan data
 ///
@@ -24,12 +24,54 @@ pub trait ScanStore {
     /// Set the cached HEAD OID
     async fn set_head_oid(&self, oid_hex: &str) -> Result<()>;
 
+    /// Get the history limits the stored results were scanned with ("" for none)
+    async fn get_scan_limits(&self) -> Option<String>;
+
+    /// Record the history limits of the latest scan (see `ScanOptions::limits_label`)
+    async fn set_scan_limits(&self, limits: &str) -> Result<()>;
+
+    /// Get the replace refs the stored results were scanned through ("" for none)
+    async fn get_replacements(&self) -> Option<String>;
+
+    /// Record the replace refs applied by the latest scan
+    async fn set_replacements(&self, replacements: &str) -> Result<()>;
+
+    /// Get the mailmap first authors were credited through ("" for none)
+    async fn get_mailmap(&self) -> Option<String>;
+
+    /// Record the mailmap applied by the latest scan (see `Mailmap::label`)
+    async fn set_mailmap(&self, mailmap: &str) -> Result<()>;
+
+    /// Get the sub-path the stored results were scanned under ("" for all)
+    async fn get_scan_path(&self) -> Option<String>;
+
+    /// Record the sub-path the latest scan was limited to (see `ScanOptions::path`)
+    async fn set_scan_path(&self, path: &str) -> Result<()>;
+
+    /// Get the base commit the stored results exclude ("" for none)
+    async fn get_scan_range(&self) -> Option<String>;
+
+    /// Record the base commit the latest scan's range excluded, resolved
+    /// (see `ScanOptions::range`)
+    async fn set_scan_range(&self, base: &str) -> Result<()>;
+
+    /// Forget everything stored, so the next scan starts from scratch
+    async fn clear(&self) -> Result<()>;
+
     /// Load all scanned commit OIDs into a set for fast lookup
     async fn load_scanned_commits(&self) -> FxHashSet<[u8; 20]>;
 
     /// Load all previously seen blob OIDs
     async fn load_seen_blobs(&self) -> Result<FxHashSet<ObjectId>>;
 
+    /// Load the paths interned by earlier scans, under the IDs they were
+    /// stored with; paths interned after them are stored by the next
+    /// `apply_scan` or `save_delta_rows`
+    async fn load_paths(&self) -> Result<PathInterner>;
+
+    /// Load (tree, path) pairs whose whole subtree was indexed by earlier scans
+    async fn load_seen_trees(&self) -> Result<Vec<(ObjectId, PathId)>>;
+
     /// Save blob and metadata rows from a scan delta
     ///
     /// This only persists the data rows, not the scanning state.
@@ -46,8 +88,16 @@ pub trait ScanStore {
     /// This advances the scanning state, separate from row persistence.
     async fn mark_scanned_commits(&self, commits: &[ObjectId]) -> Result<()>;
 
+    /// Replace the packs recorded as unreadable (`SkippedKind::Pack`) with
+    /// those the latest scan could not read
+    async fn replace_corrupt_packs(&self, packs: &[SkippedObject]) -> Result<()>;
+
+    /// Replace the stored HEAD snapshot with `head`
+    async fn replace_head_blobs(&self, head: &HeadSnapshot, interner: &PathInterner)
+        -> Result<()>;
+
     /// Load the tree from the database
-    async fn load_tree(&self) -> Result<TreeNode>;
+    async fn load_tree(&self) -> Result<Tree>;
 
     /// Apply a scan result atomically if the store supports it.
     ///
@@ -1,14 +1,30 @@
 //! Tree scanning context
 //!
 //! Encapsulates the recursive tree scanning algorithm with all necessary state.
+//! A commit whose parent was already scanned is walked alongside the parent's
+//! tree, so only the entries that changed are visited.
 
+use gix::bstr::BStr;
+use gix::objs::tree::EntryRef;
 use gix::prelude::{Find, FindExt};
+use gix::traverse::tree::{visit::Action, Recorder, Visit};
 use gix::ObjectId;
 use rustc_hash::FxHashSet;
+use std::cmp::Ordering;
+
+use crate::model::EntryKind;
 
 use super::interner::PathInterner;
+use super::memory::{set_bytes, vec_bytes};
+use super::options::within_scope;
 use super::pack::PackSizeIndex;
-use super::types::{BlobMetaRow, BlobRow, CommitInfo, HeadSnapshot, PathId, ScanDelta};
+use super::types::{
+    entry_kind, BlobMetaRow, BlobPathRow, BlobRow, CommitInfo, HeadSnapshot, PathId, RemovalRow, ScanDelta,
+    SkippedKind, SkippedObject, TreePathRow,
+};
+
+/// Why a blob counted as 0 bytes has no size
+const UNKNOWN_SIZE: &str = "not in any readable pack or loose object";
 
 /// Buffer pool for reusing decode buffers across recursion
 #[derive(Default)]
@@ -40,6 +56,11 @@ impl BufferPool {
 pub struct DeltaBuilder {
     blobs: Vec<BlobRow>,
     metadata: Vec<BlobMetaRow>,
+    occurrences: Vec<BlobPathRow>,
+    trees: Vec<TreePathRow>,
+    gitlinks: Vec<PathId>,
+    removals: Vec<RemovalRow>,
+    skipped: Vec<SkippedObject>,
 }
 
 impl DeltaBuilder {
@@ -48,51 +69,108 @@ impl DeltaBuilder {
     }
 
     /// Record a blob found during scanning
+    #[allow(clippy::too_many_arguments)]
     pub fn record_blob(
         &mut self,
         oid: ObjectId,
         path_id: PathId,
+        kind: EntryKind,
         cumulative_size: i64,
         current_size: i64,
         commit: &CommitInfo,
         is_new_blob: bool,
     ) {
+        self.occurrences.push(BlobPathRow { oid, path_id });
+
         if is_new_blob {
             self.blobs.push(BlobRow {
                 oid,
                 path_id,
                 cumulative_size,
                 current_size,
+                kind,
             });
             self.metadata.push(BlobMetaRow {
                 oid,
                 size: cumulative_size,
                 path_id,
                 author: commit.author.clone(),
+                email: commit.email.clone(),
                 timestamp: commit.timestamp,
+                commit: commit.oid,
+                subject: commit.subject.clone(),
             });
-        } else if current_size > 0 {
-            // Existing blob at current path - only record current_size contribution
+        } else {
+            // A blob counted at another path: the path gets a version, not its
+            // bytes, whether or not HEAD has it there, so the row does not
+            // depend on when the history was scanned
             self.blobs.push(BlobRow {
                 oid,
                 path_id,
                 cumulative_size: 0,
                 current_size,
+                kind,
             });
         }
     }
 
+    /// Record a path holding a submodule commit
+    pub fn record_gitlink(&mut self, path_id: PathId) {
+        self.gitlinks.push(path_id);
+    }
+
+    /// Record a path `commit` removed
+    pub fn record_removal(&mut self, path_id: PathId, commit: &CommitInfo) {
+        self.removals.push(RemovalRow { path_id, timestamp: commit.timestamp });
+    }
+
+    /// Record a tree whose entire subtree has been indexed
+    pub fn record_tree(&mut self, oid: ObjectId, path_id: PathId) {
+        self.trees.push(TreePathRow { oid, path_id });
+    }
+
+    /// Record an object that could not be read, met at `path`
+    pub fn record_skipped(&mut self, oid: ObjectId, kind: SkippedKind, path: &[u8], reason: String) {
+        let path = String::from_utf8_lossy(path).into_owned();
+        self.skipped.push(SkippedObject { oid, kind, path, reason });
+    }
+
     /// Build the final ScanDelta from accumulated results
     pub fn build(self) -> ScanDelta {
         ScanDelta {
             blobs: self.blobs,
             metadata: self.metadata,
+            occurrences: self.occurrences,
+            trees: self.trees,
+            gitlinks: self.gitlinks,
+            removals: self.removals,
+            skipped: self.skipped,
         }
     }
 
     pub fn blob_count(&self) -> usize {
         self.blobs.len()
     }
+
+    /// Rows of every kind accumulated so far
+    pub fn row_count(&self) -> usize {
+        self.blobs.len() + self.metadata.len() + self.occurrences.len() + self.trees.len()
+    }
+
+    /// Approximate memory the rows hold, for `--profile`
+    pub fn heap_bytes(&self) -> usize {
+        let authors: usize = self.metadata.iter().map(|row| row.author.capacity()).sum();
+        let reasons: usize = self.skipped.iter().map(|skipped| skipped.reason.capacity()).sum();
+        vec_bytes(&self.blobs)
+            + vec_bytes(&self.metadata)
+            + vec_bytes(&self.occurrences)
+            + vec_bytes(&self.trees)
+            + vec_bytes(&self.gitlinks)
+            + vec_bytes(&self.removals)
+            + vec_bytes(&self.skipped)
+            + authors
+            + reasons
+    }
 }
 
 /// Context for tree scanning - replaces the 14-parameter recursive function
@@ -102,13 +180,24 @@ pub struct TreeScanCtx<'a, S: Find> {
     head: &'a HeadSnapshot,
 
     pub interner: &'a mut PathInterner,
+    /// Only paths on the way to or under this sub-path are walked
+    scope: Option<&'a [u8]>,
     buf_pool: BufferPool,
+    /// Commits walked alongside a parent's tree
+    diffed: usize,
 
+    /// (tree, path) pairs indexed in earlier runs or visited in this one
     seen_trees: FxHashSet<(ObjectId, PathId)>,
+    /// Visited trees with objects missing somewhere below (shallow clones)
+    incomplete_trees: FxHashSet<(ObjectId, PathId)>,
     seen_blobs: FxHashSet<ObjectId>,
+    /// Blobs of the commits a range grows from, not counted at any path
+    range_known: FxHashSet<ObjectId>,
     seen_path_blobs: FxHashSet<(PathId, ObjectId)>,
 
     out: DeltaBuilder,
+    /// Blobs in deltas already taken
+    taken_blobs: usize,
 }
 
 impl<'a, S: Find> TreeScanCtx<'a, S> {
@@ -118,32 +207,71 @@ impl<'a, S: Find> TreeScanCtx<'a, S> {
         head: &'a HeadSnapshot,
         interner: &'a mut PathInterner,
         initial_seen_blobs: FxHashSet<ObjectId>,
+        initial_seen_trees: FxHashSet<(ObjectId, PathId)>,
     ) -> Self {
         Self {
             odb,
             pack,
             head,
             interner,
+            scope: None,
             buf_pool: BufferPool::new(),
-            seen_trees: FxHashSet::default(),
+            diffed: 0,
+            seen_trees: initial_seen_trees,
+            incomplete_trees: FxHashSet::default(),
             seen_blobs: initial_seen_blobs,
+            range_known: FxHashSet::default(),
             seen_path_blobs: FxHashSet::default(),
             out: DeltaBuilder::new(),
+            taken_blobs: 0,
         }
     }
 
+    /// Walk only the sub-path `scope` (see `ScanOptions::path`)
+    pub fn with_scope(mut self, scope: Option<&'a [u8]>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Leave out the blobs of the commits a range grows from (see
+    /// `ScanOptions::range`)
+    pub fn with_range_known(mut self, blobs: FxHashSet<ObjectId>) -> Self {
+        self.range_known = blobs;
+        self
+    }
+
     /// Scan a single commit's tree
-    pub fn scan_commit(&mut self, commit: &CommitInfo) {
+    ///
+    /// With `parent_tree`, the tree of a parent whose entries were all
+    /// recorded, entries the parent has unchanged are skipped.
+    pub fn scan_commit(&mut self, commit: &CommitInfo, parent_tree: Option<ObjectId>) {
         let mut path_buf = Vec::with_capacity(256);
-        self.scan_tree(commit.tree, &mut path_buf, commit);
+        if parent_tree.is_some() {
+            self.diffed += 1;
+        }
+        self.scan_tree(commit.tree, parent_tree, &mut path_buf, commit);
     }
 
-    /// Recursive tree scanning
-    fn scan_tree(&mut self, tree_oid: ObjectId, path: &mut Vec<u8>, commit: &CommitInfo) {
+    /// Recursive tree scanning; returns whether every object below was found
+    ///
+    /// `parent_oid` is the tree at the same path in the parent commit.
+    fn scan_tree(
+        &mut self,
+        tree_oid: ObjectId,
+        parent_oid: Option<ObjectId>,
+        path: &mut Vec<u8>,
+        commit: &CommitInfo,
+    ) -> bool {
         // Check if we've seen this (tree_oid, path) combination
         let path_id = self.interner.intern(path);
-        if !self.seen_trees.insert((tree_oid, path_id)) {
-            return;
+        let key = (tree_oid, path_id);
+        if !self.seen_trees.insert(key) {
+            // A tree seen before can still lack entries the parent had, e.g.
+            // when a file added in between is deleted again
+            if let Some(parent_oid) = parent_oid {
+                self.diff_removals(tree_oid, parent_oid, path, commit);
+            }
+            return !self.incomplete_trees.contains(&key);
         }
 
         // Get a buffer from the pool
@@ -151,38 +279,145 @@ impl<'a, S: Find> TreeScanCtx<'a, S> {
 
         let tree = match self.odb.find_tree(&tree_oid, &mut buf) {
             Ok(t) => t,
-            Err(_) => {
+            Err(e) => {
                 self.buf_pool.give(buf);
-                return;
+                self.incomplete_trees.insert(key);
+                self.out.record_skipped(tree_oid, SkippedKind::Tree, path, e.to_string());
+                return false;
             }
         };
 
+        // The parent's entries, if readable; both lists are in git's tree order
+        let mut parent_buf = self.buf_pool.take();
+        let parent_entries = match parent_oid.map(|oid| self.odb.find_tree(&oid, &mut parent_buf)) {
+            Some(Ok(parent)) => parent.entries,
+            _ => Vec::new(),
+        };
+        let mut parent_entries = parent_entries.iter().peekable();
+
         let base_len = path.len();
+        let mut complete = true;
 
         for entry in tree.entries.iter() {
+            let mut previous = None;
+            while let Some(candidate) = parent_entries.peek() {
+                match tree_order(candidate, entry) {
+                    Ordering::Less => {
+                        self.record_removed(candidate, path, commit);
+                        parent_entries.next();
+                    }
+                    Ordering::Equal => {
+                        previous = parent_entries.next();
+                        break;
+                    }
+                    Ordering::Greater => break,
+                }
+            }
+
             // Build path
             if !path.is_empty() {
                 path.push(b'/');
             }
             path.extend_from_slice(entry.filename.as_ref());
+            if self.scope.is_some_and(|scope| !within_scope(scope, path)) {
+                path.truncate(base_len);
+                continue;
+            }
 
             let oid = entry.oid.to_owned();
 
-            if entry.mode.is_blob() {
-                self.handle_blob(oid, path, commit);
+            if previous.is_some_and(|p| p.oid == entry.oid) {
+                // Recorded with the parent; only a gap below it carries over
+                if entry.mode.is_tree() && !self.incomplete_trees.is_empty() {
+                    let key = (oid, self.interner.intern(path));
+                    complete &= !self.incomplete_trees.contains(&key);
+                }
+            } else if entry.mode.is_blob_or_symlink() {
+                self.handle_blob(oid, path, entry_kind(entry.mode), commit);
+            } else if entry.mode.is_commit() {
+                self.handle_gitlink(oid, path);
             } else if entry.mode.is_tree() {
-                self.scan_tree(oid, path, commit);
+                let previous = previous.filter(|p| p.mode.is_tree()).map(|p| p.oid.to_owned());
+                complete &= self.scan_tree(oid, previous, path, commit);
             }
 
             // Restore path
             path.truncate(base_len);
         }
+        for removed in parent_entries {
+            self.record_removed(removed, path, commit);
+        }
+
+        self.buf_pool.give(parent_buf);
+        self.buf_pool.give(buf);
+        if complete {
+            self.out.record_tree(tree_oid, path_id);
+        } else {
+            self.incomplete_trees.insert(key);
+        }
+        complete
+    }
 
+    /// Record the parent's entries `tree_oid` lacks, at `path` and below,
+    /// without indexing anything else
+    fn diff_removals(&mut self, tree_oid: ObjectId, parent_oid: ObjectId, path: &mut Vec<u8>, commit: &CommitInfo) {
+        let mut buf = self.buf_pool.take();
+        let mut parent_buf = self.buf_pool.take();
+        if let Ok(tree) = self.odb.find_tree(&tree_oid, &mut buf)
+            && let Ok(parent) = self.odb.find_tree(&parent_oid, &mut parent_buf)
+        {
+            let base_len = path.len();
+            let mut entries = tree.entries.iter().peekable();
+            for previous in parent.entries.iter() {
+                while entries.next_if(|entry| tree_order(entry, previous) == Ordering::Less).is_some() {}
+                let Some(entry) = entries.next_if(|entry| tree_order(entry, previous) == Ordering::Equal) else {
+                    self.record_removed(previous, path, commit);
+                    continue;
+                };
+                if entry.oid == previous.oid || !entry.mode.is_tree() {
+                    continue;
+                }
+                if !path.is_empty() {
+                    path.push(b'/');
+                }
+                path.extend_from_slice(entry.filename.as_ref());
+                if self.scope.is_none_or(|scope| within_scope(scope, path)) {
+                    self.diff_removals(entry.oid.to_owned(), previous.oid.to_owned(), path, commit);
+                }
+                path.truncate(base_len);
+            }
+        }
+        self.buf_pool.give(parent_buf);
         self.buf_pool.give(buf);
     }
 
+    /// Record `entry` of the parent's tree at `dir` as removed by `commit`
+    fn record_removed(&mut self, entry: &EntryRef<'_>, dir: &mut Vec<u8>, commit: &CommitInfo) {
+        let base_len = dir.len();
+        if !dir.is_empty() {
+            dir.push(b'/');
+        }
+        dir.extend_from_slice(entry.filename.as_ref());
+        if self.scope.is_none_or(|scope| within_scope(scope, dir)) {
+            let path_id = self.interner.intern(dir);
+            self.out.record_removal(path_id, commit);
+        }
+        dir.truncate(base_len);
+    }
+
+    /// Handle a submodule entry; its commit belongs to another repository
+    fn handle_gitlink(&mut self, oid: ObjectId, path: &[u8]) {
+        let path_id = self.interner.intern(path);
+        if self.seen_path_blobs.insert((path_id, oid)) {
+            self.out.record_gitlink(path_id);
+        }
+    }
+
     /// Handle a blob entry
-    fn handle_blob(&mut self, oid: ObjectId, path: &[u8], commit: &CommitInfo) {
+    fn handle_blob(&mut self, oid: ObjectId, path: &[u8], kind: EntryKind, commit: &CommitInfo) {
+        if self.range_known.contains(&oid) {
+            return;
+        }
         let path_id = self.interner.intern(path);
 
         // Check if we've seen this (path, oid) combination
@@ -191,24 +426,62 @@ impl<'a, S: Find> TreeScanCtx<'a, S> {
         }
 
         let is_new_blob = self.seen_blobs.insert(oid);
-        let size = self.pack.size_of(oid);
+        let size = match self.pack.lookup(oid) {
+            Some(size) => size as i64,
+            None => {
+                if is_new_blob {
+                    self.out.record_skipped(oid, SkippedKind::Size, path, UNKNOWN_SIZE.to_string());
+                }
+                0
+            }
+        };
 
         // Check if this blob is at this path in HEAD
         let current_size = self
             .head
             .blobs_by_path
             .get(&path_id)
-            .filter(|(head_oid, _)| *head_oid == oid)
-            .map(|(_, s)| *s)
+            .filter(|head_blob| head_blob.oid == oid)
+            .map(|head_blob| head_blob.size)
             .unwrap_or(0);
 
         self.out
-            .record_blob(oid, path_id, size, current_size, commit, is_new_blob);
+            .record_blob(oid, path_id, kind, size, current_size, commit, is_new_blob);
+    }
+
+    /// Record a commit that could not be read or parsed
+    pub fn skip_commit(&mut self, oid: ObjectId, reason: String) {
+        self.out.record_skipped(oid, SkippedKind::Commit, b"", reason);
+    }
+
+    /// Hand over everything recorded so far, starting a new delta
+    pub fn take_delta(&mut self) -> ScanDelta {
+        self.taken_blobs += self.out.blob_count();
+        std::mem::take(&mut self.out).build()
+    }
+
+    /// Rows recorded since the last `take_delta`
+    pub fn pending_rows(&self) -> usize {
+        self.out.row_count()
+    }
+
+    /// Approximate memory the rows recorded since the last `take_delta` hold
+    pub fn pending_bytes(&self) -> usize {
+        self.out.heap_bytes()
+    }
+
+    /// Approximate memory of the sets of trees and blobs already seen
+    pub fn seen_bytes(&self) -> usize {
+        set_bytes(&self.seen_trees)
+            + set_bytes(&self.incomplete_trees)
+            + set_bytes(&self.seen_blobs)
+            + set_bytes(&self.range_known)
+            + set_bytes(&self.seen_path_blobs)
     }
 
-    /// Finish scanning and return the delta
-    pub fn finish(self) -> ScanDelta {
-        self.out.build()
+    /// Number of distinct blobs seen, in earlier runs or this one
+    pub fn blobs_seen(&self) -> usize {
+        self.seen_blobs.len()
     }
 
     /// Number of trees visited
@@ -216,8 +489,81 @@ impl<'a, S: Find> TreeScanCtx<'a, S> {
         self.seen_trees.len()
     }
 
+    /// Number of commits walked alongside a parent's tree
+    pub fn commits_diffed(&self) -> usize {
+        self.diffed
+    }
+
     /// Number of blobs found
     pub fn blobs_found(&self) -> usize {
-        self.out.blob_count()
+        self.taken_blobs + self.out.blob_count()
+    }
+}
+
+/// Records the entries of a breadth-first tree walk, pruning everything
+/// outside the scanned sub-path
+pub struct ScopedRecorder<'a> {
+    inner: Recorder,
+    scope: Option<&'a [u8]>,
+}
+
+impl<'a> ScopedRecorder<'a> {
+    pub fn new(scope: Option<&'a [u8]>) -> Self {
+        Self { inner: Recorder::default(), scope }
+    }
+
+    pub fn into_records(self) -> Vec<gix::traverse::tree::recorder::Entry> {
+        self.inner.records
+    }
+
+    fn in_scope(&self) -> bool {
+        self.scope.is_none_or(|scope| within_scope(scope, self.inner.path()))
+    }
+}
+
+impl Visit for ScopedRecorder<'_> {
+    fn pop_back_tracked_path_and_set_current(&mut self) {
+        self.inner.pop_back_tracked_path_and_set_current();
+    }
+
+    fn pop_front_tracked_path_and_set_current(&mut self) {
+        self.inner.pop_front_tracked_path_and_set_current();
+    }
+
+    fn push_back_tracked_path_component(&mut self, component: &BStr) {
+        self.inner.push_back_tracked_path_component(component);
+    }
+
+    fn push_path_component(&mut self, component: &BStr) {
+        self.inner.push_path_component(component);
+    }
+
+    fn pop_path_component(&mut self) {
+        self.inner.pop_path_component();
+    }
+
+    fn visit_tree(&mut self, entry: &EntryRef<'_>) -> Action {
+        if !self.in_scope() {
+            return Action::Skip;
+        }
+        self.inner.visit_tree(entry)
     }
+
+    fn visit_nontree(&mut self, entry: &EntryRef<'_>) -> Action {
+        if !self.in_scope() {
+            return Action::Continue;
+        }
+        self.inner.visit_nontree(entry)
+    }
+}
+
+/// Git's tree entry order: by name, with a tree sorting as if its name
+/// ended in '/'
+fn tree_order(a: &EntryRef<'_>, b: &EntryRef<'_>) -> Ordering {
+    let common = a.filename.len().min(b.filename.len());
+    a.filename[..common].cmp(&b.filename[..common]).then_with(|| {
+        let a = a.filename.get(common).or_else(|| a.mode.is_tree().then_some(&b'/'));
+        let b = b.filename.get(common).or_else(|| b.mode.is_tree().then_some(&b'/'));
+        a.cmp(&b)
+    })
 }
@@ -4,6 +4,8 @@
 
 use gix::ObjectId;
 
+use crate::model::EntryKind;
+
 /// Interned path identifier to avoid String allocations
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct PathId(pub u32);
@@ -18,11 +20,14 @@ impl PathId {
 /// Information about a commit being scanned
 #[derive(Debug, Clone)]
 pub struct CommitInfo {
-    #[allow(dead_code)]
     pub oid: ObjectId,
     pub tree: ObjectId,
+    pub parents: Vec<ObjectId>,
     pub author: String,
+    pub email: String,
     pub timestamp: i64,
+    /// First paragraph of the message, as `git log --oneline` shows it
+    pub subject: String,
 }
 
 /// A blob record to be saved to the database
@@ -32,6 +37,7 @@ pub struct BlobRow {
     pub path_id: PathId,
     pub cumulative_size: i64,
     pub current_size: i64,
+    pub kind: EntryKind,
 }
 
 /// Metadata about a blob (first author, first commit date)
@@ -41,17 +47,100 @@ pub struct BlobMetaRow {
     pub size: i64,
     pub path_id: PathId,
     pub author: String,
+    pub email: String,
+    pub timestamp: i64,
+    /// Commit that introduced the blob, and its subject
+    pub commit: ObjectId,
+    pub subject: String,
+}
+
+/// A distinct (blob, path) pair seen during scanning
+#[derive(Debug, Clone)]
+pub struct BlobPathRow {
+    pub oid: ObjectId,
+    pub path_id: PathId,
+}
+
+/// A tree fully indexed at a path; the same tree at another path is not
+#[derive(Debug, Clone)]
+pub struct TreePathRow {
+    pub oid: ObjectId,
+    pub path_id: PathId,
+}
+
+/// A path gone from a commit's tree that its parent's tree had: deleted,
+/// or renamed away
+#[derive(Debug, Clone, Copy)]
+pub struct RemovalRow {
+    pub path_id: PathId,
+    /// Commit date
     pub timestamp: i64,
 }
 
+/// Kind of object a scan had to skip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkippedKind {
+    Commit,
+    Tree,
+    /// A blob in no readable pack nor loose, counted as 0 bytes
+    Size,
+    /// A pack that could not be read, under its checksum
+    Pack,
+}
+
+impl SkippedKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkippedKind::Commit => "commit",
+            SkippedKind::Tree => "tree",
+            SkippedKind::Size => "size",
+            SkippedKind::Pack => "pack",
+        }
+    }
+}
+
+/// An object that could not be read, so its contents are missing from the
+/// scan, or whose size could not be found, or a pack that could not be read
+#[derive(Debug, Clone)]
+pub struct SkippedObject {
+    pub oid: ObjectId,
+    pub kind: SkippedKind,
+    /// Where the scan met it: a tree's or blob's first path, a pack's file
+    /// name; empty for commits
+    pub path: String,
+    pub reason: String,
+}
+
 /// Snapshot of HEAD tree for determining "current" files
 #[derive(Debug, Default)]
 pub struct HeadSnapshot {
     /// HEAD commit OID as hex string (kept for debugging/display)
     #[allow(dead_code)]
     pub head_oid_hex: String,
-    /// Maps path_id -> (blob_oid, compressed_size)
-    pub blobs_by_path: rustc_hash::FxHashMap<PathId, (ObjectId, i64)>,
+    /// Maps path_id -> blob at that path
+    pub blobs_by_path: rustc_hash::FxHashMap<PathId, HeadBlob>,
+}
+
+/// Entry kind of a blob or gitlink tree entry
+pub fn entry_kind(mode: gix::object::tree::EntryMode) -> EntryKind {
+    if mode.is_link() {
+        EntryKind::Symlink
+    } else if mode.is_executable() {
+        EntryKind::Executable
+    } else if mode.is_commit() {
+        EntryKind::Gitlink
+    } else {
+        EntryKind::File
+    }
+}
+
+/// A blob in the HEAD tree
+#[derive(Debug, Clone, Copy)]
+pub struct HeadBlob {
+    pub oid: ObjectId,
+    /// Compressed size
+    pub size: i64,
+    pub kind: EntryKind,
 }
 
 /// The result of scanning commits - blob/metadata rows to persist
@@ -62,6 +151,17 @@ pub struct HeadSnapshot {
 pub struct ScanDelta {
     pub blobs: Vec<BlobRow>,
     pub metadata: Vec<BlobMetaRow>,
+    /// Every (blob, path) pair, including blobs already seen at other paths
+    pub occurrences: Vec<BlobPathRow>,
+    /// Trees whose whole subtree was indexed, so later scans can skip them
+    pub trees: Vec<TreePathRow>,
+    /// Paths that held a submodule commit
+    pub gitlinks: Vec<PathId>,
+    /// Paths removed by a scanned commit; a directory stands for everything
+    /// below it
+    pub removals: Vec<RemovalRow>,
+    /// Commits and trees that could not be read
+    pub skipped: Vec<SkippedObject>,
 }
 
 impl ScanDelta {
@@ -72,6 +172,12 @@ impl ScanDelta {
 
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
-        self.blobs.is_empty() && self.metadata.is_empty()
+        self.blobs.is_empty()
+            && self.metadata.is_empty()
+            && self.occurrences.is_empty()
+            && self.trees.is_empty()
+            && self.gitlinks.is_empty()
+            && self.removals.is_empty()
+            && self.skipped.is_empty()
     }
 }
@@ -0,0 +1,116 @@
+//! Virtual path sources
+//!
+//! Collects blobs that are reachable only from stash entries or the index.
+//! These are never persisted: they are recomputed on every run and grafted
+//! onto the loaded tree under synthetic path prefixes, so they can't skew
+//! the attribution of blobs that are later committed for real.
+
+use anyhow::Result;
+use gix::bstr::BStr;
+use gix::ObjectId;
+use rustc_hash::FxHashSet;
+
+use crate::model::Tree;
+
+use super::options::within_scope;
+use super::pack::PackSizeIndex;
+
+/// Synthetic top-level directory for blobs staged in the index
+pub const INDEX_PREFIX: &str = ":index:";
+
+/// A blob found only in the index or a stash entry
+#[derive(Debug, Clone)]
+pub struct VirtualBlob {
+    pub path: String,
+    pub size: u64,
+}
+
+/// Collect blobs from the index and all stash entries that are not in `seen`,
+/// only those under `scope` when the scan is limited to a sub-path
+///
+/// Each blob OID is reported once, under the first source it was found in
+/// (index first, then `stash@{0}`, `stash@{1}`, ...).
+pub fn collect_virtual_blobs(
+    repo: &gix::Repository,
+    pack: &PackSizeIndex,
+    seen: &FxHashSet<ObjectId>,
+    scope: Option<&[u8]>,
+) -> Result<Vec<VirtualBlob>> {
+    let mut claimed: FxHashSet<ObjectId> = FxHashSet::default();
+    let mut out = Vec::new();
+
+    let mut push = |oid: ObjectId, source: &str, path: &BStr| {
+        if scope.is_some_and(|scope| !within_scope(scope, path)) {
+            return;
+        }
+        if !seen.contains(&oid) && claimed.insert(oid) {
+            out.push(VirtualBlob {
+                path: format!("{}/{}", source, path),
+                size: pack.size_of(oid) as u64,
+            });
+        }
+    };
+
+    // Index entries (bare repositories simply have an empty index)
+    let index = repo.index_or_empty()?;
+    for entry in index.entries() {
+        if entry.mode.is_submodule() || entry.mode.is_sparse() {
+            continue;
+        }
+        push(entry.id, INDEX_PREFIX, entry.path(&index));
+    }
+
+    // Stash entries: the stash commit's tree is the worktree state, its
+    // second parent holds the index state and the optional third parent
+    // holds untracked files.
+    for (n, stash_oid) in stash_commits(repo)?.into_iter().enumerate() {
+        let prefix = format!("stash@{{{}}}", n);
+        let commit = repo.find_commit(stash_oid)?;
+
+        let mut trees = vec![commit.tree_id()?.detach()];
+        for parent in commit.parent_ids().skip(1) {
+            trees.push(repo.find_commit(parent)?.tree_id()?.detach());
+        }
+
+        for tree_id in trees {
+            let mut recorder = gix::traverse::tree::Recorder::default();
+            repo.find_tree(tree_id)?.traverse().breadthfirst(&mut recorder)?;
+            for entry in recorder.records {
+                if entry.mode.is_blob() {
+                    push(entry.oid, &prefix, entry.filepath.as_ref());
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Stash commit OIDs, newest first (so position N is `stash@{N}`)
+fn stash_commits(repo: &gix::Repository) -> Result<Vec<ObjectId>> {
+    let Some(stash_ref) = repo.try_find_reference("refs/stash")? else {
+        return Ok(Vec::new());
+    };
+
+    let mut platform = stash_ref.log_iter();
+    let mut oids = Vec::new();
+    if let Some(lines) = platform.all()? {
+        for line in lines {
+            oids.push(line?.new_oid());
+        }
+    }
+    oids.reverse();
+    Ok(oids)
+}
+
+/// Add virtual blobs to a loaded tree and recompute its totals
+pub fn graft(root: &mut Tree, blobs: &[VirtualBlob]) {
+    if blobs.is_empty() {
+        return;
+    }
+    for blob in blobs {
+        let parts: Vec<&str> = blob.path.split('/').collect();
+        root.add_path_with_sizes(&parts, blob.size, 0, 1);
+    }
+    root.compute_totals();
+}
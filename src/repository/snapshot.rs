@@ -0,0 +1,311 @@
+//! Portable index snapshots (`repodiet export-index` / `import-index`)
+//!
+//! A snapshot holds the scan data of an index, not the SQLite file, so it
+//! can be attached to a ticket or moved between machines whatever SQLite
+//! version each side links. It is gzip-compressed JSON Lines: a header
+//! naming the format, its version and the index schema, then each table as
+//! a line naming it and its columns followed by one JSON array per row.
+//! Object IDs and other binary columns are written as hex.
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use super::database::Database;
+use super::SCHEMA_VERSION;
+
+/// `format` in the header of every snapshot
+pub const SNAPSHOT_FORMAT: &str = "repodiet-index";
+
+/// Bumped when the layout of a snapshot changes; readers refuse newer ones
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// How a column is stored in SQLite
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum ColumnKind {
+    Integer,
+    Text,
+    Blob,
+}
+
+/// A table a snapshot carries, with the columns it writes in order
+#[derive(Debug)]
+pub(super) struct TableSpec {
+    pub name: &'static str,
+    pub columns: &'static [(&'static str, ColumnKind)],
+}
+
+/// One value of a row
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Cell {
+    Integer(i64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+use ColumnKind::{Blob, Integer, Text};
+
+/// Every table of the index; must follow `Database::create_tables`
+pub(super) const TABLES: &[TableSpec] = &[
+    TableSpec {
+        name: "paths",
+        columns: &[
+            ("path", Text),
+            ("cumulative_size", Integer),
+            ("current_size", Integer),
+            ("blob_count", Integer),
+            ("kind", Integer),
+            ("extension", Text),
+            ("vendored", Text),
+            ("parent", Text),
+        ],
+    },
+    TableSpec {
+        name: "dirs",
+        columns: &[
+            ("path", Text),
+            ("parent", Text),
+            ("cumulative_size", Integer),
+            ("current_size", Integer),
+            ("blob_count", Integer),
+            ("deleted_size", Integer),
+        ],
+    },
+    TableSpec { name: "seen_blobs", columns: &[("oid", Blob)] },
+    TableSpec { name: "scanned_commits", columns: &[("oid", Blob)] },
+    TableSpec {
+        name: "blobs",
+        columns: &[
+            ("oid", Blob),
+            ("size", Integer),
+            ("path", Text),
+            ("first_author", Text),
+            ("first_email", Text),
+            ("first_date", Integer),
+            ("first_subject", Text),
+        ],
+    },
+    TableSpec { name: "path_ids", columns: &[("id", Integer), ("path", Text), ("raw", Blob)] },
+    TableSpec { name: "blob_paths", columns: &[("oid", Blob), ("path_id", Integer)] },
+    TableSpec {
+        name: "head_blobs",
+        columns: &[("path", Text), ("oid", Blob), ("size", Integer), ("kind", Integer)],
+    },
+    TableSpec { name: "path_history", columns: &[("path", Text), ("quarter", Integer), ("size", Integer)] },
+    TableSpec {
+        name: "commits",
+        columns: &[
+            ("oid", Blob),
+            ("author", Text),
+            ("email", Text),
+            ("date", Integer),
+            ("subject", Text),
+            ("added_size", Integer),
+            ("blob_count", Integer),
+            ("largest_path", Text),
+            ("largest_size", Integer),
+        ],
+    },
+    TableSpec { name: "seen_trees", columns: &[("oid", Blob), ("path_id", Integer)] },
+    TableSpec { name: "path_removals", columns: &[("path_id", Integer), ("date", Integer)] },
+    TableSpec {
+        name: "scan_diagnostics",
+        columns: &[("oid", Blob), ("kind", Text), ("path", Text), ("reason", Text)],
+    },
+    TableSpec {
+        name: "content_kinds",
+        columns: &[("extension", Text), ("path", Text), ("binary", Integer)],
+    },
+    TableSpec { name: "bookmarks", columns: &[("path", Text)] },
+    TableSpec {
+        name: "size_snapshots",
+        columns: &[
+            ("scope", Text),
+            ("taken_at", Integer),
+            ("head", Text),
+            ("cumulative_size", Integer),
+            ("current_size", Integer),
+            ("deleted_size", Integer),
+            ("blob_count", Integer),
+            ("largest_blob", Integer),
+        ],
+    },
+    TableSpec {
+        name: "size_snapshot_entries",
+        columns: &[
+            ("scope", Text),
+            ("taken_at", Integer),
+            ("kind", Text),
+            ("name", Text),
+            ("cumulative_size", Integer),
+            ("current_size", Integer),
+        ],
+    },
+    // The schema version travels in the header instead
+    TableSpec { name: "metadata", columns: &[("key", Text), ("value", Text)] },
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+    schema: String,
+    #[serde(default)]
+    generator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TableHeader {
+    table: String,
+    columns: Vec<String>,
+    rows: u64,
+}
+
+impl Cell {
+    fn to_json(&self) -> Value {
+        match self {
+            Cell::Integer(value) => Value::from(*value),
+            Cell::Text(value) => Value::from(value.as_str()),
+            Cell::Blob(value) => Value::from(hex::encode(value)),
+        }
+    }
+
+    fn from_json(value: &Value, kind: ColumnKind) -> Option<Self> {
+        match kind {
+            Integer => value.as_i64().map(Cell::Integer),
+            Text => value.as_str().map(|s| Cell::Text(s.to_string())),
+            Blob => value.as_str().and_then(|s| hex::decode(s).ok()).map(Cell::Blob),
+        }
+    }
+}
+
+fn is_schema_version(table: &TableSpec, row: &[Cell]) -> bool {
+    table.name == "metadata" && row.first() == Some(&Cell::Text("schema_version".into()))
+}
+
+impl Database {
+    /// Write every table of the index to `out` as a snapshot; returns the
+    /// number of rows written
+    pub async fn export_snapshot<W: Write>(&self, out: W) -> Result<u64> {
+        let tables = self.dump_tables(TABLES).await?;
+
+        let mut out = GzEncoder::new(out, Compression::default());
+        let header = Header {
+            format: SNAPSHOT_FORMAT.to_string(),
+            version: SNAPSHOT_VERSION,
+            schema: SCHEMA_VERSION.to_string(),
+            generator: format!("repodiet {}", env!("CARGO_PKG_VERSION")),
+        };
+        write_line(&mut out, &header)?;
+
+        let mut written = 0;
+        for (table, rows) in TABLES.iter().zip(&tables) {
+            let rows: Vec<&Vec<Cell>> = rows.iter().filter(|row| !is_schema_version(table, row)).collect();
+            write_line(&mut out, &TableHeader {
+                table: table.name.to_string(),
+                columns: table.columns.iter().map(|(name, _)| name.to_string()).collect(),
+                rows: rows.len() as u64,
+            })?;
+            written += rows.len() as u64;
+            for row in rows {
+                let values: Vec<Value> = row.iter().map(Cell::to_json).collect();
+                write_line(&mut out, &values)?;
+            }
+        }
+
+        out.finish()?.flush()?;
+        Ok(written)
+    }
+
+    /// Replace the scan data with the snapshot read from `input`; returns
+    /// the number of rows read
+    ///
+    /// Nothing changes unless the whole snapshot is valid. Bookmarks already
+    /// in the index are kept alongside the snapshot's.
+    pub async fn import_snapshot<R: Read>(&self, input: R) -> Result<u64> {
+        let mut lines = BufReader::new(GzDecoder::new(input)).lines().enumerate();
+        let mut next_line = || -> Result<Option<(usize, String)>> {
+            match lines.next() {
+                Some((index, line)) => Ok(Some((index + 1, line.context("Not a repodiet index snapshot")?))),
+                None => Ok(None),
+            }
+        };
+
+        let (_, line) = next_line()?.context("The snapshot is empty")?;
+        let header: Header = serde_json::from_str(&line).context("Not a repodiet index snapshot")?;
+        if header.format != SNAPSHOT_FORMAT {
+            bail!("Not a repodiet index snapshot (format '{}')", header.format);
+        }
+        if header.version > SNAPSHOT_VERSION {
+            bail!(
+                "The snapshot has format version {}, this version of repodiet reads up to {}",
+                header.version,
+                SNAPSHOT_VERSION
+            );
+        }
+        if header.schema != SCHEMA_VERSION {
+            bail!(
+                "The snapshot was exported by a different version of repodiet \
+                 (index schema {}, this version reads {}); scan the repository instead",
+                header.schema,
+                SCHEMA_VERSION
+            );
+        }
+
+        let mut tables: Vec<Option<Vec<Vec<Cell>>>> = vec![None; TABLES.len()];
+        let mut read = 0;
+        while let Some((number, line)) = next_line()? {
+            let table_header: TableHeader = serde_json::from_str(&line)
+                .with_context(|| format!("Line {}: expected a table header", number))?;
+            let index = TABLES
+                .iter()
+                .position(|t| t.name == table_header.table)
+                .with_context(|| format!("Line {}: unknown table '{}'", number, table_header.table))?;
+            let table = &TABLES[index];
+            if tables[index].is_some() {
+                bail!("Line {}: table '{}' appears twice", number, table.name);
+            }
+            if !table.columns.iter().map(|(name, _)| *name).eq(table_header.columns.iter().map(String::as_str)) {
+                bail!("Line {}: unexpected columns for table '{}'", number, table.name);
+            }
+
+            let mut rows = Vec::new();
+            for _ in 0..table_header.rows {
+                let (number, line) = next_line()?
+                    .with_context(|| format!("The snapshot ends inside table '{}'", table.name))?;
+                let values: Vec<Value> =
+                    serde_json::from_str(&line).with_context(|| format!("Line {}: expected a row", number))?;
+                if values.len() != table.columns.len() {
+                    bail!("Line {}: expected {} values, found {}", number, table.columns.len(), values.len());
+                }
+                let row = values
+                    .iter()
+                    .zip(table.columns)
+                    .map(|(value, (column, kind))| {
+                        Cell::from_json(value, *kind)
+                            .with_context(|| format!("Line {}: invalid {} '{}'", number, column, value))
+                    })
+                    .collect::<Result<Vec<Cell>>>()?;
+                if !is_schema_version(table, &row) {
+                    rows.push(row);
+                }
+            }
+            read += table_header.rows;
+            tables[index] = Some(rows);
+        }
+
+        let tables: Vec<Vec<Vec<Cell>>> = tables.into_iter().map(Option::unwrap_or_default).collect();
+        self.restore_tables(TABLES, &tables).await?;
+        Ok(read)
+    }
+}
+
+fn write_line<W: Write>(out: &mut W, value: &impl Serialize) -> Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Which repository a path belongs to, and what is checked out there
+//!
+//! Every worktree of a repository (`git worktree add`) shares one object
+//! database and history, so caches are keyed by the common git dir rather
+//! than by the checkout that happened to be scanned.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The repository behind a checkout
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkout {
+    /// Canonical git dir shared by all worktrees
+    pub common_dir: PathBuf,
+    /// Name of the repository: the main worktree's directory, or a bare
+    /// repository's directory without `.git`
+    pub name: String,
+    /// Directory name of the linked worktree, when this isn't the main one
+    pub linked_worktree: Option<String>,
+    /// Checked out branch, or `None` with a detached HEAD
+    pub branch: Option<String>,
+    /// Abbreviated HEAD commit, if there is one
+    pub head: Option<String>,
+}
+
+impl Checkout {
+    /// Open the repository at `repo_path` (a worktree or git dir)
+    pub fn open(repo_path: &Path) -> Result<Self> {
+        let repo = gix::open(repo_path)
+            .with_context(|| format!("Failed to open git repository: {}", repo_path.display()))?;
+        let common_dir = fs::canonicalize(repo.common_dir())
+            .with_context(|| format!("Could not resolve path: {}", repo.common_dir().display()))?;
+        let linked_worktree = match repo.kind() {
+            gix::repository::Kind::WorkTree { is_linked: true } => {
+                repo.workdir().and_then(|dir| dir.file_name()).map(|n| n.to_string_lossy().into_owned())
+            }
+            _ => None,
+        };
+        let branch = repo.head_name().ok().flatten().map(|name| name.shorten().to_string());
+        let head = repo.head_id().ok().map(|id| id.shorten_or_id().to_string());
+        Ok(Self {
+            name: repo_name(&common_dir),
+            common_dir,
+            linked_worktree,
+            branch,
+            head,
+        })
+    }
+
+    /// "worktree feature-x on fix/login", "on main" or "detached at 1a2b3c4"
+    pub fn label(&self) -> String {
+        let checked_out = match (&self.branch, &self.head) {
+            (Some(branch), _) => format!("on {}", branch),
+            (None, Some(head)) => format!("detached at {}", head),
+            (None, None) => "no commits".to_string(),
+        };
+        match &self.linked_worktree {
+            Some(worktree) => format!("worktree {} {}", worktree, checked_out),
+            None => checked_out,
+        }
+    }
+}
+
+/// `/src/app/.git` -> "app", `/srv/app.git` -> "app"
+fn repo_name(common_dir: &Path) -> String {
+    let dir = if common_dir.file_name().is_some_and(|n| n == ".git") {
+        common_dir.parent().unwrap_or(common_dir)
+    } else {
+        common_dir
+    };
+    let name = dir.file_name().and_then(|n| n.to_str()).unwrap_or("repo");
+    name.strip_suffix(".git").filter(|n| !n.is_empty()).unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_name() {
+        assert_eq!(repo_name(Path::new("/src/app/.git")), "app");
+        assert_eq!(repo_name(Path::new("/srv/app.git")), "app");
+        assert_eq!(repo_name(Path::new("/srv/mirror")), "mirror");
+    }
+
+    #[test]
+    fn test_label() {
+        let mut checkout = Checkout {
+            common_dir: PathBuf::from("/src/app/.git"),
+            name: "app".into(),
+            linked_worktree: None,
+            branch: Some("main".into()),
+            head: Some("1a2b3c4".into()),
+        };
+        assert_eq!(checkout.label(), "on main");
+        checkout.linked_worktree = Some("app-hotfix".into());
+        checkout.branch = None;
+        assert_eq!(checkout.label(), "worktree app-hotfix detached at 1a2b3c4");
+    }
+}
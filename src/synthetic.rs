@@ -0,0 +1,351 @@
+//! Generated repositories for tests and benchmarks (`test-support` feature)
+//!
+//! A `RepoShape` says how many commits to write, how wide and deep the
+//! directory tree grows, how large binary files are and how often merges,
+//! deletions and renames happen. The same shape and seed always give the same
+//! history, so a failing case can be reproduced from its shape alone.
+//!
+//! Objects are written loose, so the on-disk size of a blob is the size of
+//! its object file, which is what a scan counts.
+
+use anyhow::{Context, Result};
+use git2::{Index, IndexEntry, IndexTime, Oid, Repository, Signature, Time};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Author time of the first commit; each next one is a day later
+const START_TIME: i64 = 1_600_000_000;
+
+const AUTHORS: [&str; 3] = ["Alice", "Bob", "Carol"];
+
+/// What a generated history looks like
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoShape {
+    /// Commits on the first-parent line; each merge brings one more from a
+    /// side branch
+    pub commits: usize,
+    /// Subdirectories a directory can have
+    pub width: usize,
+    /// Directory levels below the root that files are spread over
+    pub depth: usize,
+    /// Files added or modified by each commit
+    pub files_per_commit: usize,
+    /// Bytes of each binary file, random so they do not compress
+    pub binary_size: usize,
+    /// Every Nth new file is binary; 0 for none
+    pub binary_every: usize,
+    /// Every Nth commit merges a side branch adding one file; 0 for none
+    pub merge_every: usize,
+    /// Every Nth commit also deletes a file; 0 for none
+    pub delete_every: usize,
+    /// Every Nth commit also renames a file, keeping its content; 0 for none
+    pub rename_every: usize,
+    pub seed: u64,
+}
+
+impl Default for RepoShape {
+    fn default() -> Self {
+        Self {
+            commits: 50,
+            width: 4,
+            depth: 3,
+            files_per_commit: 5,
+            binary_size: 64 * 1024,
+            binary_every: 10,
+            merge_every: 10,
+            delete_every: 4,
+            rename_every: 6,
+            seed: 0,
+        }
+    }
+}
+
+/// SplitMix64, enough to vary a history and stable across platforms
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// In `0..n`; `n` must not be 0
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len + 8);
+        while bytes.len() < len {
+            bytes.extend_from_slice(&self.next().to_le_bytes());
+        }
+        bytes.truncate(len);
+        bytes
+    }
+}
+
+/// A repository in a temporary directory, written from a `RepoShape`, that
+/// knows what a scan of it should find
+pub struct SyntheticRepo {
+    dir: TempDir,
+    repo: Repository,
+    shape: RepoShape,
+    rng: Rng,
+    /// Files of HEAD, by path
+    files: BTreeMap<String, Oid>,
+    /// On-disk size of every blob written, committed or not
+    written: BTreeMap<Oid, u64>,
+    /// Blobs of the commits written, all reachable from HEAD
+    blobs: BTreeMap<Oid, u64>,
+    head: Option<Oid>,
+    /// Commits written, side branches included
+    commit_count: usize,
+    /// First-parent commits written, which `RepoShape`'s intervals count
+    mainline: usize,
+    /// Names handed out to files, so every new path is new
+    next_file: usize,
+}
+
+impl SyntheticRepo {
+    /// Write `shape.commits` commits to a new repository, with HEAD on `main`
+    pub fn generate(shape: RepoShape) -> Result<Self> {
+        let dir = TempDir::new().context("Could not create a directory for the repository")?;
+        let repo = Repository::init(dir.path()).context("Could not create the repository")?;
+        let commits = shape.commits;
+        let mut synthetic = Self {
+            dir,
+            repo,
+            rng: Rng(shape.seed),
+            shape,
+            files: BTreeMap::new(),
+            written: BTreeMap::new(),
+            blobs: BTreeMap::new(),
+            head: None,
+            commit_count: 0,
+            mainline: 0,
+            next_file: 0,
+        };
+        synthetic.extend(commits)?;
+        Ok(synthetic)
+    }
+
+    /// Write `commits` more commits on top of HEAD, the way the shape says,
+    /// e.g. to test an incremental scan
+    pub fn extend(&mut self, commits: usize) -> Result<()> {
+        for _ in 0..commits {
+            self.mainline += 1;
+            let n = self.mainline;
+            let side = if every(self.shape.merge_every, n) && self.head.is_some() { Some(self.side_branch()?) } else { None };
+
+            for _ in 0..self.shape.files_per_commit.max(1) {
+                if !self.files.is_empty() && self.rng.below(2) == 0 {
+                    let path = self.pick_file();
+                    self.write_file(path)?;
+                } else {
+                    let path = self.new_path();
+                    self.write_file(path)?;
+                }
+            }
+            if every(self.shape.delete_every, n) && self.files.len() > 1 {
+                let path = self.pick_file();
+                self.files.remove(&path);
+            }
+            if every(self.shape.rename_every, n) && !self.files.is_empty() {
+                let path = self.pick_file();
+                let oid = self.files.remove(&path).expect("picked from the files");
+                let renamed = self.new_path();
+                self.files.insert(renamed, oid);
+            }
+
+            let mut parents: Vec<Oid> = self.head.into_iter().collect();
+            if let Some((side, path, oid)) = side {
+                self.files.insert(path, oid);
+                parents.push(side);
+            }
+            let message = if parents.len() > 1 { format!("Merge side branch {n}") } else { format!("Commit {n}") };
+            let files = std::mem::take(&mut self.files);
+            let commit = self.commit(&files, &parents, &message)?;
+            self.files = files;
+            self.head = Some(commit);
+        }
+        if let Some(head) = self.head {
+            self.repo.reference("refs/heads/main", head, true, "synthetic")?;
+            self.repo.set_head("refs/heads/main")?;
+        }
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn repo(&self) -> &Repository {
+        &self.repo
+    }
+
+    pub fn shape(&self) -> &RepoShape {
+        &self.shape
+    }
+
+    /// Commits written so far, side branches included
+    pub fn commit_count(&self) -> usize {
+        self.commit_count
+    }
+
+    /// Files of HEAD with the on-disk size of their blobs, by path
+    pub fn head_files(&self) -> BTreeMap<String, u64> {
+        self.files.iter().map(|(path, oid)| (path.clone(), self.blobs[oid])).collect()
+    }
+
+    /// On-disk size of every blob in the history, by hex object id
+    pub fn blobs(&self) -> BTreeMap<String, u64> {
+        self.blobs.iter().map(|(oid, size)| (oid.to_string(), *size)).collect()
+    }
+
+    /// A commit off HEAD's parent adding one file, with that file
+    fn side_branch(&mut self) -> Result<(Oid, String, Oid)> {
+        let head = self.head.expect("a merge needs a commit to branch from");
+        let base = self.repo.find_commit(head)?.parent_ids().next().unwrap_or(head);
+        let mut files = self.tree_files(base)?;
+        let path = self.new_path();
+        let oid = self.blob_for(&path)?;
+        files.insert(path.clone(), oid);
+        let side = self.commit(&files, &[base], &format!("Side branch {}", self.mainline))?;
+        Ok((side, path, oid))
+    }
+
+    /// Files of `commit`'s tree, by path
+    fn tree_files(&self, commit: Oid) -> Result<BTreeMap<String, Oid>> {
+        let tree = self.repo.find_commit(commit)?.tree()?;
+        let mut files = BTreeMap::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                files.insert(format!("{}{}", dir, entry.name().unwrap_or_default()), entry.id());
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+        Ok(files)
+    }
+
+    fn pick_file(&mut self) -> String {
+        let index = self.rng.below(self.files.len());
+        self.files.keys().nth(index).expect("index below the file count").clone()
+    }
+
+    /// A path no file has had, `depth` levels down at most
+    fn new_path(&mut self) -> String {
+        self.next_file += 1;
+        let binary = every(self.shape.binary_every, self.next_file);
+        let levels = self.rng.below(self.shape.depth + 1);
+        let mut path = String::new();
+        for _ in 0..levels {
+            path += &format!("d{}/", self.rng.below(self.shape.width.max(1)));
+        }
+        path + &format!("f{}.{}", self.next_file, if binary { "bin" } else { "txt" })
+    }
+
+    /// Give `path` new content in HEAD's files
+    fn write_file(&mut self, path: String) -> Result<()> {
+        let oid = self.blob_for(&path)?;
+        self.files.insert(path, oid);
+        Ok(())
+    }
+
+    /// New content for `path`, written as a blob: random bytes for a binary
+    /// file, lines of text otherwise
+    fn blob_for(&mut self, path: &str) -> Result<Oid> {
+        let content = if path.ends_with(".bin") {
+            let mut bytes = self.rng.bytes(self.shape.binary_size.max(1));
+            bytes[0] = 0;
+            bytes
+        } else {
+            let lines = 5 + self.rng.below(50);
+            (0..lines).map(|_| format!("{path} line {}\n", self.rng.next())).collect::<String>().into_bytes()
+        };
+        let oid = self.repo.blob(&content)?;
+        let hex = oid.to_string();
+        let object = self.repo.path().join("objects").join(&hex[..2]).join(&hex[2..]);
+        let size = fs::metadata(&object).with_context(|| format!("Blob {} not written loose", hex))?.len();
+        self.written.insert(oid, size);
+        Ok(oid)
+    }
+
+    fn commit(&mut self, files: &BTreeMap<String, Oid>, parents: &[Oid], message: &str) -> Result<Oid> {
+        let mut index = Index::new()?;
+        for (path, oid) in files {
+            self.blobs.insert(*oid, self.written[oid]);
+            index.add(&IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: 0o100644,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: *oid,
+                flags: 0,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            })?;
+        }
+        let tree = self.repo.find_tree(index.write_tree_to(&self.repo)?)?;
+
+        let author = AUTHORS[self.commit_count % AUTHORS.len()];
+        let time = Time::new(START_TIME + self.commit_count as i64 * 86_400, 0);
+        let signature = Signature::new(author, &format!("{}@example.com", author.to_lowercase()), &time)?;
+        let parents = parents.iter().map(|oid| self.repo.find_commit(*oid)).collect::<Result<Vec<_>, _>>()?;
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        let commit = self.repo.commit(None, &signature, &signature, message, &tree, &parents)?;
+        self.commit_count += 1;
+        Ok(commit)
+    }
+}
+
+/// Whether the `n`th (from 1) is one of every `interval`th
+fn every(interval: usize, n: usize) -> bool {
+    interval > 0 && n.is_multiple_of(interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_shape_same_history() {
+        let shape = RepoShape { commits: 20, binary_size: 512, ..RepoShape::default() };
+        let a = SyntheticRepo::generate(shape.clone()).unwrap();
+        let b = SyntheticRepo::generate(shape.clone()).unwrap();
+        assert_eq!(a.repo().head().unwrap().target(), b.repo().head().unwrap().target());
+        // Two merges, each with its side commit
+        assert_eq!(a.commit_count(), 22);
+
+        let head = a.repo().head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 2);
+        assert_eq!(a.head_files().len(), a.tree_files(head.id()).unwrap().len());
+        assert!(a.head_files().keys().any(|path| path.ends_with(".bin")));
+        assert!(a.head_files().keys().all(|path| path.matches('/').count() <= shape.depth));
+
+        let other = SyntheticRepo::generate(RepoShape { seed: 1, ..shape }).unwrap();
+        assert_ne!(a.head_files(), other.head_files());
+    }
+
+    #[test]
+    fn test_extend_adds_to_head() {
+        let shape = RepoShape { commits: 3, merge_every: 0, delete_every: 0, rename_every: 0, ..RepoShape::default() };
+        let mut repo = SyntheticRepo::generate(shape).unwrap();
+        let before = repo.repo().head().unwrap().target().unwrap();
+        let files = repo.head_files();
+        repo.extend(2).unwrap();
+        let head = repo.repo().head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent(0).unwrap().parent_id(0).unwrap(), before);
+        assert_eq!(repo.commit_count(), 5);
+        // Nothing deleted or renamed, so every file is still there
+        assert!(files.keys().all(|path| repo.head_files().contains_key(path)));
+    }
+}
@@ -0,0 +1,203 @@
+//! Terminal setup and guaranteed restoration
+//!
+//! The TUI switches the terminal into raw mode on the alternate screen.
+//! `TerminalGuard` undoes that when dropped, and the panic hook undoes it
+//! before the panic message is printed, so neither an error nor a panic
+//! leaves the user's shell unusable.
+//!
+//! While the TUI runs the window title names the repository. The previous
+//! title is saved on the terminal's title stack and restored on exit
+//! (terminals without a title stack keep the repodiet title).
+//!
+//! Text is copied to the clipboard with an OSC 52 escape sequence, which
+//! works over SSH; terminals that don't support it ignore the sequence.
+//!
+//! Charts are drawn as images in terminals with an image protocol. ratatui
+//! leaves the image's cells alone, and `InlineImages` sends the image only
+//! when it changes, since it stays on screen until removed.
+
+use anyhow::Result;
+use crossterm::{
+    clipboard::CopyToClipboard,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
+};
+use ratatui::{backend::CrosstermBackend, layout::Size, Terminal};
+use std::fmt::Write as _;
+use std::io::{self, Stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use repodiet::view::{donut_png, DonutImage, ImageProtocol};
+
+use crate::logging;
+
+pub type Tui = Terminal<CrosstermBackend<Stdout>>;
+
+/// xterm window operations: save / restore the title on the title stack
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Whether our title is on top of the stack, so it is popped exactly once
+static TITLE_PUSHED: AtomicBool = AtomicBool::new(false);
+
+/// Owns the TUI terminal; restores the normal screen on drop
+pub struct TerminalGuard {
+    terminal: Tui,
+}
+
+impl TerminalGuard {
+    /// Switch to the TUI screen, titling the window after `repo_name`
+    pub fn enter(repo_name: &str) -> Result<Self> {
+        logging::suspend_terminal(true);
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        if let Err(e) = execute!(stdout, EnterAlternateScreen, EnableMouseCapture) {
+            restore();
+            return Err(e.into());
+        }
+        // A title is cosmetic; failing to set one is not worth an error
+        if write!(stdout, "{}", PUSH_TITLE).is_ok() {
+            TITLE_PUSHED.store(true, Ordering::SeqCst);
+        }
+        let _ = execute!(stdout, SetTitle(window_title(repo_name)));
+        match Terminal::new(CrosstermBackend::new(stdout)) {
+            Ok(terminal) => Ok(Self { terminal }),
+            Err(e) => {
+                restore();
+                Err(e.into())
+            }
+        }
+    }
+
+    pub fn terminal(&mut self) -> &mut Tui {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+/// Leave raw mode and the alternate screen; safe to call more than once
+fn restore() {
+    let _ = disable_raw_mode();
+    let mut stdout = io::stdout();
+    let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture, crossterm::cursor::Show);
+    if TITLE_PUSHED.swap(false, Ordering::SeqCst) {
+        let _ = write!(stdout, "{}", POP_TITLE);
+        let _ = stdout.flush();
+    }
+    logging::suspend_terminal(false);
+}
+
+/// Pixels per side of chart images; terminals scale them to their cells
+const IMAGE_SIZE: u32 = 320;
+
+/// The image on screen, kept in step with what each frame asks for
+pub struct InlineImages {
+    protocol: Option<ImageProtocol>,
+    shown: Option<DonutImage>,
+    /// Terminal size the image was drawn at; a resize clears the screen
+    screen: Size,
+}
+
+impl InlineImages {
+    pub fn new(protocol: Option<ImageProtocol>) -> Self {
+        Self { protocol, shown: None, screen: Size::default() }
+    }
+
+    /// Whether views should leave charts to the terminal
+    pub fn enabled(&self) -> bool {
+        self.protocol.is_some()
+    }
+
+    /// Show `wanted` after a frame is drawn, or take the image away
+    pub fn update(&mut self, terminal: &mut Tui, wanted: Option<DonutImage>) -> Result<()> {
+        let Some(protocol) = self.protocol else {
+            return Ok(());
+        };
+        let screen = terminal.size()?;
+        if screen != self.screen {
+            // The screen was cleared; an image sent again replaces any left over
+            self.screen = screen;
+            self.shown = None;
+        }
+        if self.shown == wanted {
+            return Ok(());
+        }
+        let out = terminal.backend_mut();
+        if self.shown.is_some()
+            && let Some(remove) = protocol.remove()
+        {
+            write!(out, "{}", remove)?;
+        }
+        if let Some(image) = &wanted {
+            write!(out, "{}", protocol.display(&donut_png(&image.slices, IMAGE_SIZE), image.area))?;
+        }
+        Write::flush(out)?;
+        self.shown = wanted;
+        Ok(())
+    }
+}
+
+impl Drop for InlineImages {
+    /// Images outliving their text would stay on the terminal after exit
+    fn drop(&mut self) {
+        if self.shown.is_some()
+            && let Some(remove) = self.protocol.and_then(ImageProtocol::remove)
+        {
+            let mut stdout = io::stdout();
+            let _ = write!(stdout, "{}", remove);
+            let _ = stdout.flush();
+        }
+    }
+}
+
+/// Put `text` on the system clipboard via the terminal
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text))?;
+    Ok(())
+}
+
+/// "repodiet: <name>", with control characters and `%` percent-encoded so a
+/// directory name can't smuggle escape sequences into the terminal
+fn window_title(repo_name: &str) -> String {
+    let mut title = String::from("repodiet: ");
+    for c in repo_name.chars() {
+        if c.is_control() || c == '%' {
+            let mut buf = [0u8; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                let _ = write!(title, "%{:02X}", byte);
+            }
+        } else {
+            title.push(c);
+        }
+    }
+    title
+}
+
+/// Restore the terminal before the default hook prints the panic message
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_title_encodes_control_characters() {
+        assert_eq!(window_title("my-app"), "repodiet: my-app");
+        assert_eq!(window_title("evil\x1b]0;pwned\x07"), "repodiet: evil%1B]0;pwned%07");
+        assert_eq!(window_title("100%"), "repodiet: 100%25");
+        assert_eq!(window_title("naïve\u{85}"), "repodiet: naïve%C2%85");
+    }
+}
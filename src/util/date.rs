@@ -0,0 +1,203 @@
+//! How dates are shown: relative or absolute, in which time zone
+//!
+//! Stored dates are Unix timestamps. The TUI shows them relative to now
+//! unless configured otherwise; text exports default to ISO dates, which
+//! read the same tomorrow.
+
+use serde::Deserialize;
+use std::sync::OnceLock;
+use time::format_description::OwnedFormatItem;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+/// The local UTC offset, read once while the process still has one thread
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+/// Remember the local UTC offset for `TimeZone::Local`
+///
+/// The offset can only be read safely before other threads start, so call
+/// this first thing in `main`; otherwise local time falls back to UTC.
+pub fn init_local_offset() {
+    if let Ok(offset) = UtcOffset::current_local_offset() {
+        LOCAL_OFFSET.get_or_init(|| offset);
+    }
+}
+
+/// How a date is written
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum DateFormat {
+    /// "3 years ago"
+    Relative,
+    /// "2023-11-14"
+    Iso,
+    /// A `time` format description, e.g. "[day].[month].[year]" or
+    /// "[month repr:short] [day], [year]"
+    Pattern(String),
+}
+
+impl DateFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "relative" => Some(DateFormat::Relative),
+            "iso" => Some(DateFormat::Iso),
+            pattern => time::format_description::parse_owned::<2>(pattern)
+                .ok()
+                .map(|_| DateFormat::Pattern(pattern.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for DateFormat {
+    type Error = String;
+
+    fn try_from(format: String) -> Result<Self, String> {
+        Self::parse(&format).ok_or_else(|| {
+            format!("expected 'relative', 'iso' or a format like '[day].[month].[year]', got '{}'", format)
+        })
+    }
+}
+
+/// Time zone absolute dates are shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum TimeZone {
+    /// The machine's zone
+    #[default]
+    Local,
+    Utc,
+    /// A fixed offset such as "+05:30"
+    Fixed(UtcOffset),
+}
+
+impl TimeZone {
+    pub fn parse(zone: &str) -> Option<Self> {
+        match zone {
+            "local" => Some(TimeZone::Local),
+            "utc" | "UTC" => Some(TimeZone::Utc),
+            offset => {
+                let format = format_description!("[offset_hour sign:mandatory]:[offset_minute]");
+                UtcOffset::parse(offset, &format).ok().map(TimeZone::Fixed)
+            }
+        }
+    }
+
+    pub fn offset(self) -> UtcOffset {
+        match self {
+            TimeZone::Local => LOCAL_OFFSET.get().copied().unwrap_or(UtcOffset::UTC),
+            TimeZone::Utc => UtcOffset::UTC,
+            TimeZone::Fixed(offset) => offset,
+        }
+    }
+}
+
+impl TryFrom<String> for TimeZone {
+    type Error = String;
+
+    fn try_from(zone: String) -> Result<Self, String> {
+        Self::parse(&zone).ok_or_else(|| format!("expected 'local', 'utc' or an offset like '+05:30', got '{}'", zone))
+    }
+}
+
+/// A date format in a time zone, ready to write timestamps with
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateStyle {
+    format: DateFormat,
+    /// `format`'s description when it is a pattern
+    items: Option<OwnedFormatItem>,
+    offset: UtcOffset,
+}
+
+impl Default for DateStyle {
+    fn default() -> Self {
+        Self::new(DateFormat::Iso, TimeZone::Utc)
+    }
+}
+
+impl DateStyle {
+    pub fn new(format: DateFormat, zone: TimeZone) -> Self {
+        let items = match &format {
+            DateFormat::Pattern(pattern) => time::format_description::parse_owned::<2>(pattern).ok(),
+            _ => None,
+        };
+        Self { format, items, offset: zone.offset() }
+    }
+
+    /// Write `timestamp` ("unknown" for 0, as dates never recorded are)
+    pub fn format(&self, timestamp: i64) -> String {
+        self.format_at(timestamp, OffsetDateTime::now_utc().unix_timestamp())
+    }
+
+    /// Like `format`, with relative dates counted back from `now`
+    pub fn format_at(&self, timestamp: i64, now: i64) -> String {
+        if timestamp == 0 {
+            return "unknown".to_string();
+        }
+        let Ok(date) = OffsetDateTime::from_unix_timestamp(timestamp) else {
+            return "unknown".to_string();
+        };
+        let date = date.to_offset(self.offset);
+        let written = match (&self.format, &self.items) {
+            (DateFormat::Relative, _) if timestamp <= now => return relative(now - timestamp),
+            (DateFormat::Pattern(_), Some(items)) => date.format(items),
+            _ => date.format(format_description!("[year]-[month]-[day]")),
+        };
+        written.unwrap_or_else(|_| "unknown".to_string())
+    }
+}
+
+/// "5 minutes ago", "3 years ago" for a date `seconds` in the past
+fn relative(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    let (count, unit) = match seconds {
+        s if s < MINUTE => return "just now".to_string(),
+        s if s < HOUR => (s / MINUTE, "minute"),
+        s if s < DAY => (s / HOUR, "hour"),
+        s if s < 30 * DAY => (s / DAY, "day"),
+        s if s < 365 * DAY => (s / (30 * DAY), "month"),
+        s => (s / (365 * DAY), "year"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOV_14_2023: i64 = 1_700_000_000; // 22:13 UTC
+
+    #[test]
+    fn test_relative() {
+        let style = DateStyle::new(DateFormat::Relative, TimeZone::Utc);
+        let day = 24 * 3600;
+        assert_eq!(style.format_at(NOV_14_2023, NOV_14_2023 + 30), "just now");
+        assert_eq!(style.format_at(NOV_14_2023, NOV_14_2023 + 3600), "1 hour ago");
+        assert_eq!(style.format_at(NOV_14_2023, NOV_14_2023 + 3 * day), "3 days ago");
+        assert_eq!(style.format_at(NOV_14_2023, NOV_14_2023 + 70 * day), "2 months ago");
+        assert_eq!(style.format_at(NOV_14_2023, NOV_14_2023 + 3 * 365 * day), "3 years ago");
+        // Dates after now (skewed clocks) are written out
+        assert_eq!(style.format_at(NOV_14_2023, NOV_14_2023 - day), "2023-11-14");
+        assert_eq!(style.format_at(0, NOV_14_2023), "unknown");
+    }
+
+    #[test]
+    fn test_absolute_in_zone() {
+        let utc = DateStyle::new(DateFormat::Iso, TimeZone::Utc);
+        assert_eq!(utc.format(NOV_14_2023), "2023-11-14");
+        let tokyo = DateStyle::new(DateFormat::Iso, TimeZone::parse("+09:00").unwrap());
+        assert_eq!(tokyo.format(NOV_14_2023), "2023-11-15");
+        let german = DateStyle::new(DateFormat::parse("[day].[month].[year]").unwrap(), TimeZone::Utc);
+        assert_eq!(german.format(NOV_14_2023), "14.11.2023");
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        assert_eq!(DateFormat::parse("relative"), Some(DateFormat::Relative));
+        assert_eq!(DateFormat::parse("[year"), None);
+        assert_eq!(TimeZone::parse("utc"), Some(TimeZone::Utc));
+        assert_eq!(TimeZone::parse("-03:30").map(TimeZone::offset), UtcOffset::from_hms(-3, -30, 0).ok());
+        assert_eq!(TimeZone::parse("Mars/Olympus"), None);
+    }
+}
@@ -15,6 +15,24 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// `format_size` in at most 5 columns for narrow terminals: "512B",
+/// "1.5K", "340M", "12G"
+pub fn format_size_short(bytes: u64) -> String {
+    const UNITS: [(u64, char); 4] = [(1 << 40, 'T'), (1 << 30, 'G'), (1 << 20, 'M'), (1 << 10, 'K')];
+    for (unit, suffix) in UNITS {
+        if bytes >= unit {
+            let value = bytes as f64 / unit as f64;
+            // One decimal only while it fits; 9.96 would round to "10.0"
+            return if value < 9.95 {
+                format!("{:.1}{}", value, suffix)
+            } else {
+                format!("{:.0}{}", value, suffix)
+            };
+        }
+    }
+    format!("{}B", bytes)
+}
+
 /// Format a Unix timestamp as YYYY-MM-DD string
 pub fn format_timestamp(timestamp: i64) -> String {
     use time::OffsetDateTime;
@@ -33,6 +51,43 @@ pub fn format_timestamp(timestamp: i64) -> String {
         .unwrap_or_else(|| "unknown".to_string())
 }
 
+/// Parse a YYYY-MM-DD date as the Unix timestamp of its midnight, in UTC
+pub fn parse_date(date: &str) -> Option<i64> {
+    use time::macros::format_description;
+
+    let format = format_description!("[year]-[month]-[day]");
+    let date = time::Date::parse(date, &format).ok()?;
+    Some(date.midnight().assume_utc().unix_timestamp())
+}
+
+/// Parse a size like "750MB", "1.5 GB" or "4096", using the same 1024-based
+/// units as `format_size` (case-insensitive; a trailing "iB" is accepted too)
+pub fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let split = size.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
+
+/// Calendar quarter of a Unix timestamp as `year * 4 + (0..=3)`, in UTC
+pub fn quarter_of(timestamp: i64) -> Option<i32> {
+    let dt = time::OffsetDateTime::from_unix_timestamp(timestamp).ok()?;
+    Some(dt.year() * 4 + (dt.month() as i32 - 1) / 3)
+}
+
+/// Format a quarter from `quarter_of` as e.g. "2024Q3"
+pub fn format_quarter(quarter: i32) -> String {
+    format!("{}Q{}", quarter.div_euclid(4), quarter.rem_euclid(4) + 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,6 +101,16 @@ mod tests {
         assert_eq!(format_size(2 * 1024 * 1024 * 1024), "2.00 GB");
     }
 
+    #[test]
+    fn test_format_size_short() {
+        assert_eq!(format_size_short(500), "500B");
+        assert_eq!(format_size_short(1536), "1.5K");
+        assert_eq!(format_size_short(10 * 1024 - 1), "10K");
+        assert_eq!(format_size_short(1023 * 1024 * 1024 + 1), "1023M");
+        assert_eq!(format_size_short(3 * 1024 * 1024 * 1024), "3.0G");
+        assert!((0..50).all(|shift| format_size_short((1u64 << shift) * 1023 / 1000).len() <= 5));
+    }
+
     #[test]
     fn test_format_timestamp() {
         let ts = 1700000000; // Nov 14, 2023 approximately
@@ -54,4 +119,29 @@ mod tests {
 
         assert_eq!(format_timestamp(0), "unknown");
     }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2024-01-01"), Some(1704067200));
+        assert_eq!(format_timestamp(parse_date("2023-11-14").unwrap()), "2023-11-14");
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("10KB"), Some(10 * 1024));
+        assert_eq!(parse_size("1.5 gb"), Some(1536 * 1024 * 1024));
+        assert_eq!(parse_size("750MiB"), Some(750 * 1024 * 1024));
+        assert_eq!(parse_size("12 parsecs"), None);
+        assert_eq!(parse_size("MB"), None);
+    }
+
+    #[test]
+    fn test_quarters() {
+        let q = quarter_of(1700000000).unwrap(); // Nov 2023
+        assert_eq!(format_quarter(q), "2023Q4");
+        assert_eq!(quarter_of(1704067200), Some(q + 1)); // 2024-01-01
+    }
 }
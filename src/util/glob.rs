@@ -0,0 +1,241 @@
+/// Path exclusion patterns, matched roughly like `.gitignore` entries.
+///
+/// - A pattern without `/` matches a file or directory name at any depth
+///   (`*.png`, `node_modules`).
+/// - A pattern with `/` is anchored at the repository root (`vendor/*.js`,
+///   `/docs`); a `**` component matches any number of directories.
+/// - `*` matches within one path component, `?` matches one character.
+///
+/// Matching a directory excludes everything below it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathFilter {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    /// Matches any single path component
+    Name(String),
+    /// Matches the leading components of the path
+    Anchored(Vec<String>),
+}
+
+impl PathFilter {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> anyhow::Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Pattern::parse(p.as_ref()))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether `path` (or one of its parent directories) matches a pattern
+    pub fn is_excluded(&self, path: &str) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let parts: Vec<&str> = path.split('/').collect();
+        self.patterns.iter().any(|pattern| match pattern {
+            Pattern::Name(name) => parts.iter().any(|part| wildcard(name, part)),
+            Pattern::Anchored(components) => match_components(components, &parts, Rest::Any),
+        })
+    }
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> anyhow::Result<Self> {
+        let trimmed = pattern.trim().trim_end_matches('/');
+        let anchored = trimmed.contains('/');
+        let trimmed = trimmed.trim_start_matches('/');
+        anyhow::ensure!(!trimmed.is_empty(), "empty exclude pattern '{}'", pattern);
+
+        if anchored {
+            let components: Vec<String> = trimmed.split('/').map(str::to_string).collect();
+            anyhow::ensure!(
+                components.iter().all(|c| !c.is_empty()),
+                "exclude pattern '{}' has an empty path component",
+                pattern
+            );
+            Ok(Pattern::Anchored(components))
+        } else {
+            Ok(Pattern::Name(trimmed.to_string()))
+        }
+    }
+}
+
+/// A CODEOWNERS pattern, matched against file paths the way GitHub does
+///
+/// - A pattern without `/` (other than a trailing one) matches a file or
+///   directory name at any depth (`*.js`, `docs`, `apps/`).
+/// - Any other pattern is anchored at the repository root (`/build/logs/`,
+///   `src/app`); a `**` component matches any number of directories.
+/// - A trailing `/` only matches directories; a trailing `/*` only the
+///   files directly inside one (`docs/*` leaves out `docs/guide/intro.md`).
+///
+/// Matching a directory matches everything below it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+    components: Vec<String>,
+    /// What may follow the components matched
+    rest: Rest,
+}
+
+/// What a pattern allows after its last component
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rest {
+    /// The last component names the file, or any directory above it
+    Any,
+    /// The last component names the file
+    Nothing,
+    /// The last component names a directory above the file
+    Something,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let dir_only = pattern.ends_with('/');
+        let trimmed = pattern.trim_end_matches('/');
+        let anchored = trimmed.contains('/');
+        let trimmed = trimmed.trim_start_matches('/');
+        anyhow::ensure!(!trimmed.is_empty(), "empty pattern '{}'", pattern);
+        let mut components: Vec<String> = trimmed.split('/').map(str::to_string).collect();
+        anyhow::ensure!(
+            components.iter().all(|c| !c.is_empty()),
+            "pattern '{}' has an empty path component",
+            pattern
+        );
+        if !anchored {
+            components.insert(0, "**".to_string());
+        }
+        let rest = match components.last().map(String::as_str) {
+            _ if dir_only => Rest::Something,
+            Some("*") if anchored => Rest::Nothing,
+            _ => Rest::Any,
+        };
+        Ok(Self { components, rest })
+    }
+
+    /// Whether `path` (a file) matches
+    pub fn matches(&self, path: &str) -> bool {
+        let parts: Vec<&str> = path.split('/').collect();
+        match_components(&self.components, &parts, self.rest)
+    }
+}
+
+/// Whether `pattern` matches the first components of `path`, with `rest`
+/// saying what may follow them
+fn match_components(pattern: &[String], path: &[&str], rest: Rest) -> bool {
+    match pattern.split_first() {
+        None => match rest {
+            Rest::Any => true,
+            Rest::Nothing => path.is_empty(),
+            Rest::Something => !path.is_empty(),
+        },
+        Some((first, others)) if first == "**" => {
+            (0..=path.len()).any(|skip| match_components(others, &path[skip..], rest))
+        }
+        Some((first, others)) => {
+            !path.is_empty() && wildcard(first, path[0]) && match_components(others, &path[1..], rest)
+        }
+    }
+}
+
+/// Match one path component against `*` / `?` wildcards
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it currently covers up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(patterns: &[&str]) -> PathFilter {
+        PathFilter::new(patterns).unwrap()
+    }
+
+    #[test]
+    fn test_name_patterns_match_at_any_depth() {
+        let f = filter(&["*.png", "node_modules"]);
+        assert!(f.is_excluded("logo.png"));
+        assert!(f.is_excluded("assets/img/logo.png"));
+        assert!(f.is_excluded("web/node_modules/react/index.js"));
+        assert!(!f.is_excluded("src/png.rs"));
+        assert!(!f.is_excluded("node_modules_backup/a.js"));
+    }
+
+    #[test]
+    fn test_anchored_patterns() {
+        let f = filter(&["vendor/*.js", "/docs", "build/**/*.o"]);
+        assert!(f.is_excluded("vendor/jquery.js"));
+        assert!(!f.is_excluded("src/vendor/jquery.js"));
+        assert!(f.is_excluded("docs/guide/intro.md"));
+        assert!(!f.is_excluded("src/docs/intro.md"));
+        assert!(f.is_excluded("build/main.o"));
+        assert!(f.is_excluded("build/a/b/main.o"));
+        assert!(!f.is_excluded("build/main.c"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(wildcard("*", ""));
+        assert!(wildcard("a*b*c", "axxbyyc"));
+        assert!(!wildcard("a*b*c", "axxbyy"));
+        assert!(wildcard("file?.txt", "file1.txt"));
+        assert!(!wildcard("file?.txt", "file.txt"));
+    }
+
+    #[test]
+    fn test_codeowners_globs() {
+        let glob = |pattern: &str| Glob::new(pattern).unwrap();
+        assert!(glob("*").matches("a/b/c.txt"));
+        assert!(glob("*.js").matches("web/app.js"));
+        assert!(!glob("*.js").matches("web/app.jsx"));
+        assert!(glob("docs").matches("src/docs/intro.md"));
+        assert!(glob("apps/").matches("x/apps/main.go"));
+        assert!(!glob("apps/").matches("x/apps"));
+        assert!(glob("/build/logs/").matches("build/logs/today.log"));
+        assert!(!glob("/build/logs/").matches("src/build/logs/today.log"));
+        assert!(glob("docs/*").matches("docs/getting-started.md"));
+        assert!(!glob("docs/*").matches("docs/build-app/troubleshooting.md"));
+        assert!(glob("**/logs").matches("deep/down/logs/x.log"));
+        assert!(glob("/scripts/**").matches("scripts/ci/run.sh"));
+        assert!(glob("src/app").matches("src/app/main.rs"));
+        assert!(!glob("src/app").matches("lib/src/app/main.rs"));
+        assert!(Glob::new("a//b").is_err());
+        assert!(Glob::new("").is_err());
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(PathFilter::new(&[""]).is_err());
+        assert!(PathFilter::new(&["/"]).is_err());
+        assert!(PathFilter::new(&["a//b"]).is_err());
+        assert!(filter(&[]).is_empty());
+        assert!(!filter(&[]).is_excluded("anything"));
+    }
+}
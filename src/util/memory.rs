@@ -0,0 +1,40 @@
+//! Peak resident memory of this process, where the OS reports it (Linux)
+
+/// Most memory this process has held resident since it started or since
+/// the last `reset_peak_rss`, in bytes
+pub fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_hwm(&status)
+}
+
+/// Start measuring `peak_rss` again from the current resident size;
+/// false where the peak can't be reset
+pub fn reset_peak_rss() -> bool {
+    std::fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// Bytes from the `VmHWM:  1234 kB` line of /proc/self/status
+fn parse_vm_hwm(status: &str) -> Option<u64> {
+    let line = status.lines().find_map(|line| line.strip_prefix("VmHWM:"))?;
+    let kb = line.trim().strip_suffix("kB")?.trim().parse::<u64>().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vm_hwm() {
+        let status = "Name:\trepodiet\nVmPeak:\t  905460 kB\nVmHWM:\t   51200 kB\nVmRSS:\t   50000 kB\n";
+        assert_eq!(parse_vm_hwm(status), Some(51200 * 1024));
+        assert_eq!(parse_vm_hwm("Name:\trepodiet\n"), None);
+        assert_eq!(parse_vm_hwm("VmHWM:\t garbage\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_peak_rss_reported_on_linux() {
+        assert!(peak_rss().is_some_and(|bytes| bytes > 0));
+    }
+}
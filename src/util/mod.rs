@@ -1,5 +1,13 @@
+mod date;
 mod format;
+mod glob;
+mod memory;
 mod path;
+mod truncate_display;
 
-pub use format::{format_size, format_timestamp};
-pub use path::extension_label;
+pub use date::{init_local_offset, DateFormat, DateStyle, TimeZone};
+pub use format::{format_quarter, format_size, format_size_short, format_timestamp, parse_date, parse_size, quarter_of};
+pub use glob::{Glob, PathFilter};
+pub use memory::{peak_rss, reset_peak_rss};
+pub use path::{extension_label, percent_encode, uri_path};
+pub use truncate_display::{display_width, display_window, pad_left, pad_right, scroll_display, truncate_end, truncate_start};
@@ -18,6 +18,25 @@ pub fn extension_label(file_name: &str) -> Cow<'static, str> {
     }
 }
 
+/// Percent-encode everything except RFC 3986 unreserved characters
+pub fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// A repository-relative path as a relative URI reference, each segment
+/// percent-encoded: "docs/my file.md" -> "docs/my%20file.md"
+pub fn uri_path(path: &str) -> String {
+    path.split('/').map(percent_encode).collect::<Vec<_>>().join("/")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +80,12 @@ mod tests {
         // Extensions longer than 10 chars should be rejected
         assert_eq!(extension_label("file.verylongextension"), "(no ext)");
     }
+
+    #[test]
+    fn test_uri_path() {
+        assert_eq!(uri_path("src/main.rs"), "src/main.rs");
+        assert_eq!(uri_path("docs/my file.md"), "docs/my%20file.md");
+        assert_eq!(uri_path("a%b/#1?.txt"), "a%25b/%231%3F.txt");
+        assert_eq!(uri_path("ü.txt"), "%C3%BC.txt");
+    }
 }
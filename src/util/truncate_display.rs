@@ -0,0 +1,132 @@
+//! Cutting and padding text by the columns a terminal gives it
+//!
+//! Byte lengths and `char` counts both go wrong for display: multi-byte
+//! UTF-8 can't be sliced at arbitrary bytes, and CJK characters and most
+//! emoji take two columns. Everything here measures with `unicode-width`
+//! and only cuts between characters.
+
+use std::ops::Range;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// What a cut end of the text is replaced with
+const ELLIPSIS: &str = "...";
+
+/// Columns `text` takes in a terminal
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Bytes of `text` shown in `width` columns when scrolled `offset` columns
+/// back from its end, and whether it is cut at the start and at the end;
+/// each cut end takes 3 of the columns for "..."
+///
+/// A wide character that would straddle an edge is left out, so the window
+/// can come out a column narrower than `width`.
+pub fn display_window(text: &str, width: usize, offset: usize) -> (Range<usize>, bool, bool) {
+    let total = text.width();
+    if total <= width {
+        return (0..text.len(), false, false);
+    }
+    let mut end = total - offset.min(total - width);
+    let mut start = end - width;
+    let (cut_start, cut_end) = (start > 0, end < total);
+    if cut_start {
+        start = (start + ELLIPSIS.len()).min(end);
+    }
+    if cut_end {
+        end = end.saturating_sub(ELLIPSIS.len()).max(start);
+    }
+
+    let mut shown: Option<Range<usize>> = None;
+    let mut column = 0;
+    for (i, c) in text.char_indices() {
+        let char_end = column + c.width().unwrap_or(0);
+        if column >= start && char_end <= end {
+            let range = shown.get_or_insert(i..i);
+            range.end = i + c.len_utf8();
+        }
+        column = char_end;
+        if column > end {
+            break;
+        }
+    }
+    let shown = shown.unwrap_or(text.len()..text.len());
+    (shown, cut_start, cut_end)
+}
+
+/// `text` cut to `width` columns as `display_window` shows it
+pub fn scroll_display(text: &str, width: usize, offset: usize) -> String {
+    let (shown, cut_start, cut_end) = display_window(text, width, offset);
+    let mut cut = String::with_capacity(shown.len() + 2 * ELLIPSIS.len());
+    if cut_start {
+        cut.push_str(ELLIPSIS);
+    }
+    cut.push_str(&text[shown]);
+    if cut_end {
+        cut.push_str(ELLIPSIS);
+    }
+    cut
+}
+
+/// `text` in at most `width` columns, keeping its start: "long na..."
+pub fn truncate_end(text: &str, width: usize) -> String {
+    scroll_display(text, width, usize::MAX)
+}
+
+/// `text` in at most `width` columns, keeping its end (for paths, the file
+/// name): "...file.rs"
+pub fn truncate_start(text: &str, width: usize) -> String {
+    scroll_display(text, width, 0)
+}
+
+/// `text` right-aligned in `width` columns; what `format!("{:>width$}")`
+/// does for text whose characters are one column each
+pub fn pad_left(text: &str, width: usize) -> String {
+    format!("{}{}", " ".repeat(width.saturating_sub(text.width())), text)
+}
+
+/// `text` left-aligned in `width` columns
+pub fn pad_right(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii_paths() {
+        assert_eq!(truncate_start("a/b.rs", 10), "a/b.rs");
+        assert_eq!(truncate_start("very/long/path/file.rs", 10), "...file.rs");
+        assert_eq!(scroll_display("very/long/path/file.rs", 10, 4), "...ath/...");
+        assert_eq!(truncate_end("very/long/path/file.rs", 10), "very/lo...");
+    }
+
+    #[test]
+    fn test_multibyte_text_is_cut_between_characters() {
+        assert_eq!(truncate_start("дир/файл.bin", 8), "...л.bin");
+        assert_eq!(truncate_end("Łukasz Żółkiewski", 10), "Łukasz ...");
+        assert_eq!(truncate_end("naïve", 5), "naïve");
+    }
+
+    #[test]
+    fn test_wide_characters_take_two_columns() {
+        assert_eq!(display_width("資料/画像.png"), 13);
+        assert_eq!(truncate_start("資料/画像.png", 11), "...画像.png");
+        // "画" would straddle the cut, so the window comes out a column short
+        assert_eq!(truncate_start("資料/画像.png", 10), "...像.png");
+        assert_eq!(truncate_end("山田太郎さん", 9), "山田太...");
+        assert_eq!(truncate_end("山田太郎さん", 8), "山田...");
+        for width in 0..15 {
+            assert!(display_width(&truncate_start("資料/画像.png", width)) <= width.max(3));
+        }
+    }
+
+    #[test]
+    fn test_padding_by_columns() {
+        assert_eq!(pad_left("山田", 6), "  山田");
+        assert_eq!(pad_right("ab", 4), "ab  ");
+        assert_eq!(pad_left("toolong", 3), "toolong");
+    }
+}
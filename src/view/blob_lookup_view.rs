@@ -0,0 +1,134 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::model::BlobLookup;
+use crate::util::{format_size, DateStyle};
+use crate::viewmodel::{BlobLookupViewModel, LookupState};
+
+pub fn render(frame: &mut Frame, vm: &BlobLookupViewModel, dates: &DateStyle, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // OID input
+            Constraint::Length(6),  // Summary
+            Constraint::Min(0),     // Paths
+            Constraint::Length(3),  // Footer
+        ])
+        .split(area);
+
+    render_header(frame, vm, chunks[0]);
+    match vm.state() {
+        LookupState::Found(blob) => {
+            render_summary(frame, blob, dates, chunks[1]);
+            render_paths(frame, vm, blob, chunks[2]);
+        }
+        state => render_message(frame, vm, state, chunks[1].union(chunks[2])),
+    }
+    render_footer(frame, chunks[3]);
+}
+
+fn render_header(frame: &mut Frame, vm: &BlobLookupViewModel, area: Rect) {
+    let header = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("BLOB LOOKUP", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+            Span::raw(" > "),
+            Span::styled(vm.query(), Style::default().fg(Color::Yellow)),
+            Span::styled("█", Style::default().fg(Color::White)),  // Cursor
+        ]),
+    ])
+    .block(Block::default().borders(Borders::ALL).title("Look up a blob by OID (4+ hex digits)"));
+    frame.render_widget(header, area);
+}
+
+fn render_summary(frame: &mut Frame, blob: &BlobLookup, dates: &DateStyle, area: Rect) {
+    let (head_text, head_color) = if blob.in_head() {
+        ("still in HEAD", Color::Green)
+    } else {
+        ("not in HEAD", Color::Magenta)
+    };
+
+    let summary = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled(hex::encode(&blob.oid), Style::default().fg(Color::DarkGray)),
+            Span::raw("  "),
+            Span::styled(format_size(blob.size), Style::default().fg(Color::Cyan)),
+            Span::raw("  "),
+            Span::styled(head_text, Style::default().fg(head_color)),
+        ]),
+        Line::from(vec![
+            Span::raw("Introduced "),
+            Span::styled(dates.format(blob.first_date), Style::default().fg(Color::White)),
+            Span::raw(" by "),
+            Span::styled(&blob.first_author, Style::default().fg(Color::Yellow)),
+            Span::styled(format!(" <{}>", blob.first_email), Style::default().fg(Color::DarkGray)),
+        ]),
+        Line::from(vec![
+            Span::raw("in "),
+            Span::styled(&blob.first_subject, Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            Span::raw("at "),
+            Span::raw(&blob.path),
+        ]),
+    ])
+    .block(Block::default().borders(Borders::ALL).title("Blob"));
+    frame.render_widget(summary, area);
+}
+
+fn render_paths(frame: &mut Frame, vm: &BlobLookupViewModel, blob: &BlobLookup, area: Rect) {
+    let items: Vec<ListItem> = blob
+        .occurrences
+        .iter()
+        .map(|occurrence| {
+            let marker = if occurrence.in_head {
+                Span::styled("HEAD ", Style::default().fg(Color::Green))
+            } else {
+                Span::styled("     ", Style::default())
+            };
+            ListItem::new(Line::from(vec![marker, Span::raw(&occurrence.path)]))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!("Paths ({})", blob.occurrences.len())))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_message(frame: &mut Frame, vm: &BlobLookupViewModel, state: &LookupState, area: Rect) {
+    let line = match state {
+        LookupState::NotFound => Line::from(Span::styled(
+            format!("No blob matching {} in the index", vm.query()),
+            Style::default().fg(Color::Yellow),
+        )),
+        LookupState::Failed(e) => Line::from(Span::styled(e.as_str(), Style::default().fg(Color::Red))),
+        _ => Line::from(Span::styled(
+            "Type a full or abbreviated blob OID and press Enter",
+            Style::default().fg(Color::DarkGray),
+        )),
+    };
+
+    let message = Paragraph::new(vec![line]).block(Block::default().borders(Borders::ALL));
+    frame.render_widget(message, area);
+}
+
+fn render_footer(frame: &mut Frame, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" look up / go to  "),
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)), Span::raw(" cancel  "),
+        Span::styled("Backspace", Style::default().fg(Color::Yellow)), Span::raw(" delete"),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(footer, area);
+}
@@ -6,79 +6,212 @@ use ratatui::{
     Frame,
 };
 
-use crate::util::{format_size, format_timestamp};
-use crate::viewmodel::BlobsViewModel;
+use crate::util::{display_width, format_size, pad_left, scroll_display, truncate_end, DateStyle};
+use crate::viewmodel::{BlobColumns, BlobsViewModel, MarkKey, SelectionSet};
 
 use super::ui_fmt;
 
-pub fn render(frame: &mut Frame, vm: &BlobsViewModel, area: Rect) {
+const BAR_WIDTH: usize = 12;
+const OID_WIDTH: usize = 7;
+const AUTHOR_WIDTH: usize = 15;
+const GROUP_WIDTH: usize = 4;
+/// Below this the path is unreadable, so optional columns give way
+const MIN_PATH_WIDTH: usize = 24;
+
+/// Columns that actually fit in the list area
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ColumnLayout {
+    oid: bool,
+    author: bool,
+    date: bool,
+    group: bool,
+    /// Size and bar columns; blobs have no bloat ratio
+    sizes: ui_fmt::SizeColumns,
+    /// Widest date shown; relative and custom formats vary
+    date_width: usize,
+    path_width: usize,
+}
+
+impl ColumnLayout {
+    /// Width of everything left of the path, including separators
+    fn fixed_width(&self) -> usize {
+        let mut width = ui_fmt::MARK_WIDTH + self.sizes.width(BAR_WIDTH);
+        if self.oid {
+            width += OID_WIDTH + 1;
+        }
+        if self.author {
+            width += AUTHOR_WIDTH + 1;
+        }
+        if self.date {
+            width += self.date_width + 1;
+        }
+        if self.group {
+            width += GROUP_WIDTH + 1;
+        }
+        width + 1
+    }
+}
+
+/// Decide which enabled columns fit, hiding OID first, then date, then
+/// author, then the bar, and shortening sizes last; the group column,
+/// shown while grouping, always stays
+fn column_layout(area_width: u16, enabled: BlobColumns, group: bool, date_width: usize) -> ColumnLayout {
+    let available = (area_width as usize).saturating_sub(ui_fmt::CHROME_WIDTH);
+    let mut layout = ColumnLayout {
+        oid: enabled.oid,
+        author: enabled.author,
+        date: enabled.date,
+        group,
+        sizes: ui_fmt::SizeColumns { bar: true, bloat: false, compact: false },
+        date_width,
+        path_width: 0,
+    };
+
+    loop {
+        layout.path_width = available.saturating_sub(layout.fixed_width());
+        if layout.path_width >= MIN_PATH_WIDTH {
+            return layout;
+        }
+        if layout.oid {
+            layout.oid = false;
+        } else if layout.date {
+            layout.date = false;
+        } else if layout.author {
+            layout.author = false;
+        } else if layout.sizes.bar {
+            layout.sizes.bar = false;
+        } else if !layout.sizes.compact {
+            layout.sizes.compact = true;
+        } else {
+            return layout;
+        }
+    }
+}
+
+pub fn render(frame: &mut Frame, vm: &BlobsViewModel, marks: &SelectionSet, dates: &DateStyle, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),  // Header
+            Constraint::Length(4),  // Header and the selected blob's commit
             Constraint::Min(0),     // List
             Constraint::Length(3),  // Footer
         ])
         .split(area);
 
+    let dates: Vec<String> = vm.blobs().iter().map(|blob| dates.format(blob.first_date)).collect();
+    let date_width = dates.iter().map(|date| display_width(date)).max().unwrap_or(0);
+    let layout = column_layout(chunks[1].width, vm.columns(), vm.is_grouped(), date_width);
+
     render_header(frame, vm, chunks[0]);
-    render_list(frame, vm, chunks[1]);
-    render_footer(frame, chunks[2]);
+    render_list(frame, vm, marks, chunks[1], layout, &dates);
+    render_footer(frame, marks, chunks[2], layout);
 }
 
 fn render_header(frame: &mut Frame, vm: &BlobsViewModel, area: Rect) {
-    let header = Paragraph::new(vec![
-        Line::from(vec![
-            Span::styled("LARGE BLOB DETECTIVE", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
-            Span::raw(" | "),
-            Span::raw(format!("Top {} largest blobs: {} total",
+    let mut spans = vec![
+        Span::styled("LARGE BLOB DETECTIVE", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        Span::raw(" | "),
+        Span::raw(if vm.is_deleted_only() {
+            format!("Top {} largest blobs not in HEAD: {} total [DELETED ONLY]",
                 vm.blobs().len(),
-                format_size(vm.total_blob_size()))),
-        ]),
-    ])
+                format_size(vm.total_blob_size()))
+        } else {
+            format!("Top {} largest blobs: {} total",
+                vm.blobs().len(),
+                format_size(vm.total_blob_size()))
+        }),
+    ];
+    if vm.is_grouped() && !vm.needs_groups() {
+        spans.push(Span::styled(
+            format!(" [{} near-duplicate groups]", vm.group_count()),
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+    let reclaimed = vm.reclaimed_count();
+    if reclaimed > 0 {
+        spans.push(Span::styled(
+            format!(" [{} already pruned by git]", reclaimed),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    spans.extend(ui_fmt::filter_spans(vm.filter()));
+    let mut lines = vec![Line::from(spans)];
+    if let Some(blob) = vm.selected_blob().filter(|blob| !blob.first_subject.is_empty()) {
+        lines.push(Line::from(vec![
+            Span::styled("Added in ", Style::default().fg(Color::DarkGray)),
+            Span::styled(blob.first_subject.as_str(), Style::default().fg(Color::White)),
+        ]));
+    }
+    let header = Paragraph::new(lines)
     .block(Block::default().borders(Borders::ALL).title("Top Largest Blobs (Single Files)"));
     frame.render_widget(header, area);
 }
 
-fn render_list(frame: &mut Frame, vm: &BlobsViewModel, area: Rect) {
+fn render_list(
+    frame: &mut Frame,
+    vm: &BlobsViewModel,
+    marks: &SelectionSet,
+    area: Rect,
+    layout: ColumnLayout,
+    dates: &[String],
+) {
     let total_cumulative = vm.total_cumulative();
     let blobs = vm.blobs();
+    let visual = marks.visual_range(vm.selected_index());
 
     let items: Vec<ListItem> = blobs
         .iter()
-        .map(|blob| {
-            let percent = ui_fmt::percent(blob.size, total_cumulative);
-            let bar = ui_fmt::bar(percent, 12);
+        .zip(dates)
+        .enumerate()
+        .map(|(row, (blob, date))| {
+            let mut spans = vec![
+                ui_fmt::mark_span(marks.contains(&MarkKey::Blob(blob.oid.clone()))),
+                Span::styled(layout.sizes.size(blob.size), Style::default().fg(Color::Cyan)),
+                Span::raw(" "),
+            ];
+            if layout.sizes.bar {
+                let percent = ui_fmt::percent(blob.size, total_cumulative);
+                spans.push(Span::styled(format!("[{}]", ui_fmt::bar(percent, BAR_WIDTH)), Style::default().fg(Color::Blue)));
+                spans.push(Span::raw(" "));
+            }
 
-            let date_str = format_timestamp(blob.first_date);
+            if layout.oid {
+                spans.push(Span::styled(format!("{:>7}", &hex::encode(&blob.oid)[..7]), Style::default().fg(Color::DarkGray)));
+                spans.push(Span::raw(" "));
+            }
 
-            // Truncate path for display
-            let path_display = if blob.path.len() > 50 {
-                format!("...{}", &blob.path[blob.path.len()-47..])
-            } else {
-                blob.path.clone()
-            };
+            if layout.author {
+                let author = truncate_end(&vm.author_label(blob), AUTHOR_WIDTH);
+                spans.push(Span::styled(pad_left(&author, AUTHOR_WIDTH), Style::default().fg(Color::Yellow)));
+                spans.push(Span::raw(" "));
+            }
+
+            if layout.date {
+                spans.push(Span::styled(
+                    pad_left(date, layout.date_width),
+                    Style::default().fg(Color::White),
+                ));
+                spans.push(Span::raw(" "));
+            }
+
+            if layout.group {
+                let group = vm.group_of(blob).map_or_else(String::new, |g| format!("≈{}", g));
+                spans.push(Span::styled(format!("{:>4}", group), Style::default().fg(Color::Magenta)));
+                spans.push(Span::raw(" "));
+            }
 
-            // Truncate author
-            let author_display = if blob.first_author.len() > 15 {
-                format!("{}...", &blob.first_author[..12])
+            spans.push(Span::raw(" "));
+            let path = scroll_display(&blob.path, layout.path_width, vm.path_scroll());
+            // Gone from the packs: history still names it, but it takes no space
+            if vm.is_reclaimed(blob) {
+                let pruned = Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT);
+                spans.push(Span::styled(path, pruned));
             } else {
-                blob.first_author.clone()
-            };
+                spans.push(Span::raw(path));
+            }
 
-            ListItem::new(Line::from(vec![
-                Span::styled(format!("{:>10}", format_size(blob.size)), Style::default().fg(Color::Cyan)),
-                Span::raw(" "),
-                Span::styled(format!("[{}]", bar), Style::default().fg(Color::Blue)),
-                Span::raw(" "),
-                Span::styled(format!("{:>7}", &hex::encode(&blob.oid)[..7]), Style::default().fg(Color::DarkGray)),
-                Span::raw(" "),
-                Span::styled(format!("{:>15}", author_display), Style::default().fg(Color::Yellow)),
-                Span::raw(" "),
-                Span::styled(format!("{:>10}", date_str), Style::default().fg(Color::White)),
-                Span::raw("  "),
-                Span::raw(path_display),
-            ]))
+            let in_range = visual.as_ref().is_some_and(|range| range.contains(&row));
+            ListItem::new(Line::from(spans)).style(ui_fmt::visual_row_style(in_range))
         })
         .collect();
 
@@ -93,17 +226,85 @@ fn render_list(frame: &mut Frame, vm: &BlobsViewModel, area: Rect) {
     frame.render_stateful_widget(list, area, &mut list_state);
 }
 
-fn render_footer(frame: &mut Frame, area: Rect) {
-    let footer = Paragraph::new(Line::from(vec![
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect, layout: ColumnLayout) {
+    let mut spans = vec![
         Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("←/→", Style::default().fg(Color::Yellow)), Span::raw(" scroll  "),
         Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" go to  "),
+        Span::styled("o/a/D", Style::default().fg(Color::Yellow)), Span::raw(" columns  "),
+        Span::styled("d", Style::default().fg(Color::Yellow)), Span::raw(" deleted  "),
+        Span::styled("g", Style::default().fg(Color::Yellow)), Span::raw(" group similar  "),
+        Span::styled("/", Style::default().fg(Color::Yellow)), Span::raw(" filter  "),
+        Span::styled("Space/u", Style::default().fg(Color::Yellow)), Span::raw(" mark/clear  "),
         Span::styled("l/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
         Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
-        Span::styled("SIZE", Style::default().fg(Color::Cyan)), Span::raw(" "),
-        Span::styled("OID", Style::default().fg(Color::DarkGray)), Span::raw(" "),
-        Span::styled("AUTHOR", Style::default().fg(Color::Yellow)), Span::raw(" "),
-        Span::styled("DATE", Style::default().fg(Color::White)),
-    ]))
-    .block(Block::default().borders(Borders::ALL));
+        Span::styled("SIZE", Style::default().fg(Color::Cyan)),
+    ];
+    if layout.oid {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled("OID", Style::default().fg(Color::DarkGray)));
+    }
+    if layout.author {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled("AUTHOR", Style::default().fg(Color::Yellow)));
+    }
+    if layout.date {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled("DATE", Style::default().fg(Color::White)));
+    }
+    if layout.group {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled("≈GROUP", Style::default().fg(Color::Magenta)));
+    }
+
+    let footer = Paragraph::new(Line::from(spans))
+        .block(ui_fmt::footer_block(marks));
     frame.render_widget(footer, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_terminal_keeps_all_columns() {
+        let layout = column_layout(200, BlobColumns::default(), false, 10);
+        assert!(layout.oid && layout.author && layout.date);
+        assert!(layout.sizes.bar && !layout.sizes.compact);
+        assert_eq!(layout.path_width, 200 - ui_fmt::CHROME_WIDTH - layout.fixed_width());
+    }
+
+    #[test]
+    fn test_narrow_terminal_hides_oid_then_date() {
+        let all = column_layout(200, BlobColumns::default(), false, 10);
+        let width = (ui_fmt::CHROME_WIDTH + all.fixed_width() + MIN_PATH_WIDTH - 1) as u16;
+
+        let layout = column_layout(width, BlobColumns::default(), false, 10);
+        assert!(!layout.oid);
+        assert!(layout.author && layout.date);
+        assert!(layout.path_width >= MIN_PATH_WIDTH);
+
+        let layout = column_layout(60, BlobColumns::default(), false, 10);
+        assert!(!layout.oid && !layout.date && !layout.author);
+        assert!(layout.sizes.bar);
+    }
+
+    #[test]
+    fn test_narrower_still_drops_the_bar_then_shortens_sizes() {
+        let layout = column_layout(50, BlobColumns::default(), false, 10);
+        assert!(!layout.sizes.bar && !layout.sizes.compact);
+        assert!(layout.path_width >= MIN_PATH_WIDTH);
+
+        let layout = column_layout(40, BlobColumns::default(), false, 10);
+        assert!(layout.sizes.compact);
+        assert!(layout.path_width >= MIN_PATH_WIDTH);
+    }
+
+    #[test]
+    fn test_disabled_columns_stay_hidden() {
+        let enabled = BlobColumns { oid: false, author: true, date: false };
+        let layout = column_layout(200, enabled, true, 10);
+        assert!(!layout.oid && !layout.date);
+        assert!(layout.author && layout.group);
+    }
+}
@@ -0,0 +1,89 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::util::format_size;
+use crate::viewmodel::{BookmarksViewModel, SelectionSet};
+
+use super::ui_fmt;
+
+pub fn render(frame: &mut Frame, vm: &BookmarksViewModel, marks: &SelectionSet, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),     // List
+            Constraint::Length(3),  // Footer
+        ])
+        .split(area);
+
+    render_list(frame, vm, chunks[0]);
+    render_footer(frame, marks, chunks[1]);
+}
+
+fn render_list(frame: &mut Frame, vm: &BookmarksViewModel, area: Rect) {
+    let entries = vm.entries();
+    let block = Block::default().borders(Borders::ALL).title(format!("Bookmarks ({})", entries.len()));
+
+    if entries.is_empty() {
+        let hint = Paragraph::new("No bookmarks yet: press m on a tree entry to bookmark it.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let prefix = if entry.has_children { "▸ " } else { "  " };
+            let mut spans = match entry.sizes {
+                Some((cumulative, current)) => vec![
+                    Span::raw(prefix),
+                    Span::styled(format!("{:>10}", format_size(cumulative)), Style::default().fg(Color::Cyan)),
+                    Span::raw(" "),
+                    Span::styled(format!("{:>10}", format_size(current)), Style::default().fg(Color::White)),
+                    Span::raw("  "),
+                    Span::styled(entry.path.as_str(), Style::default().fg(Color::Yellow)),
+                ],
+                None => vec![
+                    Span::raw(prefix),
+                    Span::styled(format!("{:>21}", "-"), Style::default().fg(Color::DarkGray)),
+                    Span::raw("  "),
+                    Span::styled(entry.path.as_str(), Style::default().fg(Color::DarkGray)),
+                ],
+            };
+            if entry.sizes.is_none() {
+                spans.push(Span::styled(" [not in results]", Style::default().fg(Color::DarkGray)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" show in tree  "),
+        Span::styled("m/Del", Style::default().fg(Color::Yellow)), Span::raw(" forget  "),
+        Span::styled("'/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
+        Span::styled("CUM", Style::default().fg(Color::Cyan)), Span::raw(" "),
+        Span::styled("CUR", Style::default().fg(Color::White)),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
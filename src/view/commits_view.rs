@@ -0,0 +1,110 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::util::{format_size, pad_right, truncate_end, DateStyle};
+use crate::viewmodel::{CommitsViewModel, SelectionSet};
+
+use super::ui_fmt;
+
+const AUTHOR_WIDTH: usize = 16;
+
+pub fn render(frame: &mut Frame, vm: &CommitsViewModel, marks: &SelectionSet, dates: &DateStyle, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),    // List
+            Constraint::Length(7), // Details of the selected commit
+            Constraint::Length(3), // Footer
+        ])
+        .split(area);
+
+    render_list(frame, vm, dates, chunks[0]);
+    render_details(frame, vm, dates, chunks[1]);
+    render_footer(frame, marks, chunks[2]);
+}
+
+fn render_list(frame: &mut Frame, vm: &CommitsViewModel, dates: &DateStyle, area: Rect) {
+    let commits = vm.commits();
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Heavy commits ({}, {} added)",
+        commits.len(),
+        format_size(vm.total_added())
+    ));
+
+    if commits.is_empty() {
+        let hint = Paragraph::new("No commit added any blob data.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = commits
+        .iter()
+        .map(|commit| {
+            let oid = hex::encode(&commit.oid);
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:>10}", format_size(commit.added_size)), Style::default().fg(Color::Cyan)),
+                Span::styled(format!(" {:>6} blobs  ", commit.blob_count), Style::default().fg(Color::Magenta)),
+                Span::styled(format!("{}  ", &oid[..oid.len().min(10)]), Style::default().fg(Color::DarkGray)),
+                Span::styled(pad_right(&dates.format(commit.date), 17), Style::default().fg(Color::White)),
+                Span::styled(pad_right(&truncate_end(&commit.author, AUTHOR_WIDTH), AUTHOR_WIDTH + 1), Style::default().fg(Color::Yellow)),
+                Span::raw(commit.subject.as_str()),
+            ]))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_details(frame: &mut Frame, vm: &CommitsViewModel, dates: &DateStyle, area: Rect) {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::DarkGray));
+    let lines: Vec<Line> = vm
+        .selected_commit()
+        .map(|commit| {
+            vec![
+                Line::from(vec![label("Commit   "), Span::raw(hex::encode(&commit.oid))]),
+                Line::from(vec![
+                    label("Subject  "),
+                    Span::styled(commit.subject.as_str(), Style::default().fg(Color::White)),
+                ]),
+                Line::from(vec![
+                    label("Author   "),
+                    Span::raw(format!("{} <{}>, {}", commit.author, commit.email, dates.format(commit.date))),
+                ]),
+                Line::from(vec![
+                    label("Largest  "),
+                    Span::styled(commit.largest_path.as_str(), Style::default().fg(Color::Yellow)),
+                ]),
+            ]
+        })
+        .unwrap_or_default();
+    let details = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Commit"));
+    frame.render_widget(details, area);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" largest file in tree  "),
+        Span::styled("w/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
+        Span::styled("ADDED", Style::default().fg(Color::Cyan)), Span::raw(" "),
+        Span::styled("BLOBS", Style::default().fg(Color::Magenta)),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
@@ -0,0 +1,127 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::viewmodel::{DiagnosticsViewModel, SelectionSet};
+
+use super::ui_fmt;
+
+pub fn render(frame: &mut Frame, vm: &DiagnosticsViewModel, marks: &SelectionSet, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),         // List
+            Constraint::Percentage(35), // Details of the selected diagnostic
+            Constraint::Length(3),      // Footer
+        ])
+        .split(area);
+
+    render_list(frame, vm, chunks[0]);
+    render_details(frame, vm, chunks[1]);
+    render_footer(frame, marks, chunks[2]);
+}
+
+fn kind_color(kind: &str) -> Color {
+    match kind {
+        "pack" => Color::Red,
+        "commit" | "tree" => Color::Magenta,
+        _ => Color::Yellow,
+    }
+}
+
+/// What a diagnostic of `kind` means for the numbers shown
+fn kind_meaning(kind: &str) -> &'static str {
+    match kind {
+        "commit" => "The commit could not be read: the files it added are missing from history.",
+        "tree" => "The tree could not be read: nothing under this path is counted for the commits using it.",
+        "size" => "The blob's size could not be found: it counts as 0 B wherever it appears.",
+        "pack" => "The pack could not be read in full: blobs only stored in it have no size.",
+        _ => "",
+    }
+}
+
+fn render_list(frame: &mut Frame, vm: &DiagnosticsViewModel, area: Rect) {
+    let diagnostics = vm.diagnostics();
+    let counts: Vec<String> = vm.counts().iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+    let title = if counts.is_empty() {
+        "Diagnostics".to_string()
+    } else {
+        format!("Diagnostics ({})", counts.join(", "))
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    if diagnostics.is_empty() {
+        let hint = Paragraph::new("The last scan read every commit, tree, blob size and pack.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let kind = diagnostic.kind.as_str();
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<7}", kind), Style::default().fg(kind_color(kind))),
+                Span::styled(
+                    format!("{}  ", &diagnostic.oid[..diagnostic.oid.len().min(10)]),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(format!("{}  ", diagnostic.path), Style::default().fg(Color::Yellow)),
+                Span::raw(diagnostic.reason.as_str()),
+            ]))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_details(frame: &mut Frame, vm: &DiagnosticsViewModel, area: Rect) {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::DarkGray));
+    let lines: Vec<Line> = vm
+        .selected_diagnostic()
+        .map(|diagnostic| {
+            let mut lines = vec![
+                Line::from(vec![label("Object  "), Span::raw(diagnostic.oid.as_str())]),
+                Line::from(vec![label("Reason  "), Span::raw(diagnostic.reason.as_str())]),
+            ];
+            if !diagnostic.path.is_empty() {
+                lines.insert(1, Line::from(vec![label("Path    "), Span::raw(diagnostic.path.as_str())]));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                kind_meaning(&diagnostic.kind),
+                Style::default().fg(kind_color(&diagnostic.kind)),
+            )));
+            lines
+        })
+        .unwrap_or_default();
+    let details = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Details"));
+    frame.render_widget(details, area);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" show in tree  "),
+        Span::styled("!/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit"),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
@@ -0,0 +1,178 @@
+//! Donut chart of shares, drawn as a PNG image or with half-block characters
+
+use std::f64::consts::TAU;
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+
+/// Radius of the hole, as a fraction of the outer radius
+const HOLE: f64 = 0.55;
+/// Samples per pixel side when smoothing the edges of an image
+const SUPERSAMPLE: u32 = 4;
+
+/// Colors given to slices in order; the last one is also for "the rest"
+pub const PALETTE: [(u8, u8, u8); 7] = [
+    (0x4e, 0x9a, 0xf0),
+    (0xf0, 0x8c, 0x3c),
+    (0x5c, 0xc8, 0x6e),
+    (0xe0, 0x5a, 0x6a),
+    (0xb0, 0x7c, 0xe8),
+    (0xe8, 0xc8, 0x4a),
+    (0x90, 0x90, 0x90),
+];
+
+/// One share of the donut
+#[derive(Debug, Clone, PartialEq)]
+pub struct DonutSlice {
+    pub value: u64,
+    pub color: (u8, u8, u8),
+}
+
+/// A donut the terminal draws as an image over `area`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DonutImage {
+    pub area: Rect,
+    pub slices: Vec<DonutSlice>,
+}
+
+/// The slice covering point (`x`, `y`) of the square from -1 to 1, going
+/// clockwise from the top
+fn slice_at(slices: &[DonutSlice], total: u64, x: f64, y: f64) -> Option<usize> {
+    let radius = x.hypot(y);
+    if total == 0 || !(HOLE..=1.0).contains(&radius) {
+        return None;
+    }
+    let turn = x.atan2(-y).rem_euclid(TAU) / TAU;
+    let mut end = 0.0;
+    for (i, slice) in slices.iter().enumerate() {
+        end += slice.value as f64 / total as f64;
+        if turn < end {
+            return Some(i);
+        }
+    }
+    slices.len().checked_sub(1)
+}
+
+/// `slices` as a `size` x `size` PNG with a transparent background
+pub fn donut_png(slices: &[DonutSlice], size: u32) -> Vec<u8> {
+    let total: u64 = slices.iter().map(|s| s.value).sum();
+    let samples = SUPERSAMPLE * SUPERSAMPLE;
+    let mut rows = Vec::with_capacity((size * (size * 4 + 1)) as usize);
+    for py in 0..size {
+        // Each row starts with its filter type: none
+        rows.push(0);
+        for px in 0..size {
+            let (mut rgb, mut hits) = ([0u32; 3], 0);
+            for s in 0..samples {
+                let x = (px as f64 + ((s % SUPERSAMPLE) as f64 + 0.5) / SUPERSAMPLE as f64) / size as f64 * 2.0 - 1.0;
+                let y = (py as f64 + ((s / SUPERSAMPLE) as f64 + 0.5) / SUPERSAMPLE as f64) / size as f64 * 2.0 - 1.0;
+                if let Some(i) = slice_at(slices, total, x, y) {
+                    let (r, g, b) = slices[i].color;
+                    rgb[0] += r as u32;
+                    rgb[1] += g as u32;
+                    rgb[2] += b as u32;
+                    hits += 1;
+                }
+            }
+            let channel = |sum: u32| sum.checked_div(hits).unwrap_or(0) as u8;
+            rows.extend([channel(rgb[0]), channel(rgb[1]), channel(rgb[2]), (hits * 255 / samples) as u8]);
+        }
+    }
+    encode_png(size, size, &rows)
+}
+
+/// An 8-bit RGBA PNG of `rows`, each already prefixed with its filter byte
+fn encode_png(width: u32, height: u32, rows: &[u8]) -> Vec<u8> {
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(data);
+        png.extend((data.len() as u32).to_be_bytes());
+        png.extend(kind);
+        png.extend(data);
+        png.extend(crc.sum().to_be_bytes());
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // Bit depth 8, color type 6 (RGBA), default compression, filtering and no interlace
+    header.extend([8, 6, 0, 0, 0]);
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec can't fail
+    zlib.write_all(rows).expect("in-memory compression");
+    let data = zlib.finish().expect("in-memory compression");
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &data);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Draw `slices` into `area` with half blocks, two pixels to a cell;
+/// cells are about twice as tall as wide, so `area` should be too
+pub fn render_cells(slices: &[DonutSlice], area: Rect, buf: &mut Buffer) {
+    let total: u64 = slices.iter().map(|s| s.value).sum();
+    let (width, height) = (area.width as f64, area.height as f64 * 2.0);
+    let pixel = |col: u16, row: u16| {
+        let x = (col as f64 + 0.5) / width * 2.0 - 1.0;
+        let y = (row as f64 + 0.5) / height * 2.0 - 1.0;
+        slice_at(slices, total, x, y).map(|i| {
+            let (r, g, b) = slices[i].color;
+            Color::Rgb(r, g, b)
+        })
+    };
+    for row in 0..area.height {
+        for col in 0..area.width {
+            let cell = &mut buf[(area.x + col, area.y + row)];
+            match (pixel(col, row * 2), pixel(col, row * 2 + 1)) {
+                (Some(top), Some(bottom)) => cell.set_symbol("▀").set_fg(top).set_bg(bottom),
+                (Some(top), None) => cell.set_symbol("▀").set_fg(top),
+                (None, Some(bottom)) => cell.set_symbol("▄").set_fg(bottom),
+                (None, None) => continue,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn halves() -> Vec<DonutSlice> {
+        vec![DonutSlice { value: 1, color: PALETTE[0] }, DonutSlice { value: 1, color: PALETTE[1] }]
+    }
+
+    #[test]
+    fn test_slices_go_clockwise_from_the_top() {
+        let slices = halves();
+        // Right of the hole is the first half, left of it the second
+        assert_eq!(slice_at(&slices, 2, 0.8, 0.0), Some(0));
+        assert_eq!(slice_at(&slices, 2, -0.8, 0.0), Some(1));
+        assert_eq!(slice_at(&slices, 2, 0.0, 0.0), None);
+        assert_eq!(slice_at(&slices, 2, 1.0, 1.0), None);
+        assert_eq!(slice_at(&[], 0, 0.8, 0.0), None);
+    }
+
+    #[test]
+    fn test_png_structure() {
+        let png = donut_png(&halves(), 16);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x10\0\0\0\x10\x08\x06"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    fn test_cells_draw_a_ring() {
+        let area = Rect::new(0, 0, 8, 4);
+        let mut buf = Buffer::empty(area);
+        render_cells(&halves(), area, &mut buf);
+        // The middle of the ring is its hole, the middle of its edges is colored
+        assert_eq!(buf[(4, 2)].symbol(), " ");
+        assert_ne!(buf[(7, 2)].symbol(), " ");
+        assert_eq!(buf[(7, 2)].fg, Color::Rgb(0x4e, 0x9a, 0xf0));
+        assert_eq!(buf[(0, 2)].fg, Color::Rgb(0xf0, 0x8c, 0x3c));
+    }
+}
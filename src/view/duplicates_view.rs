@@ -0,0 +1,104 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::util::format_size;
+use crate::viewmodel::{DuplicatesViewModel, SelectionSet};
+
+use super::ui_fmt;
+
+pub fn render(frame: &mut Frame, vm: &DuplicatesViewModel, marks: &SelectionSet, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),         // List
+            Constraint::Percentage(35), // Paths of the selected blob
+            Constraint::Length(3),      // Footer
+        ])
+        .split(area);
+
+    render_list(frame, vm, chunks[0]);
+    render_paths(frame, vm, chunks[1]);
+    render_footer(frame, marks, chunks[2]);
+}
+
+fn render_list(frame: &mut Frame, vm: &DuplicatesViewModel, area: Rect) {
+    let duplicates = vm.duplicates();
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Duplicates ({}, {} in extra copies in HEAD)",
+        duplicates.len(),
+        format_size(vm.total_wasted())
+    ));
+
+    if duplicates.is_empty() {
+        let hint = Paragraph::new("No blob was committed at more than one path.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = duplicates
+        .iter()
+        .map(|duplicate| {
+            let oid = hex::encode(&duplicate.oid);
+            let first = duplicate.paths.first().map_or("", |p| p.path.as_str());
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:>10}", format_size(duplicate.size)), Style::default().fg(Color::Cyan)),
+                Span::styled(format!(" ×{:<4}", duplicate.paths.len()), Style::default().fg(Color::Magenta)),
+                Span::styled(format!("{:>3} in HEAD  ", duplicate.head_copies()), Style::default().fg(Color::White)),
+                Span::styled(format!("{}  ", &oid[..oid.len().min(10)]), Style::default().fg(Color::DarkGray)),
+                Span::styled(first, Style::default().fg(Color::Yellow)),
+            ]))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_paths(frame: &mut Frame, vm: &DuplicatesViewModel, area: Rect) {
+    let lines: Vec<Line> = vm
+        .selected_duplicate()
+        .map(|duplicate| {
+            duplicate
+                .paths
+                .iter()
+                .map(|occurrence| {
+                    let (tag, style) = if occurrence.in_head {
+                        ("HEAD ", Style::default().fg(Color::Green))
+                    } else {
+                        ("     ", Style::default().fg(Color::DarkGray))
+                    };
+                    Line::from(vec![Span::styled(tag, style), Span::raw(occurrence.path.as_str())])
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let paths = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Paths"));
+    frame.render_widget(paths, area);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" show in tree  "),
+        Span::styled("c/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
+        Span::styled("SIZE", Style::default().fg(Color::Cyan)), Span::raw(" "),
+        Span::styled("×PATHS", Style::default().fg(Color::Magenta)),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
@@ -0,0 +1,44 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+/// Full-screen report of an error that ended the session
+///
+/// `message` may span several lines (e.g. an anyhow error chain).
+pub fn render(frame: &mut Frame, message: &str, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),     // Message
+            Constraint::Length(3),  // Footer
+        ])
+        .split(area);
+
+    let lines: Vec<Line> = message
+        .lines()
+        .map(|line| Line::from(Span::raw(line)))
+        .collect();
+    let body = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title(Span::styled(
+                    "repodiet stopped because of an error",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                )),
+        );
+    frame.render_widget(body, chunks[0]);
+
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("any key", Style::default().fg(Color::Yellow)),
+        Span::raw(" exit (the error is printed again in your shell)"),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(footer, chunks[1]);
+}
@@ -6,12 +6,98 @@ use ratatui::{
     Frame,
 };
 
-use crate::util::format_size;
-use crate::viewmodel::ExtensionViewModel;
+use crate::model::BloatThresholds;
+use crate::util::{format_size, format_size_short, pad_left, pad_right, truncate_end};
+use crate::viewmodel::{ExtensionRanking, ExtensionViewModel, MarkKey, SelectionSet};
 
+use super::donut::{self, DonutImage, DonutSlice, PALETTE};
 use super::ui_fmt;
 
-pub fn render(frame: &mut Frame, vm: &ExtensionViewModel, area: Rect) {
+/// Width of the share chart panel, borders included
+const CHART_WIDTH: u16 = 32;
+/// The chart is only shown beside a list keeping at least this much room
+const MIN_LIST_WIDTH: u16 = 80;
+/// Smallest donut worth drawing, in rows
+const MIN_DONUT_ROWS: u16 = 4;
+/// Width of the current/deleted/older-revisions split bar
+const BREAKDOWN_WIDTH: usize = 12;
+/// Width of the share-of-total bar
+const BAR_WIDTH: usize = 20;
+/// Extension column plus its separator
+const EXTENSION_WIDTH: usize = 12 + 2;
+/// File count column plus its separator
+const FILES_WIDTH: usize = 12 + 2;
+/// Binary share column plus its separator
+const CONTENT_WIDTH: usize = 4 + 2;
+/// Extension names in the share chart's legend
+const LEGEND_NAME_WIDTH: usize = 11;
+
+/// Columns that actually fit in the list area
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ColumnLayout {
+    /// Cumulative size and bar; the bloat ratio always stays
+    sizes: ui_fmt::SizeColumns,
+    files: bool,
+    breakdown: bool,
+}
+
+impl ColumnLayout {
+    fn width(&self) -> usize {
+        let current = if self.sizes.compact { 5 } else { 8 };
+        let mut width = ui_fmt::MARK_WIDTH + EXTENSION_WIDTH + self.sizes.width(BAR_WIDTH) + 1 + current + 2 + CONTENT_WIDTH;
+        if self.files {
+            width += FILES_WIDTH;
+        }
+        if self.breakdown {
+            width += BREAKDOWN_WIDTH;
+        }
+        width
+    }
+
+    /// The current size column
+    fn current(&self, bytes: u64) -> String {
+        if self.sizes.compact {
+            format!("{:>5}", format_size_short(bytes))
+        } else {
+            format!("{:>8}", format_size(bytes))
+        }
+    }
+}
+
+/// Decide which columns fit, hiding the breakdown bar first, then the file
+/// count, then the share bar, and shortening sizes last
+fn column_layout(area_width: u16) -> ColumnLayout {
+    let available = (area_width as usize).saturating_sub(ui_fmt::CHROME_WIDTH);
+    let mut layout = ColumnLayout {
+        sizes: ui_fmt::SizeColumns { bar: true, bloat: true, compact: false },
+        files: true,
+        breakdown: true,
+    };
+    while layout.width() > available {
+        if layout.breakdown {
+            layout.breakdown = false;
+        } else if layout.files {
+            layout.files = false;
+        } else if layout.sizes.bar {
+            layout.sizes.bar = false;
+        } else {
+            layout.sizes.compact = true;
+            break;
+        }
+    }
+    layout
+}
+
+/// Draw the view; with `images`, the chart's donut is left for the terminal
+/// to draw as an image, and where and what it is is returned
+pub fn render(
+    frame: &mut Frame,
+    vm: &ExtensionViewModel,
+    marks: &SelectionSet,
+    thresholds: &BloatThresholds,
+    images: bool,
+    area: Rect,
+) -> Option<DonutImage> {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -22,51 +108,96 @@ pub fn render(frame: &mut Frame, vm: &ExtensionViewModel, area: Rect) {
         .split(area);
 
     render_header(frame, vm, chunks[0]);
-    render_list(frame, vm, chunks[1]);
-    render_footer(frame, chunks[2]);
+    let mut list_area = chunks[1];
+    let mut image = None;
+    if list_area.width >= MIN_LIST_WIDTH + CHART_WIDTH && !vm.stats().is_empty() {
+        let [list, chart] = Layout::horizontal([Constraint::Min(0), Constraint::Length(CHART_WIDTH)]).areas(list_area);
+        list_area = list;
+        image = render_chart(frame, vm, images, chart);
+    }
+    let layout = column_layout(list_area.width);
+    render_list(frame, vm, marks, thresholds, list_area, layout);
+    render_footer(frame, vm, marks, chunks[2], layout);
+    image
 }
 
 fn render_header(frame: &mut Frame, vm: &ExtensionViewModel, area: Rect) {
-    let header = Paragraph::new(vec![
-        Line::from(vec![
-            Span::styled("FILE TYPE BREAKDOWN", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
-            Span::raw(" | "),
-            Span::raw(format!("Total: {} cumulative, {} current, {} files",
+    let mut spans = vec![
+        Span::styled("FILE TYPE BREAKDOWN", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+        Span::raw(" | "),
+        Span::raw(if vm.is_deleted_only() {
+            format!("Deleted: {} (reclaimable) across {} types [DELETED ONLY]",
+                format_size(vm.total_deleted()),
+                vm.stats().len())
+        } else {
+            format!("Total: {} cumulative, {} current, {} files, {:.0}% binary",
                 format_size(vm.total_cumulative()),
                 format_size(vm.total_current()),
-                vm.total_files())),
-        ]),
-    ])
+                vm.total_files(),
+                ui_fmt::percent(vm.total_binary(), vm.total_cumulative()))
+        }),
+    ];
+    spans.extend(ui_fmt::filter_spans(vm.filter()));
+    let header = Paragraph::new(vec![Line::from(spans)])
     .block(Block::default().borders(Borders::ALL).title("Size by File Extension"));
     frame.render_widget(header, area);
 }
 
-fn render_list(frame: &mut Frame, vm: &ExtensionViewModel, area: Rect) {
-    let total_cumulative = vm.total_cumulative();
+fn render_list(
+    frame: &mut Frame,
+    vm: &ExtensionViewModel,
+    marks: &SelectionSet,
+    thresholds: &BloatThresholds,
+    area: Rect,
+    layout: ColumnLayout,
+) {
+    let show_deleted = vm.is_deleted_only();
+    let total = vm.shown_total();
     let stats = vm.stats();
 
     let items: Vec<ListItem> = stats
         .iter()
         .map(|stat| {
-            let percent = ui_fmt::percent(stat.cumulative_size, total_cumulative);
+            let display_size = vm.shown_size(stat);
+            let size_color = if show_deleted || vm.ranking() == ExtensionRanking::Deleted {
+                Color::Magenta
+            } else {
+                Color::Cyan
+            };
             let bloat = ui_fmt::bloat_ratio(stat.cumulative_size, stat.current_size);
             let bloat_str = ui_fmt::bloat_str(stat.cumulative_size, stat.current_size);
-            let bar = ui_fmt::bar(percent, 20);
-            let bloat_color = ui_fmt::bloat_color(bloat);
+            let bloat_color = ui_fmt::bloat_color(bloat, thresholds);
+            let marked = marks.contains(&MarkKey::Extension(stat.extension.clone()));
 
-            ListItem::new(Line::from(vec![
-                Span::styled(format!("{:>12}", &stat.extension), Style::default().fg(Color::Yellow)),
+            let mut spans = vec![
+                ui_fmt::mark_span(marked),
+                Span::styled(pad_left(&stat.extension, 12), Style::default().fg(Color::Yellow)),
                 Span::raw("  "),
-                Span::styled(format!("{:>10}", format_size(stat.cumulative_size)), Style::default().fg(Color::Cyan)),
-                Span::raw(" "),
-                Span::styled(format!("[{}]", bar), Style::default().fg(Color::Blue)),
+                Span::styled(layout.sizes.size(display_size), Style::default().fg(size_color)),
                 Span::raw(" "),
+            ];
+            if layout.sizes.bar {
+                let percent = ui_fmt::percent(display_size, total);
+                spans.push(Span::styled(format!("[{}]", ui_fmt::bar(percent, BAR_WIDTH)), Style::default().fg(Color::Blue)));
+                spans.push(Span::raw(" "));
+            }
+            spans.extend([
                 Span::styled(format!("{:>5}", bloat_str), Style::default().fg(bloat_color)),
                 Span::raw("  "),
-                Span::styled(format!("{:>8}", format_size(stat.current_size)), Style::default().fg(Color::White)),
+                Span::styled(layout.current(stat.current_size), Style::default().fg(Color::White)),
                 Span::raw("  "),
-                Span::styled(format!("{:>6} files", stat.file_count), Style::default().fg(Color::DarkGray)),
-            ]))
+            ]);
+            if layout.files {
+                spans.push(Span::styled(format!("{:>6} files", stat.file_count), Style::default().fg(Color::DarkGray)));
+                spans.push(Span::raw("  "));
+            }
+            spans.push(content_span(stat.binary_size, stat.cumulative_size));
+            spans.push(Span::raw("  "));
+            if layout.breakdown {
+                spans.extend(ui_fmt::breakdown_bar(
+                    stat.cumulative_size, stat.current_size, stat.deleted_size, BREAKDOWN_WIDTH));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
@@ -74,24 +205,148 @@ fn render_list(frame: &mut Frame, vm: &ExtensionViewModel, area: Rect) {
     list_state.select(Some(vm.selected_index()));
 
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(format!("Extensions ({} types)", stats.len())))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Extensions ({} types, by {} size)",
+            stats.len(),
+            if show_deleted { "deleted" } else { vm.ranking().label() }
+        )))
         .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
         .highlight_symbol(">> ");
 
     frame.render_stateful_widget(list, area, &mut list_state);
 }
 
-fn render_footer(frame: &mut Frame, area: Rect) {
-    let footer = Paragraph::new(Line::from(vec![
+/// Each listed extension's share of the total as a donut, the largest
+/// ones in their own colors and the rest together, above a legend
+fn render_chart(frame: &mut Frame, vm: &ExtensionViewModel, images: bool, area: Rect) -> Option<DonutImage> {
+    let sizes: Vec<(&str, u64)> = vm.stats().iter().map(|stat| (stat.extension.as_str(), vm.shown_size(stat))).collect();
+    let total: u64 = sizes.iter().map(|&(_, size)| size).sum();
+    let own = sizes.len().min(PALETTE.len() - 1);
+    let mut shares = sizes[..own].to_vec();
+    let rest: u64 = sizes[own..].iter().map(|&(_, size)| size).sum();
+    if rest > 0 {
+        shares.push(("other", rest));
+    }
+    let slices: Vec<DonutSlice> =
+        shares.iter().zip(PALETTE).map(|(&(_, value), color)| DonutSlice { value, color }).collect();
+
+    let title = match (vm.is_deleted_only(), vm.ranking()) {
+        (true, _) | (false, ExtensionRanking::Deleted) => "Share of deleted",
+        (false, ExtensionRanking::Cumulative) => "Share",
+        (false, ExtensionRanking::Current) => "Share of current",
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let legend: Vec<Line> = shares
+        .iter()
+        .zip(&slices)
+        .map(|(&(name, value), slice)| {
+            let (r, g, b) = slice.color;
+            Line::from(vec![
+                Span::styled("██ ", Style::default().fg(Color::Rgb(r, g, b))),
+                Span::raw(pad_right(&truncate_end(name, LEGEND_NAME_WIDTH), LEGEND_NAME_WIDTH + 1)),
+                Span::styled(format!("{:>4.0}%", ui_fmt::percent(value, total)), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+    let legend_height = legend.len() as u16;
+    // Cells are about twice as tall as wide: a round donut is half as many rows as columns
+    let rows = inner.height.saturating_sub(legend_height + 1).min(inner.width / 2);
+    let [donut_area, legend_area] =
+        Layout::vertical([Constraint::Length(if rows >= MIN_DONUT_ROWS { rows + 1 } else { 0 }), Constraint::Min(0)])
+            .areas(inner);
+    frame.render_widget(Paragraph::new(legend), legend_area);
+    if rows < MIN_DONUT_ROWS || total == 0 {
+        return None;
+    }
+
+    let donut = Rect { x: donut_area.x + (donut_area.width - rows * 2) / 2, width: rows * 2, height: rows, ..donut_area };
+    if images {
+        // The terminal draws here; redrawing the cells would erase it
+        for y in donut.top()..donut.bottom() {
+            for x in donut.left()..donut.right() {
+                frame.buffer_mut()[(x, y)].set_skip(true);
+            }
+        }
+        Some(DonutImage { area: donut, slices })
+    } else {
+        donut::render_cells(&slices, donut, frame.buffer_mut());
+        None
+    }
+}
+
+/// "bin", "txt", or the binary share of a mixed bag (files without an
+/// extension are classified one by one)
+fn content_span(binary: u64, cumulative: u64) -> Span<'static> {
+    if binary == 0 {
+        Span::styled(" txt", Style::default().fg(Color::Green))
+    } else if binary >= cumulative {
+        Span::styled(" bin", Style::default().fg(Color::Red))
+    } else {
+        Span::styled(format!("{:>3.0}%", ui_fmt::percent(binary, cumulative)), Style::default().fg(Color::Red))
+    }
+}
+
+fn render_footer(frame: &mut Frame, vm: &ExtensionViewModel, marks: &SelectionSet, area: Rect, layout: ColumnLayout) {
+    // The first size column follows the ranking
+    let (size, size_color) = match (vm.is_deleted_only(), vm.ranking()) {
+        (true, _) | (false, ExtensionRanking::Deleted) => ("DEL", Color::Magenta),
+        (false, ExtensionRanking::Cumulative) => ("CUM", Color::Cyan),
+        (false, ExtensionRanking::Current) => ("CUR", Color::Cyan),
+    };
+    let mut spans = vec![
         Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
-        Span::styled("/", Style::default().fg(Color::Yellow)), Span::raw(" search  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" blobs  "),
+        Span::styled("/", Style::default().fg(Color::Yellow)), Span::raw(" filter  "),
         Span::styled("t/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("d", Style::default().fg(Color::Yellow)), Span::raw(" deleted  "),
+        Span::styled("s", Style::default().fg(Color::Yellow)), Span::raw(" rank  "),
+        Span::styled("Space/u", Style::default().fg(Color::Yellow)), Span::raw(" mark/clear  "),
         Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
-        Span::styled("CUM", Style::default().fg(Color::Cyan)), Span::raw(" "),
+        Span::styled(size, Style::default().fg(size_color)), Span::raw(" "),
         Span::styled("BLOAT", Style::default().fg(Color::Green)), Span::raw(" "),
         Span::styled("CUR", Style::default().fg(Color::White)), Span::raw(" "),
-        Span::styled("FILES", Style::default().fg(Color::DarkGray)),
-    ]))
-    .block(Block::default().borders(Borders::ALL));
+    ];
+    if layout.files {
+        spans.push(Span::styled("FILES", Style::default().fg(Color::DarkGray)));
+        spans.push(Span::raw(" "));
+    }
+    spans.extend([
+        Span::styled("BIN", Style::default().fg(Color::Red)), Span::raw("/"),
+        Span::styled("TXT", Style::default().fg(Color::Green)),
+    ]);
+    if layout.breakdown {
+        spans.extend([
+            Span::raw("  ["),
+            Span::styled("CUR", Style::default().fg(Color::White)), Span::raw("|"),
+            Span::styled("DEL", Style::default().fg(Color::Magenta)), Span::raw("|"),
+            Span::styled("OLD", Style::default().fg(Color::DarkGray)), Span::raw("]"),
+        ]);
+    }
+    let footer = Paragraph::new(Line::from(spans))
+        .block(ui_fmt::footer_block(marks));
     frame.render_widget(footer, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_give_way_on_narrow_terminals() {
+        let wide = column_layout(200);
+        assert!(wide.breakdown && wide.files && wide.sizes.bar && !wide.sizes.compact);
+
+        let layout = column_layout(100);
+        assert!(!layout.breakdown && layout.files);
+
+        let layout = column_layout(80);
+        assert!(!layout.files && layout.sizes.bar);
+        assert!(layout.width() <= 80 - ui_fmt::CHROME_WIDTH);
+
+        let layout = column_layout(40);
+        assert!(!layout.sizes.bar && layout.sizes.compact);
+    }
+}
@@ -0,0 +1,110 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::model::BloatThresholds;
+use crate::util::{format_size, truncate_start};
+use crate::viewmodel::{FlattenViewModel, MarkKey, SelectionSet};
+
+use super::ui_fmt;
+
+/// Width of the share-of-total bar
+const BAR_WIDTH: usize = 20;
+/// Columns before the path: mark, sizes, bar, share and bloat, plus the
+/// borders and highlight symbol
+const FIXED_WIDTH: usize = 2 + 11 + BAR_WIDTH + 2 + 7 + 11 + 8 + 5;
+
+pub fn render(frame: &mut Frame, vm: &FlattenViewModel, marks: &SelectionSet, thresholds: &BloatThresholds, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),    // List
+            Constraint::Length(3), // Footer
+        ])
+        .split(area);
+
+    render_list(frame, vm, marks, thresholds, chunks[0]);
+    render_footer(frame, marks, chunks[1]);
+}
+
+fn render_list(frame: &mut Frame, vm: &FlattenViewModel, marks: &SelectionSet, thresholds: &BloatThresholds, area: Rect) {
+    let entries = vm.entries();
+    let shown = if vm.total_entries() > entries.len() {
+        format!("largest {} of {}", entries.len(), vm.total_entries())
+    } else {
+        entries.len().to_string()
+    };
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "All paths down to depth {} ({}, of {} cumulative)",
+        vm.depth(),
+        shown,
+        format_size(vm.total_cumulative())
+    ));
+
+    if entries.is_empty() {
+        let hint = Paragraph::new("The repository has no files.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let path_width = (area.width as usize).saturating_sub(FIXED_WIDTH).max(10);
+    let visual = marks.visual_range(vm.selected_index());
+    let items: Vec<ListItem> = entries
+        .iter()
+        .enumerate()
+        .map(|(row, entry)| {
+            let percent = ui_fmt::percent(entry.cumulative_size, vm.total_cumulative());
+            let bloat = ui_fmt::bloat_ratio(entry.cumulative_size, entry.current_size);
+            let (path, color) = if entry.is_dir {
+                (format!("{}/", entry.path), Color::Yellow)
+            } else {
+                (entry.path.clone(), Color::White)
+            };
+            let in_range = visual.as_ref().is_some_and(|range| range.contains(&row));
+            ListItem::new(Line::from(vec![
+                ui_fmt::mark_span(marks.contains(&MarkKey::Path(entry.path.clone()))),
+                Span::styled(format!("{:>10} ", format_size(entry.cumulative_size)), Style::default().fg(Color::Cyan)),
+                Span::styled(format!("[{}]", ui_fmt::bar(percent, BAR_WIDTH)), Style::default().fg(Color::Blue)),
+                Span::styled(format!("{:>6.1}%", percent), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{:>11}", format_size(entry.current_size)), Style::default().fg(Color::White)),
+                Span::styled(
+                    format!("{:>7} ", ui_fmt::bloat_str(entry.cumulative_size, entry.current_size)),
+                    Style::default().fg(ui_fmt::bloat_color(bloat, thresholds)),
+                ),
+                Span::styled(truncate_start(&path, path_width), Style::default().fg(color)),
+            ]))
+            .style(ui_fmt::visual_row_style(in_range))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" open in tree  "),
+        Span::styled("F/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
+        Span::styled("CUM", Style::default().fg(Color::Cyan)), Span::raw(" "),
+        Span::styled("SHARE", Style::default().fg(Color::DarkGray)), Span::raw(" "),
+        Span::styled("CUR", Style::default().fg(Color::White)), Span::raw(" "),
+        Span::styled("BLOAT", Style::default().fg(Color::Green)),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
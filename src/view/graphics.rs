@@ -0,0 +1,176 @@
+//! Inline images, for terminals that can draw them
+//!
+//! Two protocols cover the terminals that matter: kitty's graphics
+//! protocol (kitty, Ghostty) and iTerm2's inline images (iTerm2, WezTerm).
+//! Neither can be queried without reading a reply from the terminal, so
+//! support is detected from the environment the terminal sets. tmux and
+//! screen would need their passthrough escapes and are left out.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ratatui::layout::Rect;
+use serde::Deserialize;
+
+/// Id of the one image kitty is asked to keep; sending it again replaces it
+const KITTY_IMAGE_ID: u32 = 1;
+/// Base64 bytes per kitty escape sequence, as the protocol requires
+const KITTY_CHUNK: usize = 4096;
+
+/// Whether charts are drawn as images (the `images` setting)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Images {
+    /// When the terminal is known to support a protocol
+    #[default]
+    Auto,
+    Kitty,
+    Iterm,
+    /// Always draw charts with characters
+    Off,
+}
+
+impl Images {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Images::Auto),
+            "kitty" => Some(Images::Kitty),
+            "iterm" => Some(Images::Iterm),
+            "off" => Some(Images::Off),
+            _ => None,
+        }
+    }
+
+    /// The protocol images are drawn with, looking environment variables up
+    /// with `env` when detecting
+    pub fn protocol(self, env: impl Fn(&str) -> Option<String>) -> Option<ImageProtocol> {
+        match self {
+            Images::Auto => detect(env),
+            Images::Kitty => Some(ImageProtocol::Kitty),
+            Images::Iterm => Some(ImageProtocol::Iterm),
+            Images::Off => None,
+        }
+    }
+}
+
+/// How an image is sent to the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageProtocol {
+    Kitty,
+    Iterm,
+}
+
+/// The protocol the terminal running us supports, from what it sets in the
+/// environment
+fn detect(env: impl Fn(&str) -> Option<String>) -> Option<ImageProtocol> {
+    // Inside a multiplexer the outer terminal's variables are inherited, but
+    // the escapes would reach tmux or screen rather than the terminal
+    if env("TMUX").is_some() || env("TERM").is_some_and(|term| term.starts_with("screen") || term.starts_with("tmux")) {
+        return None;
+    }
+    let term = env("TERM").unwrap_or_default();
+    let program = env("TERM_PROGRAM").unwrap_or_default();
+    if env("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || term == "xterm-ghostty" || program == "ghostty" {
+        Some(ImageProtocol::Kitty)
+    } else if program == "iTerm.app" || program == "WezTerm" || env("LC_TERMINAL").as_deref() == Some("iTerm2") {
+        Some(ImageProtocol::Iterm)
+    } else {
+        None
+    }
+}
+
+impl ImageProtocol {
+    /// Escape sequences drawing `png` scaled to fill `area`
+    ///
+    /// The cursor is left where the sequences put it; the caller redraws
+    /// around the image and moves it anyway.
+    pub fn display(self, png: &[u8], area: Rect) -> String {
+        let data = STANDARD.encode(png);
+        let mut out = format!("\x1b[{};{}H", area.y + 1, area.x + 1);
+        match self {
+            ImageProtocol::Kitty => {
+                let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+                for (i, chunk) in chunks.iter().enumerate() {
+                    let more = u8::from(i + 1 < chunks.len());
+                    // Only the first chunk carries the image's keys
+                    let keys = if i == 0 {
+                        format!(
+                            "a=T,f=100,i={},c={},r={},C=1,q=2,m={}",
+                            KITTY_IMAGE_ID, area.width, area.height, more
+                        )
+                    } else {
+                        format!("m={}", more)
+                    };
+                    out += &format!("\x1b_G{};{}\x1b\\", keys, String::from_utf8_lossy(chunk));
+                }
+            }
+            ImageProtocol::Iterm => {
+                out += &format!(
+                    "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+                    png.len(),
+                    area.width,
+                    area.height,
+                    data
+                );
+            }
+        }
+        out
+    }
+
+    /// Escape sequence taking the image off the screen, for protocols whose
+    /// images outlive the text drawn over them
+    ///
+    /// An iTerm2 image is part of the cells it covers and goes when they
+    /// are redrawn.
+    pub fn remove(self) -> Option<String> {
+        match self {
+            ImageProtocol::Kitty => Some(format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_IMAGE_ID)),
+            ImageProtocol::Iterm => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+    }
+
+    #[test]
+    fn test_detect_from_environment() {
+        assert_eq!(detect(env(&[("TERM", "xterm-kitty")])), Some(ImageProtocol::Kitty));
+        assert_eq!(detect(env(&[("TERM", "xterm-256color"), ("KITTY_WINDOW_ID", "3")])), Some(ImageProtocol::Kitty));
+        assert_eq!(detect(env(&[("TERM_PROGRAM", "iTerm.app")])), Some(ImageProtocol::Iterm));
+        assert_eq!(detect(env(&[("LC_TERMINAL", "iTerm2")])), Some(ImageProtocol::Iterm));
+        assert_eq!(detect(env(&[("TERM", "xterm-256color")])), None);
+        assert_eq!(detect(env(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux-1/default,1,0")])), None);
+    }
+
+    #[test]
+    fn test_setting_overrides_detection() {
+        let kitty = env(&[("TERM", "xterm-kitty")]);
+        assert_eq!(Images::Off.protocol(&kitty), None);
+        assert_eq!(Images::Iterm.protocol(&kitty), Some(ImageProtocol::Iterm));
+        assert_eq!(Images::Auto.protocol(&kitty), Some(ImageProtocol::Kitty));
+        assert_eq!(Images::parse("sixel"), None);
+    }
+
+    #[test]
+    fn test_kitty_sends_chunks() {
+        let area = Rect::new(4, 2, 20, 10);
+        let out = ImageProtocol::Kitty.display(&[0; 4000], area);
+        assert!(out.starts_with("\x1b[3;5H\x1b_Ga=T,f=100,i=1,c=20,r=10,C=1,q=2,m=1;"));
+        // 4000 bytes are 5336 in base64: a full chunk and the rest
+        assert_eq!(out.matches("\x1b_G").count(), 2);
+        assert!(out.contains("\x1b_Gm=0;"));
+        assert!(ImageProtocol::Kitty.remove().is_some());
+    }
+
+    #[test]
+    fn test_iterm_sends_one_sequence() {
+        let out = ImageProtocol::Iterm.display(b"png", Rect::new(0, 0, 8, 4));
+        assert_eq!(out, "\x1b[1;1H\x1b]1337;File=inline=1;size=3;width=8;height=4;preserveAspectRatio=1:cG5n\x07");
+        assert_eq!(ImageProtocol::Iterm.remove(), None);
+    }
+}
@@ -0,0 +1,135 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::input::KeyMap;
+use crate::model::BloatThresholds;
+
+use super::ui_fmt;
+
+/// Keys shown in the overlay, grouped by where they apply
+fn sections(keys: &KeyMap) -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+    let k = |c: char| KeyMap::label(c);
+    vec![
+        ("Everywhere", vec![
+            (format!("↑/↓ {}/{}", k(keys.down), k(keys.up)), "move"),
+            (k(keys.search), "search (>10MB, <=1GB and ext:zip filter), Esc returns; Ctrl+S there switches case matching, Ctrl+G groups by directory"),
+            (k(keys.blob_lookup), "look up a blob by OID"),
+            (format!("{} / {}", k(keys.mark), k(keys.clear_marks)), "mark / clear marks"),
+            (k(keys.visual), "select a range to mark in the tree, blobs and search (Ctrl+V there)"),
+            (k(keys.deleted_only), "deleted-only filter (Ctrl+D in search)"),
+            (format!("{} / {}", k(keys.export_csv), k(keys.export_json)), "export the listed rows to CSV / JSON (Ctrl+E / Ctrl+O in search)"),
+            ("Ctrl+Z / Ctrl+Y".to_string(), "undo / redo marks and filters"),
+            (k(keys.rescan), "rescan and badge size changes"),
+            (k(keys.help), "this help"),
+            (k(keys.quit), "quit"),
+        ]),
+        ("Tree", vec![
+            (format!("Enter/→ {}", k(keys.open)), "open directory"),
+            (format!("←/Backspace {}", k(keys.back)), "go back"),
+            (k(keys.largest), "jump to the largest file below"),
+            (k(keys.details), "details panel (side by side when wide enough)"),
+            (k(keys.vendored), "hide generated/vendored files, show only them, show all"),
+            (k(keys.ranking), "deleted only: largest or most recently deleted first"),
+            (k(keys.old_only), "only content older than old_after_years"),
+            (k(keys.preview), "preview the selected file"),
+            (k(keys.copy_command), "copy a command restoring the file"),
+            (k(keys.restore), "restore a deleted file"),
+            (format!("{} / {}", k(keys.bookmark), k(keys.bookmarks)), "bookmark / saved bookmarks"),
+            (format!("{} / {}", k(keys.extensions), k(keys.large_blobs)), "extensions / large blobs"),
+            (k(keys.insights), "cleanup recommendations"),
+            (k(keys.duplicates), "blobs committed at several paths"),
+            (k(keys.heavy_commits), "commits that added the most data"),
+            (k(keys.top_dirs), "sizes of the top-level directories"),
+            (k(keys.flatten), "every path down to a depth, largest first"),
+            (k(keys.diagnostics), "objects and packs the scan could not read"),
+            (k(keys.owners), "size per CODEOWNERS owner"),
+        ]),
+        ("Insights and extensions", vec![
+            (k(keys.lfs_plan), "LFS migration plan"),
+            (k(keys.ranking), "rank extensions by cumulative, current or deleted size"),
+            (k(keys.search), "filter the extension or large blobs list"),
+            ("Enter".to_string(), "largest blobs with the selected extension"),
+        ]),
+        ("Bookmarks", vec![
+            (format!("Enter/→ {}", k(keys.open)), "show in tree"),
+            (format!("Del {}", k(keys.bookmark)), "forget bookmark"),
+        ]),
+        ("Duplicates", vec![
+            (format!("Enter/→ {}", k(keys.open)), "show a copy in the tree"),
+        ]),
+        ("Top-level dirs", vec![
+            (format!("Enter/→ {}", k(keys.open)), "open the directory in the tree"),
+        ]),
+        ("Heavy commits", vec![
+            (format!("Enter/→ {}", k(keys.open)), "show the largest file added in the tree"),
+        ]),
+        ("Diagnostics", vec![
+            (format!("Enter/→ {}", k(keys.open)), "show the tree or blob's path"),
+        ]),
+        ("Owners", vec![
+            (format!("Enter/→ {}", k(keys.open)), "list an owner's files / show in tree"),
+            (format!("Esc/← {}", k(keys.back)), "back to the owners"),
+        ]),
+        ("Large blobs", vec![
+            ("Enter".to_string(), "show in tree"),
+            (
+                format!("{} / {} / {}", k(keys.oid_column), k(keys.author_column), k(keys.date_column)),
+                "OID / author / date columns",
+            ),
+            ("←/→".to_string(), "scroll long paths"),
+            (k(keys.group_similar), "group near-duplicates"),
+            (k(keys.preview), "preview the blob's content"),
+            (k(keys.copy_command), "copy a command restoring the blob"),
+            (k(keys.restore), "restore a deleted blob's file"),
+        ]),
+    ]
+}
+
+/// Centered key reference drawn over the current view
+pub fn render(frame: &mut Frame, thresholds: &BloatThresholds, keys: &KeyMap, area: Rect) {
+    let key_style = Style::default().fg(Color::Yellow);
+    let heading_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+    let sections = sections(keys);
+    // Wide enough that the longest key is still followed by a space
+    let key_width = sections
+        .iter()
+        .flat_map(|(_, bindings)| bindings.iter().map(|(key, _)| key.chars().count() + 1))
+        .max()
+        .unwrap_or(0)
+        .max(15);
+
+    let mut lines = Vec::new();
+    for (heading, bindings) in sections {
+        lines.push(Line::from(Span::styled(heading, heading_style)));
+        for (key, action) in bindings {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<width$}", key, width = key_width), key_style),
+                Span::raw(action),
+            ]));
+        }
+        lines.push(Line::raw(""));
+    }
+    lines.push(ui_fmt::bloat_legend(thresholds));
+    lines.push(Line::from(Span::styled(
+        "  thresholds and keys come from repodiet/config.toml or .repodiet.toml",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let height = lines.len() as u16 + 2;
+    let [popup] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(64)]).flex(Flex::Center).areas(popup);
+
+    let help = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Help (any key to close) "),
+    );
+    frame.render_widget(Clear, popup);
+    frame.render_widget(help, popup);
+}
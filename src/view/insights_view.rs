@@ -0,0 +1,126 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::util::format_size;
+use crate::viewmodel::{InsightsViewModel, SelectionSet};
+
+use super::ui_fmt;
+
+/// Rules listed in the generated/vendored panel at most
+const VENDORED_ROWS: usize = 5;
+
+pub fn render(frame: &mut Frame, vm: &InsightsViewModel, marks: &SelectionSet, area: Rect) {
+    let shares = vm.vendored_shares();
+    // Summary line, a row per rule and the borders
+    let vendored_height = if shares.is_empty() { 0 } else { shares.len().min(VENDORED_ROWS) as u16 + 3 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(vendored_height),  // Generated/vendored share
+            Constraint::Min(0),                   // List
+            Constraint::Length(3),                // Footer
+        ])
+        .split(area);
+
+    if !shares.is_empty() {
+        render_vendored(frame, vm, chunks[0]);
+    }
+    render_list(frame, vm, chunks[1]);
+    render_footer(frame, marks, chunks[2]);
+}
+
+/// How much of history generated and vendored files hold, in all and per
+/// rule tagging them
+fn render_vendored(frame: &mut Frame, vm: &InsightsViewModel, area: Rect) {
+    let shares = vm.vendored_shares();
+    let total = vm.cumulative_size();
+    let cumulative: u64 = shares.iter().map(|s| s.cumulative).sum();
+    let current: u64 = shares.iter().map(|s| s.current).sum();
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format_size(cumulative), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::raw(format!(
+            " ({:.0}% of history) is dependencies, build output, lockfiles or minified code; {} still in HEAD",
+            ui_fmt::percent(cumulative, total),
+            format_size(current)
+        )),
+    ])];
+    lines.extend(shares.iter().take(VENDORED_ROWS).map(|share| {
+        Line::from(vec![
+            Span::styled(format!("{:>11}", format_size(share.cumulative)), Style::default().fg(Color::Green)),
+            Span::raw(format!(" {:>4.0}%  ", ui_fmt::percent(share.cumulative, total))),
+            Span::styled(share.rule.as_str(), Style::default().fg(Color::Cyan)),
+            Span::styled(format!("  {} files", share.files), Style::default().fg(Color::DarkGray)),
+        ])
+    }));
+
+    let panel = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Generated / vendored (f in the tree filters them)"));
+    frame.render_widget(panel, area);
+}
+
+fn render_list(frame: &mut Frame, vm: &InsightsViewModel, area: Rect) {
+    let insights = vm.insights();
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Insights ({}, up to {} reclaimable)",
+        insights.len(),
+        format_size(vm.total_savings())
+    ));
+
+    if insights.is_empty() {
+        let hint = Paragraph::new("Nothing to recommend: no housekeeping is due and no path or extension stands out.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = insights
+        .iter()
+        .map(|insight| {
+            let savings = insight.savings.map_or_else(|| "?".to_string(), |s| format!("~{}", format_size(s)));
+            ListItem::new(vec![
+                Line::from(vec![
+                    Span::styled(format!("{:>11}", savings), Style::default().fg(Color::Green)),
+                    Span::raw("  "),
+                    Span::styled(insight.title.as_str(), Style::default().fg(Color::Yellow)),
+                ]),
+                Line::from(vec![
+                    Span::raw(" ".repeat(13)),
+                    Span::styled(insight.reason.as_str(), Style::default().fg(Color::White)),
+                ]),
+                Line::from(vec![
+                    Span::raw(" ".repeat(13)),
+                    Span::styled(format!("$ {}", insight.command), Style::default().fg(Color::DarkGray)),
+                ]),
+            ])
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("p", Style::default().fg(Color::Yellow)), Span::raw(" LFS plan  "),
+        Span::styled("i/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
+        Span::styled("~", Style::default().fg(Color::Green)), Span::raw(" estimated savings"),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
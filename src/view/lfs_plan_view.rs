@@ -0,0 +1,65 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::model::LfsPlan;
+use crate::util::format_size;
+
+/// Centered LFS migration plan drawn over the current view
+pub fn render(frame: &mut Frame, plan: &LfsPlan, area: Rect) {
+    let heading_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let command_style = Style::default().fg(Color::Yellow);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let mut lines = Vec::new();
+    if plan.is_empty() {
+        lines.push(Line::raw(format!(
+            "No binary extension or file holds {} or more of history.",
+            format_size(plan.min_size)
+        )));
+    } else {
+        lines.push(Line::from(Span::styled("Patterns", heading_style)));
+        for pattern in &plan.patterns {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  ~{:>10}", format_size(pattern.savings())), Style::default().fg(Color::Green)),
+                Span::raw(format!("  {:>6} versions  ", pattern.files)),
+                Span::raw(pattern.pattern.as_str()),
+            ]));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::from(Span::styled("Command", heading_style)));
+        lines.push(Line::from(Span::styled(format!("  {}", plan.command()), command_style)));
+        lines.push(Line::raw(""));
+        lines.push(Line::from(Span::styled(".gitattributes", heading_style)));
+        for attribute in plan.gitattributes().lines() {
+            lines.push(Line::from(Span::styled(format!("  {}", attribute), command_style)));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "  ~{} leaves history; patterns hold at least {} (repodiet lfs-plan --min-size)",
+                format_size(plan.savings()),
+                format_size(plan.min_size)
+            ),
+            dim,
+        )));
+    }
+
+    let width = area.width.saturating_sub(4).min(100);
+    // Long commands wrap; leave them room
+    let height = (lines.len() as u16 + 6).min(area.height);
+    let [popup] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(popup);
+
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" LFS migration plan (any key to close) "),
+    );
+    frame.render_widget(Clear, popup);
+    frame.render_widget(paragraph, popup);
+}
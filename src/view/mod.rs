@@ -2,9 +2,47 @@ mod tree_view;
 mod extension_view;
 mod search_view;
 mod blobs_view;
+mod blob_lookup_view;
+mod bookmarks_view;
+mod insights_view;
+mod duplicates_view;
+mod commits_view;
+mod top_dirs_view;
+mod flatten_view;
+mod diagnostics_view;
+mod owners_view;
+mod error_view;
+mod help_view;
+mod lfs_plan_view;
+mod preview_view;
+mod recovery_view;
+mod restore_view;
+mod notice_view;
+mod donut;
+mod graphics;
+mod theme;
 mod ui_fmt;
 
 pub use tree_view::render as render_tree;
 pub use extension_view::render as render_extension;
 pub use search_view::render as render_search;
 pub use blobs_view::render as render_blobs;
+pub use blob_lookup_view::render as render_blob_lookup;
+pub use bookmarks_view::render as render_bookmarks;
+pub use insights_view::render as render_insights;
+pub use duplicates_view::render as render_duplicates;
+pub use commits_view::render as render_commits;
+pub use top_dirs_view::render as render_top_dirs;
+pub use flatten_view::render as render_flatten;
+pub use diagnostics_view::render as render_diagnostics;
+pub use owners_view::render as render_owners;
+pub use error_view::render as render_error;
+pub use help_view::render as render_help;
+pub use lfs_plan_view::render as render_lfs_plan;
+pub use preview_view::render as render_preview;
+pub use recovery_view::render as render_recovery;
+pub use restore_view::render as render_restore;
+pub use notice_view::render as render_notice;
+pub use theme::{apply as apply_theme, Theme};
+pub use donut::{donut_png, DonutImage};
+pub use graphics::{ImageProtocol, Images};
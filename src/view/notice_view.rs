@@ -0,0 +1,21 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::util::display_width;
+
+/// One-line message centered over the current view, e.g. while a rescan runs
+pub fn render(frame: &mut Frame, message: &str, area: Rect) {
+    let width = (display_width(message) as u16 + 4).min(area.width);
+    let [popup] = Layout::vertical([Constraint::Length(3)]).flex(Flex::Center).areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(popup);
+
+    let notice = Paragraph::new(message)
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(Clear, popup);
+    frame.render_widget(notice, popup);
+}
@@ -0,0 +1,158 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::model::{BloatThresholds, UNOWNED};
+use crate::util::format_size;
+use crate::viewmodel::{OwnersViewModel, SelectionSet};
+
+use super::ui_fmt;
+
+pub fn render(frame: &mut Frame, vm: &OwnersViewModel, marks: &SelectionSet, thresholds: &BloatThresholds, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // Header
+            Constraint::Min(0),     // List
+            Constraint::Length(3),  // Footer
+        ])
+        .split(area);
+
+    render_header(frame, vm, chunks[0]);
+    match vm.open_owner() {
+        Some(_) => render_files(frame, vm, thresholds, chunks[1]),
+        None => render_owners(frame, vm, thresholds, chunks[1]),
+    }
+    render_footer(frame, vm, marks, chunks[2]);
+}
+
+fn render_header(frame: &mut Frame, vm: &OwnersViewModel, area: Rect) {
+    let mut spans = vec![
+        Span::styled("OWNERS", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+        Span::raw(" | "),
+    ];
+    match (vm.source(), vm.open_owner()) {
+        (None, _) => spans.push(Span::raw("No CODEOWNERS file in HEAD (.github/, the root or docs/)")),
+        (Some(_), Some(owner)) => spans.push(Span::raw(format!(
+            "{}: {} cumulative, {} current, {} files",
+            owner.owner,
+            format_size(owner.cumulative_size),
+            format_size(owner.current_size),
+            owner.files.len()
+        ))),
+        (Some(source), None) => {
+            spans.push(Span::raw(format!(
+                "{} owners of {} cumulative, from {}",
+                vm.owners().iter().filter(|o| o.owner != UNOWNED).count(),
+                format_size(vm.total_cumulative()),
+                source
+            )));
+            if !vm.invalid_lines().is_empty() {
+                let lines: Vec<String> = vm.invalid_lines().iter().map(usize::to_string).collect();
+                spans.push(Span::styled(
+                    format!("  (ignored invalid lines {})", lines.join(", ")),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+        }
+    }
+    let header = Paragraph::new(Line::from(spans))
+        .block(Block::default().borders(Borders::ALL).title("Size by Code Owner"));
+    frame.render_widget(header, area);
+}
+
+/// One list row: sizes, share of the total and bloat, then `label`
+fn size_row<'a>(label: Span<'a>, cumulative: u64, current: u64, total: u64, thresholds: &BloatThresholds) -> Line<'a> {
+    let percent = ui_fmt::percent(cumulative, total);
+    let bloat = ui_fmt::bloat_ratio(cumulative, current);
+    Line::from(vec![
+        Span::styled(format!("{:>10}", format_size(cumulative)), Style::default().fg(Color::Cyan)),
+        Span::raw(" "),
+        Span::styled(format!("[{}]", ui_fmt::bar(percent, 20)), Style::default().fg(Color::Blue)),
+        Span::styled(format!(" {:>5.1}%", percent), Style::default().fg(Color::DarkGray)),
+        Span::raw(" "),
+        Span::styled(
+            format!("{:>5}", ui_fmt::bloat_str(cumulative, current)),
+            Style::default().fg(ui_fmt::bloat_color(bloat, thresholds)),
+        ),
+        Span::raw("  "),
+        Span::styled(format!("{:>8}", format_size(current)), Style::default().fg(Color::White)),
+        Span::raw("  "),
+        label,
+    ])
+}
+
+fn render_owners(frame: &mut Frame, vm: &OwnersViewModel, thresholds: &BloatThresholds, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(format!("Owners ({})", vm.owners().len()));
+    if vm.owners().is_empty() {
+        let hint = Paragraph::new("Add a CODEOWNERS file to see what each team's paths cost.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let total = vm.total_cumulative();
+    let items: Vec<ListItem> = vm
+        .owners()
+        .iter()
+        .map(|owner| {
+            let color = if owner.owner == UNOWNED { Color::DarkGray } else { Color::Yellow };
+            let label = Span::styled(
+                format!("{}  ({} files)", owner.owner, owner.files.len()),
+                Style::default().fg(color),
+            );
+            ListItem::new(size_row(label, owner.cumulative_size, owner.current_size, total, thresholds))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_files(frame: &mut Frame, vm: &OwnersViewModel, thresholds: &BloatThresholds, area: Rect) {
+    let Some(owner) = vm.open_owner() else {
+        return;
+    };
+    let items: Vec<ListItem> = owner
+        .files
+        .iter()
+        .map(|file| {
+            let label = Span::styled(file.path.as_str(), Style::default().fg(Color::Yellow));
+            ListItem::new(size_row(label, file.cumulative_size, file.current_size, owner.cumulative_size, thresholds))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.file_index()));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!("Files owned by {}", owner.owner)))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_footer(frame: &mut Frame, vm: &OwnersViewModel, marks: &SelectionSet, area: Rect) {
+    let enter = if vm.open_owner().is_some() { " show in tree  " } else { " owner's files  " };
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(enter),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)), Span::raw(" back  "),
+        Span::styled("O", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
+        Span::styled("CUM", Style::default().fg(Color::Cyan)), Span::raw(" "),
+        Span::styled("BLOAT", Style::default().fg(Color::Green)), Span::raw(" "),
+        Span::styled("CUR", Style::default().fg(Color::White)),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
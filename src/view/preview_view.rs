@@ -0,0 +1,55 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::model::{BlobPreview, PreviewContent};
+use crate::util::format_size;
+
+/// Centered popup with the first lines or bytes of a blob, or why it
+/// couldn't be read
+pub fn render(frame: &mut Frame, preview: Result<&BlobPreview, &str>, area: Rect) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let (title, lines) = match preview {
+        Ok(preview) => {
+            let mut lines = vec![Line::from(vec![
+                Span::styled(format_size(preview.size), Style::default().fg(Color::Cyan)),
+                Span::raw("  "),
+                Span::styled(preview.oid.as_str(), dim),
+            ])];
+            match &preview.content {
+                PreviewContent::Text(text) => {
+                    lines.push(Line::raw(""));
+                    lines.extend(text.iter().map(|line| Line::raw(line.as_str())));
+                }
+                PreviewContent::Binary { kind, lines: dump } => {
+                    let kind = kind.unwrap_or("Binary data of an unrecognized kind");
+                    lines.push(Line::from(Span::styled(kind, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+                    lines.push(Line::raw(""));
+                    lines.extend(dump.iter().map(|line| Line::raw(line.as_str())));
+                }
+            }
+            if preview.truncated {
+                lines.push(Line::from(Span::styled("...", dim)));
+            }
+            (format!(" {} (any key to close) ", preview.path), lines)
+        }
+        Err(error) => (
+            " Preview (any key to close) ".to_string(),
+            vec![Line::from(Span::styled(error, Style::default().fg(Color::Red)))],
+        ),
+    };
+
+    // Wide enough for a hexdump line; long text lines are cut at the border
+    let width = area.width.saturating_sub(4).min(100);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let [popup] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(popup);
+
+    let paragraph = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(Clear, popup);
+    frame.render_widget(paragraph, popup);
+}
@@ -0,0 +1,45 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::model::RecoveryCommands;
+use crate::util::display_width;
+
+/// Centered popup with the commands that restore a blob, or why there are
+/// none
+pub fn render(frame: &mut Frame, recovery: Result<&RecoveryCommands, &str>, area: Rect) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let command = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let (title, lines) = match recovery {
+        Ok(commands) => (
+            format!(" Restore {} (any key to close) ", commands.path),
+            vec![
+                Line::from(Span::styled("Copied to the clipboard (if the terminal allows it):", dim)),
+                Line::from(Span::styled(commands.cat_file.as_str(), command)),
+                Line::raw(""),
+                Line::from(Span::styled("Or print the content:", dim)),
+                Line::from(Span::styled(commands.show.as_str(), command)),
+                Line::raw(""),
+                Line::from(Span::styled("Run them in the repository; they are listed again on exit.", dim)),
+            ],
+        ),
+        Err(error) => (
+            " Restore (any key to close) ".to_string(),
+            vec![Line::from(Span::styled(error, Style::default().fg(Color::Red)))],
+        ),
+    };
+
+    let longest = lines.iter().map(Line::width).max().unwrap_or(0).max(display_width(&title));
+    let width = (longest as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let [popup] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(popup);
+
+    let paragraph = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(Clear, popup);
+    frame.render_widget(paragraph, popup);
+}
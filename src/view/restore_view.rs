@@ -0,0 +1,58 @@
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::model::RestorePlan;
+use crate::util::display_width;
+
+/// Centered popup with the command restoring a deleted file and a prompt
+/// to restore it right away, or why it can't be restored
+pub fn render(frame: &mut Frame, restore: Result<&RestorePlan, &str>, area: Rect) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let command = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let (title, lines) = match restore {
+        Ok(plan) if plan.restored => (
+            format!(" Restore {} (any key to close) ", plan.path),
+            vec![
+                Line::from(Span::styled(format!("Restored {} to the worktree.", plan.path), Style::default().fg(Color::Green))),
+                Line::from(Span::styled("It isn't staged: `git add` it to keep it.", dim)),
+            ],
+        ),
+        Ok(plan) => {
+            let mut lines = vec![
+                Line::from(Span::styled("Copied to the clipboard (if the terminal allows it):", dim)),
+                Line::from(Span::styled(plan.checkout.as_str(), command)),
+                Line::raw(""),
+            ];
+            if plan.overwrites {
+                lines.push(Line::from(Span::styled(
+                    format!("{} exists in the worktree and would be replaced.", plan.path),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            lines.push(Line::from(vec![
+                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" restores it now, any other key closes"),
+            ]));
+            (format!(" Restore {} ", plan.path), lines)
+        }
+        Err(error) => (
+            " Restore (any key to close) ".to_string(),
+            vec![Line::from(Span::styled(error, Style::default().fg(Color::Red)))],
+        ),
+    };
+
+    let longest = lines.iter().map(Line::width).max().unwrap_or(0).max(display_width(&title));
+    let width = (longest as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let [popup] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(popup);
+
+    let paragraph = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(Clear, popup);
+    frame.render_widget(paragraph, popup);
+}
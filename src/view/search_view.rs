@@ -8,8 +8,9 @@ use ratatui::{
     Frame,
 };
 
-use crate::util::format_size;
-use crate::viewmodel::SearchViewModel;
+use crate::model::BloatThresholds;
+use crate::util::display_window;
+use crate::viewmodel::{SearchGroup, SearchResult, SearchRow, SearchViewModel, SelectionSet};
 
 use super::ui_fmt;
 
@@ -40,7 +41,27 @@ fn highlight_matches<'a>(path: &'a str, matches: &[Range<usize>]) -> Vec<Span<'a
     spans
 }
 
-pub fn render(frame: &mut Frame, vm: &SearchViewModel, area: Rect) {
+/// `highlight_matches` on the part of `path` shown in `width` columns when
+/// scrolled `offset` columns back, with "..." where it is cut
+fn highlight_window<'a>(path: &'a str, matches: &[Range<usize>], width: usize, offset: usize) -> Vec<Span<'a>> {
+    let (shown, cut_start, cut_end) = display_window(path, width, offset);
+    let matches: Vec<Range<usize>> = matches
+        .iter()
+        .filter(|m| m.end > shown.start && m.start < shown.end)
+        .map(|m| m.start.max(shown.start) - shown.start..m.end.min(shown.end) - shown.start)
+        .collect();
+    let mut spans = Vec::new();
+    if cut_start {
+        spans.push(Span::raw("..."));
+    }
+    spans.extend(highlight_matches(&path[shown], &matches));
+    if cut_end {
+        spans.push(Span::raw("..."));
+    }
+    spans
+}
+
+pub fn render(frame: &mut Frame, vm: &SearchViewModel, marks: &SelectionSet, thresholds: &BloatThresholds, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -51,8 +72,8 @@ pub fn render(frame: &mut Frame, vm: &SearchViewModel, area: Rect) {
         .split(area);
 
     render_header(frame, vm, chunks[0]);
-    render_results(frame, vm, chunks[1]);
-    render_footer(frame, chunks[2]);
+    render_results(frame, vm, marks, thresholds, chunks[1]);
+    render_footer(frame, marks, chunks[2]);
 }
 
 fn render_header(frame: &mut Frame, vm: &SearchViewModel, area: Rect) {
@@ -64,59 +85,152 @@ fn render_header(frame: &mut Frame, vm: &SearchViewModel, area: Rect) {
             Span::styled("█", Style::default().fg(Color::White)),  // Cursor
         ]),
     ])
-    .block(Block::default().borders(Borders::ALL).title("Search Files (type to filter)"));
+    .block(Block::default().borders(Borders::ALL).title(if vm.is_deleted_only() {
+        format!("Search Deleted Files (type to filter) [DELETED ONLY] [{}]", vm.case().label())
+    } else {
+        format!("Search Files (type to filter) [{}]", vm.case().label())
+    }));
     frame.render_widget(header, area);
 }
 
-fn render_results(frame: &mut Frame, vm: &SearchViewModel, area: Rect) {
+fn render_results(frame: &mut Frame, vm: &SearchViewModel, marks: &SelectionSet, thresholds: &BloatThresholds, area: Rect) {
     let total_cumulative = vm.total_cumulative();
 
+    let grouped = vm.is_grouped();
+    let columns = ui_fmt::fit_size_columns(area.width, ui_fmt::MARK_WIDTH + 1, BAR_WIDTH, MIN_PATH_WIDTH);
+    let mut path_width = (area.width as usize).saturating_sub(ui_fmt::CHROME_WIDTH + ui_fmt::MARK_WIDTH + 1 + columns.width(BAR_WIDTH));
+    if grouped {
+        path_width = path_width.saturating_sub(GROUP_INDENT);
+    }
+    let rows = RowLayout { columns, path_width, scroll: vm.path_scroll(), grouped };
+    let visual = marks.visual_range(vm.selected_index());
     let items: Vec<ListItem> = vm
-        .results()
-        .map(|result| {
-            let percent = ui_fmt::percent(result.cumulative_size, total_cumulative);
-            let bloat = ui_fmt::bloat_ratio(result.cumulative_size, result.current_size);
-            let bloat_str = ui_fmt::bloat_str(result.cumulative_size, result.current_size);
-            let bar = ui_fmt::bar(percent, 15);
-            let bloat_color = ui_fmt::bloat_color(bloat);
-
-            let mut spans = vec![
-                Span::styled(format!("{:>10}", format_size(result.cumulative_size)), Style::default().fg(Color::Cyan)),
-                Span::raw(" "),
-                Span::styled(format!("[{}]", bar), Style::default().fg(Color::Blue)),
-                Span::raw(" "),
-                Span::styled(format!("{:>5}", bloat_str), Style::default().fg(bloat_color)),
-                Span::raw("  "),
-            ];
-            spans.extend(highlight_matches(result.path, result.matches));
-
-            ListItem::new(Line::from(spans))
+        .rows()
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let item = match row {
+                SearchRow::Group(group) => group_item(&group, columns, total_cumulative, thresholds),
+                SearchRow::Match(result) => result_item(&result, &rows, marks, total_cumulative, thresholds),
+            };
+            item.style(ui_fmt::visual_row_style(visual.as_ref().is_some_and(|range| range.contains(&i))))
         })
         .collect();
-    let result_count = items.len();
     let mut list_state = ListState::default();
     list_state.select(Some(vm.selected_index()));
 
+    let title = if grouped {
+        format!("Results ({} matches in {} directories, Enter expands)", vm.total_matches(), vm.group_count())
+    } else if vm.displayed_count() < vm.total_matches() {
+        format!("Results ({} of {} matches, scroll for more)", vm.displayed_count(), vm.total_matches())
+    } else {
+        format!("Results ({} matches)", vm.total_matches())
+    };
+
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(format!(
-            "Results ({}{} matches)",
-            result_count,
-            if result_count >= 100 { "+" } else { "" }
-        )))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
         .highlight_symbol(">> ");
 
     frame.render_stateful_widget(list, area, &mut list_state);
 }
 
-fn render_footer(frame: &mut Frame, area: Rect) {
+/// Width of the share-of-total bar in result rows
+const BAR_WIDTH: usize = 15;
+/// Room paths keep before the bar, bloat and then full sizes give way
+const MIN_PATH_WIDTH: usize = 24;
+/// Indent of matches under their directory while grouping
+const GROUP_INDENT: usize = 4;
+
+/// Size, bar and bloat columns shared by match and group rows
+fn size_spans(
+    columns: ui_fmt::SizeColumns,
+    cumulative: u64,
+    current: u64,
+    total_cumulative: u64,
+    thresholds: &BloatThresholds,
+) -> Vec<Span<'static>> {
+    let mut spans = vec![Span::styled(columns.size(cumulative), Style::default().fg(Color::Cyan)), Span::raw(" ")];
+    if columns.bar {
+        let percent = ui_fmt::percent(cumulative, total_cumulative);
+        spans.push(Span::styled(format!("[{}]", ui_fmt::bar(percent, BAR_WIDTH)), Style::default().fg(Color::Blue)));
+        spans.push(Span::raw(" "));
+    }
+    if columns.bloat {
+        let bloat_color = ui_fmt::bloat_color(ui_fmt::bloat_ratio(cumulative, current), thresholds);
+        spans.push(Span::styled(format!("{:>5}", ui_fmt::bloat_str(cumulative, current)), Style::default().fg(bloat_color)));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::raw(" "));
+    spans
+}
+
+/// How match rows are laid out in the list's width
+struct RowLayout {
+    columns: ui_fmt::SizeColumns,
+    /// Columns left for the path (or name, while grouping)
+    path_width: usize,
+    /// Columns long paths are scrolled back from their end
+    scroll: usize,
+    grouped: bool,
+}
+
+fn result_item<'a>(
+    result: &SearchResult<'a>,
+    rows: &RowLayout,
+    marks: &SelectionSet,
+    total_cumulative: u64,
+    thresholds: &BloatThresholds,
+) -> ListItem<'a> {
+    let mut spans = vec![ui_fmt::mark_span(marks.contains_path(result.path))];
+    spans.extend(size_spans(rows.columns, result.cumulative_size, result.current_size, total_cumulative, thresholds));
+    if rows.grouped {
+        // Under its group, the directory goes without saying
+        let name_start = result.path.rfind('/').map_or(0, |i| i + 1);
+        let matches: Vec<Range<usize>> = result
+            .matches
+            .iter()
+            .filter(|m| m.end > name_start)
+            .map(|m| m.start.max(name_start) - name_start..m.end - name_start)
+            .collect();
+        spans.push(Span::raw(" ".repeat(GROUP_INDENT)));
+        spans.extend(highlight_window(&result.path[name_start..], &matches, rows.path_width, rows.scroll));
+    } else {
+        spans.extend(highlight_window(result.path, result.matches, rows.path_width, rows.scroll));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+fn group_item(
+    group: &SearchGroup<'_>,
+    columns: ui_fmt::SizeColumns,
+    total_cumulative: u64,
+    thresholds: &BloatThresholds,
+) -> ListItem<'static> {
+    let mut spans = vec![Span::raw("  ")];
+    spans.extend(size_spans(columns, group.cumulative_size, group.current_size, total_cumulative, thresholds));
+    let dir = if group.dir.is_empty() { "/".to_string() } else { format!("{}/", group.dir) };
+    spans.push(Span::styled(
+        format!("{} {}", if group.expanded { "▾" } else { "▸" }, dir),
+        Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+    ));
+    spans.push(Span::styled(format!("  ({} matches)", group.count), Style::default().fg(Color::DarkGray)));
+    ListItem::new(Line::from(spans))
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
     let footer = Paragraph::new(Line::from(vec![
         Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("←/→", Style::default().fg(Color::Yellow)), Span::raw(" scroll  "),
         Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" go to  "),
+        Span::styled("Tab", Style::default().fg(Color::Yellow)), Span::raw(" mark  "),
+        Span::styled("^D", Style::default().fg(Color::Yellow)), Span::raw(" deleted  "),
+        Span::styled("^S", Style::default().fg(Color::Yellow)), Span::raw(" case  "),
+        Span::styled("^G", Style::default().fg(Color::Yellow)), Span::raw(" group  "),
         Span::styled("Esc", Style::default().fg(Color::Yellow)), Span::raw(" cancel  "),
         Span::styled("Backspace", Style::default().fg(Color::Yellow)), Span::raw(" delete"),
     ]))
-    .block(Block::default().borders(Borders::ALL));
+    .block(ui_fmt::footer_block(marks));
     frame.render_widget(footer, area);
 }
 
@@ -125,6 +239,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::single_range_in_vec_init)]
     fn test_highlight_matches_single() {
         // "src/main.rs" - "main" is at bytes 4..8
         let spans = highlight_matches("src/main.rs", &[4..8]);
@@ -145,6 +260,23 @@ mod tests {
         assert_eq!(content, "a/b/a.txt");
     }
 
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_highlight_window_clips_matches() {
+        // "file" is at bytes 15..19; ten columns keep "...file.rs"
+        let path = "very/long/path/file.rs";
+        let spans = highlight_window(path, &[15..19], 10, 0);
+        let content: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(content, ["...", "file", ".rs"]);
+
+        // Scrolled back past it, the match is out of view
+        let spans = highlight_window(path, &[15..19], 10, 4);
+        let content: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(content, ["...", "ath/", "..."]);
+        let spans = highlight_window(path, &[0..4], 10, 0);
+        assert_eq!(spans.len(), 2);
+    }
+
     #[test]
     fn test_highlight_matches_empty() {
         let spans = highlight_matches("src/main.rs", &[]);
@@ -0,0 +1,62 @@
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+use serde::Deserialize;
+
+/// Color scheme for the whole UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Colors as drawn by the views
+    #[default]
+    Default,
+    /// No colors; highlighted rows are shown reversed
+    Mono,
+}
+
+impl Theme {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Theme::Default),
+            "mono" => Some(Theme::Mono),
+            _ => None,
+        }
+    }
+}
+
+/// Restyle a finished frame; views always draw with the default palette
+pub fn apply(theme: Theme, buffer: &mut Buffer) {
+    match theme {
+        Theme::Default => {}
+        Theme::Mono => {
+            for cell in buffer.content.iter_mut() {
+                if cell.bg != Color::Reset {
+                    cell.modifier |= Modifier::REVERSED;
+                }
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{layout::Rect, style::Style};
+
+    #[test]
+    fn test_mono_strips_colors() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buffer.set_string(0, 0, "a", Style::default().fg(Color::Red));
+        buffer.set_string(1, 0, "b", Style::default().bg(Color::DarkGray));
+
+        apply(Theme::Mono, &mut buffer);
+
+        assert_eq!(buffer[(0, 0)].fg, Color::Reset);
+        assert!(!buffer[(0, 0)].modifier.contains(Modifier::REVERSED));
+        assert_eq!(buffer[(1, 0)].bg, Color::Reset);
+        assert!(buffer[(1, 0)].modifier.contains(Modifier::REVERSED));
+    }
+}
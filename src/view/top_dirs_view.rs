@@ -0,0 +1,89 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::util::{format_size, pad_right, truncate_end};
+use crate::viewmodel::{MarkKey, SelectionSet, TopDirsViewModel};
+
+use super::ui_fmt;
+
+const NAME_WIDTH: usize = 24;
+/// Width of the share-of-total bar
+const BAR_WIDTH: usize = 20;
+
+pub fn render(frame: &mut Frame, vm: &TopDirsViewModel, marks: &SelectionSet, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),    // List
+            Constraint::Length(3), // Footer
+        ])
+        .split(area);
+
+    render_list(frame, vm, marks, chunks[0]);
+    render_footer(frame, marks, chunks[1]);
+}
+
+fn render_list(frame: &mut Frame, vm: &TopDirsViewModel, marks: &SelectionSet, area: Rect) {
+    let dirs = vm.dirs();
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Top-level directories ({} of {} cumulative, {} current, {} deleted)",
+        dirs.len(),
+        format_size(vm.total_cumulative()),
+        format_size(vm.total_current()),
+        format_size(vm.total_deleted())
+    ));
+
+    if dirs.is_empty() {
+        let hint = Paragraph::new("The repository root holds no directories.")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(hint, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = dirs
+        .iter()
+        .map(|dir| {
+            let percent = ui_fmt::percent(dir.cumulative_size, vm.total_cumulative());
+            ListItem::new(Line::from(vec![
+                ui_fmt::mark_span(marks.contains(&MarkKey::Path(dir.name.clone()))),
+                Span::styled(pad_right(&truncate_end(&dir.name, NAME_WIDTH), NAME_WIDTH + 1), Style::default().fg(Color::Yellow)),
+                Span::styled(format!("{:>10} ", format_size(dir.cumulative_size)), Style::default().fg(Color::Cyan)),
+                Span::styled(format!("[{}]", ui_fmt::bar(percent, BAR_WIDTH)), Style::default().fg(Color::Blue)),
+                Span::styled(format!("{:>6.1}%", percent), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{:>11}", format_size(dir.current_size)), Style::default().fg(Color::White)),
+                Span::styled(format!("{:>11}", format_size(dir.deleted_size)), Style::default().fg(Color::Magenta)),
+            ]))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(vm.selected_index()));
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)), Span::raw(" open in tree  "),
+        Span::styled("1/Esc", Style::default().fg(Color::Yellow)), Span::raw(" tree  "),
+        Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit  |  "),
+        Span::styled("CUM", Style::default().fg(Color::Cyan)), Span::raw(" "),
+        Span::styled("SHARE", Style::default().fg(Color::DarkGray)), Span::raw(" "),
+        Span::styled("CUR", Style::default().fg(Color::White)), Span::raw(" "),
+        Span::styled("DEL", Style::default().fg(Color::Magenta)),
+    ]))
+    .block(ui_fmt::footer_block(marks));
+    frame.render_widget(footer, area);
+}
@@ -2,70 +2,227 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Sparkline},
     Frame,
 };
 
-use crate::util::format_size;
-use crate::viewmodel::TreeViewModel;
+use crate::model::{BloatThresholds, PackHealth, SizeHistory};
+use crate::util::{format_quarter, format_size, format_timestamp, truncate_start};
+use crate::viewmodel::{BiggestFile, BookmarksViewModel, SelectionSet, TreeViewModel, VendoredFilter};
 
 use super::ui_fmt;
 
-pub fn render(frame: &mut Frame, vm: &TreeViewModel, area: Rect) {
+/// Width of the details side panel, borders included
+const DETAILS_WIDTH: u16 = 40;
+/// The side panel is only shown beside a list keeping at least this much
+/// room; narrower terminals show it instead of the list when toggled
+const MIN_LIST_WIDTH: u16 = 70;
+/// Rows of the growth sparkline in the details panel, labels included
+const HISTORY_HEIGHT: u16 = 8;
+/// Width of the share-of-total bar in list rows
+const BAR_WIDTH: usize = 20;
+/// Room names keep before the bar, bloat and then full sizes give way
+const MIN_NAME_WIDTH: usize = 24;
+/// Corrupt packs listed by name before the rest are only counted
+const MAX_PACKS_SHOWN: usize = 3;
+
+pub fn render(
+    frame: &mut Frame,
+    vm: &TreeViewModel,
+    marks: &SelectionSet,
+    bookmarks: &BookmarksViewModel,
+    thresholds: &BloatThresholds,
+    area: Rect,
+) {
+    let biggest = vm.biggest_file();
+    let health = pack_health_lines(vm.pack_health());
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),  // Header
+            Constraint::Length(3 + u16::from(biggest.is_some()) + u16::from(vm.git_dir_usage().is_some())),  // Header
+            Constraint::Length(if health.is_empty() { 0 } else { health.len() as u16 + 2 }),  // Pack health
             Constraint::Min(0),     // List
-            Constraint::Length(3),  // Footer
+            Constraint::Length(4),  // Footer + legend
         ])
         .split(area);
 
-    render_header(frame, vm, chunks[0]);
-    render_list(frame, vm, chunks[1]);
-    render_footer(frame, chunks[2]);
+    render_header(frame, vm, biggest.as_ref(), chunks[0]);
+    if !health.is_empty() {
+        let panel = Paragraph::new(health).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title(Span::styled("Incomplete sizes", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))),
+        );
+        frame.render_widget(panel, chunks[1]);
+    }
+    let chunks = [chunks[0], chunks[2], chunks[3]];
+    let wide = chunks[1].width >= MIN_LIST_WIDTH + DETAILS_WIDTH;
+    let has_details = vm.selected_history().is_some() || vm.selected_details().is_some();
+    match (wide, vm.is_details_toggled()) {
+        (true, false) if has_details => {
+            let [list, panel] = Layout::horizontal([
+                Constraint::Min(0),
+                Constraint::Length(DETAILS_WIDTH),
+            ])
+            .areas(chunks[1]);
+            render_list(frame, vm, marks, bookmarks, thresholds, list);
+            render_details(frame, vm, panel);
+        }
+        (false, true) => render_details(frame, vm, chunks[1]),
+        _ => render_list(frame, vm, marks, bookmarks, thresholds, chunks[1]),
+    }
+    render_footer(frame, marks, thresholds, chunks[2]);
 }
 
-fn render_header(frame: &mut Frame, vm: &TreeViewModel, area: Rect) {
+fn render_header(frame: &mut Frame, vm: &TreeViewModel, biggest: Option<&BiggestFile>, area: Rect) {
     let current_path = vm.current_path();
     let current = vm.current_node();
 
     let header_text = if vm.is_deleted_only() {
         let deleted_size = current.deleted_cumulative_size();
-        format!("Deleted: {} (reclaimable) [DELETED ONLY]", format_size(deleted_size))
+        let order = if vm.is_sorted_by_deletion_date() { ", NEWEST FIRST" } else { "" };
+        format!("Deleted: {} (reclaimable) [DELETED ONLY{}]", format_size(deleted_size), order)
     } else {
         let bloat = if current.current_size > 0 {
             current.cumulative_size as f64 / current.current_size as f64
         } else {
             f64::INFINITY
         };
-        format!("Cumulative: {} | Current: {} | Bloat: {:.1}x",
+        let mut text = format!("Cumulative: {} | Current: {} | Bloat: {:.1}x",
             format_size(current.cumulative_size),
             format_size(current.current_size),
-            bloat)
+            bloat);
+        if let Some(binary) = vm.binary_size() {
+            text.push_str(&format!(" | Binary: {} ({:.0}%)",
+                format_size(binary),
+                ui_fmt::percent(binary, current.cumulative_size)));
+        }
+        text
+    };
+    let header_text = match vm.vendored_filter() {
+        VendoredFilter::All => header_text,
+        VendoredFilter::Hide => format!("{} [GENERATED/VENDORED HIDDEN]", header_text),
+        VendoredFilter::Only => format!("{} [GENERATED/VENDORED ONLY]", header_text),
+    };
+    let header_text = match (vm.is_old_only(), vm.old_size()) {
+        (false, _) => header_text,
+        (true, None) => format!("{} [OLDER THAN {} YEARS]", header_text, vm.old_after_years()),
+        (true, Some(old)) => {
+            let total = if vm.is_deleted_only() { current.deleted_cumulative_size() } else { current.cumulative_size };
+            format!("{} [OLDER THAN {} YEARS: {}, {:.0}%]",
+                header_text,
+                vm.old_after_years(),
+                format_size(old),
+                ui_fmt::percent(old, total))
+        }
     };
 
-    let header = Paragraph::new(vec![
+    let mut lines = vec![
         Line::from(vec![
             Span::raw("Path: "),
             Span::styled(&current_path, Style::default().fg(Color::Yellow)),
             Span::raw(" | "),
             Span::raw(header_text),
         ]),
-    ])
-    .block(Block::default().borders(Borders::ALL).title("repodiet"));
+    ];
+    if let Some(usage) = vm.git_dir_usage() {
+        lines.push(Line::from(format!(
+            ".git on disk: {} = packs {} + loose {} + refs {} + index {} + reflogs {} + other {}",
+            format_size(usage.total()),
+            format_size(usage.packs),
+            format_size(usage.loose_objects),
+            format_size(usage.refs),
+            format_size(usage.index),
+            format_size(usage.reflogs),
+            format_size(usage.other),
+        )));
+    }
+    if let Some(biggest) = biggest {
+        lines.push(Line::from(vec![
+            Span::raw("Biggest: "),
+            Span::styled(biggest.path.as_str(), Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" \u{2014} {:.0}% ({})", biggest.percent, format_size(biggest.size))),
+        ]));
+    }
+
+    let header = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(header_title(vm)));
     frame.render_widget(header, area);
 }
 
-fn render_list(frame: &mut Frame, vm: &TreeViewModel, area: Rect) {
+/// Contents of the incomplete sizes panel; none when every pack was read
+/// and every blob found
+fn pack_health_lines(health: &PackHealth) -> Vec<Line<'static>> {
+    if health.is_healthy() {
+        return Vec::new();
+    }
+    let mut lines: Vec<Line> = health
+        .corrupt_packs
+        .iter()
+        .take(MAX_PACKS_SHOWN)
+        .map(|pack| Line::from(Span::styled(pack.clone(), Style::default().fg(Color::Red))))
+        .collect();
+    if health.corrupt_packs.len() > MAX_PACKS_SHOWN {
+        lines.push(Line::from(format!("... and {} more packs", health.corrupt_packs.len() - MAX_PACKS_SHOWN)));
+    }
+    if health.unknown_sizes > 0 {
+        lines.push(Line::from(format!(
+            "{} objects have unknown sizes and count as 0 B; sizes below are too small",
+            health.unknown_sizes
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        "`!` lists them. Repair with `git fsck` or a fresh clone, then `repodiet cache clear` and rescan",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines
+}
+
+/// "repodiet", plus the checked out branch, local vs remote pack sizes when
+/// known and a warning when only part of the history was scanned or readable
+fn header_title(vm: &TreeViewModel) -> String {
+    let mut title = "repodiet".to_string();
+    if let Some(checkout) = vm.checkout() {
+        title.push_str(&format!(" | {}", checkout));
+    }
+    if let Some(hint) = vm.storage_hint() {
+        title.push_str(&format!(" | packs: {} local", format_size(hint.local_pack_size)));
+        if let Some(remote) = &hint.remote {
+            title.push_str(&format!(", {} on {}", format_size(remote.repository_size), remote.host));
+            if let Some(lfs) = remote.lfs_size {
+                title.push_str(&format!(" (+{} LFS)", format_size(lfs)));
+            }
+        }
+    }
+    if let Some(limits) = vm.scan_limits() {
+        title.push_str(&format!(" | PARTIAL: {}", limits));
+    }
+    if vm.skipped_objects() > 0 {
+        title.push_str(&format!(" | {} unreadable objects skipped (! to list)", vm.skipped_objects()));
+    }
+    title
+}
+
+fn render_list(
+    frame: &mut Frame,
+    vm: &TreeViewModel,
+    marks: &SelectionSet,
+    bookmarks: &BookmarksViewModel,
+    thresholds: &BloatThresholds,
+    area: Rect,
+) {
     let show_deleted = vm.is_deleted_only();
     let total_for_percent = vm.total_for_percent();
     let children = vm.visible_children();
     let num_children = children.len();
+    let columns = ui_fmt::fit_size_columns(area.width, ui_fmt::MARK_WIDTH + 2, BAR_WIDTH, MIN_NAME_WIDTH);
+    let visual = marks.visual_range(vm.selected_index());
 
     let items: Vec<ListItem> = children
         .iter()
-        .map(|node| {
+        .enumerate()
+        .map(|(row, node)| {
             let percent = ui_fmt::percent(node.display_size, total_for_percent);
 
             let bloat_str = if show_deleted {
@@ -74,21 +231,51 @@ fn render_list(frame: &mut Frame, vm: &TreeViewModel, area: Rect) {
                 ui_fmt::bloat_str(node.display_size, node.current_size)
             };
 
-            let bar = ui_fmt::bar(percent, 20);
-
             let prefix = if node.has_children { "▸ " } else { "  " };
             let size_color = if show_deleted { Color::Magenta } else { Color::Cyan };
+            let path = vm.child_path(&node.name);
+            let marked = marks.contains_path(&path);
+            let bloat = ui_fmt::bloat_ratio(node.cumulative_size, node.current_size);
+            let name_color = ui_fmt::bloat_color(bloat, thresholds);
 
-            ListItem::new(Line::from(vec![
+            let mut spans = vec![
+                ui_fmt::mark_span(marked),
                 Span::raw(prefix),
-                Span::styled(format!("{:>10}", format_size(node.display_size)), Style::default().fg(size_color)),
+                Span::styled(columns.size(node.display_size), Style::default().fg(size_color)),
                 Span::raw(" "),
-                Span::styled(format!("[{}]", bar), Style::default().fg(Color::Blue)),
-                Span::raw(" "),
-                Span::styled(format!("{:>5}", bloat_str), Style::default().fg(Color::Red)),
-                Span::raw(" "),
-                Span::raw(&node.name),
-            ]))
+            ];
+            if columns.bar {
+                spans.push(Span::styled(format!("[{}]", ui_fmt::bar(percent, BAR_WIDTH)), Style::default().fg(Color::Blue)));
+                spans.push(Span::raw(" "));
+            }
+            if columns.bloat {
+                spans.push(Span::styled(format!("{:>5}", bloat_str), Style::default().fg(Color::Red)));
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::styled(&node.name, Style::default().fg(name_color)));
+            if let Some(tag) = ui_fmt::entry_kind_tag(node.kind) {
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(tag, Style::default().fg(Color::DarkGray)));
+            }
+            if vm.has_deletion_dates() {
+                // Nothing removed what never reached the scanned history's
+                // main line, e.g. files added and dropped on a merged branch
+                let deleted = node.deleted_at.map_or("never current".to_string(), |date| format!("deleted {}", format_timestamp(date)));
+                spans.push(Span::styled(format!(" {}", deleted), Style::default().fg(Color::DarkGray)));
+            }
+            if node.size_delta != 0 {
+                // Growth is what a rescan is usually looking for
+                let (sign, color) = if node.size_delta > 0 { ("+", Color::Yellow) } else { ("-", Color::Green) };
+                spans.push(Span::styled(
+                    format!(" {}{}", sign, format_size(node.size_delta.unsigned_abs())),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ));
+            }
+            if bookmarks.contains(&path) {
+                spans.push(Span::styled(" [bookmark]", Style::default().fg(Color::Yellow)));
+            }
+            let in_range = visual.as_ref().is_some_and(|range| range.contains(&row));
+            ListItem::new(Line::from(spans)).style(ui_fmt::visual_row_style(in_range))
         })
         .collect();
 
@@ -103,17 +290,114 @@ fn render_list(frame: &mut Frame, vm: &TreeViewModel, area: Rect) {
     frame.render_stateful_widget(list, area, &mut list_state);
 }
 
-fn render_footer(frame: &mut Frame, area: Rect) {
-    let footer = Paragraph::new(Line::from(vec![
+/// The selected entry's growth and, for a directory, what it holds: sizes
+/// per extension and its largest files
+fn render_details(frame: &mut Frame, vm: &TreeViewModel, area: Rect) {
+    let selected = vm.visible_children().get(vm.selected_index()).map(|c| (c.name.clone(), c.display_size));
+    let title = match &selected {
+        Some((name, _)) => format!("Details: {}", name),
+        None => "Details".to_string(),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let history = vm.selected_history();
+    let [chart, rest] = Layout::vertical([
+        Constraint::Length(if history.is_some() { HISTORY_HEIGHT } else { 0 }),
+        Constraint::Min(0),
+    ])
+    .areas(inner);
+    if let Some(history) = history {
+        render_history(frame, history, chart);
+    }
+
+    let Some(details) = vm.selected_details() else {
+        return;
+    };
+    let total = selected.map_or(0, |(_, size)| size);
+    let dim = Style::default().fg(Color::DarkGray);
+    let heading = |text: &'static str| Line::from(Span::styled(text, Style::default().add_modifier(Modifier::BOLD)));
+    let mut lines = vec![heading("By extension")];
+    for (extension, size) in &details.extensions {
+        let percent = ui_fmt::percent(*size, total);
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:>10}", format_size(*size)), Style::default().fg(Color::Cyan)),
+            Span::styled(format!(" {:>3.0}% ", percent), dim),
+            Span::styled(ui_fmt::bar(percent, 8), Style::default().fg(Color::Blue)),
+            Span::raw(format!(" {}", extension)),
+        ]));
+    }
+    lines.push(Line::raw(""));
+    lines.push(heading("Largest inside"));
+    let dir = vm.selected_path().unwrap_or_default();
+    for (path, size) in &details.largest {
+        let relative = path.strip_prefix(dir.as_str()).map_or(path.as_str(), |p| p.trim_start_matches('/'));
+        // Keep the file names of deep paths in view
+        let shown = truncate_start(relative, (rest.width as usize).saturating_sub(11));
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:>10}", format_size(*size)), Style::default().fg(Color::Cyan)),
+            Span::raw(format!(" {}", shown)),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), rest);
+}
+
+/// Sparkline of the selected entry's cumulative size, one bar per quarter,
+/// with its span and recent growth below
+fn render_history(frame: &mut Frame, history: &SizeHistory, area: Rect) {
+    let [title, chart, labels] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(3)]).areas(area);
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled("Growth by quarter", Style::default().add_modifier(Modifier::BOLD)))),
+        title,
+    );
+
+    // Most recent quarters win when the history is wider than the panel
+    let shown = history.values.len().min(chart.width as usize);
+    let values = &history.values[history.values.len() - shown..];
+    let first_shown = history.end() - shown as i32 + 1;
+    frame.render_widget(
+        Sparkline::default().data(values).style(Style::default().fg(Color::Cyan)),
+        chart,
+    );
+
+    let total = values.last().copied().unwrap_or(0);
+    let year_ago = values.len().checked_sub(5).map(|i| values[i]).unwrap_or(0);
+    let text = Paragraph::new(vec![
+        Line::from(Span::styled(
+            format!("{} → {}", format_quarter(first_shown), format_quarter(history.end())),
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(vec![
+            Span::raw("total "),
+            Span::styled(format_size(total), Style::default().fg(Color::Cyan)),
+        ]),
+        Line::from(vec![
+            Span::raw("last 4 quarters "),
+            Span::styled(format!("+{}", format_size(total - year_ago)), Style::default().fg(Color::Yellow)),
+        ]),
+    ]);
+    frame.render_widget(text, labels);
+}
+
+fn render_footer(frame: &mut Frame, marks: &SelectionSet, thresholds: &BloatThresholds, area: Rect) {
+    let footer = Paragraph::new(vec![Line::from(vec![
         Span::styled("↑/↓", Style::default().fg(Color::Yellow)), Span::raw(" nav  "),
         Span::styled("Enter/→", Style::default().fg(Color::Yellow)), Span::raw(" enter  "),
         Span::styled("←", Style::default().fg(Color::Yellow)), Span::raw(" back  "),
         Span::styled("/", Style::default().fg(Color::Yellow)), Span::raw(" search  "),
         Span::styled("d", Style::default().fg(Color::Yellow)), Span::raw(" deleted  "),
+        Span::styled("f", Style::default().fg(Color::Yellow)), Span::raw(" generated  "),
         Span::styled("t", Style::default().fg(Color::Yellow)), Span::raw(" types  "),
+        Span::styled("b", Style::default().fg(Color::Yellow)), Span::raw(" details  "),
         Span::styled("L", Style::default().fg(Color::Yellow)), Span::raw(" blobs  "),
+        Span::styled("Space/u", Style::default().fg(Color::Yellow)), Span::raw(" mark/clear  "),
+        Span::styled("m/'", Style::default().fg(Color::Yellow)), Span::raw(" bookmarks  "),
+        Span::styled("r", Style::default().fg(Color::Yellow)), Span::raw(" rescan  "),
+        Span::styled("?", Style::default().fg(Color::Yellow)), Span::raw(" help  "),
         Span::styled("q", Style::default().fg(Color::Yellow)), Span::raw(" quit"),
-    ]))
-    .block(Block::default().borders(Borders::ALL));
+    ]), ui_fmt::bloat_legend(thresholds)])
+    .block(ui_fmt::footer_block(marks));
     frame.render_widget(footer, area);
 }
@@ -1,4 +1,73 @@
-use ratatui::style::Color;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders},
+};
+
+use crate::model::{BloatLevel, BloatThresholds, EntryKind};
+use crate::util::{format_size, format_size_short};
+use crate::viewmodel::{ListFilter, SelectionSet};
+
+/// Width of the mark column at the start of list rows
+pub const MARK_WIDTH: usize = 2;
+/// List borders plus the ">> " highlight symbol
+pub const CHROME_WIDTH: usize = 2 + 3;
+/// Width of a size column, full and on narrow terminals
+const SIZE_WIDTH: usize = 10;
+const SHORT_SIZE_WIDTH: usize = 5;
+/// Width of a bloat column ("12.3x", "DEL")
+const BLOAT_WIDTH: usize = 5;
+
+/// Which of a list row's size, bar and bloat columns fit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeColumns {
+    pub bar: bool,
+    pub bloat: bool,
+    /// Sizes as `format_size_short`
+    pub compact: bool,
+}
+
+impl SizeColumns {
+    /// Cells the columns take with a bar of `bar_width`, separators included
+    pub fn width(&self, bar_width: usize) -> usize {
+        let mut width = if self.compact { SHORT_SIZE_WIDTH } else { SIZE_WIDTH } + 1;
+        if self.bar {
+            width += bar_width + 2 + 1;
+        }
+        if self.bloat {
+            width += BLOAT_WIDTH + 1;
+        }
+        width
+    }
+
+    /// `bytes` right-aligned in the size column
+    pub fn size(&self, bytes: u64) -> String {
+        if self.compact {
+            format!("{:>5}", format_size_short(bytes))
+        } else {
+            format!("{:>10}", format_size(bytes))
+        }
+    }
+}
+
+/// Size columns that leave `min_rest` cells of a list `area_width` wide
+/// for the rest of the row, after `fixed` cells of other columns: the bar
+/// goes first, then the bloat ratio, then sizes are shortened
+pub fn fit_size_columns(area_width: u16, fixed: usize, bar_width: usize, min_rest: usize) -> SizeColumns {
+    let available = (area_width as usize).saturating_sub(CHROME_WIDTH + fixed);
+    let mut columns = SizeColumns { bar: true, bloat: true, compact: false };
+    for step in 0..3 {
+        if available.saturating_sub(columns.width(bar_width)) >= min_rest {
+            break;
+        }
+        match step {
+            0 => columns.bar = false,
+            1 => columns.bloat = false,
+            _ => columns.compact = true,
+        }
+    }
+    columns
+}
 
 /// Create a progress bar string with filled and empty blocks
 pub fn bar(percent: f64, width: usize) -> String {
@@ -7,6 +76,37 @@ pub fn bar(percent: f64, width: usize) -> String {
     "█".repeat(filled) + &"░".repeat(width.saturating_sub(filled))
 }
 
+/// Cells of a `width`-wide bar given to the current and deleted parts of
+/// `cumulative`; what's left stands for older revisions of current files
+///
+/// A non-zero part always gets at least one cell so it stays visible.
+pub fn breakdown_cells(cumulative: u64, current: u64, deleted: u64, width: usize) -> (usize, usize) {
+    if cumulative == 0 {
+        return (0, 0);
+    }
+    let cells = |part: u64| {
+        let n = (part as f64 / cumulative as f64 * width as f64).round() as usize;
+        if part > 0 { n.max(1) } else { n }
+    };
+    let current_cells = cells(current).min(width);
+    let deleted_cells = cells(deleted).min(width - current_cells);
+    (current_cells, deleted_cells)
+}
+
+/// Bar splitting `cumulative` into current (white), deleted (magenta) and
+/// older-revision (gray) segments
+pub fn breakdown_bar(cumulative: u64, current: u64, deleted: u64, width: usize) -> Vec<Span<'static>> {
+    let (current_cells, deleted_cells) = breakdown_cells(cumulative, current, deleted, width);
+    let rest = width - current_cells - deleted_cells;
+    vec![
+        Span::raw("["),
+        Span::styled("█".repeat(current_cells), Style::default().fg(Color::White)),
+        Span::styled("█".repeat(deleted_cells), Style::default().fg(Color::Magenta)),
+        Span::styled("░".repeat(rest), Style::default().fg(Color::DarkGray)),
+        Span::raw("]"),
+    ]
+}
+
 /// Format bloat ratio as a display string
 pub fn bloat_str(cumulative: u64, current: u64) -> String {
     if current == 0 && cumulative > 0 {
@@ -37,19 +137,131 @@ pub fn bloat_ratio(cumulative: u64, current: u64) -> f64 {
 }
 
 /// Get color based on bloat ratio thresholds
-pub fn bloat_color(bloat: f64) -> Color {
-    if bloat > 50.0 {
-        Color::Red
-    } else if bloat > 20.0 {
-        Color::Yellow
+pub fn bloat_color(bloat: f64, thresholds: &BloatThresholds) -> Color {
+    match thresholds.level(bloat) {
+        BloatLevel::Severe => Color::Red,
+        BloatLevel::Warn => Color::Yellow,
+        BloatLevel::Low => Color::Green,
+    }
+}
+
+/// Key to the `bloat_color` thresholds
+pub fn bloat_legend(thresholds: &BloatThresholds) -> Line<'static> {
+    Line::from(vec![
+        Span::styled("Bloat: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(format!("■ ≤{}x", thresholds.warn), Style::default().fg(Color::Green)),
+        Span::raw("  "),
+        Span::styled(format!("■ ≤{}x", thresholds.severe), Style::default().fg(Color::Yellow)),
+        Span::raw("  "),
+        Span::styled(format!("■ >{}x or deleted", thresholds.severe), Style::default().fg(Color::Red)),
+    ])
+}
+
+/// Tag shown after an entry's name for anything but a regular file, so a
+/// tiny symlink or an empty-looking submodule isn't mistaken for a file
+pub fn entry_kind_tag(kind: EntryKind) -> Option<&'static str> {
+    match kind {
+        EntryKind::File => None,
+        EntryKind::Executable => Some("[exec]"),
+        EntryKind::Symlink => Some("[symlink]"),
+        EntryKind::Gitlink => Some("[submodule]"),
+    }
+}
+
+/// Mark column for a list row
+pub fn mark_span(marked: bool) -> Span<'static> {
+    if marked {
+        Span::styled("* ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
     } else {
-        Color::Green
+        Span::raw("  ")
+    }
+}
+
+/// A list's filter for its header: nothing unless it is typed or set
+pub fn filter_spans(filter: &ListFilter) -> Vec<Span<'static>> {
+    if !filter.is_editing() && !filter.is_active() {
+        return Vec::new();
+    }
+    let mut spans = vec![
+        Span::raw(" | "),
+        Span::styled("/", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+        Span::styled(filter.text().to_string(), Style::default().fg(Color::Yellow)),
+    ];
+    if filter.is_editing() {
+        spans.push(Span::styled("█", Style::default().fg(Color::White)));
+    }
+    spans
+}
+
+/// Summary of marked entries, e.g. "7 items marked, 1.4 GB cumulative, 320 MB current"
+pub fn marks_summary(marks: &SelectionSet) -> Option<String> {
+    if marks.is_empty() {
+        return None;
+    }
+    let totals = marks.totals();
+    let noun = if marks.len() == 1 { "item" } else { "items" };
+    Some(format!("{} {} marked, {} cumulative, {} current",
+        marks.len(),
+        noun,
+        format_size(totals.cumulative),
+        format_size(totals.current)))
+}
+
+/// Background of rows inside the range being selected in visual mode
+pub fn visual_row_style(in_range: bool) -> Style {
+    if in_range {
+        Style::default().bg(Color::Rgb(40, 50, 80))
+    } else {
+        Style::default()
+    }
+}
+
+/// Bordered footer block, titled with the mark summary when anything is
+/// marked and flagged while a visual range is being selected
+pub fn footer_block(marks: &SelectionSet) -> Block<'static> {
+    let mut block = Block::default().borders(Borders::ALL);
+    if marks.is_visual() {
+        block = block.title(Span::styled(
+            " VISUAL ",
+            Style::default().fg(Color::Black).bg(Color::Magenta).add_modifier(Modifier::BOLD),
+        ));
+    }
+    match marks_summary(marks) {
+        Some(summary) => block.title(Span::styled(
+            format!(" {} ", summary),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )),
+        None => block,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::viewmodel::{MarkKey, MarkSizes};
+
+    #[test]
+    fn test_marks_summary() {
+        let mut marks = SelectionSet::new();
+        assert_eq!(marks_summary(&marks), None);
+
+        marks.toggle(MarkKey::Path("a".into()), MarkSizes { cumulative: 2048, current: 1024 });
+        assert_eq!(marks_summary(&marks).unwrap(), "1 item marked, 2.0 KB cumulative, 1.0 KB current");
+
+        marks.toggle(MarkKey::Path("b".into()), MarkSizes { cumulative: 2048, current: 0 });
+        assert!(marks_summary(&marks).unwrap().starts_with("2 items marked, 4.0 KB cumulative"));
+    }
+
+    #[test]
+    fn test_fit_size_columns() {
+        let full = SizeColumns { bar: true, bloat: true, compact: false };
+        assert_eq!(fit_size_columns(200, 2, 20, 24), full);
+        let width = (CHROME_WIDTH + 2 + full.width(20) + 24) as u16;
+        assert_eq!(fit_size_columns(width, 2, 20, 24), full);
+        assert_eq!(fit_size_columns(width - 1, 2, 20, 24), SizeColumns { bar: false, ..full });
+        assert_eq!(fit_size_columns(30, 2, 20, 24), SizeColumns { bar: false, bloat: false, compact: true });
+        assert_eq!(SizeColumns { compact: true, ..full }.size(1536), " 1.5K");
+    }
 
     #[test]
     fn test_bar() {
@@ -64,6 +276,23 @@ mod tests {
         assert_eq!(bar(150.0, 10), "██████████");
     }
 
+    #[test]
+    fn test_breakdown_cells() {
+        assert_eq!(breakdown_cells(1000, 500, 0, 10), (5, 0));
+        assert_eq!(breakdown_cells(1000, 0, 1000, 10), (0, 10));
+        assert_eq!(breakdown_cells(1000, 300, 500, 10), (3, 5));
+        // Tiny parts stay visible, and the bar never overflows
+        assert_eq!(breakdown_cells(1000, 1, 999, 10), (1, 9));
+        assert_eq!(breakdown_cells(0, 0, 0, 10), (0, 0));
+    }
+
+    #[test]
+    fn test_entry_kind_tag() {
+        assert_eq!(entry_kind_tag(EntryKind::File), None);
+        assert_eq!(entry_kind_tag(EntryKind::Symlink), Some("[symlink]"));
+        assert_eq!(entry_kind_tag(EntryKind::Gitlink), Some("[submodule]"));
+    }
+
     #[test]
     fn test_bloat_str() {
         assert_eq!(bloat_str(100, 50), "2.0x");
@@ -89,10 +318,14 @@ mod tests {
 
     #[test]
     fn test_bloat_color() {
-        assert_eq!(bloat_color(60.0), Color::Red);
-        assert_eq!(bloat_color(30.0), Color::Yellow);
-        assert_eq!(bloat_color(10.0), Color::Green);
-        assert_eq!(bloat_color(50.0), Color::Yellow);
-        assert_eq!(bloat_color(20.0), Color::Green);
+        let t = BloatThresholds::default();
+        assert_eq!(bloat_color(60.0, &t), Color::Red);
+        assert_eq!(bloat_color(30.0, &t), Color::Yellow);
+        assert_eq!(bloat_color(10.0, &t), Color::Green);
+        assert_eq!(bloat_color(50.0, &t), Color::Yellow);
+        assert_eq!(bloat_color(20.0, &t), Color::Green);
+
+        let strict = BloatThresholds { warn: 2.0, severe: 5.0 };
+        assert_eq!(bloat_color(10.0, &strict), Color::Red);
     }
 }
@@ -1,9 +1,22 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use crate::model::{LargeBlobInfo, TreeNode};
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+
+use crate::model::{
+    recommend, BloatThresholds, BlobFilter, BlobLookup, BlobPreview, DirEntry, ExportFormat, InsightInputs, LargeBlobInfo,
+    LfsPlan, ListExport, NodeId, NodeRef, RecoveryCommands, RestorePlan, ScanDiagnostic, SessionEvent, SessionLog, Tree,
+    DEFAULT_LFS_MIN_SIZE,
+};
 use crate::input::Intent;
-use super::{TreeViewModel, ExtensionViewModel, SearchViewModel, BlobsViewModel};
+use crate::util::DateStyle;
+use super::{TreeViewModel, VendoredFilter, ExtensionViewModel, SearchViewModel, BlobsViewModel, BlobLookupViewModel, BookmarksViewModel, CommitsViewModel, TopDirsViewModel, FlattenViewModel, DiagnosticsViewModel, DuplicatesViewModel, InsightsViewModel, OwnersViewModel};
+use super::list_filter::ListFilter;
 use super::selection::Selectable;
+use super::selection_set::{MarkKey, MarkSizes, SelectionSet};
+use super::undo_history::UndoHistory;
 
 /// Current view mode
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -12,6 +25,36 @@ pub enum ViewMode {
     ByExtension,
     LargeBlobs,
     Search,
+    BlobLookup,
+    Bookmarks,
+    Insights,
+    Duplicates,
+    HeavyCommits,
+    TopDirs,
+    Flatten,
+    Diagnostics,
+    Owners,
+}
+
+impl ViewMode {
+    /// What the view is called in investigation notes
+    pub fn name(self) -> &'static str {
+        match self {
+            ViewMode::Tree => "Tree",
+            ViewMode::ByExtension => "File types",
+            ViewMode::LargeBlobs => "Large blobs",
+            ViewMode::Search => "Search",
+            ViewMode::BlobLookup => "Blob lookup",
+            ViewMode::Bookmarks => "Bookmarks",
+            ViewMode::Insights => "Insights",
+            ViewMode::Duplicates => "Duplicates",
+            ViewMode::HeavyCommits => "Heavy commits",
+            ViewMode::TopDirs => "Top-level dirs",
+            ViewMode::Flatten => "Flattened",
+            ViewMode::Diagnostics => "Diagnostics",
+            ViewMode::Owners => "Owners",
+        }
+    }
 }
 
 /// Action to take after handling an intent
@@ -19,35 +62,142 @@ pub enum ViewMode {
 pub enum Action {
     Redraw,
     Quit,
+    /// Look up a blob by (abbreviated) hex OID and report back via
+    /// `AppViewModel::set_lookup_result`
+    LookupBlob(String),
+    /// Persist a new bookmark
+    SaveBookmark(String),
+    /// Forget a saved bookmark
+    DeleteBookmark(String),
+    /// Scan again and deliver the results via `AppViewModel::replace_tree`
+    Rescan,
+    /// Read a blob (or the one a file has in HEAD, or last had) and show it
+    /// via `AppViewModel::set_preview`
+    Preview { oid: Option<Vec<u8>>, path: String },
+    /// Copy commands restoring a blob (or the one a file has in HEAD, or
+    /// last had) and show them via `AppViewModel::set_recovery`
+    CopyRecoveryCommand { oid: Option<Vec<u8>>, path: String },
+    /// Find a commit that still has a deleted file (the blob, or the one
+    /// last committed at the path) and show how to restore it via
+    /// `AppViewModel::set_restore`
+    PlanRestore { oid: Option<Vec<u8>>, path: String },
+    /// Write `path` as `commit` has it into the worktree, confirmed in the
+    /// restore popup; report via `AppViewModel::set_restored`
+    RestoreFile { commit: String, path: String },
+    /// Write the rows the view lists to a file; report via
+    /// `AppViewModel::set_exported`
+    ExportList { list: ListExport, format: ExportFormat },
 }
 
 /// Main application ViewModel coordinating all view-specific ViewModels
 pub struct AppViewModel {
     view_mode: ViewMode,
+    /// View search was entered from, which leaving it returns to
+    search_origin: ViewMode,
+    /// Tree all views were built from
+    root: Arc<Tree>,
+    /// Tree before the last rescan, while directories of a partial one are
+    /// still loading and need their size changes worked out
+    previous_root: Option<Arc<Tree>>,
     pub tree_vm: TreeViewModel,
     pub extension_vm: ExtensionViewModel,
     pub search_vm: SearchViewModel,
     pub blobs_vm: BlobsViewModel,
+    pub lookup_vm: BlobLookupViewModel,
+    pub bookmarks_vm: BookmarksViewModel,
+    pub insights_vm: InsightsViewModel,
+    pub duplicates_vm: DuplicatesViewModel,
+    pub commits_vm: CommitsViewModel,
+    pub top_dirs_vm: TopDirsViewModel,
+    pub flatten_vm: FlattenViewModel,
+    pub diagnostics_vm: DiagnosticsViewModel,
+    pub owners_vm: OwnersViewModel,
+    /// Entries marked in any view
+    pub marks: SelectionSet,
+    /// Earlier marks and filters, for undo and redo
+    history: UndoHistory<UndoState>,
+    /// Show only content gone from HEAD, in every view at once
+    deleted_only: bool,
+    /// Ratios at which bloat is colored as a warning or as severe
+    pub bloat_thresholds: BloatThresholds,
+    /// How dates are written in the views
+    pub date_style: DateStyle,
+    show_help: bool,
+    /// LFS migration plan shown over the current view
+    lfs_plan: Option<LfsPlan>,
+    /// Blob content (or why it couldn't be read) shown over the current view
+    preview: Option<Result<BlobPreview, String>>,
+    /// Commands just copied (or why they couldn't be), shown over the view
+    recovery: Option<Result<RecoveryCommands, String>>,
+    /// Every command copied this session, printed again on exit
+    recovered: Vec<RecoveryCommands>,
+    /// How to restore a deleted file (or why it can't be), shown over the
+    /// view until confirmed or dismissed
+    restore: Option<Result<RestorePlan, String>>,
+    /// What was looked at, searched and marked, once recording is on
+    session: Option<SessionLog>,
+    /// One-line message shown over the view until the next key
+    notice: Option<String>,
+}
+
+/// What undo and redo bring back: the marks and the filters shared by views
+#[derive(Debug, Clone, PartialEq)]
+struct UndoState {
+    marks: BTreeMap<MarkKey, MarkSizes>,
+    deleted_only: bool,
+    vendored_filter: VendoredFilter,
+    old_only: bool,
 }
 
 impl AppViewModel {
-    pub fn new(root: TreeNode, large_blobs: Vec<LargeBlobInfo>) -> Self {
-        let total_cumulative = root.cumulative_size;
+    pub fn new(root: Tree, large_blobs: Vec<LargeBlobInfo>) -> Self {
+        let total_cumulative = root.root().cumulative_size;
         let root = Arc::new(root);
-        let extension_vm = ExtensionViewModel::new(&root);
+        let extension_vm = ExtensionViewModel::default();
         let search_vm = SearchViewModel::new(Arc::clone(&root));
         let tree_vm = TreeViewModel::new(Arc::clone(&root));
+        let bookmarks_vm = BookmarksViewModel::new(Arc::clone(&root));
         let blobs_vm = BlobsViewModel::new(large_blobs, total_cumulative);
 
         Self {
             view_mode: ViewMode::Tree,
+            search_origin: ViewMode::Tree,
+            root,
+            previous_root: None,
             tree_vm,
             extension_vm,
             search_vm,
             blobs_vm,
+            lookup_vm: BlobLookupViewModel::new(),
+            bookmarks_vm,
+            insights_vm: InsightsViewModel::default(),
+            duplicates_vm: DuplicatesViewModel::default(),
+            commits_vm: CommitsViewModel::default(),
+            top_dirs_vm: TopDirsViewModel::default(),
+            flatten_vm: FlattenViewModel::default(),
+            diagnostics_vm: DiagnosticsViewModel::default(),
+            owners_vm: OwnersViewModel::default(),
+            marks: SelectionSet::new(),
+            history: UndoHistory::default(),
+            deleted_only: false,
+            bloat_thresholds: BloatThresholds::default(),
+            date_style: DateStyle::default(),
+            show_help: false,
+            lfs_plan: None,
+            preview: None,
+            recovery: None,
+            recovered: Vec::new(),
+            restore: None,
+            session: None,
+            notice: None,
         }
     }
 
+    /// Tree the views show, the latest rescan's once there was one
+    pub fn root(&self) -> &Tree {
+        &self.root
+    }
+
     pub fn view_mode(&self) -> ViewMode {
         self.view_mode
     }
@@ -56,6 +206,327 @@ impl AppViewModel {
         self.view_mode == ViewMode::Search
     }
 
+    /// Whether keys should be treated as text input (search, OID prompt or
+    /// a list's filter)
+    pub fn is_text_input(&self) -> bool {
+        matches!(self.view_mode, ViewMode::Search | ViewMode::BlobLookup) || self.editing_filter().is_some()
+    }
+
+    /// Filter of the current list, while it is being typed
+    fn editing_filter(&self) -> Option<&ListFilter> {
+        let filter = match self.view_mode {
+            ViewMode::LargeBlobs => self.blobs_vm.filter(),
+            ViewMode::ByExtension => self.extension_vm.filter(),
+            _ => return None,
+        };
+        filter.is_editing().then_some(filter)
+    }
+
+    /// Apply `edit` to the current list's filter, if it has one
+    fn edit_filter(&mut self, edit: impl FnOnce(&mut ListFilter)) {
+        match self.view_mode {
+            ViewMode::LargeBlobs => self.blobs_vm.edit_filter(edit),
+            ViewMode::ByExtension => self.extension_vm.edit_filter(edit),
+            _ => {}
+        }
+    }
+
+    /// Leave search for the view it was entered from, as that was left
+    fn leave_search(&mut self) {
+        self.note_search(None);
+        self.search_vm.clear();
+        self.view_mode = self.search_origin;
+    }
+
+    /// Start recording the session for investigation notes, from where it
+    /// is now
+    pub fn record_session(&mut self) {
+        self.session.get_or_insert_with(SessionLog::default);
+        self.note_position();
+    }
+
+    /// The session so far, if it is being recorded
+    pub fn session(&self) -> Option<&SessionLog> {
+        self.session.as_ref()
+    }
+
+    fn note(&mut self, event: SessionEvent) {
+        if let Some(session) = &mut self.session {
+            session.record(event);
+        }
+    }
+
+    /// Record where the session is now: the view, and in the tree the
+    /// directory (searches are recorded as they are left)
+    fn note_position(&mut self) {
+        match self.view_mode {
+            ViewMode::Search => {}
+            ViewMode::Tree => {
+                self.note(SessionEvent::View(ViewMode::Tree.name()));
+                let dir = self.tree_vm.current_path();
+                self.note(SessionEvent::Directory(dir));
+            }
+            view => self.note(SessionEvent::View(view.name())),
+        }
+    }
+
+    /// Record the search being left, and the result opened from it
+    fn note_search(&mut self, opened: Option<String>) {
+        let query = self.search_vm.query().to_string();
+        if !query.is_empty() {
+            let matches = self.search_vm.total_matches();
+            self.note(SessionEvent::Search { query, matches, opened });
+        }
+    }
+
+    /// How a marked entry is named in notes: its path, its extension's
+    /// files, or a blob by the path it is listed at
+    pub fn mark_label(&self, key: &MarkKey) -> String {
+        match key {
+            MarkKey::Path(path) => path.clone(),
+            MarkKey::Extension(extension) => format!("all {} files", extension),
+            MarkKey::Blob(oid) => {
+                let hex = hex::encode(oid);
+                let short = &hex[..hex.len().min(12)];
+                let listed = self.blobs_vm.blobs().iter().chain(self.blobs_vm.all_blobs()).find(|b| &b.oid == oid);
+                match listed {
+                    Some(blob) => format!("{} (blob {})", blob.path, short),
+                    None => format!("blob {}", short),
+                }
+            }
+        }
+    }
+
+    /// Whether the help overlay covers the current view
+    pub fn is_help_visible(&self) -> bool {
+        self.show_help
+    }
+
+    /// The LFS migration plan covering the current view, if open
+    pub fn lfs_plan(&self) -> Option<&LfsPlan> {
+        self.lfs_plan.as_ref()
+    }
+
+    pub fn is_deleted_only(&self) -> bool {
+        self.deleted_only
+    }
+
+    /// Switch the deleted-only filter for all views together
+    fn set_deleted_only(&mut self, deleted_only: bool) {
+        self.deleted_only = deleted_only;
+        self.tree_vm.set_deleted_only(deleted_only);
+        self.extension_vm.set_deleted_only(deleted_only);
+        self.search_vm.set_deleted_only(deleted_only);
+        self.blobs_vm.set_deleted_only(deleted_only);
+    }
+
+    /// Show the results of a rescan in every view, keeping the current view,
+    /// position, filter, marks and bookmarks; tree entries whose cumulative
+    /// size changed get a badge until the next rescan
+    ///
+    /// The extension view keeps its stats until given the new ones.
+    pub fn replace_tree(&mut self, root: Tree, large_blobs: Vec<LargeBlobInfo>, deleted_blobs: Vec<LargeBlobInfo>) {
+        let deltas = size_deltas(&self.root, &root);
+        let previous = std::mem::replace(&mut self.root, Arc::new(Tree::new()));
+        self.previous_root = root.is_partial().then_some(previous);
+        let root = Arc::new(root);
+        self.search_vm.set_root(Arc::clone(&root));
+        self.blobs_vm.set_blobs(large_blobs, root.root().cumulative_size);
+        self.blobs_vm.set_deleted_blobs(deleted_blobs);
+        self.bookmarks_vm.set_root(Arc::clone(&root));
+        self.tree_vm.set_root(Arc::clone(&root));
+        self.tree_vm.set_size_deltas(deltas);
+        self.top_dirs_vm.set_root(&root);
+        self.flatten_vm.set_root(&root);
+        self.root = root;
+        self.set_deleted_only(self.deleted_only);
+        if self.view_mode == ViewMode::Search {
+            self.view_mode = self.search_origin;
+        }
+    }
+
+    /// A directory, with its path, whose entries some view needs loaded
+    /// before it can show what was asked for (only in a partial tree)
+    pub fn pending_dir(&self) -> Option<(NodeId, String)> {
+        if let Some(dir) = self.tree_vm.pending_dir() {
+            return Some(dir);
+        }
+        if self.view_mode == ViewMode::Bookmarks {
+            return self.bookmarks_vm.pending_dir();
+        }
+        if self.view_mode == ViewMode::Flatten {
+            return self.flatten_vm.pending_dir();
+        }
+        None
+    }
+
+    /// Deliver the entries of the directory at `path` named by `pending_dir`
+    pub fn load_dir(&mut self, dir: NodeId, path: &str, entries: Vec<DirEntry>) {
+        // With the view models' handles dropped the tree is extended in
+        // place instead of copied
+        self.bookmarks_vm.set_root(Arc::default());
+        self.tree_vm.release_tree();
+        Arc::make_mut(&mut self.root).set_dir_entries(dir, entries);
+        // Nothing is known about directories the old tree never loaded
+        if let Some(previous) = self.previous_root.as_ref().filter(|p| p.unloaded_ancestor(path).is_none()) {
+            let mut deltas = FxHashMap::default();
+            add_size_deltas(path, previous.find(path), self.root.node(dir), &mut deltas);
+            self.tree_vm.add_size_deltas(deltas);
+        }
+        self.bookmarks_vm.set_root(Arc::clone(&self.root));
+        self.tree_vm.extend_tree(Arc::clone(&self.root));
+        if self.view_mode == ViewMode::Flatten {
+            self.flatten_vm.set_root(&self.root);
+        }
+    }
+
+    /// Work out the recommendations from what the other views show
+    fn refresh_insights(&mut self) {
+        let root = self.root.root();
+        let inputs = InsightInputs {
+            git_dir: self.tree_vm.git_dir_usage(),
+            cumulative_size: root.cumulative_size,
+            top_level: root.children().map(|c| (c.name(), c.current_size, c.deleted_size)).collect(),
+            extensions: self
+                .extension_vm
+                .all_stats()
+                .iter()
+                .map(|s| (s.extension.as_str(), s.cumulative_size))
+                .collect(),
+            gitignore: self.insights_vm.gitignore_suggestions(),
+            delta: self.insights_vm.delta_efficiency(),
+        };
+        let insights = recommend(&inputs);
+        self.insights_vm.set_insights(insights);
+        self.insights_vm.set_cumulative_size(root.cumulative_size);
+    }
+
+    /// Plan an LFS migration from the extension totals and the largest blobs
+    fn open_lfs_plan(&mut self) {
+        let extensions: Vec<_> = self
+            .extension_vm
+            .all_stats()
+            .iter()
+            .map(|s| (s.extension.as_str(), s.cumulative_size, s.file_count))
+            .collect();
+        self.lfs_plan = Some(LfsPlan::new(&extensions, self.blobs_vm.all_blobs(), DEFAULT_LFS_MIN_SIZE));
+    }
+
+    /// Show what the last scan could not read, in the tree header and the
+    /// diagnostics list
+    pub fn set_diagnostics(&mut self, diagnostics: Vec<ScanDiagnostic>) {
+        self.tree_vm.set_diagnostics(&diagnostics);
+        self.diagnostics_vm.set_diagnostics(diagnostics);
+    }
+
+    /// Deliver the result of an `Action::LookupBlob`
+    pub fn set_lookup_result(&mut self, result: Result<Option<BlobLookup>>) {
+        self.lookup_vm.set_result(result);
+    }
+
+    pub fn preview(&self) -> Option<Result<&BlobPreview, &str>> {
+        self.preview.as_ref().map(|preview| preview.as_ref().map_err(String::as_str))
+    }
+
+    pub fn set_preview(&mut self, preview: Result<BlobPreview>) {
+        self.preview = Some(preview.map_err(|e| format!("{:#}", e)));
+    }
+
+    pub fn recovery(&self) -> Option<Result<&RecoveryCommands, &str>> {
+        self.recovery.as_ref().map(|recovery| recovery.as_ref().map_err(String::as_str))
+    }
+
+    pub fn set_recovery(&mut self, recovery: Result<RecoveryCommands>) {
+        if let Ok(commands) = &recovery
+            && !self.recovered.contains(commands)
+        {
+            self.recovered.push(commands.clone());
+        }
+        self.recovery = Some(recovery.map_err(|e| format!("{:#}", e)));
+    }
+
+    /// Commands copied this session, oldest first
+    pub fn recovered(&self) -> &[RecoveryCommands] {
+        &self.recovered
+    }
+
+    pub fn restore(&self) -> Option<Result<&RestorePlan, &str>> {
+        self.restore.as_ref().map(|restore| restore.as_ref().map_err(String::as_str))
+    }
+
+    pub fn set_restore(&mut self, restore: Result<RestorePlan>) {
+        self.restore = Some(restore.map_err(|e| format!("{:#}", e)));
+    }
+
+    /// Outcome of `Action::RestoreFile`, shown in the restore popup
+    pub fn set_restored(&mut self, restored: Result<()>) {
+        match restored {
+            Ok(()) => {
+                if let Some(Ok(plan)) = &mut self.restore {
+                    plan.restored = true;
+                }
+            }
+            Err(e) => self.restore = Some(Err(format!("{:#}", e))),
+        }
+    }
+
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_deref()
+    }
+
+    /// Outcome of `Action::ExportList` writing `rows` rows
+    pub fn set_exported(&mut self, rows: usize, written: Result<PathBuf>) {
+        self.notice = Some(match written {
+            Ok(path) => format!("Exported {} rows to {}", rows, path.display()),
+            Err(e) => format!("Export failed: {:#}", e),
+        });
+    }
+
+    /// The rows the current view lists, as `Intent::ExportList` writes them
+    fn current_export(&self) -> Option<ListExport> {
+        match self.view_mode {
+            ViewMode::Tree => Some(self.tree_vm.export()),
+            ViewMode::ByExtension => Some(self.extension_vm.export()),
+            ViewMode::LargeBlobs => Some(self.blobs_vm.export()),
+            ViewMode::Search => Some(self.search_vm.export()),
+            ViewMode::TopDirs => Some(self.top_dirs_vm.export()),
+            ViewMode::Flatten => Some(self.flatten_vm.export()),
+            ViewMode::BlobLookup
+            | ViewMode::Bookmarks
+            | ViewMode::Insights
+            | ViewMode::Duplicates
+            | ViewMode::HeavyCommits
+            | ViewMode::Diagnostics
+            | ViewMode::Owners => None,
+        }
+    }
+
+    /// The deleted file `Intent::RestoreFile` acts on: the selected blob
+    /// unless HEAD has it, or the selected file if HEAD no longer has it
+    fn restore_target(&self) -> Option<(Option<Vec<u8>>, String)> {
+        match self.view_mode {
+            ViewMode::LargeBlobs => {
+                let blob = self.blobs_vm.blobs().get(self.blobs_vm.selected_index())?;
+                (!blob.in_head).then(|| (Some(blob.oid.clone()), blob.path.clone()))
+            }
+            ViewMode::Tree => Some((None, self.tree_vm.selected_deleted_file()?)),
+            _ => None,
+        }
+    }
+
+    /// What `Intent::Preview` and `Intent::CopyRecoveryCommand` act on in
+    /// the current view: the selected blob, or the selected file's
+    fn preview_target(&self) -> Option<(Option<Vec<u8>>, String)> {
+        match self.view_mode {
+            ViewMode::LargeBlobs => {
+                let blob = self.blobs_vm.blobs().get(self.blobs_vm.selected_index())?;
+                Some((Some(blob.oid.clone()), blob.path.clone()))
+            }
+            ViewMode::Tree => Some((None, self.tree_vm.selected_file()?)),
+            _ => None,
+        }
+    }
+
     /// Get the current selectable viewmodel based on view mode
     fn current_selectable(&mut self) -> &mut dyn Selectable {
         match self.view_mode {
@@ -63,6 +534,93 @@ impl AppViewModel {
             ViewMode::ByExtension => &mut self.extension_vm,
             ViewMode::LargeBlobs => &mut self.blobs_vm,
             ViewMode::Search => &mut self.search_vm,
+            ViewMode::BlobLookup => &mut self.lookup_vm,
+            ViewMode::Bookmarks => &mut self.bookmarks_vm,
+            ViewMode::Insights => &mut self.insights_vm,
+            ViewMode::Duplicates => &mut self.duplicates_vm,
+            ViewMode::HeavyCommits => &mut self.commits_vm,
+            ViewMode::TopDirs => &mut self.top_dirs_vm,
+            ViewMode::Flatten => &mut self.flatten_vm,
+            ViewMode::Diagnostics => &mut self.diagnostics_vm,
+            ViewMode::Owners => &mut self.owners_vm,
+        }
+    }
+
+    /// Mark key and sizes of the entry under the cursor in the current view
+    fn current_mark(&self) -> Option<(MarkKey, MarkSizes)> {
+        match self.view_mode {
+            ViewMode::Tree => self.tree_vm.selected_mark(),
+            ViewMode::ByExtension => self.extension_vm.selected_mark(),
+            ViewMode::LargeBlobs => self.blobs_vm.selected_mark(),
+            ViewMode::Search => self.search_vm.selected_mark(),
+            ViewMode::TopDirs => self.top_dirs_vm.selected_mark(),
+            ViewMode::Flatten => self.flatten_vm.selected_mark(),
+            ViewMode::BlobLookup
+            | ViewMode::Bookmarks
+            | ViewMode::Insights
+            | ViewMode::Duplicates
+            | ViewMode::HeavyCommits
+            | ViewMode::Diagnostics
+            | ViewMode::Owners => None,
+        }
+    }
+
+    /// Toggle the mark on the selected entry and advance to the next one
+    fn toggle_mark(&mut self) {
+        let Some((key, sizes)) = self.current_mark() else {
+            return;
+        };
+        let label = self.session.is_some().then(|| self.mark_label(&key));
+        let marked = self.marks.toggle(key, sizes);
+        if let Some(label) = label {
+            self.note(if marked {
+                SessionEvent::Marked { label, cumulative: sizes.cumulative, current: sizes.current }
+            } else {
+                SessionEvent::Unmarked { label }
+            });
+        }
+
+        let list = self.current_selectable();
+        if list.selected() + 1 < list.len() {
+            list.move_down();
+        }
+    }
+
+    /// Mark key and sizes of row `index` in the views that select ranges
+    fn mark_at(&self, index: usize) -> Option<(MarkKey, MarkSizes)> {
+        match self.view_mode {
+            ViewMode::Tree => self.tree_vm.mark_at(index),
+            ViewMode::LargeBlobs => self.blobs_vm.mark_at(index),
+            ViewMode::Search => self.search_vm.mark_at(index),
+            ViewMode::Flatten => self.flatten_vm.mark_at(index),
+            _ => None,
+        }
+    }
+
+    /// Mark every entry in the visual range, or unmark them all when all
+    /// already are, and leave visual mode
+    fn toggle_range_marks(&mut self) {
+        let cursor = self.current_selectable().selected();
+        let Some(rows) = self.marks.visual_range(cursor) else {
+            return;
+        };
+        self.marks.end_visual();
+        let entries: Vec<(MarkKey, MarkSizes)> = rows.filter_map(|row| self.mark_at(row)).collect();
+        // Only entries whose mark changes go into the session
+        let noted: Vec<(String, MarkSizes, bool)> = match self.session {
+            Some(_) => entries
+                .iter()
+                .map(|(key, sizes)| (self.mark_label(key), *sizes, self.marks.contains(key)))
+                .collect(),
+            None => Vec::new(),
+        };
+        let marked = self.marks.toggle_all(entries);
+        for (label, sizes, was_marked) in noted {
+            if marked && !was_marked {
+                self.note(SessionEvent::Marked { label, cumulative: sizes.cumulative, current: sizes.current });
+            } else if !marked {
+                self.note(SessionEvent::Unmarked { label });
+            }
         }
     }
 
@@ -75,27 +633,195 @@ impl AppViewModel {
                     self.view_mode = ViewMode::Tree;
                 }
             }
+            // The largest blobs of the selected extension
+            ViewMode::ByExtension => {
+                let extension = self.extension_vm.selected_extension().map(str::to_string);
+                if let Some(extension) = extension.filter(|e| matches!(BlobFilter::parse(e), Some(BlobFilter::Extension(_)))) {
+                    self.blobs_vm.edit_filter(|filter| filter.set_text(&extension));
+                    self.view_mode = ViewMode::LargeBlobs;
+                }
+            }
             ViewMode::Search => {
+                if self.search_vm.toggle_selected_group() {
+                    return;
+                }
                 if let Some(path) = self.search_vm.selected_path() {
                     self.tree_vm.navigate_to_path(path);
+                    self.note_search(Some(path.to_string()));
                     self.search_vm.clear();
                     self.view_mode = ViewMode::Tree;
                 }
             }
-            ViewMode::ByExtension => {}
+            ViewMode::BlobLookup => {
+                if let Some(path) = self.lookup_vm.selected_path() {
+                    self.tree_vm.navigate_to_path(path);
+                    self.lookup_vm.clear();
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::Bookmarks => {
+                if let Some(path) = self.bookmarks_vm.selected_path().map(str::to_string) {
+                    self.tree_vm.open_path(&path);
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::Duplicates => {
+                if let Some(path) = self.duplicates_vm.selected_path().map(str::to_string) {
+                    self.tree_vm.navigate_to_path(&path);
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::HeavyCommits => {
+                if let Some(path) = self.commits_vm.selected_path().map(str::to_string) {
+                    self.tree_vm.navigate_to_path(&path);
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::TopDirs => {
+                if let Some(path) = self.top_dirs_vm.selected_path().map(str::to_string) {
+                    self.tree_vm.open_path(&path);
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::Flatten => {
+                if let Some(path) = self.flatten_vm.selected_entry().map(|entry| entry.path.clone()) {
+                    self.tree_vm.open_path(&path);
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::Diagnostics => {
+                if let Some(path) = self.diagnostics_vm.selected_path().map(str::to_string) {
+                    self.tree_vm.navigate_to_path(&path);
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::Owners => {
+                if let Some(path) = self.owners_vm.enter() {
+                    self.tree_vm.navigate_to_path(&path);
+                    self.view_mode = ViewMode::Tree;
+                }
+            }
+            ViewMode::Insights => {}
+        }
+    }
+
+    /// Bookmark the selected tree entry, or in the bookmark list forget the
+    /// selected bookmark
+    fn toggle_bookmark(&mut self) -> Action {
+        let path = match self.view_mode {
+            ViewMode::Tree => self.tree_vm.selected_path(),
+            ViewMode::Bookmarks => self.bookmarks_vm.selected_path().map(str::to_string),
+            _ => None,
+        };
+        match path {
+            Some(path) if self.bookmarks_vm.toggle(&path) => Action::SaveBookmark(path),
+            Some(path) => Action::DeleteBookmark(path),
+            None => Action::Redraw,
         }
     }
 
+    fn undo_state(&self) -> UndoState {
+        UndoState {
+            marks: self.marks.snapshot(),
+            deleted_only: self.deleted_only,
+            vendored_filter: self.tree_vm.vendored_filter(),
+            old_only: self.tree_vm.is_old_only(),
+        }
+    }
+
+    fn restore_state(&mut self, state: UndoState) {
+        self.marks.restore(state.marks);
+        if state.deleted_only != self.deleted_only {
+            self.set_deleted_only(state.deleted_only);
+        }
+        self.tree_vm.set_vendored_filter(state.vendored_filter);
+        self.tree_vm.set_old_only(state.old_only);
+    }
+
     /// Handle a user intent and return the action to take
     pub fn handle_intent(&mut self, intent: Intent) -> Action {
+        // Only changes to marks and filters can be undone
+        let undoable = matches!(
+            intent,
+            Intent::ToggleMark
+                | Intent::ClearMarks
+                | Intent::ToggleDeletedOnly
+                | Intent::CycleVendoredFilter
+                | Intent::ToggleOldOnly
+        );
+        let before = undoable.then(|| self.undo_state());
+        let action = self.apply_intent(intent);
+        if let Some(before) = before
+            && before != self.undo_state()
+        {
+            self.history.record(before);
+        }
+        if self.session.is_some() {
+            self.note_position();
+        }
+        action
+    }
+
+    fn apply_intent(&mut self, intent: Intent) -> Action {
+        self.notice = None;
+        // Enter confirms a restore; any other key dismisses an overlay
+        if intent == Intent::Enter
+            && let Some(Ok(plan)) = &self.restore
+            && !plan.restored
+        {
+            return Action::RestoreFile { commit: plan.commit.clone(), path: plan.path.clone() };
+        }
+        let overlay = self.show_help
+            || self.lfs_plan.is_some()
+            || self.preview.is_some()
+            || self.recovery.is_some()
+            || self.restore.is_some();
+        if overlay && intent != Intent::Quit {
+            self.show_help = false;
+            self.lfs_plan = None;
+            self.preview = None;
+            self.recovery = None;
+            self.restore = None;
+            return Action::Redraw;
+        }
+
+        if self.marks.is_visual() {
+            match intent {
+                Intent::MoveUp
+                | Intent::MoveDown
+                | Intent::ScrollLeft
+                | Intent::ScrollRight
+                | Intent::ToggleMark
+                | Intent::ToggleHelp
+                | Intent::Quit => {}
+                // Esc and Back cancel the selection rather than leave the view
+                Intent::ToggleVisual | Intent::ShowTree | Intent::Back => {
+                    self.marks.end_visual();
+                    return Action::Redraw;
+                }
+                // Anything else may change the rows the range covers
+                _ => self.marks.end_visual(),
+            }
+        }
+
         match intent {
             Intent::Quit => Action::Quit,
 
+            // Esc while typing a list's filter drops it, staying in the list
+            Intent::ShowTree if self.editing_filter().is_some() => {
+                self.edit_filter(ListFilter::clear);
+                Action::Redraw
+            }
+
             Intent::ShowTree => {
-                if self.view_mode == ViewMode::Search {
-                    self.search_vm.clear();
+                match self.view_mode {
+                    ViewMode::Search => self.leave_search(),
+                    ViewMode::BlobLookup => {
+                        self.lookup_vm.clear();
+                        self.view_mode = ViewMode::Tree;
+                    }
+                    _ => self.view_mode = ViewMode::Tree,
                 }
-                self.view_mode = ViewMode::Tree;
                 Action::Redraw
             }
 
@@ -109,12 +835,25 @@ impl AppViewModel {
                 Action::Redraw
             }
 
+            // The large blobs and extension lists filter themselves
+            Intent::EnterSearch if matches!(self.view_mode, ViewMode::LargeBlobs | ViewMode::ByExtension) => {
+                self.edit_filter(ListFilter::start_editing);
+                Action::Redraw
+            }
+
             Intent::EnterSearch => {
                 self.search_vm.clear();
+                self.search_origin = self.view_mode;
                 self.view_mode = ViewMode::Search;
                 Action::Redraw
             }
 
+            Intent::EnterBlobLookup => {
+                self.lookup_vm.clear();
+                self.view_mode = ViewMode::BlobLookup;
+                Action::Redraw
+            }
+
             Intent::MoveUp => {
                 self.current_selectable().move_up();
                 Action::Redraw
@@ -125,35 +864,296 @@ impl AppViewModel {
                 Action::Redraw
             }
 
+            Intent::Enter if self.editing_filter().is_some() => {
+                self.edit_filter(ListFilter::finish_editing);
+                Action::Redraw
+            }
+
             Intent::Enter => {
+                if self.view_mode == ViewMode::BlobLookup && self.lookup_vm.needs_lookup() {
+                    return Action::LookupBlob(self.lookup_vm.query().to_string());
+                }
                 self.enter_current();
                 Action::Redraw
             }
 
             Intent::Back => {
+                match self.view_mode {
+                    ViewMode::Tree => {
+                        self.tree_vm.go_back();
+                    }
+                    ViewMode::Owners if !self.owners_vm.back() => self.view_mode = ViewMode::Tree,
+                    _ => {}
+                }
+                Action::Redraw
+            }
+
+            Intent::ToggleBookmark => self.toggle_bookmark(),
+
+            Intent::ShowBookmarks => {
+                self.view_mode = if self.view_mode == ViewMode::Bookmarks {
+                    ViewMode::Tree
+                } else {
+                    ViewMode::Bookmarks
+                };
+                Action::Redraw
+            }
+
+            Intent::ShowInsights => {
+                if self.view_mode == ViewMode::Insights {
+                    self.view_mode = ViewMode::Tree;
+                } else {
+                    self.refresh_insights();
+                    self.view_mode = ViewMode::Insights;
+                }
+                Action::Redraw
+            }
+
+            Intent::ShowDuplicates => {
+                self.view_mode = if self.view_mode == ViewMode::Duplicates {
+                    ViewMode::Tree
+                } else {
+                    ViewMode::Duplicates
+                };
+                Action::Redraw
+            }
+
+            Intent::ShowHeavyCommits => {
+                self.view_mode = if self.view_mode == ViewMode::HeavyCommits {
+                    ViewMode::Tree
+                } else {
+                    ViewMode::HeavyCommits
+                };
+                Action::Redraw
+            }
+
+            Intent::ShowTopDirs => {
+                if self.view_mode == ViewMode::TopDirs {
+                    self.view_mode = ViewMode::Tree;
+                } else {
+                    self.top_dirs_vm.set_root(&self.root);
+                    self.view_mode = ViewMode::TopDirs;
+                }
+                Action::Redraw
+            }
+
+            Intent::ShowFlatten => {
+                if self.view_mode == ViewMode::Flatten {
+                    self.view_mode = ViewMode::Tree;
+                } else {
+                    self.flatten_vm.set_root(&self.root);
+                    self.view_mode = ViewMode::Flatten;
+                }
+                Action::Redraw
+            }
+
+            Intent::ShowDiagnostics => {
+                self.view_mode = if self.view_mode == ViewMode::Diagnostics {
+                    ViewMode::Tree
+                } else {
+                    ViewMode::Diagnostics
+                };
+                Action::Redraw
+            }
+
+            Intent::ShowOwners => {
+                self.view_mode = if self.view_mode == ViewMode::Owners {
+                    ViewMode::Tree
+                } else {
+                    ViewMode::Owners
+                };
+                Action::Redraw
+            }
+
+            Intent::Preview => match self.preview_target() {
+                Some((oid, path)) => Action::Preview { oid, path },
+                None => Action::Redraw,
+            },
+
+            Intent::CopyRecoveryCommand => match self.preview_target() {
+                Some((oid, path)) => Action::CopyRecoveryCommand { oid, path },
+                None => Action::Redraw,
+            },
+
+            Intent::ToggleDetails => {
                 if self.view_mode == ViewMode::Tree {
-                    self.tree_vm.go_back();
+                    self.tree_vm.toggle_details();
                 }
                 Action::Redraw
             }
 
-            Intent::ToggleDeletedOnly => {
+            Intent::CycleVendoredFilter => {
                 if self.view_mode == ViewMode::Tree {
-                    self.tree_vm.toggle_deleted_only();
+                    self.tree_vm.cycle_vendored_filter();
                 }
                 Action::Redraw
             }
 
-            Intent::SearchChar(c) => {
+            Intent::ToggleOldOnly => {
+                if self.view_mode == ViewMode::Tree {
+                    self.tree_vm.toggle_old_only();
+                }
+                Action::Redraw
+            }
+
+            Intent::RestoreFile => match (self.restore_target(), self.preview_target()) {
+                (Some((oid, path)), _) => Action::PlanRestore { oid, path },
+                (None, Some((_, path))) => {
+                    self.restore = Some(Err(format!("{} is in HEAD; only deleted files are restored", path)));
+                    Action::Redraw
+                }
+                (None, None) => Action::Redraw,
+            },
+
+            Intent::ShowLfsPlan => {
+                self.open_lfs_plan();
+                Action::Redraw
+            }
+
+            Intent::JumpToLargest => {
+                if self.view_mode == ViewMode::Tree {
+                    self.tree_vm.descend_to_largest_leaf();
+                }
+                Action::Redraw
+            }
+
+            Intent::Rescan => Action::Rescan,
+
+            Intent::ToggleDeletedOnly => {
+                if self.view_mode != ViewMode::BlobLookup {
+                    self.set_deleted_only(!self.deleted_only);
+                }
+                Action::Redraw
+            }
+
+            Intent::CycleRanking => {
+                if self.view_mode == ViewMode::ByExtension {
+                    self.extension_vm.cycle_ranking();
+                } else if self.view_mode == ViewMode::Tree && self.tree_vm.is_deleted_only() {
+                    self.tree_vm.toggle_deletion_sort();
+                }
+                Action::Redraw
+            }
+
+            Intent::CycleSearchCase => {
                 if self.view_mode == ViewMode::Search {
-                    self.search_vm.add_char(c);
+                    self.search_vm.cycle_case();
                 }
                 Action::Redraw
             }
 
-            Intent::SearchBackspace => {
+            Intent::ToggleSearchGrouping => {
                 if self.view_mode == ViewMode::Search {
-                    self.search_vm.backspace();
+                    self.search_vm.toggle_grouped();
+                }
+                Action::Redraw
+            }
+
+            Intent::ToggleOidColumn => {
+                if self.view_mode == ViewMode::LargeBlobs {
+                    self.blobs_vm.toggle_oid_column();
+                }
+                Action::Redraw
+            }
+
+            Intent::ToggleAuthorColumn => {
+                if self.view_mode == ViewMode::LargeBlobs {
+                    self.blobs_vm.toggle_author_column();
+                }
+                Action::Redraw
+            }
+
+            Intent::ToggleDateColumn => {
+                if self.view_mode == ViewMode::LargeBlobs {
+                    self.blobs_vm.toggle_date_column();
+                }
+                Action::Redraw
+            }
+
+            Intent::ToggleSimilarGroups => {
+                if self.view_mode == ViewMode::LargeBlobs {
+                    self.blobs_vm.toggle_groups();
+                }
+                Action::Redraw
+            }
+
+            Intent::ToggleMark if self.marks.is_visual() => {
+                self.toggle_range_marks();
+                Action::Redraw
+            }
+
+            Intent::ToggleMark => {
+                self.toggle_mark();
+                Action::Redraw
+            }
+
+            Intent::Undo => {
+                if let Some(state) = self.history.undo(self.undo_state()) {
+                    self.restore_state(state);
+                }
+                Action::Redraw
+            }
+
+            Intent::Redo => {
+                if let Some(state) = self.history.redo(self.undo_state()) {
+                    self.restore_state(state);
+                }
+                Action::Redraw
+            }
+
+            Intent::ToggleVisual => {
+                if matches!(self.view_mode, ViewMode::Tree | ViewMode::LargeBlobs | ViewMode::Search) {
+                    let cursor = self.current_selectable().selected();
+                    self.marks.start_visual(cursor);
+                }
+                Action::Redraw
+            }
+
+            Intent::ClearMarks => {
+                if !self.marks.is_empty() {
+                    self.note(SessionEvent::ClearedMarks(self.marks.len()));
+                }
+                self.marks.clear();
+                Action::Redraw
+            }
+
+            Intent::ToggleHelp => {
+                self.show_help = true;
+                Action::Redraw
+            }
+
+            Intent::ExportList(format) => match self.current_export() {
+                Some(list) => Action::ExportList { list, format },
+                None => {
+                    self.notice = Some(format!("The {} view has no list to export", self.view_mode.name()));
+                    Action::Redraw
+                }
+            },
+
+            Intent::SearchChar(c) => {
+                match self.view_mode {
+                    ViewMode::Search => self.search_vm.add_char(c),
+                    ViewMode::BlobLookup => self.lookup_vm.add_char(c),
+                    _ => self.edit_filter(|filter| filter.push(c)),
+                }
+                Action::Redraw
+            }
+
+            Intent::ScrollLeft | Intent::ScrollRight => {
+                let back = intent == Intent::ScrollLeft;
+                match self.view_mode {
+                    ViewMode::Search => self.search_vm.scroll_paths(back),
+                    ViewMode::LargeBlobs => self.blobs_vm.scroll_paths(back),
+                    _ => {}
+                }
+                Action::Redraw
+            }
+
+            Intent::SearchBackspace => {
+                match self.view_mode {
+                    ViewMode::Search => self.search_vm.backspace(),
+                    ViewMode::BlobLookup => self.lookup_vm.backspace(),
+                    _ => self.edit_filter(ListFilter::pop),
                 }
                 Action::Redraw
             }
@@ -166,7 +1166,45 @@ impl AppViewModel {
             ViewMode::Tree => crate::input::ViewMode::Tree,
             ViewMode::ByExtension => crate::input::ViewMode::ByExtension,
             ViewMode::LargeBlobs => crate::input::ViewMode::LargeBlobs,
-            ViewMode::Search => crate::input::ViewMode::Tree, // Search handles keys specially
+            ViewMode::Bookmarks => crate::input::ViewMode::Bookmarks,
+            ViewMode::Insights => crate::input::ViewMode::Insights,
+            ViewMode::Duplicates => crate::input::ViewMode::Duplicates,
+            ViewMode::HeavyCommits => crate::input::ViewMode::HeavyCommits,
+            ViewMode::TopDirs => crate::input::ViewMode::TopDirs,
+            ViewMode::Flatten => crate::input::ViewMode::Flatten,
+            ViewMode::Diagnostics => crate::input::ViewMode::Diagnostics,
+            ViewMode::Owners => crate::input::ViewMode::Owners,
+            // Text input modes handle keys specially
+            ViewMode::Search | ViewMode::BlobLookup => crate::input::ViewMode::Tree,
+        }
+    }
+}
+
+/// Cumulative size change of every path, directories included, whose size
+/// differs between `old` and `new`; paths only in `old` are left out, as
+/// there is no entry left to show them on
+///
+/// In partial trees only directories loaded in both are compared.
+fn size_deltas(old: &Tree, new: &Tree) -> FxHashMap<String, i64> {
+    let mut deltas = FxHashMap::default();
+    add_size_deltas("", Some(old.root()), new.root(), &mut deltas);
+    deltas
+}
+
+/// `size_deltas` of the entries below `new`, at `prefix`
+fn add_size_deltas(prefix: &str, old: Option<NodeRef<'_>>, new: NodeRef<'_>, deltas: &mut FxHashMap<String, i64>) {
+    // Entries of a directory the old tree never loaded can't be compared
+    if old.is_some_and(|o| o.is_unloaded()) {
+        return;
+    }
+    for child in new.children() {
+        let name = child.name();
+        let path = if prefix.is_empty() { name.to_string() } else { format!("{}/{}", prefix, name) };
+        let before = old.and_then(|o| o.child(name));
+        let delta = child.cumulative_size as i64 - before.map_or(0, |b| b.cumulative_size as i64);
+        add_size_deltas(&path, before, child, deltas);
+        if delta != 0 {
+            deltas.insert(path, delta);
         }
     }
 }
@@ -174,15 +1212,23 @@ impl AppViewModel {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::{EntryKind, ExtensionStats};
 
-    fn create_test_tree() -> TreeNode {
-        let mut root = TreeNode::new("(root)");
+    fn create_test_tree() -> Tree {
+        let mut root = Tree::new();
         root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
         root.add_path_with_sizes(&["assets", "logo.png"], 5000, 0, 1);
         root.compute_totals();
         root
     }
 
+    /// What `Database::get_extension_stats` returns for `create_test_tree`
+    fn create_test_stats() -> Vec<(String, ExtensionStats)> {
+        let png = ExtensionStats { cumulative_size: 5000, current_size: 0, deleted_size: 5000, file_count: 1, binary_size: 0 };
+        let rs = ExtensionStats { cumulative_size: 1000, current_size: 500, deleted_size: 0, file_count: 1, binary_size: 0 };
+        vec![(".png".into(), png), (".rs".into(), rs)]
+    }
+
     #[test]
     fn test_mode_switching() {
         let tree = create_test_tree();
@@ -200,6 +1246,567 @@ mod tests {
         assert_eq!(vm.view_mode(), ViewMode::Search);
     }
 
+    #[test]
+    fn test_search_returns_to_its_view() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        vm.handle_intent(Intent::ShowBookmarks);
+        vm.handle_intent(Intent::EnterSearch);
+        vm.handle_intent(Intent::SearchChar('m'));
+        vm.handle_intent(Intent::ShowTree);
+        assert_eq!(vm.view_mode(), ViewMode::Bookmarks);
+        assert_eq!(vm.search_vm.query(), "");
+
+        // A result is shown in the tree, whichever view search came from
+        vm.handle_intent(Intent::EnterSearch);
+        for c in "main".chars() {
+            vm.handle_intent(Intent::SearchChar(c));
+        }
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+    }
+
+    #[test]
+    fn test_list_filters() {
+        let blob = |path: &str, size| LargeBlobInfo {
+            oid: path.as_bytes().to_vec(),
+            size,
+            path: path.to_string(),
+            first_author: "Ann".into(),
+            first_email: "ann@example.com".into(),
+            first_date: 0,
+            first_subject: String::new(),
+            in_head: true,
+        };
+        let blobs = vec![blob("assets/logo.png", 5000), blob("src/main.rs", 1000), blob("assets/icon.png", 500)];
+        let mut vm = AppViewModel::new(create_test_tree(), blobs);
+        vm.handle_intent(Intent::ShowLargeBlobs);
+        vm.handle_intent(Intent::MoveDown);
+        vm.handle_intent(Intent::MoveDown);
+
+        // '/' filters the list in place; typing keeps the selection when it can
+        vm.handle_intent(Intent::EnterSearch);
+        assert!(vm.is_text_input());
+        for c in "PNG".chars() {
+            vm.handle_intent(Intent::SearchChar(c));
+        }
+        assert_eq!(vm.view_mode(), ViewMode::LargeBlobs);
+        assert_eq!(vm.blobs_vm.blobs().len(), 2);
+        assert_eq!(vm.blobs_vm.selected_path(), Some("assets/icon.png"));
+
+        // Enter keeps the filter; Esc while typing drops it
+        vm.handle_intent(Intent::Enter);
+        assert!(!vm.is_text_input());
+        assert_eq!(vm.blobs_vm.filter().text(), "PNG");
+        vm.handle_intent(Intent::EnterSearch);
+        vm.handle_intent(Intent::ShowTree);
+        assert_eq!(vm.view_mode(), ViewMode::LargeBlobs);
+        assert_eq!(vm.blobs_vm.blobs().len(), 3);
+        assert_eq!(vm.blobs_vm.selected_path(), Some("assets/icon.png"));
+
+        vm.extension_vm.set_stats(create_test_stats());
+        vm.handle_intent(Intent::ShowTree);
+        vm.handle_intent(Intent::ShowExtensions);
+        vm.handle_intent(Intent::EnterSearch);
+        vm.handle_intent(Intent::SearchChar('r'));
+        let shown: Vec<&str> = vm.extension_vm.stats().iter().map(|s| s.extension.as_str()).collect();
+        assert_eq!(shown, [".rs"]);
+        vm.handle_intent(Intent::SearchBackspace);
+        assert_eq!(vm.extension_vm.stats().len(), 2);
+
+        // Enter on an extension lists its largest blobs
+        vm.handle_intent(Intent::SearchChar('r'));
+        vm.handle_intent(Intent::Enter);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.view_mode(), ViewMode::LargeBlobs);
+        assert_eq!(vm.blobs_vm.filter().text(), ".rs");
+        assert_eq!(vm.blobs_vm.selected_path(), Some("src/main.rs"));
+        assert_eq!(vm.blobs_vm.pending_filter(), BlobFilter::parse(".rs"));
+    }
+
+    #[test]
+    fn test_bookmark_flow() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+
+        // Tree root lists assets first
+        assert_eq!(vm.handle_intent(Intent::ToggleBookmark), Action::SaveBookmark("assets".into()));
+        vm.handle_intent(Intent::ShowBookmarks);
+        assert_eq!(vm.view_mode(), ViewMode::Bookmarks);
+
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+        assert_eq!(vm.tree_vm.current_path(), "/assets");
+
+        vm.handle_intent(Intent::ShowBookmarks);
+        assert_eq!(vm.handle_intent(Intent::ToggleBookmark), Action::DeleteBookmark("assets".into()));
+        assert!(!vm.bookmarks_vm.contains("assets"));
+    }
+
+    #[test]
+    fn test_top_dirs_from_anywhere() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        vm.tree_vm.open_path("src");
+        assert!(!vm.tree_vm.is_at_root());
+
+        vm.handle_intent(Intent::ShowTopDirs);
+        assert_eq!(vm.view_mode(), ViewMode::TopDirs);
+        let names: Vec<_> = vm.top_dirs_vm.dirs().iter().map(|dir| dir.name.as_str()).collect();
+        assert_eq!(names, ["assets", "src"]);
+
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+        assert_eq!(vm.tree_vm.current_path(), "/assets");
+    }
+
+    #[test]
+    fn test_flatten_compares_across_levels() {
+        let mut tree = create_test_tree();
+        tree.add_path_with_sizes(&["assets", "img", "hero.png"], 8000, 8000, 1);
+        tree.compute_totals();
+        let mut vm = AppViewModel::new(tree, vec![]);
+        vm.flatten_vm.set_depth(2);
+
+        vm.handle_intent(Intent::ShowFlatten);
+        assert_eq!(vm.view_mode(), ViewMode::Flatten);
+        let paths: Vec<_> = vm.flatten_vm.entries().iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, ["assets", "assets/img", "assets/logo.png", "src", "src/main.rs"]);
+
+        vm.handle_intent(Intent::MoveDown);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+        assert_eq!(vm.tree_vm.current_path(), "/assets/img");
+    }
+
+    #[test]
+    fn test_export_lists_what_the_view_shows() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        vm.tree_vm.open_path("src");
+        let Action::ExportList { list, format } = vm.handle_intent(Intent::ExportList(ExportFormat::Csv)) else {
+            panic!("the tree lists entries");
+        };
+        assert_eq!(format, ExportFormat::Csv);
+        assert_eq!(list.to_csv(), "path,is_dir,size,cumulative_size,current_size,size_delta,deleted_at\nsrc/main.rs,false,1000,1000,500,0,\n");
+
+        vm.set_exported(1, Ok(PathBuf::from("repodiet-tree.csv")));
+        assert_eq!(vm.notice(), Some("Exported 1 rows to repodiet-tree.csv"));
+        vm.handle_intent(Intent::MoveDown);
+        assert_eq!(vm.notice(), None);
+
+        vm.handle_intent(Intent::ShowInsights);
+        assert_eq!(vm.handle_intent(Intent::ExportList(ExportFormat::Json)), Action::Redraw);
+        assert_eq!(vm.notice(), Some("The Insights view has no list to export"));
+    }
+
+    #[test]
+    fn test_owners_flow() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        let owners = crate::model::CodeOwners::parse("/assets/ @org/design\n");
+        vm.owners_vm.set_owners(
+            Some("CODEOWNERS".into()),
+            Vec::new(),
+            owners.tally(vec![("assets/logo.png".into(), 5000, 0), ("src/main.rs".into(), 1000, 500)]),
+        );
+
+        vm.handle_intent(Intent::ShowOwners);
+        assert_eq!(vm.view_mode(), ViewMode::Owners);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.owners_vm.open_owner().unwrap().owner, "@org/design");
+        // Back closes the owner's files, then the view
+        vm.handle_intent(Intent::Back);
+        assert_eq!(vm.view_mode(), ViewMode::Owners);
+        vm.handle_intent(Intent::Back);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+
+        vm.handle_intent(Intent::ShowOwners);
+        vm.handle_intent(Intent::Enter);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+        assert_eq!(vm.tree_vm.current_path(), "/assets");
+    }
+
+    #[test]
+    fn test_insights_from_views() {
+        const MB: u64 = 1024 * 1024;
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["src", "main.rs"], 10 * MB, 5 * MB, 1);
+        root.add_path_with_sizes(&["art", "cover.psd"], 30 * MB, 0, 1);
+        root.compute_totals();
+        let mut vm = AppViewModel::new(root, vec![]);
+        let psd = ExtensionStats { cumulative_size: 30 * MB, current_size: 0, deleted_size: 30 * MB, file_count: 1, binary_size: 0 };
+        vm.extension_vm.set_stats(vec![(".psd".into(), psd)]);
+
+        vm.handle_intent(Intent::ShowInsights);
+        assert_eq!(vm.view_mode(), ViewMode::Insights);
+        let titles: Vec<_> = vm.insights_vm.insights().iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, ["75% reclaimable via filter-repo on /art", "Enable LFS for *.psd"]);
+
+        vm.handle_intent(Intent::ShowInsights);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+    }
+
+    #[test]
+    fn test_preview_overlay() {
+        let blob = LargeBlobInfo {
+            oid: vec![0xab; 20],
+            size: 5000,
+            path: "assets/logo.png".into(),
+            first_author: "Ann".into(),
+            first_email: "ann@example.com".into(),
+            first_date: 0,
+            first_subject: String::new(),
+            in_head: false,
+        };
+        let mut vm = AppViewModel::new(create_test_tree(), vec![blob]);
+
+        // Directories have no content to show
+        assert_eq!(vm.handle_intent(Intent::Preview), Action::Redraw);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(
+            vm.handle_intent(Intent::Preview),
+            Action::Preview { oid: None, path: "assets/logo.png".into() }
+        );
+        vm.handle_intent(Intent::ShowLargeBlobs);
+        assert_eq!(
+            vm.handle_intent(Intent::Preview),
+            Action::Preview { oid: Some(vec![0xab; 20]), path: "assets/logo.png".into() }
+        );
+
+        vm.set_preview(Ok(BlobPreview::new("assets/logo.png", "abab", 5000, b"\x89PNG\r\n\x1a\n\0")));
+        assert!(vm.preview().is_some_and(|p| p.is_ok()));
+        vm.handle_intent(Intent::MoveDown);
+        assert!(vm.preview().is_none());
+        vm.set_preview(Err(anyhow::anyhow!("gone")));
+        assert_eq!(vm.preview(), Some(Err("gone")));
+    }
+
+    #[test]
+    fn test_restore_overlay() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(
+            vm.handle_intent(Intent::RestoreFile),
+            Action::PlanRestore { oid: None, path: "assets/logo.png".into() }
+        );
+        vm.handle_intent(Intent::Back);
+        vm.handle_intent(Intent::MoveDown);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(vm.handle_intent(Intent::RestoreFile), Action::Redraw);
+        assert_eq!(vm.restore(), Some(Err("src/main.rs is in HEAD; only deleted files are restored")));
+        vm.handle_intent(Intent::MoveDown);
+        assert!(vm.restore().is_none());
+
+        let plan = RestorePlan::new("cafe01", "old/data.bin", false);
+        vm.set_restore(Ok(plan.clone()));
+        assert_eq!(
+            vm.handle_intent(Intent::Enter),
+            Action::RestoreFile { commit: "cafe01".into(), path: "old/data.bin".into() }
+        );
+        vm.set_restored(Ok(()));
+        assert!(vm.restore().is_some_and(|restore| restore.is_ok_and(|plan| plan.restored)));
+        // Once restored, Enter only closes the popup
+        assert_eq!(vm.handle_intent(Intent::Enter), Action::Redraw);
+        assert!(vm.restore().is_none());
+
+        vm.set_restore(Ok(plan));
+        vm.set_restored(Err(anyhow::anyhow!("A bare repository has no worktree to restore into")));
+        assert_eq!(vm.restore(), Some(Err("A bare repository has no worktree to restore into")));
+    }
+
+    #[test]
+    fn test_recovery_commands_overlay() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        assert_eq!(vm.handle_intent(Intent::CopyRecoveryCommand), Action::Redraw);
+        vm.handle_intent(Intent::Enter);
+        assert_eq!(
+            vm.handle_intent(Intent::CopyRecoveryCommand),
+            Action::CopyRecoveryCommand { oid: None, path: "assets/logo.png".into() }
+        );
+
+        let commands = RecoveryCommands::new("abab", "assets/logo.png");
+        vm.set_recovery(Ok(commands.clone()));
+        assert_eq!(vm.recovery(), Some(Ok(&commands)));
+        vm.handle_intent(Intent::MoveDown);
+        assert!(vm.recovery().is_none());
+
+        // Copying the same blob again lists it once on exit
+        vm.set_recovery(Ok(commands.clone()));
+        vm.set_recovery(Err(anyhow::anyhow!("No blob recorded for assets/logo.png")));
+        assert_eq!(vm.recovered(), &[commands]);
+    }
+
+    #[test]
+    fn test_lfs_plan_overlay() {
+        const MB: u64 = 1024 * 1024;
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        let zip = ExtensionStats { cumulative_size: 64 * MB, current_size: 0, deleted_size: 64 * MB, file_count: 4, binary_size: 0 };
+        vm.extension_vm.set_stats(vec![(".zip".into(), zip)]);
+
+        vm.handle_intent(Intent::ShowInsights);
+        vm.handle_intent(Intent::ShowLfsPlan);
+        let plan = vm.lfs_plan().expect("plan is open");
+        assert_eq!(plan.command(), "git lfs migrate import --everything --include=\"*.zip\"");
+
+        // Like help, the next key only closes it
+        vm.handle_intent(Intent::ShowInsights);
+        assert!(vm.lfs_plan().is_none());
+        assert_eq!(vm.view_mode(), ViewMode::Insights);
+    }
+
+    #[test]
+    fn test_size_deltas() {
+        let old = create_test_tree();
+        let mut new = Tree::new();
+        new.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
+        new.add_path_with_sizes(&["src", "big.bin"], 4000, 4000, 1);
+        new.add_path_with_sizes(&["assets", "logo.png"], 4500, 0, 1);
+        new.compute_totals();
+
+        let deltas = size_deltas(&old, &new);
+        assert_eq!(deltas.get("src/big.bin"), Some(&4000));
+        assert_eq!(deltas.get("src"), Some(&4000));
+        assert_eq!(deltas.get("assets/logo.png"), Some(&-500));
+        assert!(!deltas.contains_key("src/main.rs"));
+    }
+
+    #[test]
+    fn test_replace_tree_keeps_state() {
+        let mut app = AppViewModel::new(create_test_tree(), vec![]);
+        app.tree_vm.open_path("src/main.rs");
+        app.handle_intent(Intent::ToggleDeletedOnly);
+        assert_eq!(app.handle_intent(Intent::Rescan), Action::Rescan);
+
+        let mut new = create_test_tree();
+        new.add_path_with_sizes(&["src", "gone.bin"], 700, 0, 1);
+        new.compute_totals();
+        app.replace_tree(new, vec![], vec![]);
+
+        assert!(app.is_deleted_only());
+        assert!(app.tree_vm.is_deleted_only());
+        assert_eq!(app.tree_vm.current_path(), "/src");
+        let children = app.tree_vm.visible_children();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "gone.bin");
+        assert_eq!(children[0].size_delta, 700);
+    }
+
+    #[test]
+    fn test_blob_lookup_flow() {
+        let tree = create_test_tree();
+        let mut vm = AppViewModel::new(tree, vec![]);
+
+        vm.handle_intent(Intent::EnterBlobLookup);
+        assert_eq!(vm.view_mode(), ViewMode::BlobLookup);
+        assert!(vm.is_text_input());
+
+        for c in "abcd".chars() {
+            vm.handle_intent(Intent::SearchChar(c));
+        }
+        assert_eq!(vm.handle_intent(Intent::Enter), Action::LookupBlob("abcd".to_string()));
+
+        vm.set_lookup_result(Ok(None));
+        assert_eq!(vm.handle_intent(Intent::Enter), Action::Redraw);
+
+        vm.handle_intent(Intent::ShowTree);
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+        assert_eq!(vm.lookup_vm.query(), "");
+    }
+
+    #[test]
+    fn test_marks_aggregate_across_views() {
+        let tree = create_test_tree();
+        let mut vm = AppViewModel::new(tree, vec![]);
+        vm.extension_vm.set_stats(create_test_stats());
+
+        // Tree root: assets (5000/0) then src (1000/500)
+        vm.handle_intent(Intent::ToggleMark);
+        assert_eq!(vm.tree_vm.selected_index(), 1);
+        vm.handle_intent(Intent::ToggleMark);
+
+        vm.handle_intent(Intent::ShowExtensions);
+        vm.handle_intent(Intent::ToggleMark);
+
+        // .png is the largest extension
+        assert_eq!(vm.marks.len(), 3);
+        assert_eq!(vm.marks.totals(), MarkSizes { cumulative: 11000, current: 500 });
+
+        // Marks survive mode switches; the cursor stayed on the last entry
+        vm.handle_intent(Intent::ShowTree);
+        vm.handle_intent(Intent::ToggleMark);
+        assert!(!vm.marks.contains_path("src"));
+        assert_eq!(vm.marks.len(), 2);
+
+        vm.handle_intent(Intent::ClearMarks);
+        assert!(vm.marks.is_empty());
+    }
+
+    #[test]
+    fn test_visual_range_marks() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+
+        // Tree root: assets then src
+        vm.handle_intent(Intent::ToggleVisual);
+        vm.handle_intent(Intent::MoveDown);
+        vm.handle_intent(Intent::ToggleMark);
+        assert!(!vm.marks.is_visual());
+        assert!(vm.marks.contains_path("assets") && vm.marks.contains_path("src"));
+        assert_eq!(vm.marks.totals(), MarkSizes { cumulative: 6000, current: 500 });
+
+        // Back cancels the selection without leaving the directory
+        vm.handle_intent(Intent::ToggleVisual);
+        vm.handle_intent(Intent::MoveUp);
+        vm.handle_intent(Intent::Back);
+        assert!(!vm.marks.is_visual());
+        assert!(vm.tree_vm.is_at_root());
+
+        // A range that is all marked gets unmarked
+        vm.handle_intent(Intent::ToggleVisual);
+        vm.handle_intent(Intent::MoveDown);
+        vm.handle_intent(Intent::ToggleMark);
+        assert!(vm.marks.is_empty());
+
+        // Other actions end the selection, and views without ranges never start one
+        vm.handle_intent(Intent::ToggleVisual);
+        vm.handle_intent(Intent::ShowExtensions);
+        assert!(!vm.marks.is_visual());
+        vm.handle_intent(Intent::ToggleVisual);
+        assert!(!vm.marks.is_visual());
+    }
+
+    #[test]
+    fn test_undo_redo_marks_and_filters() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+
+        vm.handle_intent(Intent::ToggleMark);
+        vm.handle_intent(Intent::ToggleDeletedOnly);
+        vm.handle_intent(Intent::CycleVendoredFilter);
+        vm.handle_intent(Intent::ClearMarks);
+        // Moving around is not a change to undo
+        vm.handle_intent(Intent::MoveUp);
+        assert!(vm.marks.is_empty());
+
+        vm.handle_intent(Intent::Undo);
+        assert!(vm.marks.contains_path("assets"));
+        assert_eq!(vm.tree_vm.vendored_filter(), VendoredFilter::Hide);
+        vm.handle_intent(Intent::Undo);
+        assert_eq!(vm.tree_vm.vendored_filter(), VendoredFilter::All);
+        vm.handle_intent(Intent::Undo);
+        assert!(!vm.is_deleted_only());
+        assert!(!vm.tree_vm.is_deleted_only());
+        vm.handle_intent(Intent::Undo);
+        assert!(vm.marks.is_empty());
+        // Nothing older: stays put
+        vm.handle_intent(Intent::Undo);
+        assert!(vm.marks.is_empty());
+
+        vm.handle_intent(Intent::Redo);
+        vm.handle_intent(Intent::Redo);
+        assert!(vm.marks.contains_path("assets"));
+        assert!(vm.is_deleted_only() && vm.blobs_vm.is_deleted_only());
+
+        // A new change forgets what could be redone
+        vm.handle_intent(Intent::ToggleMark);
+        vm.handle_intent(Intent::Redo);
+        assert_eq!(vm.tree_vm.vendored_filter(), VendoredFilter::All);
+    }
+
+    #[test]
+    fn test_session_records_the_investigation() {
+        let mut vm = AppViewModel::new(create_test_tree(), vec![]);
+        vm.extension_vm.set_stats(create_test_stats());
+        vm.handle_intent(Intent::Enter);
+        assert!(vm.session().is_none());
+
+        vm.record_session();
+        vm.handle_intent(Intent::ToggleMark);
+        vm.handle_intent(Intent::ShowExtensions);
+        vm.handle_intent(Intent::ToggleMark);
+        vm.handle_intent(Intent::ShowTree);
+        vm.handle_intent(Intent::EnterSearch);
+        for c in "main".chars() {
+            vm.handle_intent(Intent::SearchChar(c));
+        }
+        vm.handle_intent(Intent::Enter);
+        vm.handle_intent(Intent::ClearMarks);
+
+        let events = vm.session().unwrap().events();
+        assert_eq!(
+            events,
+            [
+                SessionEvent::View("Tree"),
+                SessionEvent::Directory("/assets".into()),
+                SessionEvent::Marked { label: "assets/logo.png".into(), cumulative: 5000, current: 0 },
+                SessionEvent::View("File types"),
+                SessionEvent::Marked { label: "all .png files".into(), cumulative: 5000, current: 0 },
+                SessionEvent::View("Tree"),
+                SessionEvent::Search { query: "main".into(), matches: 1, opened: Some("src/main.rs".into()) },
+                SessionEvent::Directory("/src".into()),
+                SessionEvent::ClearedMarks(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deleted_filter_is_shared() {
+        let tree = create_test_tree();
+        let mut vm = AppViewModel::new(tree, vec![]);
+        vm.extension_vm.set_stats(create_test_stats());
+
+        vm.handle_intent(Intent::ShowExtensions);
+        vm.handle_intent(Intent::ToggleDeletedOnly);
+        assert!(vm.is_deleted_only());
+        assert!(vm.tree_vm.is_deleted_only());
+        assert!(vm.blobs_vm.is_deleted_only());
+        assert_eq!(vm.extension_vm.stats().len(), 1);
+
+        // '/' filters the extension list, so search from the tree
+        vm.handle_intent(Intent::ShowTree);
+        vm.handle_intent(Intent::EnterSearch);
+        for c in "main".chars() {
+            vm.handle_intent(Intent::SearchChar(c));
+        }
+        assert_eq!(vm.search_vm.total_matches(), 0);
+
+        vm.handle_intent(Intent::ToggleDeletedOnly);
+        assert!(!vm.extension_vm.is_deleted_only());
+        assert_eq!(vm.search_vm.total_matches(), 1);
+    }
+
+    #[test]
+    fn test_help_overlay_swallows_next_key() {
+        let tree = create_test_tree();
+        let mut vm = AppViewModel::new(tree, vec![]);
+
+        vm.handle_intent(Intent::ToggleHelp);
+        assert!(vm.is_help_visible());
+
+        // The key that closes the overlay isn't acted on
+        vm.handle_intent(Intent::ShowExtensions);
+        assert!(!vm.is_help_visible());
+        assert_eq!(vm.view_mode(), ViewMode::Tree);
+    }
+
+    #[test]
+    fn test_load_dir_extends_the_tree_in_place() {
+        let entry = |name: &str, is_dir| DirEntry {
+            name: name.into(),
+            is_dir,
+            cumulative_size: 100,
+            current_size: 100,
+            blob_count: 1,
+            deleted_size: 0,
+            kind: EntryKind::File,
+        };
+        let mut vm = AppViewModel::new(Tree::from_dir_entries(vec![entry("src", true)]), vec![]);
+        let tree = Arc::as_ptr(&vm.root);
+
+        vm.tree_vm.open_path("src/main.rs");
+        let (dir, path) = vm.pending_dir().unwrap();
+        vm.load_dir(dir, &path, vec![entry("main.rs", false)]);
+
+        assert_eq!(Arc::as_ptr(&vm.root), tree);
+        assert_eq!(vm.pending_dir(), None);
+        assert_eq!(vm.tree_vm.selected_path().as_deref(), Some("src/main.rs"));
+    }
+
     #[test]
     fn test_quit_action() {
         let tree = create_test_tree();
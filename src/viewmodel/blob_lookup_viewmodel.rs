@@ -0,0 +1,169 @@
+use anyhow::Result;
+
+use crate::model::BlobLookup;
+
+use super::selection::Selectable;
+
+/// Outcome of the most recent lookup
+#[derive(Debug, Clone)]
+pub enum LookupState {
+    /// Nothing looked up yet for the current query
+    Idle,
+    Found(BlobLookup),
+    NotFound,
+    Failed(String),
+}
+
+/// ViewModel for the OID lookup prompt
+pub struct BlobLookupViewModel {
+    query: String,
+    /// Query the current state belongs to
+    looked_up: String,
+    state: LookupState,
+    selected_index: usize,
+}
+
+impl BlobLookupViewModel {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            looked_up: String::new(),
+            state: LookupState::Idle,
+            selected_index: 0,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn state(&self) -> &LookupState {
+        &self.state
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    /// Add a character to the query; only hex digits are accepted
+    pub fn add_char(&mut self, c: char) {
+        if c.is_ascii_hexdigit() && self.query.len() < 40 {
+            self.query.push(c.to_ascii_lowercase());
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.looked_up.clear();
+        self.state = LookupState::Idle;
+        self.selected_index = 0;
+    }
+
+    /// Whether Enter should run a lookup rather than open a result
+    pub fn needs_lookup(&self) -> bool {
+        !self.query.is_empty()
+            && (self.query != self.looked_up || matches!(self.state, LookupState::Idle))
+    }
+
+    /// Store the result of looking up the current query
+    pub fn set_result(&mut self, result: Result<Option<BlobLookup>>) {
+        self.looked_up = self.query.clone();
+        self.selected_index = 0;
+        self.state = match result {
+            Ok(Some(blob)) => LookupState::Found(blob),
+            Ok(None) => LookupState::NotFound,
+            Err(e) => LookupState::Failed(e.to_string()),
+        };
+    }
+
+    /// Path of the selected occurrence, if a blob was found
+    pub fn selected_path(&self) -> Option<&str> {
+        match &self.state {
+            LookupState::Found(blob) => blob
+                .occurrences
+                .get(self.selected_index)
+                .map(|o| o.path.as_str()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for BlobLookupViewModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Selectable for BlobLookupViewModel {
+    fn len(&self) -> usize {
+        match &self.state {
+            LookupState::Found(blob) => blob.occurrences.len(),
+            _ => 0,
+        }
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::BlobOccurrence;
+
+    fn found() -> BlobLookup {
+        BlobLookup {
+            oid: vec![0xab; 20],
+            size: 1000,
+            path: "a.bin".to_string(),
+            first_author: "alice".to_string(),
+            first_email: "alice@example.com".to_string(),
+            first_date: 0,
+            first_subject: String::new(),
+            occurrences: vec![
+                BlobOccurrence { path: "a.bin".to_string(), in_head: false },
+                BlobOccurrence { path: "copy/a.bin".to_string(), in_head: true },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_query_accepts_only_hex() {
+        let mut vm = BlobLookupViewModel::new();
+        for c in "aBz9!".chars() {
+            vm.add_char(c);
+        }
+        assert_eq!(vm.query(), "ab9");
+    }
+
+    #[test]
+    fn test_needs_lookup_until_result_for_query() {
+        let mut vm = BlobLookupViewModel::new();
+        assert!(!vm.needs_lookup());
+
+        for c in "abab".chars() {
+            vm.add_char(c);
+        }
+        assert!(vm.needs_lookup());
+
+        vm.set_result(Ok(Some(found())));
+        assert!(!vm.needs_lookup());
+        assert_eq!(vm.selected_path(), Some("a.bin"));
+
+        vm.move_down();
+        assert_eq!(vm.selected_path(), Some("copy/a.bin"));
+
+        // Editing the query invalidates the result for Enter purposes
+        vm.backspace();
+        assert!(vm.needs_lookup());
+    }
+}
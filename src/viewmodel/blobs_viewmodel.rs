@@ -1,27 +1,406 @@
-use crate::model::LargeBlobInfo;
+use std::collections::HashMap;
 
+use crate::model::{cluster_near_duplicates, AuthorGrouping, BlobFilter, ExportValue, Fingerprint, LargeBlobInfo, ListExport};
+use crate::util::display_width;
+
+use super::list_filter::ListFilter;
+use super::path_scroll::PathScroll;
 use super::selection::Selectable;
+use super::selection_set::{MarkKey, MarkSizes};
+
+/// Optional columns in the large blobs list
+///
+/// These are the user's choices; the view may still hide enabled columns
+/// when the terminal is too narrow to fit them next to the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobColumns {
+    pub oid: bool,
+    pub author: bool,
+    pub date: bool,
+}
+
+impl Default for BlobColumns {
+    fn default() -> Self {
+        Self { oid: true, author: true, date: true }
+    }
+}
 
 /// ViewModel for large blobs view
 pub struct BlobsViewModel {
     blobs: Vec<LargeBlobInfo>,
+    /// Largest blobs not in HEAD; starts as the subset of `blobs`
+    deleted_blobs: Vec<LargeBlobInfo>,
+    show_deleted_only: bool,
     selected_index: usize,
     total_cumulative: u64,
+    columns: BlobColumns,
+    /// Whether the author column shows names or email domains
+    author_grouping: AuthorGrouping,
+    show_groups: bool,
+    /// Near-duplicate group number (from 1) by OID, once fingerprinted
+    groups: Option<HashMap<Vec<u8>, usize>>,
+    /// The shown list reordered so each group's members sit together
+    grouped_blobs: Vec<LargeBlobInfo>,
+    /// Narrows the shown list down to the blobs its text (parsed as a
+    /// `BlobFilter`) accepts
+    filter: ListFilter,
+    /// The shown list, narrowed by an active `filter`
+    filtered_blobs: Vec<LargeBlobInfo>,
+    /// The largest blobs matching a filter text, deleted-only or not, read
+    /// from the index so they aren't limited to those loaded up front
+    matched: Option<(String, bool, Vec<LargeBlobInfo>)>,
+    /// Whether a pack or loose object still holds each blob checked so
+    /// far, by OID; `None` when that can't be told
+    stored: HashMap<Vec<u8>, Option<bool>>,
+    /// How far paths cut to fit are scrolled towards their starts
+    scroll: PathScroll,
 }
 
 impl BlobsViewModel {
     pub fn new(blobs: Vec<LargeBlobInfo>, total_cumulative: u64) -> Self {
+        let deleted_blobs = blobs.iter().filter(|b| !b.in_head).cloned().collect();
         Self {
             blobs,
+            deleted_blobs,
+            show_deleted_only: false,
             selected_index: 0,
             total_cumulative,
+            columns: BlobColumns::default(),
+            author_grouping: AuthorGrouping::default(),
+            show_groups: false,
+            groups: None,
+            grouped_blobs: Vec::new(),
+            filter: ListFilter::default(),
+            filtered_blobs: Vec::new(),
+            matched: None,
+            stored: HashMap::new(),
+            scroll: PathScroll::default(),
         }
     }
 
+    /// Columns the paths are scrolled back from their ends
+    pub fn path_scroll(&self) -> usize {
+        self.scroll.offset()
+    }
+
+    /// Scroll the paths towards their starts (`back`) or their ends
+    pub fn scroll_paths(&mut self, back: bool) {
+        if back {
+            let longest = self.blobs().iter().map(|blob| display_width(&blob.path)).max().unwrap_or(0);
+            self.scroll.left(longest);
+        } else {
+            self.scroll.right();
+        }
+    }
+
+    pub fn columns(&self) -> BlobColumns {
+        self.columns
+    }
+
+    pub fn set_author_grouping(&mut self, grouping: AuthorGrouping) {
+        self.author_grouping = grouping;
+    }
+
+    /// What the author column shows for `blob`
+    pub fn author_label(&self, blob: &LargeBlobInfo) -> String {
+        self.author_grouping.label(&blob.first_author, &blob.first_email)
+    }
+
+    pub fn toggle_oid_column(&mut self) {
+        self.columns.oid = !self.columns.oid;
+    }
+
+    pub fn toggle_author_column(&mut self) {
+        self.columns.author = !self.columns.author;
+    }
+
+    pub fn toggle_date_column(&mut self) {
+        self.columns.date = !self.columns.date;
+    }
+
+    /// Blobs to display: the largest overall, or the largest not in HEAD,
+    /// with near-duplicates pulled together when grouping is on, and only
+    /// those the filter leaves
     pub fn blobs(&self) -> &[LargeBlobInfo] {
+        if self.filter.is_active() {
+            &self.filtered_blobs
+        } else {
+            self.unfiltered_blobs()
+        }
+    }
+
+    fn unfiltered_blobs(&self) -> &[LargeBlobInfo] {
+        if self.show_groups && self.groups.is_some() {
+            &self.grouped_blobs
+        } else {
+            self.listed_blobs()
+        }
+    }
+
+    fn listed_blobs(&self) -> &[LargeBlobInfo] {
+        if self.show_deleted_only {
+            &self.deleted_blobs
+        } else {
+            &self.blobs
+        }
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.show_groups
+    }
+
+    pub fn toggle_groups(&mut self) {
+        self.show_groups = !self.show_groups;
+        self.selected_index = 0;
+        self.refilter();
+    }
+
+    pub fn filter(&self) -> &ListFilter {
+        &self.filter
+    }
+
+    /// Change the filter, keeping the selected blob selected if it is still shown
+    pub fn edit_filter(&mut self, edit: impl FnOnce(&mut ListFilter)) {
+        self.keep_selection(|vm| {
+            edit(&mut vm.filter);
+            vm.refilter();
+        });
+    }
+
+    /// The filter to look up in the index, while its matches aren't loaded
+    ///
+    /// A grouped list is only narrowed down, as grouping compares the blobs
+    /// loaded up front.
+    pub fn pending_filter(&self) -> Option<BlobFilter> {
+        if self.show_groups || self.matched_blobs().is_some() {
+            return None;
+        }
+        BlobFilter::parse(self.filter.text())
+    }
+
+    /// Show `blobs`, the largest matching filter `text` (only those not in
+    /// HEAD with `deleted_only`), while the filter still reads so
+    pub fn set_filter_matches(&mut self, text: &str, deleted_only: bool, blobs: Vec<LargeBlobInfo>) {
+        self.keep_selection(|vm| {
+            vm.matched = Some((text.to_string(), deleted_only, blobs));
+            vm.refilter();
+        });
+    }
+
+    /// Matches read for the current filter and deleted-only setting
+    fn matched_blobs(&self) -> Option<&[LargeBlobInfo]> {
+        match &self.matched {
+            Some((text, deleted_only, blobs)) if *text == self.filter.text() && *deleted_only == self.show_deleted_only => {
+                Some(blobs)
+            }
+            _ => None,
+        }
+    }
+
+    fn keep_selection(&mut self, change: impl FnOnce(&mut Self)) {
+        let selected = self.blobs().get(self.selected_index).map(|b| (b.oid.clone(), b.path.clone()));
+        change(self);
+        self.selected_index = selected
+            .and_then(|(oid, path)| self.blobs().iter().position(|b| b.oid == oid && b.path == path))
+            .unwrap_or(0);
+    }
+
+    /// Narrow the list down by the filter: to what the index returned when
+    /// it has been asked, otherwise to the loaded blobs it accepts
+    fn refilter(&mut self) {
+        let Some(matching) = BlobFilter::parse(self.filter.text()) else {
+            self.filtered_blobs = self.unfiltered_blobs().to_vec();
+            return;
+        };
+        self.filtered_blobs = match self.matched_blobs().filter(|_| !self.show_groups) {
+            Some(matched) => matched.to_vec(),
+            None => self.unfiltered_blobs().iter().filter(|b| matching.matches(&b.path)).cloned().collect(),
+        };
+    }
+
+    /// Grouping is on but the blobs haven't been fingerprinted yet
+    pub fn needs_groups(&self) -> bool {
+        self.show_groups && self.groups.is_none()
+    }
+
+    /// Blobs to fingerprint: both lists, each blob once
+    pub fn group_candidates(&self) -> Vec<&LargeBlobInfo> {
+        let mut candidates: Vec<&LargeBlobInfo> = self.blobs.iter().collect();
+        for blob in &self.deleted_blobs {
+            if !self.blobs.iter().any(|b| b.oid == blob.oid) {
+                candidates.push(blob);
+            }
+        }
+        candidates
+    }
+
+    /// Group the candidates by their fingerprints, which line up with
+    /// `group_candidates`
+    pub fn set_fingerprints(&mut self, fingerprints: &[Option<Fingerprint>]) {
+        let candidates: Vec<LargeBlobInfo> = self.group_candidates().into_iter().cloned().collect();
+        let mut groups = HashMap::new();
+        for (number, members) in cluster_near_duplicates(&candidates, fingerprints).into_iter().enumerate() {
+            for index in members {
+                groups.insert(candidates[index].oid.clone(), number + 1);
+            }
+        }
+        self.groups = Some(groups);
+        self.regroup();
+    }
+
+    /// The listed blobs as a table, with the optional columns turned on;
+    /// dates are Unix timestamps
+    pub fn export(&self) -> ListExport {
+        let name = if self.show_deleted_only { "deleted-blobs" } else { "large-blobs" };
+        let mut columns = vec!["size", "in_head"];
+        if self.columns.oid {
+            columns.push("oid");
+        }
+        if self.columns.author {
+            columns.push("author");
+        }
+        if self.columns.date {
+            columns.push("date");
+        }
+        if self.show_groups {
+            columns.push("group");
+        }
+        columns.push("path");
+        let mut export = ListExport::new(name, columns);
+        for blob in self.blobs() {
+            let mut row: Vec<ExportValue> = vec![blob.size.into(), blob.in_head.into()];
+            if self.columns.oid {
+                row.push(hex::encode(&blob.oid).into());
+            }
+            if self.columns.author {
+                row.push(self.author_label(blob).into());
+            }
+            if self.columns.date {
+                row.push(blob.first_date.into());
+            }
+            if self.show_groups {
+                row.push(self.group_of(blob).map_or(ExportValue::Empty, |group| (group as u64).into()));
+            }
+            row.push(blob.path.as_str().into());
+            export.push(row);
+        }
+        export
+    }
+
+    /// Near-duplicate group of a blob, if it has any
+    pub fn group_of(&self, blob: &LargeBlobInfo) -> Option<usize> {
+        self.groups.as_ref()?.get(&blob.oid).copied()
+    }
+
+    /// Groups with at least two members in the shown list
+    pub fn group_count(&self) -> usize {
+        let mut sizes: HashMap<usize, usize> = HashMap::new();
+        for blob in self.listed_blobs() {
+            if let Some(group) = self.group_of(blob) {
+                *sizes.entry(group).or_default() += 1;
+            }
+        }
+        sizes.values().filter(|&&n| n > 1).count()
+    }
+
+    /// Order the shown list so a group follows its largest member
+    fn regroup(&mut self) {
+        let listed = self.listed_blobs();
+        let mut grouped = Vec::with_capacity(listed.len());
+        let mut placed = vec![false; listed.len()];
+        for (i, blob) in listed.iter().enumerate() {
+            if placed[i] {
+                continue;
+            }
+            match self.group_of(blob) {
+                Some(group) => {
+                    for (j, member) in listed.iter().enumerate().skip(i) {
+                        if !placed[j] && self.group_of(member) == Some(group) {
+                            placed[j] = true;
+                            grouped.push(member.clone());
+                        }
+                    }
+                }
+                None => grouped.push(blob.clone()),
+            }
+        }
+        self.grouped_blobs = grouped;
+        self.refilter();
+    }
+
+    /// OIDs of loaded blobs not yet checked against the object database
+    pub fn unchecked_oids(&self) -> Vec<Vec<u8>> {
+        let matched = self.matched.iter().flat_map(|(_, _, blobs)| blobs);
+        let mut oids: Vec<Vec<u8>> = self
+            .blobs
+            .iter()
+            .chain(&self.deleted_blobs)
+            .chain(matched)
+            .filter(|b| !self.stored.contains_key(&b.oid))
+            .map(|b| b.oid.clone())
+            .collect();
+        oids.sort_unstable();
+        oids.dedup();
+        oids
+    }
+
+    /// Record what the object database holds, as (OID, still stored)
+    pub fn set_stored(&mut self, checked: impl IntoIterator<Item = (Vec<u8>, Option<bool>)>) {
+        self.stored.extend(checked);
+    }
+
+    /// Whether git has already pruned `blob`, so only the index remembers
+    /// it and rewriting history won't win its size back
+    pub fn is_reclaimed(&self, blob: &LargeBlobInfo) -> bool {
+        self.stored.get(&blob.oid) == Some(&Some(false))
+    }
+
+    /// Shown blobs git has already pruned
+    pub fn reclaimed_count(&self) -> usize {
+        self.blobs().iter().filter(|b| self.is_reclaimed(b)).count()
+    }
+
+    /// The largest blobs overall, whatever the filter
+    pub fn all_blobs(&self) -> &[LargeBlobInfo] {
         &self.blobs
     }
 
+    /// Show the blobs of a new scan, keeping the filter, columns and
+    /// grouping (fingerprinted again when next shown)
+    pub fn set_blobs(&mut self, blobs: Vec<LargeBlobInfo>, total_cumulative: u64) {
+        let mut fresh = Self::new(blobs, total_cumulative);
+        fresh.show_deleted_only = self.show_deleted_only;
+        fresh.columns = self.columns;
+        fresh.show_groups = self.show_groups;
+        fresh.filter = self.filter.clone();
+        fresh.refilter();
+        *self = fresh;
+    }
+
+    /// Replace the deleted-only list with one queried separately, so it
+    /// isn't limited to whatever made the overall top N
+    pub fn set_deleted_blobs(&mut self, blobs: Vec<LargeBlobInfo>) {
+        self.deleted_blobs = blobs;
+        self.groups = None;
+        if self.show_deleted_only {
+            self.selected_index = 0;
+        }
+        self.refilter();
+    }
+
+    pub fn is_deleted_only(&self) -> bool {
+        self.show_deleted_only
+    }
+
+    pub fn set_deleted_only(&mut self, deleted_only: bool) {
+        self.show_deleted_only = deleted_only;
+        self.selected_index = 0;
+        if self.groups.is_some() {
+            self.regroup();
+        }
+        self.refilter();
+    }
+
     pub fn selected_index(&self) -> usize {
         self.selected_index
     }
@@ -31,18 +410,38 @@ impl BlobsViewModel {
     }
 
     pub fn total_blob_size(&self) -> u64 {
-        self.blobs.iter().map(|b| b.size).sum()
+        self.blobs().iter().map(|b| b.size).sum()
+    }
+
+    pub fn selected_blob(&self) -> Option<&LargeBlobInfo> {
+        self.blobs().get(self.selected_index)
     }
 
     /// Get selected blob's path
     pub fn selected_path(&self) -> Option<&str> {
-        self.blobs.get(self.selected_index).map(|b| b.path.as_str())
+        self.selected_blob().map(|b| b.path.as_str())
+    }
+
+    /// Mark key and sizes of the selected blob
+    ///
+    /// A blob counts towards the current total only while HEAD still has it.
+    pub fn selected_mark(&self) -> Option<(MarkKey, MarkSizes)> {
+        self.mark_at(self.selected_index)
+    }
+
+    /// Mark key and sizes of the blob on row `index`
+    pub fn mark_at(&self, index: usize) -> Option<(MarkKey, MarkSizes)> {
+        self.blobs().get(index).map(|b| {
+            let current = if b.in_head { b.size } else { 0 };
+            let sizes = MarkSizes { cumulative: b.size, current };
+            (MarkKey::Blob(b.oid.clone()), sizes)
+        })
     }
 }
 
 impl Selectable for BlobsViewModel {
     fn len(&self) -> usize {
-        self.blobs.len()
+        self.blobs().len()
     }
 
     fn selected(&self) -> usize {
@@ -58,26 +457,34 @@ impl Selectable for BlobsViewModel {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_navigation() {
-        let blobs = vec![
+    fn test_blobs() -> Vec<LargeBlobInfo> {
+        vec![
             LargeBlobInfo {
                 oid: "abc".into(),
                 size: 1000,
                 path: "a.png".to_string(),
                 first_author: "alice".to_string(),
+                first_email: "alice@example.com".to_string(),
                 first_date: 0,
+                first_subject: String::new(),
+                in_head: true,
             },
             LargeBlobInfo {
                 oid: "def".into(),
                 size: 500,
                 path: "b.png".to_string(),
                 first_author: "bob".to_string(),
+                first_email: "bob@example.com".to_string(),
                 first_date: 0,
+                first_subject: String::new(),
+                in_head: false,
             },
-        ];
+        ]
+    }
 
-        let mut vm = BlobsViewModel::new(blobs, 10000);
+    #[test]
+    fn test_navigation() {
+        let mut vm = BlobsViewModel::new(test_blobs(), 10000);
 
         assert_eq!(vm.selected_index(), 0);
 
@@ -90,4 +497,115 @@ mod tests {
         vm.move_up();
         assert_eq!(vm.selected_index(), 1); // Wrap back
     }
+
+    #[test]
+    fn test_deleted_only_hides_head_blobs() {
+        let mut vm = BlobsViewModel::new(test_blobs(), 10000);
+
+        vm.set_deleted_only(true);
+        assert_eq!(vm.len(), 1);
+        assert_eq!(vm.selected_path(), Some("b.png"));
+        assert_eq!(vm.total_blob_size(), 500);
+
+        let mut older = test_blobs().remove(1);
+        older.path = "c.png".to_string();
+        vm.set_deleted_blobs(vec![older]);
+        assert_eq!(vm.selected_path(), Some("c.png"));
+
+        vm.set_deleted_only(false);
+        assert_eq!(vm.len(), 2);
+    }
+
+    #[test]
+    fn test_filter_shows_what_the_index_matched() {
+        let mut vm = BlobsViewModel::new(test_blobs(), 10000);
+        vm.edit_filter(|filter| filter.set_text("*.PNG"));
+        assert_eq!(vm.len(), 2);
+        assert_eq!(vm.pending_filter(), Some(BlobFilter::Extension(".png".to_string())));
+
+        let mut older = test_blobs().remove(1);
+        older.path = "old/c.png".to_string();
+        vm.set_filter_matches("*.PNG", false, vec![test_blobs().remove(0), older]);
+        assert_eq!(vm.pending_filter(), None);
+        assert_eq!(vm.blobs()[1].path, "old/c.png");
+
+        // Matches read for the whole list don't stand in for deleted-only ones
+        vm.set_deleted_only(true);
+        assert!(vm.pending_filter().is_some());
+        assert_eq!(vm.selected_path(), Some("b.png"));
+
+        vm.toggle_groups();
+        assert_eq!(vm.pending_filter(), None);
+    }
+
+    #[test]
+    fn test_reclaimed_blobs() {
+        let mut vm = BlobsViewModel::new(test_blobs(), 10000);
+        assert_eq!(vm.unchecked_oids(), [b"abc".to_vec(), b"def".to_vec()]);
+
+        vm.set_stored([(b"abc".to_vec(), Some(true)), (b"def".to_vec(), Some(false))]);
+        assert!(vm.unchecked_oids().is_empty());
+        assert!(!vm.is_reclaimed(&vm.blobs()[0]));
+        assert!(vm.is_reclaimed(&vm.blobs()[1]));
+        assert_eq!(vm.reclaimed_count(), 1);
+
+        // A new scan checks its blobs again
+        vm.set_blobs(test_blobs(), 10000);
+        assert_eq!(vm.reclaimed_count(), 0);
+        assert_eq!(vm.unchecked_oids().len(), 2);
+    }
+
+    #[test]
+    fn test_column_toggles() {
+        let mut vm = BlobsViewModel::new(vec![], 0);
+        assert_eq!(vm.columns(), BlobColumns::default());
+
+        vm.toggle_oid_column();
+        vm.toggle_date_column();
+        assert_eq!(vm.columns(), BlobColumns { oid: false, author: true, date: false });
+
+        vm.toggle_oid_column();
+        assert!(vm.columns().oid);
+    }
+
+    #[test]
+    fn test_groups_pull_near_duplicates_together() {
+        let blob = |oid: &str, size, path: &str| LargeBlobInfo {
+            oid: oid.into(),
+            size,
+            path: path.to_string(),
+            first_author: String::new(),
+            first_email: String::new(),
+            first_date: 0,
+            first_subject: String::new(),
+            in_head: true,
+        };
+        let mut vm = BlobsViewModel::new(
+            vec![blob("a", 3000, "v2.bin"), blob("b", 2900, "x.iso"), blob("c", 2800, "v1.bin")],
+            10000,
+        );
+        vm.toggle_groups();
+        assert!(vm.needs_groups());
+        // Not fingerprinted yet: plain order
+        assert_eq!(vm.selected_path(), Some("v2.bin"));
+
+        let content: Vec<u8> = (0..50_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        let other: Vec<u8> = (0..50_000u32).map(|i| (i.wrapping_mul(40_503) >> 7) as u8).collect();
+        let fingerprints = [Fingerprint::of(&content), Fingerprint::of(&other), Fingerprint::of(&content)];
+        vm.set_fingerprints(&fingerprints.map(Some));
+
+        assert!(!vm.needs_groups());
+        let paths: Vec<_> = vm.blobs().iter().map(|b| b.path.as_str()).collect();
+        assert_eq!(paths, ["v2.bin", "v1.bin", "x.iso"]);
+        assert_eq!(vm.group_count(), 1);
+        assert_eq!(vm.group_of(&vm.blobs()[1]), Some(1));
+
+        vm.toggle_groups();
+        assert_eq!(vm.blobs()[1].path, "x.iso");
+
+        // A rescan keeps grouping on but needs fresh fingerprints
+        vm.toggle_groups();
+        vm.set_blobs(vec![blob("d", 10, "y.bin")], 10);
+        assert!(vm.needs_groups());
+    }
 }
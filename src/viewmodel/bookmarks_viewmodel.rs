@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use crate::model::{NodeId, Tree};
+
+use super::selection::Selectable;
+
+/// A bookmarked path with its sizes, if the tree still has it
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookmarkView {
+    pub path: String,
+    /// (cumulative, current); `None` when the path is gone from the results
+    /// (excluded, or from a repository that was rewritten)
+    pub sizes: Option<(u64, u64)>,
+    pub has_children: bool,
+}
+
+/// ViewModel for the saved bookmark list
+pub struct BookmarksViewModel {
+    root: Arc<Tree>,
+    /// Sorted repository-relative paths
+    paths: Vec<String>,
+    selected_index: usize,
+}
+
+impl BookmarksViewModel {
+    pub fn new(root: Arc<Tree>) -> Self {
+        Self { root, paths: Vec::new(), selected_index: 0 }
+    }
+
+    /// Look sizes up in the results of a new scan
+    pub fn set_root(&mut self, root: Arc<Tree>) {
+        self.root = root;
+    }
+
+    /// Replace the list with bookmarks loaded from the index
+    pub fn set_bookmarks(&mut self, mut paths: Vec<String>) {
+        paths.sort();
+        paths.dedup();
+        self.paths = paths;
+        self.selected_index = 0;
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.binary_search_by(|p| p.as_str().cmp(path)).is_ok()
+    }
+
+    /// Add `path`, or remove it if it is already bookmarked; returns whether
+    /// it is bookmarked now
+    pub fn toggle(&mut self, path: &str) -> bool {
+        match self.paths.binary_search_by(|p| p.as_str().cmp(path)) {
+            Ok(index) => {
+                self.paths.remove(index);
+                self.selected_index = self.selected_index.min(self.paths.len().saturating_sub(1));
+                false
+            }
+            Err(index) => {
+                self.paths.insert(index, path.to_string());
+                true
+            }
+        }
+    }
+
+    /// Bookmarks in path order, with their current sizes
+    pub fn entries(&self) -> Vec<BookmarkView> {
+        self.paths
+            .iter()
+            .map(|path| {
+                let node = self.root.find(path);
+                BookmarkView {
+                    path: path.clone(),
+                    sizes: node.map(|n| (n.cumulative_size, n.current_size)),
+                    has_children: node.is_some_and(|n| n.has_children()),
+                }
+            })
+            .collect()
+    }
+
+    /// A directory, with its path, that has to be loaded before some
+    /// bookmark's sizes are known (see `Tree::set_dir_entries`)
+    pub fn pending_dir(&self) -> Option<(NodeId, String)> {
+        self.paths.iter().find_map(|path| {
+            let (dir, dir_path) = self.root.unloaded_ancestor(path)?;
+            Some((dir.id(), dir_path.to_string()))
+        })
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    pub fn selected_path(&self) -> Option<&str> {
+        self.paths.get(self.selected_index).map(String::as_str)
+    }
+}
+
+impl Selectable for BookmarksViewModel {
+    fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_keeps_paths_sorted() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["assets", "logo.png"], 5000, 0, 1);
+        root.compute_totals();
+        let mut vm = BookmarksViewModel::new(Arc::new(root));
+        vm.set_bookmarks(vec!["third_party".into(), "assets".into()]);
+
+        assert!(vm.toggle("assets/logo.png"));
+        assert_eq!(vm.selected_path(), Some("assets"));
+        let entries = vm.entries();
+        let paths: Vec<_> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["assets", "assets/logo.png", "third_party"]);
+        assert_eq!(entries[0].sizes, Some((5000, 0)));
+        assert!(entries[0].has_children);
+        assert_eq!(entries[2].sizes, None);
+
+        assert!(!vm.toggle("assets"));
+        assert!(!vm.contains("assets"));
+        assert!(vm.contains("third_party"));
+    }
+}
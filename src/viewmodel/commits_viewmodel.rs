@@ -0,0 +1,88 @@
+use crate::model::HeavyCommit;
+
+use super::selection::Selectable;
+
+/// ViewModel for the commits that added the most blob data
+#[derive(Default)]
+pub struct CommitsViewModel {
+    /// From `Database::get_heavy_commits`, heaviest first
+    commits: Vec<HeavyCommit>,
+    selected_index: usize,
+}
+
+impl CommitsViewModel {
+    /// Show the commits of a new scan, keeping the cursor in range
+    pub fn set_commits(&mut self, commits: Vec<HeavyCommit>) {
+        self.commits = commits;
+        self.selected_index = self.selected_index.min(self.commits.len().saturating_sub(1));
+    }
+
+    pub fn commits(&self) -> &[HeavyCommit] {
+        &self.commits
+    }
+
+    pub fn selected_commit(&self) -> Option<&HeavyCommit> {
+        self.commits.get(self.selected_index)
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    /// Path to show in the tree: the largest file the commit added
+    pub fn selected_path(&self) -> Option<&str> {
+        self.selected_commit().map(|commit| commit.largest_path.as_str()).filter(|path| !path.is_empty())
+    }
+
+    /// Bytes the listed commits added between them
+    pub fn total_added(&self) -> u64 {
+        self.commits.iter().map(|commit| commit.added_size).sum()
+    }
+}
+
+impl Selectable for CommitsViewModel {
+    fn len(&self) -> usize {
+        self.commits.len()
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(id: u8, added_size: u64, largest_path: &str) -> HeavyCommit {
+        HeavyCommit {
+            oid: vec![id; 20],
+            author: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            date: 1700000000,
+            subject: "Add demo".to_string(),
+            added_size,
+            blob_count: 1,
+            largest_path: largest_path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_selected_path_and_total() {
+        let mut vm = CommitsViewModel::default();
+        vm.set_commits(vec![commit(1, 300, "assets/demo.mp4"), commit(2, 100, "src/main.rs")]);
+        assert_eq!(vm.total_added(), 400);
+        vm.move_down();
+        assert_eq!(vm.selected_path(), Some("src/main.rs"));
+
+        // A rescan with fewer commits keeps the cursor on one of them
+        vm.set_commits(vec![commit(1, 300, "assets/demo.mp4")]);
+        assert_eq!(vm.selected_path(), Some("assets/demo.mp4"));
+        vm.set_commits(vec![]);
+        assert_eq!(vm.selected_path(), None);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::model::ScanDiagnostic;
+
+use super::selection::Selectable;
+
+/// ViewModel for the objects and packs the scans could not read
+#[derive(Default)]
+pub struct DiagnosticsViewModel {
+    /// From `Database::get_scan_diagnostics`, ordered by kind
+    diagnostics: Vec<ScanDiagnostic>,
+    selected_index: usize,
+}
+
+impl DiagnosticsViewModel {
+    /// Show the diagnostics of a new scan, keeping the cursor in range
+    pub fn set_diagnostics(&mut self, diagnostics: Vec<ScanDiagnostic>) {
+        self.diagnostics = diagnostics;
+        self.selected_index = self.selected_index.min(self.diagnostics.len().saturating_sub(1));
+    }
+
+    pub fn diagnostics(&self) -> &[ScanDiagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn selected_diagnostic(&self) -> Option<&ScanDiagnostic> {
+        self.diagnostics.get(self.selected_index)
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    /// Path to show in the tree: where an unreadable tree or an unsized blob
+    /// was met; commits and packs have none there
+    pub fn selected_path(&self) -> Option<&str> {
+        let diagnostic = self.selected_diagnostic()?;
+        let in_tree = matches!(diagnostic.kind.as_str(), "tree" | "size") && !diagnostic.path.is_empty();
+        in_tree.then_some(diagnostic.path.as_str())
+    }
+
+    /// (kind, count) of each kind listed, in list order
+    pub fn counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for diagnostic in &self.diagnostics {
+            match counts.last_mut() {
+                Some((kind, count)) if *kind == diagnostic.kind => *count += 1,
+                _ => counts.push((&diagnostic.kind, 1)),
+            }
+        }
+        counts
+    }
+}
+
+impl Selectable for DiagnosticsViewModel {
+    fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(kind: &str, path: &str) -> ScanDiagnostic {
+        ScanDiagnostic { oid: "ab".repeat(20), kind: kind.to_string(), path: path.to_string(), reason: "lost".to_string() }
+    }
+
+    #[test]
+    fn test_counts_and_selected_path() {
+        let mut vm = DiagnosticsViewModel::default();
+        vm.set_diagnostics(vec![
+            diagnostic("commit", ""),
+            diagnostic("pack", "pack-ab.idx"),
+            diagnostic("size", "assets/logo.png"),
+            diagnostic("size", "assets/intro.mp4"),
+        ]);
+        assert_eq!(vm.counts(), vec![("commit", 1), ("pack", 1), ("size", 2)]);
+
+        assert_eq!(vm.selected_path(), None);
+        vm.move_down();
+        assert_eq!(vm.selected_path(), None);
+        vm.move_down();
+        assert_eq!(vm.selected_path(), Some("assets/logo.png"));
+
+        vm.set_diagnostics(vec![diagnostic("tree", "vendor")]);
+        assert_eq!(vm.selected_index(), 0);
+        assert_eq!(vm.selected_path(), Some("vendor"));
+    }
+}
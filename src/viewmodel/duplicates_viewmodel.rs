@@ -0,0 +1,85 @@
+use crate::model::DuplicateBlob;
+
+use super::selection::Selectable;
+
+/// ViewModel for blobs committed at several paths
+#[derive(Default)]
+pub struct DuplicatesViewModel {
+    /// From `Database::get_duplicate_blobs`, largest copies first
+    duplicates: Vec<DuplicateBlob>,
+    selected_index: usize,
+}
+
+impl DuplicatesViewModel {
+    /// Show the duplicates of a new scan, keeping the cursor in range
+    pub fn set_duplicates(&mut self, duplicates: Vec<DuplicateBlob>) {
+        self.duplicates = duplicates;
+        self.selected_index = self.selected_index.min(self.duplicates.len().saturating_sub(1));
+    }
+
+    pub fn duplicates(&self) -> &[DuplicateBlob] {
+        &self.duplicates
+    }
+
+    pub fn selected_duplicate(&self) -> Option<&DuplicateBlob> {
+        self.duplicates.get(self.selected_index)
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    /// Path to show in the tree: a copy HEAD still has, else the first path
+    pub fn selected_path(&self) -> Option<&str> {
+        let duplicate = self.selected_duplicate()?;
+        let path = duplicate.paths.iter().find(|p| p.in_head).or(duplicate.paths.first())?;
+        Some(&path.path)
+    }
+
+    /// Worktree bytes taken by extra copies across the list
+    pub fn total_wasted(&self) -> u64 {
+        self.duplicates.iter().map(DuplicateBlob::wasted).sum()
+    }
+}
+
+impl Selectable for DuplicatesViewModel {
+    fn len(&self) -> usize {
+        self.duplicates.len()
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::BlobOccurrence;
+
+    #[test]
+    fn test_selected_path_prefers_head_copy() {
+        let occurrence = |path: &str, in_head| BlobOccurrence { path: path.to_string(), in_head };
+        let mut vm = DuplicatesViewModel::default();
+        vm.set_duplicates(vec![
+            DuplicateBlob {
+                oid: vec![1; 20],
+                size: 100,
+                paths: vec![occurrence("a/x.js", false), occurrence("b/x.js", true), occurrence("c/x.js", true)],
+            },
+            DuplicateBlob { oid: vec![2; 20], size: 50, paths: vec![occurrence("old.png", false), occurrence("older.png", false)] },
+        ]);
+
+        assert_eq!(vm.selected_path(), Some("b/x.js"));
+        assert_eq!(vm.total_wasted(), 100);
+        vm.move_down();
+        assert_eq!(vm.selected_path(), Some("old.png"));
+
+        vm.set_duplicates(vec![]);
+        assert_eq!(vm.selected_path(), None);
+    }
+}
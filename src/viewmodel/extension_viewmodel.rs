@@ -1,8 +1,8 @@
-use std::collections::HashMap;
-use crate::model::{ExtensionStats, TreeNode};
-use crate::util::extension_label;
+use crate::model::{ExtensionStats, ListExport};
 
+use super::list_filter::ListFilter;
 use super::selection::Selectable;
+use super::selection_set::{MarkKey, MarkSizes};
 
 /// Computed stats for display
 #[derive(Debug, Clone)]
@@ -10,64 +10,217 @@ pub struct ExtensionStatsView {
     pub extension: String,
     pub cumulative_size: u64,
     pub current_size: u64,
+    pub deleted_size: u64,
     pub file_count: u64,
+    /// Part of `cumulative_size` classified as binary
+    pub binary_size: u64,
+}
+
+/// Size the extension list is ranked, barred and charted by
+///
+/// Cumulative size answers what history holds, current size what is big
+/// right now, and deleted size what is pure history garbage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtensionRanking {
+    #[default]
+    Cumulative,
+    Current,
+    Deleted,
+}
+
+impl ExtensionRanking {
+    /// The ranking after this one, for toggling through them
+    pub fn next(self) -> Self {
+        match self {
+            ExtensionRanking::Cumulative => ExtensionRanking::Current,
+            ExtensionRanking::Current => ExtensionRanking::Deleted,
+            ExtensionRanking::Deleted => ExtensionRanking::Cumulative,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExtensionRanking::Cumulative => "cumulative",
+            ExtensionRanking::Current => "current",
+            ExtensionRanking::Deleted => "deleted",
+        }
+    }
+
+    pub fn size(self, stat: &ExtensionStatsView) -> u64 {
+        match self {
+            ExtensionRanking::Cumulative => stat.cumulative_size,
+            ExtensionRanking::Current => stat.current_size,
+            ExtensionRanking::Deleted => stat.deleted_size,
+        }
+    }
 }
 
 /// ViewModel for extension breakdown view
+///
+/// The stats come from `Database::get_extension_stats`, so the view can be
+/// refreshed without the tree.
+#[derive(Default)]
 pub struct ExtensionViewModel {
+    /// Largest cumulative size first
     stats: Vec<ExtensionStatsView>,
+    /// `stats` in `ranking` order
+    ranked_stats: Vec<ExtensionStatsView>,
+    ranking: ExtensionRanking,
+    /// Extensions with deleted content, largest deleted size first
+    deleted_stats: Vec<ExtensionStatsView>,
+    show_deleted_only: bool,
     selected_index: usize,
     total_cumulative: u64,
     total_current: u64,
+    total_deleted: u64,
     total_files: u64,
+    total_binary: u64,
+    /// Narrows the list down to extensions containing its text
+    filter: ListFilter,
+    /// The shown list, narrowed by an active `filter`
+    filtered_stats: Vec<ExtensionStatsView>,
 }
 
 impl ExtensionViewModel {
-    pub fn new(root: &TreeNode) -> Self {
-        let stats = Self::compute_stats(root);
-        let total_cumulative = stats.iter().map(|s| s.cumulative_size).sum();
-        let total_current = stats.iter().map(|s| s.current_size).sum();
-        let total_files = stats.iter().map(|s| s.file_count).sum();
-
-        Self {
-            stats,
-            selected_index: 0,
-            total_cumulative,
-            total_current,
-            total_files,
-        }
+    pub fn new(stats: Vec<(String, ExtensionStats)>) -> Self {
+        let mut vm = Self::default();
+        vm.set_stats(stats);
+        vm
     }
 
-    fn compute_stats(root: &TreeNode) -> Vec<ExtensionStatsView> {
-        let mut stats: HashMap<String, ExtensionStats> = HashMap::new();
-
-        root.visit_leaf_nodes(|node| {
-            let ext = extension_label(&node.name).into_owned();
-
-            let entry = stats.entry(ext).or_default();
-            entry.cumulative_size += node.cumulative_size;
-            entry.current_size += node.current_size;
-            entry.file_count += node.blob_count;
-        });
-
-        let mut result: Vec<_> = stats
+    /// Show `stats` instead, keeping the deleted-only filter
+    pub fn set_stats(&mut self, stats: Vec<(String, ExtensionStats)>) {
+        let mut stats: Vec<_> = stats
             .into_iter()
             .map(|(ext, s)| ExtensionStatsView {
                 extension: ext,
                 cumulative_size: s.cumulative_size,
                 current_size: s.current_size,
+                deleted_size: s.deleted_size,
                 file_count: s.file_count,
+                binary_size: s.binary_size,
             })
             .collect();
+        // Extensions of equal size keep a stable (alphabetical) order
+        stats.sort_by(|a, b| b.cumulative_size.cmp(&a.cumulative_size).then_with(|| a.extension.cmp(&b.extension)));
+
+        self.total_cumulative = stats.iter().map(|s| s.cumulative_size).sum();
+        self.total_current = stats.iter().map(|s| s.current_size).sum();
+        self.total_deleted = stats.iter().map(|s| s.deleted_size).sum();
+        self.total_files = stats.iter().map(|s| s.file_count).sum();
+        self.total_binary = stats.iter().map(|s| s.binary_size).sum();
 
-        result.sort_by(|a, b| b.cumulative_size.cmp(&a.cumulative_size));
-        result
+        let mut deleted_stats: Vec<_> = stats
+            .iter()
+            .filter(|s| s.deleted_size > 0)
+            .cloned()
+            .collect();
+        deleted_stats.sort_by_key(|s| std::cmp::Reverse(s.deleted_size));
+
+        self.stats = stats;
+        self.deleted_stats = deleted_stats;
+        self.rerank();
+        self.refilter();
+        self.selected_index = self.selected_index.min(self.stats().len().saturating_sub(1));
     }
 
-    pub fn stats(&self) -> &[ExtensionStatsView] {
+    /// Every extension, largest cumulative size first, whatever the filter
+    pub fn all_stats(&self) -> &[ExtensionStatsView] {
         &self.stats
     }
 
+    /// Extensions to display: all of them, or only those with deleted
+    /// content, and only those the filter leaves
+    pub fn stats(&self) -> &[ExtensionStatsView] {
+        if self.filter.is_active() {
+            &self.filtered_stats
+        } else {
+            self.unfiltered_stats()
+        }
+    }
+
+    fn unfiltered_stats(&self) -> &[ExtensionStatsView] {
+        if self.show_deleted_only {
+            &self.deleted_stats
+        } else {
+            &self.ranked_stats
+        }
+    }
+
+    pub fn ranking(&self) -> ExtensionRanking {
+        self.ranking
+    }
+
+    /// Rank by the next kind of size, keeping the selected extension
+    /// selected; the deleted-only list stays ranked by deleted size
+    pub fn cycle_ranking(&mut self) {
+        let selected = self.selected_extension().map(str::to_string);
+        self.ranking = self.ranking.next();
+        self.rerank();
+        self.refilter();
+        self.selected_index = selected
+            .and_then(|extension| self.stats().iter().position(|s| s.extension == extension))
+            .unwrap_or(0);
+    }
+
+    /// Ties keep their cumulative order
+    fn rerank(&mut self) {
+        let mut ranked = self.stats.clone();
+        ranked.sort_by_key(|s| std::cmp::Reverse(self.ranking.size(s)));
+        self.ranked_stats = ranked;
+    }
+
+    /// Size a listed extension is shown, barred and charted by: its
+    /// deleted size in the deleted-only list, else the ranking's
+    pub fn shown_size(&self, stat: &ExtensionStatsView) -> u64 {
+        if self.show_deleted_only {
+            stat.deleted_size
+        } else {
+            self.ranking.size(stat)
+        }
+    }
+
+    /// Total of `shown_size` over every extension
+    pub fn shown_total(&self) -> u64 {
+        match (self.show_deleted_only, self.ranking) {
+            (true, _) | (false, ExtensionRanking::Deleted) => self.total_deleted,
+            (false, ExtensionRanking::Cumulative) => self.total_cumulative,
+            (false, ExtensionRanking::Current) => self.total_current,
+        }
+    }
+
+    pub fn is_deleted_only(&self) -> bool {
+        self.show_deleted_only
+    }
+
+    pub fn set_deleted_only(&mut self, deleted_only: bool) {
+        self.show_deleted_only = deleted_only;
+        self.selected_index = 0;
+        self.refilter();
+    }
+
+    pub fn filter(&self) -> &ListFilter {
+        &self.filter
+    }
+
+    /// Change the filter, keeping the selected extension selected if it is
+    /// still shown
+    pub fn edit_filter(&mut self, edit: impl FnOnce(&mut ListFilter)) {
+        let selected = self.stats().get(self.selected_index).map(|s| s.extension.clone());
+        edit(&mut self.filter);
+        self.refilter();
+        self.selected_index = selected
+            .and_then(|extension| self.stats().iter().position(|s| s.extension == extension))
+            .unwrap_or(0);
+    }
+
+    fn refilter(&mut self) {
+        self.filtered_stats = match self.filter.is_active() {
+            true => self.unfiltered_stats().iter().filter(|s| self.filter.matches(&s.extension)).cloned().collect(),
+            false => Vec::new(),
+        };
+    }
+
     pub fn selected_index(&self) -> usize {
         self.selected_index
     }
@@ -80,14 +233,55 @@ impl ExtensionViewModel {
         self.total_current
     }
 
+    pub fn total_deleted(&self) -> u64 {
+        self.total_deleted
+    }
+
     pub fn total_files(&self) -> u64 {
         self.total_files
     }
+
+    /// Cumulative size classified as binary
+    pub fn total_binary(&self) -> u64 {
+        self.total_binary
+    }
+
+    /// The listed extensions as a table, in the order shown
+    pub fn export(&self) -> ListExport {
+        let columns = vec!["extension", "cumulative_size", "current_size", "deleted_size", "file_count", "binary_size"];
+        let mut export = ListExport::new("extensions", columns);
+        for stat in self.stats() {
+            export.push(vec![
+                stat.extension.as_str().into(),
+                stat.cumulative_size.into(),
+                stat.current_size.into(),
+                stat.deleted_size.into(),
+                stat.file_count.into(),
+                stat.binary_size.into(),
+            ]);
+        }
+        export
+    }
+
+    pub fn selected_extension(&self) -> Option<&str> {
+        self.stats().get(self.selected_index).map(|s| s.extension.as_str())
+    }
+
+    /// Mark key and sizes of the selected extension
+    pub fn selected_mark(&self) -> Option<(MarkKey, MarkSizes)> {
+        self.stats().get(self.selected_index).map(|s| {
+            let sizes = MarkSizes {
+                cumulative: s.cumulative_size,
+                current: s.current_size,
+            };
+            (MarkKey::Extension(s.extension.clone()), sizes)
+        })
+    }
 }
 
 impl Selectable for ExtensionViewModel {
     fn len(&self) -> usize {
-        self.stats.len()
+        self.stats().len()
     }
 
     fn selected(&self) -> usize {
@@ -103,20 +297,25 @@ impl Selectable for ExtensionViewModel {
 mod tests {
     use super::*;
 
-    fn create_test_tree() -> TreeNode {
-        let mut root = TreeNode::new("(root)");
-        root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
-        root.add_path_with_sizes(&["src", "lib.rs"], 800, 400, 1);
-        root.add_path_with_sizes(&["assets", "logo.png"], 5000, 0, 1);
-        root.add_path_with_sizes(&["assets", "icon.png"], 2000, 2000, 1);
-        root.compute_totals();
-        root
+    fn stats(extension: &str, cumulative: u64, current: u64, deleted: u64) -> (String, ExtensionStats) {
+        let stats = ExtensionStats {
+            cumulative_size: cumulative,
+            current_size: current,
+            deleted_size: deleted,
+            file_count: 2,
+            binary_size: if extension == ".png" { cumulative } else { 0 },
+        };
+        (extension.to_string(), stats)
+    }
+
+    fn create_test_stats() -> Vec<(String, ExtensionStats)> {
+        vec![stats(".png", 7000, 2000, 5000), stats(".rs", 1800, 900, 0)]
     }
 
     #[test]
     fn test_extension_stats() {
-        let tree = create_test_tree();
-        let vm = ExtensionViewModel::new(&tree);
+        let vm = ExtensionViewModel::new(create_test_stats());
+        assert_eq!(vm.total_binary(), 7000);
 
         let stats = vm.stats();
         assert!(!stats.is_empty());
@@ -126,4 +325,66 @@ mod tests {
         assert!(ext_names.contains(&".rs"));
         assert!(ext_names.contains(&".png"));
     }
+
+    #[test]
+    fn test_deleted_only_stats() {
+        let mut vm = ExtensionViewModel::new(create_test_stats());
+        assert_eq!(vm.total_deleted(), 5000);
+
+        vm.set_deleted_only(true);
+        let stats = vm.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].extension, ".png");
+        assert_eq!(stats[0].deleted_size, 5000);
+        assert_eq!(stats[0].cumulative_size, 7000);
+
+        vm.set_deleted_only(false);
+        assert_eq!(vm.stats().len(), 2);
+    }
+
+    #[test]
+    fn test_set_stats_keeps_filter() {
+        let mut vm = ExtensionViewModel::new(create_test_stats());
+        vm.set_deleted_only(true);
+        vm.set_stats(vec![stats(".rs", 100, 100, 0), stats(".md", 100, 0, 100), stats(".bin", 100, 0, 100)]);
+
+        assert!(vm.is_deleted_only());
+        assert_eq!(vm.stats().len(), 2);
+        assert_eq!(vm.total_cumulative(), 300);
+        // Equal sizes in extension order
+        vm.set_deleted_only(false);
+        let ext_names: Vec<_> = vm.stats().iter().map(|s| s.extension.as_str()).collect();
+        assert_eq!(ext_names, [".bin", ".md", ".rs"]);
+    }
+
+    #[test]
+    fn test_ranking_keeps_the_selection() {
+        let mut vm = ExtensionViewModel::new(vec![
+            stats(".png", 7000, 200, 5000),
+            stats(".rs", 1800, 900, 0),
+            stats(".md", 300, 300, 0),
+        ]);
+        vm.move_down();
+        assert_eq!(vm.selected_extension(), Some(".rs"));
+
+        vm.cycle_ranking();
+        assert_eq!(vm.ranking(), ExtensionRanking::Current);
+        let ext_names: Vec<_> = vm.stats().iter().map(|s| s.extension.as_str()).collect();
+        assert_eq!(ext_names, [".rs", ".md", ".png"]);
+        assert_eq!(vm.selected_index(), 0);
+        assert_eq!(vm.shown_total(), 1400);
+
+        // Ties keep their cumulative order
+        vm.cycle_ranking();
+        let ext_names: Vec<_> = vm.stats().iter().map(|s| s.extension.as_str()).collect();
+        assert_eq!(ext_names, [".png", ".rs", ".md"]);
+        assert_eq!(vm.shown_size(&vm.stats()[0]), 5000);
+        // Other views still get the extensions by cumulative size
+        assert_eq!(vm.all_stats()[0].extension, ".png");
+        assert_eq!(vm.all_stats()[2].extension, ".md");
+
+        vm.cycle_ranking();
+        assert_eq!(vm.ranking(), ExtensionRanking::Cumulative);
+        assert_eq!(vm.selected_extension(), Some(".rs"));
+    }
 }
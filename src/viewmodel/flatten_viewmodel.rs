@@ -0,0 +1,210 @@
+use crate::model::{Descendant, ListExport, NodeId, Tree};
+
+use super::selection::Selectable;
+use super::selection_set::{MarkKey, MarkSizes};
+
+/// Levels below the root listed unless configured otherwise
+pub const DEFAULT_FLATTEN_DEPTH: usize = 3;
+
+/// Entries kept of a deep tree, the largest ones
+pub const FLATTEN_LIMIT: usize = 1000;
+
+/// A file or directory anywhere down to the listed depth
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatEntry {
+    /// Full path from the repository root
+    pub path: String,
+    pub is_dir: bool,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+}
+
+/// ViewModel for every path down to a depth as one list, so the biggest
+/// directories anywhere in the hierarchy can be compared, not only siblings
+pub struct FlattenViewModel {
+    /// Levels below the root listed, 1 being its entries
+    depth: usize,
+    /// Largest cumulative size first, at most `FLATTEN_LIMIT`
+    entries: Vec<FlatEntry>,
+    /// Paths down to the depth, before the limit
+    total_entries: usize,
+    /// Of the whole repository
+    total_cumulative: u64,
+    /// A directory shallower than the depth whose entries are still to load
+    pending: Option<(NodeId, String)>,
+    selected_index: usize,
+}
+
+impl Default for FlattenViewModel {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_FLATTEN_DEPTH,
+            entries: Vec::new(),
+            total_entries: 0,
+            total_cumulative: 0,
+            pending: None,
+            selected_index: 0,
+        }
+    }
+}
+
+impl FlattenViewModel {
+    /// Levels listed from the next `set_root` on; at least one
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth.max(1);
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// List the paths of `root` down to the depth, keeping the cursor in range
+    pub fn set_root(&mut self, root: &Tree) {
+        self.pending = None;
+        let mut entries = Vec::new();
+        for Descendant { path, depth, node } in root.root().descendants().max_depth(self.depth) {
+            if node.is_unloaded() && depth < self.depth {
+                self.pending.get_or_insert_with(|| (node.id(), path.clone()));
+            }
+            entries.push(FlatEntry {
+                path,
+                is_dir: node.has_children(),
+                cumulative_size: node.cumulative_size,
+                current_size: node.current_size,
+            });
+        }
+        entries.sort_by(|a, b| b.cumulative_size.cmp(&a.cumulative_size).then_with(|| a.path.cmp(&b.path)));
+        self.total_entries = entries.len();
+        entries.truncate(FLATTEN_LIMIT);
+        self.entries = entries;
+        self.total_cumulative = root.root().cumulative_size;
+        self.selected_index = self.selected_index.min(self.entries.len().saturating_sub(1));
+    }
+
+    /// A directory, with its path, to load before the list is complete
+    /// (only in a partial tree)
+    pub fn pending_dir(&self) -> Option<(NodeId, String)> {
+        self.pending.clone()
+    }
+
+    pub fn entries(&self) -> &[FlatEntry] {
+        &self.entries
+    }
+
+    /// Paths down to the depth, including those past the limit
+    pub fn total_entries(&self) -> usize {
+        self.total_entries
+    }
+
+    /// The listed paths as a table
+    pub fn export(&self) -> ListExport {
+        let mut export = ListExport::new("flattened", vec!["path", "is_dir", "cumulative_size", "current_size"]);
+        for entry in &self.entries {
+            export.push(vec![
+                entry.path.as_str().into(),
+                entry.is_dir.into(),
+                entry.cumulative_size.into(),
+                entry.current_size.into(),
+            ]);
+        }
+        export
+    }
+
+    pub fn total_cumulative(&self) -> u64 {
+        self.total_cumulative
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    pub fn selected_entry(&self) -> Option<&FlatEntry> {
+        self.entries.get(self.selected_index)
+    }
+
+    /// Mark key and sizes of the selected entry
+    pub fn selected_mark(&self) -> Option<(MarkKey, MarkSizes)> {
+        self.mark_at(self.selected_index)
+    }
+
+    /// Mark key and sizes of the entry in row `index`
+    pub fn mark_at(&self, index: usize) -> Option<(MarkKey, MarkSizes)> {
+        self.entries.get(index).map(|entry| {
+            let sizes = MarkSizes { cumulative: entry.cumulative_size, current: entry.current_size };
+            (MarkKey::Path(entry.path.clone()), sizes)
+        })
+    }
+}
+
+impl Selectable for FlattenViewModel {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DirEntry, EntryKind};
+
+    fn tree() -> Tree {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["src", "main.rs"], 1000, 1000, 1);
+        root.add_path_with_sizes(&["assets", "img", "big", "logo.png"], 5000, 0, 1);
+        root.add_path_with_sizes(&["assets", "fonts", "a.ttf"], 3000, 3000, 1);
+        root.add_path_with_sizes(&["README.md"], 100, 100, 1);
+        root.compute_totals();
+        root
+    }
+
+    #[test]
+    fn test_lists_paths_down_to_depth() {
+        let mut vm = FlattenViewModel::default();
+        vm.set_depth(2);
+        vm.set_root(&tree());
+        let paths: Vec<_> = vm.entries().iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(paths, ["assets", "assets/img", "assets/fonts", "src", "src/main.rs", "README.md"]);
+        assert!(vm.entries()[1].is_dir);
+        assert!(!vm.entries()[4].is_dir);
+        assert_eq!(vm.total_cumulative(), 9100);
+
+        vm.move_down();
+        assert_eq!(vm.selected_mark().unwrap().0, MarkKey::Path("assets/img".into()));
+
+        vm.set_depth(0);
+        vm.set_root(&tree());
+        assert_eq!(vm.depth(), 1);
+        assert_eq!(vm.total_entries(), 3);
+    }
+
+    #[test]
+    fn test_pending_dir_shallower_than_depth() {
+        let root = Tree::from_dir_entries(vec![DirEntry {
+            name: "assets".into(),
+            is_dir: true,
+            cumulative_size: 5000,
+            current_size: 0,
+            blob_count: 1,
+            deleted_size: 5000,
+            kind: EntryKind::File,
+        }]);
+        let mut vm = FlattenViewModel::default();
+        vm.set_depth(1);
+        vm.set_root(&root);
+        // Its entries are below the depth
+        assert_eq!(vm.pending_dir(), None);
+        assert!(vm.entries()[0].is_dir);
+
+        vm.set_depth(2);
+        vm.set_root(&root);
+        assert_eq!(vm.pending_dir().map(|(_, path)| path), Some("assets".to_string()));
+    }
+}
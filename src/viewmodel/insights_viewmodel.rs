@@ -0,0 +1,90 @@
+use crate::model::{DeltaEfficiency, GitignoreSuggestion, Insight, VendoredShare};
+
+use super::selection::Selectable;
+
+/// ViewModel for the list of recommended cleanups
+#[derive(Default)]
+pub struct InsightsViewModel {
+    insights: Vec<Insight>,
+    /// Read from the index, as `suggest_gitignore` needs every directory
+    gitignore: Vec<GitignoreSuggestion>,
+    /// Read from the index, which records every version's entry size
+    delta: Vec<DeltaEfficiency>,
+    /// History of generated and vendored files, per rule tagging them
+    vendored: Vec<VendoredShare>,
+    /// Cumulative size of all history, what the shares are of
+    cumulative_size: u64,
+    selected_index: usize,
+}
+
+impl InsightsViewModel {
+    /// Show `insights` (from `model::recommend`), keeping the cursor in range
+    pub fn set_insights(&mut self, insights: Vec<Insight>) {
+        self.insights = insights;
+        self.selected_index = self.selected_index.min(self.insights.len().saturating_sub(1));
+    }
+
+    /// `.gitignore` entries to recommend the next time insights are worked out
+    pub fn set_gitignore_suggestions(&mut self, suggestions: Vec<GitignoreSuggestion>) {
+        self.gitignore = suggestions;
+    }
+
+    pub fn gitignore_suggestions(&self) -> &[GitignoreSuggestion] {
+        &self.gitignore
+    }
+
+    /// How well the largest paths delta, for the next time insights are
+    /// worked out
+    pub fn set_delta_efficiency(&mut self, delta: Vec<DeltaEfficiency>) {
+        self.delta = delta;
+    }
+
+    pub fn delta_efficiency(&self) -> &[DeltaEfficiency] {
+        &self.delta
+    }
+
+    /// What generated and vendored files hold, from the index
+    pub fn set_vendored_shares(&mut self, shares: Vec<VendoredShare>) {
+        self.vendored = shares;
+    }
+
+    pub fn vendored_shares(&self) -> &[VendoredShare] {
+        &self.vendored
+    }
+
+    /// Size of all history, for the vendored shares
+    pub fn set_cumulative_size(&mut self, size: u64) {
+        self.cumulative_size = size;
+    }
+
+    pub fn cumulative_size(&self) -> u64 {
+        self.cumulative_size
+    }
+
+    pub fn insights(&self) -> &[Insight] {
+        &self.insights
+    }
+
+    /// Sum of the estimated savings
+    pub fn total_savings(&self) -> u64 {
+        self.insights.iter().filter_map(|i| i.savings).sum()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+}
+
+impl Selectable for InsightsViewModel {
+    fn len(&self) -> usize {
+        self.insights.len()
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
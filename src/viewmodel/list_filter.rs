@@ -0,0 +1,74 @@
+/// Text narrowing a list down to the entries containing it, typed after '/'
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListFilter {
+    text: String,
+    /// Keys go to the filter text rather than the list
+    editing: bool,
+}
+
+impl ListFilter {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    /// Whether the list is narrowed at all
+    pub fn is_active(&self) -> bool {
+        !self.text.is_empty()
+    }
+
+    /// Whether `name` contains the filter text, ignoring case
+    pub fn matches(&self, name: &str) -> bool {
+        self.text.is_empty() || name.to_lowercase().contains(&self.text.to_lowercase())
+    }
+
+    pub fn start_editing(&mut self) {
+        self.editing = true;
+    }
+
+    /// Stop editing, keeping the text
+    pub fn finish_editing(&mut self) {
+        self.editing = false;
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.text.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.text.pop();
+    }
+
+    /// Filter by `text`, as if it had been typed and confirmed
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.editing = false;
+    }
+
+    /// Stop editing and show the whole list again
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.editing = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_ignoring_case() {
+        let mut filter = ListFilter::default();
+        assert!(filter.matches("anything"));
+        for c in "PNG".chars() {
+            filter.push(c);
+        }
+        assert!(filter.matches("assets/logo.png"));
+        assert!(!filter.matches("src/main.rs"));
+        filter.clear();
+        assert!(!filter.is_active());
+    }
+}
@@ -2,11 +2,38 @@ mod tree_viewmodel;
 mod extension_viewmodel;
 mod search_viewmodel;
 mod blobs_viewmodel;
+mod blob_lookup_viewmodel;
+mod bookmarks_viewmodel;
+mod insights_viewmodel;
+mod duplicates_viewmodel;
+mod commits_viewmodel;
+mod top_dirs_viewmodel;
+mod flatten_viewmodel;
+mod diagnostics_viewmodel;
+mod owners_viewmodel;
 mod app_viewmodel;
+mod list_filter;
+mod path_scroll;
 mod selection;
+mod selection_set;
+mod undo_history;
 
-pub use tree_viewmodel::TreeViewModel;
-pub use extension_viewmodel::ExtensionViewModel;
-pub use search_viewmodel::{SearchViewModel, SearchResult};
-pub use blobs_viewmodel::BlobsViewModel;
+pub use tree_viewmodel::{BiggestFile, TreeViewModel, VendoredFilter};
+pub use extension_viewmodel::{ExtensionRanking, ExtensionViewModel};
+pub use search_viewmodel::{
+    SearchViewModel, SearchCase, SearchGroup, SearchResult, SearchRow, DEFAULT_PAGE_SIZE, INDEX_MATCH_LIMIT,
+};
+pub use blobs_viewmodel::{BlobsViewModel, BlobColumns};
+pub use blob_lookup_viewmodel::{BlobLookupViewModel, LookupState};
+pub use bookmarks_viewmodel::{BookmarksViewModel, BookmarkView};
+pub use insights_viewmodel::InsightsViewModel;
+pub use duplicates_viewmodel::DuplicatesViewModel;
+pub use commits_viewmodel::CommitsViewModel;
+pub use top_dirs_viewmodel::{TopDir, TopDirsViewModel};
+pub use flatten_viewmodel::{FlatEntry, FlattenViewModel, DEFAULT_FLATTEN_DEPTH, FLATTEN_LIMIT};
+pub use diagnostics_viewmodel::DiagnosticsViewModel;
+pub use owners_viewmodel::OwnersViewModel;
 pub use app_viewmodel::{AppViewModel, ViewMode, Action};
+pub use list_filter::ListFilter;
+pub use path_scroll::PathScroll;
+pub use selection_set::{SelectionSet, MarkKey, MarkSizes};
@@ -0,0 +1,169 @@
+use crate::model::{OwnedFile, OwnerTotals};
+
+use super::selection::Selectable;
+
+/// ViewModel for size per CODEOWNERS owner, with drill-down into the files
+/// each one owns
+#[derive(Default)]
+pub struct OwnersViewModel {
+    /// From `CodeOwners::tally`, largest cumulative size first
+    owners: Vec<OwnerTotals>,
+    /// Where the CODEOWNERS file was found; `None` when there is none
+    source: Option<String>,
+    /// Lines of the file that aren't valid rules
+    invalid_lines: Vec<usize>,
+    /// Cumulative size of all files, those with several owners counted once
+    total_cumulative: u64,
+    selected_index: usize,
+    /// Owner whose files are listed, by index into `owners`
+    open_owner: Option<usize>,
+    file_index: usize,
+}
+
+impl OwnersViewModel {
+    /// Show the owners of a new scan; an open owner stays open if it is
+    /// still there
+    pub fn set_owners(&mut self, source: Option<String>, invalid_lines: Vec<usize>, owners: Vec<OwnerTotals>) {
+        let open = self.open_owner().map(|o| o.owner.clone());
+        let mut counted = rustc_hash::FxHashSet::default();
+        self.total_cumulative = owners
+            .iter()
+            .flat_map(|o| &o.files)
+            .filter(|f| counted.insert(f.path.as_str()))
+            .map(|f| f.cumulative_size)
+            .sum();
+        self.owners = owners;
+        self.source = source;
+        self.invalid_lines = invalid_lines;
+        self.selected_index = self.selected_index.min(self.owners.len().saturating_sub(1));
+        self.open_owner = open.and_then(|name| self.owners.iter().position(|o| o.owner == name));
+        if let Some(owner) = self.open_owner() {
+            self.file_index = self.file_index.min(owner.files.len().saturating_sub(1));
+        }
+    }
+
+    pub fn owners(&self) -> &[OwnerTotals] {
+        &self.owners
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn invalid_lines(&self) -> &[usize] {
+        &self.invalid_lines
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    /// The owner whose files are listed, if one is open
+    pub fn open_owner(&self) -> Option<&OwnerTotals> {
+        self.open_owner.and_then(|i| self.owners.get(i))
+    }
+
+    pub fn file_index(&self) -> usize {
+        self.file_index
+    }
+
+    pub fn selected_file(&self) -> Option<&OwnedFile> {
+        self.open_owner()?.files.get(self.file_index)
+    }
+
+    /// Cumulative size of every file, whoever owns it
+    pub fn total_cumulative(&self) -> u64 {
+        self.total_cumulative
+    }
+
+    /// Open the selected owner; with one open, the path of the selected
+    /// file to show in the tree
+    pub fn enter(&mut self) -> Option<String> {
+        if self.open_owner.is_some() {
+            return self.selected_file().map(|f| f.path.clone());
+        }
+        if self.selected_index < self.owners.len() {
+            self.open_owner = Some(self.selected_index);
+            self.file_index = 0;
+        }
+        None
+    }
+
+    /// Close the open owner; false when the owner list was shown already
+    pub fn back(&mut self) -> bool {
+        self.open_owner.take().is_some()
+    }
+}
+
+impl Selectable for OwnersViewModel {
+    fn len(&self) -> usize {
+        match self.open_owner() {
+            Some(owner) => owner.files.len(),
+            None => self.owners.len(),
+        }
+    }
+
+    fn selected(&self) -> usize {
+        if self.open_owner.is_some() {
+            self.file_index
+        } else {
+            self.selected_index
+        }
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        if self.open_owner.is_some() {
+            self.file_index = index;
+        } else {
+            self.selected_index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CodeOwners;
+
+    fn create_test_vm() -> OwnersViewModel {
+        let owners = CodeOwners::parse("* @org/platform\n/assets/ @org/design\n");
+        let totals = owners.tally(vec![
+            ("assets/logo.psd".to_string(), 5000, 0),
+            ("assets/icon.png".to_string(), 800, 800),
+            ("src/main.rs".to_string(), 300, 300),
+        ]);
+        let mut vm = OwnersViewModel::default();
+        vm.set_owners(Some("CODEOWNERS".to_string()), Vec::new(), totals);
+        vm
+    }
+
+    #[test]
+    fn test_drill_down() {
+        let mut vm = create_test_vm();
+        assert_eq!(vm.owners()[0].owner, "@org/design");
+        assert_eq!(vm.total_cumulative(), 6100);
+        vm.move_down();
+        assert_eq!(vm.enter(), None);
+        assert_eq!(vm.open_owner().unwrap().owner, "@org/platform");
+        assert_eq!(vm.len(), 1);
+        assert_eq!(vm.enter().as_deref(), Some("src/main.rs"));
+
+        assert!(vm.back());
+        assert!(!vm.back());
+        assert_eq!(vm.selected_index(), 1);
+    }
+
+    #[test]
+    fn test_set_owners_keeps_open_owner() {
+        let mut vm = create_test_vm();
+        vm.enter();
+        vm.move_down();
+        assert_eq!(vm.selected_file().unwrap().path, "assets/icon.png");
+
+        let owners = CodeOwners::parse("/assets/ @org/design\n");
+        vm.set_owners(None, vec![3], owners.tally(vec![("assets/logo.psd".to_string(), 5000, 0)]));
+        assert_eq!(vm.open_owner().unwrap().owner, "@org/design");
+        assert_eq!(vm.selected_file().unwrap().path, "assets/logo.psd");
+        assert_eq!(vm.invalid_lines(), [3]);
+    }
+}
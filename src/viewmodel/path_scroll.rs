@@ -0,0 +1,49 @@
+/// Columns a key press scrolls long paths by
+const STEP: usize = 8;
+
+/// How far the long paths of a list are scrolled back from their ends, so
+/// the start of a path cut to fit a narrow terminal can be read; all rows
+/// scroll together
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathScroll {
+    offset: usize,
+}
+
+impl PathScroll {
+    /// Columns back from the end of each path; 0 shows the tails
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Show more of the starts, no further back than the `longest` path
+    pub fn left(&mut self, longest: usize) {
+        self.offset = (self.offset + STEP).min(longest);
+    }
+
+    /// Show more of the ends
+    pub fn right(&mut self) {
+        self.offset = self.offset.saturating_sub(STEP);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scroll_stays_within_the_longest_path() {
+        let mut scroll = PathScroll::default();
+        scroll.left(30);
+        assert_eq!(scroll.offset(), STEP);
+        for _ in 0..10 {
+            scroll.left(30);
+        }
+        assert_eq!(scroll.offset(), 30);
+        scroll.right();
+        assert_eq!(scroll.offset(), 30 - STEP);
+        for _ in 0..10 {
+            scroll.right();
+        }
+        assert_eq!(scroll.offset(), 0);
+    }
+}
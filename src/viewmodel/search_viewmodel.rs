@@ -1,20 +1,74 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Deserialize;
 use std::ops::Range;
 use std::sync::Arc;
 
-use crate::model::TreeNode;
+use crate::model::{ListExport, SearchQuery, Tree};
+use crate::util::display_width;
 
+use super::path_scroll::PathScroll;
 use super::selection::Selectable;
+use super::selection_set::{MarkKey, MarkSizes};
 
 /// Precomputed entry for fast searching
 struct SearchEntry {
-    path: String,
-    path_lower: String,
+    path: Box<str>,
     cumulative_size: u64,
     current_size: u64,
 }
 
+/// How letter case counts when matching a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchCase {
+    /// "readme" and "README" both match README.md
+    #[default]
+    Ignore,
+    /// Ignored unless the query has an uppercase letter, as in vim
+    Smart,
+    /// Always counts
+    Sensitive,
+}
+
+impl SearchCase {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ignore" => Some(SearchCase::Ignore),
+            "smart" => Some(SearchCase::Smart),
+            "sensitive" => Some(SearchCase::Sensitive),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchCase::Ignore => "ignore case",
+            SearchCase::Smart => "smart case",
+            SearchCase::Sensitive => "case sensitive",
+        }
+    }
+
+    /// The mode after this one, for toggling through them
+    pub fn next(self) -> Self {
+        match self {
+            SearchCase::Ignore => SearchCase::Smart,
+            SearchCase::Smart => SearchCase::Sensitive,
+            SearchCase::Sensitive => SearchCase::Ignore,
+        }
+    }
+
+    /// Whether case counts when matching `query`
+    pub fn is_sensitive(self, query: &str) -> bool {
+        match self {
+            SearchCase::Ignore => false,
+            SearchCase::Smart => query.chars().any(char::is_uppercase),
+            SearchCase::Sensitive => true,
+        }
+    }
+}
+
 /// Find all non-overlapping matches of `query` in `text`, returning byte ranges.
-/// Both strings must already be lowercased.
+/// Unless matching case, both strings must already be lowercased.
 fn find_matches(text: &str, query: &str) -> Vec<Range<usize>> {
     text.match_indices(query)
         .map(|(start, matched)| start..start + matched.len())
@@ -29,41 +83,124 @@ pub struct SearchResult<'a> {
     pub matches: &'a [Range<usize>],
 }
 
+/// Matches under one directory, in grouped mode
+pub struct SearchGroup<'a> {
+    /// Directory path, empty for the repository root
+    pub dir: &'a str,
+    pub count: usize,
+    /// Subtotals of the matches, not of the whole directory
+    pub cumulative_size: u64,
+    pub current_size: u64,
+    pub expanded: bool,
+}
+
+/// A row of the results list: a match, or in grouped mode a directory of them
+pub enum SearchRow<'a> {
+    Group(SearchGroup<'a>),
+    Match(SearchResult<'a>),
+}
+
 /// A matched result with its index and match ranges
 struct MatchedResult {
     index: usize,
     matches: Vec<Range<usize>>,
 }
 
+/// Results sharing a parent directory
+struct MatchGroup {
+    dir: Box<str>,
+    /// Indexes into `results`, largest first
+    members: Vec<usize>,
+    cumulative_size: u64,
+    current_size: u64,
+}
+
+/// What a row of the grouped list shows
+#[derive(Clone, Copy)]
+enum GroupedRow {
+    Group(usize),
+    Match(usize),
+}
+
+/// Results shown per page unless configured otherwise
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Most matches fetched from the index for one query
+pub const INDEX_MATCH_LIMIT: usize = 1000;
+
 /// ViewModel for search functionality
+///
+/// All matches are ranked, but only the first `displayed` are exposed;
+/// another page is revealed whenever the selection reaches the last one.
+///
+/// A partial tree (see `Tree::is_partial`) can't be searched in memory: the
+/// matches of each query are fetched from the index instead
+/// (`pending_query`, `set_matches`).
 pub struct SearchViewModel {
     query: String,
     results: Vec<MatchedResult>,
+    page_size: usize,
+    displayed: usize,
     selected_index: usize,
+    /// Only match files that are gone from HEAD
+    deleted_only: bool,
+    case: SearchCase,
+    /// Paths to search, or with `from_index` the last matches fetched
     entries: Vec<SearchEntry>,
+    /// Lowercased `entries` paths, made by the first search ignoring case
+    /// so case-sensitive searches never pay for them
+    lowered: Vec<Box<str>>,
+    /// Matches come from the index rather than `entries`
+    from_index: bool,
+    /// Query, filter and case sensitivity `entries` were fetched for
+    fetched: Option<(String, bool, bool)>,
+    /// Show matches under their directories instead of in one list; every
+    /// match is grouped, so paging doesn't apply
+    grouped: bool,
+    groups: Vec<MatchGroup>,
+    /// Directories whose matches are listed below them
+    expanded: FxHashSet<Box<str>>,
+    /// Rows of the grouped list, with expanded groups' matches
+    rows: Vec<GroupedRow>,
     total_cumulative: u64,
+    /// How far paths cut to fit are scrolled towards their starts
+    scroll: PathScroll,
 }
 
 impl SearchViewModel {
-    pub fn new(root: Arc<TreeNode>) -> Self {
-        let total_cumulative = root.cumulative_size;
+    pub fn new(root: Arc<Tree>) -> Self {
+        let total_cumulative = root.root().cumulative_size;
+        let from_index = root.is_partial();
         let mut entries = Vec::new();
 
-        root.visit_leaves(|path, node| {
-            entries.push(SearchEntry {
-                path_lower: path.to_lowercase(),
-                path: path.to_string(),
-                cumulative_size: node.cumulative_size,
-                current_size: node.current_size,
+        if !from_index {
+            root.visit_leaves(|path, node| {
+                entries.push(SearchEntry {
+                    path: path.into(),
+                    cumulative_size: node.cumulative_size,
+                    current_size: node.current_size,
+                });
             });
-        });
+        }
 
         Self {
             query: String::new(),
             results: Vec::new(),
+            page_size: DEFAULT_PAGE_SIZE,
+            displayed: 0,
             selected_index: 0,
+            deleted_only: false,
+            case: SearchCase::default(),
             entries,
+            lowered: Vec::new(),
+            from_index,
+            fetched: None,
+            grouped: false,
+            groups: Vec::new(),
+            expanded: FxHashSet::default(),
+            rows: Vec::new(),
             total_cumulative,
+            scroll: PathScroll::default(),
         }
     }
 
@@ -71,16 +208,185 @@ impl SearchViewModel {
         &self.query
     }
 
+    /// Search the results of a new scan instead; the query is cleared
+    pub fn set_root(&mut self, root: Arc<Tree>) {
+        let mut fresh = Self::new(root);
+        fresh.page_size = self.page_size;
+        fresh.deleted_only = self.deleted_only;
+        fresh.case = self.case;
+        fresh.grouped = self.grouped;
+        *self = fresh;
+    }
+
+    /// Set how many results are revealed at a time (at least one)
+    pub fn set_page_size(&mut self, page_size: usize) {
+        self.page_size = page_size.max(1);
+        self.displayed = self.results.len().min(self.page_size);
+        self.selected_index = 0;
+    }
+
+    /// Results revealed so far, best first
     pub fn results(&self) -> impl Iterator<Item = SearchResult<'_>> + '_ {
-        self.results.iter().map(|r| {
-            let e = &self.entries[r.index];
-            SearchResult {
-                path: &e.path,
-                cumulative_size: e.cumulative_size,
-                current_size: e.current_size,
-                matches: &r.matches,
+        (0..self.displayed).map(|r| self.result(r))
+    }
+
+    /// Rows of the results list: the revealed results, or in grouped mode
+    /// each directory's subtotal followed by its matches when expanded
+    /// Columns the matched paths are scrolled back from their ends
+    pub fn path_scroll(&self) -> usize {
+        self.scroll.offset()
+    }
+
+    /// Scroll the matched paths towards their starts (`back`) or their ends
+    pub fn scroll_paths(&mut self, back: bool) {
+        if back {
+            let longest = self.results().map(|result| display_width(result.path)).max().unwrap_or(0);
+            self.scroll.left(longest);
+        } else {
+            self.scroll.right();
+        }
+    }
+
+    pub fn rows(&self) -> Vec<SearchRow<'_>> {
+        if !self.grouped {
+            return self.results().map(SearchRow::Match).collect();
+        }
+        self.rows
+            .iter()
+            .map(|row| match *row {
+                GroupedRow::Group(g) => {
+                    let group = &self.groups[g];
+                    SearchRow::Group(SearchGroup {
+                        dir: &group.dir,
+                        count: group.members.len(),
+                        cumulative_size: group.cumulative_size,
+                        current_size: group.current_size,
+                        expanded: self.expanded.contains(&group.dir),
+                    })
+                }
+                GroupedRow::Match(r) => SearchRow::Match(self.result(r)),
+            })
+            .collect()
+    }
+
+    fn result(&self, r: usize) -> SearchResult<'_> {
+        let result = &self.results[r];
+        let e = &self.entries[result.index];
+        SearchResult {
+            path: &e.path,
+            cumulative_size: e.cumulative_size,
+            current_size: e.current_size,
+            matches: &result.matches,
+        }
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.grouped
+    }
+
+    /// Switch between one list of matches and matches grouped by directory
+    pub fn toggle_grouped(&mut self) {
+        self.grouped = !self.grouped;
+        self.selected_index = 0;
+        self.rebuild_rows();
+    }
+
+    /// Number of directories the matches are in (grouped mode only)
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Expand or collapse the group under the cursor; false when the cursor
+    /// isn't on a group
+    pub fn toggle_selected_group(&mut self) -> bool {
+        let Some(GroupedRow::Group(g)) = self.selected_row() else {
+            return false;
+        };
+        let dir = &self.groups[g].dir;
+        if !self.expanded.remove(dir) {
+            self.expanded.insert(dir.clone());
+        }
+        // The group's row stays where it is, so the cursor stays on it
+        self.rebuild_rows();
+        true
+    }
+
+    fn selected_row(&self) -> Option<GroupedRow> {
+        self.row_at(self.selected_index)
+    }
+
+    fn row_at(&self, index: usize) -> Option<GroupedRow> {
+        if self.grouped {
+            self.rows.get(index).copied()
+        } else {
+            (index < self.results.len()).then_some(GroupedRow::Match(index))
+        }
+    }
+
+    /// Group `results` by parent directory, largest subtotal first
+    fn rebuild_groups(&mut self) {
+        let mut groups: Vec<MatchGroup> = Vec::new();
+        let mut by_dir: FxHashMap<&str, usize> = FxHashMap::default();
+        for (r, result) in self.results.iter().enumerate() {
+            let e = &self.entries[result.index];
+            let dir = e.path.rsplit_once('/').map_or("", |(dir, _)| dir);
+            let g = *by_dir.entry(dir).or_insert_with(|| {
+                groups.push(MatchGroup { dir: dir.into(), members: Vec::new(), cumulative_size: 0, current_size: 0 });
+                groups.len() - 1
+            });
+            let group = &mut groups[g];
+            group.members.push(r);
+            group.cumulative_size += e.cumulative_size;
+            group.current_size += e.current_size;
+        }
+        groups.sort_by(|a, b| b.cumulative_size.cmp(&a.cumulative_size).then_with(|| a.dir.cmp(&b.dir)));
+        self.groups = groups;
+        self.rebuild_rows();
+    }
+
+    fn rebuild_rows(&mut self) {
+        self.rows.clear();
+        if !self.grouped {
+            return;
+        }
+        for (g, group) in self.groups.iter().enumerate() {
+            self.rows.push(GroupedRow::Group(g));
+            if self.expanded.contains(&group.dir) {
+                self.rows.extend(group.members.iter().map(|&r| GroupedRow::Match(r)));
             }
-        })
+        }
+        self.selected_index = self.selected_index.min(self.rows.len().saturating_sub(1));
+    }
+
+    pub fn is_deleted_only(&self) -> bool {
+        self.deleted_only
+    }
+
+    /// Restrict matches to deleted files, re-running the current query
+    pub fn set_deleted_only(&mut self, deleted_only: bool) {
+        self.deleted_only = deleted_only;
+        self.update_results();
+    }
+
+    pub fn case(&self) -> SearchCase {
+        self.case
+    }
+
+    /// Match case as `case` says, re-running the current query
+    pub fn set_case(&mut self, case: SearchCase) {
+        self.case = case;
+        self.update_results();
+    }
+
+    /// Switch to the next way of matching case
+    pub fn cycle_case(&mut self) {
+        self.set_case(self.case.next());
+    }
+
+    /// Whether the current query matches case; predicates such as
+    /// ">10MB" don't make it
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case.is_sensitive(&SearchQuery::parse(&self.query).text)
     }
 
     pub fn selected_index(&self) -> usize {
@@ -91,6 +397,30 @@ impl SearchViewModel {
         self.total_cumulative
     }
 
+    /// Number of paths matching the query
+    pub fn total_matches(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Number of matches revealed so far
+    pub fn displayed_count(&self) -> usize {
+        self.displayed
+    }
+
+    /// The matches revealed so far as a table, ungrouped
+    pub fn export(&self) -> ListExport {
+        let mut export = ListExport::new("search", vec!["path", "cumulative_size", "current_size"]);
+        for result in self.results() {
+            export.push(vec![result.path.into(), result.cumulative_size.into(), result.current_size.into()]);
+        }
+        export
+    }
+
+    /// Reveal the next page of results, if any
+    pub fn load_more(&mut self) {
+        self.displayed = (self.displayed + self.page_size).min(self.results.len());
+    }
+
     pub fn add_char(&mut self, c: char) {
         self.query.push(c);
         self.update_results();
@@ -104,24 +434,47 @@ impl SearchViewModel {
     pub fn clear(&mut self) {
         self.query.clear();
         self.results.clear();
+        self.displayed = 0;
         self.selected_index = 0;
+        self.groups.clear();
+        self.expanded.clear();
+        self.rows.clear();
     }
 
     fn update_results(&mut self) {
-        if self.query.is_empty() {
+        let parsed = SearchQuery::parse(&self.query);
+        // Matches of another query or filter are not shown
+        if parsed.is_empty() || (self.from_index && !self.is_fetched()) {
             self.results.clear();
+            self.displayed = 0;
             self.selected_index = 0;
+            self.rebuild_groups();
             return;
         }
 
-        let query_lower = self.query.to_lowercase();
+        let sensitive = self.case.is_sensitive(&parsed.text);
+        let query = if sensitive { parsed.text.clone() } else { parsed.text.to_lowercase() };
+        if !sensitive && !query.is_empty() && self.lowered.len() != self.entries.len() {
+            self.lowered = self.entries.iter().map(|e| e.path.to_lowercase().into()).collect();
+        }
 
         let mut matched: Vec<_> = self
             .entries
             .iter()
             .enumerate()
             .filter_map(|(i, e)| {
-                let matches = find_matches(&e.path_lower, &query_lower);
+                if self.deleted_only && e.current_size > 0 {
+                    return None;
+                }
+                if !parsed.accepts(&e.path, e.cumulative_size) {
+                    return None;
+                }
+                // Predicates alone match without highlighting anything
+                if query.is_empty() {
+                    return Some((i, e.cumulative_size, Vec::new()));
+                }
+                let text = if sensitive { &e.path } else { &self.lowered[i] };
+                let matches = find_matches(text, &query);
                 if matches.is_empty() {
                     None
                 } else {
@@ -130,27 +483,81 @@ impl SearchViewModel {
             })
             .collect();
 
-        matched.sort_by(|a, b| b.1.cmp(&a.1));
-        matched.truncate(100);
+        matched.sort_by_key(|m| std::cmp::Reverse(m.1));
 
         self.results = matched
             .into_iter()
             .map(|(index, _, matches)| MatchedResult { index, matches })
             .collect();
+        self.displayed = self.results.len().min(self.page_size);
         self.selected_index = 0;
+        self.rebuild_groups();
+    }
+
+    /// Query whose matches should be fetched from the index next (with
+    /// `is_deleted_only` and `is_case_sensitive`, at most
+    /// `INDEX_MATCH_LIMIT` of them), if any
+    pub fn pending_query(&self) -> Option<&str> {
+        let pending = self.from_index && !SearchQuery::parse(&self.query).is_empty() && !self.is_fetched();
+        pending.then_some(self.query.as_str())
+    }
+
+    /// Whether `entries` hold the index's matches for the current query
+    fn is_fetched(&self) -> bool {
+        self.fetched.as_ref().is_some_and(|(query, deleted_only, sensitive)| {
+            *query == self.query && *deleted_only == self.deleted_only && *sensitive == self.is_case_sensitive()
+        })
+    }
+
+    /// Deliver the (path, cumulative size, current size) of paths matching
+    /// `query`, as requested by `pending_query`
+    pub fn set_matches(&mut self, query: &str, deleted_only: bool, case_sensitive: bool, matches: Vec<(String, u64, u64)>) {
+        self.entries = matches
+            .into_iter()
+            .map(|(path, cumulative_size, current_size)| SearchEntry {
+                path: path.into(),
+                cumulative_size,
+                current_size,
+            })
+            .collect();
+        self.lowered.clear();
+        self.fetched = Some((query.to_string(), deleted_only, case_sensitive));
+        self.update_results();
     }
 
-    /// Get selected result's path
+    /// Get selected result's path (none on a group)
     pub fn selected_path(&self) -> Option<&str> {
-        self.results
-            .get(self.selected_index)
-            .map(|r| self.entries[r.index].path.as_str())
+        match self.selected_row()? {
+            GroupedRow::Match(r) => Some(&*self.entries[self.results[r].index].path),
+            GroupedRow::Group(_) => None,
+        }
+    }
+
+    /// Mark key and sizes of the selected result (none on a group)
+    pub fn selected_mark(&self) -> Option<(MarkKey, MarkSizes)> {
+        self.mark_at(self.selected_index)
+    }
+
+    /// Mark key and sizes of the result on row `index` (none on a group)
+    pub fn mark_at(&self, index: usize) -> Option<(MarkKey, MarkSizes)> {
+        match self.row_at(index)? {
+            GroupedRow::Match(r) => Some(&self.results[r]),
+            GroupedRow::Group(_) => None,
+        }
+        .map(|r| {
+            let e = &self.entries[r.index];
+            let sizes = MarkSizes {
+                cumulative: e.cumulative_size,
+                current: e.current_size,
+            };
+            (MarkKey::Path(e.path.to_string()), sizes)
+        })
     }
 }
 
 impl Selectable for SearchViewModel {
     fn len(&self) -> usize {
-        self.results.len()
+        if self.grouped { self.rows.len() } else { self.displayed }
     }
 
     fn selected(&self) -> usize {
@@ -159,6 +566,11 @@ impl Selectable for SearchViewModel {
 
     fn set_selected(&mut self, index: usize) {
         self.selected_index = index;
+        // Reaching the last revealed row fetches the next page, so the
+        // following move_down continues instead of wrapping
+        if !self.grouped && index + 1 >= self.displayed {
+            self.load_more();
+        }
     }
 }
 
@@ -166,8 +578,8 @@ impl Selectable for SearchViewModel {
 mod tests {
     use super::*;
 
-    fn create_test_tree() -> Arc<TreeNode> {
-        let mut root = TreeNode::new("(root)");
+    fn create_test_tree() -> Arc<Tree> {
+        let mut root = Tree::new();
         root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
         root.add_path_with_sizes(&["src", "lib.rs"], 800, 400, 1);
         root.add_path_with_sizes(&["README.md"], 100, 100, 1);
@@ -199,6 +611,155 @@ mod tests {
         assert_eq!(vm.results().count(), 1);
     }
 
+    #[test]
+    fn test_search_case_modes() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["README.md"], 100, 100, 1);
+        root.add_path_with_sizes(&["docs", "readme.txt"], 50, 50, 1);
+        root.compute_totals();
+        let mut vm = SearchViewModel::new(Arc::new(root));
+
+        for c in "readme".chars() {
+            vm.add_char(c);
+        }
+        assert_eq!(vm.total_matches(), 2);
+        vm.set_case(SearchCase::Sensitive);
+        assert_eq!(vm.selected_path(), Some("docs/readme.txt"));
+        assert_eq!(vm.total_matches(), 1);
+
+        // Smart case ignores case until the query has an uppercase letter
+        vm.cycle_case();
+        assert_eq!(vm.case(), SearchCase::Ignore);
+        vm.cycle_case();
+        assert_eq!(vm.total_matches(), 2);
+        vm.clear();
+        for c in "READ".chars() {
+            vm.add_char(c);
+        }
+        assert!(vm.is_case_sensitive());
+        assert_eq!(vm.selected_path(), Some("README.md"));
+        assert_eq!(vm.total_matches(), 1);
+    }
+
+    #[test]
+    fn test_grouped_by_directory() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["assets", "a.png"], 300, 300, 1);
+        root.add_path_with_sizes(&["assets", "b.png"], 200, 0, 1);
+        root.add_path_with_sizes(&["icons", "c.png"], 400, 400, 1);
+        root.add_path_with_sizes(&["d.png"], 10, 10, 1);
+        root.compute_totals();
+        let mut vm = SearchViewModel::new(Arc::new(root));
+        for c in ".png".chars() {
+            vm.add_char(c);
+        }
+        vm.toggle_grouped();
+        assert_eq!(vm.group_count(), 3);
+
+        let groups: Vec<(&str, usize, u64)> = vm
+            .rows()
+            .into_iter()
+            .map(|row| match row {
+                SearchRow::Group(g) => (g.dir, g.count, g.cumulative_size),
+                SearchRow::Match(_) => panic!("groups start collapsed"),
+            })
+            .collect();
+        assert_eq!(groups, [("assets", 2, 500), ("icons", 1, 400), ("", 1, 10)]);
+        assert_eq!(vm.selected_path(), None);
+
+        // Expanding lists the group's matches below it, largest first
+        assert!(vm.toggle_selected_group());
+        assert_eq!(vm.len(), 5);
+        vm.move_down();
+        assert_eq!(vm.selected_path(), Some("assets/a.png"));
+        assert!(!vm.toggle_selected_group());
+        vm.move_up();
+        vm.toggle_selected_group();
+        assert_eq!(vm.len(), 3);
+
+        // Typing keeps the grouping; clearing the query collapses everything
+        vm.backspace();
+        assert_eq!(vm.group_count(), 3);
+        vm.toggle_grouped();
+        assert_eq!(vm.len(), 4);
+    }
+
+    #[test]
+    fn test_paging_reveals_more_on_scroll() {
+        let mut root = Tree::new();
+        for i in 0..5u64 {
+            root.add_path_with_sizes(&[&format!("file{}.bin", i)], 100 * (i + 1), 0, 1);
+        }
+        root.compute_totals();
+        let mut vm = SearchViewModel::new(Arc::new(root));
+        vm.set_page_size(2);
+
+        for c in "file".chars() {
+            vm.add_char(c);
+        }
+        assert_eq!(vm.total_matches(), 5);
+        assert_eq!(vm.displayed_count(), 2);
+
+        // Largest first, and the full ranking is preserved across pages
+        vm.move_down();
+        assert_eq!(vm.displayed_count(), 4);
+        vm.move_down();
+        vm.move_down();
+        assert_eq!(vm.displayed_count(), 5);
+        assert_eq!(vm.selected_path(), Some("file1.bin"));
+
+        vm.move_down();
+        assert_eq!(vm.selected_path(), Some("file0.bin"));
+
+        // A new query starts over at one page
+        vm.backspace();
+        assert_eq!(vm.displayed_count(), 2);
+    }
+
+    #[test]
+    fn test_deleted_only_matches() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
+        root.add_path_with_sizes(&["src", "old.rs"], 800, 0, 1);
+        root.compute_totals();
+        let mut vm = SearchViewModel::new(Arc::new(root));
+
+        for c in ".rs".chars() {
+            vm.add_char(c);
+        }
+        assert_eq!(vm.total_matches(), 2);
+
+        vm.set_deleted_only(true);
+        assert_eq!(vm.total_matches(), 1);
+        assert_eq!(vm.selected_path(), Some("src/old.rs"));
+
+        vm.set_deleted_only(false);
+        assert_eq!(vm.total_matches(), 2);
+    }
+
+    #[test]
+    fn test_size_predicates() {
+        let tree = create_test_tree();
+        let mut vm = SearchViewModel::new(tree);
+        vm.set_case(SearchCase::Smart);
+
+        // The unit's capitals don't make the search match case
+        for c in ">0.5KB".chars() {
+            vm.add_char(c);
+        }
+        assert!(!vm.is_case_sensitive());
+        assert_eq!(vm.total_matches(), 2);
+        assert!(vm.results().all(|result| result.matches.is_empty()));
+
+        for c in " ext:rs MAIN".chars() {
+            vm.add_char(c);
+        }
+        assert_eq!(vm.total_matches(), 0);
+        vm.set_case(SearchCase::Ignore);
+        assert_eq!(vm.selected_path(), Some("src/main.rs"));
+        assert_eq!(vm.results().next().unwrap().matches.to_vec(), vec![Range { start: 4, end: 8 }]);
+    }
+
     #[test]
     fn test_empty_search() {
         let tree = create_test_tree();
@@ -206,4 +767,42 @@ mod tests {
 
         assert_eq!(vm.results().count(), 0);
     }
+
+    #[test]
+    fn test_partial_tree_searches_the_index() {
+        use crate::model::{DirEntry, EntryKind};
+
+        let src = DirEntry {
+            name: "src".into(),
+            is_dir: true,
+            cumulative_size: 1800,
+            current_size: 900,
+            blob_count: 2,
+            deleted_size: 0,
+            kind: EntryKind::File,
+        };
+        let mut vm = SearchViewModel::new(Arc::new(Tree::from_dir_entries(vec![src])));
+        assert_eq!(vm.pending_query(), None);
+
+        vm.add_char('m');
+        assert_eq!(vm.pending_query(), Some("m"));
+        assert_eq!(vm.total_matches(), 0);
+
+        // Matches for an older query are kept for it but not shown
+        vm.add_char('a');
+        vm.set_matches("m", false, false, vec![("src/main.rs".into(), 1000, 500)]);
+        assert_eq!(vm.total_matches(), 0);
+        vm.set_matches("ma", false, false, vec![("src/main.rs".into(), 1000, 500)]);
+        assert_eq!(vm.pending_query(), None);
+        assert_eq!(vm.selected_path(), Some("src/main.rs"));
+        assert_eq!(vm.results().next().unwrap().matches.to_vec(), vec![Range { start: 4, end: 6 }]);
+
+        vm.set_deleted_only(true);
+        assert_eq!(vm.pending_query(), Some("ma"));
+
+        // Matches fetched ignoring case don't do for a case-sensitive search
+        vm.set_matches("ma", true, false, vec![]);
+        vm.set_case(SearchCase::Sensitive);
+        assert_eq!(vm.pending_query(), Some("ma"));
+    }
 }
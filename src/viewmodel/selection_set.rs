@@ -0,0 +1,252 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Identity of a marked entry, shared across views
+///
+/// Paths are repository-relative without a leading slash, so a file marked
+/// in search results shows up as marked in the tree view too.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MarkKey {
+    Path(String),
+    Blob(Vec<u8>),
+    Extension(String),
+}
+
+/// A `MarkKey` borrowed, ordered the same way, so a lookup needn't build one
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MarkRef<'a> {
+    Path(&'a str),
+    Blob(&'a [u8]),
+    Extension(&'a str),
+}
+
+/// What `entries` can be searched by: a `MarkKey` or a `MarkRef`
+trait AsMarkRef {
+    fn as_mark_ref(&self) -> MarkRef<'_>;
+}
+
+impl AsMarkRef for MarkKey {
+    fn as_mark_ref(&self) -> MarkRef<'_> {
+        match self {
+            MarkKey::Path(path) => MarkRef::Path(path),
+            MarkKey::Blob(oid) => MarkRef::Blob(oid),
+            MarkKey::Extension(extension) => MarkRef::Extension(extension),
+        }
+    }
+}
+
+impl AsMarkRef for MarkRef<'_> {
+    fn as_mark_ref(&self) -> MarkRef<'_> {
+        *self
+    }
+}
+
+impl<'a> Borrow<dyn AsMarkRef + 'a> for MarkKey {
+    fn borrow(&self) -> &(dyn AsMarkRef + 'a) {
+        self
+    }
+}
+
+impl PartialEq for dyn AsMarkRef + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_mark_ref() == other.as_mark_ref()
+    }
+}
+
+impl Eq for dyn AsMarkRef + '_ {}
+
+impl PartialOrd for dyn AsMarkRef + '_ {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for dyn AsMarkRef + '_ {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_mark_ref().cmp(&other.as_mark_ref())
+    }
+}
+
+/// Sizes contributed by a marked entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarkSizes {
+    pub cumulative: u64,
+    pub current: u64,
+}
+
+/// Entries marked across all views
+///
+/// Totals are a plain sum: marking a directory and a file inside it counts
+/// the file twice, just as selecting both in a file manager would.
+///
+/// While in visual mode it also holds the row a range started at, so the
+/// list being selected in only has to report where its cursor is.
+#[derive(Debug, Default)]
+pub struct SelectionSet {
+    entries: BTreeMap<MarkKey, MarkSizes>,
+    visual_anchor: Option<usize>,
+}
+
+impl SelectionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark or unmark an entry, returning whether it is now marked
+    pub fn toggle(&mut self, key: MarkKey, sizes: MarkSizes) -> bool {
+        if self.entries.remove(&key).is_some() {
+            false
+        } else {
+            self.entries.insert(key, sizes);
+            true
+        }
+    }
+
+    /// Mark every entry, or unmark them all if all already are, returning
+    /// whether they are now marked
+    pub fn toggle_all(&mut self, entries: Vec<(MarkKey, MarkSizes)>) -> bool {
+        if !entries.is_empty() && entries.iter().all(|(key, _)| self.entries.contains_key(key)) {
+            for (key, _) in entries {
+                self.entries.remove(&key);
+            }
+            false
+        } else {
+            self.entries.extend(entries);
+            true
+        }
+    }
+
+    /// Start selecting a range at row `anchor`
+    pub fn start_visual(&mut self, anchor: usize) {
+        self.visual_anchor = Some(anchor);
+    }
+
+    pub fn end_visual(&mut self) {
+        self.visual_anchor = None;
+    }
+
+    pub fn is_visual(&self) -> bool {
+        self.visual_anchor.is_some()
+    }
+
+    /// Rows between the anchor and `cursor`, both included, while selecting
+    pub fn visual_range(&self, cursor: usize) -> Option<RangeInclusive<usize>> {
+        self.visual_anchor.map(|anchor| anchor.min(cursor)..=anchor.max(cursor))
+    }
+
+    pub fn contains(&self, key: &MarkKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Whether a repository path is marked, without building a key
+    pub fn contains_path(&self, path: &str) -> bool {
+        self.entries.contains_key(&MarkRef::Path(path) as &dyn AsMarkRef)
+    }
+
+    /// The marked entries, to bring back later with `restore`
+    pub fn snapshot(&self) -> BTreeMap<MarkKey, MarkSizes> {
+        self.entries.clone()
+    }
+
+    pub fn restore(&mut self, entries: BTreeMap<MarkKey, MarkSizes>) {
+        self.entries = entries;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Marked entries in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&MarkKey, &MarkSizes)> {
+        self.entries.iter()
+    }
+
+    /// Summed sizes of all marked entries
+    pub fn totals(&self) -> MarkSizes {
+        self.entries.values().fold(MarkSizes::default(), |acc, s| MarkSizes {
+            cumulative: acc.cumulative + s.cumulative,
+            current: acc.current + s.current,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(cumulative: u64, current: u64) -> MarkSizes {
+        MarkSizes { cumulative, current }
+    }
+
+    #[test]
+    fn test_toggle_and_totals() {
+        let mut set = SelectionSet::new();
+        assert!(set.toggle(MarkKey::Path("a.bin".into()), sizes(100, 10)));
+        assert!(set.toggle(MarkKey::Blob(vec![1; 20]), sizes(50, 0)));
+        assert!(set.toggle(MarkKey::Extension(".png".into()), sizes(7, 7)));
+
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.totals(), sizes(157, 17));
+        assert!(set.contains_path("a.bin"));
+
+        // Toggling again unmarks
+        assert!(!set.toggle(MarkKey::Path("a.bin".into()), sizes(100, 10)));
+        assert!(!set.contains_path("a.bin"));
+        assert_eq!(set.totals(), sizes(57, 7));
+
+        set.clear();
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_visual_range_and_toggle_all() {
+        let mut set = SelectionSet::new();
+        assert_eq!(set.visual_range(3), None);
+        set.start_visual(5);
+        assert!(set.is_visual());
+        // The range runs either way from the anchor
+        assert_eq!(set.visual_range(2), Some(2..=5));
+        assert_eq!(set.visual_range(7), Some(5..=7));
+        set.end_visual();
+        assert!(!set.is_visual());
+
+        let range = |names: &[&str]| names.iter().map(|n| (MarkKey::Path(n.to_string()), sizes(10, 1))).collect();
+        assert!(set.toggle(MarkKey::Path("b".into()), sizes(10, 1)));
+        // Partly marked: everything gets marked, "b" only once
+        assert!(set.toggle_all(range(&["a", "b", "c"])));
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.totals(), sizes(30, 3));
+        // All marked: the whole range is unmarked
+        assert!(!set.toggle_all(range(&["a", "b"])));
+        assert_eq!(set.len(), 1);
+        assert!(set.contains_path("c"));
+    }
+
+    #[test]
+    fn test_contains_path_among_other_keys() {
+        let mut set = SelectionSet::new();
+        for path in ["src", "src/main.rs", "assets/logo.png", "b"] {
+            set.toggle(MarkKey::Path(path.into()), sizes(1, 1));
+        }
+        set.toggle(MarkKey::Extension(".rs".into()), sizes(1, 1));
+        set.toggle(MarkKey::Blob(b"docs".to_vec()), sizes(1, 1));
+
+        for path in ["src", "src/main.rs", "assets/logo.png", "b"] {
+            assert!(set.contains_path(path), "{}", path);
+        }
+        // Only paths count, whatever the other keys hold
+        for path in [".rs", "docs", "a", "src/", ""] {
+            assert!(!set.contains_path(path), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::model::{ListExport, Tree};
+
+use super::selection::Selectable;
+use super::selection_set::{MarkKey, MarkSizes};
+
+/// A directory at the root of the repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopDir {
+    pub name: String,
+    pub cumulative_size: u64,
+    pub current_size: u64,
+    /// Cumulative size of its files that are gone from HEAD
+    pub deleted_size: u64,
+}
+
+/// ViewModel for the root-level directories, wherever the tree is
+#[derive(Default)]
+pub struct TopDirsViewModel {
+    /// Largest cumulative size first
+    dirs: Vec<TopDir>,
+    /// Of the whole repository, files at the root included
+    total_cumulative: u64,
+    total_current: u64,
+    total_deleted: u64,
+    selected_index: usize,
+}
+
+impl TopDirsViewModel {
+    /// List the directories at the root of `root`, keeping the cursor in range
+    pub fn set_root(&mut self, root: &Tree) {
+        let top = root.root();
+        let mut dirs: Vec<TopDir> = top
+            .children()
+            .filter(|child| child.has_children())
+            .map(|child| TopDir {
+                name: child.name().to_string(),
+                cumulative_size: child.cumulative_size,
+                current_size: child.current_size,
+                deleted_size: child.deleted_size,
+            })
+            .collect();
+        dirs.sort_by(|a, b| b.cumulative_size.cmp(&a.cumulative_size).then_with(|| a.name.cmp(&b.name)));
+        self.dirs = dirs;
+        self.total_cumulative = top.cumulative_size;
+        self.total_current = top.current_size;
+        self.total_deleted = top.deleted_size;
+        self.selected_index = self.selected_index.min(self.dirs.len().saturating_sub(1));
+    }
+
+    pub fn dirs(&self) -> &[TopDir] {
+        &self.dirs
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    /// Path of the selected directory, to open in the tree
+    pub fn selected_path(&self) -> Option<&str> {
+        self.dirs.get(self.selected_index).map(|dir| dir.name.as_str())
+    }
+
+    /// The listed directories as a table
+    pub fn export(&self) -> ListExport {
+        let mut export = ListExport::new("top-dirs", vec!["path", "cumulative_size", "current_size", "deleted_size"]);
+        for dir in &self.dirs {
+            export.push(vec![
+                dir.name.as_str().into(),
+                dir.cumulative_size.into(),
+                dir.current_size.into(),
+                dir.deleted_size.into(),
+            ]);
+        }
+        export
+    }
+
+    pub fn total_cumulative(&self) -> u64 {
+        self.total_cumulative
+    }
+
+    pub fn total_current(&self) -> u64 {
+        self.total_current
+    }
+
+    pub fn total_deleted(&self) -> u64 {
+        self.total_deleted
+    }
+
+    /// Mark key and sizes of the selected directory
+    pub fn selected_mark(&self) -> Option<(MarkKey, MarkSizes)> {
+        self.dirs.get(self.selected_index).map(|dir| {
+            let sizes = MarkSizes { cumulative: dir.cumulative_size, current: dir.current_size };
+            (MarkKey::Path(dir.name.clone()), sizes)
+        })
+    }
+}
+
+impl Selectable for TopDirsViewModel {
+    fn len(&self) -> usize {
+        self.dirs.len()
+    }
+
+    fn selected(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected(&mut self, index: usize) {
+        self.selected_index = index;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_only_root_directories() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
+        root.add_path_with_sizes(&["assets", "img", "logo.png"], 5000, 0, 1);
+        root.add_path_with_sizes(&["README.md"], 100, 100, 1);
+        root.compute_totals();
+
+        let mut vm = TopDirsViewModel::default();
+        vm.set_root(&root);
+        let names: Vec<_> = vm.dirs().iter().map(|dir| dir.name.as_str()).collect();
+        assert_eq!(names, ["assets", "src"]);
+        assert_eq!(vm.dirs()[0].deleted_size, 5000);
+        // Shares are of the whole repository, root files included
+        assert_eq!(vm.total_cumulative(), 6100);
+        assert_eq!(vm.total_current(), 600);
+
+        vm.move_down();
+        assert_eq!(vm.selected_path(), Some("src"));
+        assert_eq!(vm.selected_mark().unwrap().0, MarkKey::Path("src".into()));
+    }
+}
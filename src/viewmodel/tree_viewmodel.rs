@@ -1,45 +1,176 @@
 use std::cell::RefCell;
 use std::sync::Arc;
 
-use crate::model::TreeNode;
+use rustc_hash::FxHashMap;
+
+use crate::model::{old_cutoff, EntryDetails, EntryKind, ExportValue, GitDirUsage, ListExport, NodeId, NodeRef, OldSizes, PackHealth, ScanDiagnostic, SizeHistory, StorageHint, Tree, VendoredSizes, DEFAULT_OLD_AFTER_YEARS};
 
 use super::selection::Selectable;
+use super::selection_set::{MarkKey, MarkSizes};
 
 /// View representation of a tree node for rendering
 #[derive(Debug, Clone)]
 pub struct TreeNodeView {
+    pub id: NodeId,
     pub name: String,
     pub display_size: u64,
+    pub cumulative_size: u64,
     pub current_size: u64,
     pub has_children: bool,
+    pub kind: EntryKind,
+    /// Change in cumulative size since the tree before the last rescan
+    pub size_delta: i64,
+    /// In deleted-only mode, once read: when its deleted files were last
+    /// deleted; none if no scanned commit removed them
+    pub deleted_at: Option<i64>,
+}
+
+/// Largest file anywhere below the current directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct BiggestFile {
+    /// Path relative to the current directory
+    pub path: String,
+    pub size: u64,
+    /// Share of the directory's total, 0-100
+    pub percent: f64,
+}
+
+/// How the tree treats files tagged as generated or vendored
+/// (`model::vendored_rule`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VendoredFilter {
+    #[default]
+    All,
+    /// List sizes without them, and drop entries holding nothing else
+    Hide,
+    /// List only entries holding some, sized by what they hold
+    Only,
+}
+
+impl VendoredFilter {
+    fn next(self) -> Self {
+        match self {
+            VendoredFilter::All => VendoredFilter::Hide,
+            VendoredFilter::Hide => VendoredFilter::Only,
+            VendoredFilter::Only => VendoredFilter::All,
+        }
+    }
+}
+
+/// Navigation waiting for a directory to load, replayed once it has
+#[derive(Debug, Clone)]
+enum Resume {
+    Open(String),
+    Navigate(String),
+    Descend,
+    /// Back to `dir` with `selected` selected, after a rescan
+    Restore { dir: String, selected: Option<String> },
+}
+
+/// Largest file below `dir` as the index reported it
+struct IndexedBiggest {
+    dir: String,
+    deleted_only: bool,
+    /// Repository-relative path and size
+    found: Option<(String, u64)>,
 }
 
 struct ChildrenCache {
     children: Vec<TreeNodeView>,
+    biggest: Option<BiggestFile>,
     dirty: bool,
 }
 
 /// ViewModel for tree navigation
 pub struct TreeViewModel {
-    root: Arc<TreeNode>,
-    path_stack: Vec<String>,
+    tree: Arc<Tree>,
+    /// Directories from the root down to the current one
+    path_stack: Vec<NodeId>,
     selected_index: usize,
     show_deleted_only: bool,
     total_cumulative: u64,
+    storage_hint: Option<StorageHint>,
+    /// What the repository's `.git` directory takes on disk
+    git_dir_usage: Option<GitDirUsage>,
+    /// Worktree and branch the repository was opened at
+    checkout: Option<String>,
+    /// History limits of the scan behind these results, if it was partial
+    scan_limits: Option<String>,
+    /// Objects the scan could not read
+    skipped_objects: usize,
+    /// Packs the scan could not read, and the sizes they cost
+    pack_health: PackHealth,
+    /// Quarters spanned by the repository's history, once known
+    history_range: Option<(i32, i32)>,
+    /// Growth of the most recently loaded path
+    history: Option<(String, SizeHistory)>,
+    /// Breakdown of the most recently loaded directory, and whether only
+    /// deleted files counted
+    details: Option<(String, bool, EntryDetails)>,
+    /// The details panel was switched away from its default: hidden beside
+    /// the list on a wide terminal, shown instead of it on a narrow one
+    details_toggled: bool,
+    /// Path -> cumulative size change from the last rescan
+    size_deltas: FxHashMap<String, i64>,
+    /// What to carry on with once the current directory has loaded
+    resume: Option<Resume>,
+    /// A partial tree can't find the largest file by walking
+    indexed_biggest: Option<IndexedBiggest>,
+    /// Directory and the cumulative size below it classified as binary
+    binary_size: Option<(String, u64)>,
+    vendored_filter: VendoredFilter,
+    /// Directory and the tagged files below each of its entries, by name
+    vendored: Option<(String, FxHashMap<String, VendoredSizes>)>,
+    /// Directory and when the deleted files below each of its entries were
+    /// last deleted, by name
+    deletion_dates: Option<(String, FxHashMap<String, i64>)>,
+    /// Deleted-only mode lists the most recently deleted first
+    by_deletion_date: bool,
+    /// Content first committed this many years ago, before `old_cutoff`,
+    /// is old
+    old_after_years: u32,
+    old_cutoff: i64,
+    /// List only entries holding old content, sized by it
+    old_only: bool,
+    /// Directory and the old content below each of its entries, by name
+    old: Option<(String, FxHashMap<String, OldSizes>)>,
     cache: RefCell<ChildrenCache>,
 }
 
 impl TreeViewModel {
-    pub fn new(root: Arc<TreeNode>) -> Self {
-        let total_cumulative = root.cumulative_size;
+    pub fn new(tree: Arc<Tree>) -> Self {
+        let total_cumulative = tree.root().cumulative_size;
         Self {
-            root,
+            tree,
             path_stack: Vec::new(),
             selected_index: 0,
             show_deleted_only: false,
             total_cumulative,
+            storage_hint: None,
+            git_dir_usage: None,
+            checkout: None,
+            scan_limits: None,
+            skipped_objects: 0,
+            pack_health: PackHealth::default(),
+            history_range: None,
+            history: None,
+            details: None,
+            details_toggled: false,
+            size_deltas: FxHashMap::default(),
+            resume: None,
+            indexed_biggest: None,
+            binary_size: None,
+            vendored_filter: VendoredFilter::default(),
+            vendored: None,
+            deletion_dates: None,
+            by_deletion_date: false,
+            old_after_years: DEFAULT_OLD_AFTER_YEARS,
+            old_cutoff: i64::MIN,
+            old_only: false,
+            old: None,
             cache: RefCell::new(ChildrenCache {
                 children: Vec::new(),
+                biggest: None,
                 dirty: true,
             }),
         }
@@ -47,7 +178,226 @@ impl TreeViewModel {
 
     /// Get the total deleted size (for deleted-only mode header)
     pub fn total_deleted(&self) -> u64 {
-        self.root.deleted_cumulative_size()
+        self.tree.root().deleted_cumulative_size()
+    }
+
+    /// Pack sizes to show alongside the computed totals
+    pub fn storage_hint(&self) -> Option<&StorageHint> {
+        self.storage_hint.as_ref()
+    }
+
+    pub fn set_storage_hint(&mut self, hint: StorageHint) {
+        self.storage_hint = Some(hint);
+    }
+
+    /// On-disk size of the repository's `.git` directory, when known
+    pub fn git_dir_usage(&self) -> Option<&GitDirUsage> {
+        self.git_dir_usage.as_ref()
+    }
+
+    pub fn set_git_dir_usage(&mut self, usage: GitDirUsage) {
+        self.git_dir_usage = Some(usage);
+    }
+
+    /// What is checked out where the repository was opened, e.g. "on main"
+    pub fn checkout(&self) -> Option<&str> {
+        self.checkout.as_deref()
+    }
+
+    pub fn set_checkout(&mut self, checkout: String) {
+        self.checkout = Some(checkout);
+    }
+
+    /// Limits the results were scanned with, when not all history was walked
+    pub fn scan_limits(&self) -> Option<&str> {
+        self.scan_limits.as_deref()
+    }
+
+    pub fn set_scan_limits(&mut self, limits: String) {
+        self.scan_limits = Some(limits);
+    }
+
+    /// Number of objects left out of the results because they couldn't be read
+    pub fn skipped_objects(&self) -> usize {
+        self.skipped_objects
+    }
+
+    /// Corrupt packs and the blobs counted as 0 bytes because of them
+    pub fn pack_health(&self) -> &PackHealth {
+        &self.pack_health
+    }
+
+    /// Show what the last scan could not read
+    pub fn set_diagnostics(&mut self, diagnostics: &[ScanDiagnostic]) {
+        self.skipped_objects = diagnostics.iter().filter(|d| d.is_skipped_object()).count();
+        self.pack_health = PackHealth::from_diagnostics(diagnostics);
+    }
+
+    /// Show the results of a new scan, staying in the same directory (or the
+    /// deepest part of it that still exists) on the same entry if it is there
+    pub fn set_root(&mut self, tree: Arc<Tree>) {
+        let selected = self.visible_children().get(self.selected_index).map(|c| c.name.clone());
+        let dir = self.dir_path();
+        self.total_cumulative = tree.root().cumulative_size;
+        self.tree = tree;
+        self.history = None;
+        self.details = None;
+        self.indexed_biggest = None;
+        self.binary_size = None;
+        self.vendored = None;
+        self.deletion_dates = None;
+        self.old = None;
+        self.restore(dir, selected);
+    }
+
+    /// Let go of the tree until `extend_tree` hands it back, so its owner
+    /// can change it without a copy
+    pub fn release_tree(&mut self) {
+        self.tree = Arc::default();
+    }
+
+    /// Show a tree that has more directories loaded than the current one;
+    /// node ids stay valid, and navigation waiting for a directory goes on
+    pub fn extend_tree(&mut self, tree: Arc<Tree>) {
+        self.tree = tree;
+        self.cache.borrow_mut().dirty = true;
+        match self.resume.take() {
+            Some(Resume::Open(path)) => self.open_path(&path),
+            Some(Resume::Navigate(path)) => self.navigate_to_path(&path),
+            Some(Resume::Descend) => self.descend_to_largest_leaf(),
+            Some(Resume::Restore { dir, selected }) => self.restore(dir, selected),
+            None => {}
+        }
+    }
+
+    /// The current directory, with its path, when its entries are still to
+    /// be loaded (see `Tree::set_dir_entries`)
+    pub fn pending_dir(&self) -> Option<(NodeId, String)> {
+        let current = self.current_node();
+        current.is_unloaded().then(|| (current.id(), self.dir_path()))
+    }
+
+    /// Open `dir` and select `selected` in it, as far down as it exists
+    fn restore(&mut self, dir: String, selected: Option<String>) {
+        let mut stack = Vec::new();
+        let mut node = self.tree.root();
+        for part in dir.split('/').filter(|p| !p.is_empty()) {
+            if node.is_unloaded() {
+                break;
+            }
+            match node.child(part) {
+                Some(child) if child.has_children() => {
+                    stack.push(child.id());
+                    node = child;
+                }
+                _ => break,
+            }
+        }
+        let waiting = node.is_unloaded();
+        self.path_stack = stack;
+        self.invalidate();
+        if waiting {
+            self.resume = Some(Resume::Restore { dir, selected });
+            return;
+        }
+        let index = selected.and_then(|name| self.visible_children().iter().position(|c| c.name == name));
+        self.selected_index = index.unwrap_or(0);
+    }
+
+    /// Cumulative size changes to badge, keyed by repository-relative path
+    pub fn set_size_deltas(&mut self, deltas: FxHashMap<String, i64>) {
+        self.size_deltas = deltas;
+        self.cache.borrow_mut().dirty = true;
+    }
+
+    /// Badge more size changes, e.g. those of a directory just loaded
+    pub fn add_size_deltas(&mut self, deltas: FxHashMap<String, i64>) {
+        self.size_deltas.extend(deltas);
+        self.cache.borrow_mut().dirty = true;
+    }
+
+    /// Enable size history for selected entries over `first..=last` quarters
+    pub fn set_history_range(&mut self, first: i32, last: i32) {
+        self.history_range = Some((first, last));
+    }
+
+    /// Path of the selected entry
+    pub fn selected_path(&self) -> Option<String> {
+        let children = self.visible_children();
+        children.get(self.selected_index).map(|c| self.child_path(&c.name))
+    }
+
+    /// Path of the selected entry when it is a file (not a directory or
+    /// submodule), which has a blob to show
+    pub fn selected_file(&self) -> Option<String> {
+        let children = self.visible_children();
+        children
+            .get(self.selected_index)
+            .filter(|c| !c.has_children && c.kind != EntryKind::Gitlink)
+            .map(|c| self.child_path(&c.name))
+    }
+
+    /// Selected directory whose details should be loaded next, with
+    /// whether only deleted files count
+    pub fn pending_details_dir(&self) -> Option<(String, bool)> {
+        let dir = self.visible_children().get(self.selected_index).filter(|c| c.has_children).map(|c| self.child_path(&c.name))?;
+        match &self.details {
+            Some((loaded, deleted_only, _)) if *loaded == dir && *deleted_only == self.show_deleted_only => None,
+            _ => Some((dir, self.show_deleted_only)),
+        }
+    }
+
+    /// Deliver the breakdown of `dir`, as requested by `pending_details_dir`
+    pub fn set_details(&mut self, dir: String, deleted_only: bool, details: EntryDetails) {
+        self.details = Some((dir, deleted_only, details));
+    }
+
+    /// Breakdown of the selected directory, once loaded
+    pub fn selected_details(&self) -> Option<&EntryDetails> {
+        let (dir, deleted_only, details) = self.details.as_ref()?;
+        (*deleted_only == self.show_deleted_only && self.selected_path().as_deref() == Some(dir.as_str())).then_some(details)
+    }
+
+    /// Hide the details panel on a wide terminal, or show it on a narrow one
+    pub fn toggle_details(&mut self) {
+        self.details_toggled = !self.details_toggled;
+    }
+
+    pub fn is_details_toggled(&self) -> bool {
+        self.details_toggled
+    }
+
+    /// Path of the selected file when HEAD no longer has it
+    pub fn selected_deleted_file(&self) -> Option<String> {
+        let children = self.visible_children();
+        children
+            .get(self.selected_index)
+            .filter(|c| !c.has_children && c.kind != EntryKind::Gitlink && c.current_size == 0)
+            .map(|c| self.child_path(&c.name))
+    }
+
+    /// Selected path whose history should be loaded next, if any
+    pub fn pending_history_path(&self) -> Option<String> {
+        self.history_range?;
+        let path = self.selected_path()?;
+        match &self.history {
+            Some((loaded, _)) if *loaded == path => None,
+            _ => Some(path),
+        }
+    }
+
+    /// Deliver per-quarter additions for `path`, as requested by
+    /// `pending_history_path`
+    pub fn set_history(&mut self, path: String, added: &[(i32, u64)]) {
+        if let Some((first, last)) = self.history_range {
+            self.history = Some((path, SizeHistory::from_additions(added, first, last)));
+        }
+    }
+
+    /// Size history of the selected entry, once loaded
+    pub fn selected_history(&self) -> Option<&SizeHistory> {
+        let (path, history) = self.history.as_ref()?;
+        (self.selected_path().as_deref() == Some(path.as_str())).then_some(history)
     }
 
     /// Check if we're at the root level
@@ -62,28 +412,51 @@ impl TreeViewModel {
 
     /// Get the current path as a string
     pub fn current_path(&self) -> String {
+        format!("/{}", self.dir_path())
+    }
+
+    /// Repository-relative path of a child of the current directory
+    pub fn child_path(&self, name: &str) -> String {
         if self.path_stack.is_empty() {
-            "/".to_string()
+            name.to_string()
         } else {
-            format!("/{}", self.path_stack.join("/"))
+            format!("{}/{}", self.dir_path(), name)
         }
     }
 
+    /// Repository-relative path of the current directory, "" at the root
+    fn dir_path(&self) -> String {
+        let names: Vec<&str> = self.path_stack.iter().map(|&id| self.tree.node(id).name()).collect();
+        names.join("/")
+    }
+
+    /// Mark key and sizes of the selected child
+    pub fn selected_mark(&self) -> Option<(MarkKey, MarkSizes)> {
+        self.mark_at(self.selected_index)
+    }
+
+    /// Mark key and sizes of the child on row `index`
+    pub fn mark_at(&self, index: usize) -> Option<(MarkKey, MarkSizes)> {
+        let children = self.visible_children();
+        children.get(index).map(|c| {
+            let sizes = MarkSizes {
+                cumulative: c.cumulative_size,
+                current: c.current_size,
+            };
+            (MarkKey::Path(self.child_path(&c.name)), sizes)
+        })
+    }
+
     /// Get the current node in the path
-    pub fn current_node(&self) -> &TreeNode {
-        let mut node: &TreeNode = &self.root;
-        for name in &self.path_stack {
-            if let Some(child) = node.children.get(name) {
-                node = child;
-            }
-        }
-        node
+    pub fn current_node(&self) -> NodeRef<'_> {
+        self.tree.node(self.path_stack.last().copied().unwrap_or(NodeId::ROOT))
     }
 
     /// Invalidate the children cache and reset selection
     fn invalidate(&mut self) {
         self.cache.borrow_mut().dirty = true;
         self.selected_index = 0;
+        self.resume = None;
     }
 
     /// Ensure the children cache is populated
@@ -95,35 +468,372 @@ impl TreeViewModel {
 
         let current = self.current_node();
         let mut children: Vec<_> = current
-            .children
-            .values()
+            .children()
             .filter(|node| !self.show_deleted_only || node.contains_deleted_files())
-            .map(|node| {
-                let display_size = if self.show_deleted_only {
+            .filter_map(|node| {
+                let mut display_size = if self.show_deleted_only {
                     node.deleted_cumulative_size()
                 } else {
                     node.cumulative_size
                 };
-                TreeNodeView {
-                    name: node.name.clone(),
+                if self.old_only {
+                    let old = self.old_sizes(node.name());
+                    display_size = if self.show_deleted_only { old.deleted } else { old.cumulative };
+                    if display_size == 0 {
+                        return None;
+                    }
+                } else if self.vendored_filter != VendoredFilter::All {
+                    let tagged = self.vendored_sizes(node.name());
+                    let tagged_size = if self.show_deleted_only { tagged.deleted } else { tagged.cumulative };
+                    match self.vendored_filter {
+                        VendoredFilter::Only if tagged.files == 0 => return None,
+                        VendoredFilter::Only => display_size = tagged_size,
+                        _ if tagged.files > 0 && tagged_size >= display_size => return None,
+                        _ => display_size -= tagged_size,
+                    }
+                }
+                Some(TreeNodeView {
+                    id: node.id(),
+                    name: node.name().to_string(),
                     display_size,
+                    cumulative_size: node.cumulative_size,
                     current_size: node.current_size,
-                    has_children: !node.children.is_empty(),
-                }
+                    has_children: node.has_children(),
+                    kind: node.kind,
+                    size_delta: self.size_deltas.get(&self.child_path(node.name())).copied().unwrap_or(0),
+                    deleted_at: self.deletion_date(node.name()),
+                })
             })
             .collect();
 
-        children.sort_by(|a, b| b.display_size.cmp(&a.display_size));
+        if self.is_sorted_by_deletion_date() {
+            // Never removed last, as `None` sorts first
+            children.sort_by_key(|c| (std::cmp::Reverse(c.deleted_at), std::cmp::Reverse(c.display_size)));
+        } else {
+            children.sort_by_key(|c| std::cmp::Reverse(c.display_size));
+        }
         cache.children = children;
+        cache.biggest = if self.tree.is_partial() {
+            self.indexed_biggest_file(current)
+        } else {
+            self.find_biggest_file(current)
+        };
         cache.dirty = false;
     }
 
+    /// What `BiggestFile::percent` is a share of
+    fn biggest_total(&self, dir: NodeRef<'_>) -> u64 {
+        if self.show_deleted_only {
+            dir.deleted_cumulative_size()
+        } else {
+            dir.cumulative_size
+        }
+    }
+
+    /// Largest file under `dir` by the size currently listed (deleted size in
+    /// deleted-only mode; a file counts as deleted when HEAD no longer has it)
+    fn find_biggest_file(&self, dir: NodeRef<'_>) -> Option<BiggestFile> {
+        let total = self.biggest_total(dir);
+        let mut biggest: Option<(String, u64)> = None;
+        dir.visit_leaves(|path, node| {
+            let size = if self.show_deleted_only && node.current_size > 0 {
+                0
+            } else {
+                node.cumulative_size
+            };
+            // Ties go to the first path alphabetically, so the pick is stable
+            let better = match &biggest {
+                None => size > 0,
+                Some((best_path, best)) => size > *best || (size == *best && path < best_path.as_str()),
+            };
+            if better {
+                biggest = Some((path.to_string(), size));
+            }
+        });
+        biggest.map(|(path, size)| BiggestFile {
+            path,
+            size,
+            percent: if total > 0 { size as f64 * 100.0 / total as f64 } else { 0.0 },
+        })
+    }
+
+    /// The largest file under `dir` as set by `set_biggest_file`, if it was
+    /// for the current directory and filter
+    fn indexed_biggest_file(&self, dir: NodeRef<'_>) -> Option<BiggestFile> {
+        let indexed = self.indexed_biggest.as_ref()?;
+        if indexed.dir != self.dir_path() || indexed.deleted_only != self.show_deleted_only {
+            return None;
+        }
+        let (file, size) = indexed.found.as_ref()?;
+        let total = self.biggest_total(dir);
+        let relative = file.strip_prefix(indexed.dir.as_str()).map_or(file.as_str(), |f| f.trim_start_matches('/'));
+        Some(BiggestFile {
+            path: relative.to_string(),
+            size: *size,
+            percent: if total > 0 { *size as f64 * 100.0 / total as f64 } else { 0.0 },
+        })
+    }
+
+    /// Directory whose largest file should be looked up in the index next,
+    /// with whether only deleted files count; only partial trees need this
+    pub fn pending_biggest_dir(&self) -> Option<(String, bool)> {
+        if !self.tree.is_partial() || self.current_node().is_unloaded() {
+            return None;
+        }
+        let dir = self.dir_path();
+        match &self.indexed_biggest {
+            Some(loaded) if loaded.dir == dir && loaded.deleted_only == self.show_deleted_only => None,
+            _ => Some((dir, self.show_deleted_only)),
+        }
+    }
+
+    /// Deliver the largest file (repository-relative path and size) below
+    /// `dir`, as requested by `pending_biggest_dir`
+    pub fn set_biggest_file(&mut self, dir: String, deleted_only: bool, found: Option<(String, u64)>) {
+        self.indexed_biggest = Some(IndexedBiggest { dir, deleted_only, found });
+        self.cache.borrow_mut().dirty = true;
+    }
+
+    /// Directory whose binary size should be read from the index next
+    pub fn pending_binary_dir(&self) -> Option<String> {
+        let dir = self.dir_path();
+        match &self.binary_size {
+            Some((loaded, _)) if *loaded == dir => None,
+            _ => Some(dir),
+        }
+    }
+
+    /// Deliver the cumulative size classified as binary below `dir`, as
+    /// requested by `pending_binary_dir`
+    pub fn set_binary_size(&mut self, dir: String, size: u64) {
+        self.binary_size = Some((dir, size));
+    }
+
+    /// Cumulative size classified as binary below the current directory,
+    /// once read
+    pub fn binary_size(&self) -> Option<u64> {
+        let (dir, size) = self.binary_size.as_ref()?;
+        (*dir == self.dir_path()).then_some(*size)
+    }
+
+    pub fn vendored_filter(&self) -> VendoredFilter {
+        self.vendored_filter
+    }
+
+    /// Go from listing everything to hiding generated and vendored files,
+    /// to listing only them, and back
+    pub fn cycle_vendored_filter(&mut self) {
+        self.set_vendored_filter(self.vendored_filter.next());
+    }
+
+    /// Filtering by tag turns the old content filter off; the index has no
+    /// sizes for both at once
+    pub fn set_vendored_filter(&mut self, filter: VendoredFilter) {
+        if filter != self.vendored_filter {
+            self.vendored_filter = filter;
+            if filter != VendoredFilter::All {
+                self.old_only = false;
+            }
+            self.invalidate();
+        }
+    }
+
+    /// Directory whose tagged files should be read from the index next,
+    /// while the tree is filtered by them
+    pub fn pending_vendored_dir(&self) -> Option<String> {
+        if self.vendored_filter == VendoredFilter::All {
+            return None;
+        }
+        let dir = self.dir_path();
+        match &self.vendored {
+            Some((loaded, _)) if *loaded == dir => None,
+            _ => Some(dir),
+        }
+    }
+
+    /// Deliver the tagged files below the entries of `dir`, as requested by
+    /// `pending_vendored_dir`; the selected entry stays selected
+    pub fn set_vendored_sizes(&mut self, dir: String, sizes: Vec<(String, VendoredSizes)>) {
+        let selected = self.visible_children().get(self.selected_index).map(|c| c.name.clone());
+        self.vendored = Some((dir, sizes.into_iter().collect()));
+        self.cache.borrow_mut().dirty = true;
+        let children = self.visible_children();
+        let index = selected.and_then(|name| children.iter().position(|c| c.name == name)).unwrap_or(0);
+        drop(children);
+        self.selected_index = index;
+    }
+
+    /// Tagged files below the current directory's entry `name`, once read
+    fn vendored_sizes(&self, name: &str) -> VendoredSizes {
+        match &self.vendored {
+            Some((dir, sizes)) if *dir == self.dir_path() => sizes.get(name).copied().unwrap_or_default(),
+            _ => VendoredSizes::default(),
+        }
+    }
+
+    /// Directory whose deletion dates should be read from the index next,
+    /// while only deleted files are listed
+    pub fn pending_deletion_dates_dir(&self) -> Option<String> {
+        if !self.show_deleted_only {
+            return None;
+        }
+        let dir = self.dir_path();
+        match &self.deletion_dates {
+            Some((loaded, _)) if *loaded == dir => None,
+            _ => Some(dir),
+        }
+    }
+
+    /// Deliver when the deleted files below the entries of `dir` were last
+    /// deleted, as requested by `pending_deletion_dates_dir`; the selected
+    /// entry stays selected
+    pub fn set_deletion_dates(&mut self, dir: String, dates: Vec<(String, i64)>) {
+        let selected = self.visible_children().get(self.selected_index).map(|c| c.name.clone());
+        self.deletion_dates = Some((dir, dates.into_iter().collect()));
+        self.cache.borrow_mut().dirty = true;
+        let children = self.visible_children();
+        let index = selected.and_then(|name| children.iter().position(|c| c.name == name)).unwrap_or(0);
+        drop(children);
+        self.selected_index = index;
+    }
+
+    /// Whether the listed entries' deletion dates have been read
+    pub fn has_deletion_dates(&self) -> bool {
+        self.show_deleted_only && self.deletion_dates.as_ref().is_some_and(|(dir, _)| *dir == self.dir_path())
+    }
+
+    /// When the deleted files below the current directory's entry `name`
+    /// were last deleted, once read
+    fn deletion_date(&self, name: &str) -> Option<i64> {
+        if !self.has_deletion_dates() {
+            return None;
+        }
+        self.deletion_dates.as_ref().and_then(|(_, dates)| dates.get(name).copied())
+    }
+
+    /// Switch deleted-only mode between largest first and most recently
+    /// deleted first
+    pub fn toggle_deletion_sort(&mut self) {
+        self.by_deletion_date = !self.by_deletion_date;
+        self.invalidate();
+    }
+
+    /// Whether the list is in most recently deleted first order
+    pub fn is_sorted_by_deletion_date(&self) -> bool {
+        self.show_deleted_only && self.by_deletion_date
+    }
+
+    /// Count content first committed more than `years` before `now` as old
+    pub fn set_old_age(&mut self, years: u32, now: i64) {
+        self.old_after_years = years;
+        self.old_cutoff = old_cutoff(now, years);
+        self.old = None;
+        self.cache.borrow_mut().dirty = true;
+    }
+
+    pub fn old_after_years(&self) -> u32 {
+        self.old_after_years
+    }
+
+    /// Unix timestamp before which content counts as old
+    pub fn old_cutoff(&self) -> i64 {
+        self.old_cutoff
+    }
+
+    pub fn is_old_only(&self) -> bool {
+        self.old_only
+    }
+
+    pub fn toggle_old_only(&mut self) {
+        self.set_old_only(!self.old_only);
+    }
+
+    /// List only entries holding old content; turns the vendored filter off
+    pub fn set_old_only(&mut self, old_only: bool) {
+        if old_only != self.old_only {
+            self.old_only = old_only;
+            if old_only {
+                self.vendored_filter = VendoredFilter::All;
+            }
+            self.invalidate();
+        }
+    }
+
+    /// Directory whose old content should be read from the index next,
+    /// while the tree is filtered by it
+    pub fn pending_old_dir(&self) -> Option<String> {
+        if !self.old_only {
+            return None;
+        }
+        let dir = self.dir_path();
+        match &self.old {
+            Some((loaded, _)) if *loaded == dir => None,
+            _ => Some(dir),
+        }
+    }
+
+    /// Deliver the old content below the entries of `dir`, as requested by
+    /// `pending_old_dir`; the selected entry stays selected
+    pub fn set_old_sizes(&mut self, dir: String, sizes: Vec<(String, OldSizes)>) {
+        let selected = self.visible_children().get(self.selected_index).map(|c| c.name.clone());
+        self.old = Some((dir, sizes.into_iter().collect()));
+        self.cache.borrow_mut().dirty = true;
+        let children = self.visible_children();
+        let index = selected.and_then(|name| children.iter().position(|c| c.name == name)).unwrap_or(0);
+        drop(children);
+        self.selected_index = index;
+    }
+
+    /// Old content below the current directory, once read: deleted files'
+    /// only in deleted-only mode
+    pub fn old_size(&self) -> Option<u64> {
+        match &self.old {
+            Some((dir, sizes)) if self.old_only && *dir == self.dir_path() => Some(
+                sizes.values().map(|old| if self.show_deleted_only { old.deleted } else { old.cumulative }).sum(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Old content below the current directory's entry `name`, once read
+    fn old_sizes(&self, name: &str) -> OldSizes {
+        match &self.old {
+            Some((dir, sizes)) if *dir == self.dir_path() => sizes.get(name).copied().unwrap_or_default(),
+            _ => OldSizes::default(),
+        }
+    }
+
+    /// Largest file below the current directory, if it has any
+    pub fn biggest_file(&self) -> Option<BiggestFile> {
+        self.ensure_children();
+        self.cache.borrow().biggest.clone()
+    }
+
     /// Get visible children based on current filters
     pub fn visible_children(&self) -> std::cell::Ref<'_, Vec<TreeNodeView>> {
         self.ensure_children();
         std::cell::Ref::map(self.cache.borrow(), |c| &c.children)
     }
 
+    /// The listed children as a table; `size` is the one shown, deleted
+    /// size in deleted-only mode, which also fills in `deleted_at`
+    pub fn export(&self) -> ListExport {
+        let columns = vec!["path", "is_dir", "size", "cumulative_size", "current_size", "size_delta", "deleted_at"];
+        let mut export = ListExport::new("tree", columns);
+        for child in self.visible_children().iter() {
+            export.push(vec![
+                self.child_path(&child.name).into(),
+                child.has_children.into(),
+                child.display_size.into(),
+                child.cumulative_size.into(),
+                child.current_size.into(),
+                child.size_delta.into(),
+                child.deleted_at.map_or(ExportValue::Empty, ExportValue::from),
+            ]);
+        }
+        export
+    }
+
     /// Get the selected index
     pub fn selected_index(&self) -> usize {
         self.selected_index
@@ -139,19 +849,46 @@ impl TreeViewModel {
     }
 
     pub fn enter_selected(&mut self) {
-        let child_name = {
+        let child = {
             let children = self.visible_children();
-            children
-                .get(self.selected_index)
-                .filter(|c| c.has_children)
-                .map(|c| c.name.clone())
+            children.get(self.selected_index).filter(|c| c.has_children).map(|c| c.id)
         };
-        if let Some(name) = child_name {
-            self.path_stack.push(name);
+        if let Some(id) = child {
+            self.path_stack.push(id);
             self.invalidate();
         }
     }
 
+    /// Follow the largest visible child level by level and select the leaf
+    /// at the end of the chain (the entry at the top of each listing)
+    pub fn descend_to_largest_leaf(&mut self) {
+        loop {
+            if self.current_node().is_unloaded() {
+                self.resume = Some(Resume::Descend);
+                return;
+            }
+            let largest = {
+                let children = self.visible_children();
+                // The first of the largest, in case they're sorted by date
+                children.iter().min_by_key(|c| std::cmp::Reverse(c.display_size)).map(|c| (c.id, c.has_children))
+            };
+            match largest {
+                Some((id, true)) => {
+                    self.path_stack.push(id);
+                    self.invalidate();
+                }
+                Some((id, false)) => {
+                    let children = self.visible_children();
+                    let index = children.iter().position(|c| c.id == id).unwrap_or(0);
+                    drop(children);
+                    self.selected_index = index;
+                    return;
+                }
+                None => return,
+            }
+        }
+    }
+
     /// Go back one level, returns false if already at root
     pub fn go_back(&mut self) -> bool {
         if self.path_stack.is_empty() {
@@ -164,19 +901,74 @@ impl TreeViewModel {
     }
 
     pub fn toggle_deleted_only(&mut self) {
-        self.show_deleted_only = !self.show_deleted_only;
+        self.set_deleted_only(!self.show_deleted_only);
+    }
+
+    pub fn set_deleted_only(&mut self, deleted_only: bool) {
+        self.show_deleted_only = deleted_only;
+        self.invalidate();
+    }
+
+    /// Show `path`: open it if it is a directory, otherwise open its parent
+    /// and select it; stops at the deepest part that still exists
+    pub fn open_path(&mut self, path: &str) {
+        let mut stack = Vec::new();
+        let mut node = self.tree.root();
+        let mut leaf = None;
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            if node.is_unloaded() {
+                break;
+            }
+            match node.child(part) {
+                Some(child) if child.has_children() => {
+                    stack.push(child.id());
+                    node = child;
+                }
+                Some(_) => {
+                    leaf = Some(part.to_string());
+                    break;
+                }
+                None => break,
+            }
+        }
+        let waiting = node.is_unloaded();
+        self.path_stack = stack;
         self.invalidate();
+        if waiting {
+            self.resume = Some(Resume::Open(path.to_string()));
+            return;
+        }
+        let index = leaf.and_then(|name| self.visible_children().iter().position(|c| c.name == name));
+        if let Some(index) = index {
+            self.selected_index = index;
+        }
     }
 
-    /// Navigate to a specific path (used by search results)
+    /// Navigate to a specific path (used by search results): open the
+    /// directory holding it, as far down as it still exists
     pub fn navigate_to_path(&mut self, path: &str) {
         let parts: Vec<&str> = path.split('/').collect();
         if parts.len() > 1 {
-            self.path_stack = parts[..parts.len() - 1]
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
+            let mut stack = Vec::new();
+            let mut node = self.tree.root();
+            for part in &parts[..parts.len() - 1] {
+                if node.is_unloaded() {
+                    break;
+                }
+                match node.child(part) {
+                    Some(child) if child.has_children() => {
+                        stack.push(child.id());
+                        node = child;
+                    }
+                    _ => break,
+                }
+            }
+            let waiting = node.is_unloaded();
+            self.path_stack = stack;
             self.invalidate();
+            if waiting {
+                self.resume = Some(Resume::Navigate(path.to_string()));
+            }
         }
     }
 }
@@ -200,8 +992,8 @@ impl Selectable for TreeViewModel {
 mod tests {
     use super::*;
 
-    fn create_test_tree() -> Arc<TreeNode> {
-        let mut root = TreeNode::new("(root)");
+    fn create_test_tree() -> Arc<Tree> {
+        let mut root = Tree::new();
         root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
         root.add_path_with_sizes(&["src", "lib.rs"], 800, 400, 1);
         root.add_path_with_sizes(&["assets", "logo.png"], 5000, 0, 1);
@@ -229,6 +1021,205 @@ mod tests {
         assert!(vm.is_at_root());
     }
 
+    #[test]
+    fn test_history_follows_selection() {
+        let tree = create_test_tree();
+        let mut vm = TreeViewModel::new(tree);
+        assert_eq!(vm.pending_history_path(), None);
+
+        vm.set_history_range(8000, 8002);
+        let path = vm.pending_history_path().unwrap();
+        vm.set_history(path, &[(8001, 10)]);
+        assert_eq!(vm.pending_history_path(), None);
+        assert_eq!(vm.selected_history().unwrap().values, vec![0, 10, 10]);
+
+        vm.move_down();
+        assert!(vm.selected_history().is_none());
+        assert!(vm.pending_history_path().is_some());
+    }
+
+    #[test]
+    fn test_details_follow_selection() {
+        let mut vm = TreeViewModel::new(create_test_tree());
+        let (dir, deleted_only) = vm.pending_details_dir().unwrap();
+        assert_eq!((dir.as_str(), deleted_only), ("assets", false));
+        let details = EntryDetails { extensions: vec![(".png".into(), 7000)], largest: vec![("assets/logo.png".into(), 5000)] };
+        vm.set_details(dir, deleted_only, details.clone());
+        assert_eq!(vm.pending_details_dir(), None);
+        assert_eq!(vm.selected_details(), Some(&details));
+
+        // Deleted-only sizes are a different breakdown
+        vm.toggle_deleted_only();
+        assert!(vm.selected_details().is_none());
+        assert_eq!(vm.pending_details_dir(), Some(("assets".to_string(), true)));
+        vm.toggle_deleted_only();
+
+        // Files have no breakdown
+        vm.move_down();
+        vm.move_down();
+        assert_eq!(vm.selected_path().as_deref(), Some("README.md"));
+        assert_eq!(vm.pending_details_dir(), None);
+        assert!(vm.selected_details().is_none());
+    }
+
+    #[test]
+    fn test_biggest_file_follows_navigation() {
+        let mut vm = TreeViewModel::new(create_test_tree());
+
+        let biggest = vm.biggest_file().unwrap();
+        assert_eq!(biggest.path, "assets/logo.png");
+        assert_eq!(biggest.size, 5000);
+        assert!((biggest.percent - 5000.0 * 100.0 / 8900.0).abs() < 1e-9);
+
+        vm.navigate_to_path("src/main.rs");
+        let biggest = vm.biggest_file().unwrap();
+        assert_eq!(biggest.path, "main.rs");
+        assert!((biggest.percent - 1000.0 * 100.0 / 1800.0).abs() < 1e-9);
+
+        // Only deleted files count in deleted-only mode; src has none
+        vm.set_deleted_only(true);
+        assert_eq!(vm.biggest_file(), None);
+        vm.go_back();
+        assert_eq!(vm.biggest_file().unwrap().percent, 100.0);
+    }
+
+    #[test]
+    fn test_descend_to_largest_leaf() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["assets", "video", "intro.mp4"], 9000, 9000, 1);
+        root.add_path_with_sizes(&["assets", "video", "old.mp4"], 4000, 0, 1);
+        root.add_path_with_sizes(&["assets", "logo.png"], 500, 500, 1);
+        root.add_path_with_sizes(&["big.iso"], 12000, 0, 1);
+        root.compute_totals();
+        let mut vm = TreeViewModel::new(Arc::new(root));
+
+        vm.descend_to_largest_leaf();
+        assert_eq!(vm.current_path(), "/assets/video");
+        assert_eq!(vm.selected_path().as_deref(), Some("assets/video/intro.mp4"));
+
+        // The chain follows the listed sizes, so deleted-only picks deleted files
+        vm.go_back();
+        vm.go_back();
+        vm.set_deleted_only(true);
+        vm.descend_to_largest_leaf();
+        assert!(vm.is_at_root());
+        assert_eq!(vm.selected_path().as_deref(), Some("big.iso"));
+    }
+
+    #[test]
+    fn test_open_path() {
+        let mut vm = TreeViewModel::new(create_test_tree());
+
+        vm.open_path("assets");
+        assert_eq!(vm.current_path(), "/assets");
+
+        vm.open_path("assets/icon.png");
+        assert_eq!(vm.current_path(), "/assets");
+        assert_eq!(vm.selected_path().as_deref(), Some("assets/icon.png"));
+
+        vm.open_path("src/gone/file.rs");
+        assert_eq!(vm.current_path(), "/src");
+    }
+
+    #[test]
+    fn test_set_root_keeps_position_and_badges() {
+        let mut vm = TreeViewModel::new(create_test_tree());
+        vm.open_path("src/lib.rs");
+
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["src", "main.rs"], 1000, 500, 1);
+        root.add_path_with_sizes(&["src", "lib.rs"], 3000, 400, 1);
+        root.compute_totals();
+        vm.set_root(Arc::new(root));
+        let mut deltas = FxHashMap::default();
+        deltas.insert("src/lib.rs".to_string(), 2200);
+        vm.set_size_deltas(deltas);
+
+        assert_eq!(vm.current_path(), "/src");
+        assert_eq!(vm.selected_path().as_deref(), Some("src/lib.rs"));
+        let children = vm.visible_children();
+        assert_eq!(children[0].size_delta, 2200);
+        assert_eq!(children[1].size_delta, 0);
+        drop(children);
+
+        // The directory is gone: fall back to the root
+        vm.set_root(Arc::new(Tree::new()));
+        assert!(vm.is_at_root());
+    }
+
+    #[test]
+    fn test_vendored_filter() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["web", "node_modules", "a.js"], 400, 0, 1);
+        root.add_path_with_sizes(&["web", "app.js"], 100, 100, 1);
+        root.add_path_with_sizes(&["Cargo.lock"], 300, 300, 1);
+        root.add_path_with_sizes(&["src", "main.rs"], 200, 200, 1);
+        root.compute_totals();
+        let mut vm = TreeViewModel::new(Arc::new(root));
+        assert_eq!(vm.pending_vendored_dir(), None);
+
+        let names = |vm: &TreeViewModel| -> Vec<(String, u64)> {
+            vm.visible_children().iter().map(|c| (c.name.clone(), c.display_size)).collect()
+        };
+        let tagged = |files, cumulative, deleted| VendoredSizes { files, cumulative, deleted };
+        vm.cycle_vendored_filter();
+        assert_eq!(vm.vendored_filter(), VendoredFilter::Hide);
+        assert_eq!(vm.pending_vendored_dir().as_deref(), Some(""));
+        vm.set_vendored_sizes(String::new(), vec![
+            ("Cargo.lock".to_string(), tagged(1, 300, 0)),
+            ("web".to_string(), tagged(1, 400, 400)),
+        ]);
+        assert_eq!(vm.pending_vendored_dir(), None);
+        assert_eq!(names(&vm), [("src".to_string(), 200), ("web".to_string(), 100)]);
+
+        vm.cycle_vendored_filter();
+        assert_eq!(names(&vm), [("web".to_string(), 400), ("Cargo.lock".to_string(), 300)]);
+
+        // Sizes were read for the root only
+        vm.enter_selected();
+        assert_eq!(vm.pending_vendored_dir().as_deref(), Some("web"));
+
+        vm.cycle_vendored_filter();
+        assert_eq!(vm.vendored_filter(), VendoredFilter::All);
+        assert_eq!(vm.pending_vendored_dir(), None);
+        assert_eq!(vm.visible_children().len(), 2);
+    }
+
+    #[test]
+    fn test_old_filter() {
+        let mut vm = TreeViewModel::new(create_test_tree());
+        vm.set_old_age(5, 1_000_000_000);
+        assert_eq!(vm.old_cutoff(), old_cutoff(1_000_000_000, 5));
+        assert_eq!(vm.pending_old_dir(), None);
+
+        vm.cycle_vendored_filter();
+        vm.toggle_old_only();
+        assert_eq!(vm.vendored_filter(), VendoredFilter::All);
+        assert_eq!(vm.pending_old_dir().as_deref(), Some(""));
+        assert_eq!(vm.old_size(), None);
+        vm.set_old_sizes(String::new(), vec![
+            ("assets".to_string(), OldSizes { cumulative: 5000, deleted: 5000 }),
+            ("src".to_string(), OldSizes { cumulative: 800, deleted: 0 }),
+        ]);
+        assert_eq!(vm.pending_old_dir(), None);
+        assert_eq!(vm.old_size(), Some(5800));
+        let names = |vm: &TreeViewModel| -> Vec<(String, u64)> {
+            vm.visible_children().iter().map(|c| (c.name.clone(), c.display_size)).collect()
+        };
+        assert_eq!(names(&vm), [("assets".to_string(), 5000), ("src".to_string(), 800)]);
+
+        vm.toggle_deleted_only();
+        assert_eq!(names(&vm), [("assets".to_string(), 5000)]);
+        assert_eq!(vm.old_size(), Some(5000));
+
+        vm.enter_selected();
+        assert_eq!(vm.pending_old_dir().as_deref(), Some("assets"));
+
+        vm.cycle_vendored_filter();
+        assert!(!vm.is_old_only());
+        assert_eq!(vm.pending_old_dir(), None);
+    }
+
     #[test]
     fn test_deleted_filter() {
         let tree = create_test_tree();
@@ -242,4 +1233,99 @@ mod tests {
         assert_eq!(children.len(), 1);
         assert_eq!(children[0].name, "assets");
     }
+
+    #[test]
+    fn test_deletion_dates_in_deleted_only_mode() {
+        let mut root = Tree::new();
+        root.add_path_with_sizes(&["assets", "logo.png"], 5000, 0, 1);
+        root.add_path_with_sizes(&["docs", "old.md"], 300, 0, 1);
+        root.add_path_with_sizes(&["wip.bin"], 1000, 0, 1);
+        root.add_path_with_sizes(&["README.md"], 100, 100, 1);
+        root.compute_totals();
+        let mut vm = TreeViewModel::new(Arc::new(root));
+        assert_eq!(vm.pending_deletion_dates_dir(), None);
+
+        vm.toggle_deleted_only();
+        assert_eq!(vm.pending_deletion_dates_dir().as_deref(), Some(""));
+        assert!(!vm.has_deletion_dates());
+        vm.move_down();
+        vm.set_deletion_dates(String::new(), vec![("assets".to_string(), 1000), ("docs".to_string(), 3000)]);
+        assert_eq!(vm.pending_deletion_dates_dir(), None);
+        let dates = |vm: &TreeViewModel| -> Vec<(String, Option<i64>)> {
+            vm.visible_children().iter().map(|c| (c.name.clone(), c.deleted_at)).collect()
+        };
+        assert_eq!(dates(&vm), [
+            ("assets".to_string(), Some(1000)),
+            ("wip.bin".to_string(), None),
+            ("docs".to_string(), Some(3000)),
+        ]);
+        assert_eq!(vm.selected_index(), 1);
+
+        // Most recent first, entries nothing removed last
+        vm.toggle_deletion_sort();
+        assert!(vm.is_sorted_by_deletion_date());
+        assert_eq!(dates(&vm), [
+            ("docs".to_string(), Some(3000)),
+            ("assets".to_string(), Some(1000)),
+            ("wip.bin".to_string(), None),
+        ]);
+        // Still the largest, wherever it is listed
+        vm.descend_to_largest_leaf();
+        assert_eq!(vm.selected_path().as_deref(), Some("assets/logo.png"));
+        assert_eq!(vm.pending_deletion_dates_dir().as_deref(), Some("assets"));
+
+        vm.toggle_deleted_only();
+        assert!(!vm.is_sorted_by_deletion_date());
+        assert_eq!(vm.pending_deletion_dates_dir(), None);
+        assert!(vm.visible_children().iter().all(|c| c.deleted_at.is_none()));
+    }
+
+    #[test]
+    fn test_selected_mark_uses_full_path() {
+        let tree = create_test_tree();
+        let mut vm = TreeViewModel::new(tree);
+
+        // assets is the largest top-level entry
+        vm.enter_selected();
+        let (key, sizes) = vm.selected_mark().unwrap();
+        assert_eq!(key, MarkKey::Path("assets/logo.png".to_string()));
+        assert_eq!(sizes, MarkSizes { cumulative: 5000, current: 0 });
+    }
+
+    #[test]
+    fn test_partial_tree_loads_on_navigation() {
+        use crate::model::DirEntry;
+
+        let entry = |name: &str, is_dir, cumulative| DirEntry {
+            name: name.into(),
+            is_dir,
+            cumulative_size: cumulative,
+            current_size: cumulative,
+            blob_count: 1,
+            deleted_size: 0,
+            kind: EntryKind::File,
+        };
+        let mut tree = Tree::from_dir_entries(vec![entry("src", true, 1800), entry("README.md", false, 100)]);
+        let mut vm = TreeViewModel::new(Arc::new(tree.clone()));
+        assert_eq!(vm.pending_dir(), None);
+        assert_eq!(vm.pending_biggest_dir(), Some((String::new(), false)));
+        vm.set_biggest_file(String::new(), false, Some(("src/main.rs".into(), 1000)));
+        assert_eq!(vm.biggest_file().unwrap().path, "src/main.rs");
+
+        // Opening a path below a directory not loaded yet waits for it
+        vm.open_path("src/lib.rs");
+        let (id, path) = vm.pending_dir().unwrap();
+        assert_eq!(path, "src");
+        assert!(vm.visible_children().is_empty());
+
+        tree.set_dir_entries(id, vec![entry("lib.rs", false, 800), entry("main.rs", false, 1000)]);
+        vm.extend_tree(Arc::new(tree));
+        assert_eq!(vm.pending_dir(), None);
+        assert_eq!(vm.current_path(), "/src");
+        assert_eq!(vm.selected_path().as_deref(), Some("src/lib.rs"));
+
+        // Fully loaded now, so the largest file is found by walking again
+        assert_eq!(vm.pending_biggest_dir(), None);
+        assert_eq!(vm.biggest_file().unwrap().path, "main.rs");
+    }
 }
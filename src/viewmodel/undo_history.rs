@@ -0,0 +1,81 @@
+/// Most changes kept to undo; older ones are forgotten
+const LIMIT: usize = 100;
+
+/// States to go back to with undo, and the undone ones redo returns to
+#[derive(Debug)]
+pub struct UndoHistory<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> Default for UndoHistory<T> {
+    fn default() -> Self {
+        Self { undo: Vec::new(), redo: Vec::new() }
+    }
+}
+
+impl<T> UndoHistory<T> {
+    /// Remember the state from before a change; what was undone can no
+    /// longer be redone
+    pub fn record(&mut self, before: T) {
+        if self.undo.len() == LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+        self.redo.clear();
+    }
+
+    /// Trade the current state for the one before the last change
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Trade the current state for the one the last undo left
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo_and_new_changes() {
+        let mut history = UndoHistory::default();
+        assert_eq!(history.undo(0), None);
+        history.record(0);
+        history.record(1);
+
+        // At 2: back to 1, then 0, then forward again
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), Some(0));
+        assert_eq!(history.undo(0), None);
+        assert_eq!(history.redo(0), Some(1));
+
+        // A new change drops what was left to redo
+        history.record(1);
+        assert_eq!(history.redo(5), None);
+        assert_eq!(history.undo(5), Some(1));
+    }
+
+    #[test]
+    fn test_limit_forgets_the_oldest() {
+        let mut history = UndoHistory::default();
+        for state in 0..LIMIT + 5 {
+            history.record(state);
+        }
+        let mut undone = 0;
+        let mut current = LIMIT + 5;
+        while let Some(previous) = history.undo(current) {
+            current = previous;
+            undone += 1;
+        }
+        assert_eq!(undone, LIMIT);
+        assert_eq!(current, 5);
+    }
+}
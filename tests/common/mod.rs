@@ -55,7 +55,7 @@ pub fn add_commit(
     // Get parent commit if exists
     let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
 
-    let commit_id = if let Some(parent) = parent {
+    if let Some(parent) = parent {
         repo.commit(
             Some("HEAD"),
             &sig,
@@ -73,9 +73,7 @@ pub fn add_commit(
             &tree,
             &[],
         ).unwrap()
-    };
-
-    commit_id
+    }
 }
 
 /// Remove a file from the repository and create a commit
@@ -111,3 +109,66 @@ pub fn remove_file_commit(
         &[&parent],
     ).unwrap()
 }
+
+/// Commit exactly `files` (no working tree or index involved) with the given
+/// parents, author and author time; no ref is updated
+pub fn commit_files(
+    repo: &Repository,
+    files: &[(&str, &[u8])],
+    parents: &[git2::Oid],
+    author: &str,
+    time: i64,
+    message: &str,
+) -> git2::Oid {
+    let entries: Vec<(&str, u32, &[u8])> = files.iter().map(|(path, content)| (*path, 0o100644, *content)).collect();
+    commit_entries(repo, &entries, parents, author, time, message)
+}
+
+/// Like `commit_files`, with an explicit git mode per entry; for a gitlink
+/// (0o160000) the content is ignored and a dangling submodule commit is used
+pub fn commit_entries(
+    repo: &Repository,
+    entries: &[(&str, u32, &[u8])],
+    parents: &[git2::Oid],
+    author: &str,
+    time: i64,
+    message: &str,
+) -> git2::Oid {
+    let sig = Signature::new(author, "dev@example.com", &git2::Time::new(time, 0)).unwrap();
+
+    let mut index = git2::Index::new().unwrap();
+    for (path, mode, content) in entries {
+        let id = if *mode == 0o160000 {
+            git2::Oid::from_bytes(&[7; 20]).unwrap()
+        } else {
+            repo.blob(content).unwrap()
+        };
+        let entry = git2::IndexEntry {
+            ctime: git2::IndexTime::new(0, 0),
+            mtime: git2::IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode: *mode,
+            uid: 0,
+            gid: 0,
+            file_size: content.len() as u32,
+            id,
+            flags: 0,
+            flags_extended: 0,
+            path: path.as_bytes().to_vec(),
+        };
+        index.add(&entry).unwrap();
+    }
+    let tree_id = index.write_tree_to(repo).unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+
+    let parents: Vec<git2::Commit> = parents.iter().map(|p| repo.find_commit(*p).unwrap()).collect();
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+    repo.commit(None, &sig, &sig, message, &tree, &parent_refs).unwrap()
+}
+
+/// Point HEAD at `commit` through a `main` branch
+pub fn set_head(repo: &Repository, commit: git2::Oid) {
+    repo.reference("refs/heads/main", commit, true, "test").unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+}
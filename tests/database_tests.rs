@@ -3,7 +3,13 @@
 
 mod common;
 
-use repodiet::repository::{BlobMetaRecord, BlobRecord, Database, SCHEMA_VERSION};
+use repodiet::model::{AuthorGrouping, BlobFilter, OldSizes, SizeSnapshot, SnapshotEntry, SnapshotEntryKind, VendoredShare, VendoredSizes};
+use repodiet::repository::{
+    BlobMetaRecord, BlobPathRecord, BlobRecord, Database, DbTuning, HeadBlobRecord, PathRecord, ScanRecords,
+    SkippedRecord, TreePathRecord, SCHEMA_VERSION, SNAPSHOT_FORMAT,
+};
+use sqlx::Connection;
+use repodiet::util::PathFilter;
 
 /// Helper to create a 20-byte OID from a test identifier
 fn test_oid(id: u8) -> [u8; 20] {
@@ -19,6 +25,22 @@ async fn setup_db() -> Database {
     db
 }
 
+/// Save (blob, path) occurrences, adding their paths to the path dictionary
+async fn save_blob_paths(db: &Database, occurrences: &[([u8; 20], &str)]) {
+    let mut paths: Vec<&str> = Vec::new();
+    for (_, path) in occurrences {
+        if !paths.contains(path) {
+            paths.push(path);
+        }
+    }
+    let stored = db.path_count().await.unwrap();
+    let id = |path: &str| (stored + paths.iter().position(|p| *p == path).unwrap()) as i64;
+    let records: Vec<_> = paths.iter().map(|path| PathRecord::new(id(path), path.as_bytes())).collect();
+    db.save_paths(&records).await.unwrap();
+    let rows: Vec<_> = occurrences.iter().map(|(oid, path)| BlobPathRecord::new(*oid, id(path))).collect();
+    db.save_blob_paths(&rows).await.unwrap();
+}
+
 #[tokio::test]
 async fn test_schema_init() {
     let db = common::create_test_db().await;
@@ -81,16 +103,16 @@ async fn test_save_and_load_tree() {
     let tree = db.load_tree().await.unwrap();
 
     // Verify structure
-    assert_eq!(tree.name, "(root)");
-    assert!(tree.children.contains_key("src"));
-    assert!(tree.children.contains_key("README.md"));
+    assert_eq!(tree.root().name(), "(root)");
+    assert!(tree.find("src").is_some());
+    assert!(tree.find("README.md").is_some());
 
-    let src = tree.children.get("src").unwrap();
-    assert!(src.children.contains_key("main.rs"));
-    assert!(src.children.contains_key("lib.rs"));
+    let src = tree.find("src").unwrap();
+    assert!(src.find("main.rs").is_some());
+    assert!(src.find("lib.rs").is_some());
 
     // Verify sizes
-    let main_rs = src.children.get("main.rs").unwrap();
+    let main_rs = src.find("main.rs").unwrap();
     assert_eq!(main_rs.cumulative_size, 1000);
     assert_eq!(main_rs.current_size, 500);
 }
@@ -113,8 +135,8 @@ async fn test_blob_conflict_handling() {
 
     // Load tree - cumulative should accumulate, current should update
     let tree = db.load_tree().await.unwrap();
-    let src = tree.children.get("src").unwrap();
-    let file = src.children.get("file.rs").unwrap();
+    let src = tree.find("src").unwrap();
+    let file = src.find("file.rs").unwrap();
 
     // Cumulative size should be sum of both
     assert_eq!(file.cumulative_size, 250); // 100 + 150
@@ -130,10 +152,10 @@ async fn test_top_blobs_sorted() {
 
     // Save blob metadata with different sizes
     let metadata = vec![
-        BlobMetaRecord::new(test_oid(1), 100, "small.txt", "author", 1000),
-        BlobMetaRecord::new(test_oid(2), 500, "medium.txt", "author", 1001),
-        BlobMetaRecord::new(test_oid(3), 1000, "large.txt", "author", 1002),
-        BlobMetaRecord::new(test_oid(4), 250, "small2.txt", "author", 1003),
+        BlobMetaRecord::new(test_oid(1), 100, "small.txt", "author", "author@example.com", 1000),
+        BlobMetaRecord::new(test_oid(2), 500, "medium.txt", "author", "author@example.com", 1001),
+        BlobMetaRecord::new(test_oid(3), 1000, "large.txt", "author", "author@example.com", 1002),
+        BlobMetaRecord::new(test_oid(4), 250, "small2.txt", "author", "author@example.com", 1003),
     ];
     db.save_blob_metadata_with_callback(&metadata, |_| {}).await.unwrap();
 
@@ -150,6 +172,201 @@ async fn test_top_blobs_sorted() {
     assert_eq!(top[2].path, "small2.txt");
 }
 
+#[tokio::test]
+async fn test_blob_metadata_beyond_one_batch() {
+    let db = setup_db().await;
+
+    // More rows than fit in one statement's variables
+    let paths: Vec<String> = (0..6000).map(|i| format!("f{i}.txt")).collect();
+    let metadata: Vec<_> = paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let mut oid = [0u8; 20];
+            oid[..4].copy_from_slice(&(i as u32).to_be_bytes());
+            BlobMetaRecord::new(oid, i as i64 + 1, path.as_str(), "author", "author@example.com", 1000)
+        })
+        .collect();
+    let mut saved = 0;
+    db.save_blob_metadata_with_callback(&metadata, |n| saved += n).await.unwrap();
+    assert_eq!(saved, 6000);
+    assert_eq!(db.get_top_blobs(1).await.unwrap()[0].size, 6000);
+}
+
+#[tokio::test]
+async fn test_heavy_commits_add_up_across_batches() {
+    let db = setup_db().await;
+
+    let blob = |id, size, path: &'static str, commit| {
+        BlobMetaRecord::new(test_oid(id), size, path, "alice", "alice@example.com", 1000).with_commit(test_oid(commit), "Add binaries")
+    };
+    db.save_blob_metadata_with_callback(&[blob(1, 300, "a.bin", 8), blob(2, 50, "b.txt", 9)], |_| {}).await.unwrap();
+    // A later batch of the same commit, and a blob no commit is known for
+    let later = [blob(3, 500, "c.bin", 8), BlobMetaRecord::new(test_oid(4), 900, "d.bin", "bob", "bob@example.com", 1000)];
+    db.save_blob_metadata_with_callback(&later, |_| {}).await.unwrap();
+
+    let commits = db.get_heavy_commits(10).await.unwrap();
+    let summary: Vec<_> = commits.iter().map(|c| (c.oid.clone(), c.added_size, c.blob_count, c.largest_path.as_str())).collect();
+    assert_eq!(summary, [(test_oid(8).to_vec(), 800, 2, "c.bin"), (test_oid(9).to_vec(), 50, 1, "b.txt")]);
+    assert_eq!(db.get_heavy_commits(1).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_top_blobs_not_in_head() {
+    let db = setup_db().await;
+
+    let metadata = vec![
+        BlobMetaRecord::new(test_oid(1), 1000, "kept.bin", "author", "author@example.com", 1000),
+        BlobMetaRecord::new(test_oid(2), 500, "gone.bin", "author", "author@example.com", 1001),
+    ];
+    db.save_blob_metadata_with_callback(&metadata, |_| {}).await.unwrap();
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(1), "kept.bin", 1000)])
+        .await
+        .unwrap();
+
+    let top = db.get_top_blobs(10).await.unwrap();
+    let flags: Vec<_> = top.iter().map(|b| (b.path.as_str(), b.in_head)).collect();
+    assert_eq!(flags, vec![("kept.bin", true), ("gone.bin", false)]);
+
+    let deleted = db.get_top_blobs_not_in_head(10).await.unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].path, "gone.bin");
+}
+
+#[tokio::test]
+async fn test_top_blobs_filtered() {
+    let db = setup_db().await;
+
+    // More excluded blobs than the limit, so the query has to page past them
+    let mut metadata: Vec<_> = (1..=5)
+        .map(|i| BlobMetaRecord::new(test_oid(i), 1000 + i as i64, format!("vendor/lib{}.js", i), "author", "author@example.com", 1000))
+        .collect();
+    metadata.push(BlobMetaRecord::new(test_oid(10), 500, "src/main.rs", "author", "author@example.com", 1000));
+    metadata.push(BlobMetaRecord::new(test_oid(11), 100, "logo.png", "author", "author@example.com", 1000));
+    db.save_blob_metadata_with_callback(&metadata, |_| {}).await.unwrap();
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(10), "src/main.rs", 500)])
+        .await
+        .unwrap();
+
+    let filter = PathFilter::new(&["vendor/"]).unwrap();
+    let top = db.get_top_blobs_filtered(2, false, &filter).await.unwrap();
+    let paths: Vec<_> = top.iter().map(|b| b.path.as_str()).collect();
+    assert_eq!(paths, vec!["src/main.rs", "logo.png"]);
+
+    let deleted = db.get_top_blobs_filtered(2, true, &filter).await.unwrap();
+    let paths: Vec<_> = deleted.iter().map(|b| b.path.as_str()).collect();
+    assert_eq!(paths, vec!["logo.png"]);
+}
+
+#[tokio::test]
+async fn test_top_blobs_matching() {
+    let db = setup_db().await;
+
+    let blob = |id, size, path: &'static str| BlobMetaRecord::new(test_oid(id), size, path, "author", "author@example.com", 1000);
+    let metadata = vec![
+        blob(1, 5000, "media/intro.mp4"),
+        blob(2, 4000, "dist/Release.ZIP"),
+        blob(3, 3000, "vendor/old.zip"),
+        blob(4, 2000, "media/backup.zip"),
+        blob(5, 1000, "dist.zip/readme_1.txt"),
+        blob(6, 500, "docs/readme%1.txt"),
+    ];
+    db.save_blob_metadata_with_callback(&metadata, |_| {}).await.unwrap();
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(2), "dist/Release.ZIP", 4000)])
+        .await
+        .unwrap();
+
+    let paths = |blobs: Vec<repodiet::model::LargeBlobInfo>| blobs.into_iter().map(|b| b.path).collect::<Vec<_>>();
+    let none = PathFilter::default();
+    let zip = BlobFilter::parse(".zip").unwrap();
+    assert_eq!(
+        paths(db.get_top_blobs_matching(10, false, &zip, &none).await.unwrap()),
+        ["dist/Release.ZIP", "vendor/old.zip", "media/backup.zip"]
+    );
+    assert_eq!(paths(db.get_top_blobs_matching(1, true, &zip, &none).await.unwrap()), ["vendor/old.zip"]);
+    let vendor = PathFilter::new(&["vendor/"]).unwrap();
+    assert_eq!(
+        paths(db.get_top_blobs_matching(1, true, &zip, &vendor).await.unwrap()),
+        ["media/backup.zip"]
+    );
+
+    let media = BlobFilter::parse("Media/").unwrap();
+    assert_eq!(
+        paths(db.get_top_blobs_matching(10, false, &media, &none).await.unwrap()),
+        ["media/intro.mp4", "media/backup.zip"]
+    );
+    // `_` and `%` are matched literally
+    let readme = BlobFilter::parse("readme_").unwrap();
+    assert_eq!(paths(db.get_top_blobs_matching(10, false, &readme, &none).await.unwrap()), ["dist.zip/readme_1.txt"]);
+}
+
+#[tokio::test]
+async fn test_extension_stats_match_tree() {
+    let db = setup_db().await;
+    let blobs = vec![
+        BlobRecord::new(test_oid(1), "src/main.rs", 1000, 500),
+        BlobRecord::new(test_oid(2), "src/lib.RS", 800, 400),
+        BlobRecord::new(test_oid(3), "assets/logo.png", 5000, 0),
+        BlobRecord::new(test_oid(4), "v1.2/Makefile", 30, 30),
+        BlobRecord::new(test_oid(5), "data.averylongsuffix", 70, 70),
+        BlobRecord::new(test_oid(6), "vendor/lib.js", 900, 900),
+        // A file that later became a directory only counts as the directory
+        BlobRecord::new(test_oid(7), "docs", 10, 0),
+        BlobRecord::new(test_oid(8), "docs/guide.md", 20, 20),
+    ];
+    db.save_blobs_with_callback(&blobs, |_| {}).await.unwrap();
+
+    let stats = db.get_extension_stats(&PathFilter::default()).await.unwrap();
+    let summary: Vec<_> = stats
+        .iter()
+        .map(|(ext, s)| (ext.as_str(), s.cumulative_size, s.current_size, s.deleted_size, s.file_count))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("(no ext)", 100, 100, 0, 2),
+            (".js", 900, 900, 0, 1),
+            (".md", 20, 20, 0, 1),
+            (".png", 5000, 0, 5000, 1),
+            (".rs", 1800, 900, 0, 2),
+        ]
+    );
+
+    let filter = PathFilter::new(&["vendor/", "*.png"]).unwrap();
+    let filtered = db.get_extension_stats(&filter).await.unwrap();
+    let extensions: Vec<_> = filtered.iter().map(|(ext, _)| ext.as_str()).collect();
+    assert_eq!(extensions, vec!["(no ext)", ".md", ".rs"]);
+    assert_eq!(filtered[2].1.cumulative_size, 1800);
+}
+
+#[tokio::test]
+async fn test_path_history_by_quarter() {
+    let db = setup_db().await;
+    assert_eq!(db.get_history_range().await.unwrap(), None);
+
+    // 2023-11-14, 2024-01-01, 2024-02-01
+    let metadata = vec![
+        BlobMetaRecord::new(test_oid(1), 100, "src/a.rs", "author", "author@example.com", 1700000000),
+        BlobMetaRecord::new(test_oid(2), 200, "src/a.rs", "author", "author@example.com", 1704067200),
+        BlobMetaRecord::new(test_oid(3), 50, "src/b.rs", "author", "author@example.com", 1706745600),
+        BlobMetaRecord::new(test_oid(4), 999, "src0/c.rs", "author", "author@example.com", 1706745600),
+    ];
+    db.save_blob_metadata_with_callback(&metadata, |_| {}).await.unwrap();
+
+    let (first, last) = db.get_history_range().await.unwrap().unwrap();
+    assert_eq!(last, first + 1);
+
+    let mut dir = db.get_path_history("src").await.unwrap();
+    dir.sort();
+    assert_eq!(dir, vec![(first, 100), (last, 250)]);
+
+    let file = db.get_path_history("src/a.rs").await.unwrap();
+    assert_eq!(file.iter().map(|(_, size)| size).sum::<u64>(), 300);
+
+    let all: u64 = db.get_path_history("").await.unwrap().iter().map(|(_, size)| size).sum();
+    assert_eq!(all, 1349);
+}
+
 #[tokio::test]
 async fn test_seen_blobs_tracking() {
     let db = setup_db().await;
@@ -181,6 +398,335 @@ async fn test_seen_blobs_tracking() {
     assert_eq!(seen.len(), 2); // Still 2, not 3
 }
 
+#[tokio::test]
+async fn test_seen_trees_roundtrip() {
+    let db = setup_db().await;
+    assert!(db.load_seen_trees().await.unwrap().is_empty());
+
+    // The same tree at two paths is two entries; repeats are ignored
+    let trees = vec![
+        TreePathRecord::new(test_oid(1), 0),
+        TreePathRecord::new(test_oid(1), 1),
+        TreePathRecord::new(test_oid(1), 0),
+    ];
+    db.save_seen_trees(&trees).await.unwrap();
+
+    let mut seen = db.load_seen_trees().await.unwrap();
+    seen.sort();
+    assert_eq!(seen, vec![(test_oid(1), 0), (test_oid(1), 1)]);
+}
+
+#[tokio::test]
+async fn test_path_dictionary() {
+    let db = setup_db().await;
+    assert!(db.load_paths().await.unwrap().is_empty());
+
+    // Bytes that aren't UTF-8 come back as they went in
+    let latin1 = b"docs/caf\xe9.txt";
+    db.save_paths(&[PathRecord::new(0, b"docs"), PathRecord::new(1, latin1)]).await.unwrap();
+    assert_eq!(db.path_count().await.unwrap(), 2);
+    assert_eq!(db.load_paths().await.unwrap(), vec![b"docs".to_vec(), latin1.to_vec()]);
+
+    // An id is never handed out twice
+    assert!(db.save_paths(&[PathRecord::new(1, b"src")]).await.is_err());
+    // A gap means the dictionary doesn't match the ids rows refer to
+    db.save_paths(&[PathRecord::new(5, b"src")]).await.unwrap();
+    assert!(db.load_paths().await.is_err());
+}
+
+#[tokio::test]
+async fn test_current_sizes_follow_head() {
+    let db = setup_db().await;
+
+    // Both versions were in HEAD when first scanned
+    let records = ScanRecords {
+        blobs: vec![
+            BlobRecord::new(test_oid(1), "file.txt", 100, 100),
+            BlobRecord::new(test_oid(2), "file.txt", 150, 150),
+            BlobRecord::new(test_oid(3), "old.txt", 40, 40),
+        ],
+        ..Default::default()
+    };
+    db.apply_scan_with_callback(&records, &[test_oid(9)], |_| {}, |_| {}).await.unwrap();
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(2), "file.txt", 150)]).await.unwrap();
+
+    let tree = db.load_tree().await.unwrap();
+    assert_eq!(tree.root().cumulative_size, 290);
+    assert_eq!(tree.find("file.txt").unwrap().current_size, 150);
+    assert_eq!(tree.find("old.txt").unwrap().current_size, 0);
+}
+
+/// Every directory's `get_dir_children` agrees with the loaded tree
+async fn assert_dirs_match_tree(db: &Database) {
+    let tree = db.load_tree().await.unwrap();
+    let mut dirs = vec![(String::new(), tree.root())];
+    while let Some((path, node)) = dirs.pop() {
+        let entries = db.get_dir_children(&path).await.unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        let expected: Vec<_> = node.children().map(|c| c.name()).collect();
+        assert_eq!(names, expected, "entries of '{}'", path);
+
+        for (entry, child) in entries.iter().zip(node.children()) {
+            let sizes = (entry.cumulative_size, entry.current_size, entry.blob_count, entry.deleted_size);
+            let expected = (child.cumulative_size, child.current_size, child.blob_count, child.deleted_cumulative_size());
+            assert_eq!(sizes, expected, "totals of '{}' in '{}'", entry.name, path);
+            assert_eq!(entry.is_dir, child.has_children());
+            if entry.is_dir {
+                let child_path = if path.is_empty() { entry.name.clone() } else { format!("{}/{}", path, entry.name) };
+                dirs.push((child_path, child));
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_dir_totals_follow_scans() {
+    let db = setup_db().await;
+    let records = ScanRecords {
+        blobs: vec![
+            BlobRecord::new(test_oid(1), "src/main.rs", 100, 100),
+            BlobRecord::new(test_oid(2), "src/util/fmt.rs", 50, 50),
+            BlobRecord::new(test_oid(3), "docs/guide.md", 30, 30),
+            BlobRecord::new(test_oid(4), "README.md", 10, 10),
+        ],
+        gitlinks: vec!["vendor/lib".into()],
+        ..Default::default()
+    };
+    db.apply_scan_with_callback(&records, &[test_oid(9)], |_| {}, |_| {}).await.unwrap();
+    let head = [
+        HeadBlobRecord::new(test_oid(1), "src/main.rs", 100),
+        HeadBlobRecord::new(test_oid(2), "src/util/fmt.rs", 50),
+        HeadBlobRecord::new(test_oid(4), "README.md", 10),
+    ];
+    db.replace_head_blobs(&head).await.unwrap();
+    assert_dirs_match_tree(&db).await;
+
+    let docs = db.get_dir_children("").await.unwrap().into_iter().find(|e| e.name == "docs").unwrap();
+    assert!(docs.is_dir);
+    assert_eq!(docs.deleted_size, 30);
+
+    // A later scan adds to some directories and changes HEAD below others;
+    // a file that became a directory counts as the directory
+    let records = ScanRecords {
+        blobs: vec![
+            BlobRecord::new(test_oid(5), "src/util/fmt.rs", 70, 70),
+            BlobRecord::new(test_oid(6), "README.md/nested.txt", 5, 5),
+        ],
+        ..Default::default()
+    };
+    db.apply_scan_with_callback(&records, &[test_oid(10)], |_| {}, |_| {}).await.unwrap();
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(5), "src/util/fmt.rs", 70)]).await.unwrap();
+    assert_dirs_match_tree(&db).await;
+
+    let src = db.get_dir_children("src").await.unwrap();
+    assert_eq!(src[1].name, "util");
+    assert_eq!((src[1].cumulative_size, src[1].current_size), (120, 70));
+}
+
+#[tokio::test]
+async fn test_path_entry_matches_dir_children() {
+    let db = setup_db().await;
+    let records = ScanRecords {
+        blobs: vec![
+            BlobRecord::new(test_oid(1), "src/main.rs", 100, 100),
+            BlobRecord::new(test_oid(2), "src/old.rs", 40, 40),
+        ],
+        ..Default::default()
+    };
+    db.apply_scan_with_callback(&records, &[test_oid(10)], |_| {}, |_| {}).await.unwrap();
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(1), "src/main.rs", 100)]).await.unwrap();
+
+    let src = db.get_path_entry("src").await.unwrap().unwrap();
+    assert_eq!(Some(&src), db.get_dir_children("").await.unwrap().first());
+    assert!(src.is_dir);
+    assert_eq!((src.cumulative_size, src.current_size, src.deleted_size), (140, 100, 40));
+
+    let old = db.get_path_entry("src/old.rs").await.unwrap().unwrap();
+    assert_eq!((old.name.as_str(), old.is_dir, old.deleted_size), ("old.rs", false, 40));
+    assert_eq!(db.get_path_entry("src/missing.rs").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_largest_file_and_path_search() {
+    let db = setup_db().await;
+    let blobs = vec![
+        BlobRecord::new(test_oid(1), "src/main.rs", 100, 100),
+        BlobRecord::new(test_oid(2), "src/Old.RS", 400, 0),
+        BlobRecord::new(test_oid(3), "srcs/big.bin", 900, 900),
+        BlobRecord::new(test_oid(4), "README.md", 10, 10),
+    ];
+    db.save_blobs_with_callback(&blobs, |_| {}).await.unwrap();
+
+    // `srcs` sorts right after `src/` but isn't below it
+    assert_eq!(db.get_largest_file("", false).await.unwrap(), Some(("srcs/big.bin".into(), 900)));
+    assert_eq!(db.get_largest_file("src", false).await.unwrap(), Some(("src/Old.RS".into(), 400)));
+    assert_eq!(db.get_largest_file("srcs", true).await.unwrap(), None);
+
+    let matches = db.search_paths(".rs", false, false, 10).await.unwrap();
+    assert_eq!(matches, vec![("src/Old.RS".into(), 400, 0), ("src/main.rs".into(), 100, 100)]);
+    assert_eq!(db.search_paths(".RS", false, true, 10).await.unwrap(), vec![("src/Old.RS".into(), 400, 0)]);
+    assert_eq!(db.search_paths("src", true, false, 10).await.unwrap().len(), 1);
+    assert_eq!(db.search_paths("", false, false, 2).await.unwrap()[0].0, "srcs/big.bin");
+
+    // Size and extension predicates narrow the search in the index too
+    let paths = |matches: Vec<(String, u64, u64)>| matches.into_iter().map(|m| m.0).collect::<Vec<_>>();
+    assert_eq!(paths(db.search_paths(">100 <=900", false, false, 10).await.unwrap()), vec!["srcs/big.bin", "src/Old.RS"]);
+    assert_eq!(paths(db.search_paths("ext:rs >=100", false, false, 10).await.unwrap()), vec!["src/Old.RS", "src/main.rs"]);
+    assert_eq!(paths(db.search_paths("src ext:bin", false, false, 10).await.unwrap()), vec!["srcs/big.bin"]);
+}
+
+#[tokio::test]
+async fn test_entry_details() {
+    let db = setup_db().await;
+    let blobs = vec![
+        BlobRecord::new(test_oid(1), "src/main.rs", 100, 100),
+        BlobRecord::new(test_oid(2), "src/lib.rs", 300, 0),
+        BlobRecord::new(test_oid(3), "src/logo.png", 350, 350),
+        BlobRecord::new(test_oid(4), "srcs/big.bin", 900, 900),
+    ];
+    db.save_blobs_with_callback(&blobs, |_| {}).await.unwrap();
+
+    let details = db.get_entry_details("src", false, 2).await.unwrap();
+    assert_eq!(details.extensions, vec![(".rs".to_string(), 400), (".png".to_string(), 350)]);
+    assert_eq!(details.largest, vec![("src/logo.png".to_string(), 350), ("src/lib.rs".to_string(), 300)]);
+
+    let deleted = db.get_entry_details("src", true, 5).await.unwrap();
+    assert_eq!(deleted.extensions, vec![(".rs".to_string(), 300)]);
+    assert_eq!(deleted.largest, vec![("src/lib.rs".to_string(), 300)]);
+}
+
+#[tokio::test]
+async fn test_dirs_named() {
+    let db = setup_db().await;
+    let blobs = vec![
+        BlobRecord::new(test_oid(1), "build/app.bin", 500, 0),
+        BlobRecord::new(test_oid(2), "web/__pycache__/x.pyc", 30, 30),
+        BlobRecord::new(test_oid(3), "web/xx__pycache__/y.py", 7, 7),
+        BlobRecord::new(test_oid(4), "rebuild/z.rs", 10, 10),
+    ];
+    db.save_blobs_with_callback(&blobs, |_| {}).await.unwrap();
+
+    let dirs = db.get_dirs_named(&["build", "__pycache__"]).await.unwrap();
+    assert_eq!(dirs, vec![
+        ("build".into(), 500, 0, 500),
+        ("web/__pycache__".into(), 30, 30, 0),
+    ]);
+    assert!(db.get_dirs_named(&[]).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_vendored_paths() {
+    let db = setup_db().await;
+    let blobs = vec![
+        BlobRecord::new(test_oid(1), "web/node_modules/a/index.js", 400, 0),
+        BlobRecord::new(test_oid(2), "web/node_modules/b/index.js", 100, 100),
+        BlobRecord::new(test_oid(3), "web/app.min.js", 50, 50),
+        BlobRecord::new(test_oid(4), "web/app.js", 20, 20),
+        BlobRecord::new(test_oid(5), "Cargo.lock", 30, 30),
+        BlobRecord::new(test_oid(6), "src/main.rs", 10, 10),
+    ];
+    db.save_blobs_with_callback(&blobs, |_| {}).await.unwrap();
+
+    let share = |rule: &str, files, cumulative, current| VendoredShare { rule: rule.into(), files, cumulative, current };
+    assert_eq!(db.get_vendored_shares(&PathFilter::default()).await.unwrap(), vec![
+        share("node_modules/", 2, 500, 100),
+        share("*.min.js", 1, 50, 50),
+        share("lockfiles", 1, 30, 30),
+    ]);
+
+    let sizes = |files, cumulative, deleted| VendoredSizes { files, cumulative, deleted };
+    assert_eq!(db.get_vendored_sizes("", &PathFilter::default()).await.unwrap(), vec![
+        ("Cargo.lock".to_string(), sizes(1, 30, 0)),
+        ("web".to_string(), sizes(3, 550, 400)),
+    ]);
+    let filter = PathFilter::new(&["*.min.js"]).unwrap();
+    assert_eq!(db.get_vendored_sizes("web", &filter).await.unwrap(), vec![
+        ("node_modules".to_string(), sizes(2, 500, 400)),
+    ]);
+}
+
+#[tokio::test]
+async fn test_old_sizes() {
+    let db = setup_db().await;
+    let blobs = vec![
+        BlobRecord::new(test_oid(1), "assets/logo.png", 400, 0),
+        BlobRecord::new(test_oid(2), "assets/logo.png", 300, 300),
+        BlobRecord::new(test_oid(3), "assets/old/icon.png", 100, 0),
+        BlobRecord::new(test_oid(4), "src/main.rs", 50, 50),
+        BlobRecord::new(test_oid(5), "README.md", 20, 20),
+    ];
+    db.save_blobs_with_callback(&blobs, |_| {}).await.unwrap();
+    let meta = |id, size, path, date| BlobMetaRecord::new(test_oid(id), size, path, "dev", "dev@example.com", date);
+    let metadata = vec![
+        meta(1, 400, "assets/logo.png", 1000),
+        meta(2, 300, "assets/logo.png", 3000),
+        meta(3, 100, "assets/old/icon.png", 1500),
+        meta(4, 50, "src/main.rs", 1200),
+        meta(5, 20, "README.md", 5000),
+    ];
+    db.save_blob_metadata_with_callback(&metadata, |_| {}).await.unwrap();
+
+    let old = |cumulative, deleted| OldSizes { cumulative, deleted };
+    assert_eq!(db.get_old_sizes("", 2000, &PathFilter::default()).await.unwrap(), vec![
+        ("assets".to_string(), old(500, 100)),
+        ("src".to_string(), old(50, 0)),
+    ]);
+    assert_eq!(db.get_old_sizes("assets", 2000, &PathFilter::default()).await.unwrap(), vec![
+        ("logo.png".to_string(), old(400, 0)),
+        ("old".to_string(), old(100, 100)),
+    ]);
+    let filter = PathFilter::new(&["old/"]).unwrap();
+    assert_eq!(db.get_old_sizes("assets", 2000, &filter).await.unwrap(), vec![("logo.png".to_string(), old(400, 0))]);
+    assert!(db.get_old_sizes("", 1000, &PathFilter::default()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_scan_diagnostics() {
+    let db = setup_db().await;
+
+    let records = ScanRecords {
+        skipped: vec![
+            SkippedRecord::new(test_oid(2), "tree", "lost", "object not found"),
+            SkippedRecord::new(test_oid(1), "commit", "", "object not found"),
+        ],
+        ..Default::default()
+    };
+    db.apply_scan_with_callback(&records, &[], |_| {}, |_| {}).await.unwrap();
+
+    let kinds: Vec<_> = db.get_scan_diagnostics().await.unwrap().into_iter().map(|d| d.kind).collect();
+    assert_eq!(kinds, vec!["commit", "tree"]);
+
+    // The commit reads fine on a later scan
+    db.apply_scan_with_callback(&ScanRecords::default(), &[test_oid(1)], |_| {}, |_| {}).await.unwrap();
+    let remaining = db.get_scan_diagnostics().await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].oid, hex::encode(test_oid(2)));
+    assert_eq!(remaining[0].path, "lost");
+}
+
+#[tokio::test]
+async fn test_apply_scan_beyond_one_statement() {
+    let db = setup_db().await;
+
+    // More rows than SQLite binds variables for in one multi-row insert
+    let oids: Vec<[u8; 20]> = (0..6000u32)
+        .map(|i| {
+            let mut oid = [0u8; 20];
+            oid[..4].copy_from_slice(&i.to_be_bytes());
+            oid
+        })
+        .collect();
+    let records = ScanRecords {
+        blobs: oids.iter().enumerate().map(|(i, oid)| BlobRecord::new(*oid, format!("dir/f{}.txt", i), 10, 0)).collect(),
+        gitlinks: (0..6000).map(|i| format!("mods/m{}", i).into()).collect(),
+        ..Default::default()
+    };
+    db.apply_scan_with_callback(&records, &[], |_| {}, |_| {}).await.unwrap();
+
+    assert_eq!(db.get_dir_children("dir").await.unwrap().len(), 6000);
+    assert_eq!(db.get_dir_children("mods").await.unwrap().len(), 6000);
+}
+
 #[tokio::test]
 async fn test_commit_scanned_tracking() {
     let db = setup_db().await;
@@ -209,3 +755,411 @@ async fn test_commit_scanned_tracking() {
         .unwrap();
     assert!(db.is_commit_scanned(&commit1).await);
 }
+
+#[tokio::test]
+async fn test_lookup_blob_paths_and_head() {
+    let db = setup_db().await;
+
+    let mut oid = test_oid(0xab);
+    oid[1] = 0xcd;
+
+    db.save_blob_metadata_with_callback(
+        &[BlobMetaRecord::new(oid, 4096, "old/video.mp4", "alice", "alice@example.com", 1700000000)],
+        |_| {},
+    )
+    .await
+    .unwrap();
+    save_blob_paths(&db, &[(oid, "old/video.mp4"), (oid, "assets/video.mp4")]).await;
+    db.replace_head_blobs(&[HeadBlobRecord::new(oid, "assets/video.mp4", 4096)])
+        .await
+        .unwrap();
+
+    // Abbreviated, mixed-case prefix resolves to the blob
+    let blob = db.lookup_blob("ABcd").await.unwrap().expect("blob found");
+    assert_eq!(blob.size, 4096);
+    assert_eq!(blob.path, "old/video.mp4");
+    assert_eq!(blob.first_author, "alice");
+    assert!(blob.in_head());
+
+    let paths: Vec<_> = blob.occurrences.iter().map(|o| (o.path.as_str(), o.in_head)).collect();
+    assert_eq!(paths, vec![("assets/video.mp4", true), ("old/video.mp4", false)]);
+
+    // HEAD snapshot is replaced, not merged
+    db.replace_head_blobs(&[]).await.unwrap();
+    let blob = db.lookup_blob(&hex::encode(oid)).await.unwrap().unwrap();
+    assert!(!blob.in_head());
+}
+
+#[tokio::test]
+async fn test_path_blob() {
+    let db = setup_db().await;
+
+    let metadata = vec![
+        BlobMetaRecord::new(test_oid(1), 100, "notes.txt", "author", "author@example.com", 1000),
+        BlobMetaRecord::new(test_oid(2), 200, "notes.txt", "author", "author@example.com", 2000),
+        BlobMetaRecord::new(test_oid(3), 300, "kept.txt", "author", "author@example.com", 3000),
+    ];
+    db.save_blob_metadata_with_callback(&metadata, |_| {}).await.unwrap();
+    save_blob_paths(&db, &[(test_oid(1), "notes.txt"), (test_oid(2), "notes.txt"), (test_oid(3), "kept.txt")]).await;
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(3), "kept.txt", 300)]).await.unwrap();
+
+    assert_eq!(db.get_path_blob("kept.txt").await.unwrap(), Some(test_oid(3).to_vec()));
+    // Gone from HEAD: the version committed last
+    assert_eq!(db.get_path_blob("notes.txt").await.unwrap(), Some(test_oid(2).to_vec()));
+    assert_eq!(db.get_path_blob("missing.txt").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_author_totals() {
+    let db = setup_db().await;
+    db.save_blob_metadata_with_callback(
+        &[
+            BlobMetaRecord::new(test_oid(1), 1000, "vendor/lib.js", "alice", "alice@corp.example", 1700000000),
+            BlobMetaRecord::new(test_oid(2), 300, "src/a.rs", "alice", "alice@corp.example", 1700000000),
+            BlobMetaRecord::new(test_oid(3), 600, "src/b.rs", "bob", "bob@Corp.Example", 1700000000),
+            BlobMetaRecord::new(test_oid(4), 800, "src/c.rs", "carol", "carol@home.example", 1700000000),
+        ],
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    let by_name = db.get_author_totals(AuthorGrouping::Name, 2, &PathFilter::default()).await.unwrap();
+    let found: Vec<_> = by_name.iter().map(|a| (a.author.as_str(), a.blob_count, a.size)).collect();
+    assert_eq!(found, vec![("alice", 2, 1300), ("carol", 1, 800)]);
+
+    let filter = PathFilter::new(&["vendor/"]).unwrap();
+    let by_domain = db.get_author_totals(AuthorGrouping::Domain, 10, &filter).await.unwrap();
+    let found: Vec<_> = by_domain.iter().map(|a| (a.author.as_str(), a.blob_count, a.size)).collect();
+    assert_eq!(found, vec![("corp.example", 2, 900), ("home.example", 1, 800)]);
+}
+
+#[tokio::test]
+async fn test_duplicate_blobs() {
+    let db = setup_db().await;
+    db.save_blob_metadata_with_callback(
+        &[
+            BlobMetaRecord::new(test_oid(1), 1000, "lib/a.js", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(2), 300, "icon.png", "bob", "bob@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(3), 5000, "only.bin", "bob", "bob@example.com", 1700000000),
+        ],
+        |_| {},
+    )
+    .await
+    .unwrap();
+    save_blob_paths(
+        &db,
+        &[
+            (test_oid(1), "lib/a.js"),
+            (test_oid(1), "vendor/lib/a.js"),
+            (test_oid(2), "icon.png"),
+            (test_oid(2), "web/icon.png"),
+            (test_oid(2), "docs/icon.png"),
+            (test_oid(3), "only.bin"),
+        ],
+    )
+    .await;
+    db.replace_head_blobs(&[
+        HeadBlobRecord::new(test_oid(1), "lib/a.js", 1000),
+        HeadBlobRecord::new(test_oid(1), "vendor/lib/a.js", 1000),
+    ])
+    .await
+    .unwrap();
+
+    // 1000 bytes once more outranks 300 bytes twice more
+    let duplicates = db.get_duplicate_blobs(10, &PathFilter::default()).await.unwrap();
+    let found: Vec<_> = duplicates.iter().map(|d| (d.size, d.paths.len(), d.head_copies())).collect();
+    assert_eq!(found, vec![(1000, 2, 2), (300, 3, 0)]);
+    assert_eq!(duplicates[0].wasted(), 1000);
+    assert_eq!(duplicates[1].paths[0].path, "docs/icon.png");
+
+    // Without its vendored copy the first blob is no duplicate
+    let filter = PathFilter::new(&["vendor/"]).unwrap();
+    let duplicates = db.get_duplicate_blobs(10, &filter).await.unwrap();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].size, 300);
+}
+
+#[tokio::test]
+async fn test_delta_efficiency() {
+    let db = setup_db().await;
+    db.save_blob_metadata_with_callback(
+        &[
+            // One full version of the schema, then deltas against it
+            BlobMetaRecord::new(test_oid(1), 1000, "schema.sql", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(2), 40, "schema.sql", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(3), 60, "schema.sql", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(4), 900, "vault.gpg", "bob", "bob@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(5), 950, "vault.gpg", "bob", "bob@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(6), 1000, "vault.gpg", "bob", "bob@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(7), 5000, "once.bin", "bob", "bob@example.com", 1700000000),
+        ],
+        |_| {},
+    )
+    .await
+    .unwrap();
+    let paths: Vec<_> = [(1, "schema.sql"), (2, "schema.sql"), (3, "schema.sql"), (4, "vault.gpg"), (5, "vault.gpg")]
+        .into_iter()
+        .chain([(6, "vault.gpg"), (6, "old/vault.gpg"), (4, "old/vault.gpg"), (7, "once.bin")])
+        .map(|(id, path)| (test_oid(id), path))
+        .collect();
+    save_blob_paths(&db, &paths).await;
+
+    // A single version says nothing about deltas
+    let delta = db.get_delta_efficiency(10, &PathFilter::default()).await.unwrap();
+    let found: Vec<_> = delta.iter().map(|d| (d.path.as_str(), d.versions, d.stored, d.largest)).collect();
+    assert_eq!(found, vec![("vault.gpg", 3, 2850, 1000), ("old/vault.gpg", 2, 1900, 1000), ("schema.sql", 3, 1100, 1000)]);
+    assert!(delta[0].is_delta_hostile());
+    assert!(!delta[2].is_delta_hostile());
+
+    let filter = PathFilter::new(&["old/"]).unwrap();
+    let delta = db.get_delta_efficiency(1, &filter).await.unwrap();
+    assert_eq!(delta.len(), 1);
+    assert_eq!(delta[0].path, "vault.gpg");
+}
+
+#[tokio::test]
+async fn test_content_kinds() {
+    let db = setup_db().await;
+    let blobs = vec![
+        BlobRecord::new(test_oid(1), "assets/logo.png", 5000, 5000),
+        BlobRecord::new(test_oid(2), "assets/icon.png", 1000, 0),
+        BlobRecord::new(test_oid(3), "src/main.rs", 800, 800),
+        BlobRecord::new(test_oid(4), "bin/tool", 3000, 3000),
+        BlobRecord::new(test_oid(5), "Makefile", 200, 200),
+    ];
+    db.save_blobs_with_callback(&blobs, |_| {}).await.unwrap();
+    db.save_blob_metadata_with_callback(
+        &[
+            BlobMetaRecord::new(test_oid(1), 5000, "assets/logo.png", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(2), 1000, "assets/icon.png", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(3), 800, "src/main.rs", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(4), 3000, "bin/tool", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(5), 200, "Makefile", "alice", "alice@example.com", 1700000000),
+        ],
+        |_| {},
+    )
+    .await
+    .unwrap();
+    let paths: Vec<_> = blobs.iter().map(|b| (b.oid, b.path.as_ref())).collect();
+    save_blob_paths(&db, &paths).await;
+
+    // Extensions are sampled smallest blob first; files without one go path by path
+    let unclassified = db.get_unclassified_content(1).await.unwrap();
+    let found: Vec<_> = unclassified.iter().map(|(ext, path, oids)| (ext.as_str(), path.as_str(), oids.clone())).collect();
+    assert_eq!(
+        found,
+        vec![
+            (".png", "", vec![test_oid(2).to_vec()]),
+            (".rs", "", vec![test_oid(3).to_vec()]),
+            ("(no ext)", "Makefile", vec![test_oid(5).to_vec()]),
+            ("(no ext)", "bin/tool", vec![test_oid(4).to_vec()]),
+        ]
+    );
+
+    // Unclassified content counts as text
+    assert_eq!(db.get_binary_size("", &PathFilter::default()).await.unwrap(), 0);
+
+    let kinds = [(".png", "", true), (".rs", "", false), ("(no ext)", "bin/tool", true), ("(no ext)", "Makefile", false)];
+    let kinds: Vec<_> = kinds.iter().map(|(e, p, b)| (e.to_string(), p.to_string(), *b)).collect();
+    db.save_content_kinds(&kinds).await.unwrap();
+    assert!(db.get_unclassified_content(1).await.unwrap().is_empty());
+
+    let stats = db.get_extension_stats(&PathFilter::default()).await.unwrap();
+    let binary: Vec<_> = stats.iter().map(|(ext, s)| (ext.as_str(), s.binary_size)).collect();
+    assert_eq!(binary, vec![("(no ext)", 3000), (".png", 6000), (".rs", 0)]);
+
+    assert_eq!(db.get_binary_size("", &PathFilter::default()).await.unwrap(), 9000);
+    assert_eq!(db.get_binary_size("assets", &PathFilter::default()).await.unwrap(), 6000);
+    let filter = PathFilter::new(&["assets/icon.png"]).unwrap();
+    assert_eq!(db.get_binary_size("assets", &filter).await.unwrap(), 5000);
+}
+
+#[tokio::test]
+async fn test_lookup_blob_errors() {
+    let db = setup_db().await;
+
+    // Two OIDs sharing a long prefix
+    let mut other = test_oid(0x11);
+    other[19] = 0x01;
+
+    db.save_blob_metadata_with_callback(
+        &[
+            BlobMetaRecord::new(test_oid(0x11), 1, "a", "a", "a@example.com", 0),
+            BlobMetaRecord::new(other, 1, "b", "b", "b@example.com", 0),
+        ],
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    assert!(db.lookup_blob("xyz1").await.is_err(), "non-hex input");
+    assert!(db.lookup_blob("11").await.is_err(), "too short");
+    assert!(db.lookup_blob("1100").await.is_err(), "ambiguous prefix");
+    assert!(db.lookup_blob("ffff").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_bookmarks_survive_index_rebuild() {
+    let db = setup_db().await;
+    db.add_bookmark("third_party").await.unwrap();
+    db.add_bookmark("assets").await.unwrap();
+    db.add_bookmark("assets").await.unwrap();
+    assert_eq!(db.get_bookmarks().await.unwrap(), vec!["assets", "third_party"]);
+
+    db.clear_index().await.unwrap();
+    assert_eq!(db.get_bookmarks().await.unwrap(), vec!["assets", "third_party"]);
+
+    db.remove_bookmark("assets").await.unwrap();
+    assert_eq!(db.get_bookmarks().await.unwrap(), vec!["third_party"]);
+}
+
+#[tokio::test]
+async fn test_size_snapshots_survive_rebuild_and_prune() {
+    let db = setup_db().await;
+    let entry = |name: &str, size| SnapshotEntry {
+        kind: SnapshotEntryKind::Directory,
+        name: name.to_string(),
+        cumulative_size: size,
+        current_size: size,
+    };
+    for (day, size) in [(1, 100), (2, 150), (3, 400)] {
+        let snapshot = SizeSnapshot {
+            taken_at: day * 86400,
+            cumulative_size: size,
+            entries: vec![entry("assets", size / 2), entry("src", size / 4)],
+            ..Default::default()
+        };
+        db.add_size_snapshot(&snapshot).await.unwrap();
+    }
+    db.add_size_snapshot(&SizeSnapshot { taken_at: 86400, scope: "assets".into(), ..Default::default() }).await.unwrap();
+
+    db.clear_index().await.unwrap();
+    let sizes: Vec<u64> = db.get_size_snapshots("").await.unwrap().iter().map(|s| s.cumulative_size).collect();
+    assert_eq!(sizes, [100, 150, 400]);
+
+    assert_eq!(db.prune_size_snapshots(2 * 86400).await.unwrap(), 2);
+    let kept = db.get_size_snapshots("").await.unwrap();
+    assert_eq!(kept.len(), 2);
+    assert!(db.get_size_snapshots("assets").await.unwrap().is_empty());
+    assert!(db.get_size_snapshot_entries("", 86400).await.unwrap().is_empty());
+    assert_eq!(db.get_size_snapshot_entries("", 3 * 86400).await.unwrap(), [entry("assets", 200), entry("src", 100)]);
+}
+
+#[tokio::test]
+async fn test_reads_proceed_while_another_connection_writes() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("index.db");
+    let tuning = DbTuning { cache_mb: 8, mmap_mb: 16, wal_autocheckpoint: 100, read_connections: 2 };
+    let db = Database::with_tuning(path.to_str().unwrap(), &tuning).await.unwrap();
+    db.init_schema().await.unwrap();
+    db.save_blob_metadata_with_callback(&[BlobMetaRecord::new(test_oid(1), 100, "a.bin", "Dev", "dev@example.com", 0)], |_| {})
+        .await
+        .unwrap();
+
+    // A scan in another process holds the write lock mid-transaction
+    let mut writer = sqlx::SqliteConnection::connect(&format!("sqlite:{}", path.display())).await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut writer).await.unwrap();
+    sqlx::query("DELETE FROM blobs").execute(&mut writer).await.unwrap();
+
+    let blobs = db.get_top_blobs(10).await.unwrap();
+    assert_eq!(blobs.len(), 1, "readers see the last committed state");
+
+    sqlx::query("COMMIT").execute(&mut writer).await.unwrap();
+    assert!(db.get_top_blobs(10).await.unwrap().is_empty());
+
+    // Without read connections everything goes through the writer
+    let shared = DbTuning { read_connections: 0, ..DbTuning::default() };
+    let db = Database::with_tuning(path.to_str().unwrap(), &shared).await.unwrap();
+    db.add_bookmark("assets").await.unwrap();
+    assert_eq!(db.get_bookmarks().await.unwrap(), vec!["assets"]);
+}
+
+#[tokio::test]
+async fn test_snapshot_roundtrip() {
+    let db = setup_db().await;
+    let records = ScanRecords {
+        blobs: vec![
+            BlobRecord::new(test_oid(1), "assets/logo.png", 5000, 5000),
+            BlobRecord::new(test_oid(2), "old/video.mp4", 9000, 0),
+        ],
+        metadata: vec![
+            BlobMetaRecord::new(test_oid(1), 5000, "assets/logo.png", "alice", "alice@example.com", 1700000000),
+            BlobMetaRecord::new(test_oid(2), 9000, "old/video.mp4", "bob", "bob@example.com", 1600000000),
+        ],
+        paths: vec![PathRecord::new(0, b"assets"), PathRecord::new(1, b"old/video.mp4")],
+        blob_paths: vec![BlobPathRecord::new(test_oid(2), 1)],
+        seen_trees: vec![TreePathRecord::new(test_oid(7), 0)],
+        skipped: vec![SkippedRecord::new(test_oid(8), "tree", "lost", "object not found")],
+        ..Default::default()
+    };
+    db.apply_scan_with_callback(&records, &[test_oid(9)], |_| {}, |_| {}).await.unwrap();
+    db.replace_head_blobs(&[HeadBlobRecord::new(test_oid(1), "assets/logo.png", 5000)]).await.unwrap();
+    db.set_metadata("head_oid", "abc123").await.unwrap();
+    db.add_bookmark("assets").await.unwrap();
+
+    let mut snapshot = Vec::new();
+    let rows = db.export_snapshot(&mut snapshot).await.unwrap();
+    assert!(rows > 0);
+    assert_eq!(&snapshot[..2], &[0x1f, 0x8b], "gzip-compressed");
+
+    // Stale data in the target is replaced; its bookmarks are kept
+    let target = setup_db().await;
+    target
+        .apply_scan_with_callback(
+            &ScanRecords { blobs: vec![BlobRecord::new(test_oid(3), "stale.txt", 1, 1)], ..Default::default() },
+            &[],
+            |_| {},
+            |_| {},
+        )
+        .await
+        .unwrap();
+    target.add_bookmark("docs").await.unwrap();
+    assert_eq!(target.import_snapshot(snapshot.as_slice()).await.unwrap(), rows);
+
+    let tree = target.load_tree().await.unwrap();
+    assert_eq!(tree.root().cumulative_size, 14000);
+    assert_eq!(tree.root().current_size, 5000);
+    assert!(tree.find("stale.txt").is_none());
+    let deleted = target.get_top_blobs_not_in_head(10).await.unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].first_author, "bob");
+    assert!(target.is_commit_scanned(&test_oid(9)).await);
+    assert_eq!(target.load_seen_trees().await.unwrap(), vec![(test_oid(7), 0)]);
+    assert_eq!(target.load_paths().await.unwrap(), vec![b"assets".to_vec(), b"old/video.mp4".to_vec()]);
+    assert_eq!(target.lookup_blob(&hex::encode(test_oid(2))).await.unwrap().unwrap().occurrences.len(), 1);
+    assert_eq!(target.get_scan_diagnostics().await.unwrap().len(), 1);
+    assert_eq!(target.get_metadata("head_oid").await.as_deref(), Some("abc123"));
+    assert_eq!(target.get_metadata("schema_version").await.as_deref(), Some(SCHEMA_VERSION));
+    assert_eq!(target.get_bookmarks().await.unwrap(), vec!["assets", "docs"]);
+}
+
+#[tokio::test]
+async fn test_snapshot_rejects_other_schema() {
+    use std::io::Write;
+
+    let snapshot = |header: String| {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        writeln!(encoder, "{}", header).unwrap();
+        encoder.finish().unwrap()
+    };
+    let db = setup_db().await;
+    db.set_metadata("head_oid", "abc123").await.unwrap();
+
+    let old_schema = snapshot(format!(r#"{{"format":"{}","version":1,"schema":"1"}}"#, SNAPSHOT_FORMAT));
+    let err = db.import_snapshot(old_schema.as_slice()).await.unwrap_err();
+    assert!(err.to_string().contains("index schema 1"), "{}", err);
+
+    let other_format = snapshot(format!(r#"{{"format":"other","version":1,"schema":"{}"}}"#, SCHEMA_VERSION));
+    assert!(db.import_snapshot(other_format.as_slice()).await.is_err());
+    assert!(db.import_snapshot(&b"not gzip"[..]).await.is_err());
+
+    // A truncated table leaves the index untouched
+    let truncated = snapshot(format!(
+        "{{\"format\":\"{}\",\"version\":1,\"schema\":\"{}\"}}\n{{\"table\":\"bookmarks\",\"columns\":[\"path\"],\"rows\":2}}\n[\"a\"]",
+        SNAPSHOT_FORMAT, SCHEMA_VERSION
+    ));
+    assert!(db.import_snapshot(truncated.as_slice()).await.is_err());
+    assert_eq!(db.get_metadata("head_oid").await.as_deref(), Some("abc123"));
+    assert!(db.get_bookmarks().await.unwrap().is_empty());
+}
@@ -112,7 +112,7 @@ async fn test_large_blobs_from_scan() {
     let db = create_db_in_dir(&dir).await;
     let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
     let tree = scanner.scan(&db).await.unwrap();
-    let total_cumulative = tree.cumulative_size;
+    let total_cumulative = tree.root().cumulative_size;
 
     // Get large blobs
     let blobs = db.get_top_blobs(10).await.unwrap();
@@ -132,3 +132,29 @@ async fn test_large_blobs_from_scan() {
     // Total blob size should be calculated
     assert!(vm.total_blob_size() > 0);
 }
+
+#[tokio::test]
+async fn test_copied_index_opens_without_repository() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("assets/logo.png", b"not really a png")], "Add logo");
+
+    let db = create_db_in_dir(&dir).await;
+    let scanned = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    db.add_bookmark("assets").await.unwrap();
+    db.close().await;
+
+    // Only the database file travels, as from a build server
+    let elsewhere = TempDir::new().unwrap();
+    let copy = elsewhere.path().join("copied.db");
+    std::fs::copy(dir.path().join("test.db"), &copy).unwrap();
+    drop(dir);
+
+    let db = Database::open_read_only(copy.to_str().unwrap()).await.unwrap();
+    assert!(db.is_read_only());
+    let tree = db.load_tree().await.unwrap();
+    assert_eq!(tree.root().cumulative_size, scanned.root().cumulative_size);
+    assert!(tree.find("assets").unwrap().find("logo.png").is_some());
+    assert_eq!(db.get_bookmarks().await.unwrap(), vec!["assets"]);
+    assert_eq!(db.get_top_blobs(10).await.unwrap().len(), 1);
+    assert!(db.add_bookmark("src").await.is_err());
+}
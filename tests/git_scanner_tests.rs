@@ -3,7 +3,15 @@
 
 mod common;
 
-use repodiet::repository::{Database, GitScanner};
+use repodiet::model::{AuthorGrouping, AuthorTotals, CodeOwners, EntryKind, NodeRef, PreviewContent, Tree};
+use repodiet::repository::{
+    branch_blobs, classify_blobs, plan_restore, preview_blob, read_codeowners, restore_file, CancelToken, Checkout, Database, GitScanner, MemoryStore, ProgressHandle, ProgressReporter,
+    RevRange, ScanCancelled, ScanOptions, ScanStore,
+};
+use repodiet::synthetic::{RepoShape, SyntheticRepo};
+use repodiet::util::PathFilter;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use tempfile::TempDir;
 
 /// Create a test database in a temp directory
@@ -14,6 +22,31 @@ async fn create_db_in_dir(dir: &TempDir) -> Database {
     db
 }
 
+/// Scan `repo_path` into a fresh database
+async fn scan_fresh(repo_path: &std::path::Path) -> Tree {
+    let dir = TempDir::new().unwrap();
+    let db = create_db_in_dir(&dir).await;
+    GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap()
+}
+
+/// Current size of every file in the tree, by path
+fn current_sizes(tree: &Tree) -> BTreeMap<String, u64> {
+    fn walk(node: NodeRef<'_>, prefix: &str, out: &mut BTreeMap<String, u64>) {
+        for child in node.children() {
+            let name = child.name();
+            let path = if prefix.is_empty() { name.to_string() } else { format!("{}/{}", prefix, name) };
+            if !child.has_children() {
+                out.insert(path, child.current_size);
+            } else {
+                walk(child, &path, out);
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(tree.root(), "", &mut out);
+    out
+}
+
 #[tokio::test]
 async fn test_scan_empty_repo() {
     let (dir, repo_path, _repo) = common::create_test_repo();
@@ -31,8 +64,8 @@ async fn test_scan_empty_repo() {
     let tree = scanner.scan(&db).await.unwrap();
 
     // Root should exist but have no children (empty tree)
-    assert_eq!(tree.name, "(root)");
-    assert!(tree.children.is_empty() || tree.cumulative_size == 0);
+    assert_eq!(tree.root().name(), "(root)");
+    assert!(tree.is_empty() || tree.root().cumulative_size == 0);
 }
 
 #[tokio::test]
@@ -52,8 +85,8 @@ async fn test_scan_single_commit() {
     let tree = scanner.scan(&db).await.unwrap();
 
     // Should have hello.txt
-    assert!(tree.children.contains_key("hello.txt"));
-    let hello = tree.children.get("hello.txt").unwrap();
+    assert!(tree.find("hello.txt").is_some());
+    let hello = tree.find("hello.txt").unwrap();
     assert!(hello.cumulative_size > 0);
     assert!(hello.current_size > 0); // File exists in HEAD
 }
@@ -74,8 +107,8 @@ async fn test_scan_multiple_commits() {
     let tree = scanner.scan(&db).await.unwrap();
 
     // File should exist
-    assert!(tree.children.contains_key("file.txt"));
-    let file = tree.children.get("file.txt").unwrap();
+    assert!(tree.find("file.txt").is_some());
+    let file = tree.find("file.txt").unwrap();
 
     // Should have cumulative size from both versions
     assert!(file.cumulative_size > 0);
@@ -101,8 +134,8 @@ async fn test_deleted_file_detection() {
     let tree = scanner.scan(&db).await.unwrap();
 
     // File should still appear in tree (was in history)
-    assert!(tree.children.contains_key("to_delete.txt"));
-    let file = tree.children.get("to_delete.txt").unwrap();
+    assert!(tree.find("to_delete.txt").is_some());
+    let file = tree.find("to_delete.txt").unwrap();
 
     // Cumulative size > 0 (was in history)
     assert!(file.cumulative_size > 0);
@@ -122,15 +155,824 @@ async fn test_incremental_scan() {
 
     // First scan
     let tree1 = scanner.scan(&db).await.unwrap();
-    assert!(tree1.children.contains_key("file1.txt"));
+    assert!(tree1.find("file1.txt").is_some());
 
     // Add another commit
     common::add_commit(&repo, &[("file2.txt", b"content2")], "Second");
 
     // Second scan should be incremental (only new commit)
     let tree2 = scanner.scan(&db).await.unwrap();
-    assert!(tree2.children.contains_key("file1.txt"));
-    assert!(tree2.children.contains_key("file2.txt"));
+    assert!(tree2.find("file1.txt").is_some());
+    assert!(tree2.find("file2.txt").is_some());
+}
+
+#[tokio::test]
+async fn test_incremental_scan_prunes_seen_trees() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    common::add_commit(
+        &repo,
+        &[("lib/a.txt", b"aaaa"), ("lib/deep/b.txt", b"bbbbbbbb")],
+        "Add lib",
+    );
+
+    let db = create_db_in_dir(&dir).await;
+    let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+    scanner.scan(&db).await.unwrap();
+
+    // The unchanged lib/ tree is recorded as fully indexed
+    let lib_oid = {
+        let head = repo.head().unwrap().peel_to_tree().unwrap();
+        head.get_path(std::path::Path::new("lib")).unwrap().id()
+    };
+    let seen = db.load_seen_trees().await.unwrap();
+    let paths = db.load_paths().await.unwrap();
+    assert!(seen.iter().any(|(oid, id)| oid == lib_oid.as_bytes() && paths[*id as usize] == b"lib"));
+
+    common::add_commit(&repo, &[("top.txt", b"top")], "Add top");
+    let incremental = scanner.scan(&db).await.unwrap();
+    // Paths from the first run keep their ids; only top.txt is new
+    assert_eq!(db.path_count().await.unwrap(), paths.len() + 1);
+
+    // Skipping lib/ on the second run loses nothing
+    let fresh_dir = TempDir::new().unwrap();
+    let fresh_db = create_db_in_dir(&fresh_dir).await;
+    let fresh = scanner.scan(&fresh_db).await.unwrap();
+
+    assert_eq!(incremental.root().cumulative_size, fresh.root().cumulative_size);
+    let lib = &incremental.find("lib").unwrap();
+    assert_eq!(lib.cumulative_size, fresh.find("lib").unwrap().cumulative_size);
+    assert!(lib.find("deep").unwrap().find("b.txt").is_some());
+    assert!(incremental.find("top.txt").is_some());
+}
+
+/// (cumulative size, current size, blob count, kind) of every file, by path
+fn file_stats(tree: &Tree) -> BTreeMap<String, (u64, u64, u64, EntryKind)> {
+    fn walk(node: NodeRef<'_>, prefix: &str, out: &mut BTreeMap<String, (u64, u64, u64, EntryKind)>) {
+        for child in node.children() {
+            let path = if prefix.is_empty() { child.name().to_string() } else { format!("{}/{}", prefix, child.name()) };
+            if child.has_children() {
+                walk(child, &path, out);
+            } else {
+                out.insert(path, (child.cumulative_size, child.current_size, child.blob_count, child.kind));
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(tree.root(), "", &mut out);
+    out
+}
+
+#[tokio::test]
+async fn test_commits_scanned_against_parent_match_full_walk() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    let base = common::commit_entries(
+        &repo,
+        &[
+            ("a/x.txt", 0o100644, b"x v1"),
+            ("a/y.sh", 0o100644, b"echo y"),
+            ("b/z.txt", 0o100644, b"zzzz"),
+            ("c/deep/w.txt", 0o100644, b"wwwwww"),
+        ],
+        &[],
+        "alice",
+        1_700_000_000,
+        "base",
+    );
+    // Content, mode and a directory turned into a file (and back)
+    let changed = common::commit_entries(
+        &repo,
+        &[
+            ("a/x.txt", 0o100644, b"x v2, longer"),
+            ("a/y.sh", 0o100755, b"echo y"),
+            ("b", 0o100644, b"b is a file now"),
+            ("c/deep/w.txt", 0o100644, b"wwwwww"),
+        ],
+        &[base],
+        "bob",
+        1_700_000_100,
+        "changed",
+    );
+    let restored = common::commit_entries(
+        &repo,
+        &[
+            ("a/x.txt", 0o100644, b"x v2, longer"),
+            ("a/new.txt", 0o100644, b"new"),
+            ("a/y.sh", 0o100755, b"echo y"),
+            ("b/z.txt", 0o100644, b"zzzz"),
+            ("c/deep/w.txt", 0o100644, b"wwwwww v2"),
+        ],
+        &[changed],
+        "carol",
+        1_700_000_200,
+        "restored",
+    );
+
+    let db = create_db_in_dir(&dir).await;
+    let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+    common::set_head(&repo, base);
+    scanner.scan(&db).await.unwrap();
+    common::set_head(&repo, restored);
+    let incremental = scanner.scan(&db).await.unwrap();
+
+    let fresh = scan_fresh(&repo_path).await;
+    assert_eq!(file_stats(&incremental), file_stats(&fresh));
+    assert_eq!(incremental.find("a/y.sh").unwrap().kind, EntryKind::Executable);
+    assert_eq!(incremental.find("b/z.txt").unwrap().blob_count, 1);
+    assert_eq!(incremental.find("c/deep/w.txt").unwrap().blob_count, 2);
+}
+
+#[tokio::test]
+async fn test_scan_written_in_batches_matches_one_batch() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("src/a.rs", b"fn a() {}"), ("docs/guide.md", b"# guide")], "init");
+    common::add_commit(&repo, &[("src/a.rs", b"fn a() { todo!() }"), ("src/b.rs", b"fn b() {}")], "grow");
+    common::remove_file_commit(&repo, "docs/guide.md", "drop docs");
+    common::add_commit(&repo, &[("assets/logo.png", b"\x89PNG not really")], "logo");
+
+    // A batch per commit, each with paths the previous ones never interned
+    let db = create_db_in_dir(&dir).await;
+    let batched = GitScanner::quiet(repo_path.to_str().unwrap())
+        .with_batch_rows(1)
+        .scan(&db)
+        .await
+        .unwrap();
+
+    let whole_dir = TempDir::new().unwrap();
+    let whole_db = create_db_in_dir(&whole_dir).await;
+    let whole = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&whole_db).await.unwrap();
+    assert_eq!(file_stats(&batched), file_stats(&whole));
+    assert_eq!(db.load_scanned_commits().await.len(), 4);
+    // Directory totals built up batch by batch
+    assert_eq!(db.get_dir_children("").await.unwrap(), whole_db.get_dir_children("").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_scan_from_tip_then_head_matches_full_scan() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("a.txt", b"one")], "first");
+    let base = common::add_commit(&repo, &[("a.txt", b"one, two")], "second");
+    common::add_commit(&repo, &[("b.txt", b"three")], "third");
+
+    let db = create_db_in_dir(&dir).await;
+    let as_of_base = GitScanner::quiet(repo_path.to_str().unwrap())
+        .with_tip("HEAD~1")
+        .scan(&db)
+        .await
+        .unwrap();
+    assert!(as_of_base.find("b.txt").is_none());
+    assert_eq!(db.get_metadata("head_oid").await, Some(base.to_string()));
+    assert_eq!(db.load_scanned_commits().await.len(), 2);
+
+    let caught_up = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert_eq!(db.load_scanned_commits().await.len(), 3);
+    assert_eq!(file_stats(&caught_up), file_stats(&scan_fresh(&repo_path).await));
+
+    let err = GitScanner::quiet(repo_path.to_str().unwrap()).with_tip("HEAD~5").scan(&db).await.unwrap_err();
+    assert!(err.to_string().contains("HEAD~5"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_max_commits_scan_is_partial_until_rescanned() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    common::add_commit(&repo, &[("file.txt", b"version 1")], "v1");
+    common::add_commit(&repo, &[("file.txt", b"version 2, longer")], "v2");
+    common::add_commit(&repo, &[("file.txt", b"version 3, longer still")], "v3");
+
+    let db = create_db_in_dir(&dir).await;
+    let limited = GitScanner::quiet(repo_path.to_str().unwrap()).with_options(ScanOptions {
+        max_commits: Some(1),
+        ..Default::default()
+    });
+    let partial = limited.scan(&db).await.unwrap();
+    assert_eq!(db.get_metadata("scan_limits").await.as_deref(), Some("1 newest commit"));
+
+    // Same HEAD, but the cached results don't cover full history
+    let full = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert!(full.root().cumulative_size > partial.root().cumulative_size);
+    assert_eq!(db.get_metadata("scan_limits").await.as_deref(), Some(""));
+}
+
+#[tokio::test]
+async fn test_limited_scan_after_full_scan_rebuilds() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("file.txt", b"version 1")], "v1");
+    common::add_commit(&repo, &[("old.txt", b"gone soon")], "old");
+    common::remove_file_commit(&repo, "old.txt", "drop old");
+    common::add_commit(&repo, &[("file.txt", b"version 2, longer")], "v2");
+
+    let db = create_db_in_dir(&dir).await;
+    GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+
+    let options = ScanOptions { max_commits: Some(1), ..Default::default() };
+    let limited = GitScanner::quiet(repo_path.to_str().unwrap()).with_options(options.clone());
+    let after_full = limited.scan(&db).await.unwrap();
+    let fresh_dir = TempDir::new().unwrap();
+    let fresh = limited.scan(&create_db_in_dir(&fresh_dir).await).await.unwrap();
+
+    // Not the full history's totals under a partial label
+    assert_eq!(file_stats(&after_full), file_stats(&fresh));
+    assert!(after_full.find("old.txt").is_none());
+    assert_eq!(db.load_scanned_commits().await.len(), 1);
+    assert_eq!(db.get_metadata("scan_limits").await.as_deref(), Some("1 newest commit"));
+}
+
+#[tokio::test]
+async fn test_path_scoped_scan() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    common::add_commit(
+        &repo,
+        &[("src/vendor/lib.bin", b"vendored library"), ("src/main.rs", b"fn main() {}"), ("docs/a.md", b"docs")],
+        "Initial",
+    );
+    common::add_commit(&repo, &[("src/vendor/lib.bin", b"vendored library, updated")], "Update");
+    common::add_commit(&repo, &[("src/vendored.txt", b"sibling, not inside")], "Sibling");
+
+    let db = create_db_in_dir(&dir).await;
+    let scoped = GitScanner::quiet(repo_path.to_str().unwrap()).with_options(ScanOptions {
+        path: Some("src/vendor".to_string()),
+        ..Default::default()
+    });
+    let tree = scoped.scan(&db).await.unwrap();
+    let paths: Vec<String> = current_sizes(&tree).into_keys().collect();
+    assert_eq!(paths, vec!["src/vendor/lib.bin"]);
+    // Both versions of the library, and nothing else, make up the totals
+    let vendor = tree.root().child("src").unwrap().child("vendor").unwrap();
+    assert_eq!(tree.root().cumulative_size, vendor.cumulative_size);
+    assert_eq!(db.get_metadata("scan_limits").await.as_deref(), Some("under src/vendor"));
+
+    // Dropping the scope rebuilds the index with everything
+    let full = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert_eq!(current_sizes(&full).len(), 4);
+    assert!(full.root().cumulative_size > tree.root().cumulative_size);
+}
+
+#[tokio::test]
+async fn test_codeowners_tally_over_index() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(
+        &repo,
+        &[
+            (".github/CODEOWNERS", b"* @org/platform\n/assets/ @org/design\n"),
+            ("assets/logo.psd", b"layered artwork"),
+            ("src/main.rs", b"fn main() {}"),
+        ],
+        "Initial",
+    );
+
+    let db = create_db_in_dir(&dir).await;
+    GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    let (source, text) = read_codeowners(repo_path.to_str().unwrap()).unwrap().unwrap();
+    assert_eq!(source, ".github/CODEOWNERS");
+
+    let files = db.get_file_sizes(&PathFilter::default()).await.unwrap();
+    let totals = CodeOwners::parse(&text).tally(files);
+    let owned: BTreeMap<&str, Vec<&str>> = totals
+        .iter()
+        .map(|t| (t.owner.as_str(), t.files.iter().map(|f| f.path.as_str()).collect()))
+        .collect();
+    assert_eq!(owned["@org/design"], ["assets/logo.psd"]);
+    assert_eq!(owned["@org/platform"].len(), 2);
+}
+
+#[test]
+fn test_preview_blob_reads_the_object_database() {
+    let (_dir, repo_path, repo) = common::create_test_repo();
+    let text = repo.blob(b"hello\nworld\n").unwrap();
+    let repo_path = repo_path.to_str().unwrap();
+
+    let preview = preview_blob(repo_path, text.as_bytes(), "notes.txt").unwrap();
+    assert_eq!(preview.oid, text.to_string());
+    assert_eq!(preview.size, 12);
+    assert_eq!(preview.content, PreviewContent::Text(vec!["hello".to_string(), "world".to_string()]));
+    assert!(!preview.truncated);
+
+    let err = preview_blob(repo_path, &[0x12; 20], "gone.bin").unwrap_err();
+    assert!(format!("{:#}", err).contains("pruned since the scan"));
+}
+
+#[test]
+fn test_classify_large_blobs_from_a_prefix() {
+    let (_dir, repo_path, repo) = common::create_test_repo();
+    let mut binary = vec![b'a'; 3 << 20];
+    binary[10] = 0;
+    let text = b"line\n".repeat(1 << 20);
+    let oids = [repo.blob(&binary).unwrap(), repo.blob(&text).unwrap()];
+    let ids: Vec<&[u8]> = oids.iter().map(|oid| oid.as_bytes()).collect();
+    let repo_path_str = repo_path.to_str().unwrap();
+
+    // Loose
+    assert_eq!(classify_blobs(repo_path_str, &ids).unwrap(), vec![Some(true), Some(false)]);
+
+    // Packed
+    let mut builder = repo.packbuilder().unwrap();
+    for oid in oids {
+        builder.insert_object(oid, None).unwrap();
+    }
+    builder.write(&repo_path.join(".git/objects/pack"), 0o644).unwrap();
+    for oid in oids {
+        let hex = oid.to_string();
+        std::fs::remove_file(repo_path.join(".git/objects").join(&hex[..2]).join(&hex[2..])).unwrap();
+    }
+    assert_eq!(classify_blobs(repo_path_str, &ids).unwrap(), vec![Some(true), Some(false)]);
+}
+
+#[test]
+fn test_restore_a_deleted_file() {
+    let (_dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("assets/old.bin", b"version 1")], "Add");
+    let kept = common::add_commit(&repo, &[("assets/old.bin", b"version 2")], "Update");
+    common::remove_file_commit(&repo, "assets/old.bin", "Delete");
+    let workdir = repo_path.clone();
+    let repo_path = repo_path.to_str().unwrap();
+
+    // The newest commit that still has the blob, not the one deleting it
+    let blob = repo.blob(b"version 2").unwrap();
+    let plan = plan_restore(repo_path, blob.as_bytes(), "assets/old.bin").unwrap();
+    assert_eq!(plan.commit, kept.to_string());
+    assert_eq!(plan.checkout, format!("git checkout {} -- assets/old.bin", kept));
+    assert!(!plan.overwrites);
+
+    let written = restore_file(repo_path, &plan.commit, "assets/old.bin").unwrap();
+    assert_eq!(written, workdir.join("assets/old.bin"));
+    assert_eq!(std::fs::read(&written).unwrap(), b"version 2");
+    assert!(plan_restore(repo_path, blob.as_bytes(), "assets/old.bin").unwrap().overwrites);
+
+    let never = repo.blob(b"never committed").unwrap();
+    assert!(plan_restore(repo_path, never.as_bytes(), "assets/old.bin").is_err());
+    assert!(restore_file(repo_path, &plan.commit, "../outside.bin").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_restore_does_not_follow_symlinks() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    let outside_dir = TempDir::new().unwrap();
+    let outside = outside_dir.path();
+    let old = common::commit_files(&repo, &[("assets/authorized_keys", b"ssh-ed25519 AAAA"), ("notes.txt", b"notes")], &[], "Dev", 1000, "Add");
+    // HEAD has `assets` as a symlink out of the repository
+    let link = outside.to_str().unwrap().as_bytes();
+    let head = common::commit_entries(&repo, &[("assets", 0o120000, link)], &[old], "Dev", 2000, "Link");
+    common::set_head(&repo, head);
+    std::os::unix::fs::symlink(outside, repo_path.join("assets")).unwrap();
+    let repo_path = repo_path.to_str().unwrap();
+
+    let err = restore_file(repo_path, &old.to_string(), "assets/authorized_keys").unwrap_err();
+    assert!(err.to_string().contains("symlink"), "{:#}", err);
+    assert!(!outside.join("authorized_keys").exists());
+
+    // A symlink at the path itself is replaced, not written through
+    std::fs::write(outside.join("victim"), b"keep").unwrap();
+    let notes = dir.path().join("notes.txt");
+    std::os::unix::fs::symlink(outside.join("victim"), &notes).unwrap();
+    restore_file(repo_path, &old.to_string(), "notes.txt").unwrap();
+    assert!(!notes.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(std::fs::read(&notes).unwrap(), b"notes");
+    assert_eq!(std::fs::read(outside.join("victim")).unwrap(), b"keep");
+}
+
+#[test]
+fn test_branch_blobs_leave_out_the_base() {
+    let (_dir, repo_path, repo) = common::create_test_repo();
+    let big = vec![b'x'; 100];
+    let root = common::commit_files(&repo, &[("big.bin", &big)], &[], "Dev", 1_700_000_000, "Base");
+    let upstream = common::commit_files(&repo, &[("big.bin", &big), ("upstream.bin", &[b'u'; 300])], &[root], "Dev", 1_700_000_100, "Upstream");
+    repo.reference("refs/heads/base", upstream, true, "test").unwrap();
+
+    let added = common::commit_files(
+        &repo,
+        &[("big.bin", &big), ("copy.bin", &big), ("new.bin", &[b'n'; 200]), ("small.txt", b"tiny")],
+        &[root],
+        "Dev",
+        1_700_000_200,
+        "Add files",
+    );
+    // Merging the base in brings its files, which the base already has
+    let merge = common::commit_files(
+        &repo,
+        &[("big.bin", &big), ("copy.bin", &big), ("new.bin", &[b'n'; 200]), ("small.txt", b"tiny"), ("upstream.bin", &[b'u'; 300])],
+        &[added, upstream],
+        "Dev",
+        1_700_000_300,
+        "Merge base",
+    );
+    common::set_head(&repo, merge);
+
+    let found = branch_blobs(repo_path.to_str().unwrap(), "base", 10).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].path, "new.bin");
+    assert_eq!(found[0].size, 200);
+    assert_eq!(found[0].commit, added.to_string());
+
+    assert_eq!(branch_blobs(repo_path.to_str().unwrap(), "base", 0).unwrap().len(), 2);
+    assert!(branch_blobs(repo_path.to_str().unwrap(), "main", 0).unwrap().is_empty());
+    assert!(branch_blobs(repo_path.to_str().unwrap(), "no-such-branch", 0).is_err());
+}
+
+#[tokio::test]
+async fn test_range_scan_counts_only_what_the_range_adds() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    let big = vec![b'x'; 100];
+    let base = common::commit_files(&repo, &[("big.bin", &big)], &[], "Dev", 1_700_000_000, "Base");
+    repo.reference("refs/heads/base", base, true, "test").unwrap();
+    let added = common::commit_files(
+        &repo,
+        &[("big.bin", &big), ("copy.bin", &big), ("new.bin", &[b'n'; 200])],
+        &[base],
+        "Dev",
+        1_700_000_100,
+        "Add files",
+    );
+    common::set_head(&repo, added);
+
+    let db = create_db_in_dir(&dir).await;
+    let ranged = GitScanner::quiet(repo_path.to_str().unwrap()).with_options(ScanOptions {
+        range: RevRange::parse("base.."),
+        ..Default::default()
+    });
+    let tree = ranged.scan(&db).await.unwrap();
+    // The base's blob isn't credited, even under a new path
+    let blobs = db.get_top_blobs(10).await.unwrap();
+    let paths: Vec<&str> = blobs.iter().map(|b| b.path.as_str()).collect();
+    assert_eq!(paths, ["new.bin"]);
+    assert_eq!(db.get_metadata("scan_limits").await.as_deref(), Some("range base..HEAD"));
+    let current: Vec<String> = current_sizes(&tree).into_keys().collect();
+    assert_eq!(current, ["new.bin"]);
+
+    // Dropping the range rebuilds the index with everything
+    let everything = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert_eq!(db.get_top_blobs(10).await.unwrap().len(), 2);
+    assert!(everything.root().cumulative_size > tree.root().cumulative_size);
+}
+
+/// Cancels its token once the scanning phase has started `after` commits
+struct CancelAfter {
+    token: CancelToken,
+    after: u64,
+}
+
+struct CancelAfterHandle {
+    token: CancelToken,
+    left: std::sync::atomic::AtomicU64,
+}
+
+impl ProgressReporter for CancelAfter {
+    fn start(&self, label: &str, _total: u64) -> Box<dyn ProgressHandle> {
+        let after = if label == "Scanning" { self.after } else { u64::MAX };
+        Box::new(CancelAfterHandle { token: self.token.clone(), left: after.into() })
+    }
+}
+
+impl ProgressHandle for CancelAfterHandle {
+    fn inc(&self, n: u64) {
+        let left = self.left.load(std::sync::atomic::Ordering::Relaxed).saturating_sub(n);
+        self.left.store(left, std::sync::atomic::Ordering::Relaxed);
+        if left == 0 {
+            self.token.cancel();
+        }
+    }
+
+    fn finish(&self) {}
+}
+
+#[tokio::test]
+async fn test_cancelled_scan_resumes() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("a.txt", b"first file")], "One");
+    common::add_commit(&repo, &[("b.txt", b"second file, longer")], "Two");
+    common::add_commit(&repo, &[("c.txt", b"third file, longest of all")], "Three");
+
+    let db = create_db_in_dir(&dir).await;
+    let token = CancelToken::new();
+    let cancelling = GitScanner::quiet(repo_path.to_str().unwrap())
+        .with_progress(Arc::new(CancelAfter { token: token.clone(), after: 2 }))
+        .with_cancel(token);
+    let err = cancelling.scan(&db).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<ScanCancelled>(), Some(&ScanCancelled { scanned: 2, total: 3 }));
+
+    // The two commits are saved, but HEAD isn't taken as indexed
+    assert_eq!(db.load_scanned_commits().await.len(), 2);
+    assert_eq!(db.get_metadata("head_oid").await.as_deref(), Some(""));
+
+    let resumed = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert_eq!(db.load_scanned_commits().await.len(), 3);
+    let fresh = scan_fresh(&repo_path).await;
+    assert_eq!(resumed.root().cumulative_size, fresh.root().cumulative_size);
+    assert_eq!(current_sizes(&resumed), current_sizes(&fresh));
+}
+
+/// main:    base -- main_work ------- merge (by "Merger")
+///               \                    /
+/// feature:       feature_work ------
+///
+/// Both branches add the same content at different paths; the feature side
+/// is dated earlier, so it introduced the blob whichever parent comes first.
+async fn merged_blob_author(feature_first: bool) -> (String, i64, String) {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    let base = common::commit_files(&repo, &[("a.txt", b"a")], &[], "Main Dev", 1000, "base");
+    let feature = common::commit_files(
+        &repo, &[("a.txt", b"a"), ("feature/shared.bin", b"shared blob")], &[base], "Feature Dev", 2000, "feature");
+    let main = common::commit_files(
+        &repo, &[("a.txt", b"a"), ("main/shared.bin", b"shared blob")], &[base], "Main Dev", 3000, "main");
+    let files: &[(&str, &[u8])] = &[
+        ("a.txt", b"a"),
+        ("feature/shared.bin", b"shared blob"),
+        ("main/shared.bin", b"shared blob"),
+        ("merge.txt", b"merge only"),
+    ];
+    let parents = if feature_first { [feature, main] } else { [main, feature] };
+    let merge = common::commit_files(&repo, files, &parents, "Merger", 4000, "merge");
+    common::set_head(&repo, merge);
+
+    let db = create_db_in_dir(&dir).await;
+    GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+
+    let oid = repo.blob(b"shared blob").unwrap();
+    let blob = db.lookup_blob(&oid.to_string()).await.unwrap().unwrap();
+    (blob.first_author, blob.first_date, blob.path)
+}
+
+#[tokio::test]
+async fn test_merge_attribution_independent_of_parent_order() {
+    let expected = ("Feature Dev".to_string(), 2000, "feature/shared.bin".to_string());
+    assert_eq!(merged_blob_author(true).await, expected);
+    assert_eq!(merged_blob_author(false).await, expected);
+}
+
+#[tokio::test]
+async fn test_octopus_merge() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    let base = common::commit_files(&repo, &[("a.txt", b"a")], &[], "Base", 1000, "base");
+    let mut files: Vec<(&str, &[u8])> = vec![("a.txt", b"a")];
+    let mut branches = Vec::new();
+    for (i, (path, author)) in [("one.bin", "One"), ("two.bin", "Two"), ("three.bin", "Three")].into_iter().enumerate() {
+        let content: &[u8] = path.as_bytes();
+        branches.push(common::commit_files(
+            &repo, &[("a.txt", b"a"), (path, content)], &[base], author, 2000 + i as i64, path));
+        files.push((path, content));
+    }
+    let merge = common::commit_files(&repo, &files, &branches, "Merger", 5000, "octopus");
+    common::set_head(&repo, merge);
+
+    let db = create_db_in_dir(&dir).await;
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+
+    assert_eq!(tree.root().children().len(), 4);
+    for (path, author) in [("one.bin", "One"), ("two.bin", "Two"), ("three.bin", "Three")] {
+        let oid = repo.blob(path.as_bytes()).unwrap();
+        let blob = db.lookup_blob(&oid.to_string()).await.unwrap().unwrap();
+        assert_eq!(blob.first_author, author);
+    }
+    assert!(db.get_scan_diagnostics().await.unwrap().is_empty());
+
+    // Mainline only: the merge is what brings the branch files in
+    let first_parent = GitScanner::quiet(repo_path.to_str().unwrap()).with_options(ScanOptions {
+        first_parent: true,
+        ..Default::default()
+    });
+    let fp_dir = TempDir::new().unwrap();
+    let fp_db = create_db_in_dir(&fp_dir).await;
+    let fp_tree = first_parent.scan(&fp_db).await.unwrap();
+    assert_eq!(current_sizes(&fp_tree), current_sizes(&tree));
+}
+
+#[tokio::test]
+async fn test_deletion_dates() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    let base: &[(&str, &[u8])] = &[("keep.txt", b"keep"), ("src/a.rs", b"a"), ("old/x.bin", b"x")];
+    let c1 = common::commit_files(&repo, base, &[], "Dev", 1000, "base");
+    let with_tmp = [base, &[("src/tmp.rs", b"tmp" as &[u8])]].concat();
+    let c2 = common::commit_files(&repo, &with_tmp, &[c1], "Dev", 2000, "add tmp");
+    // `src` goes back to a tree the scan has already seen
+    let c3 = common::commit_files(&repo, base, &[c2], "Dev", 3000, "drop tmp");
+    let c4 = common::commit_files(&repo, &base[..2], &[c3], "Dev", 4000, "drop old");
+    common::set_head(&repo, c4);
+
+    let db = create_db_in_dir(&dir).await;
+    GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+
+    let none = PathFilter::default();
+    assert_eq!(db.get_deletion_dates("", &none).await.unwrap(), vec![
+        ("old".to_string(), 4000),
+        ("src".to_string(), 3000),
+    ]);
+    assert_eq!(db.get_deletion_dates("src", &none).await.unwrap(), vec![("tmp.rs".to_string(), 3000)]);
+    assert_eq!(db.get_deletion_dates("old", &none).await.unwrap(), vec![("x.bin".to_string(), 4000)]);
+    let filter = PathFilter::new(&["old/"]).unwrap();
+    assert_eq!(db.get_deletion_dates("", &filter).await.unwrap(), vec![("src".to_string(), 3000)]);
+}
+
+#[tokio::test]
+async fn test_heavy_commits() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    // Noise, so the video stays the largest blob however it is stored
+    let mut state = 7_u32;
+    let video: Vec<u8> = (0..8192)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect();
+    let add = common::commit_files(&repo, &[("demo.mp4", &video), ("notes.txt", b"v1")], &[], "Alice", 1000, "add");
+    let edit = common::commit_files(&repo, &[("demo.mp4", &video), ("notes.txt", b"v2")], &[add], "Bob", 2000, "edit");
+    // Only blobs already in history: adds nothing
+    let copy = common::commit_files(
+        &repo, &[("demo.mp4", &video), ("copy.mp4", &video), ("notes.txt", b"v2")], &[edit], "Carol", 3000, "copy");
+    common::set_head(&repo, copy);
+
+    let db = create_db_in_dir(&dir).await;
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+
+    let commits = db.get_heavy_commits(10).await.unwrap();
+    let oids: Vec<String> = commits.iter().map(|c| hex::encode(&c.oid)).collect();
+    assert_eq!(oids, [add.to_string(), edit.to_string()]);
+    assert_eq!((commits[0].author.as_str(), commits[0].date, commits[0].subject.as_str()), ("Alice", 1000, "add"));
+    assert_eq!((commits[0].blob_count, commits[0].largest_path.as_str()), (2, "demo.mp4"));
+    assert_eq!((commits[1].blob_count, commits[1].largest_path.as_str()), (1, "notes.txt"));
+    // Every blob is added by exactly one commit
+    assert_eq!(commits.iter().map(|c| c.added_size).sum::<u64>(), tree.root().cumulative_size);
+
+    let video_oid = repo.blob(&video).unwrap();
+    let blob = db.lookup_blob(&video_oid.to_string()).await.unwrap().unwrap();
+    assert_eq!(blob.first_subject, "add");
+    assert_eq!(db.get_top_blobs(1).await.unwrap()[0].first_subject, "add");
+}
+
+#[tokio::test]
+async fn test_disconnected_history_stable_across_incremental_scans() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    let db = create_db_in_dir(&dir).await;
+    let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+
+    let v1 = common::commit_files(&repo, &[("app.txt", b"version 1")], &[], "Dev", 1000, "v1");
+    common::set_head(&repo, v1);
+    scanner.scan(&db).await.unwrap();
+
+    // A second root (an orphan branch), merged into a mainline that changed app.txt
+    let v2 = common::commit_files(&repo, &[("app.txt", b"version 2!")], &[v1], "Dev", 2000, "v2");
+    let docs = common::commit_files(&repo, &[("docs/guide.md", b"guide")], &[], "Writer", 1500, "docs");
+    let files: &[(&str, &[u8])] = &[("app.txt", b"version 2!"), ("docs/guide.md", b"guide")];
+    let merge = common::commit_files(&repo, files, &[v2, docs], "Dev", 3000, "merge docs");
+    common::set_head(&repo, merge);
+    let incremental = scanner.scan(&db).await.unwrap();
+
+    let fresh = scan_fresh(&repo_path).await;
+    assert_eq!(incremental.root().cumulative_size, fresh.root().cumulative_size);
+    assert_eq!(incremental.root().current_size, fresh.root().current_size);
+    assert_eq!(current_sizes(&incremental), current_sizes(&fresh));
+    // The old app.txt no longer counts as current
+    let v2_blob = repo.blob(b"version 2!").unwrap();
+    let v2_size = db.lookup_blob(&v2_blob.to_string()).await.unwrap().unwrap().size;
+    assert_eq!(incremental.find("app.txt").unwrap().current_size, v2_size);
+}
+
+#[tokio::test]
+async fn test_unreadable_tree_is_reported() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    let old = common::commit_files(&repo, &[("lost/data.bin", b"lost data")], &[], "Dev", 1000, "old");
+    let head = common::commit_files(&repo, &[("kept.txt", b"kept")], &[old], "Dev", 2000, "head");
+    common::set_head(&repo, head);
+
+    // Delete the old commit's `lost/` tree, as a partial clone might lack it
+    let lost = repo.find_commit(old).unwrap().tree().unwrap().get_name("lost").unwrap().id();
+    let hex = lost.to_string();
+    std::fs::remove_file(repo_path.join(".git/objects").join(&hex[..2]).join(&hex[2..])).unwrap();
+
+    let db = create_db_in_dir(&dir).await;
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert!(tree.find("kept.txt").is_some());
+    assert!(tree.find("lost").is_none());
+
+    let diagnostics = db.get_scan_diagnostics().await.unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!((diagnostics[0].kind.as_str(), diagnostics[0].oid.as_str()), ("tree", hex.as_str()));
+    assert_eq!(diagnostics[0].path, "lost");
+}
+
+#[tokio::test]
+async fn test_undecodable_commit_does_not_stop_the_walk() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    let base = common::commit_files(&repo, &[("a.txt", b"a")], &[], "Dev", 1000, "base");
+    let blob = repo.blob(b"added under a broken author").unwrap();
+    let mut builder = repo.treebuilder(Some(&repo.find_commit(base).unwrap().tree().unwrap())).unwrap();
+    builder.insert("b.txt", blob, 0o100644).unwrap();
+    let tree = builder.write().unwrap();
+    let raw = format!(
+        "tree {}\nparent {}\nauthor broken\ncommitter Dev <dev@example.com> 2000 +0000\n\nbroken author\n",
+        tree, base
+    );
+    let odb = repo.odb().unwrap();
+    let broken = odb.write(git2::ObjectType::Commit, raw.as_bytes()).unwrap();
+    // git2 won't parse the broken commit to take it as a parent
+    let signature = "Dev <dev@example.com> 3000 +0000";
+    let raw = format!("tree {}\nparent {}\nauthor {}\ncommitter {}\n\ntop\n", tree, broken, signature, signature);
+    let top = odb.write(git2::ObjectType::Commit, raw.as_bytes()).unwrap();
+    common::set_head(&repo, top);
+
+    let db = create_db_in_dir(&dir).await;
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert!(tree.find("a.txt").is_some());
+    // Credited to the commit on top, which is walked in full
+    assert!(tree.find("b.txt").unwrap().cumulative_size > 0);
+
+    let diagnostics = db.get_scan_diagnostics().await.unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!((diagnostics[0].kind.as_str(), diagnostics[0].oid.as_str()), ("commit", broken.to_string().as_str()));
+}
+
+/// A repository whose `data.bin` blob is only in a pack cut short by a few
+/// bytes, as an interrupted copy would leave it
+fn repo_with_truncated_pack() -> (TempDir, std::path::PathBuf) {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("kept.txt", b"kept"), ("data.bin", &[9u8; 4000])], "init");
+
+    let blob = repo.blob(&[9u8; 4000]).unwrap();
+    let pack_dir = repo_path.join(".git/objects/pack");
+    let mut builder = repo.packbuilder().unwrap();
+    builder.insert_object(blob, None).unwrap();
+    builder.write(&pack_dir, 0o644).unwrap();
+    let hex = blob.to_string();
+    std::fs::remove_file(repo_path.join(".git/objects").join(&hex[..2]).join(&hex[2..])).unwrap();
+
+    let pack = std::fs::read_dir(&pack_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|e| e == "pack"))
+        .unwrap();
+    let len = std::fs::metadata(&pack).unwrap().len();
+    std::fs::OpenOptions::new().write(true).open(&pack).unwrap().set_len(len - 30).unwrap();
+    (dir, repo_path)
+}
+
+#[tokio::test]
+async fn test_truncated_pack_is_reported() {
+    let (dir, repo_path) = repo_with_truncated_pack();
+
+    let db = create_db_in_dir(&dir).await;
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+    assert!(tree.find("kept.txt").unwrap().cumulative_size > 0);
+    assert_eq!(tree.find("data.bin").unwrap().cumulative_size, 0);
+
+    let diagnostics = db.get_scan_diagnostics().await.unwrap();
+    let kinds: Vec<&str> = diagnostics.iter().map(|d| d.kind.as_str()).collect();
+    assert_eq!(kinds, ["pack", "size"]);
+    assert!(diagnostics[0].path.ends_with(".idx"));
+    assert_eq!(diagnostics[0].reason, "truncated: 1 of 1 objects are incomplete");
+    assert_eq!(diagnostics[1].path, "data.bin");
+}
+
+#[tokio::test]
+async fn test_strict_scan_fails_on_truncated_pack() {
+    let (dir, repo_path) = repo_with_truncated_pack();
+
+    let db = create_db_in_dir(&dir).await;
+    let options = ScanOptions { strict: true, ..Default::default() };
+    let err = GitScanner::quiet(repo_path.to_str().unwrap())
+        .with_options(options)
+        .scan(&db)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("1 packs could not be read (--strict)"), "{}", err);
+    assert_eq!(db.get_metadata("head_oid").await, None);
+}
+
+#[tokio::test]
+async fn test_entry_kinds() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    let first = common::commit_entries(&repo, &[
+        ("run.sh", 0o100644, b"echo hi"),
+        ("latest", 0o100644, b"placeholder"),
+    ], &[], "Dev", 1000, "first");
+    // Same content, new modes: only HEAD knows run.sh became executable
+    let head = common::commit_entries(&repo, &[
+        ("run.sh", 0o100755, b"echo hi"),
+        ("latest", 0o120000, b"releases/v2"),
+        ("readme.txt", 0o100644, b"readme"),
+        ("vendor/lib", 0o160000, b""),
+    ], &[first], "Dev", 2000, "second");
+    common::set_head(&repo, head);
+
+    let db = create_db_in_dir(&dir).await;
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap()).scan(&db).await.unwrap();
+
+    assert_eq!(tree.find("run.sh").unwrap().kind, EntryKind::Executable);
+    assert_eq!(tree.find("latest").unwrap().kind, EntryKind::Symlink);
+    assert!(tree.find("latest").unwrap().current_size > 0);
+    assert_eq!(tree.find("readme.txt").unwrap().kind, EntryKind::File);
+
+    let submodule = &tree.find("vendor").unwrap().find("lib").unwrap();
+    assert_eq!(submodule.kind, EntryKind::Gitlink);
+    assert_eq!((submodule.cumulative_size, submodule.current_size), (0, 0));
+    assert!(!submodule.contains_deleted_files());
 }
 
 #[tokio::test]
@@ -153,7 +995,86 @@ async fn test_head_caching() {
     // Second scan with same HEAD should use cache (fast path)
     // This is hard to verify directly, but at least ensure it works
     let tree = scanner.scan(&db).await.unwrap();
-    assert!(tree.children.contains_key("file.txt"));
+    assert!(tree.find("file.txt").is_some());
+}
+
+#[tokio::test]
+async fn test_in_memory_index() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("a.txt", b"first"), ("b.txt", b"second")], "Initial");
+    common::add_commit(&repo, &[("a.txt", b"first, edited")], "Edit");
+
+    let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+    let on_disk = scanner.scan(&create_db_in_dir(&dir).await).await.unwrap();
+
+    let memory = Database::in_memory().await.unwrap();
+    memory.init_schema().await.unwrap();
+    let in_memory = scanner.scan(&memory).await.unwrap();
+
+    assert_eq!(current_sizes(&in_memory), current_sizes(&on_disk));
+    assert_eq!(in_memory.root().cumulative_size, on_disk.root().cumulative_size);
+    assert_eq!(memory.get_top_blobs(10).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_memory_store_matches_database() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("a.txt", b"first"), ("docs/b.txt", b"second")], "Initial");
+
+    let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+    let db = create_db_in_dir(&dir).await;
+    let store = MemoryStore::new();
+    scanner.scan(&db).await.unwrap();
+    scanner.scan(&store).await.unwrap();
+
+    // Incremental: edit one file, delete the other
+    common::add_commit(&repo, &[("a.txt", b"first, edited")], "Edit");
+    common::remove_file_commit(&repo, "docs/b.txt", "Remove");
+    let from_db = scanner.scan(&db).await.unwrap();
+    let from_memory = scanner.scan(&store).await.unwrap();
+
+    assert_eq!(current_sizes(&from_memory), current_sizes(&from_db));
+    assert_eq!(from_memory.root().cumulative_size, from_db.root().cumulative_size);
+    assert_eq!(from_memory.root().blob_count, from_db.root().blob_count);
+    assert!(from_memory.find("docs").unwrap().contains_deleted_files());
+
+    let top_db = db.get_top_blobs(10).await.unwrap();
+    let top_memory = store.top_blobs(10);
+    let summary = |blobs: &[repodiet::model::LargeBlobInfo]| -> Vec<(String, u64, bool)> {
+        blobs.iter().map(|b| (b.path.clone(), b.size, b.in_head)).collect()
+    };
+    assert_eq!(summary(&top_memory), summary(&top_db));
+    assert!(store.skipped().is_empty());
+}
+
+#[tokio::test]
+async fn test_incremental_scan_counts_a_revert_once() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    let first = common::commit_files(&repo, &[("app.cfg", b"v1"), ("old.cfg", b"v1")], &[], "Dev", 1000, "Add");
+    let second = common::commit_files(&repo, &[("app.cfg", b"v2")], &[first], "Dev", 2000, "Change");
+    common::set_head(&repo, second);
+
+    let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+    let db = create_db_in_dir(&dir).await;
+    let store = MemoryStore::new();
+    scanner.scan(&db).await.unwrap();
+    scanner.scan(&store).await.unwrap();
+
+    // Back to the first version, which the index already has at this path
+    let revert = common::commit_files(&repo, &[("app.cfg", b"v1")], &[second], "Dev", 3000, "Revert");
+    common::set_head(&repo, revert);
+    let incremental = scanner.scan(&db).await.unwrap();
+    let from_memory = scanner.scan(&store).await.unwrap();
+    let fresh = scan_fresh(&repo_path).await;
+
+    for tree in [&incremental, &from_memory, &fresh] {
+        let app = tree.find("app.cfg").unwrap();
+        assert_eq!(app.blob_count, 2);
+        assert!(app.current_size > 0);
+        // old.cfg held v1 too; its bytes count at the first path only
+        assert_eq!(tree.find("old.cfg").unwrap().blob_count, 1);
+        assert_eq!(tree.root().cumulative_size, fresh.root().cumulative_size);
+    }
 }
 
 #[tokio::test]
@@ -184,3 +1105,206 @@ async fn test_large_blob_metadata() {
     assert_eq!(large.first_author, "Test User");
     assert!(large.first_date > 0);
 }
+
+#[tokio::test]
+async fn test_mailmap_normalizes_first_authors() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    let base = common::commit_files(&repo, &[("a.bin", &[1u8; 300])], &[], "Dev", 1_700_000_000, "a");
+    let head = common::commit_files(&repo, &[("a.bin", &[1u8; 300]), ("b.bin", &[2u8; 200])], &[base], "dev", 1_700_000_100, "b");
+    repo.reference("refs/heads/main", head, true, "test").unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+
+    let db = create_db_in_dir(&dir).await;
+    let scanner = GitScanner::quiet(repo_path.to_str().unwrap());
+    scanner.scan(&db).await.unwrap();
+    let authors = db.get_author_totals(AuthorGrouping::Name, 10, &PathFilter::default()).await.unwrap();
+    assert_eq!(authors.iter().map(|a| a.author.as_str()).collect::<Vec<_>>(), vec!["Dev", "dev"]);
+
+    // Adding a .mailmap rebuilds the index with both names credited as one
+    std::fs::write(repo_path.join(".mailmap"), "Dev Team <team@corp.example> <dev@example.com>\n").unwrap();
+    scanner.scan(&db).await.unwrap();
+    let blobs = db.get_top_blobs(10).await.unwrap();
+    assert!(blobs.iter().all(|b| (b.first_author.as_str(), b.first_email.as_str()) == ("Dev Team", "team@corp.example")));
+    let size = blobs.iter().map(|b| b.size).sum();
+    let authors = db.get_author_totals(AuthorGrouping::Domain, 10, &PathFilter::default()).await.unwrap();
+    assert_eq!(authors, vec![AuthorTotals { author: "corp.example".to_string(), blob_count: 2, size }]);
+}
+
+fn stash_index_options() -> ScanOptions {
+    ScanOptions {
+        include_stash_and_index: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_staged_only_blob_under_index_prefix() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    common::add_commit(&repo, &[("committed.txt", b"committed")], "Initial");
+
+    // Stage a file without committing it
+    std::fs::write(repo_path.join("staged.bin"), [7u8; 2000]).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(std::path::Path::new("staged.bin")).unwrap();
+    index.write().unwrap();
+
+    let db = create_db_in_dir(&dir).await;
+
+    // Without the option the staged blob is invisible
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap())
+        .scan(&db)
+        .await
+        .unwrap();
+    assert!(tree.find(":index:").is_none());
+
+    // With it, only the blob not present in history shows up
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap())
+        .with_options(stash_index_options())
+        .scan(&db)
+        .await
+        .unwrap();
+    let index_node = tree.find(":index:").expect(":index: prefix");
+    assert!(index_node.find("staged.bin").is_some());
+    assert!(index_node.find("committed.txt").is_none());
+    assert_eq!(index_node.current_size, 0);
+}
+
+#[tokio::test]
+async fn test_stashed_blob_under_stash_prefix() {
+    let (dir, repo_path, mut repo) = common::create_test_repo();
+
+    common::add_commit(&repo, &[("file.txt", b"committed")], "Initial");
+
+    // Modify a tracked file and stash the change
+    std::fs::write(repo_path.join("file.txt"), b"stashed change").unwrap();
+    let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
+    repo.stash_save(&sig, "wip", None).unwrap();
+
+    let db = create_db_in_dir(&dir).await;
+    let tree = GitScanner::quiet(repo_path.to_str().unwrap())
+        .with_options(stash_index_options())
+        .scan(&db)
+        .await
+        .unwrap();
+
+    let stash = tree.find("stash@{0}").expect("stash@{0} prefix");
+    assert!(stash.find("file.txt").is_some());
+    assert!(stash.cumulative_size > 0);
+
+    // The committed version is still attributed to the real path only
+    let file = tree.find("file.txt").unwrap();
+    assert_eq!(file.blob_count, 1);
+}
+
+#[tokio::test]
+async fn test_lookup_blob_after_scan() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+
+    let content: &[u8] = b"shared binary content";
+    common::add_commit(&repo, &[("a/one.bin", content)], "Add one");
+    common::add_commit(&repo, &[("b/two.bin", content)], "Copy to two");
+    common::remove_file_commit(&repo, "a/one.bin", "Remove one");
+
+    let db = create_db_in_dir(&dir).await;
+    GitScanner::quiet(repo_path.to_str().unwrap())
+        .scan(&db)
+        .await
+        .unwrap();
+
+    let oid = git2::Oid::hash_object(git2::ObjectType::Blob, content).unwrap();
+    let blob = db
+        .lookup_blob(&oid.to_string()[..10])
+        .await
+        .unwrap()
+        .expect("blob found");
+
+    assert_eq!(blob.path, "a/one.bin");
+    assert_eq!(blob.first_author, "Test User");
+    let paths: Vec<_> = blob.occurrences.iter().map(|o| (o.path.as_str(), o.in_head)).collect();
+    assert_eq!(paths, vec![("a/one.bin", false), ("b/two.bin", true)]);
+}
+
+/// History where `old.bin` was added then deleted, and a replace ref that
+/// swaps the root commit for one that never had it
+fn repo_with_replaced_root() -> (TempDir, std::path::PathBuf) {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    let root = common::commit_files(&repo, &[("old.bin", &[1u8; 5000])], &[], "Dev", 1_700_000_000, "add old");
+    let head = common::commit_files(&repo, &[("new.txt", b"new")], &[root], "Dev", 1_700_000_100, "replace");
+    common::set_head(&repo, head);
+    let replacement = common::commit_files(&repo, &[("new.txt", b"new")], &[], "Dev", 1_700_000_000, "clean root");
+    repo.reference(&format!("refs/replace/{}", root), replacement, false, "test").unwrap();
+    (dir, repo_path)
+}
+
+#[tokio::test]
+async fn test_replace_refs_are_honored() {
+    let (_dir, repo_path) = repo_with_replaced_root();
+
+    let tree = scan_fresh(&repo_path).await;
+
+    assert!(tree.find("old.bin").is_none());
+    assert_eq!(tree.root().cumulative_size, tree.root().current_size);
+}
+
+#[tokio::test]
+async fn test_no_replace_objects_scans_original_history() {
+    let (dir, repo_path) = repo_with_replaced_root();
+    let db = create_db_in_dir(&dir).await;
+    let scanner = |no_replace_objects| {
+        GitScanner::quiet(repo_path.to_str().unwrap())
+            .with_options(ScanOptions { no_replace_objects, ..Default::default() })
+    };
+
+    let original = scanner(true).scan(&db).await.unwrap();
+    let old = original.find("old.bin").expect("original root commit is scanned");
+    assert_eq!(old.current_size, 0);
+    assert!(old.cumulative_size > 0);
+
+    // Same index, replacements applied again: the cached rows are discarded
+    let replaced = scanner(false).scan(&db).await.unwrap();
+    assert!(replaced.find("old.bin").is_none());
+}
+
+#[tokio::test]
+async fn test_linked_worktree_shares_the_repository() {
+    let (dir, repo_path, repo) = common::create_test_repo();
+    common::add_commit(&repo, &[("app.bin", &[3u8; 2000])], "Initial");
+    let worktree_path = dir.path().join("app-hotfix");
+    repo.worktree("hotfix", &worktree_path, None).unwrap();
+
+    let main = Checkout::open(&repo_path).unwrap();
+    let linked = Checkout::open(&worktree_path).unwrap();
+    assert_eq!(linked.common_dir, main.common_dir);
+    assert_eq!(linked.name, main.name);
+    assert_eq!(main.linked_worktree, None);
+    assert_eq!(linked.linked_worktree.as_deref(), Some("app-hotfix"));
+    assert_eq!(linked.branch.as_deref(), Some("hotfix"));
+
+    // The worktree sees the main repository's history
+    let tree = scan_fresh(&worktree_path).await;
+    assert_eq!(tree.root().current_size, scan_fresh(&repo_path).await.root().current_size);
+    assert!(tree.find("app.bin").is_some());
+}
+
+#[tokio::test]
+async fn test_scan_synthetic_repo() {
+    let repo = SyntheticRepo::generate(RepoShape { binary_size: 4096, ..RepoShape::default() }).unwrap();
+    let tree = scan_fresh(repo.path()).await;
+
+    let current: BTreeMap<String, u64> = current_sizes(&tree).into_iter().filter(|(_, size)| *size > 0).collect();
+    assert_eq!(current, repo.head_files());
+    assert_eq!(tree.root().current_size, repo.head_files().values().sum::<u64>());
+    // Renamed files keep their blob, so every blob is counted at least once
+    assert!(tree.root().cumulative_size >= repo.blobs().values().sum::<u64>());
+}
+
+/// Thousands of commits over a wide tree; run with `--ignored`
+#[tokio::test]
+#[ignore]
+async fn test_scan_large_synthetic_repo() {
+    let shape = RepoShape { commits: 1000, width: 8, depth: 5, files_per_commit: 10, binary_size: 16 * 1024, ..RepoShape::default() };
+    let repo = SyntheticRepo::generate(shape).unwrap();
+    let tree = scan_fresh(repo.path()).await;
+    assert_eq!(tree.root().current_size, repo.head_files().values().sum::<u64>());
+}
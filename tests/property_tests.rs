@@ -0,0 +1,166 @@
+// Property tests over generated repositories
+// Sizes must add up however a history is shaped, and however it was scanned
+
+use proptest::prelude::*;
+use repodiet::model::{NodeRef, Tree};
+use repodiet::repository::{Database, GitScanner, MemoryStore, ScanStore};
+use repodiet::synthetic::{RepoShape, SyntheticRepo};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Sizes a scan reports for one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LeafSizes {
+    cumulative: u64,
+    current: u64,
+    blob_count: u64,
+    deleted: u64,
+}
+
+prop_compose! {
+    /// Small histories of every kind: flat or deep, with or without binaries,
+    /// merges, deletions and renames
+    fn shapes()(
+        commits in 1usize..20,
+        width in 1usize..4,
+        depth in 0usize..4,
+        files_per_commit in 1usize..5,
+        binary_size in 1usize..2048,
+        binary_every in 0usize..4,
+        merge_every in 0usize..5,
+        delete_every in 0usize..4,
+        rename_every in 0usize..4,
+        seed in any::<u64>(),
+    ) -> RepoShape {
+        RepoShape {
+            commits,
+            width,
+            depth,
+            files_per_commit,
+            binary_size,
+            binary_every,
+            merge_every,
+            delete_every,
+            rename_every,
+            seed,
+        }
+    }
+}
+
+/// Each case writes and scans a repository, so fewer than proptest's default
+/// unless `PROPTEST_CASES` asks for more
+fn cases() -> u32 {
+    std::env::var("PROPTEST_CASES").ok().and_then(|cases| cases.parse().ok()).unwrap_or(24)
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+}
+
+async fn open_db(dir: &TempDir) -> Database {
+    let db = Database::new(dir.path().join("index.db").to_str().unwrap()).await.unwrap();
+    db.init_schema().await.unwrap();
+    db
+}
+
+async fn scan(repo: &Path, store: &impl ScanStore) -> Tree {
+    GitScanner::quiet(repo.to_str().unwrap()).scan(store).await.unwrap()
+}
+
+/// Every file's sizes, by path
+fn leaves(tree: &Tree) -> BTreeMap<String, LeafSizes> {
+    let mut leaves = BTreeMap::new();
+    tree.visit_leaves(|path, node| {
+        let sizes = LeafSizes {
+            cumulative: node.cumulative_size,
+            current: node.current_size,
+            blob_count: node.blob_count,
+            deleted: node.deleted_size,
+        };
+        leaves.insert(path.to_string(), sizes);
+    });
+    leaves
+}
+
+/// Directories whose sizes are not the sums of their entries'
+fn unbalanced(node: NodeRef<'_>, path: &str, out: &mut Vec<String>) {
+    if !node.has_children() {
+        return;
+    }
+    let (mut cumulative, mut current, mut blob_count, mut deleted) = (0, 0, 0, 0);
+    for child in node.children() {
+        cumulative += child.cumulative_size;
+        current += child.current_size;
+        blob_count += child.blob_count;
+        deleted += child.deleted_size;
+        unbalanced(child, &format!("{}/{}", path, child.name()), out);
+    }
+    if (cumulative, current, blob_count, deleted) != (node.cumulative_size, node.current_size, node.blob_count, node.deleted_size) {
+        out.push(path.to_string());
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(cases()))]
+
+    #[test]
+    fn sizes_add_up(shape in shapes()) {
+        let repo = SyntheticRepo::generate(shape).unwrap();
+        let tree = runtime().block_on(async {
+            let dir = TempDir::new().unwrap();
+            scan(repo.path(), &open_db(&dir).await).await
+        });
+
+        let leaves = leaves(&tree);
+        let root = tree.root();
+        prop_assert_eq!(leaves.values().map(|leaf| leaf.cumulative).sum::<u64>(), root.cumulative_size);
+        prop_assert_eq!(leaves.values().map(|leaf| leaf.blob_count).sum::<u64>(), root.blob_count);
+        let mut wrong = Vec::new();
+        unbalanced(root, "", &mut wrong);
+        prop_assert!(wrong.is_empty(), "directories not adding up: {:?}", wrong);
+
+        // Each blob's bytes once, at the first path it was found at
+        prop_assert_eq!(root.cumulative_size, repo.blobs().values().sum::<u64>());
+        prop_assert_eq!(root.deleted_size, leaves.values().map(|leaf| leaf.deleted).sum::<u64>());
+    }
+
+    #[test]
+    fn current_sizes_match_head(shape in shapes()) {
+        let repo = SyntheticRepo::generate(shape).unwrap();
+        let tree = runtime().block_on(async {
+            let dir = TempDir::new().unwrap();
+            scan(repo.path(), &open_db(&dir).await).await
+        });
+
+        let current: BTreeMap<String, u64> = leaves(&tree)
+            .into_iter()
+            .filter(|(_, leaf)| leaf.current > 0)
+            .map(|(path, leaf)| (path, leaf.current))
+            .collect();
+        prop_assert_eq!(&current, &repo.head_files());
+        prop_assert_eq!(tree.root().current_size, repo.head_files().values().sum::<u64>());
+    }
+
+    #[test]
+    fn incremental_scan_equals_full_scan(shape in shapes(), more in 1usize..10) {
+        let mut repo = SyntheticRepo::generate(shape).unwrap();
+        runtime().block_on(async {
+            let dir = TempDir::new().unwrap();
+            let db = open_db(&dir).await;
+            let memory = MemoryStore::new();
+            scan(repo.path(), &db).await;
+            scan(repo.path(), &memory).await;
+
+            repo.extend(more).unwrap();
+            let incremental = leaves(&scan(repo.path(), &db).await);
+            let incremental_memory = leaves(&scan(repo.path(), &memory).await);
+            let fresh_dir = TempDir::new().unwrap();
+            let fresh = leaves(&scan(repo.path(), &open_db(&fresh_dir).await).await);
+
+            prop_assert_eq!(&incremental, &fresh);
+            prop_assert_eq!(&incremental_memory, &fresh);
+            Ok(())
+        })?;
+    }
+}
@@ -39,19 +39,19 @@ async fn test_tree_hierarchy_from_db() {
     let tree = db.load_tree().await.unwrap();
 
     // Verify root level
-    assert_eq!(tree.children.len(), 3); // src, tests, README.md
+    assert_eq!(tree.root().children().len(), 3); // src, tests, README.md
 
     // Verify src structure
-    let src = tree.children.get("src").unwrap();
-    assert_eq!(src.children.len(), 3); // main.rs, lib.rs, utils
+    let src = tree.find("src").unwrap();
+    assert_eq!(src.children().len(), 3); // main.rs, lib.rs, utils
 
     // Verify nested structure
-    let utils = src.children.get("utils").unwrap();
-    assert!(utils.children.contains_key("helpers.rs"));
+    let utils = src.find("utils").unwrap();
+    assert!(utils.find("helpers.rs").is_some());
 
     // Verify tests
-    let tests = tree.children.get("tests").unwrap();
-    assert!(tests.children.contains_key("test.rs"));
+    let tests = tree.find("tests").unwrap();
+    assert!(tests.find("test.rs").is_some());
 }
 
 #[tokio::test]
@@ -69,16 +69,16 @@ async fn test_size_aggregation_on_load() {
     let tree = db.load_tree().await.unwrap();
 
     // Verify root totals (should be sum of all)
-    assert_eq!(tree.cumulative_size, 600); // 100 + 200 + 300
-    assert_eq!(tree.current_size, 300);    // 50 + 100 + 150
+    assert_eq!(tree.root().cumulative_size, 600); // 100 + 200 + 300
+    assert_eq!(tree.root().current_size, 300);    // 50 + 100 + 150
 
     // Verify src totals
-    let src = tree.children.get("src").unwrap();
+    let src = tree.find("src").unwrap();
     assert_eq!(src.cumulative_size, 600);
     assert_eq!(src.current_size, 300);
 
     // Verify src/sub totals
-    let sub = src.children.get("sub").unwrap();
+    let sub = src.find("sub").unwrap();
     assert_eq!(sub.cumulative_size, 300);
     assert_eq!(sub.current_size, 150);
 }
@@ -98,19 +98,19 @@ async fn test_deleted_files_marked() {
     let tree = db.load_tree().await.unwrap();
 
     // Verify existing file
-    let existing = tree.children.get("existing.txt").unwrap();
+    let existing = tree.find("existing.txt").unwrap();
     assert!(existing.current_size > 0);
 
     // Verify deleted file (cumulative > 0, current = 0)
-    let deleted = tree.children.get("deleted.txt").unwrap();
+    let deleted = tree.find("deleted.txt").unwrap();
     assert_eq!(deleted.cumulative_size, 200);
     assert_eq!(deleted.current_size, 0);
 
     // contains_deleted_files should be true for root
-    assert!(tree.contains_deleted_files());
+    assert!(tree.root().contains_deleted_files());
 
     // deleted_cumulative_size should sum up deleted content
-    assert_eq!(tree.deleted_cumulative_size(), 500); // 200 + 300
+    assert_eq!(tree.root().deleted_cumulative_size(), 500); // 200 + 300
 }
 
 #[tokio::test]
@@ -132,7 +132,7 @@ async fn test_extension_stats_from_tree() {
 
     // Compute extension stats
     let mut stats: HashMap<String, ExtensionStats> = HashMap::new();
-    collect_extension_stats(&tree, &mut stats);
+    collect_extension_stats(tree.root(), &mut stats);
 
     // Verify .rs stats
     let rs = stats.get(".rs").unwrap();
@@ -151,18 +151,18 @@ async fn test_extension_stats_from_tree() {
 }
 
 // Helper function to collect extension stats from tree
-fn collect_extension_stats(node: &repodiet::model::TreeNode, stats: &mut HashMap<String, ExtensionStats>) {
-    if node.children.is_empty() {
+fn collect_extension_stats(node: repodiet::model::NodeRef<'_>, stats: &mut HashMap<String, ExtensionStats>) {
+    if !node.has_children() {
         // Leaf node - file
-        let ext = extension_label(&node.name).into_owned();
+        let ext = extension_label(node.name()).into_owned();
 
-        let entry = stats.entry(ext).or_insert(ExtensionStats::default());
+        let entry = stats.entry(ext).or_default();
         entry.file_count += 1;
         entry.cumulative_size += node.cumulative_size;
         entry.current_size += node.current_size;
     } else {
         // Directory - recurse
-        for child in node.children.values() {
+        for child in node.children() {
             collect_extension_stats(child, stats);
         }
     }
@@ -0,0 +1,238 @@
+// View snapshot tests
+// Each view is drawn for fixed ViewModels on a TestBackend and compared with
+// a golden file in tests/snapshots, so column overlap, truncation and the
+// highlighted row show up as a diff. After an intended layout change, rewrite
+// the files with `UPDATE_SNAPSHOTS=1 cargo test --test view_snapshot_tests`
+// and review them like code.
+
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+use ratatui::{Frame, Terminal};
+use repodiet::input::KeyMap;
+use repodiet::model::{BloatThresholds, ExtensionStats, HeavyCommit, LargeBlobInfo, Tree};
+use repodiet::util::DateStyle;
+use repodiet::view;
+use repodiet::viewmodel::{
+    BlobsViewModel, BookmarksViewModel, CommitsViewModel, ExtensionViewModel, FlattenViewModel, SearchViewModel,
+    SelectionSet, TopDirsViewModel, TreeViewModel,
+};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const WIDTH: u16 = 100;
+const HEIGHT: u16 = 20;
+
+/// A small history: live source, a deleted asset and a vendored library
+fn tree() -> Tree {
+    let mut root = Tree::new();
+    root.add_path_with_sizes(&["src", "main.rs"], 12_000, 4_000, 3);
+    root.add_path_with_sizes(&["src", "lib.rs"], 3_000, 3_000, 1);
+    root.add_path_with_sizes(&["assets", "video.mp4"], 2_500_000, 0, 1);
+    root.add_path_with_sizes(&["assets", "logo.png"], 40_000, 20_000, 2);
+    root.add_path_with_sizes(&["vendor", "lib", "huge_generated_bindings_with_a_long_name.c"], 800_000, 800_000, 1);
+    root.add_path_with_sizes(&["README.md"], 1_500, 1_000, 2);
+    root.compute_totals();
+    root
+}
+
+fn blob(id: u8, size: u64, path: &str, in_head: bool) -> LargeBlobInfo {
+    LargeBlobInfo {
+        oid: vec![id; 20],
+        size,
+        path: path.to_string(),
+        first_author: "Alice".into(),
+        first_email: "alice@example.com".into(),
+        first_date: 1_700_000_000 + i64::from(id) * 86_400,
+        first_subject: "Add assets".into(),
+        in_head,
+    }
+}
+
+/// `buffer` as text, a line per row with trailing blanks dropped, each
+/// prefixed by `>` when the row has cells in the selection highlight
+fn buffer_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut highlighted = false;
+        for x in area.left()..area.right() {
+            let cell = &buffer[(x, y)];
+            line.push_str(cell.symbol());
+            highlighted |= cell.bg == Color::DarkGray;
+        }
+        text.push(if highlighted { '>' } else { ' ' });
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+fn draw(width: u16, height: u16, render: impl FnOnce(&mut Frame)) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(render).unwrap();
+    buffer_text(terminal.backend().buffer())
+}
+
+/// Compare `actual` with `tests/snapshots/<name>.txt`, or write it there
+/// when `UPDATE_SNAPSHOTS` is set
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{name}.txt"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("No snapshot at {}; run with UPDATE_SNAPSHOTS=1 to write it", path.display()));
+    assert!(
+        expected == actual,
+        "{name} no longer matches {}\n--- expected\n{expected}--- actual\n{actual}",
+        path.display()
+    );
+}
+
+#[test]
+fn test_tree_view() {
+    let tree = Arc::new(tree());
+    let vm = TreeViewModel::new(Arc::clone(&tree));
+    let bookmarks = BookmarksViewModel::new(tree);
+    let screen = draw(WIDTH, HEIGHT, |frame| {
+        view::render_tree(frame, &vm, &SelectionSet::default(), &bookmarks, &BloatThresholds::default(), frame.area())
+    });
+    assert_snapshot("tree", &screen);
+}
+
+#[test]
+fn test_tree_view_deleted_only_narrow() {
+    let tree = Arc::new(tree());
+    let mut vm = TreeViewModel::new(Arc::clone(&tree));
+    vm.toggle_deleted_only();
+    let bookmarks = BookmarksViewModel::new(tree);
+    // Narrower than the columns want, so names are cut rather than overlapped
+    let screen = draw(60, HEIGHT, |frame| {
+        view::render_tree(frame, &vm, &SelectionSet::default(), &bookmarks, &BloatThresholds::default(), frame.area())
+    });
+    assert_snapshot("tree_deleted_only_narrow", &screen);
+}
+
+#[test]
+fn test_extension_view() {
+    let stats = |cumulative, current, files, binary| ExtensionStats {
+        cumulative_size: cumulative,
+        current_size: current,
+        deleted_size: cumulative - current,
+        file_count: files,
+        binary_size: binary,
+    };
+    let vm = ExtensionViewModel::new(vec![
+        (".mp4".into(), stats(2_500_000, 0, 1, 2_500_000)),
+        (".c".into(), stats(800_000, 800_000, 1, 0)),
+        (".png".into(), stats(40_000, 20_000, 1, 40_000)),
+        (".rs".into(), stats(15_000, 7_000, 2, 0)),
+    ]);
+    let screen = draw(WIDTH, HEIGHT, |frame| {
+        view::render_extension(frame, &vm, &SelectionSet::default(), &BloatThresholds::default(), false, frame.area());
+    });
+    assert_snapshot("extensions", &screen);
+}
+
+#[test]
+fn test_extension_chart_legend_with_wide_names() {
+    let stats = |size| ExtensionStats { cumulative_size: size, current_size: size, deleted_size: 0, file_count: 1, binary_size: 0 };
+    let vm = ExtensionViewModel::new(vec![
+        (".データ".into(), stats(600_000)),
+        (".generated_c".into(), stats(300_000)),
+        (".rs".into(), stats(100_000)),
+    ]);
+    // Wide enough for the share chart beside the list
+    let screen = draw(120, 24, |frame| {
+        view::render_extension(frame, &vm, &SelectionSet::default(), &BloatThresholds::default(), false, frame.area());
+    });
+    assert_snapshot("extensions_chart", &screen);
+}
+
+#[test]
+fn test_blobs_view() {
+    let blobs = vec![
+        blob(1, 2_500_000, "assets/video.mp4", false),
+        blob(2, 800_000, "vendor/lib/huge_generated_bindings_with_a_long_name.c", true),
+        blob(3, 20_000, "assets/logo.png", true),
+    ];
+    let vm = BlobsViewModel::new(blobs, 3_356_500);
+    let screen = draw(WIDTH, HEIGHT, |frame| {
+        view::render_blobs(frame, &vm, &SelectionSet::default(), &DateStyle::default(), frame.area())
+    });
+    assert_snapshot("blobs", &screen);
+}
+
+#[test]
+fn test_search_view() {
+    let mut vm = SearchViewModel::new(Arc::new(tree()));
+    for c in "lib".chars() {
+        vm.add_char(c);
+    }
+    let screen = draw(WIDTH, HEIGHT, |frame| {
+        view::render_search(frame, &vm, &SelectionSet::default(), &BloatThresholds::default(), frame.area())
+    });
+    assert_snapshot("search", &screen);
+}
+
+#[test]
+fn test_commits_view() {
+    let commit = |id: u8, added_size, subject: &str, path: &str| HeavyCommit {
+        oid: vec![id; 20],
+        author: "Alice".into(),
+        email: "alice@example.com".into(),
+        date: 1_700_000_000 + i64::from(id) * 86_400,
+        subject: subject.into(),
+        added_size,
+        blob_count: 1,
+        largest_path: path.into(),
+    };
+    let mut vm = CommitsViewModel::default();
+    vm.set_commits(vec![
+        commit(1, 2_500_000, "Add the launch video", "assets/video.mp4"),
+        commit(2, 800_000, "Vendor the generated bindings", "vendor/lib/huge_generated_bindings_with_a_long_name.c"),
+    ]);
+    let screen = draw(WIDTH, HEIGHT, |frame| {
+        view::render_commits(frame, &vm, &SelectionSet::default(), &DateStyle::default(), frame.area())
+    });
+    assert_snapshot("commits", &screen);
+}
+
+#[test]
+fn test_top_dirs_view() {
+    let mut vm = TopDirsViewModel::default();
+    vm.set_root(&tree());
+    let screen = draw(WIDTH, HEIGHT, |frame| view::render_top_dirs(frame, &vm, &SelectionSet::default(), frame.area()));
+    assert_snapshot("top_dirs", &screen);
+}
+
+#[test]
+fn test_flatten_view() {
+    let mut vm = FlattenViewModel::default();
+    vm.set_root(&tree());
+    let screen = draw(WIDTH, HEIGHT, |frame| {
+        view::render_flatten(frame, &vm, &SelectionSet::default(), &BloatThresholds::default(), frame.area())
+    });
+    assert_snapshot("flatten", &screen);
+}
+
+#[test]
+fn test_help_view() {
+    let screen = draw(WIDTH, 40, |frame| {
+        view::render_help(frame, &BloatThresholds::default(), &KeyMap::default(), frame.area())
+    });
+    assert_snapshot("help", &screen);
+}
+
+#[test]
+fn test_error_and_notice() {
+    let error = draw(60, 10, |frame| view::render_error(frame, "Failed to open git repository: not a repository", frame.area()));
+    assert_snapshot("error", &error);
+    let notice = draw(60, 10, |frame| view::render_notice(frame, "Report written to report.json", frame.area()));
+    assert_snapshot("notice", &notice);
+}